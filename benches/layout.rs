@@ -67,7 +67,34 @@ fn layout(c: &mut Criterion) {
     }
 }
 
-criterion_group!(benches, layout, load_font_system);
+fn align(c: &mut Criterion) {
+    let mut fs = ct::FontSystem::new();
+    let mut buffer = ct::Buffer::new(&mut fs, ct::Metrics::new(10.0, 10.0));
+    buffer.set_size(&mut fs, Some(80.0), None);
+    buffer.set_wrap(&mut fs, ct::Wrap::Word);
+    buffer.set_text(
+        &mut fs,
+        FIRST_CHAPTER_OF_MOBY_DICK,
+        ct::Attrs::new(),
+        ct::Shaping::Advanced,
+    );
+
+    let mut group = c.benchmark_group("Align");
+    group.bench_function("realign large amount of text", |b| {
+        b.iter(|| {
+            buffer.set_align(&mut fs, Some(ct::Align::Right));
+            buffer.set_align(&mut fs, Some(ct::Align::Center));
+        });
+    });
+    group.bench_function("justify large amount of text", |b| {
+        b.iter(|| {
+            buffer.set_align(&mut fs, Some(ct::Align::Justified));
+            buffer.set_align(&mut fs, Some(ct::Align::Left));
+        });
+    });
+}
+
+criterion_group!(benches, layout, align, load_font_system);
 
 criterion_main!(benches);
 
@@ -32,6 +32,9 @@ build --no-default-features --features std,syntect
 echo Build with only std and vi features
 build --no-default-features --features std,vi
 
+echo Build with only std and unicode-linebreak features, without the editor
+build --no-default-features --features std,unicode-linebreak
+
 echo Build with all features
 build --all-features
 
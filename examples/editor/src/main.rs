@@ -27,6 +27,7 @@ fn main() {
     let syntax_system = SyntaxSystem::new();
     let mut swash_cache = SwashCache::new();
 
+    let start_time = std::time::Instant::now();
     let mut display_scale = window.scale_factor() as f32;
 
     let scrollbar_width = 12.0;
@@ -130,30 +131,27 @@ fn main() {
                                     None,
                                 );
                             });
-                            if let Some((x, y)) = editor.cursor_position() {
+                            // The buffer is already in physical pixels
+                            if let Some(area) = editor.cursor_area(1.0) {
+                                let cursor = area.device_cursor();
                                 window.set_ime_cursor_area(
-                                    PhysicalPosition::new(x, y),
-                                    PhysicalSize::new(20, 20),
+                                    PhysicalPosition::new(cursor.x, cursor.y),
+                                    PhysicalSize::new(cursor.width.max(1.0), cursor.height),
                                 );
                             }
 
                             // Draw scrollbar
                             {
-                                let mut start_line_opt = None;
-                                let mut end_line = 0;
-                                editor.with_buffer(|buffer| {
-                                    for run in buffer.layout_runs() {
-                                        end_line = run.line_i;
-                                        if start_line_opt.is_none() {
-                                            start_line_opt = Some(end_line);
-                                        }
-                                    }
+                                let (start_y, end_y) = editor.with_buffer(|buffer| {
+                                    let (_, max) = buffer.scroll_limits();
+                                    let view = height as f32;
+                                    let total = max + view;
+                                    let offset = buffer.scroll_offset();
+                                    (
+                                        (offset * view / total) as usize,
+                                        ((offset + view) * view / total) as usize,
+                                    )
                                 });
-
-                                let start_line = start_line_opt.unwrap_or(end_line);
-                                let lines = editor.with_buffer(|buffer| buffer.lines.len());
-                                let start_y = (start_line * height as usize) / lines;
-                                let end_y = (end_line * height as usize) / lines;
                                 paint.set_color_rgba8(0xFF, 0xFF, 0xFF, 0x40);
                                 if end_y > start_y {
                                     pixmap.fill_rect(
@@ -184,10 +182,18 @@ fn main() {
                             if state.is_pressed() {
                                 match logical_key {
                                     Key::Named(NamedKey::ArrowLeft) => {
-                                        editor.action(Action::Motion(Motion::Left))
+                                        editor.action(Action::Motion(if ctrl_pressed {
+                                            Motion::LeftWord
+                                        } else {
+                                            Motion::Left
+                                        }))
                                     }
                                     Key::Named(NamedKey::ArrowRight) => {
-                                        editor.action(Action::Motion(Motion::Right))
+                                        editor.action(Action::Motion(if ctrl_pressed {
+                                            Motion::RightWord
+                                        } else {
+                                            Motion::Right
+                                        }))
                                     }
                                     Key::Named(NamedKey::ArrowUp) => {
                                         editor.action(Action::Motion(Motion::Up))
@@ -210,9 +216,19 @@ fn main() {
                                     Key::Named(NamedKey::Escape) => editor.action(Action::Escape),
                                     Key::Named(NamedKey::Enter) => editor.action(Action::Enter),
                                     Key::Named(NamedKey::Backspace) => {
-                                        editor.action(Action::Backspace)
+                                        editor.action(if ctrl_pressed {
+                                            Action::DeleteWordBackward
+                                        } else {
+                                            Action::Backspace
+                                        })
+                                    }
+                                    Key::Named(NamedKey::Delete) => {
+                                        editor.action(if ctrl_pressed {
+                                            Action::DeleteWordForward
+                                        } else {
+                                            Action::Delete
+                                        })
                                     }
-                                    Key::Named(NamedKey::Delete) => editor.action(Action::Delete),
                                     Key::Named(key) => {
                                         if let Some(text) = key.to_text() {
                                             for c in text.chars() {
@@ -313,10 +329,11 @@ fn main() {
                                 if state == ElementState::Pressed
                                     && mouse_left == ElementState::Released
                                 {
-                                    editor.action(Action::Click {
-                                        x: mouse_x as i32,
-                                        y: mouse_y as i32,
-                                    });
+                                    editor.click(
+                                        mouse_x as i32,
+                                        mouse_y as i32,
+                                        start_time.elapsed(),
+                                    );
                                     window.request_redraw();
                                 }
                                 mouse_left = state;
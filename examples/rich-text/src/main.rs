@@ -227,10 +227,18 @@ fn main() {
                             if state.is_pressed() {
                                 match logical_key {
                                     Key::Named(NamedKey::ArrowLeft) => {
-                                        editor.action(Action::Motion(Motion::Left))
+                                        editor.action(Action::Motion(if ctrl_pressed {
+                                            Motion::LeftWord
+                                        } else {
+                                            Motion::Left
+                                        }))
                                     }
                                     Key::Named(NamedKey::ArrowRight) => {
-                                        editor.action(Action::Motion(Motion::Right))
+                                        editor.action(Action::Motion(if ctrl_pressed {
+                                            Motion::RightWord
+                                        } else {
+                                            Motion::Right
+                                        }))
                                     }
                                     Key::Named(NamedKey::ArrowUp) => {
                                         editor.action(Action::Motion(Motion::Up))
@@ -253,9 +261,19 @@ fn main() {
                                     Key::Named(NamedKey::Escape) => editor.action(Action::Escape),
                                     Key::Named(NamedKey::Enter) => editor.action(Action::Enter),
                                     Key::Named(NamedKey::Backspace) => {
-                                        editor.action(Action::Backspace)
+                                        editor.action(if ctrl_pressed {
+                                            Action::DeleteWordBackward
+                                        } else {
+                                            Action::Backspace
+                                        })
+                                    }
+                                    Key::Named(NamedKey::Delete) => {
+                                        editor.action(if ctrl_pressed {
+                                            Action::DeleteWordForward
+                                        } else {
+                                            Action::Delete
+                                        })
                                     }
-                                    Key::Named(NamedKey::Delete) => editor.action(Action::Delete),
                                     Key::Named(key) => {
                                         if let Some(text) = key.to_text() {
                                             for c in text.chars() {
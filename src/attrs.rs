@@ -2,6 +2,7 @@
 
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
+use core::hash::{Hash, Hasher};
 use core::ops::Range;
 use rangemap::RangeMap;
 use smol_str::SmolStr;
@@ -125,6 +126,309 @@ impl From<CacheMetrics> for Metrics {
     }
 }
 
+/// Height of a line of text
+///
+/// Proportional line heights are resolved against the font size used for layout, so they stay
+/// proportional when the font size changes.
+#[derive(Clone, Copy, Debug)]
+pub enum LineHeight {
+    /// Line height in pixels
+    Absolute(f32),
+    /// Line height as a multiple of the font size
+    Proportional(f32),
+}
+
+impl LineHeight {
+    /// Get the line height in pixels for text with `font_size`
+    pub fn resolve(self, font_size: f32) -> f32 {
+        match self {
+            Self::Absolute(line_height) => line_height,
+            Self::Proportional(scale) => font_size * scale,
+        }
+    }
+}
+
+impl From<f32> for LineHeight {
+    fn from(line_height: f32) -> Self {
+        Self::Absolute(line_height)
+    }
+}
+
+/// Compares using the u32 representation of f32, like [`CacheMetrics`]
+impl PartialEq for LineHeight {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Absolute(a), Self::Absolute(b))
+            | (Self::Proportional(a), Self::Proportional(b)) => a.to_bits() == b.to_bits(),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for LineHeight {}
+
+impl Hash for LineHeight {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+        match self {
+            Self::Absolute(value) | Self::Proportional(value) => value.to_bits().hash(state),
+        }
+    }
+}
+
+/// Vertical shift of text from the baseline, for superscripts and subscripts, see
+/// [`Attrs::baseline_shift`]
+#[derive(Clone, Copy, Debug)]
+pub enum BaselineShift {
+    /// Raised by a third of the font size and scaled to two thirds of it
+    Super,
+    /// Lowered by a fifth of the font size and scaled to two thirds of it
+    Sub,
+    /// Raised by `shift` times the font size, lowered if negative, and scaled by `scale`
+    Custom { shift: f32, scale: f32 },
+}
+
+impl BaselineShift {
+    /// Get the distance the baseline is raised by, as a multiple of the unscaled font size
+    pub fn shift(self) -> f32 {
+        match self {
+            Self::Super => 1.0 / 3.0,
+            Self::Sub => -0.2,
+            Self::Custom { shift, .. } => shift,
+        }
+    }
+
+    /// Get the factor the font size is multiplied by
+    pub fn scale(self) -> f32 {
+        match self {
+            Self::Super | Self::Sub => 2.0 / 3.0,
+            Self::Custom { scale, .. } => scale,
+        }
+    }
+}
+
+/// Compares using the u32 representation of f32, like [`CacheMetrics`]
+impl PartialEq for BaselineShift {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Super, Self::Super) | (Self::Sub, Self::Sub) => true,
+            (
+                Self::Custom { shift, scale },
+                Self::Custom {
+                    shift: other_shift,
+                    scale: other_scale,
+                },
+            ) => {
+                shift.to_bits() == other_shift.to_bits() && scale.to_bits() == other_scale.to_bits()
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for BaselineShift {}
+
+impl Hash for BaselineShift {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+        if let Self::Custom { shift, scale } = self {
+            shift.to_bits().hash(state);
+            scale.to_bits().hash(state);
+        }
+    }
+}
+
+bitflags::bitflags! {
+    /// Lines drawn under, through, or over text, see [`crate::LayoutRun::decorations`]
+    #[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    #[repr(transparent)]
+    pub struct TextDecoration: u8 {
+        /// Line below the baseline
+        const UNDERLINE = 1;
+        /// Line through the middle of lowercase letters
+        const STRIKETHROUGH = 2;
+        /// Line at the ascent of the font
+        const OVERLINE = 4;
+    }
+}
+
+/// Shape of an [`EmphasisMark`]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum EmphasisShape {
+    /// A small dot
+    Dot,
+    /// A large circle
+    Circle,
+    /// A large circle around a small one
+    DoubleCircle,
+    /// A triangle pointing up
+    Triangle,
+}
+
+/// Side of the text an [`EmphasisMark`] is placed on
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum EmphasisPosition {
+    /// Above the ascent of the font
+    Over,
+    /// Below the descent of the font
+    Under,
+}
+
+/// An East Asian emphasis mark (圏点), drawn over or under every character except whitespace and
+/// punctuation, see [`crate::LayoutRun::emphasis_marks`]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct EmphasisMark {
+    pub shape: EmphasisShape,
+    /// False to draw only the outline of the shape
+    pub filled: bool,
+    pub position: EmphasisPosition,
+}
+
+impl EmphasisMark {
+    /// Create a filled emphasis mark
+    pub const fn new(shape: EmphasisShape, position: EmphasisPosition) -> Self {
+        Self {
+            shape,
+            filled: true,
+            position,
+        }
+    }
+
+    /// Set whether the shape is filled or only outlined
+    pub const fn filled(mut self, filled: bool) -> Self {
+        self.filled = filled;
+        self
+    }
+}
+
+/// An OpenType feature applied to the text of [`Attrs`], like turning off ligatures with `liga`
+/// or choosing a stylistic set with `ss01`
+///
+/// Features only apply to the text of the span they are set on, so they can differ within text
+/// that is shaped together.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct FontFeature {
+    /// Tag of the feature
+    pub tag: [u8; 4],
+    /// Value of the feature, 0 to turn it off, 1 to turn it on, or the index of an alternate
+    pub value: u32,
+}
+
+impl FontFeature {
+    /// Create a feature with the given tag and value
+    pub const fn new(tag: &[u8; 4], value: u32) -> Self {
+        Self { tag: *tag, value }
+    }
+
+    /// Create a feature that turns `tag` on
+    pub const fn enable(tag: &[u8; 4]) -> Self {
+        Self::new(tag, 1)
+    }
+
+    /// Create a feature that turns `tag` off
+    pub const fn disable(tag: &[u8; 4]) -> Self {
+        Self::new(tag, 0)
+    }
+}
+
+/// Capitalization of letters with small capitals, see [`Attrs::font_caps`]
+///
+/// The OpenType features of the font are used if it has them, otherwise small capitals are
+/// synthesized by shaping the uppercase letters at [`Caps::SYNTHESIZED_SCALE`] of the font size.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Caps {
+    /// Lowercase letters as small capitals, with the `smcp` feature
+    SmallCaps,
+    /// Lowercase and uppercase letters as small capitals, with the `smcp` and `c2sc` features
+    AllSmallCaps,
+    /// Lowercase letters as petite capitals, with the `pcap` feature, falling back to small
+    /// capitals
+    Petite,
+}
+
+impl Caps {
+    /// Factor the font size of synthesized small capitals is multiplied by
+    pub const SYNTHESIZED_SCALE: f32 = 0.7;
+
+    /// Get the OpenType features `face` has for these caps, or `None` if they must be synthesized
+    pub(crate) fn font_features(self, face: &rustybuzz::Face<'_>) -> Option<&'static [[u8; 4]]> {
+        let has_features = |tags: &[[u8; 4]]| {
+            face.tables().gsub.map_or(false, |gsub| {
+                tags.iter().all(|tag| {
+                    let tag = rustybuzz::ttf_parser::Tag::from_bytes(tag);
+                    gsub.features.into_iter().any(|feature| feature.tag == tag)
+                })
+            })
+        };
+        const SMCP: [u8; 4] = *b"smcp";
+        const C2SC: [u8; 4] = *b"c2sc";
+        const PCAP: [u8; 4] = *b"pcap";
+        let candidates: &[&'static [[u8; 4]]] = match self {
+            Self::SmallCaps => &[&[SMCP]],
+            Self::AllSmallCaps => &[&[SMCP, C2SC]],
+            Self::Petite => &[&[PCAP], &[SMCP]],
+        };
+        candidates.iter().copied().find(|tags| has_features(tags))
+    }
+
+    /// Whether `c` is shaped as an uppercase letter at a reduced size when synthesizing
+    pub(crate) fn synthesizes(self, c: char) -> bool {
+        match self {
+            Self::SmallCaps | Self::Petite => c.is_lowercase(),
+            Self::AllSmallCaps => c.is_lowercase() || c.is_uppercase(),
+        }
+    }
+}
+
+/// A value for an axis of variable fonts applied to the text of [`Attrs`], like `wdth`, `slnt`,
+/// `opsz`, `GRAD`, or a custom axis of the font
+///
+/// A `wght` value overrides [`Attrs::weight`] for the axis, and values are clamped to the range
+/// of the axis. Fonts without the axis ignore it.
+#[derive(Clone, Copy, Debug)]
+pub struct FontVariation {
+    /// Tag of the axis
+    pub tag: [u8; 4],
+    /// Value of the axis, in the units of the axis
+    pub value: f32,
+}
+
+impl FontVariation {
+    /// Create a variation with the given axis tag and value
+    pub const fn new(tag: &[u8; 4], value: f32) -> Self {
+        Self { tag: *tag, value }
+    }
+}
+
+impl PartialEq for FontVariation {
+    fn eq(&self, other: &Self) -> bool {
+        self.tag == other.tag && self.value.to_bits() == other.value.to_bits()
+    }
+}
+
+impl Eq for FontVariation {}
+
+impl Hash for FontVariation {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        self.tag.hash(hasher);
+        self.value.to_bits().hash(hasher);
+    }
+}
+
+/// Metadata of [`Attrs`], kept by every glyph shaped with them
+///
+/// This is `usize`, or `u32` with the `u32-metadata` feature, which makes a
+/// [`crate::LayoutGlyph`] smaller, see [`crate::MemoryUsage`].
+#[cfg(not(feature = "u32-metadata"))]
+pub type Metadata = usize;
+
+/// Metadata of [`Attrs`], kept by every glyph shaped with them
+///
+/// This is `usize`, or `u32` with the `u32-metadata` feature, which makes a
+/// [`crate::LayoutGlyph`] smaller, see [`crate::MemoryUsage`].
+#[cfg(feature = "u32-metadata")]
+pub type Metadata = u32;
+
 /// Text attributes
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct Attrs<'a> {
@@ -134,9 +438,21 @@ pub struct Attrs<'a> {
     pub stretch: Stretch,
     pub style: Style,
     pub weight: Weight,
-    pub metadata: usize,
+    pub metadata: Metadata,
     pub cache_key_flags: CacheKeyFlags,
     pub metrics_opt: Option<CacheMetrics>,
+    pub line_height_opt: Option<LineHeight>,
+    pub decoration: TextDecoration,
+    pub decoration_color_opt: Option<Color>,
+    pub background_color_opt: Option<Color>,
+    pub emphasis_opt: Option<EmphasisMark>,
+    pub baseline_shift_opt: Option<BaselineShift>,
+    pub combine_upright: bool,
+    pub shaping_boundary: bool,
+    pub font_features: &'a [FontFeature],
+    pub variations: &'a [FontVariation],
+    pub named_instance_opt: Option<&'a str>,
+    pub caps_opt: Option<Caps>,
 }
 
 impl<'a> Attrs<'a> {
@@ -153,6 +469,18 @@ impl<'a> Attrs<'a> {
             metadata: 0,
             cache_key_flags: CacheKeyFlags::empty(),
             metrics_opt: None,
+            line_height_opt: None,
+            decoration: TextDecoration::empty(),
+            decoration_color_opt: None,
+            background_color_opt: None,
+            emphasis_opt: None,
+            baseline_shift_opt: None,
+            combine_upright: false,
+            shaping_boundary: false,
+            font_features: &[],
+            variations: &[],
+            named_instance_opt: None,
+            caps_opt: None,
         }
     }
 
@@ -187,12 +515,12 @@ impl<'a> Attrs<'a> {
     }
 
     /// Set metadata
-    pub fn metadata(mut self, metadata: usize) -> Self {
+    pub fn metadata(mut self, metadata: Metadata) -> Self {
         self.metadata = metadata;
         self
     }
 
-    /// Set [`CacheKeyFlags`]
+    /// Set [`CacheKeyFlags`], including any application bits in [`CacheKeyFlags::USER`]
     pub fn cache_key_flags(mut self, cache_key_flags: CacheKeyFlags) -> Self {
         self.cache_key_flags = cache_key_flags;
         self
@@ -204,6 +532,101 @@ impl<'a> Attrs<'a> {
         self
     }
 
+    /// Set [`LineHeight`], overriding the line height of the buffer or of [`Self::metrics`].
+    /// Proportional line heights are resolved against the font size of these attributes
+    pub fn line_height(mut self, line_height: LineHeight) -> Self {
+        self.line_height_opt = Some(line_height);
+        self
+    }
+
+    /// Set [`TextDecoration`]
+    pub fn decoration(mut self, decoration: TextDecoration) -> Self {
+        self.decoration = decoration;
+        self
+    }
+
+    /// Set the [Color] of decorations, overriding the text color
+    pub fn decoration_color(mut self, color: Color) -> Self {
+        self.decoration_color_opt = Some(color);
+        self
+    }
+
+    /// Set the [Color] filling the background of the text, see [`crate::LayoutRun::backgrounds`]
+    pub fn background_color(mut self, color: Color) -> Self {
+        self.background_color_opt = Some(color);
+        self
+    }
+
+    /// Set the [`EmphasisMark`] drawn for every character, with the color of decorations
+    pub fn emphasis(mut self, emphasis: EmphasisMark) -> Self {
+        self.emphasis_opt = Some(emphasis);
+        self
+    }
+
+    /// Set the [`BaselineShift`] raising or lowering the text and scaling its font size, for
+    /// superscripts and subscripts
+    pub fn baseline_shift(mut self, baseline_shift: BaselineShift) -> Self {
+        self.baseline_shift_opt = Some(baseline_shift);
+        self
+    }
+
+    /// Set whether the text is combined upright in vertical text (縦中横, tate-chu-yoko), for
+    /// short runs of digits or Latin letters in a column
+    ///
+    /// The glyphs of each word of the text are set side by side across the column, taking up one
+    /// em down the column and scaled down to fit in one em across it. Horizontal text is not
+    /// affected, see [`crate::Direction::Vertical`].
+    pub fn combine_upright(mut self, combine_upright: bool) -> Self {
+        self.combine_upright = combine_upright;
+        self
+    }
+
+    /// Set whether text with these attributes is shaped separately from neighbouring text with
+    /// different attributes, so ligatures and kerning do not cross the boundary.
+    ///
+    /// By default text is only shaped separately where the font changes, and attributes that do
+    /// not affect shaping, like [`Self::color`] and [`Self::metadata`], are applied to each glyph
+    /// after shaping.
+    pub fn shaping_boundary(mut self, shaping_boundary: bool) -> Self {
+        self.shaping_boundary = shaping_boundary;
+        self
+    }
+
+    /// Set the OpenType features applied to the text, see [`FontFeature`]
+    pub fn font_features(mut self, font_features: &'a [FontFeature]) -> Self {
+        self.font_features = font_features;
+        self
+    }
+
+    /// Set the values of variable font axes, see [`FontVariation`]
+    ///
+    /// Text with different variations is shaped separately, as each set of values is a different
+    /// instance of the font.
+    pub fn variations(mut self, variations: &'a [FontVariation]) -> Self {
+        self.variations = variations;
+        self
+    }
+
+    /// Set the named instance of variable fonts to use, like `Condensed SemiBold`, see
+    /// [`crate::FontSystem::named_instances`]
+    ///
+    /// Fonts of the family that have the instance are preferred when matching fonts, and the
+    /// axis values of the instance override [`Self::weight`]. [`Self::variations`] override the
+    /// values of the instance.
+    pub fn named_instance(mut self, name: &'a str) -> Self {
+        self.named_instance_opt = Some(name);
+        self
+    }
+
+    /// Set the [`Caps`] the letters are shaped with, using small capitals of the font or
+    /// synthesizing them with [`crate::Shaping::Advanced`]
+    ///
+    /// Text with different caps is shaped separately.
+    pub fn font_caps(mut self, caps: Caps) -> Self {
+        self.caps_opt = Some(caps);
+        self
+    }
+
     /// Check if font matches
     pub fn matches(&self, face: &fontdb::FaceInfo) -> bool {
         //TODO: smarter way of including emoji
@@ -217,6 +640,10 @@ impl<'a> Attrs<'a> {
             && self.stretch == other.stretch
             && self.style == other.style
             && self.weight == other.weight
+            && self.variations == other.variations
+            && self.named_instance_opt == other.named_instance_opt
+            && self.caps_opt == other.caps_opt
+            && (!(self.shaping_boundary || other.shaping_boundary) || self == other)
     }
 }
 
@@ -227,6 +654,7 @@ pub struct FontMatchAttrs {
     stretch: Stretch,
     style: Style,
     weight: Weight,
+    named_instance_opt: Option<SmolStr>,
 }
 
 impl<'a> From<Attrs<'a>> for FontMatchAttrs {
@@ -236,6 +664,36 @@ impl<'a> From<Attrs<'a>> for FontMatchAttrs {
             stretch: attrs.stretch,
             style: attrs.style,
             weight: attrs.weight,
+            named_instance_opt: attrs.named_instance_opt.map(SmolStr::from),
+        }
+    }
+}
+
+impl FontMatchAttrs {
+    /// Check if these are the attributes of `attrs`, without converting them
+    pub(crate) fn matches(&self, attrs: &Attrs) -> bool {
+        self.family.as_family() == attrs.family
+            && self.stretch == attrs.stretch
+            && self.style == attrs.style
+            && self.weight == attrs.weight
+            && self.named_instance_opt.as_deref() == attrs.named_instance_opt
+    }
+}
+
+/// Part of [`FontMatchAttrs`] that selects the faces to match, the weight only changes the order
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub(crate) struct FontCandidateAttrs {
+    family: FamilyOwned,
+    stretch: Stretch,
+    style: Style,
+}
+
+impl From<&FontMatchAttrs> for FontCandidateAttrs {
+    fn from(attrs: &FontMatchAttrs) -> Self {
+        Self {
+            family: attrs.family.clone(),
+            stretch: attrs.stretch,
+            style: attrs.style,
         }
     }
 }
@@ -249,9 +707,21 @@ pub struct AttrsOwned {
     pub stretch: Stretch,
     pub style: Style,
     pub weight: Weight,
-    pub metadata: usize,
+    pub metadata: Metadata,
     pub cache_key_flags: CacheKeyFlags,
     pub metrics_opt: Option<CacheMetrics>,
+    pub line_height_opt: Option<LineHeight>,
+    pub decoration: TextDecoration,
+    pub decoration_color_opt: Option<Color>,
+    pub background_color_opt: Option<Color>,
+    pub emphasis_opt: Option<EmphasisMark>,
+    pub baseline_shift_opt: Option<BaselineShift>,
+    pub combine_upright: bool,
+    pub shaping_boundary: bool,
+    pub font_features: Vec<FontFeature>,
+    pub variations: Vec<FontVariation>,
+    pub named_instance_opt: Option<SmolStr>,
+    pub caps_opt: Option<Caps>,
 }
 
 impl AttrsOwned {
@@ -265,6 +735,18 @@ impl AttrsOwned {
             metadata: attrs.metadata,
             cache_key_flags: attrs.cache_key_flags,
             metrics_opt: attrs.metrics_opt,
+            line_height_opt: attrs.line_height_opt,
+            decoration: attrs.decoration,
+            decoration_color_opt: attrs.decoration_color_opt,
+            background_color_opt: attrs.background_color_opt,
+            emphasis_opt: attrs.emphasis_opt,
+            baseline_shift_opt: attrs.baseline_shift_opt,
+            combine_upright: attrs.combine_upright,
+            shaping_boundary: attrs.shaping_boundary,
+            font_features: attrs.font_features.to_vec(),
+            variations: attrs.variations.to_vec(),
+            named_instance_opt: attrs.named_instance_opt.map(SmolStr::from),
+            caps_opt: attrs.caps_opt,
         }
     }
 
@@ -278,6 +760,18 @@ impl AttrsOwned {
             metadata: self.metadata,
             cache_key_flags: self.cache_key_flags,
             metrics_opt: self.metrics_opt,
+            line_height_opt: self.line_height_opt,
+            decoration: self.decoration,
+            decoration_color_opt: self.decoration_color_opt,
+            background_color_opt: self.background_color_opt,
+            emphasis_opt: self.emphasis_opt,
+            baseline_shift_opt: self.baseline_shift_opt,
+            combine_upright: self.combine_upright,
+            shaping_boundary: self.shaping_boundary,
+            font_features: &self.font_features,
+            variations: &self.variations,
+            named_instance_opt: self.named_instance_opt.as_deref(),
+            caps_opt: self.caps_opt,
         }
     }
 }
@@ -304,7 +798,7 @@ impl AttrsList {
         self.defaults.as_attrs()
     }
 
-    /// Get the current attribute spans
+    /// Get the current attribute spans, allocating a [`Vec`], see [`Self::spans_iter`]
     pub fn spans(&self) -> Vec<(&Range<usize>, &AttrsOwned)> {
         self.spans_iter().collect()
     }
@@ -329,6 +823,33 @@ impl AttrsList {
         self.spans.insert(range, AttrsOwned::new(attrs));
     }
 
+    /// Set the color of `range`, keeping the other attributes of any spans it overlaps
+    pub fn set_color(&mut self, range: Range<usize>, color: Color) {
+        if range.is_empty() {
+            return;
+        }
+
+        let mut changes: Vec<(Range<usize>, AttrsOwned)> = self
+            .spans
+            .overlapping(&range)
+            .map(|(span, attrs)| {
+                (
+                    span.start.max(range.start)..span.end.min(range.end),
+                    attrs.clone(),
+                )
+            })
+            .collect();
+        changes.extend(
+            self.spans
+                .gaps(&range)
+                .map(|gap| (gap, self.defaults.clone())),
+        );
+        for (span, mut attrs) in changes {
+            attrs.color_opt = Some(color);
+            self.spans.insert(span, attrs);
+        }
+    }
+
     /// Get the attribute span for an index
     ///
     /// This returns a span that contains the index
@@ -339,6 +860,14 @@ impl AttrsList {
             .unwrap_or(self.defaults.as_attrs())
     }
 
+    /// Get the range of the attribute span that contains the index, or None if the index uses
+    /// the default [Attrs]
+    pub fn get_span_range(&self, index: usize) -> Option<Range<usize>> {
+        self.spans
+            .get_key_value(&index)
+            .map(|(range, _)| range.clone())
+    }
+
     /// Split attributes list at an offset
     pub fn split_off(&mut self, index: usize) -> Self {
         let mut new = Self::new(self.defaults.as_attrs());
@@ -1,14 +1,24 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use alloc::sync::Arc;
 #[cfg(not(feature = "std"))]
 use alloc::{string::String, vec::Vec};
-use core::{cmp, fmt};
+use core::{cmp, fmt, ops::Range, time::Duration};
+use smol_str::SmolStr;
 use unicode_segmentation::UnicodeSegmentation;
 
+use crate::layout::find_extras;
+use crate::minimap;
 use crate::{
-    Affinity, Align, Attrs, AttrsList, BidiParagraphs, BorrowedWithFontSystem, BufferLine, Color,
-    Cursor, FontSystem, LayoutCursor, LayoutGlyph, LayoutLine, LineEnding, LineIter, Motion,
-    Scroll, ShapeLine, Shaping, Wrap,
+    Affinity, Align, Attrs, AttrsList, BidiParagraphs, BorrowedWithFontSystem, BufferLine,
+    ChainedMotion, Color, ControlChars, Cursor, Decoration, DecorationKind, Direction,
+    EmphasisPosition, Error, FontSystem, Insets, JustifyMode, LayoutBackground, LayoutCursor,
+    LayoutDecoration, LayoutEmphasisMark, LayoutGlyph, LayoutGlyphExtras, LayoutGlyphMetrics,
+    LayoutLine, LayoutRangeDecoration, LineEnding, LineEndingStats, LineExclusions, LineHeight,
+    LineIter, LineProfileEntry, LineSettings, MemoryUsage, MinimapRun, MonoMetrics,
+    MonospaceDiagnostic, MonospaceMode, Motion, Overflow, ProfileReport, ProfileTimer, Rect,
+    Scroll, ScrollPolicy, ShapeLine, Shaping, TabStops, TextDecoration, TrailingWhitespace,
+    WordSegmenter, Wrap, WrapAffinity,
 };
 
 /// A line of visible text for rendering
@@ -16,12 +26,16 @@ use crate::{
 pub struct LayoutRun<'a> {
     /// The index of the original text line
     pub line_i: usize,
+    /// The index of the layout line within the original text line
+    pub layout_i: usize,
     /// The original text line
     pub text: &'a str,
     /// True if the original paragraph direction is RTL
     pub rtl: bool,
     /// The array of layout glyphs to draw
     pub glyphs: &'a [LayoutGlyph],
+    /// The rarely set attributes of [`Self::glyphs`], see [`LayoutLine::extras`]
+    pub extras: &'a [(usize, LayoutGlyphExtras)],
     /// Y offset to baseline of line
     pub line_y: f32,
     /// Y offset to top of line
@@ -33,6 +47,11 @@ pub struct LayoutRun<'a> {
 }
 
 impl<'a> LayoutRun<'a> {
+    /// Get the rarely set attributes of the glyph at `glyph_i` in [`Self::glyphs`], if it has any
+    pub fn glyph_extras(&self, glyph_i: usize) -> Option<&'a LayoutGlyphExtras> {
+        find_extras(self.extras, glyph_i)
+    }
+
     /// Return the pixel span `Some((x_left, x_width))` of the highlighted area between `cursor_start`
     /// and `cursor_end` within this run, or None if the cursor range does not intersect this run.
     /// This may return widths of zero if `cursor_start == cursor_end`, if the run is empty, or if the
@@ -71,25 +90,327 @@ impl<'a> LayoutRun<'a> {
         }
     }
 
+    /// Iterate over the glyphs of this run that are entirely before `end`, such as the end of a
+    /// prefix returned by [`Buffer::visible_prefix`]. Glyphs covering several characters, like
+    /// ligatures, are only included once all of their characters are before `end`
+    pub fn glyphs_before(&self, end: Cursor) -> impl Iterator<Item = &'a LayoutGlyph> + '_ {
+        self.glyphs
+            .iter()
+            .filter(move |glyph| match self.line_i.cmp(&end.line) {
+                cmp::Ordering::Less => true,
+                cmp::Ordering::Equal => glyph.end as usize <= end.index,
+                cmp::Ordering::Greater => false,
+            })
+    }
+
+    /// Get the background colors of this run, in visual order.
+    ///
+    /// Adjacent glyphs with the same background color share a single rectangle covering the
+    /// height of the line.
+    pub fn backgrounds(&self) -> Vec<LayoutBackground> {
+        let mut backgrounds: Vec<LayoutBackground> = Vec::new();
+        let mut previous_color = None;
+        for (glyph_i, glyph) in self.glyphs.iter().enumerate() {
+            let color_opt = self
+                .glyph_extras(glyph_i)
+                .and_then(|extras| extras.background_color_opt);
+            let glyph_x = glyph.x.min(glyph.x + glyph.w);
+            let glyph_w = glyph.w.abs();
+            match (color_opt, backgrounds.last_mut()) {
+                (Some(color), Some(background)) if previous_color == Some(color) => {
+                    let end = (background.x + background.w).max(glyph_x + glyph_w);
+                    background.x = background.x.min(glyph_x);
+                    background.w = end - background.x;
+                }
+                (Some(color), _) => backgrounds.push(LayoutBackground {
+                    x: glyph_x,
+                    y: self.line_top,
+                    w: glyph_w,
+                    h: self.line_height,
+                    color,
+                }),
+                (None, _) => {}
+            }
+            previous_color = color_opt;
+        }
+        backgrounds
+    }
+
+    /// Get the underlines, strikethroughs, and overlines of this run, in visual order.
+    ///
+    /// Adjacent glyphs with the same decoration and color share a single line, placed and sized
+    /// using the [`crate::DecorationMetrics`] of the largest glyph.
+    pub fn decorations(&self, font_system: &mut FontSystem) -> Vec<LayoutDecoration> {
+        let mut decorations = Vec::new();
+        for decoration in [
+            TextDecoration::UNDERLINE,
+            TextDecoration::STRIKETHROUGH,
+            TextDecoration::OVERLINE,
+        ] {
+            // Current line and the font size and font it was placed with
+            let mut current: Option<(LayoutDecoration, f32, fontdb::ID)> = None;
+            for (glyph_i, glyph) in self.glyphs.iter().enumerate() {
+                let color_opt = self
+                    .glyph_extras(glyph_i)
+                    .and_then(|extras| extras.decoration_color_opt)
+                    .or(glyph.color_opt);
+                let glyph_x = glyph.x.min(glyph.x + glyph.w);
+                let glyph_w = glyph.w.abs();
+                match &mut current {
+                    Some((line, font_size, font_id))
+                        if glyph.decoration.contains(decoration) && line.color_opt == color_opt =>
+                    {
+                        let end = (line.x + line.w).max(glyph_x + glyph_w);
+                        line.x = line.x.min(glyph_x);
+                        line.w = end - line.x;
+                        if glyph.font_size > *font_size {
+                            *font_size = glyph.font_size;
+                            *font_id = glyph.font_id;
+                        }
+                        continue;
+                    }
+                    _ => {}
+                }
+                if let Some(line) = current.take() {
+                    decorations.extend(self.place_decoration(font_system, line));
+                }
+                if glyph.decoration.contains(decoration) {
+                    let line = LayoutDecoration {
+                        decoration,
+                        x: glyph_x,
+                        y: 0.0,
+                        w: glyph_w,
+                        h: 0.0,
+                        color_opt,
+                    };
+                    current = Some((line, glyph.font_size, glyph.font_id));
+                }
+            }
+            if let Some(line) = current {
+                decorations.extend(self.place_decoration(font_system, line));
+            }
+        }
+        decorations
+    }
+
+    /// Get the shapes of the `decorations` covering text of this run, such as those of
+    /// [`Buffer::decorations`], in the order of `decorations`.
+    ///
+    /// Underlines and squiggles are placed using the [`crate::DecorationMetrics`] of the largest
+    /// decorated glyph, while boxes and highlights cover the height of the line.
+    pub fn range_decorations(
+        &self,
+        font_system: &mut FontSystem,
+        decorations: &[Decoration],
+    ) -> Vec<LayoutRangeDecoration> {
+        let mut shapes = Vec::new();
+        for (index, decoration) in decorations.iter().enumerate() {
+            let Range { start, end } = decoration.range;
+            if start.line > self.line_i || end.line < self.line_i {
+                continue;
+            }
+            let Some((x, w)) = self.highlight(start, end) else {
+                continue;
+            };
+            if w <= 0.0 {
+                continue;
+            }
+            let (y, h) = match decoration.kind {
+                DecorationKind::Box | DecorationKind::Highlight => {
+                    (self.line_top, self.line_height)
+                }
+                DecorationKind::Underline | DecorationKind::Squiggle => {
+                    let Some((y, thickness)) = self.underline_opt(font_system, start, end) else {
+                        continue;
+                    };
+                    if decoration.kind == DecorationKind::Squiggle {
+                        (y, thickness.max(1.0) * 3.0)
+                    } else {
+                        (y, thickness)
+                    }
+                }
+            };
+            shapes.push(LayoutRangeDecoration {
+                index,
+                kind: decoration.kind,
+                x,
+                y,
+                w,
+                h,
+                color: decoration.color,
+            });
+        }
+        shapes
+    }
+
+    /// Get the emphasis marks of the clusters of this run with [`crate::Attrs::emphasis`], in
+    /// visual order.
+    ///
+    /// Marks are centered on the advance of each cluster and placed at the ascent or descent of
+    /// its font, so they line up along the run. Whitespace and punctuation are not marked.
+    pub fn emphasis_marks(&self, font_system: &mut FontSystem) -> Vec<LayoutEmphasisMark> {
+        let mut marks: Vec<(LayoutEmphasisMark, f32, f32)> = Vec::new();
+        for (glyph_i, glyph) in self.glyphs.iter().enumerate() {
+            let Some(extras) = self.glyph_extras(glyph_i) else {
+                continue;
+            };
+            let Some(mark) = extras.emphasis_opt else {
+                continue;
+            };
+            let left = glyph.x.min(glyph.x + glyph.w);
+            let right = glyph.x.max(glyph.x + glyph.w);
+            if let Some((last, last_left, last_right)) = marks.last_mut() {
+                if (last.start..last.end) == glyph.range() {
+                    // Glyphs of the same cluster share one mark
+                    *last_left = last_left.min(left);
+                    *last_right = last_right.max(right);
+                    continue;
+                }
+            }
+            if !self.text[glyph.range()]
+                .chars()
+                .any(|c| !c.is_whitespace() && !c.is_control() && !is_punctuation(c))
+            {
+                continue;
+            }
+            let Some(font) = font_system.get_font(glyph.font_id) else {
+                continue;
+            };
+            let face = font.rustybuzz();
+            let upem = face.units_per_em() as f32;
+            let size = glyph.font_size / 2.0;
+            let y = match mark.position {
+                EmphasisPosition::Over => {
+                    self.line_y - f32::from(face.ascender()) / upem * glyph.font_size - size / 2.0
+                }
+                EmphasisPosition::Under => {
+                    self.line_y - f32::from(face.descender()) / upem * glyph.font_size + size / 2.0
+                }
+            };
+            let emphasis = LayoutEmphasisMark {
+                start: glyph.start as usize,
+                end: glyph.end as usize,
+                mark,
+                x: 0.0,
+                y,
+                size,
+                color_opt: extras.decoration_color_opt.or(glyph.color_opt),
+            };
+            marks.push((emphasis, left, right));
+        }
+        marks
+            .into_iter()
+            .map(|(mut mark, left, right)| {
+                mark.x = (left + right) / 2.0;
+                mark
+            })
+            .collect()
+    }
+
+    /// Get the origin, advance, and ink bounds of every glyph of this run, in the same order as
+    /// [`Self::glyphs`] and in the same coordinates as [`Self::line_top`].
+    ///
+    /// Advances cover the height of the line, so they can be used for custom cursors, while ink
+    /// bounds fit the outlines, to place marks like emphasis dots above or below them.
+    pub fn glyph_metrics(&self, font_system: &mut FontSystem) -> Vec<LayoutGlyphMetrics> {
+        let offset = |rect: Rect| Rect::new(rect.x, rect.y + self.line_y, rect.width, rect.height);
+        self.glyphs
+            .iter()
+            .map(|glyph| {
+                let (x, y) = glyph.origin();
+                let advance = glyph.advance_rect();
+                LayoutGlyphMetrics {
+                    start: glyph.start as usize,
+                    end: glyph.end as usize,
+                    origin: (x, y + self.line_y),
+                    advance: Rect::new(advance.x, self.line_top, advance.width, self.line_height),
+                    ink_opt: glyph.ink_rect(font_system).map(offset),
+                }
+            })
+            .collect()
+    }
+
+    /// Get the top and thickness of an underline of the glyphs between `start` and `end`
+    fn underline_opt(
+        &self,
+        font_system: &mut FontSystem,
+        start: Cursor,
+        end: Cursor,
+    ) -> Option<(f32, f32)> {
+        let glyph = self
+            .glyphs
+            .iter()
+            .filter(|glyph| {
+                (self.line_i, glyph.start as usize) >= (start.line, start.index)
+                    && (self.line_i, glyph.end as usize) <= (end.line, end.index)
+            })
+            .max_by(|a, b| a.font_size.total_cmp(&b.font_size))?;
+        let metrics = font_system.get_font(glyph.font_id)?.decoration_metrics();
+        Some((
+            self.line_y - metrics.underline_position * glyph.font_size,
+            metrics.underline_thickness * glyph.font_size,
+        ))
+    }
+
+    /// Set the position and thickness of a decoration line from the metrics of `font_id`
+    fn place_decoration(
+        &self,
+        font_system: &mut FontSystem,
+        (mut line, font_size, font_id): (LayoutDecoration, f32, fontdb::ID),
+    ) -> Option<LayoutDecoration> {
+        let metrics = font_system.get_font(font_id)?.decoration_metrics();
+        let (position, thickness) = if line.decoration == TextDecoration::UNDERLINE {
+            (metrics.underline_position, metrics.underline_thickness)
+        } else if line.decoration == TextDecoration::STRIKETHROUGH {
+            (
+                metrics.strikethrough_position,
+                metrics.strikethrough_thickness,
+            )
+        } else {
+            (metrics.overline_position, metrics.underline_thickness)
+        };
+        line.y = self.line_y - position * font_size;
+        line.h = thickness * font_size;
+        Some(line)
+    }
+
     fn cursor_from_glyph_left(&self, glyph: &LayoutGlyph) -> Cursor {
         if self.rtl {
-            Cursor::new_with_affinity(self.line_i, glyph.end, Affinity::Before)
+            Cursor::new_with_affinity(self.line_i, glyph.end as usize, Affinity::Before)
         } else {
-            Cursor::new_with_affinity(self.line_i, glyph.start, Affinity::After)
+            Cursor::new_with_affinity(self.line_i, glyph.start as usize, Affinity::After)
         }
     }
 
     fn cursor_from_glyph_right(&self, glyph: &LayoutGlyph) -> Cursor {
         if self.rtl {
-            Cursor::new_with_affinity(self.line_i, glyph.start, Affinity::After)
+            Cursor::new_with_affinity(self.line_i, glyph.start as usize, Affinity::After)
         } else {
-            Cursor::new_with_affinity(self.line_i, glyph.end, Affinity::Before)
+            Cursor::new_with_affinity(self.line_i, glyph.end as usize, Affinity::Before)
         }
     }
 }
 
+/// Returns true for ASCII and common CJK punctuation, which is not marked with emphasis marks
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation()
+        || matches!(
+            c,
+            '\u{2010}'..='\u{2027}'
+                | '\u{3001}'..='\u{3003}'
+                | '\u{3008}'..='\u{3011}'
+                | '\u{3014}'..='\u{301F}'
+                | '\u{30FB}'
+                | '\u{FF01}'..='\u{FF0F}'
+                | '\u{FF1A}'..='\u{FF20}'
+                | '\u{FF3B}'..='\u{FF40}'
+                | '\u{FF5B}'..='\u{FF65}'
+        )
+}
+
 /// An iterator of visible text lines, see [`LayoutRun`]
 #[derive(Debug)]
+#[must_use = "iterators are lazy and do nothing unless consumed"]
 pub struct LayoutRunIter<'b> {
     buffer: &'b Buffer,
     line_i: usize,
@@ -120,30 +441,39 @@ impl<'b> Iterator for LayoutRunIter<'b> {
             while let Some(layout_line) = layout.get(self.layout_i) {
                 self.layout_i += 1;
 
-                let line_height = layout_line
-                    .line_height_opt
-                    .unwrap_or(self.buffer.metrics.line_height);
-                self.total_height += line_height;
+                let default_line_height = self.buffer.scaled_metrics().line_height;
+                let line_height = layout_line.line_height_opt.unwrap_or(default_line_height);
+                let height = layout_line.height(default_line_height);
+                self.total_height += height;
 
-                let line_top = self.line_top - self.buffer.scroll.vertical;
+                let padding = self.buffer.layout_padding();
+                let line_top = padding.top + self.line_top + layout_line.space_before
+                    - self.buffer.scroll.vertical;
                 let glyph_height = layout_line.max_ascent + layout_line.max_descent;
                 let centering_offset = (line_height - glyph_height) / 2.0;
                 let line_y = line_top + centering_offset + layout_line.max_ascent;
-                if let Some(height) = self.buffer.height_opt {
-                    if line_y > height {
+                if let Some(height) = self.buffer.content_height_opt() {
+                    if line_y > padding.top + height {
                         return None;
                     }
                 }
-                self.line_top += line_height;
-                if line_y < 0.0 {
+                self.line_top += height;
+                if line_y < padding.top {
                     continue;
                 }
 
+                let line_i = self.line_i;
+                if line.last_line() == Some(self.layout_i - 1) {
+                    // Text after the last line that is laid out is not visible
+                    self.line_i = self.buffer.lines.len();
+                }
                 return Some(LayoutRun {
-                    line_i: self.line_i,
+                    line_i,
+                    layout_i: self.layout_i - 1,
                     text: line.text(),
                     rtl: shape.rtl,
                     glyphs: &layout_line.glyphs,
+                    extras: &layout_line.extras,
                     line_y,
                     line_top,
                     line_height,
@@ -158,6 +488,30 @@ impl<'b> Iterator for LayoutRunIter<'b> {
     }
 }
 
+/// A line of text in logical reading order, see [`Buffer::reading_text`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReadingParagraph<'a> {
+    /// The index of the original text line
+    pub line_i: usize,
+    /// The original text line, in logical order
+    pub text: &'a str,
+    /// True if the paragraph direction is RTL
+    pub rtl: bool,
+    /// The words of the line, in logical order
+    pub words: Vec<ReadingWord>,
+}
+
+/// A word of a [`ReadingParagraph`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReadingWord {
+    /// Byte range of the word in the line
+    pub range: core::ops::Range<usize>,
+    /// Visible areas covered by the word, in the same coordinates as [`LayoutRun`]. Empty if the
+    /// word is not visible, and may have more than one entry if the word is wrapped or split by
+    /// bidi reordering
+    pub rects: Vec<Rect>,
+}
+
 /// Metrics of text
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Metrics {
@@ -184,6 +538,14 @@ impl Metrics {
         }
     }
 
+    /// Create metrics with given font size and [`LineHeight`], resolved against the font size
+    pub fn with_line_height(font_size: f32, line_height: LineHeight) -> Self {
+        Self {
+            font_size,
+            line_height: line_height.resolve(font_size),
+        }
+    }
+
     /// Scale font size and line height
     pub fn scale(self, scale: f32) -> Self {
         Self {
@@ -199,6 +561,32 @@ impl fmt::Display for Metrics {
     }
 }
 
+/// How much shaping [`Buffer::shape_with_budget`] may do in one call
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ShapeBudget {
+    /// Shape up to this many lines
+    Lines(usize),
+    /// Shape lines until this much time has passed. Time is only measured with the `std` feature
+    /// on targets other than `wasm32`, otherwise a single line is shaped
+    Duration(Duration),
+}
+
+/// Progress of shaping a [`Buffer`] with [`Buffer::shape_with_budget`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ShapeProgress {
+    /// Number of lines shaped and laid out by this call
+    pub shaped: usize,
+    /// Number of lines still to be shaped or laid out
+    pub remaining: usize,
+}
+
+impl ShapeProgress {
+    /// Returns true if every line of the buffer is shaped and laid out
+    pub fn is_complete(&self) -> bool {
+        self.remaining == 0
+    }
+}
+
 /// A buffer of text that is shaped and laid out
 #[derive(Debug)]
 pub struct Buffer {
@@ -207,12 +595,24 @@ pub struct Buffer {
     metrics: Metrics,
     width_opt: Option<f32>,
     height_opt: Option<f32>,
+    padding: Insets,
     scroll: Scroll,
     /// True if a redraw is requires. Set to false after processing
     redraw: bool,
     wrap: Wrap,
     monospace_width: Option<f32>,
     tab_width: u16,
+    exclusions: Vec<Rect>,
+    /// Tops of the first lines laid out around [`Self::exclusions`], in buffer coordinates
+    line_tops: Vec<f32>,
+    split_paragraphs: bool,
+    line_settings: Arc<LineSettings>,
+    wrap_affinity: WrapAffinity,
+    monospace_mode: MonospaceMode,
+    overflow: Overflow,
+    ellipsis: SmolStr,
+    max_lines: Option<usize>,
+    decorations: Vec<Decoration>,
 }
 
 impl Clone for Buffer {
@@ -222,16 +622,31 @@ impl Clone for Buffer {
             metrics: self.metrics,
             width_opt: self.width_opt,
             height_opt: self.height_opt,
+            padding: self.padding,
             scroll: self.scroll,
             redraw: self.redraw,
             wrap: self.wrap,
             monospace_width: self.monospace_width,
             tab_width: self.tab_width,
+            exclusions: self.exclusions.clone(),
+            line_tops: self.line_tops.clone(),
+            split_paragraphs: self.split_paragraphs,
+            line_settings: self.line_settings.clone(),
+            wrap_affinity: self.wrap_affinity,
+            monospace_mode: self.monospace_mode,
+            overflow: self.overflow,
+            ellipsis: self.ellipsis.clone(),
+            max_lines: self.max_lines,
+            decorations: self.decorations.clone(),
         }
     }
 }
 
 impl Buffer {
+    /// Default length in bytes above which runs of text are shaped in chunks, see
+    /// [`Self::set_shape_chunk_len`]
+    pub const DEFAULT_SHAPE_CHUNK_LEN: usize = 4096;
+
     /// Create an empty [`Buffer`] with the provided [`Metrics`].
     /// This is useful for initializing a [`Buffer`] without a [`FontSystem`].
     ///
@@ -250,11 +665,25 @@ impl Buffer {
             metrics,
             width_opt: None,
             height_opt: None,
+            padding: Insets::default(),
             scroll: Scroll::default(),
             redraw: false,
             wrap: Wrap::WordOrGlyph,
             monospace_width: None,
             tab_width: 8,
+            exclusions: Vec::new(),
+            line_tops: Vec::new(),
+            split_paragraphs: false,
+            line_settings: Arc::new(LineSettings {
+                shape_chunk_len: Some(Self::DEFAULT_SHAPE_CHUNK_LEN),
+                ..LineSettings::default()
+            }),
+            wrap_affinity: WrapAffinity::default(),
+            monospace_mode: MonospaceMode::default(),
+            overflow: Overflow::default(),
+            ellipsis: SmolStr::new_inline("…"),
+            max_lines: None,
+            decorations: Vec::new(),
         }
     }
 
@@ -284,13 +713,35 @@ impl Buffer {
         #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
         let instant = std::time::Instant::now();
 
+        self.update_line_settings();
+        if !self.exclusions.is_empty() {
+            // Every line depends on the height of the lines before it
+            for line in &mut self.lines {
+                line.set_last_line(None);
+                line.reset_layout();
+            }
+            self.line_tops.clear();
+            // Laying out the last shaped line lays out all lines before it
+            if let Some(line_i) = self
+                .lines
+                .iter()
+                .rposition(|line| line.shape_opt().is_some())
+            {
+                self.line_layout(font_system, line_i);
+            }
+            self.redraw = true;
+            return;
+        }
+
+        let width_opt = self.content_width_opt();
         for line in &mut self.lines {
             if line.shape_opt().is_some() {
+                line.set_last_line(None);
                 line.reset_layout();
                 line.layout(
                     font_system,
                     self.metrics.font_size,
-                    self.width_opt,
+                    width_opt,
                     self.wrap,
                     self.monospace_width,
                     self.tab_width,
@@ -305,33 +756,49 @@ impl Buffer {
     }
 
     /// Shape lines until cursor, also scrolling to include cursor in view
+    ///
+    /// An invalid cursor is logged and only shapes lines until scroll, see
+    /// [`Self::try_shape_until_cursor`] to handle it.
     pub fn shape_until_cursor(
         &mut self,
         font_system: &mut FontSystem,
         cursor: Cursor,
         prune: bool,
     ) {
-        let metrics = self.metrics;
+        if let Err(err) = self.try_shape_until_cursor(font_system, cursor, prune) {
+            log::warn!("shape_until_cursor: {}", err);
+            self.shape_until_scroll(font_system, prune);
+        }
+    }
+
+    /// Shape lines until cursor, also scrolling to include cursor in view
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidCursor`] without changing the scroll if the cursor is not in the
+    /// buffer
+    pub fn try_shape_until_cursor(
+        &mut self,
+        font_system: &mut FontSystem,
+        cursor: Cursor,
+        prune: bool,
+    ) -> Result<(), Error> {
+        let metrics = self.scaled_metrics();
         let old_scroll = self.scroll;
 
-        let layout_cursor = self
-            .layout_cursor(font_system, cursor)
-            .expect("shape_until_cursor invalid cursor");
+        let layout_cursor = self.try_layout_cursor(font_system, cursor)?;
 
         let mut layout_y = 0.0;
         let mut total_height = {
             let layout = self
                 .line_layout(font_system, layout_cursor.line)
-                .expect("shape_until_cursor failed to scroll forwards");
+                .ok_or(Error::InvalidCursor(cursor))?;
             for layout_i in 0..layout_cursor.layout {
-                layout_y += layout[layout_i]
-                    .line_height_opt
-                    .unwrap_or(metrics.line_height);
-            }
-            layout_y
-                + layout[layout_cursor.layout]
-                    .line_height_opt
-                    .unwrap_or(metrics.line_height)
+                layout_y += layout[layout_i].height(metrics.line_height);
+            }
+            let layout_line = &layout[layout_cursor.layout];
+            layout_y += layout_line.space_before;
+            layout_y + layout_line.line_height_opt.unwrap_or(metrics.line_height)
         };
 
         if self.scroll.line > layout_cursor.line
@@ -340,7 +807,7 @@ impl Buffer {
             // Adjust scroll backwards if cursor is before it
             self.scroll.line = layout_cursor.line;
             self.scroll.vertical = layout_y;
-        } else if let Some(height) = self.height_opt {
+        } else if let Some(height) = self.content_height_opt() {
             // Adjust scroll forwards if cursor is after it
             let mut line_i = layout_cursor.line;
             if line_i <= self.scroll.line {
@@ -353,9 +820,9 @@ impl Buffer {
                     line_i -= 1;
                     let layout = self
                         .line_layout(font_system, line_i)
-                        .expect("shape_until_cursor failed to scroll forwards");
+                        .ok_or(Error::InvalidCursor(cursor))?;
                     for layout_line in layout.iter() {
-                        total_height += layout_line.line_height_opt.unwrap_or(metrics.line_height);
+                        total_height += layout_line.height(metrics.line_height);
                     }
                     if total_height > height + self.scroll.vertical {
                         self.scroll.line = line_i;
@@ -372,6 +839,7 @@ impl Buffer {
         self.shape_until_scroll(font_system, prune);
 
         // Adjust horizontal scroll to include cursor
+        let left = self.layout_padding().left;
         if let Some(layout_cursor) = self.layout_cursor(font_system, cursor) {
             if let Some(layout_lines) = self.line_layout(font_system, layout_cursor.line) {
                 if let Some(layout_line) = layout_lines.get(layout_cursor.layout) {
@@ -381,8 +849,8 @@ impl Buffer {
                         .or_else(|| layout_line.glyphs.last())
                     {
                         //TODO: use code from cursor_glyph_opt?
-                        let x_a = glyph.x;
-                        let x_b = glyph.x + glyph.w;
+                        let x_a = glyph.x - left;
+                        let x_b = glyph.x + glyph.w - left;
                         (x_a.min(x_b), x_a.max(x_b))
                     } else {
                         (0.0, 0.0)
@@ -391,7 +859,7 @@ impl Buffer {
                         self.scroll.horizontal = x_min;
                         self.redraw = true;
                     }
-                    if let Some(width) = self.width_opt {
+                    if let Some(width) = self.content_width_opt() {
                         if x_max > self.scroll.horizontal + width {
                             self.scroll.horizontal = x_max - width;
                             self.redraw = true;
@@ -400,11 +868,89 @@ impl Buffer {
                 }
             }
         }
+
+        // Do not scroll past the visible lines, in either direction
+        let range = self.horizontal_scroll_range();
+        let horizontal = self.scroll.horizontal.max(range.start).min(range.end);
+        if horizontal != self.scroll.horizontal {
+            self.scroll.horizontal = horizontal;
+            self.redraw = true;
+        }
+
+        Ok(())
+    }
+
+    /// Get the range of [`Scroll::horizontal`] that keeps the visible lines in view
+    ///
+    /// The range always includes 0.0, the start edge of lines. It extends to the right for lines
+    /// that overflow the width of the buffer to the right, like LTR lines, and to the left for
+    /// lines that overflow to the left, like RTL lines. Without a width, it is only 0.0.
+    pub fn horizontal_scroll_range(&self) -> Range<f32> {
+        let Some(width) = self.content_width_opt() else {
+            return 0.0..0.0;
+        };
+        let left = self.layout_padding().left;
+        let mut range = 0.0..0.0;
+        for run in self.layout_runs() {
+            for glyph in run.glyphs.iter() {
+                range.start = f32::min(range.start, glyph.x - left);
+                range.end = f32::max(range.end, glyph.x + glyph.w - left - width);
+            }
+        }
+        range
+    }
+
+    /// Scroll to show `cursor` as placed by `policy`, shaping lines as needed. Scrolling stops at
+    /// the start and end of the buffer, so the cursor may not end up where the policy places it
+    pub fn scroll_to_cursor(
+        &mut self,
+        font_system: &mut FontSystem,
+        cursor: Cursor,
+        policy: ScrollPolicy,
+        prune: bool,
+    ) {
+        let line_height = self.scaled_metrics().line_height;
+        let height_opt = self.content_height_opt();
+        let Some(layout_cursor) = self.layout_cursor(font_system, cursor) else {
+            return;
+        };
+        let Some(layout) = self.line_layout(font_system, layout_cursor.line) else {
+            return;
+        };
+        let mut layout_y = 0.0;
+        for layout_line in layout.iter().take(layout_cursor.layout) {
+            layout_y += layout_line.height(line_height);
+        }
+        let cursor_height = match layout.get(layout_cursor.layout) {
+            Some(layout_line) => {
+                layout_y += layout_line.space_before;
+                layout_line.line_height_opt.unwrap_or(line_height)
+            }
+            None => line_height,
+        };
+
+        let vertical_opt = match (policy, height_opt) {
+            (ScrollPolicy::Top, _) => Some(layout_y),
+            (ScrollPolicy::Center, Some(height)) => Some(layout_y + (cursor_height - height) / 2.0),
+            _ => None,
+        };
+        if let Some(vertical) = vertical_opt {
+            let scroll = Scroll::new(layout_cursor.line, vertical, self.scroll.horizontal);
+            if scroll != self.scroll {
+                self.scroll = scroll;
+                self.redraw = true;
+            }
+        }
+
+        // Clamps the scroll to the buffer, then scrolls horizontally and as little as possible
+        // vertically to show the cursor
+        self.shape_until_scroll(font_system, prune);
+        self.shape_until_cursor(font_system, cursor, prune);
     }
 
     /// Shape lines until scroll
     pub fn shape_until_scroll(&mut self, font_system: &mut FontSystem, prune: bool) {
-        let metrics = self.metrics;
+        let metrics = self.scaled_metrics();
         let old_scroll = self.scroll;
 
         loop {
@@ -415,8 +961,7 @@ impl Buffer {
                     if let Some(layout) = self.line_layout(font_system, line_i) {
                         let mut layout_height = 0.0;
                         for layout_line in layout.iter() {
-                            layout_height +=
-                                layout_line.line_height_opt.unwrap_or(metrics.line_height);
+                            layout_height += layout_line.height(metrics.line_height);
                         }
                         self.scroll.line = line_i;
                         self.scroll.vertical += layout_height;
@@ -432,19 +977,19 @@ impl Buffer {
             }
 
             let scroll_start = self.scroll.vertical;
-            let scroll_end = scroll_start + self.height_opt.unwrap_or(f32::INFINITY);
+            let scroll_end = scroll_start + self.content_height_opt().unwrap_or(f32::INFINITY);
 
             let mut total_height = 0.0;
             for line_i in 0..self.lines.len() {
                 if line_i < self.scroll.line {
                     if prune {
-                        self.lines[line_i].reset_shaping();
+                        self.lines[line_i].release_caches();
                     }
                     continue;
                 }
                 if total_height > scroll_end {
                     if prune {
-                        self.lines[line_i].reset_shaping();
+                        self.lines[line_i].release_caches();
                         continue;
                     } else {
                         break;
@@ -456,7 +1001,7 @@ impl Buffer {
                     .line_layout(font_system, line_i)
                     .expect("shape_until_scroll invalid line");
                 for layout_line in layout.iter() {
-                    let line_height = layout_line.line_height_opt.unwrap_or(metrics.line_height);
+                    let line_height = layout_line.height(metrics.line_height);
                     layout_height += line_height;
                     total_height += line_height;
                 }
@@ -481,6 +1026,188 @@ impl Buffer {
         if old_scroll != self.scroll {
             self.redraw = true;
         }
+
+        self.update_last_line(font_system);
+    }
+
+    /// Shape and lay out lines that are not laid out yet, starting at the scroll position and
+    /// wrapping around to the start of the buffer, until `budget` is used up.
+    ///
+    /// At least one line is shaped per call, so calling this once per frame until
+    /// [`ShapeProgress::is_complete`] spreads the shaping of a large document over several
+    /// frames, unlike [`Self::shape_until_scroll`] which does not return until the whole view is
+    /// shaped. The scroll position is not adjusted.
+    pub fn shape_with_budget(
+        &mut self,
+        font_system: &mut FontSystem,
+        budget: ShapeBudget,
+    ) -> ShapeProgress {
+        let timer = ProfileTimer::start();
+        let mut progress = ShapeProgress::default();
+        let start = self.scroll.line.min(self.lines.len());
+        for line_i in (start..self.lines.len()).chain(0..start) {
+            if self.lines[line_i].layout_opt().is_some() {
+                continue;
+            }
+            let exhausted = progress.shaped > 0
+                && match budget {
+                    ShapeBudget::Lines(lines) => progress.shaped >= lines,
+                    ShapeBudget::Duration(duration) => {
+                        !ProfileTimer::MEASURED || timer.elapsed() >= duration
+                    }
+                };
+            if exhausted {
+                progress.remaining += 1;
+            } else {
+                self.line_layout(font_system, line_i);
+                progress.shaped += 1;
+            }
+        }
+
+        if progress.shaped > 0 {
+            self.redraw = true;
+            if progress.is_complete() {
+                self.update_last_line(font_system);
+            }
+        }
+        progress
+    }
+
+    /// Cut the text short at the last visual line within [`Self::max_lines`], or that fits in
+    /// the height with [`Overflow::Ellipsis`], if there is more text after it.
+    ///
+    /// Only the visual lines after [`Self::scroll`] count toward the height.
+    fn update_last_line(&mut self, font_system: &mut FontSystem) {
+        let height_opt = match self.overflow {
+            Overflow::Clip => None,
+            Overflow::Ellipsis => self.content_height_opt(),
+        };
+        let max_lines_opt = self.max_lines;
+        let line_height = self.scaled_metrics().line_height;
+        // Cutting a line short changes its layout, so repeat until the last line is stable
+        for pass in 0..3 {
+            let mut last_opt = None;
+            if height_opt.is_some() || max_lines_opt.is_some() {
+                let start = if max_lines_opt.is_some() {
+                    0
+                } else {
+                    self.scroll.line
+                };
+                let mut count = 0;
+                let mut bottom = -self.scroll.vertical;
+                let mut prev_opt = None;
+                for line_i in start..self.lines.len() {
+                    let scrolled = line_i >= self.scroll.line;
+                    let Some(layout) = self.line_layout(font_system, line_i) else {
+                        break;
+                    };
+                    let layout_len = layout.len();
+                    for (layout_i, layout_line) in layout.iter().enumerate() {
+                        let layout_height = layout_line.height(line_height);
+                        let over_height = scrolled
+                            && height_opt.map_or(false, |height| bottom + layout_height > height);
+                        let over_lines = max_lines_opt.map_or(false, |max| count >= max);
+                        if over_height || over_lines {
+                            last_opt = Some(prev_opt.unwrap_or((line_i, layout_i)));
+                            break;
+                        }
+                        if scrolled {
+                            bottom += layout_height;
+                        }
+                        count += 1;
+                        prev_opt = Some((line_i, layout_i));
+                    }
+                    if last_opt.is_some() {
+                        break;
+                    }
+                    // Text hidden by a previous cut is only shown again if there is room for it
+                    if self.lines[line_i].last_line() == Some(layout_len - 1) {
+                        let room = height_opt.map_or(true, |height| bottom + line_height <= height)
+                            && max_lines_opt.map_or(true, |max| count < max);
+                        if pass > 0 || !room {
+                            last_opt = prev_opt;
+                            break;
+                        }
+                    }
+                }
+            }
+
+            let mut changed = false;
+            for line_i in 0..self.lines.len() {
+                let last_line = last_opt
+                    .filter(|&(last_i, _)| last_i == line_i)
+                    .map(|(_, layout_i)| layout_i);
+                if self.lines[line_i].set_last_line(last_line) {
+                    self.line_layout(font_system, line_i);
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+            self.redraw = true;
+        }
+    }
+
+    /// Get the index of the [`LayoutLine`] of [`Self::lines`]`[cursor.line]` that `cursor` is
+    /// placed on, resolving cursors at soft wrap boundaries with [`Self::wrap_affinity`].
+    /// Returns `None` if the line has not been laid out
+    pub fn cursor_layout_line(&self, cursor: Cursor) -> Option<usize> {
+        let layout = self.lines.get(cursor.line)?.layout_opt()?;
+        let bounds = |layout_line: &LayoutLine| {
+            let start = layout_line
+                .glyphs
+                .iter()
+                .map(|glyph| glyph.start as usize)
+                .min()?;
+            let end = layout_line
+                .glyphs
+                .iter()
+                .map(|glyph| glyph.end as usize)
+                .max()?;
+            Some((start, end))
+        };
+        for (layout_i, layout_line) in layout.iter().enumerate() {
+            let Some((_, end)) = bounds(layout_line) else {
+                continue;
+            };
+            if cursor.index < end {
+                return Some(layout_i);
+            }
+            if cursor.index == end {
+                let next_start = layout
+                    .get(layout_i + 1)
+                    .and_then(bounds)
+                    .map(|(start, _)| start);
+                if next_start == Some(end) && self.wrap_affinity.resolve(cursor).after() {
+                    return Some(layout_i + 1);
+                }
+                return Some(layout_i);
+            }
+        }
+        Some(layout.len().saturating_sub(1))
+    }
+
+    /// Convert a [`Cursor`] to a [`LayoutCursor`], like [`Self::layout_cursor`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidCursor`] if the cursor is not on a line of the buffer or not at a
+    /// character boundary of the line
+    pub fn try_layout_cursor(
+        &mut self,
+        font_system: &mut FontSystem,
+        cursor: Cursor,
+    ) -> Result<LayoutCursor, Error> {
+        let valid = self
+            .lines
+            .get(cursor.line)
+            .map_or(false, |line| line.text().is_char_boundary(cursor.index));
+        if !valid {
+            return Err(Error::InvalidCursor(cursor));
+        }
+        self.layout_cursor(font_system, cursor)
+            .ok_or(Error::InvalidCursor(cursor))
     }
 
     /// Convert a [`Cursor`] to a [`LayoutCursor`]
@@ -489,26 +1216,32 @@ impl Buffer {
         font_system: &mut FontSystem,
         cursor: Cursor,
     ) -> Option<LayoutCursor> {
-        let layout = self.line_layout(font_system, cursor.line)?;
-        for (layout_i, layout_line) in layout.iter().enumerate() {
-            for (glyph_i, glyph) in layout_line.glyphs.iter().enumerate() {
-                let cursor_end =
-                    Cursor::new_with_affinity(cursor.line, glyph.end, Affinity::Before);
-                let cursor_start =
-                    Cursor::new_with_affinity(cursor.line, glyph.start, Affinity::After);
-                let (cursor_left, cursor_right) = if glyph.level.is_ltr() {
-                    (cursor_start, cursor_end)
-                } else {
-                    (cursor_end, cursor_start)
-                };
-                if cursor == cursor_left {
-                    return Some(LayoutCursor::new(cursor.line, layout_i, glyph_i));
-                }
-                if cursor == cursor_right {
-                    return Some(LayoutCursor::new(cursor.line, layout_i, glyph_i + 1));
+        self.line_layout(font_system, cursor.line)?;
+        let layout_i = self.cursor_layout_line(cursor)?;
+        let layout_line = self.lines[cursor.line].layout_opt()?.get(layout_i)?;
+        // Edges of glyphs with different directions may be at the same index, so prefer the
+        // edge matching the affinity of the cursor
+        let mut glyph_opt = None;
+        for (glyph_i, glyph) in layout_line.glyphs.iter().enumerate() {
+            let start = (glyph.start as usize, Affinity::After);
+            let end = (glyph.end as usize, Affinity::Before);
+            let (left, right) = if glyph.level.is_ltr() {
+                (start, end)
+            } else {
+                (end, start)
+            };
+            for (edge_i, (index, affinity)) in [(glyph_i, left), (glyph_i + 1, right)] {
+                if index == cursor.index {
+                    if affinity == cursor.affinity {
+                        return Some(LayoutCursor::new(cursor.line, layout_i, edge_i));
+                    }
+                    glyph_opt.get_or_insert(edge_i);
                 }
             }
         }
+        if let Some(glyph_i) = glyph_opt {
+            return Some(LayoutCursor::new(cursor.line, layout_i, glyph_i));
+        }
 
         // Fall back to start of line
         //TODO: should this be the end of the line?
@@ -522,6 +1255,7 @@ impl Buffer {
         line_i: usize,
     ) -> Option<&ShapeLine> {
         let line = self.lines.get_mut(line_i)?;
+        line.apply_settings(&self.line_settings);
         Some(line.shape(font_system, self.tab_width))
     }
 
@@ -531,15 +1265,62 @@ impl Buffer {
         font_system: &mut FontSystem,
         line_i: usize,
     ) -> Option<&[LayoutLine]> {
-        let line = self.lines.get_mut(line_i)?;
-        Some(line.layout(
-            font_system,
-            self.metrics.font_size,
-            self.width_opt,
-            self.wrap,
-            self.monospace_width,
-            self.tab_width,
-        ))
+        if line_i >= self.lines.len() {
+            return None;
+        }
+
+        let width_opt = self.content_width_opt();
+        if self.exclusions.is_empty() {
+            let line = &mut self.lines[line_i];
+            line.apply_settings(&self.line_settings);
+            return Some(line.layout(
+                font_system,
+                self.metrics.font_size,
+                width_opt,
+                self.wrap,
+                self.monospace_width,
+                self.tab_width,
+            ));
+        }
+
+        // The position of this line depends on the layout of all lines before it. The tops of
+        // lines are kept until a line is laid out again, as its height may have changed
+        let checked = self.line_tops.len().min(line_i + 1);
+        if let Some(changed) = self.lines[..checked]
+            .iter()
+            .zip(self.line_tops.iter())
+            .position(|(line, &top)| line.layout_top_opt() != Some(top))
+        {
+            self.line_tops.truncate(changed + 1);
+        }
+        if self.line_tops.is_empty() {
+            self.line_tops.push(0.0);
+        }
+
+        let line_height = self.scaled_metrics().line_height;
+        for i in (self.line_tops.len() - 1).min(line_i)..=line_i {
+            let top = self.line_tops[i];
+            self.lines[i].apply_settings(&self.line_settings);
+            let layout = self.lines[i].layout_excluding(
+                font_system,
+                self.metrics.font_size,
+                width_opt,
+                self.wrap,
+                self.monospace_width,
+                self.tab_width,
+                LineExclusions {
+                    rects: &self.exclusions,
+                    top,
+                    line_height,
+                    ..LineExclusions::default()
+                },
+            );
+            if i + 1 == self.line_tops.len() {
+                let height: f32 = layout.iter().map(|line| line.height(line_height)).sum();
+                self.line_tops.push(top + height);
+            }
+        }
+        self.lines[line_i].layout_opt().map(Vec::as_slice)
     }
 
     /// Get the current [`Metrics`]
@@ -547,6 +1328,33 @@ impl Buffer {
         self.metrics
     }
 
+    /// Get the current [`Metrics`] multiplied by the text scale, as used for layout, see
+    /// [`Self::set_text_scale`]
+    pub fn scaled_metrics(&self) -> Metrics {
+        self.metrics.scale(self.line_settings.text_scale)
+    }
+
+    /// Get the factor all font sizes are multiplied by
+    pub fn text_scale(&self) -> f32 {
+        self.line_settings.text_scale
+    }
+
+    /// Set the factor all font sizes and line heights are multiplied by at layout time,
+    /// including per span overrides from [`crate::Attrs::metrics`], for example to apply an
+    /// accessibility zoom. Scales that are not finite and positive are ignored.
+    pub fn set_text_scale(&mut self, font_system: &mut FontSystem, text_scale: f32) {
+        if !(text_scale.is_finite() && text_scale > 0.0) {
+            log::warn!("ignoring invalid text scale {}", text_scale);
+            return;
+        }
+        if text_scale != self.line_settings.text_scale {
+            self.line_settings_mut().text_scale = text_scale;
+            self.update_line_settings();
+            self.redraw = true;
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
     /// Set the current [`Metrics`]
     ///
     /// # Panics
@@ -570,6 +1378,21 @@ impl Buffer {
         }
     }
 
+    /// Get where cursors at soft wrap boundaries are placed
+    pub fn wrap_affinity(&self) -> WrapAffinity {
+        self.wrap_affinity
+    }
+
+    /// Set where cursors at soft wrap boundaries are placed, at the end of the previous visual
+    /// line or the start of the next. This applies to drawing cursors and to motions on visual
+    /// lines like [`Motion::Home`] and [`Motion::End`]
+    pub fn set_wrap_affinity(&mut self, wrap_affinity: WrapAffinity) {
+        if wrap_affinity != self.wrap_affinity {
+            self.wrap_affinity = wrap_affinity;
+            self.redraw = true;
+        }
+    }
+
     /// Get the current `monospace_width`
     pub fn monospace_width(&self) -> Option<f32> {
         self.monospace_width
@@ -588,10 +1411,97 @@ impl Buffer {
         }
     }
 
-    /// Get the current `tab_width`
-    pub fn tab_width(&self) -> u16 {
-        self.tab_width
-    }
+    /// Set the [`Metrics`] and monospace width to the font size and cell size of a monospace
+    /// grid, see [`FontSystem::snap_mono_metrics`]
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `mono_metrics.font_size` is zero.
+    pub fn set_mono_metrics(&mut self, font_system: &mut FontSystem, mono_metrics: MonoMetrics) {
+        self.set_metrics(font_system, mono_metrics.metrics());
+        self.set_monospace_width(font_system, Some(mono_metrics.cell_width));
+    }
+
+    /// Get how monospace glyphs that do not match the monospace width are handled
+    pub fn monospace_mode(&self) -> MonospaceMode {
+        self.monospace_mode
+    }
+
+    /// Set how monospace glyphs that do not match the monospace width are handled, see
+    /// [`MonospaceMode`]
+    pub fn set_monospace_mode(
+        &mut self,
+        font_system: &mut FontSystem,
+        monospace_mode: MonospaceMode,
+    ) {
+        if monospace_mode != self.monospace_mode {
+            self.monospace_mode = monospace_mode;
+            self.relayout(font_system);
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
+    /// Iterate over the glyphs of laid out lines that were scaled or substituted to match the
+    /// monospace width, see [`Self::set_monospace_width`] and [`Self::set_monospace_mode`]
+    pub fn monospace_diagnostics(&self) -> impl Iterator<Item = MonospaceDiagnostic<'_>> + '_ {
+        self.lines.iter().enumerate().flat_map(|(line_i, line)| {
+            line.layout_opt()
+                .into_iter()
+                .flatten()
+                .enumerate()
+                .flat_map(move |(layout_i, layout_line)| {
+                    layout_line
+                        .monospace_adjustments
+                        .iter()
+                        .map(move |adjustment| MonospaceDiagnostic {
+                            line_i,
+                            layout_i,
+                            glyph: &layout_line.glyphs[adjustment.glyph_i()],
+                            adjustment: *adjustment,
+                        })
+                })
+        })
+    }
+
+    /// Font size and monospace width lines are shaped with in [`MonospaceMode::Strict`]
+    fn strict_monospace(&self) -> Option<(f32, f32)> {
+        match self.monospace_mode {
+            MonospaceMode::Scale => None,
+            MonospaceMode::Strict => self
+                .monospace_width
+                .map(|monospace_width| (self.metrics.font_size, monospace_width)),
+        }
+    }
+
+    fn line_settings_mut(&mut self) -> &mut LineSettings {
+        Arc::make_mut(&mut self.line_settings)
+    }
+
+    /// Update the line settings derived from other settings of the buffer, and apply the line
+    /// settings to every line, resetting the caches that changed settings affect
+    fn update_line_settings(&mut self) {
+        let x_offset = self.layout_padding().left;
+        let strict_monospace = self.strict_monospace();
+        let ellipsis = self.line_ellipsis();
+        let settings = &self.line_settings;
+        if settings.x_offset != x_offset
+            || settings.strict_monospace != strict_monospace
+            || settings.ellipsis != ellipsis
+        {
+            let settings = self.line_settings_mut();
+            settings.x_offset = x_offset;
+            settings.strict_monospace = strict_monospace;
+            settings.ellipsis = ellipsis;
+        }
+        for line in self.lines.iter_mut() {
+            line.apply_settings(&self.line_settings);
+        }
+    }
+
+    /// Get the current `tab_width`
+    pub fn tab_width(&self) -> u16 {
+        self.tab_width
+    }
 
     /// Set tab width (number of spaces between tab stops)
     pub fn set_tab_width(&mut self, font_system: &mut FontSystem, tab_width: u16) {
@@ -601,11 +1511,12 @@ impl Buffer {
         }
         if tab_width != self.tab_width {
             self.tab_width = tab_width;
-            // Shaping must be reset when tab width is changed
+            // Layout must be reset when tab width is changed, tabs are expanded again when the line
+            // is laid out without reshaping it
             for line in self.lines.iter_mut() {
                 if line.shape_opt().is_some() {
                     if line.text().contains('\t') {
-                        line.reset_shaping();
+                        line.reset_layout();
                     }
                 }
             }
@@ -614,9 +1525,367 @@ impl Buffer {
         }
     }
 
+    /// Get the tab stops used instead of the tab width, if any
+    pub fn tab_stops(&self) -> Option<&TabStops> {
+        self.line_settings.tab_stops.as_ref()
+    }
+
+    /// Set tab stops at explicit positions with their own alignment, used instead of the tab
+    /// width, or None to use the tab width again
+    ///
+    /// The tab width is still used for indentation by editors, and as the interval of the stops
+    /// after the last stop if [`TabStops::interval`] is None.
+    pub fn set_tab_stops(&mut self, font_system: &mut FontSystem, tab_stops: Option<TabStops>) {
+        if tab_stops != self.line_settings.tab_stops {
+            self.line_settings_mut().tab_stops = tab_stops;
+            self.update_line_settings();
+            self.redraw = true;
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
+    /// Set the alignment of every line, see [`BufferLine::set_align`]
+    ///
+    /// Lines that are laid out are only moved, unless they were or will be justified, so
+    /// changing the alignment of a large buffer does not lay it out again.
+    pub fn set_align(&mut self, font_system: &mut FontSystem, align: Option<Align>) {
+        let mut changed = false;
+        for line in self.lines.iter_mut() {
+            changed |= line.set_align(align);
+        }
+        if changed {
+            self.redraw = true;
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
+    /// Get the current exclusion zones
+    pub fn exclusions(&self) -> &[Rect] {
+        &self.exclusions
+    }
+
+    /// Set exclusion zones that text will wrap around, in buffer coordinates
+    ///
+    /// Each visual line is shortened to the widest segment not covered by an exclusion.
+    /// As line positions depend on the lines before them, laying out any line with exclusions
+    /// set requires laying out all previous lines that were not laid out yet.
+    pub fn set_exclusions(&mut self, font_system: &mut FontSystem, exclusions: Vec<Rect>) {
+        if exclusions != self.exclusions {
+            self.exclusions = exclusions;
+            self.relayout(font_system);
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
+    /// Get whether [`Buffer::set_text`] splits lines on Unicode paragraph separators
+    pub fn split_paragraphs(&self) -> bool {
+        self.split_paragraphs
+    }
+
+    /// Set whether [`Buffer::set_text`] splits lines on the Unicode paragraph separators U+2029
+    /// and NEL, in addition to CR and LF
+    pub fn set_split_paragraphs(&mut self, split_paragraphs: bool) {
+        self.split_paragraphs = split_paragraphs;
+    }
+
+    /// Get how control characters are displayed
+    pub fn control_chars(&self) -> ControlChars {
+        self.line_settings.control_chars
+    }
+
+    /// Set how control characters are displayed
+    pub fn set_control_chars(&mut self, font_system: &mut FontSystem, control_chars: ControlChars) {
+        if control_chars != self.line_settings.control_chars {
+            self.line_settings_mut().control_chars = control_chars;
+            self.update_line_settings();
+            self.redraw = true;
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
+    /// Get whether whitespace is shown
+    pub fn show_whitespace(&self) -> bool {
+        self.line_settings.show_whitespace
+    }
+
+    /// Set whether whitespace is shown
+    ///
+    /// Glyphs of spaces, tabs and no-break spaces are always marked with their
+    /// [`LayoutGlyph::whitespace_opt`]. When shown, they are drawn as their
+    /// [`crate::Whitespace::marker`] with their advances unchanged, and lines with a line ending
+    /// get a marker glyph after their last glyph.
+    pub fn set_show_whitespace(&mut self, font_system: &mut FontSystem, show_whitespace: bool) {
+        if show_whitespace != self.line_settings.show_whitespace {
+            self.line_settings_mut().show_whitespace = show_whitespace;
+            self.update_line_settings();
+            self.redraw = true;
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
+    /// Get the direction the lines of this buffer run in
+    pub fn direction(&self) -> Direction {
+        self.line_settings.direction
+    }
+
+    /// Set the direction the lines of this buffer run in, see [`Direction::Vertical`]
+    ///
+    /// The size of the buffer keeps its physical width and height, lines wrap at the height of
+    /// vertical buffers.
+    pub fn set_direction(&mut self, font_system: &mut FontSystem, direction: Direction) {
+        if direction != self.line_settings.direction {
+            let (width_opt, height_opt) = self.size();
+            self.line_settings_mut().direction = direction;
+            (self.width_opt, self.height_opt) = self.layout_size(width_opt, height_opt);
+            self.update_line_settings();
+            self.redraw = true;
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
+    /// Get the padding between the edges of the buffer and its text
+    pub fn padding(&self) -> Insets {
+        self.padding
+    }
+
+    /// Set the padding between the edges of the buffer and its text
+    ///
+    /// Lines wrap at the width minus the left and right padding, and scrolling keeps text out of
+    /// the top and bottom padding. Glyph positions, [`LayoutRun::line_top`] and [`Self::hit`]
+    /// include the padding, so they stay relative to the top left of the buffer. Exclusions are
+    /// relative to the top left of the text, inside the padding. The padding of
+    /// [`Direction::Vertical`] buffers applies to their physical edges.
+    pub fn set_padding(&mut self, font_system: &mut FontSystem, padding: Insets) {
+        if padding != self.padding {
+            self.padding = padding;
+            self.relayout(font_system);
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
+    /// Get the padding in the rotated layout frame of vertical buffers, where the top of the
+    /// layout is the right edge of the buffer
+    fn layout_padding(&self) -> Insets {
+        let padding = self.padding;
+        match self.line_settings.direction {
+            Direction::Horizontal => padding,
+            Direction::Vertical => {
+                Insets::new(padding.right, padding.bottom, padding.left, padding.top)
+            }
+        }
+    }
+
+    /// Get the width lines are laid out in, the width without the padding
+    fn content_width_opt(&self) -> Option<f32> {
+        let horizontal = self.layout_padding().horizontal();
+        self.width_opt.map(|width| (width - horizontal).max(0.0))
+    }
+
+    /// Get the height text is shown in, the height without the padding
+    fn content_height_opt(&self) -> Option<f32> {
+        let vertical = self.layout_padding().vertical();
+        self.height_opt.map(|height| (height - vertical).max(0.0))
+    }
+
+    /// Convert a physical size to the size of the rotated layout frame of vertical buffers, or
+    /// back
+    fn layout_size(
+        &self,
+        width_opt: Option<f32>,
+        height_opt: Option<f32>,
+    ) -> (Option<f32>, Option<f32>) {
+        match self.line_settings.direction {
+            Direction::Horizontal => (width_opt, height_opt),
+            Direction::Vertical => (height_opt, width_opt),
+        }
+    }
+
+    /// Get the locale of this buffer, see [`Self::set_locale`]
+    pub fn locale(&self) -> Option<&str> {
+        self.line_settings.locale.as_deref()
+    }
+
+    /// Set the locale of the text in this buffer as a language tag like `ja-JP`, overriding the
+    /// [`FontSystem`] locale when choosing fallback fonts. `None` uses the [`FontSystem`] locale
+    pub fn set_locale(&mut self, font_system: &mut FontSystem, locale: Option<&str>) {
+        if locale != self.locale() {
+            self.line_settings_mut().locale = locale.map(Arc::from);
+            self.update_line_settings();
+            self.redraw = true;
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
+    /// Get the segmenter used to split lines into words, `None` for [`crate::UnicodeWordSegmenter`]
+    pub fn word_segmenter(&self) -> Option<&Arc<dyn WordSegmenter>> {
+        self.line_settings.word_segmenter.as_ref()
+    }
+
+    /// Set the segmenter used to split lines into words for word motions like
+    /// [`Motion::NextWord`] and [`Motion::LeftWord`], `None` for [`crate::UnicodeWordSegmenter`].
+    /// The segmenter is given the locale of each line, see [`Self::set_locale`]
+    pub fn set_word_segmenter(&mut self, word_segmenter: Option<Arc<dyn WordSegmenter>>) {
+        self.line_settings_mut().word_segmenter = word_segmenter;
+        self.update_line_settings();
+    }
+
+    /// Get how whitespace at the end of visual lines is laid out
+    pub fn trailing_whitespace(&self) -> TrailingWhitespace {
+        self.line_settings.trailing_whitespace
+    }
+
+    /// Set how whitespace at the end of visual lines is laid out, and whether it counts toward
+    /// the width of [`LayoutLine`]s
+    pub fn set_trailing_whitespace(
+        &mut self,
+        font_system: &mut FontSystem,
+        trailing_whitespace: TrailingWhitespace,
+    ) {
+        if trailing_whitespace != self.line_settings.trailing_whitespace {
+            self.line_settings_mut().trailing_whitespace = trailing_whitespace;
+            self.update_line_settings();
+            self.redraw = true;
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
+    /// Get where [`Align::Justified`] spreads the extra width of visual lines
+    pub fn justify_mode(&self) -> JustifyMode {
+        self.line_settings.justify_mode
+    }
+
+    /// Set where [`Align::Justified`] spreads the extra width of visual lines
+    pub fn set_justify_mode(&mut self, font_system: &mut FontSystem, justify_mode: JustifyMode) {
+        if justify_mode != self.line_settings.justify_mode {
+            self.line_settings_mut().justify_mode = justify_mode;
+            self.update_line_settings();
+            self.redraw = true;
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
+    /// Get how text that does not fit in the buffer dimensions is handled
+    pub fn overflow(&self) -> Overflow {
+        self.overflow
+    }
+
+    /// Set how text that does not fit in the buffer dimensions is handled, see [`Overflow`]
+    pub fn set_overflow(&mut self, font_system: &mut FontSystem, overflow: Overflow) {
+        if overflow != self.overflow {
+            self.overflow = overflow;
+            self.update_line_settings();
+            self.redraw = true;
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
+    /// Get the text that replaces overflowing text with [`Overflow::Ellipsis`]
+    pub fn ellipsis(&self) -> &str {
+        &self.ellipsis
+    }
+
+    /// Set the text that replaces overflowing text with [`Overflow::Ellipsis`], `"…"` by
+    /// default. It is shaped with the default attributes of each line.
+    pub fn set_ellipsis(&mut self, font_system: &mut FontSystem, ellipsis: &str) {
+        if ellipsis != self.ellipsis {
+            self.ellipsis = SmolStr::new(ellipsis);
+            self.update_line_settings();
+            self.redraw = true;
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
+    /// Get the maximum number of visual lines laid out, if any
+    pub fn max_lines(&self) -> Option<usize> {
+        self.max_lines
+    }
+
+    /// Set the maximum number of visual lines laid out, counted from the start of the buffer.
+    /// Lines after the limit are not laid out or returned by [`Self::layout_runs`], and the last
+    /// line ends with [`Self::ellipsis`] if there is more text after it and
+    /// [`Overflow::Ellipsis`] is used. At least one line is always laid out.
+    ///
+    /// Unlike limiting the height of the buffer, this does not depend on the line heights, so it
+    /// also works with lines of different heights.
+    pub fn set_max_lines(&mut self, font_system: &mut FontSystem, max_lines: Option<usize>) {
+        let max_lines = max_lines.map(|max_lines| max_lines.max(1));
+        if max_lines != self.max_lines {
+            self.max_lines = max_lines;
+            self.redraw = true;
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
+    /// Ellipsis of each line for the current [`Overflow`]
+    fn line_ellipsis(&self) -> Option<SmolStr> {
+        match self.overflow {
+            Overflow::Clip => None,
+            Overflow::Ellipsis => Some(self.ellipsis.clone()),
+        }
+    }
+
+    /// Get the length in bytes above which runs of text are shaped in chunks, if any
+    pub fn shape_chunk_len(&self) -> Option<usize> {
+        self.line_settings.shape_chunk_len
+    }
+
+    /// Set the length in bytes above which runs of text with the same attributes and no line
+    /// break opportunities, like minified code or base64 data, are shaped in separate chunks.
+    ///
+    /// Chunks end after an ASCII character where possible, and are at most twice this length.
+    /// This keeps shaping and font fallback of very long lines fast, but kerning and ligatures
+    /// are not applied across chunk boundaries. The line is still laid out and edited as one.
+    /// `None` or a length of zero shapes every run as a whole. Defaults to
+    /// [`Self::DEFAULT_SHAPE_CHUNK_LEN`]
+    pub fn set_shape_chunk_len(
+        &mut self,
+        font_system: &mut FontSystem,
+        shape_chunk_len: Option<usize>,
+    ) {
+        let shape_chunk_len = shape_chunk_len.filter(|&len| len > 0);
+        if shape_chunk_len != self.line_settings.shape_chunk_len {
+            self.line_settings_mut().shape_chunk_len = shape_chunk_len;
+            self.update_line_settings();
+            self.redraw = true;
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
+    /// Get whether the shaping and layout costs of lines are recorded
+    pub fn profiling(&self) -> bool {
+        self.line_settings.profiling
+    }
+
+    /// Enable or disable recording the time spent shaping and laying out each line and the
+    /// number of fallback fonts tried for it, see [`Self::profile_report`]. Disabling profiling
+    /// discards the recorded costs
+    pub fn set_profiling(&mut self, profiling: bool) {
+        self.line_settings_mut().profiling = profiling;
+        self.update_line_settings();
+    }
+
+    /// Get the costs recorded for every line that was shaped or laid out since profiling was
+    /// enabled with [`Self::set_profiling`]
+    pub fn profile_report(&self) -> ProfileReport {
+        let lines = self
+            .lines
+            .iter()
+            .enumerate()
+            .filter_map(|(line_i, line)| {
+                let profile = *line.profile()?;
+                (profile.shape_count > 0 || profile.layout_count > 0).then(|| LineProfileEntry {
+                    line_i,
+                    len: line.text().len(),
+                    profile,
+                })
+            })
+            .collect();
+        ProfileReport { lines }
+    }
+
     /// Get the current buffer dimensions (width, height)
     pub fn size(&self) -> (Option<f32>, Option<f32>) {
-        (self.width_opt, self.height_opt)
+        self.layout_size(self.width_opt, self.height_opt)
     }
 
     /// Set the current buffer dimensions
@@ -641,6 +1910,7 @@ impl Buffer {
         width_opt: Option<f32>,
         height_opt: Option<f32>,
     ) {
+        let (width_opt, height_opt) = self.layout_size(width_opt, height_opt);
         let clamped_width_opt = width_opt.map(|width| width.max(0.0));
         let clamped_height_opt = height_opt.map(|height| height.max(0.0));
 
@@ -670,7 +1940,86 @@ impl Buffer {
         }
     }
 
+    /// Get the decorations drawn over ranges of text, see [`Self::set_decorations`]
+    pub fn decorations(&self) -> &[Decoration] {
+        &self.decorations
+    }
+
+    /// Set the decorations drawn over ranges of text by [`Self::draw`] and the editors, such as
+    /// spelling squiggles or search highlights. These do not change the text or its attributes,
+    /// so replacing them only requires a redraw. They are not moved when the text is edited
+    pub fn set_decorations(&mut self, decorations: Vec<Decoration>) {
+        if decorations != self.decorations {
+            self.decorations = decorations;
+            self.redraw = true;
+        }
+    }
+
+    /// Get the range of [`BufferLine`]s with at least one visual line returned by
+    /// [`Self::layout_runs`], as of the last call to [`Self::shape_until_scroll`]. The range is
+    /// empty if nothing is visible
+    pub fn visible_lines(&self) -> Range<usize> {
+        let mut runs = self.layout_runs();
+        let Some(first) = runs.next() else {
+            return self.scroll.line..self.scroll.line;
+        };
+        let last = runs.last().map_or(first.line_i, |run| run.line_i);
+        first.line_i..last + 1
+    }
+
+    /// Get the height in pixels of a [`BufferLine`] from its layout, or a single line of
+    /// [`Self::scaled_metrics`] if it is not laid out
+    fn line_height_estimate(&self, line_i: usize) -> f32 {
+        let line_height = self.scaled_metrics().line_height;
+        match self.lines[line_i].layout_opt() {
+            Some(layout) => layout
+                .iter()
+                .map(|layout_line| layout_line.height(line_height))
+                .sum(),
+            None => line_height,
+        }
+    }
+
+    /// Get the vertical scroll position in pixels from the top of the first line, see
+    /// [`Self::scroll_limits`]. Lines that are not laid out count as a single line each
+    pub fn scroll_offset(&self) -> f32 {
+        let line = cmp::min(self.scroll.line, self.lines.len());
+        (0..line)
+            .map(|line_i| self.line_height_estimate(line_i))
+            .sum::<f32>()
+            + self.scroll.vertical
+    }
+
+    /// Get the minimum and maximum of [`Self::scroll_offset`], where the maximum shows the end
+    /// of the last line at the bottom of the buffer height. Lines that are not laid out count as
+    /// a single line each, so the maximum grows as lines are shaped. Use these with the height to
+    /// size and position a scrollbar
+    pub fn scroll_limits(&self) -> (f32, f32) {
+        let total_height: f32 = (0..self.lines.len())
+            .map(|line_i| self.line_height_estimate(line_i))
+            .sum();
+        let height = self.content_height_opt().unwrap_or(f32::INFINITY);
+        (0.0, (total_height - height).max(0.0))
+    }
+
+    /// Iterate over the index and [`LineEnding`] of each line
+    pub fn line_endings(&self) -> impl Iterator<Item = (usize, LineEnding)> + '_ {
+        self.lines
+            .iter()
+            .enumerate()
+            .map(|(line_i, line)| (line_i, line.ending()))
+    }
+
+    /// Count the lines with each [`LineEnding`], for example to show the line ending of the text
+    /// in a status bar and warn about mixed line endings
+    pub fn line_ending_stats(&self) -> LineEndingStats {
+        self.lines.iter().map(BufferLine::ending).collect()
+    }
+
     /// Set text of buffer, using provided attributes for each line by default
+    ///
+    /// The text, attributes and caches of existing lines are reused, so setting text with the
+    /// same number of lines every frame does not allocate once the caches are warm.
     pub fn set_text(
         &mut self,
         font_system: &mut FontSystem,
@@ -678,24 +2027,108 @@ impl Buffer {
         attrs: Attrs,
         shaping: Shaping,
     ) {
-        self.lines.clear();
-        for (range, ending) in LineIter::new(text) {
+        let lines = if self.split_paragraphs {
+            LineIter::new_with_paragraphs(text)
+        } else {
+            LineIter::new(text)
+        };
+        let mut line_count = 0;
+        for (range, ending) in lines {
+            self.reset_line(line_count, &text[range], ending, attrs, shaping);
+            line_count += 1;
+        }
+        if line_count == 0 {
+            self.reset_line(0, "", LineEnding::default(), attrs, shaping);
+            line_count = 1;
+        }
+        self.lines.truncate(line_count);
+        self.scroll = Scroll::default();
+        self.shape_until_scroll(font_system, false);
+    }
+
+    /// Reset line `line_i` to `text` with `attrs`, reusing the allocations of the line if it
+    /// exists, or push a new line
+    fn reset_line(
+        &mut self,
+        line_i: usize,
+        text: &str,
+        ending: LineEnding,
+        attrs: Attrs,
+        shaping: Shaping,
+    ) {
+        if let Some(line) = self.lines.get_mut(line_i) {
+            let mut line_text = line.reclaim_text();
+            line_text.push_str(text);
+            let attrs_list = line.reclaim_attrs().reset(attrs);
+            line.reset_new(line_text, ending, attrs_list, shaping);
+        } else {
             self.lines.push(BufferLine::new(
-                &text[range],
+                text,
                 ending,
                 AttrsList::new(attrs),
                 shaping,
             ));
         }
-        if self.lines.is_empty() {
-            self.lines.push(BufferLine::new(
-                "",
-                LineEnding::default(),
-                AttrsList::new(attrs),
-                shaping,
-            ));
+    }
+
+    /// Set text of buffer from bytes that may not be valid UTF-8, using provided attributes for
+    /// each line by default
+    ///
+    /// Invalid sequences are replaced with U+FFFD REPLACEMENT CHARACTER, which is given
+    /// `replacement_attrs` to distinguish it from replacement characters present in the text.
+    pub fn set_text_lossy(
+        &mut self,
+        font_system: &mut FontSystem,
+        bytes: &[u8],
+        attrs: Attrs,
+        replacement_attrs: Attrs,
+        shaping: Shaping,
+    ) {
+        let mut text = String::with_capacity(bytes.len());
+        let mut replacements = Vec::new();
+        let mut remaining = bytes;
+        loop {
+            match core::str::from_utf8(remaining) {
+                Ok(valid) => {
+                    text.push_str(valid);
+                    break;
+                }
+                Err(err) => {
+                    let (valid, invalid) = remaining.split_at(err.valid_up_to());
+                    // The prefix was validated by from_utf8
+                    text.push_str(core::str::from_utf8(valid).unwrap_or_default());
+                    replacements.push(text.len());
+                    text.push(char::REPLACEMENT_CHARACTER);
+                    remaining = match err.error_len() {
+                        Some(len) => &invalid[len..],
+                        None => &[],
+                    };
+                }
+            }
+        }
+
+        self.set_text(font_system, &text, attrs, shaping);
+        if replacements.is_empty() {
+            return;
+        }
+
+        let replacement_len = char::REPLACEMENT_CHARACTER.len_utf8();
+        let mut replacements = replacements.into_iter().peekable();
+        let mut line_start = 0;
+        for line in self.lines.iter_mut() {
+            let line_end = line_start + line.text().len();
+            let mut attrs_list = line.attrs_list().clone();
+            while let Some(&index) = replacements.peek() {
+                if index >= line_end {
+                    break;
+                }
+                let start = index - line_start;
+                attrs_list.add_span(start..start + replacement_len, replacement_attrs);
+                replacements.next();
+            }
+            line.set_attrs_list(attrs_list);
+            line_start = line_end + line.ending().as_str().len();
         }
-        self.scroll = Scroll::default();
         self.shape_until_scroll(font_system, false);
     }
 
@@ -848,28 +2281,223 @@ impl Buffer {
             line.set_align(alignment);
         });
 
-        self.scroll = Scroll::default();
+        self.scroll = Scroll::default();
+
+        self.shape_until_scroll(font_system, false);
+    }
+
+    /// True if a redraw is needed
+    pub fn redraw(&self) -> bool {
+        self.redraw
+    }
+
+    /// Set redraw needed flag
+    pub fn set_redraw(&mut self, redraw: bool) {
+        self.redraw = redraw;
+    }
+
+    /// Set the color of `range` in line `line_i` without shaping it again, for color and opacity
+    /// animations. Only sets the redraw flag
+    pub fn recolor_span(&mut self, line_i: usize, range: core::ops::Range<usize>, color: Color) {
+        if let Some(line) = self.lines.get_mut(line_i) {
+            line.set_color(range, color);
+            self.redraw = true;
+        }
+    }
+
+    /// Iterate over the text between `start` and `end` without allocating, with one item for
+    /// every line, not including line endings
+    ///
+    /// The cursors may be in either order. Indices past the end of their line are clamped to it,
+    /// and indices inside a character are moved to its start.
+    pub fn text_between(&self, start: Cursor, end: Cursor) -> impl Iterator<Item = &str> + '_ {
+        let (start, end) = if start <= end {
+            (start, end)
+        } else {
+            (end, start)
+        };
+        // Cursors may be stale after an edit
+        let clamp = |text: &str, mut index: usize| {
+            index = index.min(text.len());
+            while !text.is_char_boundary(index) {
+                index -= 1;
+            }
+            index
+        };
+        self.lines
+            .get(start.line..=end.line.min(self.lines.len().saturating_sub(1)))
+            .unwrap_or_default()
+            .iter()
+            .enumerate()
+            .map(move |(i, line)| {
+                let line_i = start.line + i;
+                let text = line.text();
+                let start_index = if line_i == start.line {
+                    clamp(text, start.index)
+                } else {
+                    0
+                };
+                let end_index = if line_i == end.line {
+                    clamp(text, end.index)
+                } else {
+                    text.len()
+                };
+                &text[start_index..end_index]
+            })
+    }
+
+    /// Get the memory held by the glyphs of the shaping and layout caches of all lines
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let mut usage = MemoryUsage::default();
+        for line in self.lines.iter() {
+            usage.add(&line.memory_usage());
+        }
+        usage
+    }
 
-        self.shape_until_scroll(font_system, false);
+    /// Get the visible layout runs for rendering and other tasks
+    pub fn layout_runs(&self) -> LayoutRunIter {
+        LayoutRunIter::new(self)
     }
 
-    /// True if a redraw is needed
-    pub fn redraw(&self) -> bool {
-        self.redraw
+    /// Get a coarse representation of every line of the buffer for drawing a minimap, scaled by
+    /// `scale`
+    ///
+    /// Glyphs are merged into blocks of text with the same color and metadata, split at
+    /// whitespace. Lines that are laid out use their cached layout, so only lines that were shaped
+    /// again change. Other lines are estimated from their text and attributes, giving each
+    /// character half the font size in width, without shaping them.
+    pub fn minimap_runs(&self, scale: f32) -> Vec<MinimapRun> {
+        let metrics = self.scaled_metrics();
+        let char_w = metrics.font_size / 2.0;
+        let mut runs = Vec::with_capacity(self.lines.len());
+        let mut y = 0.0;
+        for (line_i, line) in self.lines.iter().enumerate() {
+            let Some(layout) = line.layout_opt() else {
+                runs.push(MinimapRun {
+                    line_i,
+                    estimated: true,
+                    y: y * scale,
+                    h: metrics.line_height * scale,
+                    segments: minimap::text_segments(
+                        line.text(),
+                        line.attrs_list(),
+                        char_w,
+                        self.tab_width,
+                        scale,
+                    ),
+                });
+                y += metrics.line_height;
+                continue;
+            };
+            for layout_line in layout.iter() {
+                let h = layout_line.height(metrics.line_height);
+                runs.push(MinimapRun {
+                    line_i,
+                    estimated: false,
+                    y: y * scale,
+                    h: h * scale,
+                    segments: minimap::glyph_segments(line.text(), &layout_line.glyphs, scale),
+                });
+                y += h;
+            }
+        }
+        runs
     }
 
-    /// Set redraw needed flag
-    pub fn set_redraw(&mut self, redraw: bool) {
-        self.redraw = redraw;
+    /// Get the end of the first `graphemes` grapheme clusters of the text, counting across lines
+    /// in logical order. Use with [`LayoutRun::glyphs_before`] to progressively reveal text
+    /// without shaping it again
+    pub fn visible_prefix(&self, graphemes: usize) -> Cursor {
+        let mut remaining = graphemes;
+        for (line_i, line) in self.lines.iter().enumerate() {
+            let boundaries = line.graphemes_cow();
+            let boundaries = boundaries.boundaries();
+            let clusters = boundaries.len() - 1;
+            if remaining < clusters || line_i + 1 == self.lines.len() {
+                let index = boundaries[remaining.min(clusters)];
+                return Cursor::new(line_i, index);
+            }
+            remaining -= clusters;
+        }
+        Cursor::default()
     }
 
-    /// Get the visible layout runs for rendering and other tasks
-    pub fn layout_runs(&self) -> LayoutRunIter {
-        LayoutRunIter::new(self)
+    /// Get the text of every line in logical reading order, for text-to-speech and other
+    /// accessibility uses, with the visible areas of each word for highlighting while speaking.
+    ///
+    /// Only words in the visible layout runs have rects, so call [`Self::shape_until_scroll`]
+    /// first and again after scrolling.
+    pub fn reading_text(&self) -> Vec<ReadingParagraph<'_>> {
+        let mut paragraphs: Vec<ReadingParagraph> = self
+            .lines
+            .iter()
+            .enumerate()
+            .map(|(line_i, line)| {
+                let text = line.text();
+                let rtl = line.shape_opt().map_or_else(
+                    || {
+                        unicode_bidi::BidiInfo::new(text, None)
+                            .paragraphs
+                            .first()
+                            .map_or(false, |paragraph| paragraph.level.is_rtl())
+                    },
+                    |shape| shape.rtl,
+                );
+                let words = line
+                    .graphemes_cow()
+                    .words()
+                    .iter()
+                    .map(|range| ReadingWord {
+                        range: range.clone(),
+                        rects: Vec::new(),
+                    })
+                    .collect();
+                ReadingParagraph {
+                    line_i,
+                    text,
+                    rtl,
+                    words,
+                }
+            })
+            .collect();
+
+        for run in self.layout_runs() {
+            for word in paragraphs[run.line_i].words.iter_mut() {
+                let start = Cursor::new(run.line_i, word.range.start);
+                let end = Cursor::new(run.line_i, word.range.end);
+                if let Some((x, w)) = run.highlight(start, end) {
+                    if w > 0.0 {
+                        word.rects
+                            .push(Rect::new(x, run.line_top, w, run.line_height));
+                    }
+                }
+            }
+        }
+
+        paragraphs
     }
 
     /// Convert x, y position to Cursor (hit detection)
+    ///
+    /// `x` is in layout coordinates, the same as [`LayoutGlyph::x`], so it does not account for
+    /// [`Scroll::horizontal`]. `y` is relative to the top of the view, the same as
+    /// [`LayoutRun::line_top`], so vertical scroll is already accounted for. Alignment offsets are
+    /// part of the glyph positions and need no adjustment. See [`Self::hit_view`] to hit test
+    /// positions in the view directly.
     pub fn hit(&self, x: f32, y: f32) -> Option<Cursor> {
+        self.hit_layout(x, y).map(|(cursor, _)| cursor)
+    }
+
+    /// Convert x, y position relative to the top left of the view to a Cursor, accounting for
+    /// both horizontal and vertical scroll. Also returns the index of the layout line within
+    /// [`Self::lines`] that was hit, see [`LayoutRun::layout_i`]
+    pub fn hit_view(&self, x: f32, y: f32) -> Option<(Cursor, usize)> {
+        self.hit_layout(x + self.scroll.horizontal, y)
+    }
+
+    /// Hit detection returning the cursor and the index of its layout line, see [`Self::hit`]
+    fn hit_layout(&self, x: f32, y: f32) -> Option<(Cursor, usize)> {
         #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
         let instant = std::time::Instant::now();
 
@@ -880,12 +2508,17 @@ impl Buffer {
         while let Some(run) = runs.next() {
             let line_top = run.line_top;
             let line_height = run.line_height;
+            // The paragraph spacing below a line hits that line
+            let line_bottom = runs
+                .peek()
+                .map_or(line_top + line_height, |next| next.line_top)
+                .max(line_top + line_height);
 
             if first_run && y < line_top {
                 first_run = false;
                 let new_cursor = Cursor::new(run.line_i, 0);
-                new_cursor_opt = Some(new_cursor);
-            } else if y >= line_top && y < line_top + line_height {
+                new_cursor_opt = Some((new_cursor, run.layout_i));
+            } else if y >= line_top && y < line_bottom {
                 let mut new_cursor_glyph = run.glyphs.len();
                 let mut new_cursor_char = 0;
                 let mut new_cursor_affinity = Affinity::After;
@@ -895,7 +2528,8 @@ impl Buffer {
                 'hit: for (glyph_i, glyph) in run.glyphs.iter().enumerate() {
                     if first_glyph {
                         first_glyph = false;
-                        if (run.rtl && x > glyph.x) || (!run.rtl && x < 0.0) {
+                        if (run.rtl && x > glyph.x) || (!run.rtl && x < self.layout_padding().left)
+                        {
                             new_cursor_glyph = 0;
                             new_cursor_char = 0;
                         }
@@ -903,13 +2537,14 @@ impl Buffer {
                     if x >= glyph.x && x <= glyph.x + glyph.w {
                         new_cursor_glyph = glyph_i;
 
-                        let cluster = &run.text[glyph.start..glyph.end];
-                        let total = cluster.grapheme_indices(true).count();
+                        let cluster = &run.text[glyph.range()];
+                        let graphemes = self.lines[run.line_i].graphemes_cow();
+                        let total = graphemes.within(glyph.range()).count();
                         let mut egc_x = glyph.x;
                         let egc_w = glyph.w / (total as f32);
-                        for (egc_i, egc) in cluster.grapheme_indices(true) {
+                        for egc in graphemes.within(glyph.range()) {
                             if x >= egc_x && x <= egc_x + egc_w {
-                                new_cursor_char = egc_i;
+                                new_cursor_char = egc.start - glyph.start as usize;
 
                                 let right_half = x >= egc_x + egc_w / 2.0;
                                 if right_half != glyph.level.is_rtl() {
@@ -937,19 +2572,19 @@ impl Buffer {
                 match run.glyphs.get(new_cursor_glyph) {
                     Some(glyph) => {
                         // Position at glyph
-                        new_cursor.index = glyph.start + new_cursor_char;
+                        new_cursor.index = glyph.start as usize + new_cursor_char;
                         new_cursor.affinity = new_cursor_affinity;
                     }
                     None => {
                         if let Some(glyph) = run.glyphs.last() {
                             // Position at end of line
-                            new_cursor.index = glyph.end;
+                            new_cursor.index = glyph.end as usize;
                             new_cursor.affinity = Affinity::Before;
                         }
                     }
                 }
 
-                new_cursor_opt = Some(new_cursor);
+                new_cursor_opt = Some((new_cursor, run.layout_i));
 
                 break;
             } else if runs.peek().is_none() && y > run.line_y {
@@ -957,7 +2592,7 @@ impl Buffer {
                 if let Some(glyph) = run.glyphs.last() {
                     new_cursor = run.cursor_from_glyph_right(glyph);
                 }
-                new_cursor_opt = Some(new_cursor);
+                new_cursor_opt = Some((new_cursor, run.layout_i));
             }
         }
 
@@ -967,6 +2602,132 @@ impl Buffer {
         new_cursor_opt
     }
 
+    /// Get the x position of the glyph edge at `layout_cursor`
+    fn layout_cursor_x(
+        &mut self,
+        font_system: &mut FontSystem,
+        layout_cursor: LayoutCursor,
+    ) -> Option<f32> {
+        let left = self.layout_padding().left;
+        let layout = self.line_layout(font_system, layout_cursor.line)?;
+        let layout_line = layout.get(layout_cursor.layout)?;
+        Some(match layout_line.glyphs.get(layout_cursor.glyph) {
+            Some(glyph) if glyph.level.is_rtl() => glyph.x + glyph.w,
+            Some(glyph) => glyph.x,
+            None => layout_line.glyphs.last().map_or(left, |glyph| {
+                if glyph.level.is_rtl() {
+                    glyph.x
+                } else {
+                    glyph.x + glyph.w
+                }
+            }),
+        })
+    }
+
+    /// Move `cursor` to the glyph edge closest to `cursor_x` in the layout line of
+    /// `layout_cursor`, using the last layout line if it is out of bounds
+    fn cursor_motion_to_x(
+        &mut self,
+        font_system: &mut FontSystem,
+        cursor: Cursor,
+        mut layout_cursor: LayoutCursor,
+        cursor_x: i32,
+    ) -> Option<(Cursor, Option<i32>)> {
+        let layout = self.line_layout(font_system, layout_cursor.line)?;
+        layout_cursor.layout = cmp::min(layout_cursor.layout, layout.len().checked_sub(1)?);
+        let glyphs = &layout[layout_cursor.layout].glyphs;
+        let x = cursor_x as f32;
+        let mut closest = (usize::MAX, f32::INFINITY);
+        for (glyph_i, glyph) in glyphs.iter().enumerate() {
+            let (edge_x, end_x) = if glyph.level.is_rtl() {
+                (glyph.x + glyph.w, glyph.x)
+            } else {
+                (glyph.x, glyph.x + glyph.w)
+            };
+            if (edge_x - x).abs() < closest.1 {
+                closest = (glyph_i, (edge_x - x).abs());
+            }
+            if glyph_i + 1 == glyphs.len() && (end_x - x).abs() < closest.1 {
+                closest = (usize::MAX, (end_x - x).abs());
+            }
+        }
+        layout_cursor.glyph = closest.0;
+
+        let (cursor, _) = self.cursor_motion(
+            font_system,
+            cursor,
+            None,
+            Motion::LayoutCursor(layout_cursor),
+        )?;
+        Some((cursor, Some(cursor_x)))
+    }
+
+    /// Apply a [`Motion`] to a [`Cursor`] like [`Self::cursor_motion`], but report
+    /// [`ChainedMotion::AtBoundary`] instead of staying in place when [`Motion::Up`] or
+    /// [`Motion::PreviousLine`] is on the first visual line, or [`Motion::Down`] is on the last
+    /// visual line and [`Motion::NextLine`] on the last line.
+    ///
+    /// This lets hosts with several buffers, like forms or notebooks, move focus to the adjacent
+    /// buffer and place its cursor with [`Self::cursor_enter`], keeping the horizontal position.
+    pub fn cursor_motion_chained(
+        &mut self,
+        font_system: &mut FontSystem,
+        cursor: Cursor,
+        cursor_x_opt: Option<i32>,
+        motion: Motion,
+    ) -> Option<ChainedMotion> {
+        if matches!(
+            motion,
+            Motion::Up | Motion::Down | Motion::PreviousLine | Motion::NextLine
+        ) {
+            let layout_cursor = self.layout_cursor(font_system, cursor)?;
+            let at_boundary = match motion {
+                Motion::Up | Motion::PreviousLine => {
+                    layout_cursor.line == 0 && layout_cursor.layout == 0
+                }
+                Motion::Down => {
+                    let layout_len = self.line_layout(font_system, layout_cursor.line)?.len();
+                    layout_cursor.line + 1 >= self.lines.len()
+                        && layout_cursor.layout + 1 >= layout_len
+                }
+                _ => layout_cursor.line + 1 >= self.lines.len(),
+            };
+            if at_boundary {
+                let cursor_x = match cursor_x_opt {
+                    Some(cursor_x) => cursor_x,
+                    None => self.layout_cursor_x(font_system, layout_cursor)? as i32,
+                };
+                return Some(ChainedMotion::AtBoundary { cursor_x });
+            }
+        }
+
+        let (cursor, cursor_x_opt) =
+            self.cursor_motion(font_system, cursor, cursor_x_opt, motion)?;
+        Some(ChainedMotion::Moved(cursor, cursor_x_opt))
+    }
+
+    /// Get the cursor entering the buffer from an adjacent one with `motion`, at the horizontal
+    /// position `cursor_x` of [`ChainedMotion::AtBoundary`]. [`Motion::Down`] and
+    /// [`Motion::NextLine`] enter on the first visual line, [`Motion::Up`] on the last visual line,
+    /// and [`Motion::PreviousLine`] on the first visual line of the last line. Other motions
+    /// return `None`
+    pub fn cursor_enter(
+        &mut self,
+        font_system: &mut FontSystem,
+        motion: Motion,
+        cursor_x: i32,
+    ) -> Option<(Cursor, Option<i32>)> {
+        let last = self.lines.len().checked_sub(1)?;
+        let layout_cursor = match motion {
+            Motion::Down | Motion::NextLine => LayoutCursor::new(0, 0, 0),
+            Motion::Up => LayoutCursor::new(last, usize::MAX, 0),
+            Motion::PreviousLine => LayoutCursor::new(last, 0, 0),
+            _ => return None,
+        };
+        let cursor = Cursor::new(layout_cursor.line, 0);
+        self.cursor_motion_to_x(font_system, cursor, layout_cursor, cursor_x)
+    }
+
     /// Apply a [`Motion`] to a [`Cursor`]
     pub fn cursor_motion(
         &mut self,
@@ -990,9 +2751,9 @@ impl Buffer {
                 };
 
                 let (new_index, new_affinity) = match layout_line.glyphs.get(layout_cursor.glyph) {
-                    Some(glyph) => (glyph.start, Affinity::After),
+                    Some(glyph) => (glyph.start as usize, Affinity::After),
                     None => match layout_line.glyphs.last() {
-                        Some(glyph) => (glyph.end, Affinity::Before),
+                        Some(glyph) => (glyph.end as usize, Affinity::Before),
                         //TODO: is this correct?
                         None => (0, Affinity::After),
                     },
@@ -1008,19 +2769,10 @@ impl Buffer {
                 }
             }
             Motion::Previous => {
-                let line = self.lines.get(cursor.line)?;
+                let line = self.lines.get_mut(cursor.line)?;
                 if cursor.index > 0 {
                     // Find previous character index
-                    let mut prev_index = 0;
-                    for (i, _) in line.text().grapheme_indices(true) {
-                        if i < cursor.index {
-                            prev_index = i;
-                        } else {
-                            break;
-                        }
-                    }
-
-                    cursor.index = prev_index;
+                    cursor.index = line.graphemes().prev(cursor.index);
                     cursor.affinity = Affinity::After;
                 } else if cursor.line > 0 {
                     cursor.line -= 1;
@@ -1030,15 +2782,10 @@ impl Buffer {
                 cursor_x_opt = None;
             }
             Motion::Next => {
-                let line = self.lines.get(cursor.line)?;
+                let line = self.lines.get_mut(cursor.line)?;
                 if cursor.index < line.text().len() {
-                    for (i, c) in line.text().grapheme_indices(true) {
-                        if i == cursor.index {
-                            cursor.index += c.len();
-                            cursor.affinity = Affinity::Before;
-                            break;
-                        }
-                    }
+                    cursor.index = line.graphemes().next(cursor.index);
+                    cursor.affinity = Affinity::Before;
                 } else if cursor.line + 1 < self.lines.len() {
                     cursor.line += 1;
                     cursor.index = 0;
@@ -1084,12 +2831,10 @@ impl Buffer {
             }
             Motion::Up => {
                 let mut layout_cursor = self.layout_cursor(font_system, cursor)?;
-
-                if cursor_x_opt.is_none() {
-                    cursor_x_opt = Some(
-                        layout_cursor.glyph as i32, //TODO: glyph x position
-                    );
-                }
+                let cursor_x = match cursor_x_opt {
+                    Some(cursor_x) => cursor_x,
+                    None => self.layout_cursor_x(font_system, layout_cursor)? as i32,
+                };
 
                 if layout_cursor.layout > 0 {
                     layout_cursor.layout -= 1;
@@ -1098,28 +2843,17 @@ impl Buffer {
                     layout_cursor.layout = usize::max_value();
                 }
 
-                if let Some(cursor_x) = cursor_x_opt {
-                    layout_cursor.glyph = cursor_x as usize; //TODO: glyph x position
-                }
-
-                (cursor, cursor_x_opt) = self.cursor_motion(
-                    font_system,
-                    cursor,
-                    cursor_x_opt,
-                    Motion::LayoutCursor(layout_cursor),
-                )?;
+                (cursor, cursor_x_opt) =
+                    self.cursor_motion_to_x(font_system, cursor, layout_cursor, cursor_x)?;
             }
             Motion::Down => {
                 let mut layout_cursor = self.layout_cursor(font_system, cursor)?;
+                let cursor_x = match cursor_x_opt {
+                    Some(cursor_x) => cursor_x,
+                    None => self.layout_cursor_x(font_system, layout_cursor)? as i32,
+                };
 
                 let layout_len = self.line_layout(font_system, layout_cursor.line)?.len();
-
-                if cursor_x_opt.is_none() {
-                    cursor_x_opt = Some(
-                        layout_cursor.glyph as i32, //TODO: glyph x position
-                    );
-                }
-
                 if layout_cursor.layout + 1 < layout_len {
                     layout_cursor.layout += 1;
                 } else if layout_cursor.line + 1 < self.lines.len() {
@@ -1127,16 +2861,25 @@ impl Buffer {
                     layout_cursor.layout = 0;
                 }
 
-                if let Some(cursor_x) = cursor_x_opt {
-                    layout_cursor.glyph = cursor_x as usize; //TODO: glyph x position
+                (cursor, cursor_x_opt) =
+                    self.cursor_motion_to_x(font_system, cursor, layout_cursor, cursor_x)?;
+            }
+            Motion::PreviousLine | Motion::NextLine => {
+                let mut layout_cursor = self.layout_cursor(font_system, cursor)?;
+                let cursor_x = match cursor_x_opt {
+                    Some(cursor_x) => cursor_x,
+                    None => self.layout_cursor_x(font_system, layout_cursor)? as i32,
+                };
+
+                if motion == Motion::PreviousLine {
+                    layout_cursor.line = layout_cursor.line.saturating_sub(1);
+                } else if layout_cursor.line + 1 < self.lines.len() {
+                    layout_cursor.line += 1;
                 }
+                layout_cursor.layout = 0;
 
-                (cursor, cursor_x_opt) = self.cursor_motion(
-                    font_system,
-                    cursor,
-                    cursor_x_opt,
-                    Motion::LayoutCursor(layout_cursor),
-                )?;
+                (cursor, cursor_x_opt) =
+                    self.cursor_motion_to_x(font_system, cursor, layout_cursor, cursor_x)?;
             }
             Motion::Home => {
                 let mut layout_cursor = self.layout_cursor(font_system, cursor)?;
@@ -1152,14 +2895,34 @@ impl Buffer {
                 }
                 cursor_x_opt = None;
             }
-            Motion::SoftHome => {
+            Motion::SoftHome | Motion::SoftEnd => {
+                let layout_cursor = self.layout_cursor(font_system, cursor)?;
                 let line = self.lines.get(cursor.line)?;
-                cursor.index = line
-                    .text()
-                    .char_indices()
-                    .filter_map(|(i, c)| if c.is_whitespace() { None } else { Some(i) })
-                    .next()
+                let layout_line = line.layout_opt()?.get(layout_cursor.layout)?;
+                let start = layout_line
+                    .glyphs
+                    .iter()
+                    .map(|glyph| glyph.start as usize)
+                    .min()
                     .unwrap_or(0);
+                let end = layout_line
+                    .glyphs
+                    .iter()
+                    .map(|glyph| glyph.end as usize)
+                    .max()
+                    .unwrap_or(start);
+                let mut chars = line.text()[start..end].char_indices();
+                if motion == Motion::SoftHome {
+                    cursor.index = chars
+                        .find(|(_, c)| !c.is_whitespace())
+                        .map_or(start, |(i, _)| start + i);
+                    cursor.affinity = Affinity::After;
+                } else {
+                    cursor.index = chars
+                        .rfind(|(_, c)| !c.is_whitespace())
+                        .map_or(end, |(i, c)| start + i + c.len_utf8());
+                    cursor.affinity = Affinity::Before;
+                }
                 cursor_x_opt = None;
             }
             Motion::End => {
@@ -1184,8 +2947,79 @@ impl Buffer {
                 cursor.index = self.lines.get(cursor.line)?.text().len();
                 cursor_x_opt = None;
             }
+            Motion::ParagraphUp => {
+                let blank = |line_i: usize| self.lines[line_i].text().trim().is_empty();
+                // Skip blank lines, then the block of text before them
+                let mut line_i = cursor.line.min(self.lines.len().checked_sub(1)?);
+                while line_i > 0 && blank(line_i) {
+                    line_i -= 1;
+                }
+                while line_i > 0 && !blank(line_i) {
+                    line_i -= 1;
+                }
+                cursor.line = line_i;
+                cursor.index = 0;
+                cursor_x_opt = None;
+            }
+            Motion::ParagraphDown => {
+                let blank = |line_i: usize| self.lines[line_i].text().trim().is_empty();
+                // Skip blank lines, then the block of text after them
+                let last = self.lines.len().checked_sub(1)?;
+                let mut line_i = cursor.line.min(last);
+                while line_i < last && blank(line_i) {
+                    line_i += 1;
+                }
+                while line_i < last && !blank(line_i) {
+                    line_i += 1;
+                }
+                cursor.line = line_i;
+                cursor.index = if blank(line_i) {
+                    0
+                } else {
+                    self.lines[line_i].text().len()
+                };
+                cursor_x_opt = None;
+            }
+            Motion::SentenceNext => {
+                let text = self.lines.get(cursor.line)?.text();
+                match text
+                    .split_sentence_bound_indices()
+                    .map(|(i, _)| i)
+                    .find(|&i| i > cursor.index)
+                {
+                    Some(index) => cursor.index = index,
+                    None if cursor.line + 1 < self.lines.len() => {
+                        cursor.line += 1;
+                        cursor.index = 0;
+                    }
+                    None => cursor.index = text.len(),
+                }
+                cursor_x_opt = None;
+            }
+            Motion::SentencePrevious => {
+                let text = self.lines.get(cursor.line)?.text();
+                match text
+                    .split_sentence_bound_indices()
+                    .map(|(i, _)| i)
+                    .filter(|&i| i < cursor.index)
+                    .last()
+                {
+                    Some(index) => cursor.index = index,
+                    None if cursor.line > 0 => {
+                        // The last sentence of the previous line
+                        cursor.line -= 1;
+                        cursor.index = self.lines[cursor.line]
+                            .text()
+                            .split_sentence_bound_indices()
+                            .last()
+                            .map_or(0, |(i, _)| i);
+                    }
+                    None => cursor.index = 0,
+                }
+                cursor_x_opt = None;
+            }
             Motion::PageUp => {
-                if let Some(height) = self.height_opt {
+                if let Some(height) = self.content_height_opt() {
                     (cursor, cursor_x_opt) = self.cursor_motion(
                         font_system,
                         cursor,
@@ -1195,7 +3029,7 @@ impl Buffer {
                 }
             }
             Motion::PageDown => {
-                if let Some(height) = self.height_opt {
+                if let Some(height) = self.content_height_opt() {
                     (cursor, cursor_x_opt) = self.cursor_motion(
                         font_system,
                         cursor,
@@ -1206,7 +3040,7 @@ impl Buffer {
             }
             Motion::Vertical(px) => {
                 // TODO more efficient, use layout run line height
-                let lines = px / self.metrics().line_height as i32;
+                let lines = px / self.scaled_metrics().line_height as i32;
                 match lines.cmp(&0) {
                     cmp::Ordering::Less => {
                         for _ in 0..-lines {
@@ -1228,15 +3062,10 @@ impl Buffer {
                 }
             }
             Motion::PreviousWord => {
-                let line = self.lines.get(cursor.line)?;
+                let line = self.lines.get_mut(cursor.line)?;
+                line.apply_settings(&self.line_settings);
                 if cursor.index > 0 {
-                    cursor.index = line
-                        .text()
-                        .unicode_word_indices()
-                        .rev()
-                        .map(|(i, _)| i)
-                        .find(|&i| i < cursor.index)
-                        .unwrap_or(0);
+                    cursor.index = line.graphemes().prev_word(cursor.index);
                 } else if cursor.line > 0 {
                     cursor.line -= 1;
                     cursor.index = self.lines.get(cursor.line)?.text().len();
@@ -1244,20 +3073,22 @@ impl Buffer {
                 cursor_x_opt = None;
             }
             Motion::NextWord => {
-                let line = self.lines.get(cursor.line)?;
+                let line = self.lines.get_mut(cursor.line)?;
+                line.apply_settings(&self.line_settings);
                 if cursor.index < line.text().len() {
-                    cursor.index = line
-                        .text()
-                        .unicode_word_indices()
-                        .map(|(i, word)| i + word.len())
-                        .find(|&i| i > cursor.index)
-                        .unwrap_or(line.text().len());
+                    cursor.index = line.graphemes().next_word(cursor.index);
                 } else if cursor.line + 1 < self.lines.len() {
                     cursor.line += 1;
                     cursor.index = 0;
                 }
                 cursor_x_opt = None;
             }
+            Motion::WordEnd => {
+                let line = self.lines.get_mut(cursor.line)?;
+                line.apply_settings(&self.line_settings);
+                cursor.index = line.graphemes().next_word(cursor.index);
+                cursor_x_opt = None;
+            }
             Motion::LeftWord => {
                 let rtl_opt = self
                     .line_shape(font_system, cursor.line)
@@ -1337,15 +3168,64 @@ impl Buffer {
     ) where
         F: FnMut(i32, i32, u32, u32, Color),
     {
+        // Vertical layout is in a rotated frame, with columns stacked from the right edge
+        let columns_w = self.height_opt.unwrap_or_else(|| {
+            self.layout_runs()
+                .last()
+                .map_or(0.0, |run| run.line_top + run.line_height)
+        });
+        let mut f =
+            |x: i32, y: i32, w: u32, h: u32, color: Color| match self.line_settings.direction {
+                Direction::Horizontal => f(x, y, w, h, color),
+                Direction::Vertical => f(columns_w as i32 - y - h as i32, x, h, w, color),
+            };
+
         for run in self.layout_runs() {
-            for glyph in run.glyphs.iter() {
-                let physical_glyph = glyph.physical((0., 0.), 1.0);
+            for background in run.backgrounds() {
+                f(
+                    background.x as i32,
+                    background.y as i32,
+                    background.w as u32,
+                    background.h as u32,
+                    background.color,
+                );
+            }
+
+            let range_decorations = run.range_decorations(font_system, &self.decorations);
+            for shape in range_decorations.iter() {
+                if shape.kind == DecorationKind::Highlight {
+                    shape.draw(&mut f);
+                }
+            }
 
+            for glyph in run.glyphs.iter() {
                 let glyph_color = match glyph.color_opt {
                     Some(some) => some,
                     None => color,
                 };
 
+                if self.line_settings.direction == Direction::Vertical {
+                    let column_x = columns_w - run.line_top - run.line_height / 2.0;
+                    let physical_glyph = glyph.physical_vertical((0., 0.), 1.0, column_x);
+                    cache.with_pixels(
+                        font_system,
+                        physical_glyph.cache_key,
+                        glyph_color,
+                        |x, y, color| {
+                            // Glyph pixels are already on screen, undo the rotation of `f`
+                            f(
+                                physical_glyph.y + y,
+                                columns_w as i32 - physical_glyph.x - x - 1,
+                                1,
+                                1,
+                                color,
+                            );
+                        },
+                    );
+                    continue;
+                }
+
+                let physical_glyph = glyph.physical((0., 0.), 1.0);
                 cache.with_pixels(
                     font_system,
                     physical_glyph.cache_key,
@@ -1361,6 +3241,26 @@ impl Buffer {
                     },
                 );
             }
+
+            for decoration in run.decorations(font_system) {
+                f(
+                    decoration.x as i32,
+                    decoration.y as i32,
+                    decoration.w as u32,
+                    cmp::max(1, decoration.h.round() as u32),
+                    decoration.color_opt.unwrap_or(color),
+                );
+            }
+
+            for mark in run.emphasis_marks(font_system) {
+                mark.draw(color, &mut f);
+            }
+
+            for shape in range_decorations.iter() {
+                if shape.kind != DecorationKind::Highlight {
+                    shape.draw(&mut f);
+                }
+            }
         }
     }
 }
@@ -1372,11 +3272,42 @@ impl<'a> BorrowedWithFontSystem<'a, Buffer> {
             .shape_until_cursor(self.font_system, cursor, prune);
     }
 
+    /// Shape lines until cursor, also scrolling to include cursor in view
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidCursor`] if the cursor is not in the buffer
+    pub fn try_shape_until_cursor(&mut self, cursor: Cursor, prune: bool) -> Result<(), Error> {
+        self.inner
+            .try_shape_until_cursor(self.font_system, cursor, prune)
+    }
+
+    /// Convert a [`Cursor`] to a [`LayoutCursor`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidCursor`] if the cursor is not in the buffer, see
+    /// [`Buffer::try_layout_cursor`]
+    pub fn try_layout_cursor(&mut self, cursor: Cursor) -> Result<LayoutCursor, Error> {
+        self.inner.try_layout_cursor(self.font_system, cursor)
+    }
+
     /// Shape lines until scroll
     pub fn shape_until_scroll(&mut self, prune: bool) {
         self.inner.shape_until_scroll(self.font_system, prune);
     }
 
+    /// Scroll to show `cursor` as placed by `policy`, see [`Buffer::scroll_to_cursor`]
+    pub fn scroll_to_cursor(&mut self, cursor: Cursor, policy: ScrollPolicy, prune: bool) {
+        self.inner
+            .scroll_to_cursor(self.font_system, cursor, policy, prune);
+    }
+
+    /// Shape lines that are not laid out yet until `budget` is used up
+    pub fn shape_with_budget(&mut self, budget: ShapeBudget) -> ShapeProgress {
+        self.inner.shape_with_budget(self.font_system, budget)
+    }
+
     /// Shape the provided line index and return the result
     pub fn line_shape(&mut self, line_i: usize) -> Option<&ShapeLine> {
         self.inner.line_shape(self.font_system, line_i)
@@ -1421,16 +3352,134 @@ impl<'a> BorrowedWithFontSystem<'a, Buffer> {
             .set_metrics_and_size(self.font_system, metrics, width_opt, height_opt);
     }
 
+    /// Set monospace width monospace glyphs should be resized to match, see
+    /// [`Buffer::set_monospace_width`]
+    pub fn set_monospace_width(&mut self, monospace_width: Option<f32>) {
+        self.inner
+            .set_monospace_width(self.font_system, monospace_width);
+    }
+
+    /// Set the metrics and monospace width to those of a monospace grid, see
+    /// [`Buffer::set_mono_metrics`]
+    pub fn set_mono_metrics(&mut self, mono_metrics: MonoMetrics) {
+        self.inner.set_mono_metrics(self.font_system, mono_metrics);
+    }
+
+    /// Set how monospace glyphs that do not match the monospace width are handled, see
+    /// [`Buffer::set_monospace_mode`]
+    pub fn set_monospace_mode(&mut self, monospace_mode: MonospaceMode) {
+        self.inner
+            .set_monospace_mode(self.font_system, monospace_mode);
+    }
+
     /// Set tab width (number of spaces between tab stops)
     pub fn set_tab_width(&mut self, tab_width: u16) {
         self.inner.set_tab_width(self.font_system, tab_width);
     }
 
+    /// Set tab stops used instead of the tab width
+    pub fn set_tab_stops(&mut self, tab_stops: Option<TabStops>) {
+        self.inner.set_tab_stops(self.font_system, tab_stops);
+    }
+
+    /// Set the alignment of every line, see [`Buffer::set_align`]
+    pub fn set_align(&mut self, align: Option<Align>) {
+        self.inner.set_align(self.font_system, align);
+    }
+
+    /// Set exclusion zones that text will wrap around, in buffer coordinates
+    pub fn set_exclusions(&mut self, exclusions: Vec<Rect>) {
+        self.inner.set_exclusions(self.font_system, exclusions);
+    }
+
+    /// Set the padding between the edges of the buffer and its text, see [`Buffer::set_padding`]
+    pub fn set_padding(&mut self, padding: Insets) {
+        self.inner.set_padding(self.font_system, padding);
+    }
+
+    /// Set how control characters are displayed
+    pub fn set_control_chars(&mut self, control_chars: ControlChars) {
+        self.inner
+            .set_control_chars(self.font_system, control_chars);
+    }
+
+    /// Set whether whitespace is shown, see [`Buffer::set_show_whitespace`]
+    pub fn set_show_whitespace(&mut self, show_whitespace: bool) {
+        self.inner
+            .set_show_whitespace(self.font_system, show_whitespace);
+    }
+
+    /// Set the direction the lines of this buffer run in, see [`Buffer::set_direction`]
+    pub fn set_direction(&mut self, direction: Direction) {
+        self.inner.set_direction(self.font_system, direction);
+    }
+
+    /// Set the locale of the text in this buffer, see [`Buffer::set_locale`]
+    pub fn set_locale(&mut self, locale: Option<&str>) {
+        self.inner.set_locale(self.font_system, locale);
+    }
+
+    /// Set the factor all font sizes are multiplied by, see [`Buffer::set_text_scale`]
+    pub fn set_text_scale(&mut self, text_scale: f32) {
+        self.inner.set_text_scale(self.font_system, text_scale);
+    }
+
+    /// Set how whitespace at the end of visual lines is laid out
+    pub fn set_trailing_whitespace(&mut self, trailing_whitespace: TrailingWhitespace) {
+        self.inner
+            .set_trailing_whitespace(self.font_system, trailing_whitespace);
+    }
+
+    /// Set where [`Align::Justified`] spreads the extra width of visual lines, see
+    /// [`Buffer::set_justify_mode`]
+    pub fn set_justify_mode(&mut self, justify_mode: JustifyMode) {
+        self.inner.set_justify_mode(self.font_system, justify_mode);
+    }
+
+    /// Set how text that does not fit in the buffer dimensions is handled, see
+    /// [`Buffer::set_overflow`]
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        self.inner.set_overflow(self.font_system, overflow);
+    }
+
+    /// Set the length in bytes above which runs of text are shaped in chunks, see
+    /// [`Buffer::set_shape_chunk_len`]
+    pub fn set_shape_chunk_len(&mut self, shape_chunk_len: Option<usize>) {
+        self.inner
+            .set_shape_chunk_len(self.font_system, shape_chunk_len);
+    }
+
+    /// Set the maximum number of visual lines laid out, see [`Buffer::set_max_lines`]
+    pub fn set_max_lines(&mut self, max_lines: Option<usize>) {
+        self.inner.set_max_lines(self.font_system, max_lines);
+    }
+
+    /// Set the text that replaces overflowing text, see [`Buffer::set_ellipsis`]
+    pub fn set_ellipsis(&mut self, ellipsis: &str) {
+        self.inner.set_ellipsis(self.font_system, ellipsis);
+    }
+
     /// Set text of buffer, using provided attributes for each line by default
     pub fn set_text(&mut self, text: &str, attrs: Attrs, shaping: Shaping) {
         self.inner.set_text(self.font_system, text, attrs, shaping);
     }
 
+    /// Set text of buffer from bytes that may not be valid UTF-8, using provided attributes for
+    /// each line by default
+    ///
+    /// Invalid sequences are replaced with U+FFFD REPLACEMENT CHARACTER, which is given
+    /// `replacement_attrs` to distinguish it from replacement characters present in the text.
+    pub fn set_text_lossy(
+        &mut self,
+        bytes: &[u8],
+        attrs: Attrs,
+        replacement_attrs: Attrs,
+        shaping: Shaping,
+    ) {
+        self.inner
+            .set_text_lossy(self.font_system, bytes, attrs, replacement_attrs, shaping);
+    }
+
     /// Set text of buffer, using an iterator of styled spans (pairs of text and attributes)
     ///
     /// ```
@@ -1473,6 +3522,22 @@ impl<'a> BorrowedWithFontSystem<'a, Buffer> {
             .cursor_motion(self.font_system, cursor, cursor_x_opt, motion)
     }
 
+    /// Apply a [`Motion`] to a [`Cursor`], reporting when it would leave the buffer
+    pub fn cursor_motion_chained(
+        &mut self,
+        cursor: Cursor,
+        cursor_x_opt: Option<i32>,
+        motion: Motion,
+    ) -> Option<ChainedMotion> {
+        self.inner
+            .cursor_motion_chained(self.font_system, cursor, cursor_x_opt, motion)
+    }
+
+    /// Get the cursor entering the buffer from an adjacent one with `motion`
+    pub fn cursor_enter(&mut self, motion: Motion, cursor_x: i32) -> Option<(Cursor, Option<i32>)> {
+        self.inner.cursor_enter(self.font_system, motion, cursor_x)
+    }
+
     /// Draw the buffer
     #[cfg(feature = "swash")]
     pub fn draw<F>(&mut self, cache: &mut crate::SwashCache, color: Color, f: F)
@@ -1,11 +1,75 @@
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
 #[cfg(not(feature = "std"))]
 use alloc::{string::String, vec::Vec};
-use core::mem;
+use core::{mem, ops::Range};
+use smol_str::SmolStr;
 
+use crate::shape::{push_line_ending, ShapeSettings};
 use crate::{
-    Align, Attrs, AttrsList, Cached, FontSystem, LayoutLine, LineEnding, ShapeLine, Shaping, Wrap,
+    Align, Attrs, AttrsList, Cached, Color, ControlChars, Direction, FontSystem, Graphemes, Indent,
+    JustifyMode, LayoutGlyph, LayoutGlyphExtras, LayoutLine, LineEnding, LineExclusions,
+    LineProfile, MemoryUsage, ParagraphSpacing, ProfileTimer, ShapeGlyph, ShapeGlyphExtras,
+    ShapeLine, Shaping, TabStops, TrailingWhitespace, UnicodeWordSegmenter, Whitespace,
+    WordSegmenter, Wrap,
 };
 
+/// Settings a [`crate::Buffer`] shapes and lays out all of its lines with
+///
+/// The buffer keeps one shared copy, see [`BufferLine::apply_settings`]. Each field is
+/// documented on the [`BufferLine`] getter of the same name.
+#[derive(Clone, Debug)]
+pub(crate) struct LineSettings {
+    pub(crate) control_chars: ControlChars,
+    pub(crate) show_whitespace: bool,
+    pub(crate) direction: Direction,
+    pub(crate) locale: Option<Arc<str>>,
+    pub(crate) word_segmenter: Option<Arc<dyn WordSegmenter>>,
+    pub(crate) trailing_whitespace: TrailingWhitespace,
+    pub(crate) justify_mode: JustifyMode,
+    pub(crate) text_scale: f32,
+    pub(crate) x_offset: f32,
+    pub(crate) strict_monospace: Option<(f32, f32)>,
+    pub(crate) tab_stops: Option<TabStops>,
+    pub(crate) shape_chunk_len: Option<usize>,
+    pub(crate) ellipsis: Option<SmolStr>,
+    pub(crate) profiling: bool,
+}
+
+impl LineSettings {
+    /// Get the settings that change how the text of a line is shaped
+    fn shape_settings(&self) -> ShapeSettings<'_> {
+        ShapeSettings {
+            control_chars: self.control_chars,
+            direction: self.direction,
+            locale_opt: self.locale.as_deref(),
+            strict_monospace: self.strict_monospace,
+            shape_chunk_len: self.shape_chunk_len,
+        }
+    }
+}
+
+impl Default for LineSettings {
+    fn default() -> Self {
+        Self {
+            control_chars: ControlChars::default(),
+            show_whitespace: false,
+            direction: Direction::default(),
+            locale: None,
+            word_segmenter: None,
+            trailing_whitespace: TrailingWhitespace::default(),
+            justify_mode: JustifyMode::default(),
+            text_scale: 1.0,
+            x_offset: 0.0,
+            strict_monospace: None,
+            tab_stops: None,
+            shape_chunk_len: None,
+            ellipsis: None,
+            profiling: false,
+        }
+    }
+}
+
 /// A line (or paragraph) of text that is shaped and laid out
 #[derive(Clone, Debug)]
 pub struct BufferLine {
@@ -13,9 +77,18 @@ pub struct BufferLine {
     ending: LineEnding,
     attrs_list: AttrsList,
     align: Option<Align>,
+    indent: Indent,
+    spacing: ParagraphSpacing,
     shape_opt: Cached<ShapeLine>,
+    graphemes_opt: Cached<Graphemes>,
     layout_opt: Cached<Vec<LayoutLine>>,
+    layout_top_opt: Option<f32>,
     shaping: Shaping,
+    settings: Arc<LineSettings>,
+    line_ending_shape_opt: Option<ShapeLine>,
+    ellipsis_shape_opt: Option<ShapeLine>,
+    last_line: Option<usize>,
+    profile_opt: Option<LineProfile>,
     metadata: Option<usize>,
 }
 
@@ -34,9 +107,18 @@ impl BufferLine {
             ending,
             attrs_list,
             align: None,
+            indent: Indent::default(),
+            spacing: ParagraphSpacing::default(),
             shape_opt: Cached::Empty,
+            graphemes_opt: Cached::Empty,
             layout_opt: Cached::Empty,
+            layout_top_opt: None,
             shaping,
+            settings: Arc::default(),
+            line_ending_shape_opt: None,
+            ellipsis_shape_opt: None,
+            last_line: None,
+            profile_opt: None,
             metadata: None,
         }
     }
@@ -55,10 +137,16 @@ impl BufferLine {
         self.ending = ending;
         self.attrs_list = attrs_list;
         self.align = None;
+        self.indent = Indent::default();
+        self.spacing = ParagraphSpacing::default();
         self.shape_opt.set_unused();
+        self.graphemes_opt.set_unused();
         self.layout_opt.set_unused();
         self.shaping = shaping;
         self.metadata = None;
+        if let Some(profile) = &mut self.profile_opt {
+            *profile = LineProfile::default();
+        }
     }
 
     /// Get current text
@@ -132,6 +220,34 @@ impl BufferLine {
         }
     }
 
+    /// Set the color of `range`, updating the cached shape and layout in place instead of
+    /// resetting them, as color does not affect shaping
+    pub fn set_color(&mut self, range: Range<usize>, color: Color) {
+        self.attrs_list.set_color(range.clone(), color);
+
+        let in_range = |start: usize| range.contains(&start);
+        if let Some(shape) = self.shape_opt.get_mut() {
+            for span in shape.spans.iter_mut() {
+                for word in span.words.iter_mut() {
+                    for glyph in word.glyphs.iter_mut() {
+                        if in_range(glyph.start) {
+                            glyph.color_opt = Some(color);
+                        }
+                    }
+                }
+            }
+        }
+        if let Some(layout) = self.layout_opt.get_mut() {
+            for layout_line in layout.iter_mut() {
+                for glyph in layout_line.glyphs.iter_mut() {
+                    if in_range(glyph.start as usize) {
+                        glyph.color_opt = Some(color);
+                    }
+                }
+            }
+        }
+    }
+
     /// Get the Text alignment
     pub fn align(&self) -> Option<Align> {
         self.align
@@ -139,12 +255,53 @@ impl BufferLine {
 
     /// Set the text alignment
     ///
-    /// Will reset layout if it differs from current alignment.
+    /// Will move the glyphs of the cached layout if it differs from current alignment, or reset
+    /// layout if the line was or will be justified, see [`LayoutLine::realign`].
     /// Setting to None will use `Align::Right` for RTL lines, and `Align::Left` for LTR lines.
-    /// Returns true if the line was reset
+    /// Returns true if the line was realigned or reset
     pub fn set_align(&mut self, align: Option<Align>) -> bool {
         if align != self.align {
             self.align = align;
+            if !self.realign() {
+                self.reset_layout();
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Move the glyphs of the cached layout to the current alignment, returning false if the
+    /// line must be laid out again
+    fn realign(&mut self) -> bool {
+        let (Some(shape), Some(layout)) = (self.shape_opt.get(), self.layout_opt.get_mut()) else {
+            return false;
+        };
+        let rtl = shape.rtl;
+        let align = self
+            .align
+            .unwrap_or(if rtl { Align::Right } else { Align::Left });
+        for layout_line in layout.iter_mut() {
+            if !layout_line.realign(align, rtl) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Get the indentation of the visual lines
+    pub fn indent(&self) -> Indent {
+        self.indent
+    }
+
+    /// Set the indentation of the visual lines, reducing their width and moving their start away
+    /// from the left edge, or the right edge for RTL lines
+    ///
+    /// Will reset layout if it differs from current indentation.
+    /// Returns true if the line was reset
+    pub fn set_indent(&mut self, indent: Indent) -> bool {
+        if indent != self.indent {
+            self.indent = indent;
             self.reset_layout();
             true
         } else {
@@ -152,6 +309,363 @@ impl BufferLine {
         }
     }
 
+    /// Get the vertical space around the line
+    pub fn spacing(&self) -> ParagraphSpacing {
+        self.spacing
+    }
+
+    /// Set the vertical space above the first visual line and below the last visual line, to
+    /// separate paragraphs without inserting empty lines
+    ///
+    /// Will reset layout if it differs from current spacing.
+    /// Returns true if the line was reset
+    pub fn set_spacing(&mut self, spacing: ParagraphSpacing) -> bool {
+        if spacing != self.spacing {
+            self.spacing = spacing;
+            self.reset_layout();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get how control characters are displayed
+    pub fn control_chars(&self) -> ControlChars {
+        self.settings.control_chars
+    }
+
+    /// Set how control characters are displayed
+    ///
+    /// Will reset shape and layout if it differs from the current setting.
+    /// Returns true if the line was reset
+    pub fn set_control_chars(&mut self, control_chars: ControlChars) -> bool {
+        if control_chars != self.settings.control_chars {
+            self.settings_mut().control_chars = control_chars;
+            self.reset_shaping();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get whether whitespace is shown
+    pub fn show_whitespace(&self) -> bool {
+        self.settings.show_whitespace
+    }
+
+    /// Set whether whitespace is shown, drawing spaces, tabs, no-break spaces and the line
+    /// ending as their [`Whitespace::marker`]
+    ///
+    /// Will reset shape and layout if it differs from the current setting.
+    /// Returns true if the line was reset
+    pub fn set_show_whitespace(&mut self, show_whitespace: bool) -> bool {
+        if show_whitespace != self.settings.show_whitespace {
+            self.settings_mut().show_whitespace = show_whitespace;
+            self.reset_shaping();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get the direction the line runs in
+    pub fn direction(&self) -> Direction {
+        self.settings.direction
+    }
+
+    /// Set the direction the line runs in
+    ///
+    /// Will reset shape and layout if it differs from the current direction.
+    /// Returns true if the line was reset
+    pub fn set_direction(&mut self, direction: Direction) -> bool {
+        if direction != self.settings.direction {
+            self.settings_mut().direction = direction;
+            self.reset_shaping();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get where [`Align::Justified`] spreads the extra width of visual lines
+    pub fn justify_mode(&self) -> JustifyMode {
+        self.settings.justify_mode
+    }
+
+    /// Set where [`Align::Justified`] spreads the extra width of visual lines
+    ///
+    /// Will reset layout if it differs from the current setting, or shaping if
+    /// [`JustifyMode::Kashida`] is selected or deselected.
+    /// Returns true if the line was reset
+    pub fn set_justify_mode(&mut self, justify_mode: JustifyMode) -> bool {
+        if justify_mode != self.settings.justify_mode {
+            // Only Kashida needs the joints that tatweels lengthen, which are found when shaping
+            let kashida = JustifyMode::Kashida;
+            let reshape = (justify_mode == kashida) != (self.settings.justify_mode == kashida);
+            self.settings_mut().justify_mode = justify_mode;
+            if reshape {
+                self.reset_shaping();
+            } else {
+                self.reset_layout();
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get the locale used for script fallback, overriding the [`FontSystem`] locale
+    pub fn locale(&self) -> Option<&str> {
+        self.settings.locale.as_deref()
+    }
+
+    /// Set the locale used for script fallback, overriding the [`FontSystem`] locale
+    ///
+    /// Will reset shape and layout if it differs from the current locale.
+    /// Returns true if the line was reset
+    pub fn set_locale(&mut self, locale: Option<Arc<str>>) -> bool {
+        if locale != self.settings.locale {
+            self.settings_mut().locale = locale;
+            self.reset_shaping();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get the segmenter used to split the line into words, `None` for [`crate::UnicodeWordSegmenter`]
+    pub fn word_segmenter(&self) -> Option<&Arc<dyn WordSegmenter>> {
+        self.settings.word_segmenter.as_ref()
+    }
+
+    /// Set the segmenter used to split the line into words, `None` for
+    /// [`crate::UnicodeWordSegmenter`]
+    ///
+    /// Will reset the grapheme cache if it differs from the current segmenter.
+    /// Returns true if the cache was reset
+    pub fn set_word_segmenter(&mut self, word_segmenter: Option<Arc<dyn WordSegmenter>>) -> bool {
+        let same = match (&word_segmenter, &self.settings.word_segmenter) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+        if !same {
+            self.settings_mut().word_segmenter = word_segmenter;
+            self.graphemes_opt.set_unused();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get how whitespace at the end of visual lines is laid out
+    pub fn trailing_whitespace(&self) -> TrailingWhitespace {
+        self.settings.trailing_whitespace
+    }
+
+    /// Set how whitespace at the end of visual lines is laid out
+    ///
+    /// Will reset layout if it differs from the current setting.
+    /// Returns true if the line was reset
+    pub fn set_trailing_whitespace(&mut self, trailing_whitespace: TrailingWhitespace) -> bool {
+        if trailing_whitespace != self.settings.trailing_whitespace {
+            self.settings_mut().trailing_whitespace = trailing_whitespace;
+            self.reset_layout();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get the factor all font sizes of this line are multiplied by
+    pub fn text_scale(&self) -> f32 {
+        self.settings.text_scale
+    }
+
+    /// Set the factor all font sizes of this line are multiplied by, including the font sizes of
+    /// [`Attrs::metrics`] overrides
+    ///
+    /// Will reset layout if it differs from the current scale.
+    /// Returns true if the line was reset
+    pub fn set_text_scale(&mut self, text_scale: f32) -> bool {
+        if text_scale != self.settings.text_scale {
+            self.settings_mut().text_scale = text_scale;
+            self.reset_layout();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get the offset added to the X position of every glyph of this line
+    pub fn x_offset(&self) -> f32 {
+        self.settings.x_offset
+    }
+
+    /// Set the offset added to the X position of every glyph of this line after layout, used for
+    /// the left [`crate::Buffer::padding`]
+    ///
+    /// Will reset layout if it differs from the current offset.
+    /// Returns true if the line was reset
+    pub fn set_x_offset(&mut self, x_offset: f32) -> bool {
+        if x_offset != self.settings.x_offset {
+            self.settings_mut().x_offset = x_offset;
+            self.reset_layout();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Set the font size and monospace width used to substitute monospace fonts of other widths
+    /// while shaping, see [`crate::MonospaceMode::Strict`]
+    ///
+    /// Will reset shaping if it differs from the current setting.
+    /// Returns true if the line was reset
+    pub(crate) fn set_strict_monospace(&mut self, strict_monospace: Option<(f32, f32)>) -> bool {
+        if strict_monospace != self.settings.strict_monospace {
+            self.settings_mut().strict_monospace = strict_monospace;
+            self.reset_shaping();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get the tab stops used instead of the tab width, if any
+    pub fn tab_stops(&self) -> Option<&TabStops> {
+        self.settings.tab_stops.as_ref()
+    }
+
+    /// Set the tab stops used instead of the tab width, see [`crate::Buffer::set_tab_stops`]
+    ///
+    /// Will reset layout if it differs from the current tab stops.
+    /// Returns true if the line was reset
+    pub fn set_tab_stops(&mut self, tab_stops: Option<&TabStops>) -> bool {
+        if tab_stops != self.settings.tab_stops.as_ref() {
+            self.settings_mut().tab_stops = tab_stops.cloned();
+            self.reset_layout();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get the length in bytes above which runs of text are shaped in chunks, if any
+    pub fn shape_chunk_len(&self) -> Option<usize> {
+        self.settings.shape_chunk_len
+    }
+
+    /// Set the length in bytes above which runs of text are shaped in chunks, see
+    /// [`crate::Buffer::set_shape_chunk_len`]
+    ///
+    /// Will reset shaping if it differs from the current setting and the line is long enough
+    /// to be affected. Returns true if the line was reset
+    pub fn set_shape_chunk_len(&mut self, shape_chunk_len: Option<usize>) -> bool {
+        if shape_chunk_len != self.settings.shape_chunk_len {
+            let affected = |chunk_len: Option<usize>| {
+                chunk_len.map_or(false, |chunk_len| self.text.len() > chunk_len)
+            };
+            let reset = affected(shape_chunk_len) || affected(self.settings.shape_chunk_len);
+            self.settings_mut().shape_chunk_len = shape_chunk_len;
+            if reset {
+                self.reset_shaping();
+            }
+            reset
+        } else {
+            false
+        }
+    }
+
+    /// Get the text that ends visual lines which are cut short to fit, if any
+    pub fn ellipsis(&self) -> Option<&str> {
+        self.settings.ellipsis.as_deref()
+    }
+
+    /// Set the text that ends visual lines which are cut short to fit. Visual lines wider than
+    /// the layout width are only cut short when this is set.
+    ///
+    /// Will reset layout if it differs from the current setting.
+    /// Returns true if the line was reset
+    pub fn set_ellipsis(&mut self, ellipsis: Option<SmolStr>) -> bool {
+        if ellipsis != self.settings.ellipsis {
+            self.settings_mut().ellipsis = ellipsis;
+            self.ellipsis_shape_opt = None;
+            self.reset_layout();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Apply the settings a [`crate::Buffer`] shapes and lays out all of its lines with
+    ///
+    /// The buffer replaces its shared settings when one of them changes, so the settings are
+    /// only compared field by field, resetting the caches the changed fields affect like their
+    /// setters do, when they were replaced since they were last applied to this line.
+    pub(crate) fn apply_settings(&mut self, settings: &Arc<LineSettings>) {
+        if Arc::ptr_eq(&self.settings, settings) {
+            return;
+        }
+        self.set_control_chars(settings.control_chars);
+        self.set_show_whitespace(settings.show_whitespace);
+        self.set_direction(settings.direction);
+        self.set_locale(settings.locale.clone());
+        self.set_word_segmenter(settings.word_segmenter.clone());
+        self.set_trailing_whitespace(settings.trailing_whitespace);
+        self.set_justify_mode(settings.justify_mode);
+        self.set_text_scale(settings.text_scale);
+        self.set_x_offset(settings.x_offset);
+        self.set_strict_monospace(settings.strict_monospace);
+        // Tabs are expanded to the tab stops at layout, which lines without tabs do not depend on
+        if settings.tab_stops != self.settings.tab_stops && self.text.contains('\t') {
+            self.reset_layout();
+        }
+        self.set_shape_chunk_len(settings.shape_chunk_len);
+        self.set_ellipsis(settings.ellipsis.clone());
+        self.set_profiling(settings.profiling);
+        self.settings = Arc::clone(settings);
+    }
+
+    fn settings_mut(&mut self) -> &mut LineSettings {
+        Arc::make_mut(&mut self.settings)
+    }
+
+    /// Get the index of the last visual line kept in the layout, which ends with the ellipsis if
+    /// there is one
+    pub(crate) fn last_line(&self) -> Option<usize> {
+        self.last_line
+    }
+
+    /// Set the index of the last visual line kept in the layout, which ends with the ellipsis if
+    /// there is one
+    ///
+    /// Will reset layout if it differs from the current setting.
+    /// Returns true if the line was reset
+    pub(crate) fn set_last_line(&mut self, last_line: Option<usize>) -> bool {
+        if last_line != self.last_line {
+            self.last_line = last_line;
+            self.reset_layout();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get the shaping and layout costs recorded since profiling was enabled or the text was
+    /// replaced with [`Self::reset_new`], `None` if profiling is disabled
+    pub fn profile(&self) -> Option<&LineProfile> {
+        self.profile_opt.as_ref()
+    }
+
+    /// Enable or disable recording the shaping and layout costs of the line, see
+    /// [`crate::Buffer::set_profiling`]. Disabling profiling discards the recorded costs
+    pub fn set_profiling(&mut self, profiling: bool) {
+        if profiling != self.profile_opt.is_some() {
+            self.profile_opt = profiling.then(LineProfile::default);
+        }
+    }
+
     /// Append line at end of this line
     ///
     /// The wrap setting of the appended line will be lost
@@ -182,9 +696,37 @@ impl BufferLine {
 
         let mut new = Self::new(text, self.ending, attrs_list, self.shaping);
         new.align = self.align;
+        new.indent = self.indent;
+        new.spacing = self.spacing;
+        new.settings = self.settings.clone();
+        new.profile_opt = self.profile_opt.map(|_| LineProfile::default());
         new
     }
 
+    /// Split this line on the Unicode paragraph separators U+2029 and NEL
+    ///
+    /// This line keeps the first paragraph and the following paragraphs are returned in order.
+    /// The separators are stored as line endings so the original text can be reconstructed.
+    pub fn split_paragraphs(&mut self) -> Vec<Self> {
+        let mut lines = Vec::new();
+        while let Some((index, c)) = self
+            .text
+            .char_indices()
+            .rev()
+            .find(|&(_, c)| c == '\u{2029}' || c == '\u{85}')
+        {
+            lines.push(self.split_off(index + c.len_utf8()));
+            self.split_off(index);
+            self.ending = if c == '\u{2029}' {
+                LineEnding::Ps
+            } else {
+                LineEnding::Nel
+            };
+        }
+        lines.reverse();
+        lines
+    }
+
     /// Reset shaping, layout, and metadata caches
     pub fn reset(&mut self) {
         self.metadata = None;
@@ -194,9 +736,51 @@ impl BufferLine {
     /// Reset shaping and layout caches
     pub fn reset_shaping(&mut self) {
         self.shape_opt.set_unused();
+        self.graphemes_opt.set_unused();
         self.reset_layout();
     }
 
+    /// Reset shaping and layout caches and free their allocations, which are otherwise kept to be
+    /// reused when the line is shaped again
+    pub fn release_caches(&mut self) {
+        self.shape_opt.clear();
+        self.graphemes_opt.clear();
+        self.layout_opt.clear();
+        self.ellipsis_shape_opt = None;
+        self.line_ending_shape_opt = None;
+    }
+
+    /// Get the memory held by the glyphs of the shaping and layout caches
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let mut usage = MemoryUsage::default();
+        let shapes = self
+            .shape_opt
+            .value()
+            .into_iter()
+            .chain(self.ellipsis_shape_opt.as_ref());
+        for shape in shapes {
+            for word in shape.spans.iter().flat_map(|span| span.words.iter()) {
+                if self.shape_opt.is_used() {
+                    usage.shape_glyphs += word.glyphs.len();
+                }
+                usage.glyph_bytes += word.glyphs.capacity() * mem::size_of::<ShapeGlyph>();
+                usage.glyph_bytes +=
+                    word.extras.capacity() * mem::size_of::<(usize, ShapeGlyphExtras)>();
+            }
+        }
+        if let Some(layout) = self.layout_opt.value() {
+            for layout_line in layout.iter() {
+                if self.layout_opt.is_used() {
+                    usage.layout_glyphs += layout_line.glyphs.len();
+                }
+                usage.glyph_bytes += layout_line.glyphs.capacity() * mem::size_of::<LayoutGlyph>();
+                usage.glyph_bytes +=
+                    layout_line.extras.capacity() * mem::size_of::<(usize, LayoutGlyphExtras)>();
+            }
+        }
+        usage
+    }
+
     /// Reset only layout cache
     pub fn reset_layout(&mut self) {
         self.layout_opt.set_unused();
@@ -205,28 +789,161 @@ impl BufferLine {
     /// Shape line, will cache results
     pub fn shape(&mut self, font_system: &mut FontSystem, tab_width: u16) -> &ShapeLine {
         if self.shape_opt.is_unused() {
+            let timer_opt = self.profile_opt.is_some().then(ProfileTimer::start);
+            let fallback_iterations = font_system.fallback_iterations;
             let mut line = self
                 .shape_opt
                 .take_unused()
                 .unwrap_or_else(ShapeLine::empty);
-            line.build(
+            line.build_with_settings(
                 font_system,
                 &self.text,
                 &self.attrs_list,
                 self.shaping,
                 tab_width,
+                self.settings.shape_settings(),
             );
+            if self.settings.show_whitespace {
+                line.show_whitespace(font_system);
+            }
+            if self.settings.justify_mode == JustifyMode::Kashida {
+                line.mark_kashidas(font_system, &self.text);
+            }
             self.shape_opt.set_used(line);
+            self.ellipsis_shape_opt = None;
+            self.line_ending_shape_opt =
+                (self.settings.show_whitespace && self.ending != LineEnding::None).then(|| {
+                    let mut marker = [0; 4];
+                    ShapeLine::new(
+                        font_system,
+                        Whitespace::LineEnding
+                            .marker(false)
+                            .encode_utf8(&mut marker),
+                        &AttrsList::new(self.attrs_list.defaults()),
+                        self.shaping,
+                        tab_width,
+                    )
+                });
             self.layout_opt.set_unused();
+            self.graphemes();
+            if let (Some(profile), Some(timer)) = (&mut self.profile_opt, timer_opt) {
+                profile.shape_count += 1;
+                profile.shape_time += timer.elapsed();
+                profile.fallback_iterations += font_system
+                    .fallback_iterations
+                    .wrapping_sub(fallback_iterations);
+            }
+        } else if let Some(line) = self.shape_opt.get_mut() {
+            // Tabs are expanded again without reshaping when the tab width changes
+            if line.set_tab_width(tab_width) {
+                self.layout_opt.set_unused();
+            }
         }
         self.shape_opt.get().expect("shape not found")
     }
 
+    /// Shape the line and expand its tabs to the tab stops at `font_size`
+    fn expand_tab_stops(&mut self, font_system: &mut FontSystem, font_size: f32, tab_width: u16) {
+        self.shape(font_system, tab_width);
+        if let Some(shape) = self.shape_opt.get_mut() {
+            shape.set_tab_stops(&self.text, self.settings.tab_stops.as_ref(), font_size);
+        }
+    }
+
+    /// Add the line ending marker after the last layout line when showing whitespace
+    fn push_line_ending(&self, layout: &mut [LayoutLine], font_size: f32) {
+        if let (Some(marker), Some(shape), Some(layout_line)) = (
+            &self.line_ending_shape_opt,
+            self.shape_opt.get(),
+            layout.last_mut(),
+        ) {
+            push_line_ending(
+                layout_line,
+                marker,
+                self.text.len(),
+                font_size,
+                self.settings.text_scale,
+                shape.rtl,
+            );
+        }
+    }
+
+    /// Shape the line and its ellipsis, will cache results
+    fn shape_with_ellipsis(
+        &mut self,
+        font_system: &mut FontSystem,
+        tab_width: u16,
+    ) -> (&ShapeLine, Option<&ShapeLine>) {
+        self.shape(font_system, tab_width);
+        if self.ellipsis_shape_opt.is_none() {
+            if let Some(ellipsis) = &self.settings.ellipsis {
+                let attrs_list = AttrsList::new(self.attrs_list.defaults());
+                self.ellipsis_shape_opt = Some(ShapeLine::new(
+                    font_system,
+                    ellipsis,
+                    &attrs_list,
+                    self.shaping,
+                    tab_width,
+                ));
+            }
+        }
+        (
+            self.shape_opt.get().expect("shape not found"),
+            self.ellipsis_shape_opt.as_ref(),
+        )
+    }
+
     /// Get line shaping cache
     pub fn shape_opt(&self) -> Option<&ShapeLine> {
         self.shape_opt.get()
     }
 
+    /// Segment line into grapheme clusters and words, will cache results
+    ///
+    /// This is also done when shaping the line.
+    pub fn graphemes(&mut self) -> &Graphemes {
+        if self.graphemes_opt.is_unused() {
+            let mut graphemes = self.graphemes_opt.take_unused().unwrap_or_default();
+            self.build_graphemes(&mut graphemes);
+            self.graphemes_opt.set_used(graphemes);
+        }
+        self.graphemes_opt.get().expect("graphemes not found")
+    }
+
+    /// Get line grapheme cache
+    pub fn graphemes_opt(&self) -> Option<&Graphemes> {
+        self.graphemes_opt.get()
+    }
+
+    fn build_graphemes(&self, graphemes: &mut Graphemes) {
+        match &self.settings.word_segmenter {
+            Some(segmenter) => {
+                graphemes.build_with_segmenter(
+                    &self.text,
+                    &**segmenter,
+                    self.settings.locale.as_deref(),
+                );
+            }
+            None => graphemes.build_with_segmenter(
+                &self.text,
+                &UnicodeWordSegmenter,
+                self.settings.locale.as_deref(),
+            ),
+        }
+    }
+
+    /// Get line grapheme cache, segmenting the line without caching if there is no cache
+    pub(crate) fn graphemes_cow(&self) -> Cow<'_, Graphemes> {
+        match self.graphemes_opt.get() {
+            Some(graphemes) => Cow::Borrowed(graphemes),
+            None => {
+                let mut graphemes = Graphemes::default();
+                self.build_graphemes(&mut graphemes);
+                Cow::Owned(graphemes)
+            }
+        }
+    }
+
     /// Layout line, will cache results
     pub fn layout(
         &mut self,
@@ -238,31 +955,131 @@ impl BufferLine {
         tab_width: u16,
     ) -> &[LayoutLine] {
         if self.layout_opt.is_unused() {
-            let align = self.align;
-            let mut layout = self
-                .layout_opt
-                .take_unused()
-                .unwrap_or_else(|| Vec::with_capacity(1));
-            let shape = self.shape(font_system, tab_width);
-            shape.layout_to_buffer(
-                &mut font_system.shape_buffer,
+            self.layout_unused(
+                font_system,
+                font_size,
+                width_opt,
+                wrap,
+                match_mono_width,
+                tab_width,
+                LineExclusions::default(),
+            );
+            self.layout_top_opt = None;
+        }
+        self.layout_opt.get().expect("layout not found")
+    }
+
+    /// Layout line avoiding exclusions, will cache results
+    ///
+    /// Layout is reset if the line was previously laid out at a different position.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn layout_excluding(
+        &mut self,
+        font_system: &mut FontSystem,
+        font_size: f32,
+        width_opt: Option<f32>,
+        wrap: Wrap,
+        match_mono_width: Option<f32>,
+        tab_width: u16,
+        exclusions: LineExclusions,
+    ) -> &[LayoutLine] {
+        if self.layout_top_opt != Some(exclusions.top) {
+            self.reset_layout();
+        }
+        if self.layout_opt.is_unused() {
+            let top = exclusions.top;
+            // The text starts below the space before the paragraph
+            let exclusions = LineExclusions {
+                top: top + self.spacing.before,
+                ..exclusions
+            };
+            self.layout_unused(
+                font_system,
                 font_size,
                 width_opt,
                 wrap,
-                align,
-                &mut layout,
                 match_mono_width,
+                tab_width,
+                exclusions,
             );
-            self.layout_opt.set_used(layout);
+            self.layout_top_opt = Some(top);
         }
         self.layout_opt.get().expect("layout not found")
     }
 
+    /// Layout line into the unused layout cache, avoiding `exclusions` and indented by
+    /// [`Self::indent`]
+    #[allow(clippy::too_many_arguments)]
+    fn layout_unused(
+        &mut self,
+        font_system: &mut FontSystem,
+        font_size: f32,
+        width_opt: Option<f32>,
+        wrap: Wrap,
+        match_mono_width: Option<f32>,
+        tab_width: u16,
+        exclusions: LineExclusions,
+    ) {
+        let align = self.align;
+        let trailing_whitespace = self.settings.trailing_whitespace;
+        let justify_mode = self.settings.justify_mode;
+        let text_scale = self.settings.text_scale;
+        let spacing = self.spacing;
+        let exclusions = LineExclusions {
+            indent: self.indent,
+            ..exclusions
+        };
+        let mut layout = self
+            .layout_opt
+            .take_unused()
+            .unwrap_or_else(|| Vec::with_capacity(1));
+        let last_line = self.last_line;
+        let profiling = self.profile_opt.is_some();
+        self.expand_tab_stops(font_system, font_size, tab_width);
+        let (shape, ellipsis) = self.shape_with_ellipsis(font_system, tab_width);
+        let timer_opt = profiling.then(ProfileTimer::start);
+        shape.layout_to_buffer_excluding(
+            &mut font_system.shape_buffer,
+            font_size,
+            text_scale,
+            width_opt,
+            wrap,
+            align,
+            &mut layout,
+            match_mono_width,
+            trailing_whitespace,
+            justify_mode,
+            exclusions,
+            ellipsis,
+            last_line,
+            None,
+        );
+        apply_spacing(&mut layout, spacing);
+        self.push_line_ending(&mut layout, font_size);
+        apply_x_offset(&mut layout, self.settings.x_offset);
+        self.record_layout(timer_opt);
+        self.layout_opt.set_used(layout);
+    }
+
+    fn record_layout(&mut self, timer_opt: Option<ProfileTimer>) {
+        if let (Some(profile), Some(timer)) = (&mut self.profile_opt, timer_opt) {
+            profile.layout_count += 1;
+            profile.layout_time += timer.elapsed();
+        }
+    }
+
     /// Get line layout cache
     pub fn layout_opt(&self) -> Option<&Vec<LayoutLine>> {
         self.layout_opt.get()
     }
 
+    /// Get the top the cached layout was placed at by [`Self::layout_excluding`], or `None` if
+    /// there is no such layout
+    pub(crate) fn layout_top_opt(&self) -> Option<f32> {
+        self.layout_top_opt
+            .filter(|_| self.layout_opt.get().is_some())
+    }
+
     /// Get line metadata. This will be None if [`BufferLine::set_metadata`] has not been called
     /// after the last reset of shaping and layout caches
     pub fn metadata(&self) -> Option<usize> {
@@ -283,9 +1100,18 @@ impl BufferLine {
             ending: LineEnding::default(),
             attrs_list: AttrsList::new(Attrs::new()),
             align: None,
+            indent: Indent::default(),
+            spacing: ParagraphSpacing::default(),
             shape_opt: Cached::Empty,
+            graphemes_opt: Cached::Empty,
             layout_opt: Cached::Empty,
+            layout_top_opt: None,
             shaping: Shaping::Advanced,
+            settings: Arc::default(),
+            line_ending_shape_opt: None,
+            ellipsis_shape_opt: None,
+            last_line: None,
+            profile_opt: None,
             metadata: None,
         }
     }
@@ -306,3 +1132,25 @@ impl BufferLine {
         text
     }
 }
+
+/// Move every glyph of `layout` right by `x_offset`
+fn apply_x_offset(layout: &mut [LayoutLine], x_offset: f32) {
+    if x_offset == 0.0 {
+        return;
+    }
+    for layout_line in layout.iter_mut() {
+        for glyph in layout_line.glyphs.iter_mut() {
+            glyph.x += x_offset;
+        }
+    }
+}
+
+/// Add the paragraph `spacing` to the first and last visual lines of `layout`
+fn apply_spacing(layout: &mut [LayoutLine], spacing: ParagraphSpacing) {
+    if let Some(first) = layout.first_mut() {
+        first.space_before = spacing.before;
+    }
+    if let Some(last) = layout.last_mut() {
+        last.space_after = spacing.after;
+    }
+}
@@ -42,6 +42,20 @@ impl<T: Clone + Debug> Cached<T> {
         }
     }
 
+    /// Gets the value if in state `Self::Used` or `Self::Unused`, where it is only kept for its
+    /// allocations.
+    pub fn value(&self) -> Option<&T> {
+        match self {
+            Self::Empty => None,
+            Self::Unused(t) | Self::Used(t) => Some(t),
+        }
+    }
+
+    /// Drops the value, freeing its allocations.
+    pub fn clear(&mut self) {
+        *self = Self::Empty;
+    }
+
     /// Takes the buffered value if in state `Self::Unused`.
     pub fn take_unused(&mut self) -> Option<T> {
         if matches!(*self, Self::Unused(_)) {
@@ -60,6 +60,30 @@ impl Affinity {
     }
 }
 
+/// Where a cursor at a soft wrap boundary is placed, when the end of one visual line and the
+/// start of the next are at the same index, see [`crate::Buffer::set_wrap_affinity`]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum WrapAffinity {
+    /// Use the [`Affinity`] of the cursor
+    #[default]
+    Cursor,
+    /// Place the cursor at the end of the previous visual line
+    Before,
+    /// Place the cursor at the start of the next visual line
+    After,
+}
+
+impl WrapAffinity {
+    /// Get the affinity used for `cursor` at a soft wrap boundary
+    pub fn resolve(&self, cursor: Cursor) -> Affinity {
+        match self {
+            Self::Cursor => cursor.affinity,
+            Self::Before => Affinity::Before,
+            Self::After => Affinity::After,
+        }
+    }
+}
+
 /// The position of a cursor within a [`Buffer`].
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
 pub struct LayoutCursor {
@@ -95,20 +119,37 @@ pub enum Motion {
     Left,
     /// Move cursor right
     Right,
-    /// Move cursor up
+    /// Move cursor up one visual line, keeping the horizontal position
     Up,
-    /// Move cursor down
+    /// Move cursor down one visual line, keeping the horizontal position
     Down,
+    /// Move cursor to the first visual line of the previous [`BufferLine`], keeping the
+    /// horizontal position
+    PreviousLine,
+    /// Move cursor to the first visual line of the next [`BufferLine`], keeping the horizontal
+    /// position
+    NextLine,
     /// Move cursor to start of line
     Home,
     /// Move cursor to start of line, skipping whitespace
     SoftHome,
     /// Move cursor to end of line
     End,
+    /// Move cursor to end of line, skipping whitespace
+    SoftEnd,
     /// Move cursor to start of paragraph
     ParagraphStart,
     /// Move cursor to end of paragraph
     ParagraphEnd,
+    /// Move cursor to the previous blank line before a block of text, or the start of the
+    /// document
+    ParagraphUp,
+    /// Move cursor to the next blank line after a block of text, or the end of the document
+    ParagraphDown,
+    /// Move cursor to the start of the next sentence
+    SentenceNext,
+    /// Move cursor to the start of the sentence it is in, or of the previous sentence
+    SentencePrevious,
     /// Move cursor up one page
     PageUp,
     /// Move cursor down one page
@@ -123,6 +164,8 @@ pub enum Motion {
     LeftWord,
     /// Move cursor to next word boundary to the right
     RightWord,
+    /// Move cursor to the end of the word it is in, or of the next word, without leaving the line
+    WordEnd,
     /// Move cursor to the start of the document
     BufferStart,
     /// Move cursor to the end of the document
@@ -131,6 +174,29 @@ pub enum Motion {
     GotoLine(usize),
 }
 
+/// The result of [`crate::Buffer::cursor_motion_chained`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChainedMotion {
+    /// The cursor moved within the buffer, with the new horizontal position to keep
+    Moved(Cursor, Option<i32>),
+    /// The motion would leave the buffer, so the cursor did not move. `cursor_x` is the
+    /// horizontal position to keep, to enter the adjacent buffer with
+    /// [`crate::Buffer::cursor_enter`]
+    AtBoundary { cursor_x: i32 },
+}
+
+/// Where [`Buffer::scroll_to_cursor`] places the line of the cursor in the view
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ScrollPolicy {
+    /// Scroll so the visual line of the cursor is at the top of the view
+    Top,
+    /// Scroll so the visual line of the cursor is in the middle of the view
+    Center,
+    /// Scroll as little as possible to show the visual line of the cursor
+    #[default]
+    Minimal,
+}
+
 /// Scroll position in [`Buffer`]
 #[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
 pub struct Scroll {
@@ -141,6 +207,11 @@ pub struct Scroll {
     /// if it is negative or exceeds the height of the [`BufferLine::layout`] lines.
     pub vertical: f32,
     /// The horizontal position of scroll in fractional pixels
+    ///
+    /// Lines laid out with a width start at its start edge, the left edge of LTR lines and the
+    /// right edge of RTL lines, so 0.0 shows the start of lines in both directions. RTL lines
+    /// wider than the buffer overflow to the left, where the scroll is negative. See
+    /// [`crate::Buffer::horizontal_scroll_range`].
     pub horizontal: f32,
 }
 
@@ -153,4 +224,13 @@ impl Scroll {
             horizontal,
         }
     }
+
+    /// Get this scroll moved to the start edge of lines, which is the same for both directions,
+    /// see [`Self::horizontal`]
+    pub const fn horizontal_start(self) -> Self {
+        Self {
+            horizontal: 0.0,
+            ..self
+        }
+    }
 }
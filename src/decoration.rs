@@ -0,0 +1,40 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::ops::Range;
+
+use crate::{Color, Cursor};
+
+/// How a [`Decoration`] is drawn
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DecorationKind {
+    /// A straight line below the text
+    Underline,
+    /// A wavy line below the text, for example to mark spelling errors
+    Squiggle,
+    /// An outline around the text, covering the height of the line
+    Box,
+    /// A filled rectangle behind the text, covering the height of the line
+    Highlight,
+}
+
+/// A decoration of a range of text in a [`crate::Buffer`], see
+/// [`crate::Buffer::set_decorations`]
+///
+/// Unlike [`crate::Attrs::decoration`], these are not part of the text attributes, so they can be
+/// changed without shaping or laying out the text again.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Decoration {
+    /// The decorated text, which may span several lines
+    pub range: Range<Cursor>,
+    /// How the decoration is drawn
+    pub kind: DecorationKind,
+    /// Color of the decoration
+    pub color: Color,
+}
+
+impl Decoration {
+    /// Create a new [`Decoration`]
+    pub const fn new(range: Range<Cursor>, kind: DecorationKind, color: Color) -> Self {
+        Self { range, kind, color }
+    }
+}
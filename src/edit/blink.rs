@@ -0,0 +1,89 @@
+use core::time::Duration;
+
+/// Cursor blink state, shared by all editors so blinking behaves consistently
+///
+/// Call [`CursorBlink::tick`] with the time elapsed since the last call to advance the timer.
+/// Editors reset the timer when handling an [`crate::Action`], so the cursor stays visible while
+/// typing.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CursorBlink {
+    interval: Option<Duration>,
+    elapsed: Duration,
+}
+
+impl CursorBlink {
+    /// Default time the cursor is shown or hidden for
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_millis(500);
+
+    /// Create a new [`CursorBlink`] that toggles visibility every `interval`, or never blinks if
+    /// `interval` is `None`
+    pub const fn new(interval: Option<Duration>) -> Self {
+        Self {
+            interval,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Get the time the cursor is shown or hidden for
+    pub fn interval(&self) -> Option<Duration> {
+        self.interval
+    }
+
+    /// Set the time the cursor is shown or hidden for, `None` disables blinking
+    ///
+    /// A zero interval is not allowed and disables blinking.
+    pub fn set_interval(&mut self, interval: Option<Duration>) {
+        self.interval = interval.filter(|interval| !interval.is_zero());
+        self.reset();
+    }
+
+    /// Advance the blink timer by `dt`, returning true if the visibility of the cursor changed
+    pub fn tick(&mut self, dt: Duration) -> bool {
+        let Some(interval) = self.interval else {
+            return false;
+        };
+        let visible = self.visible();
+        // Keep only the position within one show and hide cycle
+        let cycle = interval.as_nanos() * 2;
+        let elapsed = (self.elapsed.as_nanos() + dt.as_nanos()) % cycle;
+        self.elapsed = Duration::from_nanos(elapsed as u64);
+        visible != self.visible()
+    }
+
+    /// Restart the blink timer, making the cursor visible
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+    }
+
+    /// Returns true if the cursor should be drawn
+    pub fn visible(&self) -> bool {
+        match self.interval {
+            Some(interval) => self.elapsed < interval,
+            None => true,
+        }
+    }
+}
+
+impl Default for CursorBlink {
+    fn default() -> Self {
+        Self::new(Some(Self::DEFAULT_INTERVAL))
+    }
+}
+
+#[test]
+fn test_cursor_blink() {
+    let mut blink = CursorBlink::new(Some(Duration::from_millis(500)));
+    assert!(blink.visible());
+    assert!(!blink.tick(Duration::from_millis(499)));
+    assert!(blink.tick(Duration::from_millis(1)));
+    assert!(!blink.visible());
+    assert!(blink.tick(Duration::from_millis(600)));
+    assert!(blink.visible());
+    assert!(blink.tick(Duration::from_millis(700)));
+    blink.reset();
+    assert!(blink.visible());
+
+    blink.set_interval(None);
+    assert!(!blink.tick(Duration::from_millis(700)));
+    assert!(blink.visible());
+}
@@ -0,0 +1,106 @@
+use core::time::Duration;
+
+use crate::Action;
+
+/// Click counting, shared by all editors so double and triple clicks behave consistently
+///
+/// Pass clicks to [`ClickChain::click`], or use [`crate::Edit::click`] which also performs the
+/// resulting [`Action`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ClickChain {
+    interval: Duration,
+    slop: i32,
+    last: Option<(i32, i32, Duration, u8)>,
+}
+
+impl ClickChain {
+    /// Default maximum time between chained clicks
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_millis(500);
+
+    /// Default maximum distance in pixels between chained clicks
+    pub const DEFAULT_SLOP: i32 = 4;
+
+    /// Create a new [`ClickChain`] with the maximum time and distance between chained clicks
+    pub const fn new(interval: Duration, slop: i32) -> Self {
+        Self {
+            interval,
+            slop,
+            last: None,
+        }
+    }
+
+    /// Get the maximum time between chained clicks
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Set the maximum time between chained clicks
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+
+    /// Get the maximum distance in pixels between chained clicks
+    pub fn slop(&self) -> i32 {
+        self.slop
+    }
+
+    /// Set the maximum distance in pixels between chained clicks, on each axis
+    pub fn set_slop(&mut self, slop: i32) {
+        self.slop = slop.max(0);
+    }
+
+    /// Count a click at `x`, `y` at `time`, returning [`Action::Click`], [`Action::DoubleClick`],
+    /// or [`Action::TripleClick`]. A fourth chained click starts over with [`Action::Click`].
+    ///
+    /// `time` may be measured from any fixed point, such as application start.
+    pub fn click(&mut self, x: i32, y: i32, time: Duration) -> Action {
+        let count = match self.last {
+            Some((last_x, last_y, last_time, count))
+                if time >= last_time
+                    && time - last_time <= self.interval
+                    && (x - last_x).abs() <= self.slop
+                    && (y - last_y).abs() <= self.slop =>
+            {
+                count % 3 + 1
+            }
+            _ => 1,
+        };
+        self.last = Some((x, y, time, count));
+        match count {
+            1 => Action::Click { x, y },
+            2 => Action::DoubleClick { x, y },
+            _ => Action::TripleClick { x, y },
+        }
+    }
+
+    /// Forget the previous click, so the next click is not chained
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+impl Default for ClickChain {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_INTERVAL, Self::DEFAULT_SLOP)
+    }
+}
+
+#[test]
+fn test_click_chain() {
+    let mut chain = ClickChain::default();
+    let ms = Duration::from_millis;
+    assert_eq!(chain.click(10, 10, ms(0)), Action::Click { x: 10, y: 10 });
+    assert_eq!(
+        chain.click(12, 9, ms(300)),
+        Action::DoubleClick { x: 12, y: 9 }
+    );
+    assert_eq!(
+        chain.click(12, 9, ms(600)),
+        Action::TripleClick { x: 12, y: 9 }
+    );
+    assert_eq!(chain.click(12, 9, ms(700)), Action::Click { x: 12, y: 9 });
+
+    // Too late or too far away
+    assert_eq!(chain.click(12, 9, ms(1300)), Action::Click { x: 12, y: 9 });
+    assert_eq!(chain.click(20, 9, ms(1400)), Action::Click { x: 20, y: 9 });
+}
@@ -0,0 +1,13 @@
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+use core::fmt;
+
+/// The clipboard of the host, used by [`crate::Action::Copy`], [`crate::Action::Cut`], and
+/// [`crate::Edit::paste`], see [`crate::Edit::set_clipboard`]
+pub trait ClipboardProvider: fmt::Debug + Send + Sync {
+    /// Get the text on the clipboard, or None if it is empty or does not contain text
+    fn text(&self) -> Option<String>;
+
+    /// Replace the contents of the clipboard with `text`
+    fn set_text(&self, text: String);
+}
@@ -0,0 +1,349 @@
+#[cfg(not(feature = "std"))]
+use alloc::{
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::ops::Range;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{Buffer, Change, ChangeItem, Cursor, LineIter};
+
+/// Number of edits after which [`diff_ranges`] stops searching for the shortest edit script of a
+/// region and replaces it as a whole
+const MAX_EDITS: usize = 4096;
+
+/// Compute the changes that turn the text of `old` into `new_text`
+///
+/// Lines are compared first, then the words of changed lines, so text that did not change is
+/// left alone. This keeps its attributes, and the cursors, marks and history of an editor when
+/// the changes are applied with [`crate::Edit::apply_changes`]. There is one [`Change`] per
+/// changed region, in document order, with positions valid after the previous changes were
+/// applied.
+///
+/// Line endings of `new_text` are compared as `\n`, matching how [`crate::Edit::insert_at`]
+/// inserts text.
+pub fn diff(old: &Buffer, new_text: &str) -> Vec<Change> {
+    diff_lines(&buffer_lines(old), &text_lines(new_text))
+}
+
+/// Get the lines of `buffer`, with a `\n` after every line but the last
+pub(crate) fn buffer_lines(buffer: &Buffer) -> Vec<String> {
+    let mut lines = Vec::with_capacity(buffer.lines.len());
+    for (line_i, line) in buffer.lines.iter().enumerate() {
+        let mut text = line.text().to_string();
+        if line_i + 1 < buffer.lines.len() {
+            text.push('\n');
+        }
+        lines.push(text);
+    }
+    lines
+}
+
+/// Get the lines of `text` as [`buffer_lines`] would after [`Buffer::set_text`], which has no
+/// empty line after a final line ending
+pub(crate) fn text_lines(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = LineIter::new(text)
+        .map(|(range, _)| {
+            let mut line = text[range].to_string();
+            line.push('\n');
+            line
+        })
+        .collect();
+    match lines.last_mut() {
+        Some(last) => {
+            last.pop();
+        }
+        None => lines.push(String::new()),
+    }
+    lines
+}
+
+/// Compute the changes that turn `old_lines` into `new_lines`, see [`diff`]
+pub(crate) fn diff_lines(old_lines: &[String], new_lines: &[String]) -> Vec<Change> {
+    let mut changes = Vec::new();
+    for (old_range, new_range) in diff_ranges(old_lines, new_lines) {
+        let old_words: Vec<&str> = old_lines[old_range]
+            .iter()
+            .flat_map(|line| line.split_word_bounds())
+            .collect();
+        let new_words: Vec<&str> = new_lines[new_range.clone()]
+            .iter()
+            .flat_map(|line| line.split_word_bounds())
+            .collect();
+
+        // Earlier regions already match the new text
+        let mut cursor = Cursor::new(new_range.start, 0);
+        let mut words = 0;
+        let mut change = Change::default();
+        for (old_words_range, new_words_range) in diff_ranges(&old_words, &new_words) {
+            for word in &new_words[words..new_words_range.start] {
+                cursor = advance(cursor, word);
+            }
+            words = new_words_range.end;
+
+            let deleted = old_words[old_words_range].concat();
+            if !deleted.is_empty() {
+                change.items.push(ChangeItem {
+                    start: cursor,
+                    end: advance(cursor, &deleted),
+                    text: deleted,
+                    insert: false,
+                });
+            }
+            let inserted = new_words[new_words_range].concat();
+            if !inserted.is_empty() {
+                let end = advance(cursor, &inserted);
+                change.items.push(ChangeItem {
+                    start: cursor,
+                    end,
+                    text: inserted,
+                    insert: true,
+                });
+                cursor = end;
+            }
+        }
+        changes.push(change);
+    }
+    changes
+}
+
+/// Get the position after inserting `text` at `cursor`
+fn advance(mut cursor: Cursor, text: &str) -> Cursor {
+    match text.rfind('\n') {
+        Some(i) => {
+            cursor.line += text.matches('\n').count();
+            cursor.index = text.len() - i - 1;
+        }
+        None => cursor.index += text.len(),
+    }
+    cursor
+}
+
+/// Get the regions of `old` and `new` that differ, in order, using the Myers diff algorithm
+pub(crate) fn diff_ranges<T: PartialEq>(old: &[T], new: &[T]) -> Vec<(Range<usize>, Range<usize>)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+    if a.is_empty() && b.is_empty() {
+        return Vec::new();
+    }
+    let whole = vec![(prefix..prefix + a.len(), prefix..prefix + b.len())];
+    if a.is_empty() || b.is_empty() {
+        return whole;
+    }
+
+    // Matching elements, found in order by splitting the regions at their middle snake
+    let mut matches = Vec::new();
+    let mut forward = Vec::new();
+    let mut backward = Vec::new();
+    diff_matches(a, b, (0, 0), &mut forward, &mut backward, &mut matches);
+
+    // The regions between matching elements differ
+    let mut ranges = Vec::new();
+    let (mut x, mut y) = (0, 0);
+    for (match_x, match_y) in matches.into_iter().chain([(a.len(), b.len())]) {
+        if match_x > x || match_y > y {
+            ranges.push((prefix + x..prefix + match_x, prefix + y..prefix + match_y));
+        }
+        x = match_x + 1;
+        y = match_y + 1;
+    }
+    ranges
+}
+
+/// Push the matching elements of `a` and `b` to `matches` in order, offset by `offset`. The
+/// furthest reaching paths of [`middle_snake`] are kept in `forward` and `backward`
+fn diff_matches<T: PartialEq>(
+    a: &[T],
+    b: &[T],
+    offset: (usize, usize),
+    forward: &mut Vec<usize>,
+    backward: &mut Vec<usize>,
+    matches: &mut Vec<(usize, usize)>,
+) {
+    // Without a common prefix and suffix, both halves around the middle snake are smaller
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    matches.extend((0..prefix).map(|i| (offset.0 + i, offset.1 + i)));
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let suffix = a
+        .iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a_end, b_end) = (a.len() - suffix, b.len() - suffix);
+
+    if a_end > 0 && b_end > 0 {
+        let (a, b) = (&a[..a_end], &b[..b_end]);
+        let offset = (offset.0 + prefix, offset.1 + prefix);
+        if let Some((start, end)) = middle_snake(a, b, forward, backward) {
+            diff_matches(
+                &a[..start.0],
+                &b[..start.1],
+                offset,
+                forward,
+                backward,
+                matches,
+            );
+            matches.extend(
+                (0..end.0 - start.0).map(|i| (offset.0 + start.0 + i, offset.1 + start.1 + i)),
+            );
+            let end_offset = (offset.0 + end.0, offset.1 + end.1);
+            diff_matches(
+                &a[end.0..],
+                &b[end.1..],
+                end_offset,
+                forward,
+                backward,
+                matches,
+            );
+        }
+    }
+
+    matches.extend(
+        (0..suffix).map(|i| (offset.0 + prefix + a_end + i, offset.1 + prefix + b_end + i)),
+    );
+}
+
+/// Find the middle snake of the shortest edit script of `a` and `b` with the linear space
+/// variant of the Myers diff algorithm, returning its start and end. Returns None if the script
+/// takes more than [`MAX_EDITS`] edits, so `a` is replaced by `b` as a whole
+fn middle_snake<T: PartialEq>(
+    a: &[T],
+    b: &[T],
+    forward: &mut Vec<usize>,
+    backward: &mut Vec<usize>,
+) -> Option<((usize, usize), (usize, usize))> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let d_max = (n + m + 1) / 2;
+
+    // Furthest x reached on every diagonal k = x - y, from the start of `a` and `b` forward and
+    // from their end backward, indexed by k offset by d_max + 1
+    let len = 2 * d_max as usize + 3;
+    forward.clear();
+    forward.resize(len, 0);
+    backward.clear();
+    backward.resize(len, 0);
+    let i = |k: isize| (k + d_max + 1) as usize;
+
+    for d in 0..=d_max.min(MAX_EDITS as isize / 2 + 1) {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && forward[i(k - 1)] < forward[i(k + 1)]) {
+                forward[i(k + 1)] as isize
+            } else {
+                forward[i(k - 1)] as isize + 1
+            };
+            let mut y = x - k;
+            let start = (x, y);
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[i(k)] = x as usize;
+            let back_k = delta - k;
+            if odd && back_k.abs() < d && x + backward[i(back_k)] as isize >= n {
+                return Some((
+                    (start.0 as usize, start.1 as usize),
+                    (x as usize, y as usize),
+                ));
+            }
+        }
+
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && backward[i(k - 1)] < backward[i(k + 1)]) {
+                backward[i(k + 1)] as isize
+            } else {
+                backward[i(k - 1)] as isize + 1
+            };
+            let mut y = x - k;
+            let start = (x, y);
+            while x < n && y < m && a[(n - x - 1) as usize] == b[(m - y - 1) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[i(k)] = x as usize;
+            let forward_k = delta - k;
+            if !odd && forward_k.abs() <= d && x + forward[i(forward_k)] as isize >= n {
+                return Some((
+                    ((n - x) as usize, (m - y) as usize),
+                    ((n - start.0) as usize, (m - start.1) as usize),
+                ));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::diff_ranges;
+
+    #[test]
+    fn diff_ranges_large() {
+        // Every 20th element changes, which takes 2000 edits over 40000 elements
+        let old: Vec<usize> = (0..20_000).collect();
+        let new: Vec<usize> = old
+            .iter()
+            .map(|&i| if i % 20 == 10 { i + 100_000 } else { i })
+            .collect();
+        let ranges = diff_ranges(&old, &new);
+        assert_eq!(ranges.len(), 1000);
+        for (n, (old_range, new_range)) in ranges.into_iter().enumerate() {
+            let i = n * 20 + 10;
+            assert_eq!(old_range, i..i + 1);
+            assert_eq!(new_range, i..i + 1);
+        }
+    }
+
+    #[test]
+    fn diff_ranges_shortest() {
+        // The ranges of every pair of short sequences over two letters keep a longest common
+        // subsequence
+        let sequences: Vec<Vec<u8>> = (0..1 << 7)
+            .flat_map(|bits: u32| {
+                (0..=7).map(move |len| (0..len).map(|i| (bits >> i) as u8 & 1).collect())
+            })
+            .collect();
+        for old in sequences.iter() {
+            for new in sequences.iter().step_by(7) {
+                let mut lcs = vec![vec![0; new.len() + 1]; old.len() + 1];
+                for i in 0..old.len() {
+                    for j in 0..new.len() {
+                        lcs[i + 1][j + 1] = if old[i] == new[j] {
+                            lcs[i][j] + 1
+                        } else {
+                            lcs[i][j + 1].max(lcs[i + 1][j])
+                        };
+                    }
+                }
+                let ranges = diff_ranges(old, new);
+                let changed: usize = ranges.iter().map(|(o, n)| o.len() + n.len()).sum();
+                assert_eq!(
+                    changed,
+                    old.len() + new.len() - 2 * lcs[old.len()][new.len()],
+                    "{old:?} {new:?}"
+                );
+
+                // Applying the ranges gives the new sequence
+                let mut applied = Vec::new();
+                let mut x = 0;
+                for (old_range, new_range) in ranges {
+                    applied.extend_from_slice(&old[x..old_range.start]);
+                    applied.extend_from_slice(&new[new_range]);
+                    x = old_range.end;
+                }
+                applied.extend_from_slice(&old[x..]);
+                assert_eq!(&applied, new);
+            }
+        }
+    }
+}
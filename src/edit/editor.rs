@@ -1,19 +1,28 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
 #[cfg(not(feature = "std"))]
 use alloc::{
     string::{String, ToString},
+    vec,
     vec::Vec,
 };
-use core::{cmp, iter::once};
-use unicode_segmentation::UnicodeSegmentation;
+use core::{cmp, iter::once, mem, ops::Range};
 
 #[cfg(feature = "swash")]
-use crate::Color;
+use super::selection_bounds;
+use super::{adjust_cursor, adjust_selection};
+use crate::graphemes::grapheme_indices;
 use crate::{
-    Action, Attrs, AttrsList, BorrowedWithFontSystem, BufferLine, BufferRef, Change, ChangeItem,
-    Cursor, Edit, FontSystem, LayoutRun, Selection, Shaping,
+    Action, Attrs, AttrsList, BorrowedWithFontSystem, Buffer, BufferLine, BufferRef, ChainedMotion,
+    Change, ChangeItem, ClickChain, ClipboardProvider, CopyOptions, Cursor, CursorArea,
+    CursorBlink, CursorColorPolicy, Edit, EditHistory, FontSystem, Graphemes, Gutter, Hover,
+    InputTransformer, JumpList, LayoutRun, Motion, Rect, Selection, SelectionHandle,
+    SelectionHandles, Shaping, VerticalMotion,
 };
+#[cfg(feature = "swash")]
+use crate::{Color, DecorationKind};
 
 /// A wrapper of [`Buffer`] for easy editing
 #[derive(Debug, Clone)]
@@ -24,22 +33,58 @@ pub struct Editor<'buffer> {
     selection: Selection,
     cursor_moved: bool,
     auto_indent: bool,
+    auto_pair: bool,
+    overtype: bool,
+    vertical_motion: VerticalMotion,
+    input_transformer: Option<Arc<dyn InputTransformer>>,
+    clipboard: Option<Arc<dyn ClipboardProvider>>,
+    extra_cursors: Vec<EditorCursor>,
     change: Option<Change>,
+    history: EditHistory,
+    cursor_blink: CursorBlink,
+    cursor_color_policy: CursorColorPolicy,
+    click_chain: ClickChain,
+    hover: Option<Hover>,
+    primary_selection: bool,
+    primary_selection_bounds: Option<(Cursor, Cursor)>,
+    gutter: Option<Gutter>,
+    gutter_click: Option<usize>,
+    jump_list: JumpList,
+}
+
+/// A cursor of an [`Editor`] with its selection
+#[derive(Clone, Copy, Debug)]
+struct EditorCursor {
+    cursor: Cursor,
+    cursor_x_opt: Option<i32>,
+    selection: Selection,
+}
+
+impl EditorCursor {
+    /// Move the cursor and selection to keep them on the same text after `item` was applied
+    fn adjust(&mut self, item: &ChangeItem) {
+        adjust_cursor(&mut self.cursor, item);
+        adjust_selection(&mut self.selection, item);
+    }
 }
 
-fn cursor_glyph_opt(cursor: &Cursor, run: &LayoutRun) -> Option<(usize, f32)> {
-    if cursor.line == run.line_i {
+fn cursor_glyph_opt(
+    cursor: &Cursor,
+    run: &LayoutRun,
+    graphemes: &Graphemes,
+    cursor_layout_i: Option<usize>,
+) -> Option<(usize, f32)> {
+    if cursor.line == run.line_i && cursor_layout_i.map_or(true, |i| i == run.layout_i) {
         for (glyph_i, glyph) in run.glyphs.iter().enumerate() {
-            if cursor.index == glyph.start {
+            if cursor.index == glyph.start as usize {
                 return Some((glyph_i, 0.0));
-            } else if cursor.index > glyph.start && cursor.index < glyph.end {
+            } else if cursor.index > glyph.start as usize && cursor.index < glyph.end as usize {
                 // Guess x offset based on characters
                 let mut before = 0;
                 let mut total = 0;
 
-                let cluster = &run.text[glyph.start..glyph.end];
-                for (i, _) in cluster.grapheme_indices(true) {
-                    if glyph.start + i < cursor.index {
+                for egc in graphemes.within(glyph.range()) {
+                    if egc.start < cursor.index {
                         before += 1;
                     }
                     total += 1;
@@ -51,7 +96,7 @@ fn cursor_glyph_opt(cursor: &Cursor, run: &LayoutRun) -> Option<(usize, f32)> {
         }
         match run.glyphs.last() {
             Some(glyph) => {
-                if cursor.index == glyph.end {
+                if cursor.index == glyph.end as usize {
                     return Some((run.glyphs.len(), 0.0));
                 }
             }
@@ -63,8 +108,16 @@ fn cursor_glyph_opt(cursor: &Cursor, run: &LayoutRun) -> Option<(usize, f32)> {
     None
 }
 
-fn cursor_position(cursor: &Cursor, run: &LayoutRun) -> Option<(i32, i32)> {
-    let (cursor_glyph, cursor_glyph_offset) = cursor_glyph_opt(cursor, run)?;
+/// Get the position of `cursor` in `run` of `buffer`, if it is placed on the layout line
+/// returned by [`Buffer::cursor_layout_line`]
+fn cursor_position(
+    cursor: &Cursor,
+    run: &LayoutRun,
+    graphemes: &Graphemes,
+    buffer: &Buffer,
+) -> Option<(i32, i32)> {
+    let (cursor_glyph, cursor_glyph_offset) =
+        cursor_glyph_opt(cursor, run, graphemes, buffer.cursor_layout_line(*cursor))?;
     let x = match run.glyphs.get(cursor_glyph) {
         Some(glyph) => {
             // Start of detected glyph
@@ -85,7 +138,7 @@ fn cursor_position(cursor: &Cursor, run: &LayoutRun) -> Option<(i32, i32)> {
             }
             None => {
                 // Start of empty line
-                0
+                buffer.padding().left as i32
             }
         },
     };
@@ -93,6 +146,48 @@ fn cursor_position(cursor: &Cursor, run: &LayoutRun) -> Option<(i32, i32)> {
     Some((x, run.line_top as i32))
 }
 
+/// Copy the attributes of `ranges` in `attrs_list`, as if the ranges were concatenated in order
+fn concat_attrs(attrs_list: &AttrsList, ranges: &[Range<usize>]) -> AttrsList {
+    let mut concat = AttrsList::new(attrs_list.defaults());
+    let mut offset = 0;
+    for range in ranges {
+        for (span, attrs) in attrs_list.spans_iter() {
+            let start = span.start.max(range.start);
+            let end = span.end.min(range.end);
+            if start < end {
+                concat.add_span(
+                    offset + start - range.start..offset + end - range.start,
+                    attrs.as_attrs(),
+                );
+            }
+        }
+        offset += range.len();
+    }
+    concat
+}
+
+/// Returns true if `motion` moves far enough to be recorded in the [`JumpList`]
+fn is_jump(motion: Motion) -> bool {
+    matches!(
+        motion,
+        Motion::PageUp
+            | Motion::PageDown
+            | Motion::BufferStart
+            | Motion::BufferEnd
+            | Motion::GotoLine(_)
+    )
+}
+
+/// Opening and closing characters paired by [`Edit::set_auto_pair`]
+const AUTO_PAIRS: [(char, char); 6] = [
+    ('(', ')'),
+    ('[', ']'),
+    ('{', '}'),
+    ('"', '"'),
+    ('\'', '\''),
+    ('`', '`'),
+];
+
 impl<'buffer> Editor<'buffer> {
     /// Create a new [`Editor`] with the provided [`Buffer`]
     pub fn new(buffer: impl Into<BufferRef<'buffer>>) -> Self {
@@ -103,10 +198,218 @@ impl<'buffer> Editor<'buffer> {
             selection: Selection::None,
             cursor_moved: false,
             auto_indent: false,
+            auto_pair: false,
+            overtype: false,
+            vertical_motion: VerticalMotion::default(),
+            input_transformer: None,
+            clipboard: None,
+            extra_cursors: Vec::new(),
             change: None,
+            history: EditHistory::default(),
+            cursor_blink: CursorBlink::default(),
+            cursor_color_policy: CursorColorPolicy::default(),
+            click_chain: ClickChain::default(),
+            hover: None,
+            primary_selection: false,
+            primary_selection_bounds: None,
+            gutter: None,
+            gutter_click: None,
+            jump_list: JumpList::default(),
         }
     }
 
+    /// Get the characters before and after `cursor` in its line
+    fn chars_around(&self, cursor: Cursor) -> (Option<char>, Option<char>) {
+        self.with_buffer(|buffer| {
+            let text = buffer.lines[cursor.line].text();
+            (
+                text[..cursor.index].chars().next_back(),
+                text[cursor.index..].chars().next(),
+            )
+        })
+    }
+
+    /// Wrap the selection with `open` and `close`, returning false if there is no selection
+    fn surround_selection(&mut self, open: char, close: char) -> bool {
+        let Some((start, end)) = self.selection_bounds() else {
+            return false;
+        };
+
+        let mut str_buf = [0u8; 8];
+        self.insert_at(end, close.encode_utf8(&mut str_buf), None);
+        let inner_start = self.insert_at(start, open.encode_utf8(&mut str_buf), None);
+        let mut inner_end = end;
+        if end.line == start.line {
+            inner_end.index += inner_start.index - start.index;
+        }
+
+        self.selection = Selection::Normal(inner_start);
+        self.cursor = inner_end;
+        self.with_buffer_mut(|buffer| buffer.set_redraw(true));
+        true
+    }
+
+    /// Insert `text` for [`Action::InsertText`], starting new lines like [`Action::Enter`]
+    fn insert_text(&mut self, font_system: &mut FontSystem, text: &str) {
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.action(font_system, Action::Enter);
+            }
+
+            // Filter out special chars (except for tab), like Action::Insert
+            let filter = |c: &char| !c.is_control() || ['\t', '\u{92}'].contains(c);
+            let line: Cow<str> = if line.chars().all(|c| filter(&c)) {
+                Cow::Borrowed(line)
+            } else {
+                log::debug!("Refusing to insert control characters in {:?}", line);
+                Cow::Owned(line.chars().filter(filter).collect())
+            };
+            if line.is_empty() {
+                continue;
+            }
+
+            if self.overtype && self.selection_bounds().is_none() {
+                // Replace one grapheme cluster under the cursor for every inserted one
+                let count = grapheme_indices(&line).count();
+                let cursor = self.cursor;
+                let end = self.with_buffer(|buffer| {
+                    let graphemes = buffer.lines[cursor.line].graphemes_cow();
+                    (0..count).fold(cursor.index, |index, _| graphemes.next(index))
+                });
+                if end > cursor.index {
+                    self.delete_range(cursor, Cursor::new(cursor.line, end));
+                }
+            }
+
+            self.insert_input(&line);
+        }
+    }
+
+    /// Handle inserting `character` with automatic pairing, returning false if it should be
+    /// inserted normally
+    fn auto_pair_insert(&mut self, character: char) -> bool {
+        let Some(&(open, close)) = AUTO_PAIRS
+            .iter()
+            .find(|&&(open, close)| character == open || character == close)
+        else {
+            return false;
+        };
+
+        if self.selection_bounds().is_some() {
+            return character == open && self.surround_selection(open, close);
+        }
+
+        let (prev, next) = self.chars_around(self.cursor);
+        if character == close && next == Some(close) {
+            // Skip over the existing closer
+            self.cursor.index += close.len_utf8();
+            return true;
+        }
+
+        // Only pair before whitespace or a closer, and not quotes directly after a word
+        let pairs_next = next.map_or(true, |next| {
+            next.is_whitespace() || AUTO_PAIRS.iter().any(|&(_, close)| next == close)
+        });
+        let pairs_prev = open != close || !prev.map_or(false, char::is_alphanumeric);
+        if character != open || !pairs_next || !pairs_prev {
+            return false;
+        }
+
+        let pair: String = [open, close].iter().collect();
+        self.insert_at(self.cursor, &pair, None);
+        self.cursor.index += open.len_utf8();
+        true
+    }
+
+    /// Swap the `first` and `second` ranges of text in `line`, keeping the text between them,
+    /// and move the cursor after both
+    fn transpose(&mut self, line: usize, first: Range<usize>, second: Range<usize>) {
+        let Some((text, attrs_list)) = self.with_buffer(|buffer| {
+            let buffer_line = buffer.lines.get(line)?;
+            let text = buffer_line.text();
+            let ranges = [second.clone(), first.end..second.start, first.clone()];
+            let swapped: String = ranges.iter().map(|range| &text[range.clone()]).collect();
+            Some((swapped, concat_attrs(buffer_line.attrs_list(), &ranges)))
+        }) else {
+            return;
+        };
+
+        let start = Cursor::new(line, first.start);
+        self.delete_range(start, Cursor::new(line, second.end));
+        self.cursor = self.insert_at(start, &text, Some(attrs_list));
+        self.with_buffer_mut(|buffer| buffer.set_redraw(true));
+    }
+
+    /// Get the [`Motion`] applied to the buffer for `motion`, following the
+    /// [`VerticalMotion`] setting
+    fn buffer_motion(&self, motion: Motion) -> Motion {
+        match (self.vertical_motion, motion) {
+            (VerticalMotion::Logical, Motion::Up) => Motion::PreviousLine,
+            (VerticalMotion::Logical, Motion::Down) => Motion::NextLine,
+            _ => motion,
+        }
+    }
+
+    /// Perform an action at every cursor, moving the other cursors to keep them on the same text
+    /// after each edit
+    fn action_all_cursors(&mut self, font_system: &mut FontSystem, action: Action) {
+        let mut cursors = mem::take(&mut self.extra_cursors);
+        cursors.insert(
+            0,
+            EditorCursor {
+                cursor: self.cursor,
+                cursor_x_opt: self.cursor_x_opt,
+                selection: self.selection,
+            },
+        );
+
+        // Changes are collected even if none was started, to adjust the other cursors
+        let started = self.change.is_none();
+        self.start_change();
+        for i in 0..cursors.len() {
+            self.cursor = cursors[i].cursor;
+            self.cursor_x_opt = cursors[i].cursor_x_opt;
+            self.selection = cursors[i].selection;
+            let items_start = self.change.as_ref().map_or(0, |change| change.items.len());
+            self.action(font_system, action.clone());
+            cursors[i] = EditorCursor {
+                cursor: self.cursor,
+                cursor_x_opt: self.cursor_x_opt,
+                selection: self.selection,
+            };
+            if let Some(change) = &self.change {
+                for item in change.items[items_start..].iter() {
+                    for (j, other) in cursors.iter_mut().enumerate() {
+                        if j != i {
+                            other.adjust(item);
+                        }
+                    }
+                }
+            }
+        }
+        if started {
+            self.change = None;
+        }
+
+        // Cursors that meet are merged
+        let primary = cursors.remove(0);
+        self.cursor = primary.cursor;
+        self.cursor_x_opt = primary.cursor_x_opt;
+        self.selection = primary.selection;
+        let mut positions = Vec::from([(primary.cursor.line, primary.cursor.index)]);
+        cursors.retain(|other| {
+            let position = (other.cursor.line, other.cursor.index);
+            if positions.contains(&position) {
+                false
+            } else {
+                positions.push(position);
+                true
+            }
+        });
+        self.extra_cursors = cursors;
+        self.with_buffer_mut(|buffer| buffer.set_redraw(true));
+    }
+
     /// Draw the editor
     #[cfg(feature = "swash")]
     pub fn draw<F>(
@@ -121,27 +424,53 @@ impl<'buffer> Editor<'buffer> {
     ) where
         F: FnMut(i32, i32, u32, u32, Color),
     {
-        let selection_bounds = self.selection_bounds();
+        let cursor_color = self.cursor_color_at(cursor_color, text_color);
+        let cursors = self.cursors();
         self.with_buffer(|buffer| {
+            let selections: Vec<_> = cursors
+                .iter()
+                .filter_map(|&(cursor, selection)| selection_bounds(buffer, cursor, selection))
+                .collect();
+            let padding = buffer.padding();
+            // Full line highlights span the width inside the padding
+            let line_start = padding.left as i32;
+            let line_end = buffer.size().0.map_or(0.0, |width| width - padding.right) as i32;
             for run in buffer.layout_runs() {
                 let line_i = run.line_i;
+                let graphemes = buffer.lines[line_i].graphemes_cow();
                 let line_y = run.line_y;
                 let line_top = run.line_top;
                 let line_height = run.line_height;
 
-                // Highlight selection
-                if let Some((start, end)) = selection_bounds {
+                for background in run.backgrounds() {
+                    f(
+                        background.x as i32,
+                        background.y as i32,
+                        background.w as u32,
+                        background.h as u32,
+                        background.color,
+                    );
+                }
+
+                let range_decorations = run.range_decorations(font_system, buffer.decorations());
+                for shape in range_decorations.iter() {
+                    if shape.kind == DecorationKind::Highlight {
+                        shape.draw(&mut f);
+                    }
+                }
+
+                // Highlight selections
+                for &(start, end) in selections.iter() {
                     if line_i >= start.line && line_i <= end.line {
                         let mut range_opt = None;
                         for glyph in run.glyphs.iter() {
                             // Guess x offset based on characters
-                            let cluster = &run.text[glyph.start..glyph.end];
-                            let total = cluster.grapheme_indices(true).count();
+                            let total = graphemes.within(glyph.range()).count();
                             let mut c_x = glyph.x;
                             let c_w = glyph.w / total as f32;
-                            for (i, c) in cluster.grapheme_indices(true) {
-                                let c_start = glyph.start + i;
-                                let c_end = glyph.start + i + c.len();
+                            for egc in graphemes.within(glyph.range()) {
+                                let c_start = egc.start;
+                                let c_end = egc.end;
                                 if (start.line != line_i || c_end > start.index)
                                     && (end.line != line_i || c_start < end.index)
                                 {
@@ -167,16 +496,16 @@ impl<'buffer> Editor<'buffer> {
 
                         if run.glyphs.is_empty() && end.line > line_i {
                             // Highlight all of internal empty lines
-                            range_opt = Some((0, buffer.size().0.unwrap_or(0.0) as i32));
+                            range_opt = Some((line_start, line_end));
                         }
 
                         if let Some((mut min, mut max)) = range_opt.take() {
                             if end.line > line_i {
                                 // Draw to end of line
                                 if run.rtl {
-                                    min = 0;
+                                    min = line_start;
                                 } else {
-                                    max = buffer.size().0.unwrap_or(0.0) as i32;
+                                    max = line_end;
                                 }
                             }
                             f(
@@ -190,9 +519,29 @@ impl<'buffer> Editor<'buffer> {
                     }
                 }
 
-                // Draw cursor
-                if let Some((x, y)) = cursor_position(&self.cursor, &run) {
-                    f(x, y, 1, line_height as u32, cursor_color);
+                // Draw cursors
+                if self.cursor_blink.visible() {
+                    for &(cursor, _) in cursors.iter() {
+                        if let Some((x, y)) = cursor_position(&cursor, &run, &graphemes, buffer) {
+                            // Overtype draws a block over the grapheme cluster that will be replaced
+                            let block_opt = run
+                                .glyphs
+                                .iter()
+                                .filter(|_| self.overtype && cursor.line == line_i)
+                                .find(|glyph| glyph.range().contains(&cursor.index))
+                                .map(|glyph| {
+                                    let total = graphemes.within(glyph.range()).count();
+                                    let w = cmp::max(1, (glyph.w / total as f32) as i32);
+                                    if glyph.level.is_rtl() {
+                                        (x - w, w)
+                                    } else {
+                                        (x, w)
+                                    }
+                                });
+                            let (x, w) = block_opt.unwrap_or((x, 1));
+                            f(x, y, w as u32, line_height as u32, cursor_color);
+                        }
+                    }
                 }
 
                 for glyph in run.glyphs.iter() {
@@ -202,16 +551,15 @@ impl<'buffer> Editor<'buffer> {
                         Some(some) => some,
                         None => text_color,
                     };
-                    if text_color != selected_text_color {
-                        if let Some((start, end)) = selection_bounds {
-                            if line_i >= start.line
+                    if text_color != selected_text_color
+                        && selections.iter().any(|&(start, end)| {
+                            line_i >= start.line
                                 && line_i <= end.line
-                                && (start.line != line_i || glyph.end > start.index)
-                                && (end.line != line_i || glyph.start < end.index)
-                            {
-                                glyph_color = selected_text_color;
-                            }
-                        }
+                                && (start.line != line_i || glyph.end as usize > start.index)
+                                && (end.line != line_i || end.index > glyph.start as usize)
+                        })
+                    {
+                        glyph_color = selected_text_color;
                     }
 
                     cache.with_pixels(
@@ -229,6 +577,26 @@ impl<'buffer> Editor<'buffer> {
                         },
                     );
                 }
+
+                for decoration in run.decorations(font_system) {
+                    f(
+                        decoration.x as i32,
+                        decoration.y as i32,
+                        decoration.w as u32,
+                        cmp::max(1, decoration.h.round() as u32),
+                        decoration.color_opt.unwrap_or(text_color),
+                    );
+                }
+
+                for mark in run.emphasis_marks(font_system) {
+                    mark.draw(text_color, &mut f);
+                }
+
+                for shape in range_decorations.iter() {
+                    if shape.kind != DecorationKind::Highlight {
+                        shape.draw(&mut f);
+                    }
+                }
             }
         });
     }
@@ -266,6 +634,112 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
         }
     }
 
+    fn cursors(&self) -> Vec<(Cursor, Selection)> {
+        once((self.cursor, self.selection))
+            .chain(
+                self.extra_cursors
+                    .iter()
+                    .map(|extra| (extra.cursor, extra.selection)),
+            )
+            .collect()
+    }
+
+    fn add_cursor(&mut self, cursor: Cursor, selection: Selection) {
+        let position = (cursor.line, cursor.index);
+        if (self.cursor.line, self.cursor.index) == position
+            || self
+                .extra_cursors
+                .iter()
+                .any(|extra| (extra.cursor.line, extra.cursor.index) == position)
+        {
+            return;
+        }
+        self.extra_cursors.push(EditorCursor {
+            cursor,
+            cursor_x_opt: None,
+            selection,
+        });
+        self.with_buffer_mut(|buffer| buffer.set_redraw(true));
+    }
+
+    fn clear_cursors(&mut self) {
+        if !self.extra_cursors.is_empty() {
+            self.extra_cursors.clear();
+            self.with_buffer_mut(|buffer| buffer.set_redraw(true));
+        }
+    }
+
+    fn cursor_blink(&self) -> Option<&CursorBlink> {
+        Some(&self.cursor_blink)
+    }
+
+    fn cursor_blink_mut(&mut self) -> Option<&mut CursorBlink> {
+        Some(&mut self.cursor_blink)
+    }
+
+    fn click_chain(&self) -> Option<&ClickChain> {
+        Some(&self.click_chain)
+    }
+
+    fn click_chain_mut(&mut self) -> Option<&mut ClickChain> {
+        Some(&mut self.click_chain)
+    }
+
+    fn cursor_color_policy(&self) -> CursorColorPolicy {
+        self.cursor_color_policy
+    }
+
+    fn set_cursor_color_policy(&mut self, cursor_color_policy: CursorColorPolicy) {
+        self.cursor_color_policy = cursor_color_policy;
+    }
+
+    fn overtype(&self) -> bool {
+        self.overtype
+    }
+
+    fn set_overtype(&mut self, overtype: bool) {
+        if overtype != self.overtype {
+            self.overtype = overtype;
+            self.with_buffer_mut(|buffer| buffer.set_redraw(true));
+        }
+    }
+
+    fn vertical_motion(&self) -> VerticalMotion {
+        self.vertical_motion
+    }
+
+    fn set_vertical_motion(&mut self, vertical_motion: VerticalMotion) {
+        self.vertical_motion = vertical_motion;
+    }
+
+    fn chained_motion(&mut self, font_system: &mut FontSystem, motion: Motion) -> Option<i32> {
+        let buffer_motion = self.buffer_motion(motion);
+        let cursor = self.cursor;
+        let cursor_x_opt = self.cursor_x_opt;
+        let chained = self.with_buffer_mut(|buffer| {
+            buffer.cursor_motion_chained(font_system, cursor, cursor_x_opt, buffer_motion)
+        });
+        if let Some(ChainedMotion::AtBoundary { cursor_x }) = chained {
+            return Some(cursor_x);
+        }
+        self.action(font_system, Action::Motion(motion));
+        None
+    }
+
+    fn enter_chained(&mut self, font_system: &mut FontSystem, motion: Motion, cursor_x: i32) {
+        let motion = self.buffer_motion(motion);
+        let Some((cursor, cursor_x_opt)) =
+            self.with_buffer_mut(|buffer| buffer.cursor_enter(font_system, motion, cursor_x))
+        else {
+            return;
+        };
+        self.clear_cursors();
+        self.set_selection(Selection::None);
+        self.set_cursor(cursor);
+        self.cursor_x_opt = cursor_x_opt;
+        self.cursor_blink.reset();
+    }
+
     fn auto_indent(&self) -> bool {
         self.auto_indent
     }
@@ -274,6 +748,30 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
         self.auto_indent = auto_indent;
     }
 
+    fn auto_pair(&self) -> bool {
+        self.auto_pair
+    }
+
+    fn set_auto_pair(&mut self, auto_pair: bool) {
+        self.auto_pair = auto_pair;
+    }
+
+    fn input_transformer(&self) -> Option<&dyn InputTransformer> {
+        self.input_transformer.as_deref()
+    }
+
+    fn set_input_transformer(&mut self, input_transformer: Option<Arc<dyn InputTransformer>>) {
+        self.input_transformer = input_transformer;
+    }
+
+    fn clipboard(&self) -> Option<&dyn ClipboardProvider> {
+        self.clipboard.as_deref()
+    }
+
+    fn set_clipboard(&mut self, clipboard: Option<Arc<dyn ClipboardProvider>>) {
+        self.clipboard = clipboard;
+    }
+
     fn tab_width(&self) -> u16 {
         self.with_buffer(|buffer| buffer.tab_width())
     }
@@ -349,6 +847,10 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
             }
         });
 
+        if let Some(gutter) = &mut self.gutter {
+            gutter.adjust(&change_item);
+        }
+        self.jump_list.adjust(&change_item);
         if let Some(ref mut change) = self.change {
             change.items.push(change_item);
         }
@@ -469,6 +971,10 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
             }
         });
 
+        if let Some(gutter) = &mut self.gutter {
+            gutter.adjust(&change_item);
+        }
+        self.jump_list.adjust(&change_item);
         if let Some(ref mut change) = self.change {
             change.items.push(change_item);
         }
@@ -477,34 +983,31 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
     }
 
     fn copy_selection(&self) -> Option<String> {
-        let (start, end) = self.selection_bounds()?;
-        self.with_buffer(|buffer| {
-            let mut selection = String::new();
-            // Take the selection from the first line
-            {
-                // Add selected part of line to string
-                if start.line == end.line {
-                    selection.push_str(&buffer.lines[start.line].text()[start.index..end.index]);
-                } else {
-                    selection.push_str(&buffer.lines[start.line].text()[start.index..]);
-                    selection.push('\n');
-                }
-            }
+        let mut selection = String::new();
+        self.copy_selection_into(&mut selection, CopyOptions::default())
+            .then_some(selection)
+    }
 
-            // Take the selection from all interior lines (if they exist)
-            for line_i in start.line + 1..end.line {
-                selection.push_str(buffer.lines[line_i].text());
-                selection.push('\n');
-            }
+    fn primary_selection(&self) -> bool {
+        self.primary_selection
+    }
 
-            // Take the selection from the last line
-            if end.line > start.line {
-                // Add selected part of line to string
-                selection.push_str(&buffer.lines[end.line].text()[..end.index]);
-            }
+    fn set_primary_selection(&mut self, primary_selection: bool) {
+        self.primary_selection = primary_selection;
+        self.primary_selection_bounds = None;
+    }
 
-            Some(selection)
-        })
+    fn take_primary_selection(&mut self) -> Option<String> {
+        if !self.primary_selection {
+            return None;
+        }
+        let bounds = self.selection_bounds().filter(|(start, end)| start != end);
+        if bounds == self.primary_selection_bounds {
+            return None;
+        }
+        self.primary_selection_bounds = bounds;
+        bounds?;
+        self.copy_selection()
     }
 
     fn delete_selection(&mut self) -> bool {
@@ -539,6 +1042,9 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
             None => {}
         }
 
+        // The secondary cursors are not tracked through applied changes
+        self.clear_cursors();
+
         for item in change.items.iter() {
             //TODO: edit cursor if needed?
             if item.insert {
@@ -551,6 +1057,45 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
         true
     }
 
+    fn apply_changes(&mut self, changes: &[Change]) -> bool {
+        if self
+            .change
+            .as_ref()
+            .map_or(false, |pending| !pending.items.is_empty())
+        {
+            log::warn!("pending change caused apply_changes to be ignored!");
+            return false;
+        }
+
+        let mut cursors = vec![EditorCursor {
+            cursor: self.cursor,
+            cursor_x_opt: self.cursor_x_opt,
+            selection: self.selection,
+        }];
+        cursors.append(&mut self.extra_cursors);
+
+        self.start_change();
+        for item in changes.iter().flat_map(|change| change.items.iter()) {
+            if item.insert {
+                self.insert_at(item.start, &item.text, None);
+            } else {
+                self.delete_range(item.start, item.end);
+            }
+            for cursor in cursors.iter_mut() {
+                cursor.adjust(item);
+            }
+        }
+        self.finish_change();
+
+        let primary = cursors.remove(0);
+        self.cursor = primary.cursor;
+        self.cursor_x_opt = primary.cursor_x_opt;
+        self.selection = primary.selection;
+        self.extra_cursors = cursors;
+        self.set_redraw(true);
+        true
+    }
+
     fn start_change(&mut self) {
         if self.change.is_none() {
             self.change = Some(Change::default());
@@ -558,19 +1103,68 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
     }
 
     fn finish_change(&mut self) -> Option<Change> {
-        self.change.take()
+        let change = self.change.take()?;
+        self.history.push(change.clone());
+        Some(change)
+    }
+
+    fn history(&self) -> Option<&EditHistory> {
+        Some(&self.history)
+    }
+
+    fn history_mut(&mut self) -> Option<&mut EditHistory> {
+        Some(&mut self.history)
     }
 
     fn action(&mut self, font_system: &mut FontSystem, action: Action) {
+        if !self.extra_cursors.is_empty() {
+            match action {
+                Action::Motion(_)
+                | Action::Insert(_)
+                | Action::InsertText(_)
+                | Action::Enter
+                | Action::Backspace
+                | Action::Delete
+                | Action::DeleteWordBackward
+                | Action::DeleteWordForward
+                | Action::Indent
+                | Action::Unindent
+                | Action::TransposeChars
+                | Action::TransposeWords
+                | Action::SurroundSelection(..) => {
+                    self.action_all_cursors(font_system, action);
+                    return;
+                }
+                Action::Escape
+                | Action::Click { .. }
+                | Action::DoubleClick { .. }
+                | Action::TripleClick { .. }
+                | Action::LongPress { .. } => self.clear_cursors(),
+                _ => {}
+            }
+        }
+
         let old_cursor = self.cursor;
 
+        // Keep the cursor visible while it is being used
+        if !matches!(action, Action::Scroll { .. }) {
+            if !self.cursor_blink.visible() {
+                self.with_buffer_mut(|buffer| buffer.set_redraw(true));
+            }
+            self.cursor_blink.reset();
+        }
+
         match action {
             Action::Motion(motion) => {
+                let motion = self.buffer_motion(motion);
                 let cursor = self.cursor;
                 let cursor_x_opt = self.cursor_x_opt;
                 if let Some((new_cursor, new_cursor_x_opt)) = self.with_buffer_mut(|buffer| {
                     buffer.cursor_motion(font_system, cursor, cursor_x_opt, motion)
                 }) {
+                    if is_jump(motion) && new_cursor.line != cursor.line {
+                        self.jump_list.push(cursor);
+                    }
                     self.cursor = new_cursor;
                     self.cursor_x_opt = new_cursor_x_opt;
                 }
@@ -589,11 +1183,43 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
                 } else if character == '\n' {
                     self.action(font_system, Action::Enter);
                 } else {
-                    let mut str_buf = [0u8; 8];
-                    let str_ref = character.encode_utf8(&mut str_buf);
-                    self.insert_string(str_ref, None);
+                    if self.overtype && self.selection_bounds().is_none() {
+                        // Replace the grapheme cluster under the cursor
+                        let cursor = self.cursor;
+                        let range_opt = self.with_buffer(|buffer| {
+                            buffer.lines[cursor.line]
+                                .graphemes_cow()
+                                .containing(cursor.index)
+                        });
+                        if let Some(range) = range_opt {
+                            self.delete_range(
+                                Cursor::new(cursor.line, range.start),
+                                Cursor::new(cursor.line, range.end),
+                            );
+                        }
+                    }
+
+                    if self.auto_pair && self.auto_pair_insert(character) {
+                        // Inserted with automatic pairing
+                    } else {
+                        let mut str_buf = [0u8; 8];
+                        let str_ref = character.encode_utf8(&mut str_buf);
+                        self.insert_input(str_ref);
+                    }
+                }
+            }
+            Action::InsertText(text) => {
+                let mut chars = text.chars();
+                match (chars.next(), chars.next()) {
+                    (None, _) => {}
+                    // A single character is inserted like Action::Insert, with automatic pairing
+                    (Some(character), None) => self.action(font_system, Action::Insert(character)),
+                    _ => self.insert_text(font_system, &text),
                 }
             }
+            Action::ToggleOvertype => {
+                self.set_overtype(!self.overtype);
+            }
             Action::Enter => {
                 //TODO: what about indenting more after opening brackets or parentheses?
                 if self.auto_indent {
@@ -625,7 +1251,16 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
                     // Deleted selection
                 } else {
                     // Save current cursor as end
-                    let end = self.cursor;
+                    let mut end = self.cursor;
+
+                    if self.auto_pair {
+                        // Delete both characters of an empty pair
+                        if let (Some(prev), Some(next)) = self.chars_around(end) {
+                            if AUTO_PAIRS.contains(&(prev, next)) {
+                                end.index += next.len_utf8();
+                            }
+                        }
+                    }
 
                     if self.cursor.index > 0 {
                         // Move cursor to previous character index
@@ -658,14 +1293,9 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
 
                     self.with_buffer(|buffer| {
                         if start.index < buffer.lines[start.line].text().len() {
-                            let line = &buffer.lines[start.line];
-
-                            let range_opt = line
-                                .text()
-                                .grapheme_indices(true)
-                                .take_while(|(i, _)| *i <= start.index)
-                                .last()
-                                .map(|(i, c)| i..(i + c.len()));
+                            let range_opt = buffer.lines[start.line]
+                                .graphemes_cow()
+                                .containing(start.index);
 
                             if let Some(range) = range_opt {
                                 start.index = range.start;
@@ -683,6 +1313,30 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
                     }
                 }
             }
+            Action::DeleteWordBackward | Action::DeleteWordForward => {
+                if !self.delete_selection() {
+                    let motion = if action == Action::DeleteWordBackward {
+                        Motion::PreviousWord
+                    } else {
+                        Motion::NextWord
+                    };
+                    let cursor = self.cursor;
+                    let target_opt = self.with_buffer_mut(|buffer| {
+                        buffer.cursor_motion(font_system, cursor, None, motion)
+                    });
+                    if let Some((target, _)) = target_opt {
+                        let (start, end) = if target < cursor {
+                            (target, cursor)
+                        } else {
+                            (cursor, target)
+                        };
+                        if start != end {
+                            self.cursor = start;
+                            self.delete_range(start, end);
+                        }
+                    }
+                }
+            }
             Action::Indent => {
                 // Get start and end of selection
                 let (start, end) = match self.selection_bounds() {
@@ -818,7 +1472,7 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
                     }
                 }
             }
-            Action::DoubleClick { x, y } => {
+            Action::DoubleClick { x, y } | Action::LongPress { x, y } => {
                 self.set_selection(Selection::None);
 
                 if let Some(new_cursor) = self.with_buffer(|buffer| buffer.hit(x as f32, y as f32))
@@ -861,10 +1515,137 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
                 self.with_buffer_mut(|buffer| {
                     let mut scroll = buffer.scroll();
                     //TODO: align to layout lines
-                    scroll.vertical += lines as f32 * buffer.metrics().line_height;
+                    scroll.vertical += lines as f32 * buffer.scaled_metrics().line_height;
                     buffer.set_scroll(scroll);
                 });
             }
+            Action::SurroundSelection(open, close) => {
+                self.surround_selection(open, close);
+            }
+            Action::GutterClick { y } => {
+                let y = y as f32;
+                self.gutter_click = self.with_buffer(|buffer| {
+                    buffer
+                        .layout_runs()
+                        .find(|run| y >= run.line_top && y < run.line_top + run.line_height)
+                        .map(|run| run.line_i)
+                });
+            }
+            Action::Copy => {
+                if let (Some(clipboard), Some(text)) = (&self.clipboard, self.copy_selection()) {
+                    clipboard.set_text(text);
+                }
+            }
+            Action::Cut => {
+                if let (Some(clipboard), Some(text)) = (&self.clipboard, self.copy_selection()) {
+                    clipboard.set_text(text);
+                    self.delete_selection();
+                }
+            }
+            Action::Paste(text) => {
+                // Pasted text is inserted as is, without the input transformer
+                self.delete_selection();
+                self.insert_string(&text, None);
+            }
+            Action::TransposeChars => {
+                let cursor = self.cursor;
+                let ranges_opt = self.with_buffer_mut(|buffer| {
+                    let line = buffer.lines.get_mut(cursor.line)?;
+                    let len = line.text().len();
+                    let graphemes = line.graphemes();
+                    // At the end of a line, swap the two characters before the cursor
+                    let middle = if cursor.index >= len {
+                        graphemes.prev(len)
+                    } else {
+                        cursor.index
+                    };
+                    if middle == 0 {
+                        return None;
+                    }
+                    let first = graphemes.prev(middle)..middle;
+                    let second = middle..graphemes.next(middle);
+                    Some((first, second))
+                });
+                if let Some((first, second)) = ranges_opt {
+                    self.set_selection(Selection::None);
+                    self.transpose(cursor.line, first, second);
+                }
+            }
+            Action::TransposeWords => {
+                let cursor = self.cursor;
+                let ranges_opt = self.with_buffer_mut(|buffer| {
+                    let words = buffer.lines.get_mut(cursor.line)?.graphemes().words();
+                    // Swap the word before or at the cursor with the next one, or the last two
+                    // words if there is no next word
+                    let second_i = match words.iter().position(|word| word.start >= cursor.index) {
+                        Some(0) => 1,
+                        Some(i) => i,
+                        None => words.len().checked_sub(1)?,
+                    };
+                    let first = words.get(second_i.checked_sub(1)?)?.clone();
+                    let second = words.get(second_i)?.clone();
+                    Some((first, second))
+                });
+                if let Some((first, second)) = ranges_opt {
+                    self.set_selection(Selection::None);
+                    self.transpose(cursor.line, first, second);
+                }
+            }
+            Action::DragHandle { which, x, y } => {
+                if let Some((start, end)) = self.selection_bounds() {
+                    // The edge of the selection that is not being dragged stays in place
+                    let (anchor, dragged) = match which {
+                        SelectionHandle::Start => (end, start),
+                        SelectionHandle::End => (start, end),
+                    };
+                    self.selection = Selection::Normal(anchor);
+                    self.cursor = dragged;
+
+                    if let Some(mut new_cursor) =
+                        self.with_buffer(|buffer| buffer.hit(x as f32, y as f32))
+                    {
+                        // Move past the visible lines to scroll when dragging beyond the buffer
+                        let height_opt = self.with_buffer(|buffer| buffer.size().1);
+                        let motion_opt = if y < 0 {
+                            Some(Motion::Up)
+                        } else if height_opt.map_or(false, |height| y as f32 >= height) {
+                            Some(Motion::Down)
+                        } else {
+                            None
+                        };
+                        if let Some(motion) = motion_opt {
+                            if let Some((cursor, _)) = self.with_buffer_mut(|buffer| {
+                                buffer.cursor_motion(font_system, new_cursor, None, motion)
+                            }) {
+                                new_cursor = cursor;
+                            }
+                        }
+                        self.cursor = new_cursor;
+                    }
+                    self.with_buffer_mut(|buffer| buffer.set_redraw(true));
+                }
+            }
+            Action::JumpBack | Action::JumpForward => {
+                let cursor_opt = if action == Action::JumpBack {
+                    self.jump_list.back(self.cursor)
+                } else {
+                    self.jump_list.forward()
+                };
+                if let Some(cursor) = cursor_opt {
+                    // The buffer may have been replaced without going through the editor
+                    self.cursor = self.with_buffer(|buffer| {
+                        let mut cursor = cursor;
+                        cursor.line = cursor.line.min(buffer.lines.len().saturating_sub(1));
+                        let text = buffer.lines.get(cursor.line).map_or("", |line| line.text());
+                        cursor.index = cursor.index.min(text.len());
+                        while !text.is_char_boundary(cursor.index) {
+                            cursor.index -= 1;
+                        }
+                        cursor
+                    });
+                    self.cursor_x_opt = None;
+                }
+            }
         }
 
         if old_cursor != self.cursor {
@@ -891,9 +1672,109 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
 
     fn cursor_position(&self) -> Option<(i32, i32)> {
         self.with_buffer(|buffer| {
-            buffer
-                .layout_runs()
-                .find_map(|run| cursor_position(&self.cursor, &run))
+            buffer.layout_runs().find_map(|run| {
+                let graphemes = buffer.lines[run.line_i].graphemes_cow();
+                cursor_position(&self.cursor, &run, &graphemes, buffer)
+            })
+        })
+    }
+
+    fn cursor_area(&self, scale_factor: f32) -> Option<CursorArea> {
+        self.with_buffer(|buffer| {
+            buffer.layout_runs().find_map(|run| {
+                let graphemes = buffer.lines[run.line_i].graphemes_cow();
+                let (x, _) = cursor_position(&self.cursor, &run, &graphemes, buffer)?;
+                let cursor = Rect::new(x as f32, run.line_top, 0.0, run.line_height);
+                let line = run
+                    .glyphs
+                    .iter()
+                    .map(|glyph| (glyph.x, glyph.x + glyph.w))
+                    .reduce(|(left, right), (glyph_left, glyph_right)| {
+                        (left.min(glyph_left), right.max(glyph_right))
+                    })
+                    .map_or(cursor, |(left, right)| {
+                        Rect::new(left, run.line_top, right - left, run.line_height)
+                    });
+                Some(CursorArea {
+                    cursor,
+                    line,
+                    scale_factor,
+                })
+            })
+        })
+    }
+
+    fn hover(&self) -> Option<&Hover> {
+        self.hover.as_ref()
+    }
+
+    fn set_hover_position(&mut self, position: Option<(i32, i32)>) {
+        let hover = position.and_then(|(x, y)| {
+            let (x, y) = (x as f32, y as f32);
+            self.with_buffer(|buffer| {
+                let run = buffer
+                    .layout_runs()
+                    .find(|run| y >= run.line_top && y < run.line_top + run.line_height)?;
+                let glyph = run
+                    .glyphs
+                    .iter()
+                    .find(|glyph| x >= glyph.x && x < glyph.x + glyph.w)?;
+                Some(Hover {
+                    cursor: Cursor::new(run.line_i, glyph.start as usize),
+                    glyph: Rect::new(glyph.x, run.line_top, glyph.w, run.line_height),
+                    span: buffer.lines[run.line_i]
+                        .attrs_list()
+                        .get_span_range(glyph.start as usize),
+                    metadata: glyph.metadata,
+                })
+            })
+        });
+        if hover != self.hover {
+            self.hover = hover;
+            self.with_buffer_mut(|buffer| buffer.set_redraw(true));
+        }
+    }
+
+    fn gutter(&self) -> Option<&Gutter> {
+        self.gutter.as_ref()
+    }
+
+    fn gutter_mut(&mut self) -> Option<&mut Gutter> {
+        self.gutter.as_mut()
+    }
+
+    fn set_gutter(&mut self, gutter: Option<Gutter>) {
+        self.gutter = gutter;
+        self.gutter_click = None;
+        self.with_buffer_mut(|buffer| buffer.set_redraw(true));
+    }
+
+    fn take_gutter_click(&mut self) -> Option<usize> {
+        self.gutter_click.take()
+    }
+
+    fn jump_list(&self) -> Option<&JumpList> {
+        Some(&self.jump_list)
+    }
+
+    fn jump_list_mut(&mut self) -> Option<&mut JumpList> {
+        Some(&mut self.jump_list)
+    }
+
+    fn selection_handles(&self) -> Option<SelectionHandles> {
+        let (start, end) = self.selection_bounds()?;
+        self.with_buffer(|buffer| {
+            let anchor = |cursor: Cursor| {
+                buffer.layout_runs().find_map(|run| {
+                    let graphemes = buffer.lines[run.line_i].graphemes_cow();
+                    let (x, y) = cursor_position(&cursor, &run, &graphemes, buffer)?;
+                    Some((x, y + run.line_height as i32))
+                })
+            };
+            Some(SelectionHandles {
+                start: anchor(start),
+                end: anchor(end),
+            })
         })
     }
 }
@@ -0,0 +1,109 @@
+use alloc::collections::BTreeMap;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use super::adjust_cursor;
+use crate::{ChangeItem, Cursor};
+
+/// A region left of the text of an editor for line numbers and markers like breakpoints, see
+/// [`crate::Edit::set_gutter`]
+///
+/// The editor does not draw the gutter or offset the text by its width. Draw the text
+/// [`Self::width`] pixels to the right, the cells of [`crate::Edit::gutter_cells`] left of it,
+/// and send clicks on the gutter as [`crate::Action::GutterClick`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Gutter {
+    width: f32,
+    /// Sorted marker ids by line
+    markers: BTreeMap<usize, Vec<usize>>,
+}
+
+impl Gutter {
+    /// Create a gutter `width` pixels wide, without markers
+    pub fn new(width: f32) -> Self {
+        Self {
+            width,
+            markers: BTreeMap::new(),
+        }
+    }
+
+    /// Get the width reserved left of the text
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    /// Set the width reserved left of the text
+    pub fn set_width(&mut self, width: f32) {
+        self.width = width;
+    }
+
+    /// Get the marker ids of a line, in ascending order
+    pub fn markers(&self, line_i: usize) -> &[usize] {
+        self.markers.get(&line_i).map_or(&[], Vec::as_slice)
+    }
+
+    /// Add marker `id` to a line, returning false if it was already there
+    pub fn add_marker(&mut self, line_i: usize, id: usize) -> bool {
+        let markers = self.markers.entry(line_i).or_default();
+        match markers.binary_search(&id) {
+            Ok(_) => false,
+            Err(i) => {
+                markers.insert(i, id);
+                true
+            }
+        }
+    }
+
+    /// Remove marker `id` from a line, returning false if it was not there
+    pub fn remove_marker(&mut self, line_i: usize, id: usize) -> bool {
+        let Some(markers) = self.markers.get_mut(&line_i) else {
+            return false;
+        };
+        let Ok(i) = markers.binary_search(&id) else {
+            return false;
+        };
+        markers.remove(i);
+        if markers.is_empty() {
+            self.markers.remove(&line_i);
+        }
+        true
+    }
+
+    /// Remove all markers
+    pub fn clear_markers(&mut self) {
+        self.markers.clear();
+    }
+
+    /// Move markers to keep them on the same lines after `item` was applied. Markers of deleted
+    /// lines move to the line the deletion started on
+    pub(crate) fn adjust(&mut self, item: &ChangeItem) {
+        if item.start.line == item.end.line {
+            return;
+        }
+        let markers = core::mem::take(&mut self.markers);
+        for (line_i, ids) in markers {
+            let mut cursor = Cursor::new(line_i, 0);
+            adjust_cursor(&mut cursor, item);
+            for id in ids {
+                self.add_marker(cursor.line, id);
+            }
+        }
+    }
+}
+
+/// A cell of a [`Gutter`] next to a visual line, see [`crate::Edit::gutter_cells`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct GutterCell {
+    /// Index of the line in the buffer
+    pub line_i: usize,
+    /// Index of the visual line within the line, greater than zero for wrapped lines
+    pub layout_i: usize,
+    /// Y offset of the top of the cell, in the same coordinates as [`crate::LayoutRun::line_top`]
+    pub y: f32,
+    /// Height of the cell
+    pub height: f32,
+    /// Line number to show, counting from one, only on the first visual line of a line
+    pub line_number: Option<usize>,
+    /// Marker ids of the line, only on the first visual line of a line
+    pub markers: Vec<usize>,
+}
@@ -0,0 +1,188 @@
+use alloc::collections::VecDeque;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use crate::Change;
+
+/// Undo and redo history, shared by all editors
+///
+/// Changes returned by [`crate::Edit::finish_change`] are recorded automatically, and undone or
+/// redone with [`crate::Edit::undo`] and [`crate::Edit::redo`]. Characters typed one after
+/// another are coalesced into a single change, split after whitespace, so undoing removes a word
+/// at a time.
+#[derive(Clone, Debug)]
+pub struct EditHistory {
+    undo: VecDeque<Change>,
+    redo: Vec<Change>,
+    max_depth: usize,
+    coalesce: bool,
+    /// Number of changes on the undo stack when the history was saved, None if that state can no
+    /// longer be reached
+    saved: Option<usize>,
+}
+
+impl Default for EditHistory {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MAX_DEPTH)
+    }
+}
+
+impl EditHistory {
+    /// Default number of changes that can be undone
+    pub const DEFAULT_MAX_DEPTH: usize = 1000;
+
+    /// Create a new [`EditHistory`] keeping up to `max_depth` changes that can be undone
+    pub fn new(max_depth: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            max_depth,
+            coalesce: true,
+            saved: Some(0),
+        }
+    }
+
+    /// Get the number of changes that can be undone
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Set the number of changes that can be undone, dropping the oldest changes over the limit
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+        self.trim();
+    }
+
+    /// Get whether characters typed one after another are coalesced into a single change
+    pub fn coalesce(&self) -> bool {
+        self.coalesce
+    }
+
+    /// Set whether characters typed one after another are coalesced into a single change
+    pub fn set_coalesce(&mut self, coalesce: bool) {
+        self.coalesce = coalesce;
+    }
+
+    /// Returns true if there is a change to undo
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Returns true if there is a change to redo
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Remove all changes
+    pub fn clear(&mut self) {
+        self.saved = if self.is_saved() { Some(0) } else { None };
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Mark the current state as saved, see [`Self::is_saved`]
+    pub fn set_saved(&mut self) {
+        self.saved = Some(self.undo.len());
+    }
+
+    /// Forget the saved state, when the saved file no longer matches any state of the history
+    pub fn clear_saved(&mut self) {
+        self.saved = None;
+    }
+
+    /// Returns true if the changes were undone or redone back to the state marked with
+    /// [`Self::set_saved`], or to the initial state if it was never marked
+    pub fn is_saved(&self) -> bool {
+        self.saved == Some(self.undo.len())
+    }
+
+    /// Get the changes that lead from the state marked with [`Self::set_saved`] to the current
+    /// state, in the order they apply, or None if the saved state can no longer be reached
+    pub fn unsaved_changes(&self) -> Option<Vec<Change>> {
+        let saved = self.saved?;
+        if saved <= self.undo.len() {
+            return Some(self.undo.range(saved..).cloned().collect());
+        }
+        // The saved state was undone, so going back to the current state undoes the redo changes
+        let undone = saved - self.undo.len();
+        let start = self.redo.len().checked_sub(undone)?;
+        Some(
+            self.redo[start..]
+                .iter()
+                .map(|change| {
+                    let mut reversed = change.clone();
+                    reversed.reverse();
+                    reversed
+                })
+                .collect(),
+        )
+    }
+
+    /// Record a finished change, clearing the changes that could be redone. Empty changes are
+    /// ignored
+    pub fn push(&mut self, change: Change) {
+        if change.items.is_empty() {
+            return;
+        }
+        if self.saved.map_or(false, |saved| saved > self.undo.len()) {
+            // The saved state was undone and can no longer be redone
+            self.saved = None;
+        }
+        self.redo.clear();
+        if self.coalesce && !self.is_saved() {
+            if let Some(last) = self.undo.back_mut() {
+                if Self::coalesce_change(last, &change) {
+                    return;
+                }
+            }
+        }
+        self.undo.push_back(change);
+        self.trim();
+    }
+
+    /// Take the last change, reversed so applying it undoes the change
+    pub fn undo(&mut self) -> Option<Change> {
+        let change = self.undo.pop_back()?;
+        let mut reversed = change.clone();
+        reversed.reverse();
+        self.redo.push(change);
+        Some(reversed)
+    }
+
+    /// Take the last undone change, to be applied again
+    pub fn redo(&mut self) -> Option<Change> {
+        let change = self.redo.pop()?;
+        self.undo.push_back(change.clone());
+        Some(change)
+    }
+
+    /// Append a typed character to the last change if it continues it
+    fn coalesce_change(last: &mut Change, change: &Change) -> bool {
+        let ([last_item], [item]) = (last.items.as_mut_slice(), change.items.as_slice()) else {
+            return false;
+        };
+        let mut chars = item.text.chars();
+        let (Some(c), None) = (chars.next(), chars.next()) else {
+            return false;
+        };
+        let continues = last_item.insert
+            && item.insert
+            && (last_item.end.line, last_item.end.index) == (item.start.line, item.start.index)
+            && c != '\n'
+            && !last_item.text.contains('\n')
+            // Start a new change at the start of each word
+            && (c.is_whitespace() || !last_item.text.ends_with(char::is_whitespace));
+        if continues {
+            last_item.text.push(c);
+            last_item.end = item.end;
+        }
+        continues
+    }
+
+    fn trim(&mut self) {
+        while self.undo.len() > self.max_depth {
+            self.undo.pop_front();
+            self.saved = self.saved.and_then(|saved| saved.checked_sub(1));
+        }
+    }
+}
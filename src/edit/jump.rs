@@ -0,0 +1,101 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use super::adjust_cursor;
+use crate::{ChangeItem, Cursor};
+
+/// Positions the cursor jumped away from, navigated with [`crate::Action::JumpBack`] and
+/// [`crate::Action::JumpForward`]
+///
+/// Editors record the position before significant cursor moves, like going to a line, the start
+/// or end of the buffer, a page up or down, or a search match. Entries follow their text as it is
+/// edited.
+#[derive(Clone, Debug)]
+pub struct JumpList {
+    entries: Vec<Cursor>,
+    /// Index of the entry that was jumped to, equal to the number of entries if not navigating
+    index: usize,
+    max_len: usize,
+}
+
+impl Default for JumpList {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_MAX_LEN)
+    }
+}
+
+impl JumpList {
+    /// Default number of entries that are kept
+    pub const DEFAULT_MAX_LEN: usize = 100;
+
+    /// Create a new [`JumpList`] keeping up to `max_len` entries
+    pub fn new(max_len: usize) -> Self {
+        Self {
+            entries: Vec::new(),
+            index: 0,
+            max_len,
+        }
+    }
+
+    /// Get the entries, oldest first
+    pub fn entries(&self) -> &[Cursor] {
+        &self.entries
+    }
+
+    /// Returns true if [`Self::back`] would return an entry
+    pub fn can_back(&self) -> bool {
+        self.index > 0
+    }
+
+    /// Returns true if [`Self::forward`] would return an entry
+    pub fn can_forward(&self) -> bool {
+        self.index + 1 < self.entries.len()
+    }
+
+    /// Remove all entries
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.index = 0;
+    }
+
+    /// Record a jump away from `cursor`, dropping the entries that could be navigated forward to
+    pub fn push(&mut self, cursor: Cursor) {
+        self.entries.truncate(self.index);
+        // Only keep the newest entry on each line
+        self.entries.retain(|entry| entry.line != cursor.line);
+        self.entries.push(cursor);
+        if self.entries.len() > self.max_len {
+            self.entries.drain(..self.entries.len() - self.max_len);
+        }
+        self.index = self.entries.len();
+    }
+
+    /// Get the previous entry, jumping away from `cursor`. When not already navigating, `cursor`
+    /// is recorded so [`Self::forward`] can return to it
+    pub fn back(&mut self, cursor: Cursor) -> Option<Cursor> {
+        if self.index == 0 {
+            return None;
+        }
+        if self.index == self.entries.len() {
+            self.entries.push(cursor);
+        }
+        self.index -= 1;
+        self.entries.get(self.index).copied()
+    }
+
+    /// Get the next entry, undoing a [`Self::back`]
+    pub fn forward(&mut self) -> Option<Cursor> {
+        if !self.can_forward() {
+            return None;
+        }
+        self.index += 1;
+        self.entries.get(self.index).copied()
+    }
+
+    /// Move entries to keep them on the same text after `item` was applied
+    pub(crate) fn adjust(&mut self, item: &ChangeItem) {
+        for entry in self.entries.iter_mut() {
+            adjust_cursor(entry, item);
+        }
+    }
+}
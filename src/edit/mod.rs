@@ -1,26 +1,203 @@
 use alloc::sync::Arc;
 #[cfg(not(feature = "std"))]
 use alloc::{string::String, vec::Vec};
-use core::cmp;
-use unicode_segmentation::UnicodeSegmentation;
+use core::time::Duration;
+use core::{cmp, ops::Range};
+use smol_str::SmolStr;
 
-use crate::{AttrsList, BorrowedWithFontSystem, Buffer, Cursor, FontSystem, Motion};
+use crate::{
+    AttrsList, BorrowedWithFontSystem, Buffer, Color, Cursor, FontSystem, LineEnding, Metadata,
+    Motion, Rect, ScrollPolicy,
+};
+
+pub use self::blink::*;
+mod blink;
+
+pub use self::click::*;
+mod click;
+
+pub use self::clipboard::*;
+mod clipboard;
+
+pub use self::diff::*;
+mod diff;
 
 pub use self::editor::*;
 mod editor;
 
+pub use self::gutter::*;
+mod gutter;
+
+pub use self::history::*;
+mod history;
+
+pub use self::jump::*;
+mod jump;
+
+pub use self::search::*;
+mod search;
+
 #[cfg(feature = "syntect")]
 pub use self::syntect::*;
 #[cfg(feature = "syntect")]
 mod syntect;
 
+pub use self::transform::*;
+mod transform;
+
 #[cfg(feature = "vi")]
 pub use self::vi::*;
 #[cfg(feature = "vi")]
 mod vi;
 
+/// Move `cursor` to keep it on the same text after `item` was applied
+pub(crate) fn adjust_cursor(cursor: &mut Cursor, item: &ChangeItem) {
+    let position = (cursor.line, cursor.index);
+    let start = (item.start.line, item.start.index);
+    let end = (item.end.line, item.end.index);
+    if item.insert {
+        if position < start {
+            return;
+        }
+        if cursor.line == start.0 {
+            cursor.index = cursor.index - start.1 + end.1;
+        }
+        cursor.line += end.0 - start.0;
+    } else {
+        if position <= start {
+            return;
+        }
+        if position <= end {
+            // Deleted text collapses to its start
+            cursor.line = start.0;
+            cursor.index = start.1;
+            return;
+        }
+        if cursor.line == end.0 {
+            cursor.index = cursor.index - end.1 + start.1;
+        }
+        cursor.line -= end.0 - start.0;
+    }
+}
+
+/// Move the anchor of `selection` to keep it on the same text after `item` was applied
+pub(crate) fn adjust_selection(selection: &mut Selection, item: &ChangeItem) {
+    match selection {
+        Selection::None => {}
+        Selection::Normal(select) | Selection::Line(select) | Selection::Word(select) => {
+            adjust_cursor(select, item);
+        }
+    }
+}
+
+/// Replace the text between `start` and `end` with `text`, keeping every cursor of `editor` on
+/// the same text, and returning the end of the inserted text
+fn replace_range<'buffer, E: Edit<'buffer> + ?Sized>(
+    editor: &mut E,
+    start: Cursor,
+    end: Cursor,
+    text: &str,
+) -> Cursor {
+    let mut cursors = editor.cursors();
+    editor.delete_range(start, end);
+    let inserted_end = editor.insert_at(start, text, None);
+    let delete = ChangeItem {
+        start,
+        end,
+        text: String::new(),
+        insert: false,
+    };
+    let insert = ChangeItem {
+        start,
+        end: inserted_end,
+        text: String::new(),
+        insert: true,
+    };
+    // Cursors at the start of the replaced text stay before the replacement
+    let adjust = |cursor: &mut Cursor| {
+        let at_start = (cursor.line, cursor.index) == (start.line, start.index);
+        adjust_cursor(cursor, &delete);
+        if !at_start {
+            adjust_cursor(cursor, &insert);
+        }
+    };
+    for (cursor, selection) in cursors.iter_mut() {
+        adjust(cursor);
+        match selection {
+            Selection::None => {}
+            Selection::Normal(select) | Selection::Line(select) | Selection::Word(select) => {
+                adjust(select);
+            }
+        }
+    }
+
+    let (cursor, selection) = cursors[0];
+    editor.set_cursor(cursor);
+    editor.set_selection(selection);
+    editor.clear_cursors();
+    for &(cursor, selection) in cursors[1..].iter() {
+        editor.add_cursor(cursor, selection);
+    }
+    inserted_end
+}
+
+/// Get the bounds of the selection between `cursor` and `selection`
+pub(crate) fn selection_bounds(
+    buffer: &Buffer,
+    cursor: Cursor,
+    selection: Selection,
+) -> Option<(Cursor, Cursor)> {
+    match selection {
+        Selection::None => None,
+        Selection::Normal(select) => match select.line.cmp(&cursor.line) {
+            cmp::Ordering::Greater => Some((cursor, select)),
+            cmp::Ordering::Less => Some((select, cursor)),
+            cmp::Ordering::Equal => {
+                /* select.line == cursor.line */
+                if select.index < cursor.index {
+                    Some((select, cursor))
+                } else {
+                    /* select.index >= cursor.index */
+                    Some((cursor, select))
+                }
+            }
+        },
+        Selection::Line(select) => {
+            let start_line = cmp::min(select.line, cursor.line);
+            let end_line = cmp::max(select.line, cursor.line);
+            let end_index = buffer.lines[end_line].text().len();
+            Some((Cursor::new(start_line, 0), Cursor::new(end_line, end_index)))
+        }
+        Selection::Word(select) => {
+            let (mut start, mut end) = match select.line.cmp(&cursor.line) {
+                cmp::Ordering::Greater => (cursor, select),
+                cmp::Ordering::Less => (select, cursor),
+                cmp::Ordering::Equal => {
+                    /* select.line == cursor.line */
+                    if select.index < cursor.index {
+                        (select, cursor)
+                    } else {
+                        /* select.index >= cursor.index */
+                        (cursor, select)
+                    }
+                }
+            };
+
+            // Move start to beginning of word
+            start.index = buffer.lines[start.line]
+                .graphemes_cow()
+                .prev_word(start.index);
+
+            // Move end to end of word
+            end.index = buffer.lines[end.line].graphemes_cow().next_word(end.index);
+
+            Some((start, end))
+        }
+    }
+}
+
 /// An action to perform on an [`Editor`]
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Action {
     /// Move the cursor with some motion
     Motion(Motion),
@@ -28,12 +205,22 @@ pub enum Action {
     Escape,
     /// Insert character at cursor
     Insert(char),
+    /// Insert text from one input event at cursor, such as an IME commit, as a single change.
+    /// Control characters other than tab and newline are filtered out and overtype replaces one
+    /// grapheme cluster for every inserted grapheme cluster
+    InsertText(SmolStr),
     /// Create new line
     Enter,
     /// Delete text behind cursor
     Backspace,
     /// Delete text in front of cursor
     Delete,
+    /// Delete the selection, or the text from the start of the word behind the cursor, joining
+    /// lines at the start of a line
+    DeleteWordBackward,
+    /// Delete the selection, or the text to the end of the word in front of the cursor, joining
+    /// lines at the end of a line
+    DeleteWordForward,
     // Indent text (typically Tab)
     Indent,
     // Unindent text (typically Shift+Tab)
@@ -62,9 +249,75 @@ pub enum Action {
     Scroll {
         lines: i32,
     },
+    /// Swap the characters before and after the cursor, or the two characters before the cursor
+    /// at the end of a line
+    TransposeChars,
+    /// Swap the word before or at the cursor with the word after it
+    TransposeWords,
+    /// Toggle overtype mode, see [`Edit::set_overtype`]
+    ToggleOvertype,
+    /// Wrap the selection with the opening and closing characters, keeping the original text
+    /// selected. Does nothing if there is no selection
+    SurroundSelection(char, char),
+    /// Touch long press at specified position, selecting the word there. Following [`Action::Drag`]
+    /// actions extend the selection by whole words
+    LongPress {
+        x: i32,
+        y: i32,
+    },
+    /// Touch drag of a selection handle to specified position
+    DragHandle {
+        which: SelectionHandle,
+        x: i32,
+        y: i32,
+    },
+    /// Mouse click on the [`Gutter`] at the specified height, recording the clicked line for
+    /// [`Edit::take_gutter_click`] without moving the cursor
+    GutterClick {
+        y: i32,
+    },
+    /// Copy the selection to the [`ClipboardProvider`]. Does nothing if there is no selection or
+    /// no clipboard
+    Copy,
+    /// Copy the selection to the [`ClipboardProvider`] and delete it. Does nothing if there is no
+    /// selection or no clipboard
+    Cut,
+    /// Insert text from the clipboard at the cursor, replacing the selection. The text is not
+    /// passed through the [`InputTransformer`]
+    Paste(String),
+    /// Move the cursor back to the position it was at before the last significant move, see
+    /// [`JumpList`]
+    JumpBack,
+    /// Move the cursor forward again after an [`Action::JumpBack`]
+    JumpForward,
+}
+
+/// Area around the cursor, for placing input method candidate windows
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CursorArea {
+    /// The cursor, with zero width and the height of its line, in buffer coordinates
+    pub cursor: Rect,
+    /// The extents of the glyphs in the visual line containing the cursor, in buffer coordinates
+    pub line: Rect,
+    /// Scale factor from buffer coordinates to device pixels
+    pub scale_factor: f32,
+}
+
+impl CursorArea {
+    /// Get the cursor in device pixels
+    pub fn device_cursor(&self) -> Rect {
+        self.cursor.scale(self.scale_factor)
+    }
+
+    /// Get the extents of the line containing the cursor in device pixels
+    pub fn device_line(&self) -> Rect {
+        self.line.scale(self.scale_factor)
+    }
 }
 
 #[derive(Debug)]
+// Boxing the owned buffer would add an indirection to every buffer access
+#[allow(clippy::large_enum_variant)]
 pub enum BufferRef<'buffer> {
     Owned(Buffer),
     Borrowed(&'buffer mut Buffer),
@@ -150,7 +403,77 @@ pub enum Selection {
     //TODO: Select block
 }
 
+/// A selection handle, used for touch selection
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SelectionHandle {
+    /// Handle at the start of the selection
+    Start,
+    /// Handle at the end of the selection
+    End,
+}
+
+/// Pixel anchors of the selection handles, at the bottom left of each edge of the selection
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SelectionHandles {
+    /// Anchor of the start handle, or None if the start of the selection is not visible
+    pub start: Option<(i32, i32)>,
+    /// Anchor of the end handle, or None if the end of the selection is not visible
+    pub end: Option<(i32, i32)>,
+}
+
+/// How the color of the cursor is chosen, see [`Edit::cursor_color_at`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum CursorColorPolicy {
+    /// Always use the cursor color
+    #[default]
+    Fixed,
+    /// Use the color of the text at the cursor, or the cursor color if it has none
+    FromAttrs,
+    /// Use the inverse of the color of the text at the cursor, so the cursor contrasts with it
+    Inverted,
+}
+
+/// How [`Motion::Up`] and [`Motion::Down`] move the cursor, see [`Edit::set_vertical_motion`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum VerticalMotion {
+    /// Move by visual lines, so wrapped lines take several motions to cross
+    #[default]
+    Visual,
+    /// Move by [`BufferLine`](crate::BufferLine)s, like [`Motion::PreviousLine`] and
+    /// [`Motion::NextLine`]
+    Logical,
+}
+
+/// Options for [`Edit::copy_selection_into`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CopyOptions {
+    /// Line ending to insert between lines
+    pub line_ending: LineEnding,
+    /// Add a line ending after the last line of a [`Selection::Line`]
+    pub line_trailing_newline: bool,
+    /// Copy the rectangle of characters between the columns of the start and end of the
+    /// selection on every selected line, with one row per line
+    pub block: bool,
+}
+
+/// The text under the pointer, see [`Edit::set_hover_position`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hover {
+    /// Cursor at the start of the hovered glyph
+    pub cursor: Cursor,
+    /// Bounds of the hovered glyph, for anchoring tooltips
+    pub glyph: Rect,
+    /// Byte range of the attribute span containing the hovered glyph, or None if it uses the
+    /// default attributes. Use this to underline links on hover
+    pub span: Option<Range<usize>>,
+    /// Metadata of the hovered glyph, see [`crate::Attrs::metadata`]
+    pub metadata: Metadata,
+}
+
 /// A trait to allow easy replacements of [`Editor`], like `SyntaxEditor`
+///
+/// Optional features like multiple cursors, the gutter or the clipboard have default
+/// implementations that leave them unsupported.
 pub trait Edit<'buffer> {
     /// Mutably borrows `self` together with an [`FontSystem`] for more convenient methods
     fn borrow_with<'font_system>(
@@ -212,82 +535,212 @@ pub trait Edit<'buffer> {
     /// Set the current selection position
     fn set_selection(&mut self, selection: Selection);
 
+    /// Move the cursor to `column` characters into `line`, both counting from zero and clamped
+    /// to the text, clearing the selection and scrolling to show the cursor as placed by
+    /// `policy`. Moves to another line are recorded in the [`JumpList`]
+    fn goto(
+        &mut self,
+        font_system: &mut FontSystem,
+        line: usize,
+        column: usize,
+        policy: ScrollPolicy,
+    ) {
+        let cursor = self.with_buffer(|buffer| {
+            let line = line.min(buffer.lines.len().saturating_sub(1));
+            let text = buffer.lines.get(line).map_or("", |line| line.text());
+            let index = text
+                .char_indices()
+                .nth(column)
+                .map_or(text.len(), |(index, _)| index);
+            Cursor::new(line, index)
+        });
+        let old_cursor = self.cursor();
+        if cursor.line != old_cursor.line {
+            if let Some(jump_list) = self.jump_list_mut() {
+                jump_list.push(old_cursor);
+            }
+        }
+        self.set_selection(Selection::None);
+        self.set_cursor(cursor);
+        self.with_buffer_mut(|buffer| {
+            buffer.scroll_to_cursor(font_system, cursor, policy, false);
+        });
+    }
+
     /// Get the bounds of the current selection
     //TODO: will not work with Block select
     fn selection_bounds(&self) -> Option<(Cursor, Cursor)> {
-        self.with_buffer(|buffer| {
-            let cursor = self.cursor();
-            match self.selection() {
-                Selection::None => None,
-                Selection::Normal(select) => match select.line.cmp(&cursor.line) {
-                    cmp::Ordering::Greater => Some((cursor, select)),
-                    cmp::Ordering::Less => Some((select, cursor)),
-                    cmp::Ordering::Equal => {
-                        /* select.line == cursor.line */
-                        if select.index < cursor.index {
-                            Some((select, cursor))
-                        } else {
-                            /* select.index >= cursor.index */
-                            Some((cursor, select))
-                        }
-                    }
-                },
-                Selection::Line(select) => {
-                    let start_line = cmp::min(select.line, cursor.line);
-                    let end_line = cmp::max(select.line, cursor.line);
-                    let end_index = buffer.lines[end_line].text().len();
-                    Some((Cursor::new(start_line, 0), Cursor::new(end_line, end_index)))
-                }
-                Selection::Word(select) => {
-                    let (mut start, mut end) = match select.line.cmp(&cursor.line) {
-                        cmp::Ordering::Greater => (cursor, select),
-                        cmp::Ordering::Less => (select, cursor),
-                        cmp::Ordering::Equal => {
-                            /* select.line == cursor.line */
-                            if select.index < cursor.index {
-                                (select, cursor)
-                            } else {
-                                /* select.index >= cursor.index */
-                                (cursor, select)
-                            }
-                        }
-                    };
-
-                    // Move start to beginning of word
-                    {
-                        let line = &buffer.lines[start.line];
-                        start.index = line
-                            .text()
-                            .unicode_word_indices()
-                            .rev()
-                            .map(|(i, _)| i)
-                            .find(|&i| i < start.index)
-                            .unwrap_or(0);
-                    }
+        self.with_buffer(|buffer| selection_bounds(buffer, self.cursor(), self.selection()))
+    }
 
-                    // Move end to end of word
-                    {
-                        let line = &buffer.lines[end.line];
-                        end.index = line
-                            .text()
-                            .unicode_word_indices()
-                            .map(|(i, word)| i + word.len())
-                            .find(|&i| i > end.index)
-                            .unwrap_or(line.text().len());
-                    }
+    /// Get every cursor and its selection, starting with the primary cursor from [`Self::cursor`]
+    /// and [`Self::selection`]
+    fn cursors(&self) -> Vec<(Cursor, Selection)> {
+        Vec::from([(self.cursor(), self.selection())])
+    }
 
-                    Some((start, end))
-                }
+    /// Add a secondary cursor with a selection. Motions and editing actions are performed at
+    /// every cursor. Clicking, [`Action::Escape`], and applying a change such as when undoing
+    /// return to only the primary cursor
+    fn add_cursor(&mut self, _cursor: Cursor, _selection: Selection) {}
+
+    /// Remove all secondary cursors
+    fn clear_cursors(&mut self) {}
+
+    /// Get the cursor blink state, if the cursor blinks
+    fn cursor_blink(&self) -> Option<&CursorBlink> {
+        None
+    }
+
+    /// Get the cursor blink state mutably, if the cursor blinks
+    fn cursor_blink_mut(&mut self) -> Option<&mut CursorBlink> {
+        None
+    }
+
+    /// Advance the cursor blink timer by `dt`, setting the [`Buffer`] redraw flag if the cursor
+    /// visibility changed
+    fn tick_cursor_blink(&mut self, dt: Duration) {
+        if self
+            .cursor_blink_mut()
+            .map_or(false, |cursor_blink| cursor_blink.tick(dt))
+        {
+            self.set_redraw(true);
+        }
+    }
+
+    /// Get the click counting state, if clicks are counted
+    fn click_chain(&self) -> Option<&ClickChain> {
+        None
+    }
+
+    /// Get the click counting state mutably, if clicks are counted
+    fn click_chain_mut(&mut self) -> Option<&mut ClickChain> {
+        None
+    }
+
+    /// Click at `x`, `y` at `time`, performing [`Action::DoubleClick`] or
+    /// [`Action::TripleClick`] instead of [`Action::Click`] when chained with previous clicks,
+    /// see [`ClickChain::click`]
+    fn click(&mut self, font_system: &mut FontSystem, x: i32, y: i32, time: Duration) {
+        let action = match self.click_chain_mut() {
+            Some(click_chain) => click_chain.click(x, y, time),
+            None => Action::Click { x, y },
+        };
+        self.action(font_system, action);
+    }
+
+    /// Get the policy for choosing the color of the cursor
+    fn cursor_color_policy(&self) -> CursorColorPolicy {
+        CursorColorPolicy::default()
+    }
+
+    /// Set the policy for choosing the color of the cursor
+    fn set_cursor_color_policy(&mut self, _cursor_color_policy: CursorColorPolicy) {}
+
+    /// Get the color to draw the cursor with, given the default `cursor_color` and `text_color`,
+    /// using the text under the cursor, or before it at the end of a line
+    fn cursor_color_at(&self, cursor_color: Color, text_color: Color) -> Color {
+        let policy = self.cursor_color_policy();
+        if policy == CursorColorPolicy::Fixed {
+            return cursor_color;
+        }
+
+        let cursor = self.cursor();
+        let color_opt = self.with_buffer(|buffer| {
+            let line = buffer.lines.get(cursor.line)?;
+            let index = if cursor.index < line.text().len() {
+                cursor.index
+            } else {
+                cursor.index.checked_sub(1)?
+            };
+            line.attrs_list().get_span(index).color_opt
+        });
+        match policy {
+            CursorColorPolicy::Fixed => cursor_color,
+            CursorColorPolicy::FromAttrs => color_opt.unwrap_or(cursor_color),
+            CursorColorPolicy::Inverted => {
+                let color = color_opt.unwrap_or(text_color);
+                Color::rgba(255 - color.r(), 255 - color.g(), 255 - color.b(), color.a())
             }
-        })
+        }
+    }
+
+    /// Get the current overtype setting
+    fn overtype(&self) -> bool {
+        false
+    }
+
+    /// Enable or disable overtype mode, where inserted characters replace the grapheme cluster
+    /// under the cursor, except at the end of a line
+    fn set_overtype(&mut self, _overtype: bool) {}
+
+    /// Get how [`Motion::Up`] and [`Motion::Down`] move the cursor
+    fn vertical_motion(&self) -> VerticalMotion {
+        VerticalMotion::default()
     }
 
+    /// Set how [`Motion::Up`] and [`Motion::Down`] move the cursor, by visual lines or by
+    /// [`BufferLine`](crate::BufferLine)s. The horizontal position of the cursor is kept in both
+    /// cases
+    fn set_vertical_motion(&mut self, _vertical_motion: VerticalMotion) {}
+
+    /// Perform [`Action::Motion`] with `motion`, unless it would move the cursor out of the
+    /// buffer, see [`Buffer::cursor_motion_chained`]. Then the cursor does not move and the
+    /// horizontal position to keep is returned, so the host can move focus to the adjacent editor
+    /// and call [`Self::enter_chained`] on it
+    fn chained_motion(&mut self, font_system: &mut FontSystem, motion: Motion) -> Option<i32> {
+        self.action(font_system, Action::Motion(motion));
+        None
+    }
+
+    /// Move the cursor into the editor from an adjacent one with `motion`, at the horizontal
+    /// position `cursor_x` returned by [`Self::chained_motion`], see [`Buffer::cursor_enter`]
+    fn enter_chained(&mut self, _font_system: &mut FontSystem, _motion: Motion, _cursor_x: i32) {}
+
     /// Get the current automatic indentation setting
     fn auto_indent(&self) -> bool;
 
     /// Enable or disable automatic indentation
     fn set_auto_indent(&mut self, auto_indent: bool);
 
+    /// Get the current automatic bracket and quote pairing setting
+    fn auto_pair(&self) -> bool {
+        false
+    }
+
+    /// Enable or disable automatic bracket and quote pairing. When enabled, inserting an opening
+    /// bracket or quote also inserts its closer, typing a closer skips over an existing one,
+    /// inserting an opener with a selection surrounds it, and backspace between an empty pair
+    /// deletes both
+    fn set_auto_pair(&mut self, _auto_pair: bool) {}
+
+    /// Get the hook that rewrites text typed or pasted into the editor
+    fn input_transformer(&self) -> Option<&dyn InputTransformer> {
+        None
+    }
+
+    /// Set the hook that rewrites text typed or pasted into the editor, `None` disables it
+    fn set_input_transformer(&mut self, _input_transformer: Option<Arc<dyn InputTransformer>>) {}
+
+    /// Get the clipboard used by [`Action::Copy`], [`Action::Cut`], and [`Self::paste`]
+    fn clipboard(&self) -> Option<&dyn ClipboardProvider> {
+        None
+    }
+
+    /// Set the clipboard used by [`Action::Copy`], [`Action::Cut`], and [`Self::paste`], `None`
+    /// disables them
+    fn set_clipboard(&mut self, _clipboard: Option<Arc<dyn ClipboardProvider>>) {}
+
+    /// Paste the text of the [`ClipboardProvider`] with [`Action::Paste`], returning false if
+    /// there is no clipboard or it has no text
+    fn paste(&mut self, font_system: &mut FontSystem) -> bool {
+        let Some(text) = self.clipboard().and_then(ClipboardProvider::text) else {
+            return false;
+        };
+        self.action(font_system, Action::Paste(text));
+        true
+    }
+
     /// Get the current tab width
     fn tab_width(&self) -> u16;
 
@@ -306,6 +759,67 @@ pub trait Edit<'buffer> {
     /// Copy selection
     fn copy_selection(&self) -> Option<String>;
 
+    /// Copy selection into `data` using `options`, returning false if there is no selection.
+    /// Reusing `data` avoids allocating, for example when updating the primary selection
+    fn copy_selection_into(&self, data: &mut String, options: CopyOptions) -> bool {
+        let Some((start, end)) = self.selection_bounds() else {
+            return false;
+        };
+        let line_ending = options.line_ending.as_str();
+        self.with_buffer(|buffer| {
+            if options.block {
+                let column = |cursor: Cursor| {
+                    buffer.lines[cursor.line].text()[..cursor.index]
+                        .chars()
+                        .count()
+                };
+                let (left, right) = (column(start), column(end));
+                let (left, right) = (cmp::min(left, right), cmp::max(left, right));
+                for line_i in start.line..=end.line {
+                    if line_i > start.line {
+                        data.push_str(line_ending);
+                    }
+                    data.extend(
+                        buffer.lines[line_i]
+                            .text()
+                            .chars()
+                            .skip(left)
+                            .take(right - left),
+                    );
+                }
+                return;
+            }
+
+            for (i, text) in buffer.text_between(start, end).enumerate() {
+                if i > 0 {
+                    data.push_str(line_ending);
+                }
+                data.push_str(text);
+            }
+            if options.line_trailing_newline && matches!(self.selection(), Selection::Line(_)) {
+                data.push_str(line_ending);
+            }
+        });
+        true
+    }
+
+    /// Get the current primary selection setting
+    fn primary_selection(&self) -> bool {
+        false
+    }
+
+    /// Enable or disable primary selection, where selected text is copied as soon as it is
+    /// selected, see [`Self::take_primary_selection`]
+    fn set_primary_selection(&mut self, _primary_selection: bool) {}
+
+    /// Get the selected text if primary selection is enabled and the selection changed since the
+    /// last call. Poll this once per frame to update the primary selection of the platform, so
+    /// changes while dragging are coalesced. Clearing the selection does not return anything,
+    /// so the last selected text remains available for pasting
+    fn take_primary_selection(&mut self) -> Option<String> {
+        None
+    }
+
     /// Delete selection, adjusting cursor and returning true if there was a selection
     // Also used by backspace, delete, insert, and enter when there is a selection
     fn delete_selection(&mut self) -> bool;
@@ -318,20 +832,222 @@ pub trait Edit<'buffer> {
         self.set_cursor(new_cursor);
     }
 
+    /// Insert text typed or pasted by the user at the current cursor or replacing the current
+    /// selection, rewritten by the [`InputTransformer`] if there is one.
+    ///
+    /// Any text before the cursor that the transformer replaces is deleted as part of the same
+    /// pending change as the insertion, so both are undone together.
+    fn insert_input(&mut self, data: &str) {
+        self.delete_selection();
+        let cursor = self.cursor();
+        let transform = self.input_transformer().and_then(|input_transformer| {
+            self.with_buffer(|buffer| {
+                let text = buffer.lines.get(cursor.line)?.text();
+                input_transformer.transform(&text[..cursor.index], data)
+            })
+        });
+        match transform {
+            Some(transform) => {
+                let start = Cursor {
+                    index: cursor.index - transform.replace.min(cursor.index),
+                    ..cursor
+                };
+                if start != cursor {
+                    self.delete_range(start, cursor);
+                }
+                let new_cursor = self.insert_at(start, &transform.text, None);
+                self.set_cursor(new_cursor);
+            }
+            None => self.insert_string(data, None),
+        }
+    }
+
+    /// Find the non-overlapping matches of `query` using `options`, as the start and end of each
+    /// match in buffer order. A newline in `query` matches the end of a line, so matches can span
+    /// several lines
+    fn find(&self, query: &str, options: SearchOptions) -> Vec<(Cursor, Cursor)> {
+        self.with_buffer(|buffer| find_matches(buffer, query, options))
+    }
+
+    /// Replace the first match of `query` at or after the start of the selection, or the cursor
+    /// if there is no selection, wrapping around to the start of the buffer. The selection is
+    /// cleared and the cursor is moved to the end of the replacement, so calling this again
+    /// replaces the following match. Returns false if there was no match
+    fn replace_next(&mut self, query: &str, replacement: &str, options: SearchOptions) -> bool {
+        let position = self
+            .selection_bounds()
+            .map_or(self.cursor(), |(start, _)| start);
+        let position = (position.line, position.index);
+        let matches = self.find(query, options);
+        let Some(&(start, end)) = matches
+            .iter()
+            .find(|(start, _)| (start.line, start.index) >= position)
+            .or_else(|| matches.first())
+        else {
+            return false;
+        };
+        self.set_selection(Selection::None);
+        let cursor = replace_range(self, start, end, replacement);
+        self.set_cursor(cursor);
+        true
+    }
+
+    /// Replace every match of `query` with `replacement`, returning the number of matches
+    /// replaced. Cursors and selections stay on the same text, or move to the end of the
+    /// replacement if they were inside a match
+    fn replace_all(&mut self, query: &str, replacement: &str, options: SearchOptions) -> usize {
+        let matches = self.find(query, options);
+        // Replace from the end so the positions of earlier matches stay valid
+        for &(start, end) in matches.iter().rev() {
+            replace_range(self, start, end, replacement);
+        }
+        matches.len()
+    }
+
     /// Apply a change
     fn apply_change(&mut self, change: &Change) -> bool;
 
+    /// Apply changes made to the text elsewhere, like the result of [`diff`], as one change in
+    /// the [`EditHistory`]
+    ///
+    /// Unlike [`Self::apply_change`], the cursors, selections and marks are moved to stay on
+    /// their text. Returns false if a change is pending, or if the editor does not support it.
+    fn apply_changes(&mut self, _changes: &[Change]) -> bool {
+        false
+    }
+
     /// Start collecting change
     fn start_change(&mut self);
 
-    /// Get completed change
+    /// Get completed change, recording it in the [`EditHistory`]
     fn finish_change(&mut self) -> Option<Change>;
 
+    /// Get the undo and redo history, if changes are recorded
+    fn history(&self) -> Option<&EditHistory> {
+        None
+    }
+
+    /// Get the undo and redo history mutably, if changes are recorded
+    fn history_mut(&mut self) -> Option<&mut EditHistory> {
+        None
+    }
+
+    /// Undo the last change in the [`EditHistory`], returning false if there was nothing to undo
+    /// or a change is pending
+    fn undo(&mut self) -> bool {
+        let Some(change) = self.history_mut().and_then(|history| history.undo()) else {
+            return false;
+        };
+        if self.apply_change(&change) {
+            true
+        } else {
+            // Keep the change on the undo stack
+            if let Some(history) = self.history_mut() {
+                history.redo();
+            }
+            false
+        }
+    }
+
+    /// Redo the last undone change in the [`EditHistory`], returning false if there was nothing
+    /// to redo or a change is pending
+    fn redo(&mut self) -> bool {
+        let Some(change) = self.history_mut().and_then(|history| history.redo()) else {
+            return false;
+        };
+        if self.apply_change(&change) {
+            true
+        } else {
+            // Keep the change on the redo stack
+            if let Some(history) = self.history_mut() {
+                history.undo();
+            }
+            false
+        }
+    }
+
     /// Perform an [Action] on the editor
     fn action(&mut self, font_system: &mut FontSystem, action: Action);
 
     /// Get X and Y position of the top left corner of the cursor
     fn cursor_position(&self) -> Option<(i32, i32)>;
+
+    /// Get the cursor and the line containing it, in buffer coordinates and in device pixels
+    /// using `scale_factor`. Returns None if the cursor is not visible
+    fn cursor_area(&self, _scale_factor: f32) -> Option<CursorArea> {
+        None
+    }
+
+    /// Get the text under the pointer, updated by [`Self::set_hover_position`]
+    fn hover(&self) -> Option<&Hover> {
+        None
+    }
+
+    /// Set the pointer position, or None if the pointer left the editor, updating
+    /// [`Self::hover`] and setting the [`Buffer`] redraw flag if it changed. Call again after
+    /// scrolling or editing to refresh the hover
+    fn set_hover_position(&mut self, _position: Option<(i32, i32)>) {}
+
+    /// Get the pixel anchors of the selection handles, or None if there is no selection
+    fn selection_handles(&self) -> Option<SelectionHandles> {
+        None
+    }
+
+    /// Get the gutter, if there is one
+    fn gutter(&self) -> Option<&Gutter> {
+        None
+    }
+
+    /// Get the gutter mutably, to change its markers
+    fn gutter_mut(&mut self) -> Option<&mut Gutter> {
+        None
+    }
+
+    /// Set the gutter, or None to remove it. Its markers follow their lines as text is edited
+    fn set_gutter(&mut self, _gutter: Option<Gutter>) {}
+
+    /// Take the line clicked with the last [`Action::GutterClick`], if it hit a line
+    fn take_gutter_click(&mut self) -> Option<usize> {
+        None
+    }
+
+    /// Get the positions recorded before significant cursor moves, if there is a jump list, see
+    /// [`Action::JumpBack`]
+    fn jump_list(&self) -> Option<&JumpList> {
+        None
+    }
+
+    /// Get the jump list mutably, to record jumps made with [`Self::set_cursor`]
+    fn jump_list_mut(&mut self) -> Option<&mut JumpList> {
+        None
+    }
+
+    /// Get the gutter cells of the visible lines, empty if there is no gutter
+    fn gutter_cells(&self) -> Vec<GutterCell> {
+        let Some(gutter) = self.gutter() else {
+            return Vec::new();
+        };
+        self.with_buffer(|buffer| {
+            buffer
+                .layout_runs()
+                .map(|run| {
+                    let first = run.layout_i == 0;
+                    GutterCell {
+                        line_i: run.line_i,
+                        layout_i: run.layout_i,
+                        y: run.line_top,
+                        height: run.line_height,
+                        line_number: first.then_some(run.line_i + 1),
+                        markers: if first {
+                            gutter.markers(run.line_i).to_vec()
+                        } else {
+                            Vec::new()
+                        },
+                    }
+                })
+                .collect()
+        })
+    }
 }
 
 impl<'font_system, 'buffer, E: Edit<'buffer>> BorrowedWithFontSystem<'font_system, E> {
@@ -349,11 +1065,21 @@ impl<'font_system, 'buffer, E: Edit<'buffer>> BorrowedWithFontSystem<'font_syste
         })
     }
 
+    /// Click at `x`, `y` at `time`, chaining with previous clicks
+    pub fn click(&mut self, x: i32, y: i32, time: Duration) {
+        self.inner.click(self.font_system, x, y, time);
+    }
+
     /// Set the current tab width. A `tab_width` of 0 is not allowed, and will be ignored
     pub fn set_tab_width(&mut self, tab_width: u16) {
         self.inner.set_tab_width(self.font_system, tab_width);
     }
 
+    /// Move the cursor to `column` characters into `line`, see [`Edit::goto`]
+    pub fn goto(&mut self, line: usize, column: usize, policy: ScrollPolicy) {
+        self.inner.goto(self.font_system, line, column, policy);
+    }
+
     /// Shape lines until scroll, after adjusting scroll if the cursor moved
     pub fn shape_as_needed(&mut self, prune: bool) {
         self.inner.shape_as_needed(self.font_system, prune);
@@ -0,0 +1,137 @@
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::{fmt, ops::Range};
+
+use crate::{Buffer, BufferLine, Cursor};
+
+/// Finds the matches of a search pattern in a line of text, such as the searches of
+/// `ViEditor`
+pub trait SearchMatcher: fmt::Debug + Send + Sync {
+    /// Get the byte ranges of the non-overlapping matches of `pattern` in `text`, in order. Empty
+    /// matches are ignored by the callers
+    fn find(&self, pattern: &str, text: &str) -> Vec<Range<usize>>;
+}
+
+/// A [`SearchMatcher`] matching the pattern as plain text
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct PlainMatcher;
+
+impl SearchMatcher for PlainMatcher {
+    fn find(&self, pattern: &str, text: &str) -> Vec<Range<usize>> {
+        if pattern.is_empty() {
+            return Vec::new();
+        }
+        text.match_indices(pattern)
+            .map(|(i, m)| i..i + m.len())
+            .collect()
+    }
+}
+
+/// A [`SearchMatcher`] matching the pattern as a regular expression using the `regex` crate.
+/// Patterns that fail to compile, such as while they are being typed, match nothing
+#[cfg(feature = "regex")]
+#[derive(Debug, Default)]
+pub struct RegexMatcher {
+    /// The last pattern and its compiled form
+    cache: std::sync::Mutex<Option<(String, Option<regex::Regex>)>>,
+}
+
+#[cfg(feature = "regex")]
+impl SearchMatcher for RegexMatcher {
+    fn find(&self, pattern: &str, text: &str) -> Vec<Range<usize>> {
+        let mut cache = self
+            .cache
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if cache.as_ref().map_or(true, |(cached, _)| cached != pattern) {
+            *cache = Some((pattern.to_string(), regex::Regex::new(pattern).ok()));
+        }
+        match cache.as_ref().and_then(|(_, regex)| regex.as_ref()) {
+            Some(regex) => regex.find_iter(text).map(|m| m.range()).collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Options for [`crate::Edit::find`] and the replace methods
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SearchOptions {
+    /// Match letters regardless of case
+    pub case_insensitive: bool,
+    /// Only match whole words, where the text before and after the match is not a letter, digit,
+    /// or underscore
+    pub whole_word: bool,
+}
+
+/// Find the non-overlapping matches of `query` in `buffer`, see [`crate::Edit::find`]
+pub(crate) fn find_matches(
+    buffer: &Buffer,
+    query: &str,
+    options: SearchOptions,
+) -> Vec<(Cursor, Cursor)> {
+    let mut matches = Vec::new();
+    if query.is_empty() {
+        return matches;
+    }
+
+    let lines = &buffer.lines;
+    let mut start = Cursor::new(0, 0);
+    while start.line < lines.len() {
+        if let Some(end) = match_at(lines, start, query, options.case_insensitive) {
+            if !options.whole_word || is_whole_word(lines, start, end) {
+                matches.push((start, end));
+                start = end;
+                continue;
+            }
+        }
+
+        // Try again at the next character, or the start of the next line
+        match lines[start.line].text()[start.index..].chars().next() {
+            Some(c) => start.index += c.len_utf8(),
+            None => start = Cursor::new(start.line + 1, 0),
+        }
+    }
+    matches
+}
+
+/// Get the end of the match of `query` starting at `start`, if there is one
+fn match_at(
+    lines: &[BufferLine],
+    start: Cursor,
+    query: &str,
+    case_insensitive: bool,
+) -> Option<Cursor> {
+    let mut line_i = start.line;
+    let mut index = start.index;
+    for query_c in query.chars() {
+        let c = match lines[line_i].text()[index..].chars().next() {
+            Some(c) => {
+                index += c.len_utf8();
+                c
+            }
+            None => {
+                // Line breaks match newlines
+                if line_i + 1 >= lines.len() {
+                    return None;
+                }
+                line_i += 1;
+                index = 0;
+                '\n'
+            }
+        };
+        let matches =
+            c == query_c || (case_insensitive && c.to_lowercase().eq(query_c.to_lowercase()));
+        if !matches {
+            return None;
+        }
+    }
+    Some(Cursor::new(line_i, index))
+}
+
+/// Check that the match between `start` and `end` is not part of a larger word
+fn is_whole_word(lines: &[BufferLine], start: Cursor, end: Cursor) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    let before = lines[start.line].text()[..start.index].chars().next_back();
+    let after = lines[end.line].text()[end.index..].chars().next();
+    !before.map_or(false, is_word) && !after.map_or(false, is_word)
+}
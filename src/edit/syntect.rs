@@ -1,5 +1,7 @@
+use alloc::sync::Arc;
 #[cfg(not(feature = "std"))]
 use alloc::{string::String, vec::Vec};
+use core::ops::Range;
 #[cfg(feature = "std")]
 use std::{fs, io, path::Path};
 use syntect::highlighting::{
@@ -7,9 +9,13 @@ use syntect::highlighting::{
 };
 use syntect::parsing::{ParseState, ScopeStack, SyntaxReference, SyntaxSet};
 
+#[cfg(feature = "std")]
+use super::diff::{buffer_lines, diff_lines, diff_ranges, text_lines};
 use crate::{
-    Action, AttrsList, BorrowedWithFontSystem, BufferRef, Change, Color, Cursor, Edit, Editor,
-    FontSystem, Selection, Shaping, Style, Weight,
+    Action, AttrsList, BorrowedWithFontSystem, BufferRef, Change, ClickChain, ClipboardProvider,
+    Color, Cursor, CursorArea, CursorBlink, CursorColorPolicy, Edit, EditHistory, Editor,
+    FontSystem, Gutter, Hover, InputTransformer, JumpList, Motion, Selection, SelectionHandles,
+    Shaping, Style, TextDecoration, VerticalMotion, Weight,
 };
 
 pub use syntect::highlighting::Theme as SyntaxTheme;
@@ -31,6 +37,16 @@ impl SyntaxSystem {
     }
 }
 
+/// The result of [`SyntaxEditor::reload_from`]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Reload {
+    /// Number of changed regions that were replaced
+    pub changes: usize,
+    /// Lines of the reloaded text, in order, where changed regions replaced edits that were not
+    /// saved. Undoing the reload restores these edits
+    pub conflicts: Vec<Range<usize>>,
+}
+
 /// A wrapper of [`Editor`] with syntax highlighting provided by [`SyntaxSystem`]
 #[derive(Debug)]
 pub struct SyntaxEditor<'syntax_system, 'buffer> {
@@ -147,6 +163,79 @@ impl<'syntax_system, 'buffer> SyntaxEditor<'syntax_system, 'buffer> {
         Ok(())
     }
 
+    /// Reload the text from a file that changed on disk, keeping attributes, cursors, marks and
+    /// the undo history
+    ///
+    /// Only the regions that differ, see [`crate::diff`], are replaced, as a single change that
+    /// can be undone, and only those lines are highlighted again. Edits that were not saved are
+    /// kept if the file did not change around them, otherwise the file wins and the region is
+    /// reported in [`Reload::conflicts`]. If no edits are kept, the reloaded text is marked as
+    /// saved in the [`EditHistory`].
+    ///
+    /// ## Errors
+    ///
+    /// Returns an [`io::Error`] if reading the file fails, or if a change is pending
+    #[cfg(feature = "std")]
+    pub fn reload_from<P: AsRef<Path>>(&mut self, path: P) -> io::Result<Reload> {
+        let text = fs::read_to_string(path)?;
+        let current = self.editor.with_buffer(buffer_lines);
+        let disk = text_lines(&text);
+        let base_opt = self
+            .editor
+            .history()
+            .and_then(|history| history.unsaved_changes())
+            .and_then(|unsaved| unapply_lines(&current, &unsaved));
+        let (merged, conflicts) = match base_opt {
+            Some(base) => merge_lines(&base, &current, &disk),
+            None => {
+                // Without the saved text, every region that differs replaces an edit
+                let conflicts = diff_ranges(&current, &disk)
+                    .into_iter()
+                    .map(|(_, range)| range)
+                    .collect();
+                (disk.clone(), conflicts)
+            }
+        };
+
+        let changes = diff_lines(&current, &merged);
+        if !self.editor.apply_changes(&changes) {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "cannot reload while a change is pending",
+            ));
+        }
+        if let Some(history) = self.editor.history_mut() {
+            if merged == disk {
+                history.set_saved();
+            } else {
+                // The kept edits are not on disk, and undoing them no longer returns to the file
+                history.clear_saved();
+            }
+        }
+
+        // Keep the highlighting state of unchanged lines, changed lines were reset by the editor
+        for item in changes.iter().flat_map(|change| change.items.iter()) {
+            let start = item.start.line;
+            if start >= self.syntax_cache.len() {
+                continue;
+            }
+            if item.insert {
+                let state = self.syntax_cache[start].clone();
+                let lines = item.end.line - start;
+                self.syntax_cache
+                    .splice(start..start, core::iter::repeat(state).take(lines));
+            } else {
+                let end = item.end.line.min(self.syntax_cache.len());
+                self.syntax_cache.drain(start..end);
+            }
+        }
+
+        Ok(Reload {
+            changes: changes.len(),
+            conflicts,
+        })
+    }
+
     /// Set syntax highlighting by file extension
     pub fn syntax_by_extension(&mut self, extension: &str) {
         self.syntax = match self
@@ -191,6 +280,15 @@ impl<'syntax_system, 'buffer> SyntaxEditor<'syntax_system, 'buffer> {
         }
     }
 
+    /// Get the color of search match highlights, or the selection color if the theme has none
+    pub fn find_highlight_color(&self) -> Color {
+        if let Some(some) = self.theme.settings.find_highlight {
+            Color::rgba(some.r, some.g, some.b, some.a)
+        } else {
+            self.selection_color()
+        }
+    }
+
     /// Get the default selection color
     pub fn selection_color(&self) -> Color {
         if let Some(some) = self.theme.settings.selection {
@@ -260,6 +358,66 @@ impl<'syntax_system, 'buffer> Edit<'buffer> for SyntaxEditor<'syntax_system, 'bu
         self.editor.set_selection(selection);
     }
 
+    fn cursors(&self) -> Vec<(Cursor, Selection)> {
+        self.editor.cursors()
+    }
+
+    fn add_cursor(&mut self, cursor: Cursor, selection: Selection) {
+        self.editor.add_cursor(cursor, selection);
+    }
+
+    fn clear_cursors(&mut self) {
+        self.editor.clear_cursors();
+    }
+
+    fn cursor_blink(&self) -> Option<&CursorBlink> {
+        self.editor.cursor_blink()
+    }
+
+    fn cursor_blink_mut(&mut self) -> Option<&mut CursorBlink> {
+        self.editor.cursor_blink_mut()
+    }
+
+    fn click_chain(&self) -> Option<&ClickChain> {
+        self.editor.click_chain()
+    }
+
+    fn click_chain_mut(&mut self) -> Option<&mut ClickChain> {
+        self.editor.click_chain_mut()
+    }
+
+    fn cursor_color_policy(&self) -> CursorColorPolicy {
+        self.editor.cursor_color_policy()
+    }
+
+    fn set_cursor_color_policy(&mut self, cursor_color_policy: CursorColorPolicy) {
+        self.editor.set_cursor_color_policy(cursor_color_policy);
+    }
+
+    fn overtype(&self) -> bool {
+        self.editor.overtype()
+    }
+
+    fn set_overtype(&mut self, overtype: bool) {
+        self.editor.set_overtype(overtype);
+    }
+
+    fn vertical_motion(&self) -> VerticalMotion {
+        self.editor.vertical_motion()
+    }
+
+    fn set_vertical_motion(&mut self, vertical_motion: VerticalMotion) {
+        self.editor.set_vertical_motion(vertical_motion);
+    }
+
+    fn chained_motion(&mut self, font_system: &mut FontSystem, motion: Motion) -> Option<i32> {
+        self.editor.chained_motion(font_system, motion)
+    }
+
+    fn enter_chained(&mut self, font_system: &mut FontSystem, motion: Motion, cursor_x: i32) {
+        self.editor.enter_chained(font_system, motion, cursor_x);
+    }
+
     fn auto_indent(&self) -> bool {
         self.editor.auto_indent()
     }
@@ -268,6 +426,30 @@ impl<'syntax_system, 'buffer> Edit<'buffer> for SyntaxEditor<'syntax_system, 'bu
         self.editor.set_auto_indent(auto_indent);
     }
 
+    fn auto_pair(&self) -> bool {
+        self.editor.auto_pair()
+    }
+
+    fn set_auto_pair(&mut self, auto_pair: bool) {
+        self.editor.set_auto_pair(auto_pair);
+    }
+
+    fn input_transformer(&self) -> Option<&dyn InputTransformer> {
+        self.editor.input_transformer()
+    }
+
+    fn set_input_transformer(&mut self, input_transformer: Option<Arc<dyn InputTransformer>>) {
+        self.editor.set_input_transformer(input_transformer);
+    }
+
+    fn clipboard(&self) -> Option<&dyn ClipboardProvider> {
+        self.editor.clipboard()
+    }
+
+    fn set_clipboard(&mut self, clipboard: Option<Arc<dyn ClipboardProvider>>) {
+        self.editor.set_clipboard(clipboard);
+    }
+
     fn tab_width(&self) -> u16 {
         self.editor.tab_width()
     }
@@ -282,7 +464,7 @@ impl<'syntax_system, 'buffer> Edit<'buffer> for SyntaxEditor<'syntax_system, 'bu
 
         let cursor = self.cursor();
         self.editor.with_buffer_mut(|buffer| {
-            let metrics = buffer.metrics();
+            let metrics = buffer.scaled_metrics();
             let scroll = buffer.scroll();
             let scroll_end = scroll.vertical + buffer.size().1.unwrap_or(f32::INFINITY);
             let mut total_height = 0.0;
@@ -301,8 +483,7 @@ impl<'syntax_system, 'buffer> Edit<'buffer> for SyntaxEditor<'syntax_system, 'bu
                         match buffer.line_layout(font_system, line_i) {
                             Some(layout_lines) => {
                                 for layout_line in layout_lines.iter() {
-                                    total_height +=
-                                        layout_line.line_height_opt.unwrap_or(metrics.line_height);
+                                    total_height += layout_line.height(metrics.line_height);
                                 }
                             }
                             None => {
@@ -334,14 +515,13 @@ impl<'syntax_system, 'buffer> Edit<'buffer> for SyntaxEditor<'syntax_system, 'bu
                 let attrs = line.attrs_list().defaults();
                 let mut attrs_list = AttrsList::new(attrs);
                 for (style, _, range) in ranges {
-                    let span_attrs = attrs
+                    let mut span_attrs = attrs
                         .color(Color::rgba(
                             style.foreground.r,
                             style.foreground.g,
                             style.foreground.b,
                             style.foreground.a,
                         ))
-                        //TODO: background
                         .style(if style.font_style.contains(FontStyle::ITALIC) {
                             Style::Italic
                         } else {
@@ -351,7 +531,21 @@ impl<'syntax_system, 'buffer> Edit<'buffer> for SyntaxEditor<'syntax_system, 'bu
                             Weight::BOLD
                         } else {
                             Weight::NORMAL
-                        }); //TODO: underline
+                        })
+                        .decoration(if style.font_style.contains(FontStyle::UNDERLINE) {
+                            TextDecoration::UNDERLINE
+                        } else {
+                            TextDecoration::empty()
+                        });
+                    // The theme background is drawn by the editor, only differing backgrounds are kept
+                    if Some(style.background) != self.theme.settings.background {
+                        span_attrs = span_attrs.background_color(Color::rgba(
+                            style.background.r,
+                            style.background.g,
+                            style.background.b,
+                            style.background.a,
+                        ));
+                    }
                     if span_attrs != attrs {
                         attrs_list.add_span(range, span_attrs);
                     }
@@ -365,8 +559,7 @@ impl<'syntax_system, 'buffer> Edit<'buffer> for SyntaxEditor<'syntax_system, 'bu
                     match buffer.line_layout(font_system, line_i) {
                         Some(layout_lines) => {
                             for layout_line in layout_lines.iter() {
-                                total_height +=
-                                    layout_line.line_height_opt.unwrap_or(metrics.line_height);
+                                total_height += layout_line.height(metrics.line_height);
                             }
                         }
                         None => {
@@ -415,6 +608,18 @@ impl<'syntax_system, 'buffer> Edit<'buffer> for SyntaxEditor<'syntax_system, 'bu
         self.editor.copy_selection()
     }
 
+    fn primary_selection(&self) -> bool {
+        self.editor.primary_selection()
+    }
+
+    fn set_primary_selection(&mut self, primary_selection: bool) {
+        self.editor.set_primary_selection(primary_selection);
+    }
+
+    fn take_primary_selection(&mut self) -> Option<String> {
+        self.editor.take_primary_selection()
+    }
+
     fn delete_selection(&mut self) -> bool {
         self.editor.delete_selection()
     }
@@ -423,6 +628,10 @@ impl<'syntax_system, 'buffer> Edit<'buffer> for SyntaxEditor<'syntax_system, 'bu
         self.editor.apply_change(change)
     }
 
+    fn apply_changes(&mut self, changes: &[Change]) -> bool {
+        self.editor.apply_changes(changes)
+    }
+
     fn start_change(&mut self) {
         self.editor.start_change();
     }
@@ -431,6 +640,14 @@ impl<'syntax_system, 'buffer> Edit<'buffer> for SyntaxEditor<'syntax_system, 'bu
         self.editor.finish_change()
     }
 
+    fn history(&self) -> Option<&EditHistory> {
+        self.editor.history()
+    }
+
+    fn history_mut(&mut self) -> Option<&mut EditHistory> {
+        self.editor.history_mut()
+    }
+
     fn action(&mut self, font_system: &mut FontSystem, action: Action) {
         self.editor.action(font_system, action);
     }
@@ -438,6 +655,46 @@ impl<'syntax_system, 'buffer> Edit<'buffer> for SyntaxEditor<'syntax_system, 'bu
     fn cursor_position(&self) -> Option<(i32, i32)> {
         self.editor.cursor_position()
     }
+
+    fn cursor_area(&self, scale_factor: f32) -> Option<CursorArea> {
+        self.editor.cursor_area(scale_factor)
+    }
+
+    fn hover(&self) -> Option<&Hover> {
+        self.editor.hover()
+    }
+
+    fn set_hover_position(&mut self, position: Option<(i32, i32)>) {
+        self.editor.set_hover_position(position);
+    }
+
+    fn selection_handles(&self) -> Option<SelectionHandles> {
+        self.editor.selection_handles()
+    }
+
+    fn gutter(&self) -> Option<&Gutter> {
+        self.editor.gutter()
+    }
+
+    fn gutter_mut(&mut self) -> Option<&mut Gutter> {
+        self.editor.gutter_mut()
+    }
+
+    fn set_gutter(&mut self, gutter: Option<Gutter>) {
+        self.editor.set_gutter(gutter);
+    }
+
+    fn take_gutter_click(&mut self) -> Option<usize> {
+        self.editor.take_gutter_click()
+    }
+
+    fn jump_list(&self) -> Option<&JumpList> {
+        self.editor.jump_list()
+    }
+
+    fn jump_list_mut(&mut self) -> Option<&mut JumpList> {
+        self.editor.jump_list_mut()
+    }
 }
 
 impl<'font_system, 'syntax_system, 'buffer>
@@ -461,3 +718,99 @@ impl<'font_system, 'syntax_system, 'buffer>
         self.inner.draw(self.font_system, cache, f);
     }
 }
+
+/// Undo `changes` on `lines`, returning None if they do not apply
+#[cfg(feature = "std")]
+fn unapply_lines(lines: &[String], changes: &[Change]) -> Option<Vec<String>> {
+    let mut text = lines.concat();
+    for change in changes.iter().rev() {
+        let mut change = change.clone();
+        change.reverse();
+        for item in change.items.iter() {
+            let offset = |text: &str, cursor: Cursor| {
+                let line_start: usize = text
+                    .split_inclusive('\n')
+                    .take(cursor.line)
+                    .map(str::len)
+                    .sum();
+                let offset = line_start + cursor.index;
+                text.is_char_boundary(offset).then_some(offset)
+            };
+            let start = offset(&text, item.start)?;
+            if item.insert {
+                text.insert_str(start, &item.text);
+            } else {
+                let end = offset(&text, item.end)?;
+                text.replace_range(start..end, "");
+            }
+        }
+    }
+    let mut lines: Vec<String> = text.split('\n').map(|line| format!("{line}\n")).collect();
+    if let Some(last) = lines.last_mut() {
+        last.pop();
+    }
+    Some(lines)
+}
+
+/// Merge the edits from `base` to `ours` with the edits from `base` to `theirs`, returning the
+/// merged lines and the lines of the merged text where both edited the same region, which take
+/// the lines of `theirs`
+#[cfg(feature = "std")]
+fn merge_lines(
+    base: &[String],
+    ours: &[String],
+    theirs: &[String],
+) -> (Vec<String>, Vec<Range<usize>>) {
+    let mut ours_hunks = diff_ranges(base, ours).into_iter().peekable();
+    let mut theirs_hunks = diff_ranges(base, theirs).into_iter().peekable();
+    let mut merged = Vec::new();
+    let mut conflicts = Vec::new();
+    let mut base_i = 0;
+    // Difference between the line in ours or theirs and the line in base after the last hunk
+    let (mut ours_offset, mut theirs_offset) = (0isize, 0isize);
+    let shift = |line: usize, offset: isize| (line as isize + offset) as usize;
+    loop {
+        let start = match (ours_hunks.peek(), theirs_hunks.peek()) {
+            (Some(ours), Some(theirs)) => ours.0.start.min(theirs.0.start),
+            (Some(hunk), None) | (None, Some(hunk)) => hunk.0.start,
+            (None, None) => break,
+        };
+        merged.extend_from_slice(&base[base_i..start]);
+
+        // Group the hunks that overlap or start at the same line
+        let ours_start = shift(start, ours_offset);
+        let theirs_start = shift(start, theirs_offset);
+        let (mut end, mut edited_ours, mut edited_theirs) = (start, false, false);
+        loop {
+            if let Some(hunk) =
+                ours_hunks.next_if(|hunk| hunk.0.start < end || hunk.0.start == start)
+            {
+                end = end.max(hunk.0.end);
+                ours_offset = hunk.1.end as isize - hunk.0.end as isize;
+                edited_ours = true;
+            } else if let Some(hunk) =
+                theirs_hunks.next_if(|hunk| hunk.0.start < end || hunk.0.start == start)
+            {
+                end = end.max(hunk.0.end);
+                theirs_offset = hunk.1.end as isize - hunk.0.end as isize;
+                edited_theirs = true;
+            } else {
+                break;
+            }
+        }
+        let ours_lines = &ours[ours_start..shift(end, ours_offset)];
+        let theirs_lines = &theirs[theirs_start..shift(end, theirs_offset)];
+
+        if edited_ours && edited_theirs && ours_lines != theirs_lines {
+            conflicts.push(merged.len()..merged.len() + theirs_lines.len());
+        }
+        if edited_theirs {
+            merged.extend_from_slice(theirs_lines);
+        } else {
+            merged.extend_from_slice(ours_lines);
+        }
+        base_i = end;
+    }
+    merged.extend_from_slice(&base[base_i..]);
+    (merged, conflicts)
+}
@@ -0,0 +1,117 @@
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+use core::fmt;
+
+/// A rewrite of text inserted by the user, produced by an [`InputTransformer`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InputTransform {
+    /// Number of bytes before the cursor to replace, must end on a character boundary
+    pub replace: usize,
+    /// Text to insert in place of the replaced bytes and the input
+    pub text: String,
+}
+
+/// A hook that rewrites text typed or pasted into an editor, see [`crate::Edit::insert_input`]
+pub trait InputTransformer: fmt::Debug + Send + Sync {
+    /// Transform `input`, given the text of the line `before` the cursor. Returns `None` to
+    /// insert `input` unchanged
+    fn transform(&self, before: &str, input: &str) -> Option<InputTransform>;
+}
+
+/// An [`InputTransformer`] for typographic punctuation
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SmartPunctuation {
+    /// Replace straight quotes with opening or closing curly quotes
+    pub quotes: bool,
+    /// Replace two hyphens with an em dash
+    pub dashes: bool,
+    /// Replace two spaces after a word with a period and a space
+    pub double_space_period: bool,
+}
+
+impl Default for SmartPunctuation {
+    fn default() -> Self {
+        Self {
+            quotes: true,
+            dashes: true,
+            double_space_period: true,
+        }
+    }
+}
+
+impl InputTransformer for SmartPunctuation {
+    fn transform(&self, before: &str, input: &str) -> Option<InputTransform> {
+        let mut replace = 0;
+        let mut text = String::with_capacity(input.len());
+        for c in input.chars() {
+            // Text before c is the unreplaced part of before followed by the transformed input
+            let (prev, prev2) = {
+                let mut chars = text
+                    .chars()
+                    .rev()
+                    .chain(before[..before.len() - replace].chars().rev());
+                (chars.next(), chars.next())
+            };
+            // A quote opens at the start of the line or after a space, bracket, or dash
+            let opens = prev.map_or(true, |prev| {
+                prev.is_whitespace() || "([{<\u{201C}\u{2018}\u{2013}\u{2014}".contains(prev)
+            });
+
+            match c {
+                '"' if self.quotes => text.push(if opens { '\u{201C}' } else { '\u{201D}' }),
+                '\'' if self.quotes => text.push(if opens { '\u{2018}' } else { '\u{2019}' }),
+                '-' if self.dashes && prev == Some('-') => {
+                    pop_char(before, &mut replace, &mut text);
+                    text.push('\u{2014}');
+                }
+                ' ' if self.double_space_period
+                    && prev == Some(' ')
+                    && prev2.map_or(false, char::is_alphanumeric) =>
+                {
+                    pop_char(before, &mut replace, &mut text);
+                    text.push_str(". ");
+                }
+                _ => text.push(c),
+            }
+        }
+
+        if replace == 0 && text == input {
+            None
+        } else {
+            Some(InputTransform { replace, text })
+        }
+    }
+}
+
+/// Remove the last character of the transformed text, replacing it in `before` if `text` is empty
+fn pop_char(before: &str, replace: &mut usize, text: &mut String) {
+    if text.pop().is_none() {
+        if let Some(c) = before[..before.len() - *replace].chars().next_back() {
+            *replace += c.len_utf8();
+        }
+    }
+}
+
+#[test]
+fn test_smart_punctuation() {
+    let smart = SmartPunctuation::default();
+    let transform = |before, input| {
+        smart
+            .transform(before, input)
+            .map(|transform| (transform.replace, transform.text))
+    };
+    assert_eq!(transform("", "\""), Some((0, "\u{201C}".into())));
+    assert_eq!(
+        transform("say \u{201C}hi", "\""),
+        Some((0, "\u{201D}".into()))
+    );
+    assert_eq!(transform("don", "'"), Some((0, "\u{2019}".into())));
+    assert_eq!(transform("a -", "-"), Some((1, "\u{2014}".into())));
+    assert_eq!(transform("end ", " "), Some((1, ". ".into())));
+    assert_eq!(transform("end. ", " "), None);
+    assert_eq!(
+        transform("", "a--b \"c\""),
+        Some((0, "a\u{2014}b \u{201C}c\u{201D}".into()))
+    );
+    assert_eq!(transform("abc", "d"), None);
+}
@@ -1,90 +1,65 @@
-use alloc::{collections::BTreeMap, string::String};
-use core::cmp;
-use modit::{Event, Key, Parser, TextObject, WordIter};
-use unicode_segmentation::UnicodeSegmentation;
-
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use alloc::{collections::BTreeMap, string::String, sync::Arc};
+use core::{cmp, ops::Range};
+use modit::{Event, Key, Parser, TextObject, ViCmd, WordIter};
+
+#[cfg(feature = "swash")]
+use crate::DecorationKind;
 use crate::{
-    Action, AttrsList, BorrowedWithFontSystem, BufferRef, Change, Color, Cursor, Edit, FontSystem,
-    Motion, Selection, SyntaxEditor, SyntaxTheme,
+    Action, AttrsList, BorrowedWithFontSystem, BufferRef, Change, ClickChain, ClipboardProvider,
+    Color, Cursor, CursorArea, CursorBlink, CursorColorPolicy, Edit, EditHistory, FontSystem,
+    Gutter, Hover, InputTransformer, JumpList, Motion, PlainMatcher, SearchMatcher, Selection,
+    SelectionHandles, SyntaxEditor, SyntaxTheme, VerticalMotion,
 };
 
 pub use modit::{ViMode, ViParser};
 
-fn undo_2_action<'buffer, E: Edit<'buffer>>(
-    editor: &mut E,
-    action: cosmic_undo_2::Action<&Change>,
-) {
-    match action {
-        cosmic_undo_2::Action::Do(change) => {
-            editor.apply_change(change);
-        }
-        cosmic_undo_2::Action::Undo(change) => {
-            //TODO: make this more efficient
-            let mut reversed = change.clone();
-            reversed.reverse();
-            editor.apply_change(&reversed);
-        }
+fn finish_change<'buffer, E: Edit<'buffer>>(editor: &mut E, changed: &mut bool) -> Option<Change> {
+    //TODO: join changes together
+    let change = editor.finish_change()?;
+    if !change.items.is_empty() {
+        *changed = is_unsaved(editor);
     }
+    Some(change)
 }
 
-fn finish_change<'buffer, E: Edit<'buffer>>(
-    editor: &mut E,
-    commands: &mut cosmic_undo_2::Commands<Change>,
-    changed: &mut bool,
-    pivot: Option<usize>,
-) -> Option<Change> {
-    //TODO: join changes together
-    match editor.finish_change() {
-        Some(change) => {
-            if !change.items.is_empty() {
-                commands.push(change.clone());
-                *changed = eval_changed(commands, pivot);
-            }
-            Some(change)
-        }
-        None => None,
-    }
+/// Check if the text differs from the save point, always true without an [`EditHistory`]
+fn is_unsaved<'buffer, E: Edit<'buffer>>(editor: &E) -> bool {
+    editor.history().map_or(true, |history| !history.is_saved())
 }
 
-/// Evaluate if an [`ViEditor`] changed based on its last saved state.
-fn eval_changed(commands: &cosmic_undo_2::Commands<Change>, pivot: Option<usize>) -> bool {
-    // Editors are considered modified if the current change index is unequal to the last
-    // saved index or if `pivot` is None.
-    // The latter case handles a never saved editor with a current command index of None.
-    // Check the unit tests for an example.
-    match (commands.current_command_index(), pivot) {
-        (Some(current), Some(pivot)) => current != pivot,
-        // Edge case for an editor with neither a save point nor any changes.
-        // This could be a new editor or an editor without a save point where undo() is called
-        // until the editor is fresh.
-        (None, None) => false,
-        // Default to true because it's safer to assume a buffer has been modified so as to not
-        // lose changes
-        _ => true,
-    }
+/// Get the non-empty matches of `value` on a line, using `matcher`
+fn line_matches(matcher: &dyn SearchMatcher, value: &str, text: &str) -> Vec<Range<usize>> {
+    let mut matches = matcher.find(value, text);
+    matches.retain(|range| !range.is_empty());
+    matches
 }
 
-fn search<'buffer, E: Edit<'buffer>>(editor: &mut E, value: &str, forwards: bool) -> bool {
-    let mut cursor = editor.cursor();
+/// Move the cursor to the start of the next match of `value` in the given direction, returning
+/// the end of the match
+fn search<'buffer, E: Edit<'buffer>>(
+    editor: &mut E,
+    matcher: &dyn SearchMatcher,
+    value: &str,
+    forwards: bool,
+) -> Option<Cursor> {
+    let start = editor.cursor();
+    let mut cursor = start;
     let start_line = cursor.line;
     if forwards {
         while cursor.line < editor.with_buffer(|buffer| buffer.lines.len()) {
-            if let Some(index) = editor.with_buffer(|buffer| {
-                buffer.lines[cursor.line]
-                    .text()
-                    .match_indices(value)
-                    .filter_map(|(i, _)| {
-                        if cursor.line != start_line || i > cursor.index {
-                            Some(i)
-                        } else {
-                            None
-                        }
-                    })
-                    .next()
+            if let Some(range) = editor.with_buffer(|buffer| {
+                line_matches(matcher, value, buffer.lines[cursor.line].text())
+                    .into_iter()
+                    .find(|range| cursor.line != start_line || range.start > cursor.index)
             }) {
-                cursor.index = index;
+                cursor.index = range.start;
+                if let Some(jump_list) = editor.jump_list_mut() {
+                    jump_list.push(start);
+                }
                 editor.set_cursor(cursor);
-                return true;
+                return Some(Cursor::new(cursor.line, range.end));
             }
 
             cursor.line += 1;
@@ -94,26 +69,22 @@ fn search<'buffer, E: Edit<'buffer>>(editor: &mut E, value: &str, forwards: bool
         while cursor.line > 0 {
             cursor.line -= 1;
 
-            if let Some(index) = editor.with_buffer(|buffer| {
-                buffer.lines[cursor.line]
-                    .text()
-                    .rmatch_indices(value)
-                    .filter_map(|(i, _)| {
-                        if cursor.line != start_line || i < cursor.index {
-                            Some(i)
-                        } else {
-                            None
-                        }
-                    })
-                    .next()
+            if let Some(range) = editor.with_buffer(|buffer| {
+                line_matches(matcher, value, buffer.lines[cursor.line].text())
+                    .into_iter()
+                    .rev()
+                    .find(|range| cursor.line != start_line || range.start < cursor.index)
             }) {
-                cursor.index = index;
+                cursor.index = range.start;
+                if let Some(jump_list) = editor.jump_list_mut() {
+                    jump_list.push(start);
+                }
                 editor.set_cursor(cursor);
-                return true;
+                return Some(Cursor::new(cursor.line, range.end));
             }
         }
     }
-    false
+    None
 }
 
 fn select_in<'buffer, E: Edit<'buffer>>(editor: &mut E, start_c: char, end_c: char, include: bool) {
@@ -184,9 +155,8 @@ pub struct ViEditor<'syntax_system, 'buffer> {
     passthrough: bool,
     registers: BTreeMap<char, (Selection, String)>,
     search_opt: Option<(String, bool)>,
-    commands: cosmic_undo_2::Commands<Change>,
+    search_matcher: Arc<dyn SearchMatcher>,
     changed: bool,
-    save_pivot: Option<usize>,
 }
 
 impl<'syntax_system, 'buffer> ViEditor<'syntax_system, 'buffer> {
@@ -197,9 +167,8 @@ impl<'syntax_system, 'buffer> ViEditor<'syntax_system, 'buffer> {
             passthrough: false,
             registers: BTreeMap::new(),
             search_opt: None,
-            commands: cosmic_undo_2::Commands::new(),
+            search_matcher: Arc::new(PlainMatcher),
             changed: false,
-            save_pivot: None,
         }
     }
 
@@ -264,7 +233,9 @@ impl<'syntax_system, 'buffer> ViEditor<'syntax_system, 'buffer> {
     ///
     /// Undoing or redoing changes beyond the pivot point sets the editor to changed.
     pub fn save_point(&mut self) {
-        self.save_pivot = Some(self.commands.current_command_index().unwrap_or_default());
+        if let Some(history) = self.editor.history_mut() {
+            history.set_saved();
+        }
         self.changed = false;
     }
 
@@ -281,22 +252,58 @@ impl<'syntax_system, 'buffer> ViEditor<'syntax_system, 'buffer> {
         &self.parser
     }
 
-    /// Redo a change
-    pub fn redo(&mut self) {
-        log::debug!("Redo");
-        for action in self.commands.redo() {
-            undo_2_action(&mut self.editor, action);
+    /// Get the matcher used by `/` and `?` searches and the `n` and `N` motions
+    pub fn search_matcher(&self) -> &Arc<dyn SearchMatcher> {
+        &self.search_matcher
+    }
+
+    /// Set the matcher used by `/` and `?` searches and the `n` and `N` motions, such as
+    /// `RegexMatcher` with the `regex` feature. Defaults to [`PlainMatcher`]
+    pub fn set_search_matcher(&mut self, search_matcher: Arc<dyn SearchMatcher>) {
+        self.search_matcher = search_matcher;
+        self.with_buffer_mut(|buffer| buffer.set_redraw(true));
+    }
+
+    /// Get the search pattern being typed after `/` or `?`, or the last search pattern if not
+    /// in search mode
+    pub fn search_pattern(&self) -> Option<&str> {
+        match &self.parser.mode {
+            ViMode::Search { value, .. } => Some(value),
+            _ => self.search_opt.as_ref().map(|(value, _)| value.as_str()),
         }
-        self.changed = eval_changed(&self.commands, self.save_pivot);
     }
 
-    /// Undo a change
-    pub fn undo(&mut self) {
-        log::debug!("Undo");
-        for action in self.commands.undo() {
-            undo_2_action(&mut self.editor, action);
+    /// Clear the last search pattern, removing its highlights until the next search
+    pub fn clear_search(&mut self) {
+        if self.search_opt.take().is_some() {
+            self.with_buffer_mut(|buffer| buffer.set_redraw(true));
         }
-        self.changed = eval_changed(&self.commands, self.save_pivot);
+    }
+
+    /// Get the start and end of the matches of [`Self::search_pattern`] on the visible lines,
+    /// updated as the pattern is typed, for highlighting. Matches do not span lines
+    pub fn search_matches(&self) -> Vec<(Cursor, Cursor)> {
+        let Some(pattern) = self.search_pattern() else {
+            return Vec::new();
+        };
+        self.with_buffer(|buffer| {
+            let mut matches = Vec::new();
+            for line_i in buffer.visible_lines() {
+                let text = buffer.lines[line_i].text();
+                for range in line_matches(&*self.search_matcher, pattern, text) {
+                    matches.push((
+                        Cursor::new(line_i, range.start),
+                        Cursor::new(line_i, range.end),
+                    ));
+                }
+            }
+            matches
+        })
+    }
+
+    /// Get the color of search match highlights, from the theme or the selection color
+    pub fn find_highlight_color(&self) -> Color {
+        self.editor.find_highlight_color()
     }
 
     #[cfg(feature = "swash")]
@@ -306,8 +313,10 @@ impl<'syntax_system, 'buffer> ViEditor<'syntax_system, 'buffer> {
     {
         let background_color = self.background_color();
         let foreground_color = self.foreground_color();
-        let cursor_color = self.cursor_color();
+        let cursor_color = self.cursor_color_at(self.cursor_color(), foreground_color);
         let selection_color = self.selection_color();
+        let find_highlight_color = self.find_highlight_color();
+        let search_matches = self.search_matches();
         self.with_buffer(|buffer| {
             let size = buffer.size();
             if let Some(width) = size.0 {
@@ -316,30 +325,92 @@ impl<'syntax_system, 'buffer> ViEditor<'syntax_system, 'buffer> {
                 }
             }
             let font_size = buffer.metrics().font_size;
+            let cursor_layout_i = buffer.cursor_layout_line(self.cursor());
+            let padding = buffer.padding();
+            // Full line highlights span the width inside the padding
+            let line_start = padding.left as i32;
+            let line_end = buffer.size().0.map_or(0.0, |width| width - padding.right) as i32;
             for run in buffer.layout_runs() {
                 let line_i = run.line_i;
                 let line_y = run.line_y;
                 let line_top = run.line_top;
                 let line_height = run.line_height;
+                let graphemes = buffer.lines[line_i].graphemes_cow();
+
+                for background in run.backgrounds() {
+                    f(
+                        background.x as i32,
+                        background.y as i32,
+                        background.w as u32,
+                        background.h as u32,
+                        background.color,
+                    );
+                }
+
+                let range_decorations = run.range_decorations(font_system, buffer.decorations());
+                for shape in range_decorations.iter() {
+                    if shape.kind == DecorationKind::Highlight {
+                        shape.draw(&mut f);
+                    }
+                }
+
+                // Highlight search matches
+                for &(start, end) in search_matches.iter() {
+                    if start.line != line_i {
+                        continue;
+                    }
+                    if let Some((x, w)) = run.highlight(start, end) {
+                        f(
+                            x as i32,
+                            line_top as i32,
+                            w as u32,
+                            line_height as u32,
+                            find_highlight_color,
+                        );
+                    }
+                }
 
                 let cursor_glyph_opt = |cursor: &Cursor| -> Option<(usize, f32, f32)> {
                     //TODO: better calculation of width
                     let default_width = font_size / 2.0;
-                    if cursor.line == line_i {
+                    if cursor.line == line_i && cursor_layout_i.map_or(true, |i| i == run.layout_i)
+                    {
                         for (glyph_i, glyph) in run.glyphs.iter().enumerate() {
-                            if cursor.index >= glyph.start && cursor.index < glyph.end {
+                            if glyph.range().contains(&cursor.index) {
                                 // Guess x offset based on characters
                                 let mut before = 0;
                                 let mut total = 0;
 
-                                let cluster = &run.text[glyph.start..glyph.end];
-                                for (i, _) in cluster.grapheme_indices(true) {
-                                    if glyph.start + i < cursor.index {
+                                for egc in graphemes.within(glyph.range()) {
+                                    if egc.start < cursor.index {
                                         before += 1;
                                     }
                                     total += 1;
                                 }
 
+                                if total == 1 {
+                                    // Cover every glyph of the cluster, which may be shaped
+                                    // into more than one glyph
+                                    let (left, right) = run
+                                        .glyphs
+                                        .iter()
+                                        .filter(|other| {
+                                            other.start == glyph.start && other.end == glyph.end
+                                        })
+                                        .fold(
+                                            (glyph.x, glyph.x + glyph.w),
+                                            |(left, right), other| {
+                                                (left.min(other.x), right.max(other.x + other.w))
+                                            },
+                                        );
+                                    let offset = if glyph.level.is_rtl() {
+                                        glyph.x + glyph.w - right
+                                    } else {
+                                        left - glyph.x
+                                    };
+                                    return Some((glyph_i, offset, right - left));
+                                }
+
                                 let width = glyph.w / (total as f32);
                                 let offset = (before as f32) * width;
                                 return Some((glyph_i, offset, width));
@@ -347,7 +418,7 @@ impl<'syntax_system, 'buffer> ViEditor<'syntax_system, 'buffer> {
                         }
                         match run.glyphs.last() {
                             Some(glyph) => {
-                                if cursor.index == glyph.end {
+                                if cursor.index == glyph.end as usize {
                                     return Some((run.glyphs.len(), 0.0, default_width));
                                 }
                             }
@@ -365,13 +436,12 @@ impl<'syntax_system, 'buffer> ViEditor<'syntax_system, 'buffer> {
                         let mut range_opt = None;
                         for glyph in run.glyphs.iter() {
                             // Guess x offset based on characters
-                            let cluster = &run.text[glyph.start..glyph.end];
-                            let total = cluster.grapheme_indices(true).count();
+                            let total = graphemes.within(glyph.range()).count();
                             let mut c_x = glyph.x;
                             let c_w = glyph.w / total as f32;
-                            for (i, c) in cluster.grapheme_indices(true) {
-                                let c_start = glyph.start + i;
-                                let c_end = glyph.start + i + c.len();
+                            for egc in graphemes.within(glyph.range()) {
+                                let c_start = egc.start;
+                                let c_end = egc.end;
                                 if (start.line != line_i || c_end > start.index)
                                     && (end.line != line_i || c_start < end.index)
                                 {
@@ -397,16 +467,16 @@ impl<'syntax_system, 'buffer> ViEditor<'syntax_system, 'buffer> {
 
                         if run.glyphs.is_empty() && end.line > line_i {
                             // Highlight all of internal empty lines
-                            range_opt = Some((0, buffer.size().0.unwrap_or(0.0) as i32));
+                            range_opt = Some((line_start, line_end));
                         }
 
                         if let Some((mut min, mut max)) = range_opt.take() {
                             if end.line > line_i {
                                 // Draw to end of line
                                 if run.rtl {
-                                    min = 0;
+                                    min = line_start;
                                 } else {
-                                    max = buffer.size().0.unwrap_or(0.0) as i32;
+                                    max = line_end;
                                 }
                             }
                             f(
@@ -421,8 +491,16 @@ impl<'syntax_system, 'buffer> ViEditor<'syntax_system, 'buffer> {
                 }
 
                 // Draw cursor
-                if let Some((cursor_glyph, cursor_glyph_offset, cursor_glyph_width)) =
+                let cursor_glyph_opt = if self
+                    .cursor_blink()
+                    .map_or(true, |cursor_blink| cursor_blink.visible())
+                {
                     cursor_glyph_opt(&self.cursor())
+                } else {
+                    None
+                };
+                if let Some((cursor_glyph, cursor_glyph_offset, cursor_glyph_width)) =
+                    cursor_glyph_opt
                 {
                     let block_cursor = if self.passthrough {
                         false
@@ -463,7 +541,7 @@ impl<'syntax_system, 'buffer> ViEditor<'syntax_system, 'buffer> {
                             }
                             None => {
                                 // Start of empty line
-                                (0, cursor_glyph_width as i32)
+                                (line_start, line_start + cursor_glyph_width as i32)
                             }
                         },
                     };
@@ -512,6 +590,26 @@ impl<'syntax_system, 'buffer> ViEditor<'syntax_system, 'buffer> {
                         },
                     );
                 }
+
+                for decoration in run.decorations(font_system) {
+                    f(
+                        decoration.x as i32,
+                        decoration.y as i32,
+                        decoration.w as u32,
+                        cmp::max(1, decoration.h.round() as u32),
+                        decoration.color_opt.unwrap_or(foreground_color),
+                    );
+                }
+
+                for mark in run.emphasis_marks(font_system) {
+                    mark.draw(foreground_color, &mut f);
+                }
+
+                for shape in range_decorations.iter() {
+                    if shape.kind != DecorationKind::Highlight {
+                        shape.draw(&mut f);
+                    }
+                }
             }
         });
     }
@@ -542,6 +640,66 @@ impl<'syntax_system, 'buffer> Edit<'buffer> for ViEditor<'syntax_system, 'buffer
         self.editor.set_selection(selection);
     }
 
+    fn cursors(&self) -> Vec<(Cursor, Selection)> {
+        self.editor.cursors()
+    }
+
+    fn add_cursor(&mut self, cursor: Cursor, selection: Selection) {
+        self.editor.add_cursor(cursor, selection);
+    }
+
+    fn clear_cursors(&mut self) {
+        self.editor.clear_cursors();
+    }
+
+    fn cursor_blink(&self) -> Option<&CursorBlink> {
+        self.editor.cursor_blink()
+    }
+
+    fn cursor_blink_mut(&mut self) -> Option<&mut CursorBlink> {
+        self.editor.cursor_blink_mut()
+    }
+
+    fn click_chain(&self) -> Option<&ClickChain> {
+        self.editor.click_chain()
+    }
+
+    fn click_chain_mut(&mut self) -> Option<&mut ClickChain> {
+        self.editor.click_chain_mut()
+    }
+
+    fn cursor_color_policy(&self) -> CursorColorPolicy {
+        self.editor.cursor_color_policy()
+    }
+
+    fn set_cursor_color_policy(&mut self, cursor_color_policy: CursorColorPolicy) {
+        self.editor.set_cursor_color_policy(cursor_color_policy);
+    }
+
+    fn overtype(&self) -> bool {
+        self.editor.overtype()
+    }
+
+    fn set_overtype(&mut self, overtype: bool) {
+        self.editor.set_overtype(overtype);
+    }
+
+    fn vertical_motion(&self) -> VerticalMotion {
+        self.editor.vertical_motion()
+    }
+
+    fn set_vertical_motion(&mut self, vertical_motion: VerticalMotion) {
+        self.editor.set_vertical_motion(vertical_motion);
+    }
+
+    fn chained_motion(&mut self, font_system: &mut FontSystem, motion: Motion) -> Option<i32> {
+        self.editor.chained_motion(font_system, motion)
+    }
+
+    fn enter_chained(&mut self, font_system: &mut FontSystem, motion: Motion, cursor_x: i32) {
+        self.editor.enter_chained(font_system, motion, cursor_x);
+    }
+
     fn auto_indent(&self) -> bool {
         self.editor.auto_indent()
     }
@@ -550,6 +708,30 @@ impl<'syntax_system, 'buffer> Edit<'buffer> for ViEditor<'syntax_system, 'buffer
         self.editor.set_auto_indent(auto_indent);
     }
 
+    fn auto_pair(&self) -> bool {
+        self.editor.auto_pair()
+    }
+
+    fn set_auto_pair(&mut self, auto_pair: bool) {
+        self.editor.set_auto_pair(auto_pair);
+    }
+
+    fn input_transformer(&self) -> Option<&dyn InputTransformer> {
+        self.editor.input_transformer()
+    }
+
+    fn set_input_transformer(&mut self, input_transformer: Option<Arc<dyn InputTransformer>>) {
+        self.editor.set_input_transformer(input_transformer);
+    }
+
+    fn clipboard(&self) -> Option<&dyn ClipboardProvider> {
+        self.editor.clipboard()
+    }
+
+    fn set_clipboard(&mut self, clipboard: Option<Arc<dyn ClipboardProvider>>) {
+        self.editor.set_clipboard(clipboard);
+    }
+
     fn tab_width(&self) -> u16 {
         self.editor.tab_width()
     }
@@ -574,6 +756,18 @@ impl<'syntax_system, 'buffer> Edit<'buffer> for ViEditor<'syntax_system, 'buffer
         self.editor.copy_selection()
     }
 
+    fn primary_selection(&self) -> bool {
+        self.editor.primary_selection()
+    }
+
+    fn set_primary_selection(&mut self, primary_selection: bool) {
+        self.editor.set_primary_selection(primary_selection);
+    }
+
+    fn take_primary_selection(&mut self) -> Option<String> {
+        self.editor.take_primary_selection()
+    }
+
     fn delete_selection(&mut self) -> bool {
         self.editor.delete_selection()
     }
@@ -582,22 +776,71 @@ impl<'syntax_system, 'buffer> Edit<'buffer> for ViEditor<'syntax_system, 'buffer
         self.editor.apply_change(change)
     }
 
+    fn apply_changes(&mut self, changes: &[Change]) -> bool {
+        if !self.editor.apply_changes(changes) {
+            return false;
+        }
+        if changes.iter().any(|change| !change.items.is_empty()) {
+            self.changed = is_unsaved(&self.editor);
+        }
+        true
+    }
+
     fn start_change(&mut self) {
         self.editor.start_change();
     }
 
     fn finish_change(&mut self) -> Option<Change> {
-        finish_change(
-            &mut self.editor,
-            &mut self.commands,
-            &mut self.changed,
-            self.save_pivot,
-        )
+        finish_change(&mut self.editor, &mut self.changed)
+    }
+
+    fn history(&self) -> Option<&EditHistory> {
+        self.editor.history()
+    }
+
+    fn history_mut(&mut self) -> Option<&mut EditHistory> {
+        self.editor.history_mut()
+    }
+
+    fn undo(&mut self) -> bool {
+        log::debug!("Undo");
+        let undone = self.editor.undo();
+        self.changed = is_unsaved(&self.editor);
+        undone
+    }
+
+    fn redo(&mut self) -> bool {
+        log::debug!("Redo");
+        let redone = self.editor.redo();
+        self.changed = is_unsaved(&self.editor);
+        redone
     }
 
     fn action(&mut self, font_system: &mut FontSystem, action: Action) {
         log::debug!("Action {:?}", action);
 
+        // Keep the cursor visible while it is being used, even if the action only changes mode
+        if !matches!(action, Action::Scroll { .. }) {
+            if let Some(cursor_blink) = self.editor.cursor_blink_mut() {
+                let hidden = !cursor_blink.visible();
+                cursor_blink.reset();
+                if hidden {
+                    self.editor.set_redraw(true);
+                }
+            }
+        }
+
+        if let Action::InsertText(text) = &action {
+            if !self.passthrough {
+                // Parse one character at a time, so inserts are repeatable and text typed
+                // outside of insert mode is handled as commands
+                for c in text.chars() {
+                    self.action(font_system, Action::Insert(c));
+                }
+                return;
+            }
+        }
+
         let editor = &mut self.editor;
 
         // Ensure a change is always started
@@ -606,12 +849,7 @@ impl<'syntax_system, 'buffer> Edit<'buffer> for ViEditor<'syntax_system, 'buffer
         if self.passthrough {
             editor.action(font_system, action);
             // Always finish change when passing through (TODO: group changes)
-            finish_change(
-                editor,
-                &mut self.commands,
-                &mut self.changed,
-                self.save_pivot,
-            );
+            finish_change(editor, &mut self.changed);
             return;
         }
 
@@ -636,21 +874,53 @@ impl<'syntax_system, 'buffer> Edit<'buffer> for ViEditor<'syntax_system, 'buffer
                 log::debug!("Pass through action {:?}", action);
                 editor.action(font_system, action);
                 // Always finish change when passing through (TODO: group changes)
-                finish_change(
-                    editor,
-                    &mut self.commands,
-                    &mut self.changed,
-                    self.save_pivot,
-                );
+                finish_change(editor, &mut self.changed);
                 return;
             }
         };
 
+        if self.parser.mode == ViMode::Replace {
+            if let Key::Char(c) = key {
+                // Replace mode overtypes, without joining lines at the end of a line
+                let overtype = editor.overtype();
+                editor.set_overtype(true);
+                editor.action(font_system, Action::Insert(c));
+                editor.set_overtype(overtype);
+                return;
+            }
+        }
+
+        let idle = matches!(
+            self.parser.mode,
+            ViMode::Normal | ViMode::Visual | ViMode::VisualLine
+        ) && self.parser.cmd == ViCmd::default();
+        if idle {
+            // The parser only handles these as text objects, so they move by paragraph and
+            // sentence when no command is pending. Ctrl-O and Ctrl-I, which is the same
+            // character as tab, navigate the jump list
+            let action_opt = match key {
+                Key::Char('{') => Some(Action::Motion(Motion::ParagraphUp)),
+                Key::Char('}') => Some(Action::Motion(Motion::ParagraphDown)),
+                Key::Char('(') => Some(Action::Motion(Motion::SentencePrevious)),
+                Key::Char(')') => Some(Action::Motion(Motion::SentenceNext)),
+                Key::Char('\u{f}') => Some(Action::JumpBack),
+                Key::Char('\t') | Key::Tab => Some(Action::JumpForward),
+                _ => None,
+            };
+            if let Some(action) = action_opt {
+                editor.action(font_system, action);
+                return;
+            }
+        }
+
         let has_selection = match editor.selection() {
             Selection::None => false,
             _ => true,
         };
 
+        // Search highlights follow the pattern as it is typed
+        let was_searching = matches!(self.parser.mode, ViMode::Search { .. });
+
         self.parser.parse(key, has_selection, |event| {
             log::debug!("  Event {:?}", event);
             let action = match event {
@@ -672,12 +942,7 @@ impl<'syntax_system, 'buffer> Edit<'buffer> for ViEditor<'syntax_system, 'buffer
                     return;
                 }
                 Event::ChangeFinish => {
-                    finish_change(
-                        editor,
-                        &mut self.commands,
-                        &mut self.changed,
-                        self.save_pivot,
-                    );
+                    finish_change(editor, &mut self.changed);
                     return;
                 }
                 Event::Delete => Action::Delete,
@@ -744,12 +1009,7 @@ impl<'syntax_system, 'buffer> Edit<'buffer> for ViEditor<'syntax_system, 'buffer
                                 }
                             }
                         }
-                        finish_change(
-                            editor,
-                            &mut self.commands,
-                            &mut self.changed,
-                            self.save_pivot,
-                        );
+                        finish_change(editor, &mut self.changed);
                     }
                     return;
                 }
@@ -777,20 +1037,18 @@ impl<'syntax_system, 'buffer> Edit<'buffer> for ViEditor<'syntax_system, 'buffer
                         TextObject::CurlyBrackets => select_in(editor, '{', '}', include),
                         TextObject::DoubleQuotes => select_in(editor, '"', '"', include),
                         TextObject::Parentheses => select_in(editor, '(', ')', include),
-                        TextObject::Search { forwards } => {
-                            match &self.search_opt {
-                                Some((value, _)) => {
-                                    if search(editor, value, forwards) {
-                                        let mut cursor = editor.cursor();
-                                        editor.set_selection(Selection::Normal(cursor));
-                                        //TODO: traverse lines if necessary
-                                        cursor.index += value.len();
-                                        editor.set_cursor(cursor);
-                                    }
+                        TextObject::Search { forwards } => match &self.search_opt {
+                            Some((value, _)) => {
+                                if let Some(end) =
+                                    search(editor, &*self.search_matcher, value, forwards)
+                                {
+                                    let cursor = editor.cursor();
+                                    editor.set_selection(Selection::Normal(cursor));
+                                    editor.set_cursor(end);
                                 }
-                                None => {}
                             }
-                        }
+                            None => {}
+                        },
                         TextObject::SingleQuotes => select_in(editor, '\'', '\'', include),
                         TextObject::SquareBrackets => select_in(editor, '[', ']', include),
                         TextObject::Ticks => select_in(editor, '`', '`', include),
@@ -832,9 +1090,8 @@ impl<'syntax_system, 'buffer> Edit<'buffer> for ViEditor<'syntax_system, 'buffer
                     return;
                 }
                 Event::Undo => {
-                    for action in self.commands.undo() {
-                        undo_2_action(editor, action);
-                    }
+                    editor.undo();
+                    self.changed = is_unsaved(editor);
                     return;
                 }
                 Event::Yank { register } => {
@@ -916,7 +1173,7 @@ impl<'syntax_system, 'buffer> Edit<'buffer> for ViEditor<'syntax_system, 'buffer
                         }
                         modit::Motion::NextSearch => match &self.search_opt {
                             Some((value, forwards)) => {
-                                search(editor, value, *forwards);
+                                search(editor, &*self.search_matcher, value, *forwards);
                                 return;
                             }
                             None => return,
@@ -1031,7 +1288,7 @@ impl<'syntax_system, 'buffer> Edit<'buffer> for ViEditor<'syntax_system, 'buffer
                         }
                         modit::Motion::PreviousSearch => match &self.search_opt {
                             Some((value, forwards)) => {
-                                search(editor, value, !*forwards);
+                                search(editor, &*self.search_matcher, value, !*forwards);
                                 return;
                             }
                             None => return,
@@ -1160,11 +1417,55 @@ impl<'syntax_system, 'buffer> Edit<'buffer> for ViEditor<'syntax_system, 'buffer
             };
             editor.action(font_system, action);
         });
+
+        if was_searching || matches!(self.parser.mode, ViMode::Search { .. }) {
+            editor.set_redraw(true);
+        }
     }
 
     fn cursor_position(&self) -> Option<(i32, i32)> {
         self.editor.cursor_position()
     }
+
+    fn cursor_area(&self, scale_factor: f32) -> Option<CursorArea> {
+        self.editor.cursor_area(scale_factor)
+    }
+
+    fn hover(&self) -> Option<&Hover> {
+        self.editor.hover()
+    }
+
+    fn set_hover_position(&mut self, position: Option<(i32, i32)>) {
+        self.editor.set_hover_position(position);
+    }
+
+    fn selection_handles(&self) -> Option<SelectionHandles> {
+        self.editor.selection_handles()
+    }
+
+    fn gutter(&self) -> Option<&Gutter> {
+        self.editor.gutter()
+    }
+
+    fn gutter_mut(&mut self) -> Option<&mut Gutter> {
+        self.editor.gutter_mut()
+    }
+
+    fn set_gutter(&mut self, gutter: Option<Gutter>) {
+        self.editor.set_gutter(gutter);
+    }
+
+    fn take_gutter_click(&mut self) -> Option<usize> {
+        self.editor.take_gutter_click()
+    }
+
+    fn jump_list(&self) -> Option<&JumpList> {
+        self.editor.jump_list()
+    }
+
+    fn jump_list_mut(&mut self) -> Option<&mut JumpList> {
+        self.editor.jump_list_mut()
+    }
 }
 
 impl<'font_system, 'syntax_system, 'buffer>
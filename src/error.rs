@@ -0,0 +1,61 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::fmt;
+
+use crate::Cursor;
+
+/// An error of the fallible variants of functions, like [`crate::Font::try_new`] and
+/// [`crate::Buffer::try_shape_until_cursor`], so applications can recover from corrupt fonts and
+/// invalid cursors
+///
+/// New variants may be added in minor releases, and `IoError` only exists with the
+/// `std` feature, so matches need a wildcard arm.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// A font could not be parsed, with the ID of its face if it is in the database
+    FontParse(Option<fontdb::ID>),
+    /// A glyph could not be rendered with its font
+    MissingGlyph { font_id: fontdb::ID, glyph_id: u16 },
+    /// A cursor is not on a line of the buffer, or not at a character boundary of the line
+    InvalidCursor(Cursor),
+    /// Reading a font failed
+    #[cfg(feature = "std")]
+    IoError(std::io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FontParse(Some(id)) => write!(f, "failed to parse font {id:?}"),
+            Self::FontParse(None) => write!(f, "failed to parse font"),
+            Self::MissingGlyph { font_id, glyph_id } => {
+                write!(f, "font {font_id:?} has no glyph {glyph_id}")
+            }
+            Self::InvalidCursor(cursor) => write!(
+                f,
+                "invalid cursor at line {} index {}",
+                cursor.line, cursor.index
+            ),
+            #[cfg(feature = "std")]
+            Self::IoError(err) => write!(f, "failed to read font: {err}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::IoError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::IoError(err)
+    }
+}
@@ -0,0 +1,98 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use crate::Font;
+
+/// Characters with published widths, in the order of the width tables
+const CHARS: &str = " 0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// Published advance widths in 1/1000 em, from the standard Helvetica font metrics
+#[rustfmt::skip]
+const HELVETICA: [u16; 63] = [
+    278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556,
+    667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833,
+    722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611,
+    556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833,
+    556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500,
+];
+
+/// Published advance widths in 1/1000 em, from the standard Times Roman font metrics
+#[rustfmt::skip]
+const TIMES: [u16; 63] = [
+    250,
+    500, 500, 500, 500, 500, 500, 500, 500, 500, 500,
+    722, 667, 667, 722, 611, 556, 722, 722, 333, 389, 722, 611, 889,
+    722, 722, 556, 722, 667, 556, 611, 722, 722, 944, 722, 722, 611,
+    444, 500, 444, 500, 444, 333, 500, 500, 278, 278, 500, 278, 778,
+    500, 500, 500, 500, 333, 389, 278, 500, 500, 722, 500, 500, 444,
+];
+
+/// Published advance widths in 1/1000 em, from the standard Courier font metrics
+const COURIER: [u16; 63] = [600; 63];
+
+/// Get the published widths for a family name, including metric-compatible families
+fn published_widths(family: &str) -> Option<&'static [u16; 63]> {
+    const FAMILIES: [(&str, &[u16; 63]); 6] = [
+        ("helvetica", &HELVETICA),
+        ("arial", &HELVETICA),
+        ("times", &TIMES),
+        ("times new roman", &TIMES),
+        ("courier", &COURIER),
+        ("courier new", &COURIER),
+    ];
+    FAMILIES
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(family))
+        .map(|(_, widths)| *widths)
+}
+
+/// Adjusts the advances of a substitute font to match the published widths of a missing font
+#[derive(Debug)]
+pub(crate) struct AdvanceCompensation {
+    widths: &'static [u16; 63],
+    /// Scale for glyphs without a published width
+    scale: f32,
+}
+
+impl AdvanceCompensation {
+    /// Create compensation for `font` substituting the `family` font family, if `family` has
+    /// published widths
+    pub(crate) fn new(family: &str, font: &Font) -> Option<Self> {
+        let widths = published_widths(family)?;
+        let face = font.rustybuzz();
+        let upem = face.units_per_em() as f32;
+
+        // Scale by the ratio of total widths of the characters both fonts have
+        let mut published = 0.0;
+        let mut substitute = 0.0;
+        for (c, width) in CHARS.chars().zip(widths.iter()) {
+            if let Some(advance) = face
+                .glyph_index(c)
+                .and_then(|glyph_id| face.glyph_hor_advance(glyph_id))
+            {
+                published += f32::from(*width) / 1000.0;
+                substitute += f32::from(advance) / upem;
+            }
+        }
+        if substitute <= 0.0 {
+            return None;
+        }
+
+        Some(Self {
+            widths,
+            scale: published / substitute,
+        })
+    }
+
+    /// Get the compensated advance in em of a glyph for the cluster `text`
+    pub(crate) fn advance(&self, text: &str, x_advance: f32) -> f32 {
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => match CHARS.find(c) {
+                Some(i) => f32::from(self.widths[i]) / 1000.0,
+                None => x_advance * self.scale,
+            },
+            _ => x_advance * self.scale,
+        }
+    }
+}
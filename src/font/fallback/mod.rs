@@ -1,11 +1,15 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
+use core::hash::{BuildHasher as _, Hash, Hasher};
 use fontdb::Family;
 use unicode_script::Script;
 
-use crate::{Font, FontMatchKey, FontSystem, ShapeBuffer};
+use crate::{
+    Attrs, BuildHasher, Font, FontMatchAttrs, FontMatchKey, FontSystem, HashMap, ShapeBuffer,
+};
 
 use self::platform::*;
 
@@ -41,22 +45,117 @@ pub(crate) struct MonospaceFallbackInfo {
     id: fontdb::ID,
 }
 
+/// Key of [`FallbackMemo`], everything that affects the fonts chosen for a run
+///
+/// The key borrows the run so looking it up does not allocate, only inserting copies it.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct FallbackMemoKey<'a> {
+    pub word: &'a str,
+    pub scripts: &'a [Script],
+    pub attrs: Attrs<'a>,
+    pub locale: &'a str,
+}
+
+impl FallbackMemoKey<'_> {
+    fn hash(&self) -> u64 {
+        let mut hasher = BuildHasher::default().build_hasher();
+        self.word.hash(&mut hasher);
+        self.scripts.hash(&mut hasher);
+        self.attrs.family.hash(&mut hasher);
+        self.attrs.stretch.hash(&mut hasher);
+        self.attrs.style.hash(&mut hasher);
+        self.attrs.weight.hash(&mut hasher);
+        self.attrs.named_instance_opt.hash(&mut hasher);
+        self.locale.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn matches(&self, entry: &FallbackMemoEntry) -> bool {
+        self.word == entry.word
+            && self.scripts == entry.scripts.as_slice()
+            && entry.attrs.matches(&self.attrs)
+            && self.locale == entry.locale
+    }
+}
+
+/// Owned copy of a [`FallbackMemoKey`] with the fonts chosen for it
+#[derive(Debug)]
+struct FallbackMemoEntry {
+    word: String,
+    scripts: Vec<Script>,
+    attrs: FontMatchAttrs,
+    locale: String,
+    font_ids: Arc<[fontdb::ID]>,
+}
+
+/// Memo of the fonts chosen by [`FontFallbackIter`] for a run, so identical runs do not iterate
+/// over the fallback fonts again
+#[derive(Debug, Default)]
+pub(crate) struct FallbackMemo {
+    /// Entries by the hash of their key, see [`FallbackMemoKey::hash`]
+    entries: HashMap<u64, Vec<FallbackMemoEntry>>,
+    len: usize,
+}
+
+impl FallbackMemo {
+    const SIZE_LIMIT: usize = 1024;
+
+    /// Get the fonts that shaped an identical run, the default font followed by the fallback
+    /// fonts that replaced missing glyphs
+    pub fn get(&self, key: &FallbackMemoKey) -> Option<Arc<[fontdb::ID]>> {
+        self.entries
+            .get(&key.hash())?
+            .iter()
+            .find(|entry| key.matches(entry))
+            .map(|entry| entry.font_ids.clone())
+    }
+
+    /// Insert the fonts of a run that is not in the memo yet
+    pub fn insert(&mut self, key: &FallbackMemoKey, font_ids: Vec<fontdb::ID>) {
+        // Clear the memo first if it reached the size limit
+        if self.len >= Self::SIZE_LIMIT {
+            self.clear();
+        }
+        self.len += 1;
+        self.entries
+            .entry(key.hash())
+            .or_default()
+            .push(FallbackMemoEntry {
+                word: key.word.into(),
+                scripts: key.scripts.to_vec(),
+                attrs: key.attrs.into(),
+                locale: key.locale.into(),
+                font_ids: font_ids.into(),
+            });
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.len = 0;
+    }
+}
+
 pub struct FontFallbackIter<'a> {
     font_system: &'a mut FontSystem,
+    locale_opt: Option<&'a str>,
     font_match_keys: &'a [FontMatchKey],
     default_families: &'a [&'a Family<'a>],
     default_i: usize,
     scripts: &'a [Script],
     word: &'a str,
     script_i: (usize, usize),
+    script_families: Vec<&'static str>,
     common_i: usize,
     other_i: usize,
     end: bool,
 }
 
 impl<'a> FontFallbackIter<'a> {
+    /// Iterate over the fonts to try for `word`, with the families preferred for `scripts` by
+    /// `locale_opt` taking priority over those of the preferred locales of `font_system`
     pub fn new(
         font_system: &'a mut FontSystem,
+        locale_opt: Option<&'a str>,
         font_match_keys: &'a [FontMatchKey],
         default_families: &'a [&'a Family<'a>],
         scripts: &'a [Script],
@@ -65,12 +164,14 @@ impl<'a> FontFallbackIter<'a> {
         font_system.monospace_fallbacks_buffer.clear();
         Self {
             font_system,
+            locale_opt,
             font_match_keys,
             default_families,
             default_i: 0,
             scripts,
             word,
             script_i: (0, 0),
+            script_families: Vec::new(),
             common_i: 0,
             other_i: 0,
             end: false,
@@ -82,14 +183,14 @@ impl<'a> FontFallbackIter<'a> {
             missing_warn!(
                 "Failed to find any fallback for {:?} locale '{}': '{}'",
                 self.scripts,
-                self.font_system.locale(),
+                self.locale(),
                 word
             );
         } else if self.other_i > 0 {
             missing_warn!(
                 "Failed to find preset fallback for {:?} locale '{}', used '{}': '{}'",
                 self.scripts,
-                self.font_system.locale(),
+                self.locale(),
                 self.face_name(self.font_match_keys[self.other_i - 1].id),
                 word
             );
@@ -98,13 +199,18 @@ impl<'a> FontFallbackIter<'a> {
             missing_warn!(
                 "Failed to find script fallback for {:?} locale '{}', used '{}': '{}'",
                 self.scripts,
-                self.font_system.locale(),
+                self.locale(),
                 family,
                 word
             );
         }
     }
 
+    /// Get the locale that fallback prefers
+    fn locale(&self) -> &str {
+        self.locale_opt.unwrap_or(self.font_system.locale())
+    }
+
     pub fn face_name(&self, id: fontdb::ID) -> &str {
         if let Some(face) = self.font_system.db().face(id) {
             if let Some((name, _)) = face.families.first() {
@@ -132,11 +238,20 @@ impl<'a> FontFallbackIter<'a> {
     fn default_font_match_key(&self) -> Option<&FontMatchKey> {
         let default_family = self.default_families[self.default_i - 1];
         let default_family_name = self.font_system.db().family_name(default_family);
+        let find_family = |family_name: &str| {
+            self.font_match_keys
+                .iter()
+                .filter(|m_key| m_key.font_weight_diff == 0)
+                .find(|m_key| self.face_contains_family(m_key.id, family_name))
+        };
 
-        self.font_match_keys
-            .iter()
-            .filter(|m_key| m_key.font_weight_diff == 0)
-            .find(|m_key| self.face_contains_family(m_key.id, default_family_name))
+        find_family(default_family_name).or_else(|| {
+            // Try substitutes for the family before script fallback
+            self.font_system
+                .family_substitutes(default_family_name)
+                .iter()
+                .find_map(|substitute| find_family(substitute))
+        })
     }
 }
 
@@ -262,9 +377,19 @@ impl<'a> Iterator for FontFallbackIter<'a> {
         while self.script_i.0 < self.scripts.len() {
             let script = self.scripts[self.script_i.0];
 
-            let script_families = script_fallback(script, self.font_system.locale());
-            while self.script_i.1 < script_families.len() {
-                let script_family = script_families[self.script_i.1];
+            if self.script_i.1 == 0 {
+                // Families preferred by each locale, in order of priority
+                self.script_families.clear();
+                for locale in self.font_system.fallback_locales(self.locale_opt) {
+                    for family in script_fallback(script, locale) {
+                        if !self.script_families.contains(family) {
+                            self.script_families.push(family);
+                        }
+                    }
+                }
+            }
+            while self.script_i.1 < self.script_families.len() {
+                let script_family = self.script_families[self.script_i.1];
                 self.script_i.1 += 1;
                 for m_key in font_match_keys_iter(false) {
                     if self.face_contains_family(m_key.id, script_family) {
@@ -277,7 +402,7 @@ impl<'a> Iterator for FontFallbackIter<'a> {
                     "failed to find family '{}' for script {:?} and locale '{}'",
                     script_family,
                     script,
-                    self.font_system.locale(),
+                    self.locale(),
                 );
             }
 
@@ -0,0 +1,229 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use alloc::sync::Arc;
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+
+use rustybuzz::Face as RustybuzzFace;
+
+use super::OwnedFace;
+use crate::{Attrs, Font, FontVariation, HashMap};
+
+/// Id of a set of variable font axis values of glyphs, interned by the [`crate::FontSystem`] so
+/// it can be part of a [`crate::CacheKey`], see [`crate::FontSystem::font_variations`]
+///
+/// The default id is the set without any values, the default instance of the font. Up to 65536
+/// sets are interned, further sets use the default instance.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct FontVariationsId(u16);
+
+/// A named instance of a variable font, like `Condensed SemiBold`, see
+/// [`crate::FontSystem::named_instances`] and [`crate::Attrs::named_instance`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NamedInstance {
+    /// Name of the instance, the subfamily name of the font with these axis values
+    pub name: String,
+    /// Values of all axes of the font for the instance
+    pub variations: Vec<FontVariation>,
+}
+
+/// Read the named instances from the `fvar` table of `face`
+pub(crate) fn named_instances(face: &RustybuzzFace<'_>) -> Vec<NamedInstance> {
+    let mut instances = Vec::new();
+    let Some(data) = face
+        .raw_face()
+        .table(rustybuzz::ttf_parser::Tag::from_bytes(b"fvar"))
+    else {
+        return instances;
+    };
+    let read_u16 = |offset: usize| -> Option<u16> {
+        Some(u16::from_be_bytes(
+            data.get(offset..offset + 2)?.try_into().ok()?,
+        ))
+    };
+    let read_i32 = |offset: usize| -> Option<i32> {
+        Some(i32::from_be_bytes(
+            data.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+    let (Some(axes_offset), Some(axis_count), Some(axis_size), Some(count), Some(size)) = (
+        read_u16(4),
+        read_u16(8),
+        read_u16(10),
+        read_u16(12),
+        read_u16(14),
+    ) else {
+        return instances;
+    };
+    let axes: Vec<_> = face.variation_axes().into_iter().collect();
+    if axes.len() != usize::from(axis_count) {
+        return instances;
+    }
+
+    let start = usize::from(axes_offset) + usize::from(axis_count) * usize::from(axis_size);
+    for i in 0..usize::from(count) {
+        let offset = start + i * usize::from(size);
+        let Some(name_id) = read_u16(offset) else {
+            break;
+        };
+        let Some(name) = face_name(face, name_id) else {
+            continue;
+        };
+        // Coordinates are 16.16 fixed point numbers
+        let variations = axes
+            .iter()
+            .enumerate()
+            .map_while(|(axis_i, axis)| {
+                let value = read_i32(offset + 4 + axis_i * 4)?;
+                Some(FontVariation {
+                    tag: axis.tag.to_bytes(),
+                    value: value as f32 / 65536.0,
+                })
+            })
+            .collect::<Vec<_>>();
+        if variations.len() == axes.len() {
+            instances.push(NamedInstance { name, variations });
+        }
+    }
+    instances
+}
+
+/// Get the name with `name_id` from the `name` table of `face`
+fn face_name(face: &RustybuzzFace<'_>, name_id: u16) -> Option<String> {
+    face.names()
+        .into_iter()
+        .filter(|name| name.name_id == name_id && name.is_unicode())
+        .find_map(|name| {
+            let units = name
+                .name
+                .chunks_exact(2)
+                .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]));
+            char::decode_utf16(units)
+                .collect::<Result<String, _>>()
+                .ok()
+        })
+}
+
+/// A face of a variable font with a set of axis values applied, for shaping
+struct ShaperInstance(OwnedFace);
+
+/// Cache of the faces used to shape variable fonts, keyed on the font and the set of axis values
+pub(crate) struct ShaperInstances {
+    /// Interned sets of axis values, at the index of their id, the first set is empty
+    sets: Vec<Arc<[FontVariation]>>,
+    ids: HashMap<Arc<[FontVariation]>, FontVariationsId>,
+    instances: HashMap<(fontdb::ID, FontVariationsId), Option<ShaperInstance>>,
+    /// Buffer for the axis values of a font
+    coords: Vec<FontVariation>,
+}
+
+impl ShaperInstances {
+    const INSTANCES_SIZE_LIMIT: usize = 256;
+
+    /// Get the axis values of `id`
+    pub(crate) fn variations(&self, id: FontVariationsId) -> &Arc<[FontVariation]> {
+        self.sets.get(id.0 as usize).unwrap_or(&self.sets[0])
+    }
+
+    /// Resolve the axis values `font` is shaped with for `attrs`, returning their id and the face
+    /// to shape with
+    pub(crate) fn get<'a>(
+        &'a mut self,
+        font: &'a Font,
+        attrs: &Attrs,
+    ) -> (FontVariationsId, &'a RustybuzzFace<'a>) {
+        let id = self.resolve(font, attrs);
+        if id == FontVariationsId::default() {
+            return (id, font.rustybuzz());
+        }
+
+        if self.instances.len() >= Self::INSTANCES_SIZE_LIMIT
+            && !self.instances.contains_key(&(font.id(), id))
+        {
+            self.instances.clear();
+        }
+        let sets = &self.sets;
+        let instance = self
+            .instances
+            .entry((font.id(), id))
+            .or_insert_with(|| ShaperInstance::new(font, &sets[id.0 as usize]));
+        match instance {
+            Some(instance) => (id, instance.0.borrow_dependent()),
+            None => (id, font.rustybuzz()),
+        }
+    }
+
+    /// Resolve and intern the axis values `font` is shaped with, only including the axes of the
+    /// font with values other than their defaults. [`Attrs::variations`] take priority over the
+    /// named instance of [`Attrs::named_instance`], which takes priority over [`Attrs::weight`]
+    pub(crate) fn resolve(&mut self, font: &Font, attrs: &Attrs) -> FontVariationsId {
+        let named_instance = attrs
+            .named_instance_opt
+            .and_then(|name| font.named_instance(name));
+        self.coords.clear();
+        for axis in font.variation_axes() {
+            let tag = axis.tag.to_bytes();
+            let find = |variations: &[FontVariation]| {
+                variations
+                    .iter()
+                    .rev()
+                    .find(|variation| variation.tag == tag)
+                    .map(|variation| variation.value)
+            };
+            let value = find(attrs.variations)
+                .or_else(|| named_instance.and_then(|instance| find(&instance.variations)))
+                .or_else(|| (&tag == b"wght").then(|| f32::from(attrs.weight.0)));
+            if let Some(value) = value {
+                let value = value.clamp(axis.min_value, axis.max_value);
+                if value != axis.def_value {
+                    self.coords.push(FontVariation { tag, value });
+                }
+            }
+        }
+
+        if let Some(id) = self.ids.get(&self.coords[..]) {
+            return *id;
+        }
+        let Ok(id) = u16::try_from(self.sets.len()) else {
+            log::warn!("too many sets of variable font axis values, using the default instance");
+            return FontVariationsId::default();
+        };
+        let id = FontVariationsId(id);
+        let set: Arc<[FontVariation]> = Arc::from(self.coords.as_slice());
+        self.sets.push(Arc::clone(&set));
+        self.ids.insert(set, id);
+        id
+    }
+}
+
+impl Default for ShaperInstances {
+    fn default() -> Self {
+        let empty: Arc<[FontVariation]> = Arc::from(Vec::new());
+        let mut ids = HashMap::default();
+        ids.insert(Arc::clone(&empty), FontVariationsId::default());
+        Self {
+            sets: Vec::from([empty]),
+            ids,
+            instances: HashMap::default(),
+            coords: Vec::new(),
+        }
+    }
+}
+
+impl ShaperInstance {
+    fn new(font: &Font, variations: &[FontVariation]) -> Option<Self> {
+        let face = OwnedFace::try_new(Arc::clone(&font.data), |data| {
+            let mut face = RustybuzzFace::from_slice((**data).as_ref(), font.index).ok_or(())?;
+            let variations: Vec<rustybuzz::Variation> = variations
+                .iter()
+                .map(|variation| rustybuzz::Variation {
+                    tag: rustybuzz::ttf_parser::Tag::from_bytes(&variation.tag),
+                    value: variation.value,
+                })
+                .collect();
+            face.set_variations(&variations);
+            Ok::<_, ()>(face)
+        });
+        face.ok().map(Self)
+    }
+}
@@ -0,0 +1,197 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use alloc::string::String;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use crate::FontSystem;
+
+/// A single synthetic font of deterministic metrics, loaded by [`FontSystem::new_mock`] for
+/// testing layout and editing without any fonts installed
+///
+/// Every character is a box glyph with an advance of [`MockFont::ADVANCE`] em, except spaces,
+/// which are empty. The font is monospaced and is used for all generic families, and for
+/// fallback of every script.
+#[derive(Clone, Copy, Debug)]
+pub struct MockFont;
+
+impl MockFont {
+    /// Family name of the synthetic font
+    pub const FAMILY: &'static str = "Mock";
+    /// Advance of every glyph in em
+    pub const ADVANCE: f32 = 0.5;
+    /// Ascent in em
+    pub const ASCENT: f32 = 0.8;
+    /// Descent in em, below the baseline
+    pub const DESCENT: f32 = 0.2;
+
+    const UNITS_PER_EM: i16 = 1000;
+}
+
+impl FontSystem {
+    /// Create a new [`FontSystem`] with only the synthetic [`MockFont`], using the `en-US`
+    /// locale
+    pub fn new_mock() -> Self {
+        let mut db = fontdb::Database::new();
+        db.load_font_data(mock_font_data());
+        db.set_serif_family(MockFont::FAMILY);
+        db.set_sans_serif_family(MockFont::FAMILY);
+        db.set_cursive_family(MockFont::FAMILY);
+        db.set_fantasy_family(MockFont::FAMILY);
+        db.set_monospace_family(MockFont::FAMILY);
+        Self::new_with_locale_and_db(String::from("en-US"), db)
+    }
+}
+
+/// Big endian writer for font tables
+#[derive(Default)]
+struct TableWriter(Vec<u8>);
+
+impl TableWriter {
+    fn u16(&mut self, value: u16) -> &mut Self {
+        self.0.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    fn i16(&mut self, value: i16) -> &mut Self {
+        self.0.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    fn u32(&mut self, value: u32) -> &mut Self {
+        self.0.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+}
+
+/// Build a TrueType font with a box glyph for all characters other than space
+fn mock_font_data() -> Vec<u8> {
+    // The first glyph is the missing glyph
+    const BOX: u32 = 1;
+    const SPACE: u32 = 2;
+    const GLYPHS: u16 = 3;
+    let upem = MockFont::UNITS_PER_EM;
+    let advance = (f32::from(upem) * MockFont::ADVANCE) as i16;
+    let ascent = (f32::from(upem) * MockFont::ASCENT) as i16;
+    let descent = (f32::from(upem) * MockFont::DESCENT) as i16;
+    let inset = advance / 10;
+    let (x_min, x_max, y_max) = (inset, advance - inset, ascent - inset);
+
+    // A rectangle of four on-curve points, used for the box and the missing glyph
+    let mut glyf = TableWriter::default();
+    for _ in 0..2 {
+        glyf.i16(1).i16(x_min).i16(0).i16(x_max).i16(y_max);
+        glyf.u16(3).u16(0);
+        glyf.0.extend_from_slice(&[0x01; 4]);
+        glyf.i16(x_min).i16(0).i16(x_max - x_min).i16(0);
+        glyf.i16(0).i16(y_max).i16(0).i16(-y_max);
+    }
+    let glyph_len = glyf.0.len() as u16 / 2;
+
+    let mut head = TableWriter::default();
+    head.u32(0x0001_0000)
+        .u32(0x0001_0000)
+        .u32(0)
+        .u32(0x5F0F_3CF5);
+    head.u16(0x000B).i16(upem);
+    head.u32(0).u32(0).u32(0).u32(0);
+    head.i16(x_min).i16(0).i16(x_max).i16(y_max);
+    head.u16(0).u16(8).i16(2).i16(0).i16(0);
+
+    let mut hhea = TableWriter::default();
+    hhea.u32(0x0001_0000).i16(ascent).i16(-descent).i16(0);
+    hhea.u16(advance as u16).i16(x_min).i16(inset).i16(x_max);
+    hhea.i16(1).i16(0).i16(0);
+    hhea.i16(0).i16(0).i16(0).i16(0).i16(0);
+    hhea.u16(1);
+
+    let mut maxp = TableWriter::default();
+    maxp.u32(0x0001_0000)
+        .u16(GLYPHS)
+        .u16(4)
+        .u16(1)
+        .u16(0)
+        .u16(0);
+    maxp.u16(2);
+    for _ in 0..8 {
+        maxp.u16(0);
+    }
+
+    // Glyphs after the first metric share its advance
+    let mut hmtx = TableWriter::default();
+    hmtx.u16(advance as u16).i16(x_min).i16(x_min).i16(0);
+
+    // The short format stores offsets divided by two
+    let mut loca = TableWriter::default();
+    loca.u16(0).u16(glyph_len / 2).u16(glyph_len).u16(glyph_len);
+
+    // Format 13 maps ranges of characters to a single glyph
+    let groups = [
+        (0x20, 0x20, SPACE),
+        (0x21, 0xD7FF, BOX),
+        (0xE000, 0x10_FFFF, BOX),
+    ];
+    let mut cmap = TableWriter::default();
+    cmap.u16(0).u16(1).u16(3).u16(10).u32(12);
+    cmap.u16(13).u16(0);
+    cmap.u32(16 + 12 * groups.len() as u32).u32(0);
+    cmap.u32(groups.len() as u32);
+    for (start, end, glyph) in groups {
+        cmap.u32(start).u32(end).u32(glyph);
+    }
+
+    let names = [(1, MockFont::FAMILY), (2, "Regular"), (6, "Mock-Regular")];
+    let mut name = TableWriter::default();
+    name.u16(0)
+        .u16(names.len() as u16)
+        .u16(6 + 12 * names.len() as u16);
+    let mut strings = TableWriter::default();
+    for (name_id, value) in names {
+        let offset = strings.0.len() as u16;
+        for unit in value.encode_utf16() {
+            strings.u16(unit);
+        }
+        let len = strings.0.len() as u16 - offset;
+        name.u16(3)
+            .u16(1)
+            .u16(0x0409)
+            .u16(name_id)
+            .u16(len)
+            .u16(offset);
+    }
+    name.0.extend_from_slice(&strings.0);
+
+    let mut post = TableWriter::default();
+    post.u32(0x0003_0000)
+        .u32(0)
+        .i16(-descent / 2)
+        .i16(upem / 20);
+    post.u32(1).u32(0).u32(0).u32(0).u32(0);
+
+    // Tables are sorted by tag
+    let tables = [
+        (b"cmap", cmap.0),
+        (b"glyf", glyf.0),
+        (b"head", head.0),
+        (b"hhea", hhea.0),
+        (b"hmtx", hmtx.0),
+        (b"loca", loca.0),
+        (b"maxp", maxp.0),
+        (b"name", name.0),
+        (b"post", post.0),
+    ];
+    let mut font = TableWriter::default();
+    font.u32(0x0001_0000).u16(tables.len() as u16);
+    font.u16(128).u16(3).u16(tables.len() as u16 * 16 - 128);
+    let mut offset = 12 + 16 * tables.len() as u32;
+    for (tag, data) in tables.iter() {
+        font.0.extend_from_slice(&tag[..]);
+        font.u32(0).u32(offset).u32(data.len() as u32);
+        offset += (data.len() as u32 + 3) & !3;
+    }
+    for (_, data) in tables.iter() {
+        font.0.extend_from_slice(data);
+        font.0.resize((font.0.len() + 3) & !3, 0);
+    }
+    font.0
+}
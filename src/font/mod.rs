@@ -1,4 +1,5 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
+pub(crate) mod compensation;
 pub(crate) mod fallback;
 
 // re-export ttf_parser
@@ -13,6 +14,14 @@ use alloc::vec::Vec;
 use rustybuzz::Face as RustybuzzFace;
 use self_cell::self_cell;
 
+use crate::Error;
+
+pub use self::instance::*;
+mod instance;
+
+pub use self::mock::*;
+mod mock;
+
 pub use self::system::*;
 mod system;
 
@@ -31,14 +40,30 @@ struct FontMonospaceFallback {
     unicode_codepoints: Vec<u32>,
 }
 
+/// Positions and thicknesses of the text decorations of a [`Font`], in em units
+///
+/// Positions are the top of each line, with positive values above the baseline.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DecorationMetrics {
+    pub underline_position: f32,
+    pub underline_thickness: f32,
+    pub strikethrough_position: f32,
+    pub strikethrough_thickness: f32,
+    /// The ascent of the font, overlines use the underline thickness
+    pub overline_position: f32,
+}
+
 /// A font
 pub struct Font {
     #[cfg(feature = "swash")]
     swash: (u32, swash::CacheKey),
     rustybuzz: OwnedFace,
     data: Arc<dyn AsRef<[u8]> + Send + Sync>,
+    index: u32,
     id: fontdb::ID,
     monospace_fallback: Option<FontMonospaceFallback>,
+    variation_axes: Vec<ttf_parser::VariationAxis>,
+    named_instances: Vec<NamedInstance>,
 }
 
 impl fmt::Debug for Font {
@@ -70,6 +95,31 @@ impl Font {
             .map_or(&[], |x| &x.unicode_codepoints)
     }
 
+    /// Get the `wght` axis if this is a variable font
+    pub fn weight_axis(&self) -> Option<ttf_parser::VariationAxis> {
+        self.variation_axes
+            .iter()
+            .find(|axis| axis.tag == ttf_parser::Tag::from_bytes(b"wght"))
+            .copied()
+    }
+
+    /// Get the axes if this is a variable font
+    pub fn variation_axes(&self) -> &[ttf_parser::VariationAxis] {
+        &self.variation_axes
+    }
+
+    /// Get the named instances if this is a variable font
+    pub fn named_instances(&self) -> &[NamedInstance] {
+        &self.named_instances
+    }
+
+    /// Get the named instance called `name`, ignoring ASCII case
+    pub fn named_instance(&self, name: &str) -> Option<&NamedInstance> {
+        self.named_instances
+            .iter()
+            .find(|instance| instance.name.eq_ignore_ascii_case(name))
+    }
+
     pub fn data(&self) -> &[u8] {
         (*self.data).as_ref()
     }
@@ -78,6 +128,39 @@ impl Font {
         self.rustybuzz.borrow_dependent()
     }
 
+    /// Get the decoration metrics from the `post` and `OS/2` tables of the font, with common
+    /// typographic defaults for fonts that do not provide them
+    pub fn decoration_metrics(&self) -> DecorationMetrics {
+        let face = self.rustybuzz();
+        let upem = face.units_per_em() as f32;
+        let underline = face.underline_metrics();
+        let underline_thickness = underline
+            .map(|metrics| f32::from(metrics.thickness) / upem)
+            .filter(|&thickness| thickness > 0.0)
+            .unwrap_or(1.0 / 14.0);
+        let underline_position =
+            underline.map_or(-0.1, |metrics| f32::from(metrics.position) / upem);
+        let strikeout = face.strikeout_metrics();
+        let strikethrough_thickness = strikeout
+            .map(|metrics| f32::from(metrics.thickness) / upem)
+            .filter(|&thickness| thickness > 0.0)
+            .unwrap_or(underline_thickness);
+        let strikethrough_position = strikeout
+            .map(|metrics| f32::from(metrics.position) / upem)
+            .or_else(|| {
+                face.x_height()
+                    .map(|x_height| (f32::from(x_height) / upem + strikethrough_thickness) / 2.0)
+            })
+            .unwrap_or(0.3);
+        DecorationMetrics {
+            underline_position,
+            underline_thickness,
+            strikethrough_position,
+            strikethrough_thickness,
+            overline_position: f32::from(face.ascender()) / upem,
+        }
+    }
+
     #[cfg(feature = "swash")]
     pub fn as_swash(&self) -> swash::FontRef<'_> {
         let swash = &self.swash;
@@ -91,7 +174,16 @@ impl Font {
 
 impl Font {
     pub fn new(db: &fontdb::Database, id: fontdb::ID) -> Option<Self> {
-        let info = db.face(id)?;
+        Self::try_new(db, id).ok()
+    }
+
+    /// Load the font of `id` like [`Self::new`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::FontParse`] if the face is not in `db` or its data cannot be parsed
+    pub fn try_new(db: &fontdb::Database, id: fontdb::ID) -> Result<Self, Error> {
+        let info = db.face(id).ok_or(Error::FontParse(None))?;
 
         let monospace_fallback = if cfg!(feature = "monospace_fallback") {
             db.with_face_data(id, |font_data, face_index| {
@@ -141,7 +233,8 @@ impl Font {
                     scripts,
                     unicode_codepoints,
                 })
-            })?
+            })
+            .ok_or(Error::FontParse(Some(id)))?
         } else {
             None
         };
@@ -151,24 +244,36 @@ impl Font {
             #[cfg(feature = "std")]
             fontdb::Source::File(path) => {
                 log::warn!("Unsupported fontdb Source::File('{}')", path.display());
-                return None;
+                return Err(Error::FontParse(Some(id)));
             }
             #[cfg(feature = "std")]
             fontdb::Source::SharedFile(_path, data) => Arc::clone(data),
         };
 
-        Some(Self {
+        let rustybuzz = OwnedFace::try_new(Arc::clone(&data), |data| {
+            RustybuzzFace::from_slice((**data).as_ref(), info.index).ok_or(())
+        })
+        .map_err(|()| Error::FontParse(Some(id)))?;
+        let variation_axes = rustybuzz
+            .borrow_dependent()
+            .variation_axes()
+            .into_iter()
+            .collect();
+        let named_instances = instance::named_instances(rustybuzz.borrow_dependent());
+
+        Ok(Self {
+            index: info.index,
             id: info.id,
             monospace_fallback,
+            variation_axes,
+            named_instances,
             #[cfg(feature = "swash")]
             swash: {
-                let swash = swash::FontRef::from_index((*data).as_ref(), info.index as usize)?;
+                let swash = swash::FontRef::from_index((*data).as_ref(), info.index as usize)
+                    .ok_or(Error::FontParse(Some(id)))?;
                 (swash.offset, swash.key)
             },
-            rustybuzz: OwnedFace::try_new(Arc::clone(&data), |data| {
-                RustybuzzFace::from_slice((**data).as_ref(), info.index).ok_or(())
-            })
-            .ok()?,
+            rustybuzz,
             data,
         })
     }
@@ -1,5 +1,8 @@
-use crate::{Attrs, Font, FontMatchAttrs, HashMap, ShapeBuffer};
-use alloc::collections::BTreeSet;
+use crate::{
+    math, Attrs, CacheKeyFlags, Error, Font, FontCandidateAttrs, FontMatchAttrs, FontVariation,
+    FontVariationsId, HashMap, NamedInstance, ShapeBuffer,
+};
+use alloc::collections::{BTreeMap, BTreeSet};
 use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
@@ -10,13 +13,41 @@ use core::ops::{Deref, DerefMut};
 pub use fontdb;
 pub use rustybuzz;
 
-use super::fallback::MonospaceFallbackInfo;
+use super::compensation::AdvanceCompensation;
+use super::fallback::{FallbackMemo, MonospaceFallbackInfo};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct FontMatchKey {
     pub(crate) font_weight_diff: u16,
     pub(crate) font_weight: u16,
     pub(crate) id: fontdb::ID,
+    pub(crate) synthesis: CacheKeyFlags,
+}
+
+impl FontMatchKey {
+    /// Get the flags synthesizing styles the face lacks, applied to its glyphs when font
+    /// synthesis is enabled, see [`FontSystem::set_font_synthesis`]
+    pub fn synthesis(&self) -> CacheKeyFlags {
+        self.synthesis
+    }
+}
+
+/// Whether a face with `weight`, or the range of its `wght` axis, is too light for the requested
+/// weight so bold must be synthesized
+fn needs_fake_bold(requested: u16, weight: u16, weight_range: Option<(u16, u16)>) -> bool {
+    let bold = fontdb::Weight::SEMIBOLD.0;
+    requested >= bold && weight < bold && weight_range.map_or(true, |(_, max)| max < bold)
+}
+
+/// A face that may match a set of attributes, see [`FontSystem::get_font_matches`]
+#[derive(Debug, Clone, Copy)]
+struct FontMatchCandidate {
+    weight: u16,
+    /// Range of the `wght` axis if this is a variable font of the requested family
+    weight_range: Option<(u16, u16)>,
+    /// Whether the face is of the requested family
+    is_family: bool,
+    id: fontdb::ID,
 }
 
 struct FontCachedCodepointSupportInfo {
@@ -78,14 +109,45 @@ impl FontCachedCodepointSupportInfo {
     }
 }
 
+/// Font size and cell size of a monospace grid, see [`FontSystem::snap_mono_metrics`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MonoMetrics {
+    /// Font size in logical pixels
+    pub font_size: f32,
+    /// Width of a cell in logical pixels, the advance of the monospace font
+    pub cell_width: f32,
+    /// Height of a cell in logical pixels, used as the line height
+    pub cell_height: f32,
+}
+
+impl MonoMetrics {
+    /// Get the [`crate::Metrics`] with the font size and cell height
+    pub fn metrics(&self) -> crate::Metrics {
+        crate::Metrics::new(self.font_size, self.cell_height)
+    }
+}
+
 /// Access to the system fonts.
 pub struct FontSystem {
-    /// The locale of the system.
-    locale: String,
+    /// The preferred locales of the system, in order of priority.
+    locales: Vec<String>,
+
+    /// Number of fallback fonts tried while shaping, wrapping on overflow, for
+    /// [`crate::LineProfile`].
+    pub(crate) fallback_iterations: u32,
 
     /// The underlying font database.
     db: fontdb::Database,
 
+    /// Substitute families for family names, keyed by lowercase family name.
+    family_aliases: BTreeMap<String, Vec<String>>,
+
+    /// Scale advances of substitute families to match the widths of the missing family.
+    metric_compensation: bool,
+
+    /// Synthesize bold for faces lacking the requested weight.
+    font_synthesis: bool,
+
     /// Cache for loaded fonts from the database.
     font_cache: HashMap<fontdb::ID, Option<Arc<Font>>>,
 
@@ -103,9 +165,15 @@ pub struct FontSystem {
     /// Cache for font matches.
     font_matches_cache: HashMap<FontMatchAttrs, Arc<Vec<FontMatchKey>>>,
 
+    /// Cache for faces matching family, stretch, and style, shared by all weights.
+    font_candidates_cache: HashMap<FontCandidateAttrs, Arc<Vec<FontMatchCandidate>>>,
+
     /// Scratch buffer for shaping and laying out.
     pub(crate) shape_buffer: ShapeBuffer,
 
+    /// Memo of fonts chosen by `FontFallbackIter`.
+    pub(crate) fallback_memo: FallbackMemo,
+
     /// Buffer for use in FontFallbackIter.
     pub(crate) monospace_fallbacks_buffer: BTreeSet<MonospaceFallbackInfo>,
 
@@ -117,7 +185,7 @@ pub struct FontSystem {
 impl fmt::Debug for FontSystem {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("FontSystem")
-            .field("locale", &self.locale)
+            .field("locales", &self.locales)
             .field("db", &self.db)
             .finish()
     }
@@ -138,8 +206,8 @@ impl FontSystem {
 
     /// Create a new [`FontSystem`] with a pre-specified set of fonts.
     pub fn new_with_fonts(fonts: impl IntoIterator<Item = fontdb::Source>) -> Self {
-        let locale = Self::get_locale();
-        log::debug!("Locale: {}", locale);
+        let locales = Self::get_locales();
+        log::debug!("Locales: {:?}", locales);
 
         let mut db = fontdb::Database::new();
 
@@ -150,7 +218,9 @@ impl FontSystem {
 
         Self::load_fonts(&mut db, fonts.into_iter());
 
-        Self::new_with_locale_and_db(locale, db)
+        let mut font_system = Self::new_with_locale_and_db(locales[0].clone(), db);
+        font_system.locales = locales;
+        font_system
     }
 
     /// Create a new [`FontSystem`] with a pre-specified locale and font database.
@@ -193,14 +263,20 @@ impl FontSystem {
             .collect();
 
         Self {
-            locale,
+            locales: Vec::from([locale]),
+            fallback_iterations: 0,
             db,
+            family_aliases: BTreeMap::new(),
+            metric_compensation: false,
+            font_synthesis: false,
             monospace_font_ids,
             per_script_monospace_font_ids,
             font_cache: Default::default(),
             font_matches_cache: Default::default(),
+            font_candidates_cache: Default::default(),
             font_codepoint_support_info_cache: Default::default(),
             monospace_fallbacks_buffer: BTreeSet::default(),
+            fallback_memo: FallbackMemo::default(),
             #[cfg(feature = "shape-run-cache")]
             shape_run_cache: crate::ShapeRunCache::default(),
             shape_buffer: ShapeBuffer::default(),
@@ -208,8 +284,54 @@ impl FontSystem {
     }
 
     /// Get the locale.
+    ///
+    /// This is the most preferred locale, see [`Self::locales`].
     pub fn locale(&self) -> &str {
-        &self.locale
+        &self.locales[0]
+    }
+
+    /// Get the memo of fonts chosen for runs, along with the locale its keys include, which is
+    /// `locale_opt` or [`Self::locale`]
+    pub(crate) fn fallback_memo_mut<'a>(
+        &'a mut self,
+        locale_opt: Option<&'a str>,
+    ) -> (&'a mut FallbackMemo, &'a str) {
+        let locale = locale_opt.unwrap_or(&self.locales[0]);
+        (&mut self.fallback_memo, locale)
+    }
+
+    /// Get the preferred locales, in order of priority.
+    ///
+    /// Script fallback uses the fonts preferred by each locale in turn. The list is initialized
+    /// from the `LANGUAGE` environment variable followed by the system locale.
+    pub fn locales(&self) -> &[String] {
+        &self.locales
+    }
+
+    /// Set the preferred locales, in order of priority. An empty list is ignored.
+    ///
+    /// Buffers that have already been shaped must be reshaped for this to take effect.
+    pub fn set_locales(&mut self, locales: Vec<String>) {
+        if locales.is_empty() || locales == self.locales {
+            return;
+        }
+        self.locales = locales;
+        self.fallback_memo.clear();
+        #[cfg(feature = "shape-run-cache")]
+        {
+            self.shape_run_cache = crate::ShapeRunCache::default();
+        }
+    }
+
+    /// Iterate over the locales used for script fallback, in order of priority, starting with
+    /// `locale_opt`
+    pub(crate) fn fallback_locales<'a>(
+        &'a self,
+        locale_opt: Option<&'a str>,
+    ) -> impl Iterator<Item = &'a str> {
+        locale_opt
+            .into_iter()
+            .chain(self.locales.iter().map(String::as_str))
     }
 
     /// Get the database.
@@ -220,12 +342,239 @@ impl FontSystem {
     /// Get a mutable reference to the database.
     pub fn db_mut(&mut self) -> &mut fontdb::Database {
         self.font_matches_cache.clear();
+        self.font_candidates_cache.clear();
+        self.fallback_memo.clear();
         &mut self.db
     }
 
+    /// Add `substitute` as an alias for the `family` font family
+    ///
+    /// When no font of `family` is installed, the substitutes of `family` are tried in the order
+    /// they were added, before falling back to fonts for the script of the text. Family names are
+    /// compared case insensitively. Buffers that have already been shaped must be reshaped for
+    /// this to take effect.
+    pub fn add_family_alias(&mut self, family: &str, substitute: &str) {
+        let substitutes = self
+            .family_aliases
+            .entry(family.to_lowercase())
+            .or_default();
+        if !substitutes.iter().any(|name| name == substitute) {
+            substitutes.push(substitute.into());
+            self.fallback_memo.clear();
+            #[cfg(feature = "shape-run-cache")]
+            {
+                self.shape_run_cache = crate::ShapeRunCache::default();
+            }
+        }
+    }
+
+    /// Remove all aliases for the `family` font family
+    pub fn remove_family_aliases(&mut self, family: &str) {
+        if self.family_aliases.remove(&family.to_lowercase()).is_some() {
+            self.fallback_memo.clear();
+            #[cfg(feature = "shape-run-cache")]
+            {
+                self.shape_run_cache = crate::ShapeRunCache::default();
+            }
+        }
+    }
+
+    /// Get the substitutes for the `family` font family, in order of priority
+    pub fn family_substitutes(&self, family: &str) -> &[String] {
+        self.family_aliases
+            .get(&family.to_lowercase())
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Iterate over all aliases as pairs of lowercase family name and substitute, sorted by
+    /// family name. Passing these to [`Self::add_family_alias`] restores the aliases
+    pub fn family_aliases(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.family_aliases
+            .iter()
+            .flat_map(|(family, substitutes)| {
+                substitutes
+                    .iter()
+                    .map(move |substitute| (family.as_str(), substitute.as_str()))
+            })
+    }
+
+    /// Get whether advances of substitute families are compensated
+    pub fn metric_compensation(&self) -> bool {
+        self.metric_compensation
+    }
+
+    /// Enable or disable compensating advances of substitute families, see
+    /// [`Self::add_family_alias`]
+    ///
+    /// When enabled and a substitute is used for a missing family with known published widths,
+    /// like Helvetica, Arial, Times New Roman, or Courier New, glyph advances are adjusted to the
+    /// widths of the missing family. This reduces reflow of documents shared across systems.
+    /// Buffers that have already been shaped must be reshaped for this to take effect.
+    pub fn set_metric_compensation(&mut self, metric_compensation: bool) {
+        if metric_compensation != self.metric_compensation {
+            self.metric_compensation = metric_compensation;
+            #[cfg(feature = "shape-run-cache")]
+            {
+                self.shape_run_cache = crate::ShapeRunCache::default();
+            }
+        }
+    }
+
+    /// Get whether styles missing from matched faces are synthesized
+    pub fn font_synthesis(&self) -> bool {
+        self.font_synthesis
+    }
+
+    /// Enable or disable synthesizing styles missing from matched faces
+    ///
+    /// When enabled and a bold weight is requested but the matched face is lighter, without a
+    /// `wght` axis or named instance reaching it, its glyphs get [`CacheKeyFlags::FAKE_BOLD`] so
+    /// their outlines are emboldened when rendered. [`FontMatchKey::synthesis`] reports the flags
+    /// applied to each matched face. Buffers that have already been shaped must be reshaped for
+    /// this to take effect.
+    pub fn set_font_synthesis(&mut self, font_synthesis: bool) {
+        if font_synthesis != self.font_synthesis {
+            self.font_synthesis = font_synthesis;
+            self.font_matches_cache.clear();
+            #[cfg(feature = "shape-run-cache")]
+            {
+                self.shape_run_cache = crate::ShapeRunCache::default();
+            }
+        }
+    }
+
+    /// Get the flags synthesizing styles the font with `id` lacks for `attrs`, if font synthesis
+    /// is enabled
+    pub(crate) fn synthesis_flags(&mut self, id: fontdb::ID, attrs: &Attrs) -> CacheKeyFlags {
+        let mut flags = CacheKeyFlags::empty();
+        if !self.font_synthesis || attrs.weight < fontdb::Weight::SEMIBOLD {
+            return flags;
+        }
+        let Some(weight) = self.db.face(id).map(|face| face.weight.0) else {
+            return flags;
+        };
+        let Some(font) = self.get_font(id) else {
+            return flags;
+        };
+        let has_named_instance = attrs
+            .named_instance_opt
+            .map_or(false, |name| font.named_instance(name).is_some());
+        let weight_range = font
+            .weight_axis()
+            .map(|axis| (axis.min_value as u16, axis.max_value as u16));
+        if !has_named_instance && needs_fake_bold(attrs.weight.0, weight, weight_range) {
+            flags |= CacheKeyFlags::FAKE_BOLD;
+        }
+        flags
+    }
+
+    /// Get the advance compensation for `font` if it substitutes the missing `family`
+    pub(crate) fn advance_compensation(
+        &self,
+        family: &fontdb::Family<'_>,
+        font: &Font,
+    ) -> Option<AdvanceCompensation> {
+        if !self.metric_compensation {
+            return None;
+        }
+        let fontdb::Family::Name(family) = family else {
+            return None;
+        };
+        let face = self.db.face(font.id())?;
+        let substitutes = self.family_substitutes(family);
+        let substituted = face
+            .families
+            .iter()
+            .any(|(name, _)| substitutes.contains(name) && !name.eq_ignore_ascii_case(family));
+        if !substituted {
+            return None;
+        }
+        AdvanceCompensation::new(family, font)
+    }
+
+    /// Get the values of the variable font axes of glyphs with `id`, see
+    /// [`crate::LayoutGlyph::font_variations`]
+    ///
+    /// These only include the axes of the font of the glyph, with values other than their defaults
+    /// and clamped to their ranges, including `wght` if [`crate::Attrs::weight`] changes it.
+    pub fn font_variations(&self, id: FontVariationsId) -> &[FontVariation] {
+        self.shape_buffer.instances.variations(id)
+    }
+
+    /// Get the values of [`Self::font_variations`] as a shared slice
+    #[cfg(feature = "swash")]
+    pub(crate) fn font_variations_arc(&self, id: FontVariationsId) -> Arc<[FontVariation]> {
+        Arc::clone(self.shape_buffer.instances.variations(id))
+    }
+
     /// Consume this [`FontSystem`] and return the locale and database.
-    pub fn into_locale_and_db(self) -> (String, fontdb::Database) {
-        (self.locale, self.db)
+    pub fn into_locale_and_db(mut self) -> (String, fontdb::Database) {
+        (self.locales.swap_remove(0), self.db)
+    }
+
+    /// Get a font by its ID, like [`Self::get_font`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::FontParse`] if the font cannot be loaded
+    pub fn try_get_font(&mut self, id: fontdb::ID) -> Result<Arc<Font>, Error> {
+        self.get_font(id).ok_or(Error::FontParse(Some(id)))
+    }
+
+    /// Get the named instances of the variable font with `id`, like `Condensed SemiBold`, to use
+    /// with [`crate::Attrs::named_instance`]. Fonts that are not variable or cannot be loaded
+    /// have none
+    pub fn named_instances(&mut self, id: fontdb::ID) -> Vec<NamedInstance> {
+        self.get_font(id)
+            .map(|font| font.named_instances().to_vec())
+            .unwrap_or_default()
+    }
+
+    /// Load the fonts in `data`, returning their IDs
+    ///
+    /// Faces that are found in the data but cannot be loaded are removed from the database.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::FontParse`] with the first face that failed if no face can be loaded
+    pub fn load_font_data(&mut self, data: Vec<u8>) -> Result<Vec<fontdb::ID>, Error> {
+        let ids = self
+            .db_mut()
+            .load_font_source(fontdb::Source::Binary(Arc::new(data)));
+        let mut loaded = Vec::with_capacity(ids.len());
+        let mut error_opt = None;
+        for id in ids {
+            match Font::try_new(&self.db, id) {
+                Ok(font) => {
+                    self.font_cache.insert(id, Some(Arc::new(font)));
+                    loaded.push(id);
+                }
+                Err(err) => {
+                    log::warn!("{}", err);
+                    self.db.remove_face(id);
+                    error_opt.get_or_insert(err);
+                }
+            }
+        }
+        if loaded.is_empty() {
+            return Err(error_opt.unwrap_or(Error::FontParse(None)));
+        }
+        Ok(loaded)
+    }
+
+    /// Read the file at `path` and load its fonts, see [`Self::load_font_data`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::IoError`] if the file cannot be read, or [`Error::FontParse`] if no face
+    /// can be loaded
+    #[cfg(feature = "std")]
+    pub fn load_font_file<P: AsRef<std::path::Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<Vec<fontdb::ID>, Error> {
+        let data = std::fs::read(path)?;
+        self.load_font_data(data)
     }
 
     /// Get a font by its ID.
@@ -251,6 +600,42 @@ impl FontSystem {
             .clone()
     }
 
+    /// Find the font size nearest to `font_size` at which cells of the monospace `family` are a
+    /// whole number of device pixels wide at `scale_factor`, so grids like terminals render
+    /// crisply. The cell height is the line height of the font rounded to whole device pixels.
+    ///
+    /// Returns `None` if the regular face of `family` is not found or is not monospace.
+    pub fn snap_mono_metrics(
+        &mut self,
+        family: fontdb::Family<'_>,
+        font_size: f32,
+        scale_factor: f32,
+    ) -> Option<MonoMetrics> {
+        let id = self.db.query(&fontdb::Query {
+            families: &[family],
+            ..fontdb::Query::default()
+        })?;
+        if !self.db.face(id)?.monospaced {
+            return None;
+        }
+        let font = self.get_font(id)?;
+        let face = font.rustybuzz();
+        let upem = face.units_per_em() as f32;
+        let em_width = f32::from(face.glyph_hor_advance(face.glyph_index(' ')?)?) / upem;
+        let em_height = (f32::from(face.ascender()) - f32::from(face.descender())
+            + f32::from(face.line_gap()))
+            / upem;
+
+        let device_width = math::roundf(font_size * scale_factor * em_width).max(1.0);
+        let font_size = device_width / (scale_factor * em_width);
+        let device_height = math::roundf(font_size * scale_factor * em_height).max(1.0);
+        Some(MonoMetrics {
+            font_size,
+            cell_width: device_width / scale_factor,
+            cell_height: device_height / scale_factor,
+        })
+    }
+
     pub fn is_monospace(&self, id: fontdb::ID) -> bool {
         self.monospace_font_ids.binary_search(&id).is_ok()
     }
@@ -286,6 +671,10 @@ impl FontSystem {
         })
     }
 
+    /// Get the faces matching `attrs`, sorted by how close their weight is to the requested weight
+    ///
+    /// Variable fonts of the requested family match any weight within the range of their `wght`
+    /// axis exactly, the weight is applied when shaping and rendering.
     pub fn get_font_matches(&mut self, attrs: Attrs<'_>) -> Arc<Vec<FontMatchKey>> {
         // Clear the cache first if it reached the size limit
         if self.font_matches_cache.len() >= Self::FONT_MATCHES_CACHE_SIZE_LIMIT {
@@ -293,36 +682,100 @@ impl FontSystem {
             self.font_matches_cache.clear();
         }
 
-        self.font_matches_cache
-            //TODO: do not create AttrsOwned unless entry does not already exist
-            .entry(attrs.into())
-            .or_insert_with(|| {
-                #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
-                let now = std::time::Instant::now();
-
-                let mut font_match_keys = self
-                    .db
-                    .faces()
-                    .filter(|face| attrs.matches(face))
-                    .map(|face| FontMatchKey {
-                        font_weight_diff: attrs.weight.0.abs_diff(face.weight.0),
-                        font_weight: face.weight.0,
-                        id: face.id,
-                    })
-                    .collect::<Vec<_>>();
-
-                // Sort so we get the keys with weight_offset=0 first
-                font_match_keys.sort();
-
-                #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+        let key = FontMatchAttrs::from(attrs);
+        if let Some(font_match_keys) = self.font_matches_cache.get(&key) {
+            return font_match_keys.clone();
+        }
+
+        let candidates = self.get_font_match_candidates(attrs, &key);
+        let mut font_match_keys = Vec::with_capacity(candidates.len());
+        for candidate in candidates.iter() {
+            // Fonts of the family with the named instance match it exactly
+            let has_named_instance = candidate.is_family
+                && attrs.named_instance_opt.map_or(false, |name| {
+                    self.get_font(candidate.id)
+                        .map_or(false, |font| font.named_instance(name).is_some())
+                });
+            font_match_keys.push(FontMatchKey {
+                font_weight_diff: match candidate.weight_range {
+                    _ if has_named_instance => 0,
+                    Some((min, max)) if (min..=max).contains(&attrs.weight.0) => 0,
+                    _ => attrs.weight.0.abs_diff(candidate.weight),
+                },
+                font_weight: candidate.weight,
+                id: candidate.id,
+                synthesis: if self.font_synthesis
+                    && !has_named_instance
+                    && needs_fake_bold(attrs.weight.0, candidate.weight, candidate.weight_range)
                 {
-                    let elapsed = now.elapsed();
-                    log::debug!("font matches for {:?} in {:?}", attrs, elapsed);
-                }
+                    CacheKeyFlags::FAKE_BOLD
+                } else {
+                    CacheKeyFlags::empty()
+                },
+            });
+        }
+
+        // Sort so we get the keys with weight_offset=0 first
+        font_match_keys.sort();
+
+        let font_match_keys = Arc::new(font_match_keys);
+        self.font_matches_cache.insert(key, font_match_keys.clone());
+        font_match_keys
+    }
+
+    fn get_font_match_candidates(
+        &mut self,
+        attrs: Attrs<'_>,
+        key: &FontMatchAttrs,
+    ) -> Arc<Vec<FontMatchCandidate>> {
+        if self.font_candidates_cache.len() >= Self::FONT_MATCHES_CACHE_SIZE_LIMIT {
+            self.font_candidates_cache.clear();
+        }
+
+        let key = FontCandidateAttrs::from(key);
+        if let Some(candidates) = self.font_candidates_cache.get(&key) {
+            return candidates.clone();
+        }
 
-                Arc::new(font_match_keys)
+        #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+        let now = std::time::Instant::now();
+
+        let family_name = self.db.family_name(&attrs.family);
+        let mut candidates = self
+            .db
+            .faces()
+            .filter(|face| attrs.matches(face))
+            .map(|face| {
+                let is_family = face.families.iter().any(|(name, _)| name == family_name);
+                (face.id, face.weight.0, is_family)
             })
-            .clone()
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|(id, weight, is_family)| FontMatchCandidate {
+                weight,
+                // Only fonts of the requested family are loaded to check for a weight axis
+                weight_range: if is_family {
+                    self.get_font(id)
+                        .and_then(|font| font.weight_axis())
+                        .map(|axis| (axis.min_value as u16, axis.max_value as u16))
+                } else {
+                    None
+                },
+                is_family,
+                id,
+            })
+            .collect::<Vec<_>>();
+        candidates.shrink_to_fit();
+
+        #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+        {
+            let elapsed = now.elapsed();
+            log::debug!("font matches for {:?} in {:?}", attrs, elapsed);
+        }
+
+        let candidates = Arc::new(candidates);
+        self.font_candidates_cache.insert(key, candidates.clone());
+        candidates
     }
 
     #[cfg(feature = "std")]
@@ -338,6 +791,31 @@ impl FontSystem {
         String::from("en-US")
     }
 
+    /// Get the preferred locales from the colon separated `LANGUAGE` environment variable,
+    /// followed by the system locale
+    fn get_locales() -> Vec<String> {
+        let mut locales = Vec::new();
+        #[cfg(feature = "std")]
+        if let Ok(language) = std::env::var("LANGUAGE") {
+            for entry in language.split(':') {
+                // Convert POSIX locales like "pt_BR.UTF-8@euro" to language tags like "pt-BR"
+                let tag = entry
+                    .split(['.', '@'])
+                    .next()
+                    .unwrap_or_default()
+                    .replace('_', "-");
+                if !tag.is_empty() && tag != "C" && tag != "POSIX" && !locales.contains(&tag) {
+                    locales.push(tag);
+                }
+            }
+        }
+        let locale = Self::get_locale();
+        if !locales.contains(&locale) {
+            locales.push(locale);
+        }
+        locales
+    }
+
     #[cfg(feature = "std")]
     fn load_fonts(db: &mut fontdb::Database, fonts: impl Iterator<Item = fontdb::Source>) {
         #[cfg(not(target_arch = "wasm32"))]
@@ -1,12 +1,38 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
+use crate::FontVariationsId;
+
 bitflags::bitflags! {
     /// Flags that change rendering
+    ///
+    /// The low 16 bits are used by cosmic-text. The high 16 bits, [`CacheKeyFlags::USER`], are
+    /// reserved for applications, for example to keep glyphs for different atlas pages or effects
+    /// apart. They are never set or cleared by cosmic-text, and flow unchanged from
+    /// [`crate::Attrs::cache_key_flags`] to [`crate::LayoutGlyph::cache_key_flags`] and
+    /// [`CacheKey::flags`]. This layout will only change in a semver-breaking release.
     #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
     #[repr(transparent)]
     pub struct CacheKeyFlags: u32 {
         /// Skew by 14 degrees to synthesize italic
         const FAKE_ITALIC = 1;
+        /// Rotate 90 degrees clockwise, for sideways glyphs of [`crate::Direction::Vertical`] text
+        const ROTATE_CW = 2;
+        /// Embolden outlines to synthesize bold, see [`crate::FontSystem::set_font_synthesis`]
+        const FAKE_BOLD = 4;
+        /// Bits reserved for applications, see [`CacheKeyFlags::user`]
+        const USER = 0xFFFF_0000;
+    }
+}
+
+impl CacheKeyFlags {
+    /// Create flags with the application bits in [`Self::USER`] set to `bits`
+    pub const fn user(bits: u16) -> Self {
+        Self::from_bits_retain((bits as u32) << 16)
+    }
+
+    /// Get the application bits in [`Self::USER`]
+    pub const fn user_bits(self) -> u16 {
+        (self.bits() >> 16) as u16
     }
 }
 
@@ -19,6 +45,11 @@ pub struct CacheKey {
     pub glyph_id: u16,
     /// `f32` bits of font size
     pub font_size_bits: u32,
+    /// Font weight, applied to the `wght` axis of variable fonts. Always
+    /// [`fontdb::Weight::NORMAL`] for fonts without one, so their glyphs are cached once
+    pub font_weight: fontdb::Weight,
+    /// Values of other variable font axes, see [`crate::FontSystem::font_variations`]
+    pub font_variations: FontVariationsId,
     /// Binning of fractional X offset
     pub x_bin: SubpixelBin,
     /// Binning of fractional Y offset
@@ -34,6 +65,27 @@ impl CacheKey {
         font_size: f32,
         pos: (f32, f32),
         flags: CacheKeyFlags,
+    ) -> (Self, i32, i32) {
+        Self::new_variable(
+            font_id,
+            glyph_id,
+            font_size,
+            pos,
+            fontdb::Weight::NORMAL,
+            FontVariationsId::default(),
+            flags,
+        )
+    }
+
+    /// Like [`Self::new`], with the weight and variations of a variable font
+    pub fn new_variable(
+        font_id: fontdb::ID,
+        glyph_id: u16,
+        font_size: f32,
+        pos: (f32, f32),
+        font_weight: fontdb::Weight,
+        font_variations: FontVariationsId,
+        flags: CacheKeyFlags,
     ) -> (Self, i32, i32) {
         let (x, x_bin) = SubpixelBin::new(pos.0);
         let (y, y_bin) = SubpixelBin::new(pos.1);
@@ -42,6 +94,8 @@ impl CacheKey {
                 font_id,
                 glyph_id,
                 font_size_bits: font_size.to_bits(),
+                font_weight,
+                font_variations,
                 x_bin,
                 y_bin,
                 flags,
@@ -0,0 +1,328 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::{fmt, ops::Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Splits a line of text into words for word motions like [`crate::Motion::NextWord`]
+///
+/// Set a custom segmenter with [`crate::Buffer::set_word_segmenter`], for example to use
+/// dictionary based segmentation for languages written without spaces.
+pub trait WordSegmenter: fmt::Debug + Send + Sync {
+    /// Append the byte ranges of the words of `text` to `words`, in order and without overlaps.
+    ///
+    /// `locale` is the language tag of the line set with [`crate::Buffer::set_locale`], if any.
+    fn segment(&self, text: &str, locale: Option<&str>, words: &mut Vec<Range<usize>>);
+}
+
+/// The default [`WordSegmenter`], following the Unicode word boundary rules of UAX #29
+///
+/// Every Han ideograph and Hiragana character is a word of its own, while runs of Katakana and
+/// of letters and digits of alphabetic scripts form single words.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct UnicodeWordSegmenter;
+
+impl WordSegmenter for UnicodeWordSegmenter {
+    fn segment(&self, text: &str, _locale: Option<&str>, words: &mut Vec<Range<usize>>) {
+        words.extend(
+            text.unicode_word_indices()
+                .map(|(i, word)| i..i + word.len()),
+        );
+    }
+}
+
+/// Iterate over the extended grapheme clusters of `text`, with their byte offsets
+///
+/// All grapheme segmentation goes through this function so editing, hit testing, and shaping
+/// agree on cluster boundaries.
+pub(crate) fn grapheme_indices(text: &str) -> unicode_segmentation::GraphemeIndices<'_> {
+    text.grapheme_indices(true)
+}
+
+/// Returns true if the grapheme cluster `egc` is displayed as an emoji rather than as text
+///
+/// Variation selectors override the default presentation, and emoji modifier, keycap, flag, and
+/// zero width joiner sequences are always emoji.
+pub(crate) fn is_emoji_presentation(egc: &str) -> bool {
+    let mut chars = egc.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+    let mut emoji = is_default_emoji(first);
+    for c in chars {
+        match c {
+            // Text presentation selector
+            '\u{FE0E}' => return false,
+            // Emoji presentation selector and combining enclosing keycap
+            '\u{FE0F}' | '\u{20E3}' => emoji = true,
+            // Emoji modifiers and characters joined by zero width joiners
+            '\u{1F3FB}'..='\u{1F3FF}' => emoji = true,
+            c if is_default_emoji(c) => emoji = true,
+            _ => (),
+        }
+    }
+    emoji
+}
+
+/// Returns true if `c` has the `Emoji_Presentation` property
+fn is_default_emoji(c: char) -> bool {
+    matches!(
+        c,
+        '\u{231A}'..='\u{231B}'
+            | '\u{23E9}'..='\u{23EC}'
+            | '\u{23F0}'
+            | '\u{23F3}'
+            | '\u{25FD}'..='\u{25FE}'
+            | '\u{2614}'..='\u{2615}'
+            | '\u{2648}'..='\u{2653}'
+            | '\u{267F}'
+            | '\u{2693}'
+            | '\u{26A1}'
+            | '\u{26AA}'..='\u{26AB}'
+            | '\u{26BD}'..='\u{26BE}'
+            | '\u{26C4}'..='\u{26C5}'
+            | '\u{26CE}'
+            | '\u{26D4}'
+            | '\u{26EA}'
+            | '\u{26F2}'..='\u{26F3}'
+            | '\u{26F5}'
+            | '\u{26FA}'
+            | '\u{26FD}'
+            | '\u{2705}'
+            | '\u{270A}'..='\u{270B}'
+            | '\u{2728}'
+            | '\u{274C}'
+            | '\u{274E}'
+            | '\u{2753}'..='\u{2755}'
+            | '\u{2757}'
+            | '\u{2795}'..='\u{2797}'
+            | '\u{27B0}'
+            | '\u{27BF}'
+            | '\u{2B1B}'..='\u{2B1C}'
+            | '\u{2B50}'
+            | '\u{2B55}'
+            | '\u{1F004}'
+            | '\u{1F0CF}'
+            | '\u{1F18E}'
+            | '\u{1F191}'..='\u{1F19A}'
+            | '\u{1F1E6}'..='\u{1F1FF}'
+            | '\u{1F201}'
+            | '\u{1F21A}'
+            | '\u{1F22F}'
+            | '\u{1F232}'..='\u{1F236}'
+            | '\u{1F238}'..='\u{1F23A}'
+            | '\u{1F250}'..='\u{1F251}'
+            | '\u{1F300}'..='\u{1F320}'
+            | '\u{1F32D}'..='\u{1F335}'
+            | '\u{1F337}'..='\u{1F37C}'
+            | '\u{1F37E}'..='\u{1F393}'
+            | '\u{1F3A0}'..='\u{1F3CA}'
+            | '\u{1F3CF}'..='\u{1F3D3}'
+            | '\u{1F3E0}'..='\u{1F3F0}'
+            | '\u{1F3F4}'
+            | '\u{1F3F8}'..='\u{1F43E}'
+            | '\u{1F440}'
+            | '\u{1F442}'..='\u{1F4FC}'
+            | '\u{1F4FF}'..='\u{1F53D}'
+            | '\u{1F54B}'..='\u{1F54E}'
+            | '\u{1F550}'..='\u{1F567}'
+            | '\u{1F57A}'
+            | '\u{1F595}'..='\u{1F596}'
+            | '\u{1F5A4}'
+            | '\u{1F5FB}'..='\u{1F64F}'
+            | '\u{1F680}'..='\u{1F6C5}'
+            | '\u{1F6CC}'
+            | '\u{1F6D0}'..='\u{1F6D2}'
+            | '\u{1F6D5}'..='\u{1F6D7}'
+            | '\u{1F6DC}'..='\u{1F6DF}'
+            | '\u{1F6EB}'..='\u{1F6EC}'
+            | '\u{1F6F4}'..='\u{1F6FC}'
+            | '\u{1F7E0}'..='\u{1F7EB}'
+            | '\u{1F7F0}'
+            | '\u{1F90C}'..='\u{1F93A}'
+            | '\u{1F93C}'..='\u{1F945}'
+            | '\u{1F947}'..='\u{1F9FF}'
+            | '\u{1FA70}'..='\u{1FAFF}'
+    )
+}
+
+/// Grapheme cluster and word boundaries of a line of text
+///
+/// Use [`crate::BufferLine::graphemes`] to access the cached boundaries of a line.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Graphemes {
+    /// Start of every grapheme cluster, followed by the length of the text
+    boundaries: Vec<usize>,
+    /// Byte ranges of words
+    words: Vec<Range<usize>>,
+}
+
+impl Graphemes {
+    /// Segment `text` into grapheme clusters and words
+    pub fn new(text: &str) -> Self {
+        let mut graphemes = Self::default();
+        graphemes.build(text);
+        graphemes
+    }
+
+    /// See [`Self::new`].
+    ///
+    /// Reuses as much of the pre-existing internal allocations as possible.
+    pub fn build(&mut self, text: &str) {
+        self.build_with_segmenter(text, &UnicodeWordSegmenter, None);
+    }
+
+    /// See [`Self::new`], splitting words with `segmenter` for text in `locale`
+    pub fn build_with_segmenter(
+        &mut self,
+        text: &str,
+        segmenter: &dyn WordSegmenter,
+        locale: Option<&str>,
+    ) {
+        self.boundaries.clear();
+        self.boundaries
+            .extend(grapheme_indices(text).map(|(i, _)| i));
+        self.boundaries.push(text.len());
+
+        self.words.clear();
+        segmenter.segment(text, locale, &mut self.words);
+    }
+
+    /// Start of every grapheme cluster, followed by the length of the text
+    pub fn boundaries(&self) -> &[usize] {
+        &self.boundaries
+    }
+
+    /// Byte ranges of words, following the Unicode word boundary rules unless another
+    /// [`WordSegmenter`] was used
+    pub fn words(&self) -> &[Range<usize>] {
+        &self.words
+    }
+
+    /// Iterate over the byte ranges of grapheme clusters
+    pub fn iter(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        self.boundaries.windows(2).map(|w| w[0]..w[1])
+    }
+
+    /// Iterate over the byte ranges of grapheme clusters within `range`, clipped to `range`
+    pub fn within(&self, range: Range<usize>) -> impl Iterator<Item = Range<usize>> + '_ {
+        // The first cluster ends after the start of the range, the last starts before its end
+        let first = self
+            .boundaries
+            .partition_point(|&i| i <= range.start)
+            .saturating_sub(1);
+        let last = self.boundaries.partition_point(|&i| i < range.end);
+        let end = (last + 1).min(self.boundaries.len());
+        self.boundaries[first.min(end)..end]
+            .windows(2)
+            .map(move |w| w[0].max(range.start)..w[1].min(range.end))
+    }
+
+    /// Get the grapheme cluster that contains `index`
+    pub fn containing(&self, index: usize) -> Option<Range<usize>> {
+        self.iter()
+            .find(|cluster| cluster.start <= index && index < cluster.end)
+    }
+
+    /// Get the start of the grapheme cluster before `index`, or 0 if there is none
+    pub fn prev(&self, index: usize) -> usize {
+        self.boundaries
+            .iter()
+            .rev()
+            .copied()
+            .find(|&i| i < index)
+            .unwrap_or(0)
+    }
+
+    /// Get the end of the grapheme cluster after `index`, or the length of the text if there is
+    /// none
+    pub fn next(&self, index: usize) -> usize {
+        let len = self.boundaries.last().copied().unwrap_or(0);
+        self.boundaries
+            .iter()
+            .copied()
+            .find(|&i| i > index)
+            .unwrap_or(len)
+    }
+
+    /// Get the start of the word before `index`, or 0 if there is none
+    pub fn prev_word(&self, index: usize) -> usize {
+        self.words
+            .iter()
+            .rev()
+            .map(|word| word.start)
+            .find(|&i| i < index)
+            .unwrap_or(0)
+    }
+
+    /// Get the end of the word after `index`, or the length of the text if there is none
+    pub fn next_word(&self, index: usize) -> usize {
+        let len = self.boundaries.last().copied().unwrap_or(0);
+        self.words
+            .iter()
+            .map(|word| word.end)
+            .find(|&i| i > index)
+            .unwrap_or(len)
+    }
+}
+
+#[test]
+fn test_graphemes() {
+    let graphemes = Graphemes::new("e\u{301}a bc");
+    assert_eq!(graphemes.boundaries(), &[0, 3, 4, 5, 6, 7]);
+    assert_eq!(graphemes.words(), &[0..4, 5..7]);
+    assert_eq!(
+        graphemes.within(1..5).collect::<Vec<_>>(),
+        [1..3, 3..4, 4..5]
+    );
+    assert_eq!(graphemes.containing(1), Some(0..3));
+    assert_eq!(graphemes.prev(3), 0);
+    assert_eq!(graphemes.next(1), 3);
+    assert_eq!(graphemes.next(7), 7);
+    assert_eq!(graphemes.prev_word(5), 0);
+    assert_eq!(graphemes.next_word(4), 7);
+}
+
+#[test]
+fn test_emoji_presentation() {
+    assert!(is_emoji_presentation("\u{1F600}"));
+    assert!(is_emoji_presentation("\u{1F44D}\u{1F3FD}"));
+    assert!(is_emoji_presentation("\u{1F1FA}\u{1F1F8}"));
+    assert!(is_emoji_presentation("1\u{FE0F}\u{20E3}"));
+    assert!(is_emoji_presentation("\u{2764}\u{FE0F}"));
+    assert!(!is_emoji_presentation("\u{2764}"));
+    assert!(!is_emoji_presentation("\u{231A}\u{FE0E}"));
+    assert!(!is_emoji_presentation("a"));
+    assert!(!is_emoji_presentation(""));
+}
+
+#[cfg(test)]
+mod test {
+    use super::Graphemes;
+
+    #[test]
+    fn graphemes_within() {
+        // Clusters of 1, 2, 3, and 4 bytes, then one of two code points
+        let text = "aé中😀e\u{301}";
+        let graphemes = Graphemes::new(text);
+        assert_eq!(graphemes.boundaries(), [0, 1, 3, 6, 10, 13]);
+        for start in 0..=text.len() {
+            for end in start..=text.len() {
+                let expected: Vec<_> = graphemes
+                    .iter()
+                    .skip_while(|cluster| cluster.end <= start)
+                    .take_while(|cluster| cluster.start < end)
+                    .map(|cluster| cluster.start.max(start)..cluster.end.min(end))
+                    .collect();
+                let within: Vec<_> = graphemes.within(start..end).collect();
+                assert_eq!(within, expected, "{start}..{end}");
+            }
+        }
+        assert_eq!(
+            graphemes.within(2..8).collect::<Vec<_>>(),
+            [2..3, 3..6, 6..8]
+        );
+        assert_eq!(Graphemes::default().within(0..1).count(), 0);
+    }
+}
@@ -5,15 +5,37 @@ use core::fmt::Display;
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 
-use crate::{math, CacheKey, CacheKeyFlags, Color};
+use crate::{
+    math, CacheKey, CacheKeyFlags, Color, DecorationKind, EmphasisMark, EmphasisShape, FontSystem,
+    FontVariationsId, Metadata, TextDecoration,
+};
+
+bitflags::bitflags! {
+    /// Packed attributes of a [`LayoutGlyph`]
+    #[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+    #[repr(transparent)]
+    pub struct GlyphFlags: u8 {
+        /// The glyph is for [`Whitespace::Space`]
+        const SPACE = 1;
+        /// The glyph is for [`Whitespace::Tab`]
+        const TAB = 2;
+        /// The glyph is for [`Whitespace::NoBreakSpace`]
+        const NO_BREAK_SPACE = 4;
+        /// The glyph is for [`Whitespace::LineEnding`]
+        const LINE_ENDING = 8;
+    }
+}
 
 /// A laid out glyph
+///
+/// See [`crate::MemoryUsage`] for the memory used by shaped buffers. Rarely set attributes are
+/// packed into [`Self::flags`] or kept in [`LayoutLine::extras`] to keep glyphs small.
 #[derive(Clone, Debug)]
 pub struct LayoutGlyph {
-    /// Start index of cluster in original line
-    pub start: usize,
+    /// Start index of cluster in original line, lines are limited to `u32::MAX` bytes
+    pub start: u32,
     /// End index of cluster in original line
-    pub end: usize,
+    pub end: u32,
     /// Font size of the glyph
     pub font_size: f32,
     /// Line height of the glyph, will override buffer setting
@@ -22,6 +44,11 @@ pub struct LayoutGlyph {
     pub font_id: fontdb::ID,
     /// Font id of the glyph
     pub glyph_id: u16,
+    /// Font weight of the glyph, applied to the `wght` axis of variable fonts, see
+    /// [`CacheKey::font_weight`]
+    pub font_weight: fontdb::Weight,
+    /// Values of the variable font axes of the glyph, see [`FontSystem::font_variations`]
+    pub font_variations: FontVariationsId,
     /// X offset of hitbox
     pub x: f32,
     /// Y offset of hitbox
@@ -51,9 +78,265 @@ pub struct LayoutGlyph {
     /// Optional color override
     pub color_opt: Option<Color>,
     /// Metadata from `Attrs`
-    pub metadata: usize,
+    pub metadata: Metadata,
     /// [`CacheKeyFlags`]
     pub cache_key_flags: CacheKeyFlags,
+    /// Decorations from `Attrs`, see [`crate::LayoutRun::decorations`]
+    pub decoration: TextDecoration,
+    /// [`GlyphFlags`]
+    pub flags: GlyphFlags,
+}
+
+// Keep the sizes documented on `MemoryUsage` up to date, as every character of a laid out buffer
+// has a glyph
+#[cfg(all(target_pointer_width = "64", not(feature = "u32-metadata")))]
+const _: () = assert!(core::mem::size_of::<LayoutGlyph>() == 80);
+#[cfg(all(target_pointer_width = "64", feature = "u32-metadata"))]
+const _: () = assert!(core::mem::size_of::<LayoutGlyph>() == 76);
+
+/// Kind of whitespace of a [`LayoutGlyph`]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Whitespace {
+    /// A space, or any other space separator except no-break spaces
+    Space,
+    /// A tab
+    Tab,
+    /// A no-break space, U+00A0 or U+202F
+    NoBreakSpace,
+    /// The line ending, shown by a glyph added after the last glyph of the line
+    LineEnding,
+}
+
+impl Whitespace {
+    /// Get the flag of a [`LayoutGlyph`] for this kind of whitespace
+    pub(crate) fn flag(self) -> GlyphFlags {
+        match self {
+            Self::Space => GlyphFlags::SPACE,
+            Self::Tab => GlyphFlags::TAB,
+            Self::NoBreakSpace => GlyphFlags::NO_BREAK_SPACE,
+            Self::LineEnding => GlyphFlags::LINE_ENDING,
+        }
+    }
+
+    /// Get the kind of whitespace of a cluster, if it is a single whitespace character
+    pub fn from_cluster(text: &str) -> Option<Self> {
+        let mut chars = text.chars();
+        match (chars.next()?, chars.next()) {
+            ('\t', None) => Some(Self::Tab),
+            ('\u{A0}' | '\u{202F}', None) => Some(Self::NoBreakSpace),
+            (c, None) if c.is_whitespace() && !c.is_control() => Some(Self::Space),
+            _ => None,
+        }
+    }
+
+    /// Get the character drawn in place of the whitespace when it is shown, pointing left in
+    /// right-to-left text
+    pub fn marker(self, rtl: bool) -> char {
+        match self {
+            Self::Space => '\u{B7}',
+            Self::Tab if rtl => '\u{2190}',
+            Self::Tab => '\u{2192}',
+            Self::NoBreakSpace => '\u{B0}',
+            Self::LineEnding => '\u{B6}',
+        }
+    }
+}
+
+/// Geometry of a [`LayoutGlyph`] of a [`crate::LayoutRun`], see
+/// [`crate::LayoutRun::glyph_metrics`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayoutGlyphMetrics {
+    /// Start index of the cluster of the glyph in the original line
+    pub start: usize,
+    /// End index of the cluster of the glyph in the original line
+    pub end: usize,
+    /// The pen position the glyph is drawn at, on the baseline
+    pub origin: (f32, f32),
+    /// The advance of the glyph, covering the height of the line
+    pub advance: Rect,
+    /// The bounding box of the outline of the glyph, None if it has no outline
+    pub ink_opt: Option<Rect>,
+}
+
+/// A filled background of a [`crate::LayoutRun`], see [`crate::LayoutRun::backgrounds`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayoutBackground {
+    /// X offset of the left edge
+    pub x: f32,
+    /// Y offset of the top edge, in the same coordinates as [`crate::LayoutRun::line_top`]
+    pub y: f32,
+    /// Width of the background
+    pub w: f32,
+    /// Height of the background
+    pub h: f32,
+    /// Color from [`crate::Attrs::background_color`]
+    pub color: Color,
+}
+
+/// A text decoration line of a [`crate::LayoutRun`], see [`crate::LayoutRun::decorations`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayoutDecoration {
+    /// The kind of decoration, a single flag
+    pub decoration: TextDecoration,
+    /// X offset of the left edge
+    pub x: f32,
+    /// Y offset of the top edge, in the same coordinates as [`crate::LayoutRun::line_y`]
+    pub y: f32,
+    /// Width of the line
+    pub w: f32,
+    /// Thickness of the line
+    pub h: f32,
+    /// Color override from [`crate::Attrs::decoration_color`] or [`crate::Attrs::color`]
+    pub color_opt: Option<Color>,
+}
+
+/// The shape of a [`crate::Decoration`] on a [`crate::LayoutRun`], see
+/// [`crate::LayoutRun::range_decorations`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayoutRangeDecoration {
+    /// Index of the decoration in the slice it was resolved from
+    pub index: usize,
+    /// How the decoration is drawn
+    pub kind: DecorationKind,
+    /// X offset of the left edge
+    pub x: f32,
+    /// Y offset of the top edge, in the same coordinates as [`crate::LayoutRun::line_top`]
+    pub y: f32,
+    /// Width of the decoration
+    pub w: f32,
+    /// Height of the decoration, the thickness of the line for [`DecorationKind::Underline`], or
+    /// the height of the wave for [`DecorationKind::Squiggle`]
+    pub h: f32,
+    /// Color of the decoration
+    pub color: Color,
+}
+
+impl LayoutRangeDecoration {
+    /// Draw the decoration with rectangles, using the same callback as [`crate::Buffer::draw`]
+    pub fn draw<F>(&self, mut f: F)
+    where
+        F: FnMut(i32, i32, u32, u32, Color),
+    {
+        let x = self.x as i32;
+        let y = self.y as i32;
+        let w = self.w as u32;
+        let h = core::cmp::max(1, math::roundf(self.h) as u32);
+        match self.kind {
+            DecorationKind::Underline | DecorationKind::Highlight => f(x, y, w, h, self.color),
+            DecorationKind::Squiggle => {
+                // Zigzag one third of the height thick, rising and falling every height
+                let thickness = core::cmp::max(1, h / 3);
+                let amplitude = h - thickness;
+                for i in 0..w {
+                    let phase = i % (amplitude * 2).max(1);
+                    let offset = if phase < amplitude {
+                        phase
+                    } else {
+                        amplitude * 2 - phase
+                    };
+                    f(x + i as i32, y + offset as i32, 1, thickness, self.color);
+                }
+            }
+            DecorationKind::Box => {
+                f(x, y, w, 1, self.color);
+                f(x, y + h as i32 - 1, w, 1, self.color);
+                f(x, y, 1, h, self.color);
+                f(x + w as i32 - 1, y, 1, h, self.color);
+            }
+        }
+    }
+}
+
+/// An emphasis mark over or under a cluster of a [`crate::LayoutRun`], see
+/// [`crate::LayoutRun::emphasis_marks`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayoutEmphasisMark {
+    /// Start index of the cluster in the original line
+    pub start: usize,
+    /// End index of the cluster in the original line
+    pub end: usize,
+    /// The mark to draw
+    pub mark: EmphasisMark,
+    /// X offset of the center of the mark
+    pub x: f32,
+    /// Y offset of the center of the mark, in the same coordinates as [`crate::LayoutRun::line_y`]
+    pub y: f32,
+    /// Width and height of the box the mark is drawn in
+    pub size: f32,
+    /// Color override from [`crate::Attrs::decoration_color`] or [`crate::Attrs::color`]
+    pub color_opt: Option<Color>,
+}
+
+impl LayoutEmphasisMark {
+    /// Draw the mark with rectangles, using the same callback as [`crate::Buffer::draw`] and
+    /// `color` if the mark has no color override
+    pub fn draw<F>(&self, color: Color, mut f: F)
+    where
+        F: FnMut(i32, i32, u32, u32, Color),
+    {
+        let color = self.color_opt.unwrap_or(color);
+        let outline = (self.size / 8.0).max(1.0);
+        let filled = self.mark.filled;
+        match self.mark.shape {
+            EmphasisShape::Dot => self.draw_circle(self.size * 0.4, filled, outline, color, &mut f),
+            EmphasisShape::Circle => {
+                self.draw_circle(self.size * 0.8, filled, outline, color, &mut f);
+            }
+            EmphasisShape::DoubleCircle => {
+                self.draw_circle(self.size * 0.8, false, outline, color, &mut f);
+                self.draw_circle(self.size * 0.4, filled, outline, color, &mut f);
+            }
+            EmphasisShape::Triangle => {
+                let h = math::roundf(self.size * 0.7).max(1.0) as i32;
+                let top = math::roundf(self.y - h as f32 / 2.0) as i32;
+                for row in 0..h {
+                    // Width grows from the apex down to the base
+                    let half = self.size * 0.4 * (row as f32 + 0.5) / h as f32;
+                    let left = math::roundf(self.x - half) as i32;
+                    let right = math::roundf(self.x + half) as i32;
+                    let y = top + row;
+                    if filled || h - row <= outline as i32 {
+                        f(left, y, (right - left).max(1) as u32, 1, color);
+                    } else {
+                        let edge = (outline as i32).min(right - left).max(1);
+                        f(left, y, edge as u32, 1, color);
+                        f(right - edge, y, edge as u32, 1, color);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Draw a circle of `diameter` around the center, or only its outline
+    fn draw_circle<F>(&self, diameter: f32, filled: bool, outline: f32, color: Color, f: &mut F)
+    where
+        F: FnMut(i32, i32, u32, u32, Color),
+    {
+        let radius = diameter.max(1.0) / 2.0;
+        let inner = radius - outline;
+        let rows = math::roundf(diameter).max(1.0) as i32;
+        let top = math::roundf(self.y - rows as f32 / 2.0) as i32;
+        for row in 0..rows {
+            let dy = row as f32 + 0.5 - rows as f32 / 2.0;
+            let half = math::sqrtf((radius * radius - dy * dy).max(0.0));
+            let left = math::roundf(self.x - half) as i32;
+            let right = math::roundf(self.x + half) as i32;
+            if right <= left {
+                continue;
+            }
+            let y = top + row;
+            if filled || dy.abs() >= inner {
+                f(left, y, (right - left) as u32, 1, color);
+            } else {
+                // Leave the inside of the ring empty
+                let inner_half = math::sqrtf(inner * inner - dy * dy);
+                let left_w = (math::roundf(self.x - inner_half) as i32 - left).max(1);
+                let right_w = (right - math::roundf(self.x + inner_half) as i32).max(1);
+                f(left, y, left_w as u32, 1, color);
+                f(right - right_w, y, right_w as u32, 1, color);
+            }
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -67,11 +350,66 @@ pub struct PhysicalGlyph {
 }
 
 impl LayoutGlyph {
+    /// Get the byte range of the cluster of the glyph in the original line
+    pub fn range(&self) -> core::ops::Range<usize> {
+        self.start as usize..self.end as usize
+    }
+
+    /// Get the kind of whitespace the glyph is for, to show whitespace, see
+    /// [`crate::Buffer::set_show_whitespace`]
+    pub fn whitespace_opt(&self) -> Option<Whitespace> {
+        [
+            Whitespace::Space,
+            Whitespace::Tab,
+            Whitespace::NoBreakSpace,
+            Whitespace::LineEnding,
+        ]
+        .into_iter()
+        .find(|whitespace| self.flags.contains(whitespace.flag()))
+    }
+
+    /// Get the pen position the glyph is drawn at, including its offsets from shaping. Y is
+    /// relative to the baseline, add [`crate::LayoutRun::line_y`] for the position in the buffer
+    pub fn origin(&self) -> (f32, f32) {
+        (
+            self.x + self.font_size * self.x_offset,
+            self.y - self.font_size * self.y_offset,
+        )
+    }
+
+    /// Get the box covering the advance of the glyph horizontally, and the em square above the
+    /// baseline vertically. Y is relative to the baseline, like [`Self::origin`]
+    pub fn advance_rect(&self) -> Rect {
+        Rect::new(
+            self.x.min(self.x + self.w),
+            self.y - self.font_size,
+            self.w.abs(),
+            self.font_size,
+        )
+    }
+
+    /// Get the bounding box of the outline of the glyph, from the default instance of variable
+    /// fonts. Y is relative to the baseline, like [`Self::origin`]. Returns None if the glyph has
+    /// no outline, like a space, or its font cannot be loaded
+    pub fn ink_rect(&self, font_system: &mut FontSystem) -> Option<Rect> {
+        let font = font_system.get_font(self.font_id)?;
+        let face = font.rustybuzz();
+        let bounds = face.glyph_bounding_box(ttf_parser::GlyphId(self.glyph_id))?;
+        let scale = self.font_size / face.units_per_em() as f32;
+        let (x, y) = self.origin();
+        Some(Rect::new(
+            x + f32::from(bounds.x_min) * scale,
+            y - f32::from(bounds.y_max) * scale,
+            (f32::from(bounds.x_max) - f32::from(bounds.x_min)) * scale,
+            (f32::from(bounds.y_max) - f32::from(bounds.y_min)) * scale,
+        ))
+    }
+
     pub fn physical(&self, offset: (f32, f32), scale: f32) -> PhysicalGlyph {
         let x_offset = self.font_size * self.x_offset;
         let y_offset = self.font_size * self.y_offset;
 
-        let (cache_key, x, y) = CacheKey::new(
+        let (cache_key, x, y) = CacheKey::new_variable(
             self.font_id,
             self.glyph_id,
             self.font_size * scale,
@@ -79,11 +417,59 @@ impl LayoutGlyph {
                 (self.x + x_offset) * scale + offset.0,
                 math::truncf((self.y - y_offset) * scale + offset.1), // Hinting in Y axis
             ),
+            self.font_weight,
+            self.font_variations,
             self.cache_key_flags,
         );
 
         PhysicalGlyph { cache_key, x, y }
     }
+
+    /// Like [`Self::physical`], for a glyph of [`crate::Direction::Vertical`] text. The laid out
+    /// `x` runs down the column, which is centered on `column_x`
+    pub fn physical_vertical(
+        &self,
+        offset: (f32, f32),
+        scale: f32,
+        column_x: f32,
+    ) -> PhysicalGlyph {
+        let x_offset = self.font_size * self.x_offset;
+        let y_offset = self.font_size * self.y_offset;
+
+        let (cache_key, x, y) = CacheKey::new_variable(
+            self.font_id,
+            self.glyph_id,
+            self.font_size * scale,
+            (
+                (column_x + y_offset) * scale + offset.0,
+                (self.x + x_offset) * scale + offset.1,
+            ),
+            self.font_weight,
+            self.font_variations,
+            self.cache_key_flags,
+        );
+
+        PhysicalGlyph { cache_key, x, y }
+    }
+}
+
+/// Find the extras of the glyph at `glyph_i` in a list sorted by glyph index
+pub(crate) fn find_extras<T>(extras: &[(usize, T)], glyph_i: usize) -> Option<&T> {
+    extras
+        .binary_search_by_key(&glyph_i, |&(i, _)| i)
+        .ok()
+        .map(|i| &extras[i].1)
+}
+
+/// Attributes that few [`LayoutGlyph`]s have, see [`LayoutLine::extras`]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LayoutGlyphExtras {
+    /// Color override of decorations
+    pub decoration_color_opt: Option<Color>,
+    /// Background color, see [`crate::LayoutRun::backgrounds`]
+    pub background_color_opt: Option<Color>,
+    /// Emphasis mark from `Attrs`, see [`crate::LayoutRun::emphasis_marks`]
+    pub emphasis_opt: Option<EmphasisMark>,
 }
 
 /// A line of laid out glyphs
@@ -91,6 +477,9 @@ impl LayoutGlyph {
 pub struct LayoutLine {
     /// Width of the line
     pub w: f32,
+    /// Width of the whitespace at the end of the line that counts toward [`Self::w`], so
+    /// `w - trailing_whitespace_w` is the width of the visible text, see [`TrailingWhitespace`]
+    pub trailing_whitespace_w: f32,
     /// Maximum ascent of the glyphs in line
     pub max_ascent: f32,
     /// Maximum descent of the glyphs in line
@@ -99,6 +488,78 @@ pub struct LayoutLine {
     pub line_height_opt: Option<f32>,
     /// Glyphs in line
     pub glyphs: Vec<LayoutGlyph>,
+    /// [`LayoutGlyphExtras`] of the glyphs that have any, by index in [`Self::glyphs`] in
+    /// increasing order, see [`Self::glyph_extras`]
+    pub extras: Vec<(usize, LayoutGlyphExtras)>,
+    /// Number of blanks between words in line, used to distribute the extra width of
+    /// [`Align::Justified`] lines
+    pub spaces: u32,
+    /// Extra width added to the advance of each glyph of a blank word by [`Align::Justified`],
+    /// 0.0 for other alignments and the last line of a paragraph. Already applied to the glyph
+    /// positions and widths
+    pub justification_expansion: f32,
+    /// Adjustments made to glyphs of monospace fonts to match the monospace width of the
+    /// buffer, see [`crate::Buffer::monospace_diagnostics`]
+    pub monospace_adjustments: Vec<MonospaceAdjustment>,
+    /// Indices in [`Self::glyphs`] of the tabs of the line, in the same order, see
+    /// [`Self::tab_positions`]
+    pub tabs: Vec<usize>,
+    /// Alignment the line was laid out with, after resolving the default alignment
+    pub align: Align,
+    /// Width available to the line that its text does not use, which alignment distributes
+    /// between its edges
+    pub slack: f32,
+    /// Vertical space above the line, the [`ParagraphSpacing::before`] of its paragraph on the
+    /// first visual line and 0.0 on the others
+    pub space_before: f32,
+    /// Vertical space below the line, the [`ParagraphSpacing::after`] of its paragraph on the
+    /// last visual line and 0.0 on the others
+    pub space_after: f32,
+}
+
+impl LayoutLine {
+    /// Get the height of the line including the paragraph spacing around it, using
+    /// `line_height` if the line has no [`Self::line_height_opt`]
+    pub fn height(&self, line_height: f32) -> f32 {
+        self.space_before + self.line_height_opt.unwrap_or(line_height) + self.space_after
+    }
+
+    /// Get the [`LayoutGlyphExtras`] of the glyph at `glyph_i` in [`Self::glyphs`], if it has any
+    pub fn glyph_extras(&self, glyph_i: usize) -> Option<&LayoutGlyphExtras> {
+        find_extras(&self.extras, glyph_i)
+    }
+
+    /// Get the glyphs of the tabs of the line, in the order of [`Self::glyphs`], to draw tab
+    /// markers when showing whitespace
+    ///
+    /// The hitbox of a tab glyph covers the whole space the tab was expanded to, from
+    /// [`LayoutGlyph::x`] to `x + w`, and its [`LayoutGlyph::level`] gives the direction an arrow
+    /// should point in.
+    pub fn tab_positions(&self) -> impl Iterator<Item = &LayoutGlyph> + '_ {
+        self.tabs
+            .iter()
+            .filter_map(|&glyph_i| self.glyphs.get(glyph_i))
+    }
+
+    /// Move the glyphs of the line to `align` it, without laying it out again. `rtl` is the
+    /// direction of the [`crate::ShapeLine`] the line was laid out from.
+    ///
+    /// Returns `false` and leaves the line unchanged if it was or would be
+    /// [`Align::Justified`], as that changes the width of blanks and must be laid out again.
+    pub fn realign(&mut self, align: Align, rtl: bool) -> bool {
+        if align == self.align {
+            return true;
+        }
+        if align == Align::Justified || self.align == Align::Justified {
+            return false;
+        }
+        let offset = align.offset(rtl, self.slack) - self.align.offset(rtl, self.slack);
+        for glyph in self.glyphs.iter_mut() {
+            glyph.x += offset;
+        }
+        self.align = align;
+        true
+    }
 }
 
 /// Wrapping mode
@@ -112,6 +573,12 @@ pub enum Wrap {
     Word,
     /// Wraps at the word level, or fallback to glyph level if a word can't fit on a line by itself
     WordOrGlyph,
+    /// Wraps at the word level at the narrowest width that does not add visual lines, so the
+    /// lines have similar widths instead of leaving a short last line, like CSS
+    /// `text-wrap: balance`. Meant for short text like headings, paragraphs of more than six
+    /// visual lines and words that can't fit on a line by themselves are wrapped like
+    /// [`Self::WordOrGlyph`]
+    Balanced,
 }
 
 impl Display for Wrap {
@@ -121,10 +588,112 @@ impl Display for Wrap {
             Self::Word => write!(f, "Word Wrap"),
             Self::WordOrGlyph => write!(f, "Word Wrap or Character"),
             Self::Glyph => write!(f, "Character"),
+            Self::Balanced => write!(f, "Balanced"),
         }
     }
 }
 
+/// How whitespace at the end of a visual line is laid out
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub enum TrailingWhitespace {
+    /// Whitespace where a line wraps is removed from the layout, whitespace at the end of a
+    /// paragraph counts toward the width of the line
+    #[default]
+    Collapse,
+    /// Whitespace at the end of a line is laid out after the end of the line, so it can be
+    /// selected and clicked but does not count toward the width or alignment of the line, as
+    /// in word processors
+    Hang,
+    /// Whitespace at the end of a line counts toward the width of the line, as in code editors
+    Include,
+}
+
+/// Where [`Align::Justified`] spreads the extra width of a visual line
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub enum JustifyMode {
+    /// Expand the spaces between words, even in Arabic text
+    #[default]
+    InterWord,
+    /// Expand the gaps between grapheme clusters, for CJK text that has no spaces
+    InterCharacter,
+    /// Lengthen one joint between Arabic letters in each word with up to three tatweel (U+0640)
+    /// glyphs of the shaping font, expanding the spaces between words with the rest of the
+    /// extra width. Joints after seen and sad are preferred, then joints before the last letter.
+    /// Text without such joints, like Latin text, only expands the spaces between words
+    Kashida,
+}
+
+/// How text that does not fit in the size of a [`crate::Buffer`] is handled
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub enum Overflow {
+    /// Text is laid out past the edges of the buffer, to be clipped when drawn
+    #[default]
+    Clip,
+    /// Text is cut short where it overflows the width, at the last line that fits in the
+    /// height, or at [`crate::Buffer::max_lines`], and ends with [`crate::Buffer::ellipsis`]
+    /// instead
+    Ellipsis,
+}
+
+/// How glyphs of monospace fonts that do not match [`crate::Buffer::monospace_width`] are
+/// handled
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub enum MonospaceMode {
+    /// Glyphs are scaled to the closest multiple of the monospace width
+    #[default]
+    Scale,
+    /// Glyphs are shaped with a monospace fallback font of matching width when there is one,
+    /// and only scaled otherwise. Only applies to [`crate::Shaping::Advanced`]. This avoids blurry glyphs from scaling by non-integer factors
+    Strict,
+}
+
+/// An adjustment made to a glyph of a monospace font to match the monospace width
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum MonospaceAdjustment {
+    /// The font size of the glyph was multiplied by `factor`, because the em width of its font
+    /// is not a multiple of the monospace width
+    Scaled {
+        /// Index of the glyph in [`LayoutLine::glyphs`]
+        glyph_i: usize,
+        /// Width of the font in em
+        font_em_width: f32,
+        /// Monospace width in em at the font size of the glyph
+        target_em_width: f32,
+        /// Factor applied to the font size of the glyph
+        factor: f32,
+    },
+    /// The glyph was shaped with a monospace fallback font of matching width instead of the
+    /// font `from`, in [`MonospaceMode::Strict`]
+    Substituted {
+        /// Index of the glyph in [`LayoutLine::glyphs`]
+        glyph_i: usize,
+        /// Font the glyph was originally shaped with
+        from: fontdb::ID,
+    },
+}
+
+impl MonospaceAdjustment {
+    /// Index of the adjusted glyph in [`LayoutLine::glyphs`]
+    pub fn glyph_i(&self) -> usize {
+        match *self {
+            Self::Scaled { glyph_i, .. } | Self::Substituted { glyph_i, .. } => glyph_i,
+        }
+    }
+}
+
+/// A glyph adjusted to match the monospace width, see [`crate::Buffer::monospace_diagnostics`]
+#[derive(Debug)]
+pub struct MonospaceDiagnostic<'a> {
+    /// Index of the buffer line
+    pub line_i: usize,
+    /// Index of the visual line in the layout of the buffer line
+    pub layout_i: usize,
+    /// The adjusted glyph
+    pub glyph: &'a LayoutGlyph,
+    /// What was done to the glyph
+    pub adjustment: MonospaceAdjustment,
+}
+
 /// Align or justify
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
 pub enum Align {
@@ -135,6 +704,27 @@ pub enum Align {
     End,
 }
 
+impl Align {
+    /// Get how far the start of a line is moved from the start edge of the space available to
+    /// it, which is the right edge of RTL lines, when `slack` of that space is left unused
+    pub(crate) fn offset(self, rtl: bool, slack: f32) -> f32 {
+        let correction = match (self, rtl) {
+            (Self::Left, true) => slack,
+            (Self::Left, false) => 0.,
+            (Self::Right, true) => 0.,
+            (Self::Right, false) => slack,
+            (Self::Center, _) => slack / 2.0,
+            (Self::End, _) => slack,
+            (Self::Justified, _) => 0.,
+        };
+        if rtl {
+            -correction
+        } else {
+            correction
+        }
+    }
+}
+
 impl Display for Align {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
@@ -146,3 +736,201 @@ impl Display for Align {
         }
     }
 }
+
+/// A rectangle in buffer coordinates
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rect {
+    /// X offset of the left edge
+    pub x: f32,
+    /// Y offset of the top edge
+    pub y: f32,
+    /// Width of the rectangle
+    pub width: f32,
+    /// Height of the rectangle
+    pub height: f32,
+}
+
+impl Rect {
+    /// Create a new rectangle
+    pub const fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Scale the rectangle by `factor`, for example to convert to device pixels
+    pub fn scale(self, factor: f32) -> Self {
+        Self {
+            x: self.x * factor,
+            y: self.y * factor,
+            width: self.width * factor,
+            height: self.height * factor,
+        }
+    }
+}
+
+/// Space between the edges of a [`crate::Buffer`] and its text, see
+/// [`crate::Buffer::set_padding`]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Insets {
+    /// Space below the top edge
+    pub top: f32,
+    /// Space left of the right edge
+    pub right: f32,
+    /// Space above the bottom edge
+    pub bottom: f32,
+    /// Space right of the left edge
+    pub left: f32,
+}
+
+impl Insets {
+    /// Create new [`Insets`] in the same order as CSS, clockwise from the top
+    pub const fn new(top: f32, right: f32, bottom: f32, left: f32) -> Self {
+        Self {
+            top,
+            right,
+            bottom,
+            left,
+        }
+    }
+
+    /// Create new [`Insets`] with the same space on every edge
+    pub const fn uniform(inset: f32) -> Self {
+        Self::new(inset, inset, inset, inset)
+    }
+
+    /// Create new [`Insets`] with `vertical` space on the top and bottom edges and `horizontal`
+    /// space on the left and right edges
+    pub const fn symmetric(vertical: f32, horizontal: f32) -> Self {
+        Self::new(vertical, horizontal, vertical, horizontal)
+    }
+
+    /// Sum of the left and right insets
+    pub fn horizontal(&self) -> f32 {
+        self.left + self.right
+    }
+
+    /// Sum of the top and bottom insets
+    pub fn vertical(&self) -> f32 {
+        self.top + self.bottom
+    }
+}
+
+/// Indentation of the visual lines of a [`crate::BufferLine`] from the edge they start at, the
+/// left edge for LTR lines and the right edge for RTL lines, see
+/// [`crate::BufferLine::set_indent`]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Indent {
+    /// Indent of the first visual line
+    pub first_line: f32,
+    /// Indent of the visual lines wrapped after the first, for list items and block quotes
+    pub hanging: f32,
+}
+
+impl Indent {
+    /// Create a new [`Indent`]
+    pub const fn new(first_line: f32, hanging: f32) -> Self {
+        Self {
+            first_line,
+            hanging,
+        }
+    }
+
+    /// Get the indent of the visual line at `index`
+    pub fn line(&self, index: usize) -> f32 {
+        if index == 0 {
+            self.first_line
+        } else {
+            self.hanging
+        }
+    }
+}
+
+/// Vertical space around a [`crate::BufferLine`], added above its first visual line and below its
+/// last visual line, see [`crate::BufferLine::set_spacing`]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ParagraphSpacing {
+    /// Space above the paragraph
+    pub before: f32,
+    /// Space below the paragraph
+    pub after: f32,
+}
+
+impl ParagraphSpacing {
+    /// Create a new [`ParagraphSpacing`]
+    pub const fn new(before: f32, after: f32) -> Self {
+        Self { before, after }
+    }
+}
+
+/// Exclusion zones that apply to the visual lines of one [`crate::ShapeLine`]
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct LineExclusions<'a> {
+    /// Regions text may not be placed in, in buffer coordinates
+    pub rects: &'a [Rect],
+    /// Y offset of the top of the line in buffer coordinates
+    pub top: f32,
+    /// Height of the band of each visual line that is checked for exclusions
+    pub line_height: f32,
+    /// Indentation of the visual lines
+    pub indent: Indent,
+}
+
+impl<'a> LineExclusions<'a> {
+    /// Get the start X offset and width available to the visual line at `index`, placed `top`
+    /// below the top of the first visual line.
+    ///
+    /// The indent is removed from the left edge, or the right edge if `rtl` is true, and then
+    /// the widest region not covered by an exclusion is used. If `width_opt` is `None`, the
+    /// available width is unbounded. `blocked` is used as scratch space for the covered regions.
+    pub fn available(
+        &self,
+        index: usize,
+        top: f32,
+        width_opt: Option<f32>,
+        rtl: bool,
+        blocked: &mut Vec<(f32, f32)>,
+    ) -> (f32, f32) {
+        let width = width_opt.unwrap_or(f32::INFINITY);
+        let indent = self.indent.line(index);
+        let (min_x, max_x) = if rtl {
+            (0.0, width - indent)
+        } else {
+            (indent, width)
+        };
+        if self.rects.is_empty() {
+            return (min_x, max_x - min_x);
+        }
+
+        let top = self.top + top;
+        let bottom = top + self.line_height;
+
+        blocked.clear();
+        for rect in self.rects.iter() {
+            if rect.y < bottom && rect.y + rect.height > top {
+                let start = rect.x.max(min_x);
+                let end = (rect.x + rect.width).min(max_x);
+                if start < end {
+                    blocked.push((start, end));
+                }
+            }
+        }
+        blocked.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut best = (min_x, 0.0);
+        let mut x = min_x;
+        for &(start, end) in blocked.iter() {
+            if start - x > best.1 {
+                best = (x, start - x);
+            }
+            x = x.max(end);
+        }
+        if max_x - x > best.1 {
+            best = (x, max_x - x);
+        }
+        best
+    }
+}
@@ -56,6 +56,16 @@
 //!     // Fill in your code here for drawing rectangles
 //! });
 //! ```
+//!
+//! ## Features
+//!
+//! - `std` or `no_std`: one of them is required
+//! - `swash`: rasterization with `SwashCache`
+//! - `edit`: the [`Editor`] and the [`Edit`] trait, `syntect` and `vi` add syntax highlighting
+//!   and vi keybindings on top of it. Without them only [`Buffer`] and layout are built, for
+//!   label-only usage
+//! - `unicode-linebreak`: Unicode line breaking. Without it lines are only broken after
+//!   whitespace, to build without the Unicode line breaking tables
 
 // Not interested in these lints
 #![allow(clippy::new_without_default)]
@@ -114,12 +124,23 @@ mod cached;
 pub use self::glyph_cache::*;
 mod glyph_cache;
 
+pub use self::graphemes::*;
+mod graphemes;
+
 pub use self::cursor::*;
 mod cursor;
 
+pub use self::decoration::*;
+mod decoration;
+
+#[cfg(feature = "edit")]
 pub use self::edit::*;
+#[cfg(feature = "edit")]
 mod edit;
 
+pub use self::error::*;
+mod error;
+
 pub use self::font::*;
 mod font;
 
@@ -129,12 +150,36 @@ mod layout;
 pub use self::line_ending::*;
 mod line_ending;
 
+pub use self::minimap::*;
+mod minimap;
+
+pub use self::profile::*;
+mod profile;
+
+pub use self::reveal::*;
+mod reveal;
+
+pub use self::scroll_physics::*;
+mod scroll_physics;
+
 pub use self::shape::*;
 mod shape;
 
 pub use self::shape_run_cache::*;
 mod shape_run_cache;
 
+pub use self::shape_text::*;
+mod shape_text;
+
+pub use self::tab_stops::*;
+mod tab_stops;
+
+pub use self::table::*;
+mod table;
+
+pub use self::text_path::*;
+mod text_path;
+
 #[cfg(feature = "swash")]
 pub use self::swash::*;
 #[cfg(feature = "swash")]
@@ -12,6 +12,10 @@ pub enum LineEnding {
     Cr,
     /// Use `\n\r` for line ending (some legacy systems)
     LfCr,
+    /// Use U+2029 PARAGRAPH SEPARATOR for line ending
+    Ps,
+    /// Use U+0085 NEXT LINE (NEL) for line ending (some legacy systems)
+    Nel,
     /// No line ending
     None,
 }
@@ -24,17 +28,120 @@ impl LineEnding {
             Self::CrLf => "\r\n",
             Self::Cr => "\r",
             Self::LfCr => "\n\r",
+            Self::Ps => "\u{2029}",
+            Self::Nel => "\u{85}",
             Self::None => "",
         }
     }
 }
 
+/// Number of lines with each [`LineEnding`], see [`crate::Buffer::line_ending_stats`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct LineEndingStats {
+    /// Number of lines ending with [`LineEnding::Lf`]
+    pub lf: usize,
+    /// Number of lines ending with [`LineEnding::CrLf`]
+    pub crlf: usize,
+    /// Number of lines ending with [`LineEnding::Cr`]
+    pub cr: usize,
+    /// Number of lines ending with [`LineEnding::LfCr`]
+    pub lfcr: usize,
+    /// Number of lines ending with [`LineEnding::Ps`]
+    pub ps: usize,
+    /// Number of lines ending with [`LineEnding::Nel`]
+    pub nel: usize,
+    /// Number of lines without a line ending, usually only the last line
+    pub none: usize,
+}
+
+impl LineEndingStats {
+    /// Line endings in the order [`Self::dominant`] prefers them on ties
+    const ENDINGS: [LineEnding; 6] = [
+        LineEnding::Lf,
+        LineEnding::CrLf,
+        LineEnding::Cr,
+        LineEnding::LfCr,
+        LineEnding::Ps,
+        LineEnding::Nel,
+    ];
+
+    /// Get the number of lines ending with `ending`
+    pub fn count(&self, ending: LineEnding) -> usize {
+        match ending {
+            LineEnding::Lf => self.lf,
+            LineEnding::CrLf => self.crlf,
+            LineEnding::Cr => self.cr,
+            LineEnding::LfCr => self.lfcr,
+            LineEnding::Ps => self.ps,
+            LineEnding::Nel => self.nel,
+            LineEnding::None => self.none,
+        }
+    }
+
+    /// Count a line ending with `ending`
+    pub fn add(&mut self, ending: LineEnding) {
+        let count = match ending {
+            LineEnding::Lf => &mut self.lf,
+            LineEnding::CrLf => &mut self.crlf,
+            LineEnding::Cr => &mut self.cr,
+            LineEnding::LfCr => &mut self.lfcr,
+            LineEnding::Ps => &mut self.ps,
+            LineEnding::Nel => &mut self.nel,
+            LineEnding::None => &mut self.none,
+        };
+        *count += 1;
+    }
+
+    /// Get the number of lines with a line ending
+    pub fn total(&self) -> usize {
+        Self::ENDINGS.iter().map(|&ending| self.count(ending)).sum()
+    }
+
+    /// Get the most common line ending, to show in a status bar or use for new lines. Ties are
+    /// broken in the order of the [`LineEnding`] variants. Returns `None` if no line has a
+    /// line ending
+    pub fn dominant(&self) -> Option<LineEnding> {
+        Self::ENDINGS
+            .iter()
+            .copied()
+            .filter(|&ending| self.count(ending) > 0)
+            .reduce(|dominant, ending| {
+                if self.count(ending) > self.count(dominant) {
+                    ending
+                } else {
+                    dominant
+                }
+            })
+    }
+
+    /// Returns true if lines end with more than one kind of line ending
+    pub fn is_mixed(&self) -> bool {
+        Self::ENDINGS
+            .iter()
+            .filter(|&&ending| self.count(ending) > 0)
+            .count()
+            > 1
+    }
+}
+
+impl FromIterator<LineEnding> for LineEndingStats {
+    fn from_iter<I: IntoIterator<Item = LineEnding>>(iter: I) -> Self {
+        let mut stats = Self::default();
+        for ending in iter {
+            stats.add(ending);
+        }
+        stats
+    }
+}
+
 /// Iterator over lines terminated by [`LineEnding`]
 #[derive(Debug)]
+#[must_use = "iterators are lazy and do nothing unless consumed"]
 pub struct LineIter<'a> {
     string: &'a str,
     start: usize,
     end: usize,
+    paragraphs: bool,
 }
 
 impl<'a> LineIter<'a> {
@@ -44,6 +151,16 @@ impl<'a> LineIter<'a> {
             string,
             start: 0,
             end: string.len(),
+            paragraphs: false,
+        }
+    }
+
+    /// Create an iterator of lines in a string slice, also splitting on the Unicode paragraph
+    /// separators U+2029 and NEL
+    pub fn new_with_paragraphs(string: &'a str) -> Self {
+        Self {
+            paragraphs: true,
+            ..Self::new(string)
         }
     }
 }
@@ -52,7 +169,13 @@ impl<'a> Iterator for LineIter<'a> {
     type Item = (Range<usize>, LineEnding);
     fn next(&mut self) -> Option<Self::Item> {
         let start = self.start;
-        match self.string[start..self.end].find(&['\r', '\n']) {
+        let remaining = &self.string[start..self.end];
+        let found = if self.paragraphs {
+            remaining.find(['\r', '\n', '\u{2029}', '\u{85}'])
+        } else {
+            remaining.find(&['\r', '\n'])
+        };
+        match found {
             Some(i) => {
                 let end = start + i;
                 self.start = end;
@@ -65,6 +188,10 @@ impl<'a> Iterator for LineIter<'a> {
                     LineEnding::Lf
                 } else if after.starts_with("\r") {
                     LineEnding::Cr
+                } else if after.starts_with('\u{2029}') {
+                    LineEnding::Ps
+                } else if after.starts_with('\u{85}') {
+                    LineEnding::Nel
                 } else {
                     //TODO: this should not be possible
                     LineEnding::None
@@ -96,3 +223,41 @@ fn test_line_iter() {
     assert_eq!(iter.next(), Some((12..16, LineEnding::LfCr)));
     assert_eq!(iter.next(), Some((18..22, LineEnding::None)));
 }
+
+#[test]
+fn test_line_iter_paragraphs() {
+    let string = "LF\nPS\u{2029}NEL\u{85}NONE";
+    let mut iter = LineIter::new_with_paragraphs(string);
+    assert_eq!(iter.next(), Some((0..2, LineEnding::Lf)));
+    assert_eq!(iter.next(), Some((3..5, LineEnding::Ps)));
+    assert_eq!(iter.next(), Some((8..11, LineEnding::Nel)));
+    assert_eq!(iter.next(), Some((13..17, LineEnding::None)));
+    assert_eq!(iter.next(), None);
+
+    let mut iter = LineIter::new(string);
+    assert_eq!(iter.next(), Some((0..2, LineEnding::Lf)));
+    assert_eq!(iter.next(), Some((3..17, LineEnding::None)));
+}
+
+#[test]
+fn test_line_ending_stats() {
+    let stats: LineEndingStats = LineIter::new_with_paragraphs("a\r\nb\r\nc\nd\u{2029}e")
+        .map(|(_, ending)| ending)
+        .collect();
+    assert_eq!(stats.crlf, 2);
+    assert_eq!(stats.count(LineEnding::Lf), 1);
+    assert_eq!(stats.ps, 1);
+    assert_eq!(stats.none, 1);
+    assert_eq!(stats.total(), 4);
+    assert_eq!(stats.dominant(), Some(LineEnding::CrLf));
+    assert!(stats.is_mixed());
+
+    // Ties prefer LF, and the missing ending of the last line is not mixed with others
+    let stats: LineEndingStats = [LineEnding::Cr, LineEnding::Lf, LineEnding::None]
+        .into_iter()
+        .collect();
+    assert_eq!(stats.dominant(), Some(LineEnding::Lf));
+    let stats: LineEndingStats = [LineEnding::CrLf, LineEnding::None].into_iter().collect();
+    assert!(!stats.is_mixed());
+    assert_eq!(LineEndingStats::default().dominant(), None);
+}
@@ -1,5 +1,23 @@
 #[cfg(not(feature = "std"))]
-pub use libm::{floorf, roundf, truncf};
+pub use libm::{atan2f, ceilf, expf, floorf, roundf, sqrtf, truncf};
+
+#[cfg(feature = "std")]
+#[inline]
+pub fn atan2f(y: f32, x: f32) -> f32 {
+    y.atan2(x)
+}
+
+#[cfg(feature = "std")]
+#[inline]
+pub fn ceilf(x: f32) -> f32 {
+    x.ceil()
+}
+
+#[cfg(feature = "std")]
+#[inline]
+pub fn expf(x: f32) -> f32 {
+    x.exp()
+}
 
 #[cfg(feature = "std")]
 #[inline]
@@ -13,6 +31,12 @@ pub fn roundf(x: f32) -> f32 {
     x.round()
 }
 
+#[cfg(feature = "std")]
+#[inline]
+pub fn sqrtf(x: f32) -> f32 {
+    x.sqrt()
+}
+
 #[cfg(feature = "std")]
 #[inline]
 pub fn truncf(x: f32) -> f32 {
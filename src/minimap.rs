@@ -0,0 +1,115 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use crate::{AttrsList, Color, LayoutGlyph, Metadata};
+
+/// A block of text in a [`MinimapRun`], covering glyphs with the same color and metadata up to
+/// the next whitespace
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MinimapSegment {
+    /// X offset of the segment
+    pub x: f32,
+    /// Width of the segment
+    pub w: f32,
+    /// Color of the text, None for the default color
+    pub color_opt: Option<Color>,
+    /// Metadata of the text, see [`crate::Attrs::metadata`]
+    pub metadata: Metadata,
+}
+
+/// A visual line of a minimap, see [`crate::Buffer::minimap_runs`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MinimapRun {
+    /// The index of the original text line
+    pub line_i: usize,
+    /// True if the line is not laid out yet, and its segments are estimated from its text
+    pub estimated: bool,
+    /// Y offset of the top of the line
+    pub y: f32,
+    /// Height of the line
+    pub h: f32,
+    /// Blocks of text in the line, from left to right
+    pub segments: Vec<MinimapSegment>,
+}
+
+/// Add `segment` to `segments`, extending the last segment if it has the same color and metadata
+/// and `join` is true
+fn push_segment(segments: &mut Vec<MinimapSegment>, segment: MinimapSegment, join: bool) {
+    if join {
+        if let Some(last) = segments.last_mut() {
+            if last.color_opt == segment.color_opt && last.metadata == segment.metadata {
+                let end = (last.x + last.w).max(segment.x + segment.w);
+                last.x = last.x.min(segment.x);
+                last.w = end - last.x;
+                return;
+            }
+        }
+    }
+    segments.push(segment);
+}
+
+/// Get the segments of laid out glyphs, scaled by `scale`
+pub(crate) fn glyph_segments(
+    text: &str,
+    glyphs: &[LayoutGlyph],
+    scale: f32,
+) -> Vec<MinimapSegment> {
+    let mut segments = Vec::new();
+    let mut join = false;
+    for glyph in glyphs.iter() {
+        let blank = text
+            .get(glyph.range())
+            .map_or(false, |text| text.chars().all(char::is_whitespace));
+        if blank {
+            join = false;
+            continue;
+        }
+        let segment = MinimapSegment {
+            x: glyph.x.min(glyph.x + glyph.w) * scale,
+            w: glyph.w.abs() * scale,
+            color_opt: glyph.color_opt,
+            metadata: glyph.metadata,
+        };
+        push_segment(&mut segments, segment, join);
+        join = true;
+    }
+    segments
+}
+
+/// Estimate the segments of text that is not laid out, giving each character a width of
+/// `char_w` and tabs `tab_width` characters, scaled by `scale`
+pub(crate) fn text_segments(
+    text: &str,
+    attrs_list: &AttrsList,
+    char_w: f32,
+    tab_width: u16,
+    scale: f32,
+) -> Vec<MinimapSegment> {
+    let mut segments = Vec::new();
+    let mut join = false;
+    let mut x = 0.0;
+    for (i, c) in text.char_indices() {
+        let w = if c == '\t' {
+            char_w * f32::from(tab_width)
+        } else {
+            char_w
+        };
+        if c.is_whitespace() {
+            join = false;
+        } else {
+            let attrs = attrs_list.get_span(i);
+            let segment = MinimapSegment {
+                x: x * scale,
+                w: w * scale,
+                color_opt: attrs.color_opt,
+                metadata: attrs.metadata,
+            };
+            push_segment(&mut segments, segment, join);
+            join = true;
+        }
+        x += w;
+    }
+    segments
+}
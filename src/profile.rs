@@ -0,0 +1,168 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::{fmt, time::Duration};
+
+/// Cost of shaping and laying out a [`crate::BufferLine`], recorded while profiling is enabled
+/// with [`crate::Buffer::set_profiling`]
+///
+/// Durations are only measured with the `std` feature on targets other than `wasm32`, and are
+/// zero otherwise.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct LineProfile {
+    /// Number of times the line was shaped
+    pub shape_count: u32,
+    /// Total time spent shaping the line
+    pub shape_time: Duration,
+    /// Number of times the line was laid out
+    pub layout_count: u32,
+    /// Total time spent laying out the line
+    pub layout_time: Duration,
+    /// Number of fallback fonts tried for text the preceding fonts had no glyphs for, while
+    /// shaping the line
+    pub fallback_iterations: u32,
+}
+
+impl LineProfile {
+    /// Total time spent shaping and laying out the line
+    pub fn total_time(&self) -> Duration {
+        self.shape_time + self.layout_time
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.shape_count += other.shape_count;
+        self.shape_time += other.shape_time;
+        self.layout_count += other.layout_count;
+        self.layout_time += other.layout_time;
+        self.fallback_iterations += other.fallback_iterations;
+    }
+}
+
+/// A line of a [`ProfileReport`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LineProfileEntry {
+    /// The index of the line in the buffer
+    pub line_i: usize,
+    /// Length of the text of the line in bytes
+    pub len: usize,
+    /// Cost of shaping and laying out the line
+    pub profile: LineProfile,
+}
+
+/// Shaping and layout costs of the lines of a [`crate::Buffer`], see
+/// [`crate::Buffer::profile_report`]
+///
+/// The [`fmt::Display`] implementation summarizes the report in a single line listing the most
+/// expensive lines, for logging.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ProfileReport {
+    /// Every line that was shaped or laid out while profiling was enabled, in buffer order
+    pub lines: Vec<LineProfileEntry>,
+}
+
+impl ProfileReport {
+    /// Sum of the costs of all lines
+    pub fn total(&self) -> LineProfile {
+        let mut total = LineProfile::default();
+        for entry in self.lines.iter() {
+            total.add(&entry.profile);
+        }
+        total
+    }
+
+    /// Get up to `count` lines with the highest total time, or the most fallback iterations when
+    /// durations are not measured, most expensive first
+    pub fn slowest(&self, count: usize) -> Vec<&LineProfileEntry> {
+        let mut lines: Vec<_> = self.lines.iter().collect();
+        lines.sort_by(|a, b| {
+            (b.profile.total_time(), b.profile.fallback_iterations)
+                .cmp(&(a.profile.total_time(), a.profile.fallback_iterations))
+        });
+        lines.truncate(count);
+        lines
+    }
+}
+
+impl fmt::Display for ProfileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.total();
+        write!(
+            f,
+            "{} lines: shaped {} times in {:?}, laid out {} times in {:?}, {} fallback iterations",
+            self.lines.len(),
+            total.shape_count,
+            total.shape_time,
+            total.layout_count,
+            total.layout_time,
+            total.fallback_iterations
+        )?;
+        for entry in self.slowest(3) {
+            write!(
+                f,
+                "; line {} ({} bytes): shape {:?}, layout {:?}, {} fallback iterations",
+                entry.line_i,
+                entry.len,
+                entry.profile.shape_time,
+                entry.profile.layout_time,
+                entry.profile.fallback_iterations
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Memory held by the shaping and layout caches of a [`crate::Buffer`], see
+/// [`crate::Buffer::memory_usage`]
+///
+/// Glyphs are most of the memory of a shaped buffer. On 64-bit targets a
+/// [`crate::ShapeGlyph`] takes 112 bytes and a [`crate::LayoutGlyph`] 80 bytes, or 76 bytes with
+/// the `u32-metadata` feature, so every character of a laid out line costs about 190 bytes.
+/// Lines whose caches were reset keep their allocations for reuse until they are released with
+/// [`crate::BufferLine::release_caches`], which pruning does.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct MemoryUsage {
+    /// Number of glyphs of lines that are shaped
+    pub shape_glyphs: usize,
+    /// Number of glyphs of lines that are laid out
+    pub layout_glyphs: usize,
+    /// Bytes allocated for glyphs, including the allocations kept by lines whose caches were
+    /// reset
+    pub glyph_bytes: usize,
+}
+
+impl MemoryUsage {
+    pub(crate) fn add(&mut self, other: &Self) {
+        self.shape_glyphs += other.shape_glyphs;
+        self.layout_glyphs += other.layout_glyphs;
+        self.glyph_bytes += other.glyph_bytes;
+    }
+}
+
+/// Measures the time taken by shaping and layout when possible
+pub(crate) struct ProfileTimer {
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    start: std::time::Instant,
+}
+
+impl ProfileTimer {
+    /// True if [`Self::elapsed`] measures time, false if it is always zero
+    pub const MEASURED: bool = cfg!(all(feature = "std", not(target_arch = "wasm32")));
+
+    pub fn start() -> Self {
+        Self {
+            #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+            start: std::time::Instant::now(),
+        }
+    }
+
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    #[cfg(not(all(feature = "std", not(target_arch = "wasm32"))))]
+    pub fn elapsed(&self) -> Duration {
+        Duration::ZERO
+    }
+}
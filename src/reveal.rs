@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::time::Duration;
+
+use crate::{Buffer, Cursor};
+
+/// Time based progressive reveal of text, for typewriter effects
+///
+/// Call [`TextReveal::tick`] with the time elapsed since the last call, then draw only the glyphs
+/// returned by [`crate::LayoutRun::glyphs_before`] with the end from [`TextReveal::end`]. The
+/// [`Buffer`] is shaped once, no matter how many frames the reveal takes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextReveal {
+    rate: f32,
+    elapsed: Duration,
+}
+
+impl TextReveal {
+    /// Create a new [`TextReveal`] showing `rate` grapheme clusters per second, negative rates
+    /// show no text
+    pub fn new(rate: f32) -> Self {
+        Self {
+            rate: rate.max(0.0),
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Get the number of grapheme clusters shown per second
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+
+    /// Set the number of grapheme clusters shown per second, keeping the current progress
+    pub fn set_rate(&mut self, rate: f32) {
+        let count = self.count();
+        self.rate = rate.max(0.0);
+        self.elapsed = if self.rate > 0.0 {
+            Duration::from_secs_f32(count as f32 / self.rate)
+        } else {
+            Duration::ZERO
+        };
+    }
+
+    /// Advance the reveal by `dt`, returning true if more text is shown
+    pub fn tick(&mut self, dt: Duration) -> bool {
+        let count = self.count();
+        self.elapsed += dt;
+        count != self.count()
+    }
+
+    /// Restart the reveal with no text shown
+    pub fn reset(&mut self) {
+        self.elapsed = Duration::ZERO;
+    }
+
+    /// Get the number of grapheme clusters shown
+    pub fn count(&self) -> usize {
+        (self.elapsed.as_secs_f32() * self.rate) as usize
+    }
+
+    /// Get the end of the text shown in `buffer`, see [`Buffer::visible_prefix`]
+    pub fn end(&self, buffer: &Buffer) -> Cursor {
+        buffer.visible_prefix(self.count())
+    }
+
+    /// Returns true if all of the text in `buffer` is shown
+    pub fn is_finished(&self, buffer: &Buffer) -> bool {
+        let end = self.end(buffer);
+        end.line + 1 >= buffer.lines.len()
+            && buffer
+                .lines
+                .get(end.line)
+                .map_or(true, |line| end.index == line.text().len())
+    }
+}
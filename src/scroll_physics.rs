@@ -0,0 +1,206 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+use core::time::Duration;
+
+use crate::{math, Buffer, FontSystem, Scroll};
+
+/// Kinetic scrolling for touch input, with optional rubber band overshoot
+///
+/// Feed finger movement to [`ScrollPhysics::drag`], call [`ScrollPhysics::release`] when the
+/// finger is lifted, and call [`ScrollPhysics::tick`] every frame while
+/// [`ScrollPhysics::is_animating`] returns true. All scroll deltas should be applied with
+/// [`ScrollPhysics::apply`], which tracks how far the extents of the [`Buffer`] were overshot.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScrollPhysics {
+    friction: f32,
+    overshoot_limit: Option<f32>,
+    velocity: f32,
+    overshoot: f32,
+    dragging: bool,
+}
+
+impl ScrollPhysics {
+    /// Default friction, see [`Self::set_friction`]
+    pub const DEFAULT_FRICTION: f32 = 4.0;
+
+    /// Velocity in pixels per second below which a fling stops
+    const MIN_VELOCITY: f32 = 10.0;
+
+    /// Rate at which overshoot springs back, per second
+    const SPRING: f32 = 12.0;
+
+    /// Create a new [`ScrollPhysics`] with default friction and no overshoot
+    pub const fn new() -> Self {
+        Self {
+            friction: Self::DEFAULT_FRICTION,
+            overshoot_limit: None,
+            velocity: 0.0,
+            overshoot: 0.0,
+            dragging: false,
+        }
+    }
+
+    /// Get the friction applied to flings
+    pub fn friction(&self) -> f32 {
+        self.friction
+    }
+
+    /// Set the friction applied to flings, the rate per second at which velocity decays.
+    /// Larger values stop flings sooner
+    pub fn set_friction(&mut self, friction: f32) {
+        self.friction = friction.max(0.0);
+    }
+
+    /// Get the maximum overshoot in pixels
+    pub fn overshoot_limit(&self) -> Option<f32> {
+        self.overshoot_limit
+    }
+
+    /// Set the maximum overshoot in pixels beyond the extents of the [`Buffer`]. `None` disables
+    /// overshoot
+    pub fn set_overshoot_limit(&mut self, overshoot_limit: Option<f32>) {
+        self.overshoot_limit = overshoot_limit.map(|limit| limit.max(0.0));
+        if self.overshoot_limit.is_none() {
+            self.overshoot = 0.0;
+        }
+    }
+
+    /// Get the current velocity in pixels per second
+    pub fn velocity(&self) -> f32 {
+        self.velocity
+    }
+
+    /// Get the current overshoot in pixels, negative before the start of the [`Buffer`] and
+    /// positive after the end. Content should be drawn offset by the negated overshoot
+    pub fn overshoot(&self) -> f32 {
+        self.overshoot
+    }
+
+    /// Returns true if [`Self::tick`] needs to be called for the next frame
+    pub fn is_animating(&self) -> bool {
+        !self.dragging && (self.velocity != 0.0 || self.overshoot != 0.0)
+    }
+
+    /// Track a drag scrolling by `delta` pixels over `dt`, returning the scroll delta to apply.
+    /// Positive values scroll towards the end of the [`Buffer`]
+    pub fn drag(&mut self, delta: f32, dt: Duration) -> f32 {
+        self.dragging = true;
+        let dt = dt.as_secs_f32();
+        if dt > 0.0 {
+            // Smooth velocity to reduce jitter from uneven event timing
+            self.velocity = self.velocity * 0.2 + (delta / dt) * 0.8;
+        }
+        delta
+    }
+
+    /// End a drag, starting a fling with the tracked velocity
+    pub fn release(&mut self) {
+        self.dragging = false;
+        if self.velocity.abs() < Self::MIN_VELOCITY {
+            self.velocity = 0.0;
+        }
+    }
+
+    /// Stop a fling immediately, for example when the content is touched again
+    pub fn stop(&mut self) {
+        self.velocity = 0.0;
+    }
+
+    /// Advance a fling or overshoot spring back by `dt`, returning the scroll delta to apply for
+    /// this frame
+    pub fn tick(&mut self, dt: Duration) -> f32 {
+        if self.dragging {
+            return 0.0;
+        }
+
+        let dt = dt.as_secs_f32();
+        if self.overshoot != 0.0 {
+            // Spring back to the extents of the buffer
+            self.velocity = 0.0;
+            let mut remaining = self.overshoot * math::expf(-Self::SPRING * dt);
+            if remaining.abs() < 0.5 {
+                remaining = 0.0;
+            }
+            return remaining - self.overshoot;
+        }
+
+        let delta = self.velocity * dt;
+        self.velocity *= math::expf(-self.friction * dt);
+        if self.velocity.abs() < Self::MIN_VELOCITY {
+            self.velocity = 0.0;
+        }
+        delta
+    }
+
+    /// Apply a scroll delta in pixels to `buffer`
+    ///
+    /// Scrolling beyond the extents of the buffer stops a fling, and adds resistant overshoot
+    /// up to the overshoot limit.
+    pub fn apply(&mut self, font_system: &mut FontSystem, buffer: &mut Buffer, delta: f32) {
+        let mut delta = delta;
+
+        // Scrolling back towards the buffer first reduces overshoot
+        if self.overshoot * delta < 0.0 {
+            let overshoot = self.overshoot + delta;
+            if overshoot * self.overshoot > 0.0 {
+                self.overshoot = overshoot;
+                return;
+            }
+            self.overshoot = 0.0;
+            delta = overshoot;
+        }
+        if delta == 0.0 {
+            return;
+        }
+
+        let old_scroll = buffer.scroll();
+        let mut scroll = old_scroll;
+        scroll.vertical += delta;
+        buffer.set_scroll(scroll);
+        buffer.shape_until_scroll(font_system, false);
+
+        let remaining = delta - scroll_distance(font_system, buffer, old_scroll);
+        if remaining.abs() >= 0.5 {
+            // Reached the extents of the buffer
+            self.velocity = 0.0;
+            if let Some(limit) = self.overshoot_limit {
+                if limit > 0.0 {
+                    // Resistance increases the further the extents are overshot
+                    let resistance = 1.0 - (self.overshoot.abs() / limit).min(1.0);
+                    self.overshoot = (self.overshoot + remaining * resistance).clamp(-limit, limit);
+                }
+            }
+        }
+    }
+}
+
+impl Default for ScrollPhysics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Distance in pixels scrolled from `old_scroll` to the current scroll of `buffer`
+fn scroll_distance(font_system: &mut FontSystem, buffer: &mut Buffer, old_scroll: Scroll) -> f32 {
+    let scroll = buffer.scroll();
+    let line_height = buffer.scaled_metrics().line_height;
+    let (start, end, sign) = if old_scroll.line <= scroll.line {
+        (old_scroll.line, scroll.line, 1.0)
+    } else {
+        (scroll.line, old_scroll.line, -1.0)
+    };
+
+    let mut lines_height = 0.0;
+    for line_i in start..end {
+        lines_height += buffer
+            .line_layout(font_system, line_i)
+            .map_or(line_height, |layout| {
+                layout
+                    .iter()
+                    .map(|layout_line| layout_line.height(line_height))
+                    .sum()
+            });
+    }
+
+    sign * lines_height + scroll.vertical - old_scroll.vertical
+}
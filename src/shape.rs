@@ -3,18 +3,22 @@
 #![allow(clippy::too_many_arguments)]
 
 #[cfg(not(feature = "std"))]
-use alloc::vec::Vec;
+use alloc::{format, string::String, vec::Vec};
 use core::cmp::{max, min};
 use core::fmt;
 use core::mem;
 use core::ops::Range;
 use unicode_script::{Script, UnicodeScript};
-use unicode_segmentation::UnicodeSegmentation;
 
-use crate::fallback::FontFallbackIter;
+use crate::fallback::{FallbackMemoKey, FontFallbackIter};
+use crate::graphemes::{grapheme_indices, is_emoji_presentation};
+use crate::layout::find_extras;
 use crate::{
-    math, Align, AttrsList, CacheKeyFlags, Color, Font, FontSystem, LayoutGlyph, LayoutLine,
-    Metrics, Wrap,
+    math, Align, Attrs, AttrsList, BaselineShift, CacheKeyFlags, Caps, Color, EmphasisMark, Font,
+    FontFeature, FontMatchKey, FontSystem, FontVariationsId, GlyphFlags, JustifyMode, LayoutGlyph,
+    LayoutGlyphExtras, LayoutLine, LineExclusions, LineHeight, Metadata, Metrics,
+    MonospaceAdjustment, ShaperInstances, TabStops, TextDecoration, TrailingWhitespace, Whitespace,
+    Wrap,
 };
 
 /// The shaping strategy of some text.
@@ -38,23 +42,35 @@ pub enum Shaping {
 }
 
 impl Shaping {
+    #[allow(clippy::too_many_arguments)]
     fn run(
         self,
         glyphs: &mut Vec<ShapeGlyph>,
         font_system: &mut FontSystem,
+        settings: ShapeSettings,
         line: &str,
         attrs_list: &AttrsList,
         start_run: usize,
         end_run: usize,
         span_rtl: bool,
     ) {
+        let start_glyph = glyphs.len();
         match self {
             #[cfg(feature = "swash")]
-            Self::Basic => shape_skip(font_system, glyphs, line, attrs_list, start_run, end_run),
+            Self::Basic => shape_skip(
+                font_system,
+                settings,
+                glyphs,
+                line,
+                attrs_list,
+                start_run,
+                end_run,
+            ),
             #[cfg(not(feature = "shape-run-cache"))]
             Self::Advanced => shape_run(
                 glyphs,
                 font_system,
+                settings,
                 line,
                 attrs_list,
                 start_run,
@@ -65,6 +81,7 @@ impl Shaping {
             Self::Advanced => shape_run_cached(
                 glyphs,
                 font_system,
+                settings,
                 line,
                 attrs_list,
                 start_run,
@@ -72,6 +89,323 @@ impl Shaping {
                 span_rtl,
             ),
         }
+
+        // Synthesized styles depend on the face each glyph fell back to
+        if font_system.font_synthesis() {
+            for glyph in glyphs[start_glyph..].iter_mut() {
+                glyph.cache_key_flags |=
+                    font_system.synthesis_flags(glyph.font_id, &attrs_list.get_span(glyph.start));
+            }
+        }
+    }
+}
+
+/// How control characters are displayed
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum ControlChars {
+    /// Shape control characters like any other character, usually resulting in invisible glyphs
+    #[default]
+    Shape,
+    /// Display C0 control characters and DEL as control pictures like `␛`, and other control
+    /// characters as their hex value
+    Symbol,
+    /// Display control characters as their hex value, like `<1B>`
+    Hex,
+    /// Do not display control characters
+    Hide,
+}
+
+impl ControlChars {
+    /// Replace the glyphs of control characters according to this policy, shaping their
+    /// replacements with `settings`
+    #[allow(clippy::too_many_arguments)]
+    fn replace(
+        self,
+        glyphs: &mut Vec<ShapeGlyph>,
+        font_system: &mut FontSystem,
+        settings: ShapeSettings,
+        line: &str,
+        attrs_list: &AttrsList,
+        shaping: Shaping,
+        span_rtl: bool,
+    ) {
+        // Tabs are handled separately, and only glyphs covering exactly one character are replaced
+        let control_char = |glyph: &ShapeGlyph| {
+            let mut chars = line.get(glyph.start..glyph.end)?.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) if c.is_control() && c != '\t' => Some(c),
+                _ => None,
+            }
+        };
+
+        if self == Self::Shape || !glyphs.iter().any(|glyph| control_char(glyph).is_some()) {
+            return;
+        }
+
+        let mut replaced = Vec::with_capacity(glyphs.len());
+        let mut last_start = None;
+        for glyph in glyphs.drain(..) {
+            let Some(c) = control_char(&glyph) else {
+                replaced.push(glyph);
+                continue;
+            };
+
+            // Only replace the first glyph of each control character
+            if last_start == Some(glyph.start) {
+                continue;
+            }
+            last_start = Some(glyph.start);
+
+            let picture = match c {
+                '\0'..='\x1F' => char::from_u32(0x2400 + c as u32),
+                '\x7F' => Some('\u{2421}'),
+                _ => None,
+            };
+            let symbol = match (self, picture) {
+                (Self::Hide, _) => continue,
+                (Self::Symbol, Some(picture)) => String::from(picture),
+                _ => format!("<{:02X}>", c as u32),
+            };
+
+            let start = replaced.len();
+            shaping.run(
+                &mut replaced,
+                font_system,
+                settings,
+                &symbol,
+                &AttrsList::new(attrs_list.get_span(glyph.start)),
+                0,
+                symbol.len(),
+                false,
+            );
+            for replacement in replaced[start..].iter_mut() {
+                replacement.start = glyph.start;
+                replacement.end = glyph.end;
+            }
+            if span_rtl {
+                // Glyphs of RTL spans are stored in visual order
+                replaced[start..].reverse();
+            }
+        }
+        *glyphs = replaced;
+    }
+}
+
+/// Direction the lines of a [`crate::Buffer`] run in
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Direction {
+    /// Lines run left to right or right to left, and are stacked top to bottom
+    #[default]
+    Horizontal,
+    /// Lines run top to bottom as columns, and are stacked right to left, as in traditional CJK
+    /// and Mongolian text
+    ///
+    /// Upright characters like CJK ideographs, kana, and Hangul advance by their vertical
+    /// metrics from the `vmtx` table, other characters are rotated 90 degrees clockwise. Layout
+    /// happens in a rotated frame: the `x` coordinates of glyphs and cursors run down a column,
+    /// and the `y` coordinates of runs run across columns from the right edge of the buffer.
+    /// [`crate::Buffer::draw`] maps this frame to the screen.
+    Vertical,
+}
+
+impl Direction {
+    /// Get the OpenType features enabled for `range` of a run in this direction
+    ///
+    /// Vertical text enables `vert`, which substitutes vertical alternates for punctuation,
+    /// brackets and the prolonged sound mark. `vrt2` is not enabled, since it also substitutes
+    /// rotated glyphs for characters that [`Self::orient`] rotates itself.
+    fn font_features(
+        self,
+        range: Range<usize>,
+    ) -> impl Iterator<Item = (Range<usize>, FontFeature)> {
+        const VERTICAL: &[FontFeature] = &[FontFeature::enable(b"vert")];
+        let features = match self {
+            Self::Horizontal => &[],
+            Self::Vertical => VERTICAL,
+        };
+        features
+            .iter()
+            .map(move |feature| (range.clone(), *feature))
+    }
+
+    /// Orient the glyphs of a line for this direction
+    ///
+    /// Upright glyphs get the vertical advance and origin of their font, falling back to the
+    /// height of the em box. Offsets are swapped so that `x_offset` runs down the column and
+    /// `y_offset` runs across it to the right, measured from the center of the column.
+    ///
+    /// Runs of glyphs with [`crate::Attrs::combine_upright`] are set upright side by side instead,
+    /// see [`combine_upright`].
+    fn orient(
+        self,
+        glyphs: &mut [ShapeGlyph],
+        font_system: &mut FontSystem,
+        line: &str,
+        attrs_list: &AttrsList,
+    ) {
+        if self == Self::Horizontal {
+            return;
+        }
+
+        let combined = |glyph: &ShapeGlyph| attrs_list.get_span(glyph.start).combine_upright;
+        let mut start = 0;
+        while let Some(offset) = glyphs[start..].iter().position(combined) {
+            let run_start = start + offset;
+            let run_end = glyphs[run_start..]
+                .iter()
+                .position(|glyph| !combined(glyph))
+                .map_or(glyphs.len(), |len| run_start + len);
+            combine_upright(&mut glyphs[run_start..run_end]);
+            start = run_end;
+        }
+
+        for glyph in glyphs.iter_mut() {
+            if combined(glyph) {
+                continue;
+            }
+            let c_opt = line.get(glyph.start..).and_then(|text| text.chars().next());
+            let font_opt = font_system.get_font(glyph.font_id);
+            let glyph_id = ttf_parser::GlyphId(glyph.glyph_id);
+            let upright = c_opt.map_or(false, |c| {
+                // The glyph differs from the one of the character if `vert` replaced it
+                is_upright(c)
+                    || (has_vertical_alternate(c)
+                        && font_opt.as_ref().map_or(false, |font| {
+                            font.rustybuzz()
+                                .glyph_index(c)
+                                .map_or(false, |nominal| nominal != glyph_id)
+                        }))
+            });
+            if !upright {
+                // Center the em box of the rotated glyph on the column
+                glyph.cache_key_flags |= CacheKeyFlags::ROTATE_CW;
+                glyph.y_offset -= (glyph.ascent - glyph.descent) / 2.0;
+                continue;
+            }
+
+            let Some(font) = font_opt else {
+                continue;
+            };
+            let face = font.rustybuzz();
+            let font_scale = face.units_per_em() as f32;
+            let advance = face
+                .glyph_ver_advance(glyph_id)
+                .map_or(glyph.ascent + glyph.descent, |advance| {
+                    f32::from(advance) / font_scale
+                });
+            let origin = face
+                .glyph_y_origin(glyph_id)
+                .map_or(glyph.ascent, |origin| f32::from(origin) / font_scale);
+
+            let (x_offset, y_offset) = (glyph.x_offset, glyph.y_offset);
+            glyph.x_offset = origin - y_offset;
+            glyph.y_offset = x_offset - glyph.x_advance / 2.0;
+            glyph.x_advance = advance;
+            glyph.y_advance = 0.0;
+        }
+    }
+}
+
+/// Set `glyphs` side by side across a vertical column in one upright em, scaled down if they are
+/// wider than one em. The whole em is the advance of the last glyph
+fn combine_upright(glyphs: &mut [ShapeGlyph]) {
+    let scale = |glyph: &ShapeGlyph| glyph.size_scale_opt.unwrap_or(1.0);
+    let width: f32 = glyphs
+        .iter()
+        .map(|glyph| glyph.x_advance * scale(glyph))
+        .sum();
+    let fit = if width > 1.0 { 1.0 / width } else { 1.0 };
+
+    // Offsets are in em of the glyph, advances and the pen in em of the text
+    let mut pen = -width * fit / 2.0;
+    let last = glyphs.len().saturating_sub(1);
+    for (i, glyph) in glyphs.iter_mut().enumerate() {
+        if fit < 1.0 {
+            glyph.size_scale_opt = Some(scale(glyph) * fit);
+        }
+        let glyph_scale = scale(glyph);
+        // Center the em box of the glyph down the column
+        let baseline = (1.0 + (glyph.ascent - glyph.descent) * glyph_scale) / 2.0;
+
+        let (x_offset, y_offset) = (glyph.x_offset, glyph.y_offset);
+        glyph.x_offset = baseline / glyph_scale - y_offset;
+        glyph.y_offset = pen / glyph_scale + x_offset;
+        pen += glyph.x_advance * glyph_scale;
+        glyph.x_advance = if i == last { 1.0 / glyph_scale } else { 0.0 };
+        glyph.y_advance = 0.0;
+    }
+}
+
+/// Returns true if `c` is set upright in vertical text, approximating the `U` and `Tu` values of
+/// the Unicode `Vertical_Orientation` property. Characters with vertical alternates are rotated
+/// instead, see [`has_vertical_alternate`]
+fn is_upright(c: char) -> bool {
+    if has_vertical_alternate(c) {
+        return false;
+    }
+    matches!(
+        c,
+        '\u{1100}'..='\u{11FF}'
+            | '\u{2E80}'..='\u{A4CF}'
+            | '\u{A960}'..='\u{A97F}'
+            | '\u{AC00}'..='\u{D7FF}'
+            | '\u{F900}'..='\u{FAFF}'
+            | '\u{FE10}'..='\u{FE1F}'
+            | '\u{FE30}'..='\u{FE4F}'
+            | '\u{FF00}'..='\u{FF60}'
+            | '\u{FFE0}'..='\u{FFE6}'
+            | '\u{1F200}'..='\u{1FAFF}'
+            | '\u{20000}'..='\u{3FFFD}'
+    )
+}
+
+/// Returns true if `c` is a bracket or other character that is set upright with the vertical
+/// alternate glyph of the `vert` feature, and rotated if the font has none, approximating the
+/// `Tr` value of the Unicode `Vertical_Orientation` property
+fn has_vertical_alternate(c: char) -> bool {
+    matches!(
+        c,
+        '\u{3008}'..='\u{3011}'
+            | '\u{3014}'..='\u{301F}'
+            | '\u{3030}'
+            | '\u{30A0}'
+            | '\u{30FC}'
+            | '\u{FF08}'
+            | '\u{FF09}'
+            | '\u{FF0D}'
+            | '\u{FF1C}'..='\u{FF1E}'
+            | '\u{FF3B}'
+            | '\u{FF3D}'
+            | '\u{FF3F}'
+            | '\u{FF5B}'..='\u{FF60}'
+    )
+}
+
+/// Settings of a [`crate::BufferLine`] that change how its text is shaped, see
+/// [`ShapeLine::build_with_settings`]
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct ShapeSettings<'a> {
+    /// How control characters are displayed
+    pub control_chars: ControlChars,
+    /// Direction glyphs are oriented for
+    pub direction: Direction,
+    /// Locale preferred over the locales of the [`FontSystem`] for script fallback
+    pub locale_opt: Option<&'a str>,
+    /// Font size and monospace width of a [`crate::Buffer`] in
+    /// [`crate::MonospaceMode::Strict`]
+    pub strict_monospace: Option<(f32, f32)>,
+    /// Length in bytes above which runs of text are shaped in chunks
+    pub shape_chunk_len: Option<usize>,
+}
+
+impl ShapeSettings<'_> {
+    /// Get the em width monospace glyphs with `attrs` are substituted to match, if any
+    fn strict_monospace_em_width(&self, attrs: Attrs) -> Option<f32> {
+        let (font_size, monospace_width) = self.strict_monospace?;
+        let font_size = attrs
+            .metrics_opt
+            .map_or(font_size, |metrics| Metrics::from(metrics).font_size);
+        Some(monospace_width / font_size)
     }
 }
 
@@ -96,6 +430,19 @@ pub struct ShapeBuffer {
 
     /// Buffer for sets of layout glyphs.
     glyph_sets: Vec<Vec<LayoutGlyph>>,
+
+    /// Buffer for the regions of a visual line covered by exclusions.
+    blocked: Vec<(f32, f32)>,
+
+    /// Buffer for the sorted span, word, and glyph indices of tabs.
+    tab_glyphs: Vec<(usize, usize, usize)>,
+
+    /// Cluster starts and original fonts of the glyphs of the line being shaped that were
+    /// replaced by monospace fallback fonts.
+    monospace_substitutes: Vec<(usize, fontdb::ID)>,
+
+    /// Faces of variable fonts and the interned sets of axis values they are shaped with.
+    pub(crate) instances: ShaperInstances,
 }
 
 impl fmt::Debug for ShapeBuffer {
@@ -104,6 +451,43 @@ impl fmt::Debug for ShapeBuffer {
     }
 }
 
+/// Get the OpenType features of the attributes of the text of `run`, with ranges relative to the
+/// start of the run, so features of a span only apply to its text
+pub(crate) fn run_features(
+    line: &str,
+    attrs_list: &AttrsList,
+    run: Range<usize>,
+) -> Vec<(Range<usize>, FontFeature)> {
+    let mut features = Vec::new();
+    let mut push = |range: Range<usize>, attrs: Attrs| {
+        for feature in attrs.font_features.iter() {
+            features.push((range.clone(), *feature));
+        }
+    };
+    let mut start = run.start;
+    let mut attrs = attrs_list.get_span(start);
+    for (i, _) in line[run.clone()].char_indices() {
+        let attrs_char = attrs_list.get_span(run.start + i);
+        if attrs_char.font_features != attrs.font_features {
+            push(start - run.start..i, attrs);
+            start = run.start + i;
+            attrs = attrs_char;
+        }
+    }
+    push(start - run.start..run.len(), attrs);
+    features
+}
+
+/// Get the weight of glyphs of `font` for [`crate::CacheKey::font_weight`], which only variable
+/// fonts with a `wght` axis apply
+fn axis_weight(font: &Font, weight: fontdb::Weight) -> fontdb::Weight {
+    if font.weight_axis().is_some() {
+        weight
+    } else {
+        fontdb::Weight::NORMAL
+    }
+}
+
 fn shape_fallback(
     scratch: &mut ShapeBuffer,
     glyphs: &mut Vec<ShapeGlyph>,
@@ -113,6 +497,7 @@ fn shape_fallback(
     start_run: usize,
     end_run: usize,
     span_rtl: bool,
+    direction: Direction,
 ) -> Vec<usize> {
     let run = &line[start_run..end_run];
 
@@ -120,13 +505,32 @@ fn shape_fallback(
     let ascent = font.rustybuzz().ascender() as f32 / font_scale;
     let descent = -font.rustybuzz().descender() as f32 / font_scale;
 
+    // Small capitals use the features of the font, or are synthesized from uppercase letters
+    let run_attrs = attrs_list.get_span(start_run);
+    let caps_features = run_attrs
+        .caps_opt
+        .and_then(|caps| caps.font_features(font.rustybuzz()));
+    let synthesized_caps = run_attrs.caps_opt.filter(|_| caps_features.is_none());
+
     let mut buffer = scratch.rustybuzz_buffer.take().unwrap_or_default();
     buffer.set_direction(if span_rtl {
         rustybuzz::Direction::RightToLeft
     } else {
         rustybuzz::Direction::LeftToRight
     });
-    if run.contains('\t') {
+    if let Some(caps) = synthesized_caps {
+        // Clusters of uppercase letters are the indices of the letters they replace
+        for (i, c) in run.char_indices() {
+            let cluster = i as u32;
+            if caps.synthesizes(c) {
+                for upper in c.to_uppercase() {
+                    buffer.add(upper, cluster);
+                }
+            } else {
+                buffer.add(if c == '\t' { ' ' } else { c }, cluster);
+            }
+        }
+    } else if run.contains('\t') {
         // Push string to buffer, replacing tabs with spaces
         //TODO: Find a way to do this with minimal allocating, calling
         // UnicodeBuffer::push_str multiple times causes issues and
@@ -140,14 +544,32 @@ fn shape_fallback(
     let rtl = matches!(buffer.direction(), rustybuzz::Direction::RightToLeft);
     assert_eq!(rtl, span_rtl);
 
+    // Apply the weight and variations to variable fonts
+    let font_weight = axis_weight(font, run_attrs.weight);
+    let (font_variations, face) = scratch.instances.get(font, &run_attrs);
+
+    let features: Vec<rustybuzz::Feature> = caps_features
+        .unwrap_or_default()
+        .iter()
+        .map(|tag| (0..run.len(), FontFeature::enable(tag)))
+        .chain(direction.font_features(0..run.len()))
+        .chain(run_features(line, attrs_list, start_run..end_run))
+        .map(|(range, feature)| {
+            rustybuzz::Feature::new(
+                rustybuzz::ttf_parser::Tag::from_bytes(&feature.tag),
+                feature.value,
+                range,
+            )
+        })
+        .collect();
     let shape_plan = rustybuzz::ShapePlan::new(
-        font.rustybuzz(),
+        face,
         buffer.direction(),
         Some(buffer.script()),
         buffer.language().as_ref(),
-        &[],
+        &features,
     );
-    let glyph_buffer = rustybuzz::shape_with_plan(font.rustybuzz(), &shape_plan, buffer);
+    let glyph_buffer = rustybuzz::shape_with_plan(face, &shape_plan, buffer);
     let glyph_infos = glyph_buffer.glyph_infos();
     let glyph_positions = glyph_buffer.glyph_positions();
 
@@ -167,6 +589,14 @@ fn shape_fallback(
         }
 
         let attrs = attrs_list.get_span(start_glyph);
+        let caps_scale_opt = synthesized_caps
+            .filter(|caps| {
+                line[start_glyph..]
+                    .chars()
+                    .next()
+                    .map_or(false, |c| caps.synthesizes(c))
+            })
+            .map(|_| Caps::SYNTHESIZED_SCALE);
         glyphs.push(ShapeGlyph {
             start: start_glyph,
             end: end_run, // Set later
@@ -179,11 +609,17 @@ fn shape_fallback(
             font_monospace_em_width: font.monospace_em_width(),
             font_id: font.id(),
             glyph_id: info.glyph_id.try_into().expect("failed to cast glyph ID"),
+            font_weight,
+            font_variations,
             //TODO: color should not be related to shaping
             color_opt: attrs.color_opt,
             metadata: attrs.metadata,
             cache_key_flags: attrs.cache_key_flags,
             metrics_opt: attrs.metrics_opt.map(|x| x.into()),
+            line_height_opt: attrs.line_height_opt,
+            decoration: attrs.decoration,
+            size_scale_opt: caps_scale_opt,
+            whitespace_opt: None,
         });
     }
 
@@ -218,9 +654,249 @@ fn shape_fallback(
     missing
 }
 
+/// Number of tatweels a joint is lengthened by at most with [`JustifyMode::Kashida`], unless
+/// the line has no spaces to expand instead
+const KASHIDA_MAX_TATWEELS: usize = 3;
+
+/// A gap between two clusters that is expanded by [`JustifyMode::InterCharacter`] or
+/// [`JustifyMode::Kashida`]
+enum JustifyGap<'a> {
+    /// Left empty
+    Space,
+    /// Filled with tatweels lengthening the joint after this letter
+    Tatweel(ExtendedGlyph<'a>),
+}
+
+/// A shaped glyph and its extras, if it has any
+type ExtendedGlyph<'a> = (&'a ShapeGlyph, Option<&'a ShapeGlyphExtras>);
+
+/// Get the tatweel of a letter, see [`ShapeGlyphExtras::tatweel_opt`]
+fn tatweel((_, extras): ExtendedGlyph) -> Option<(u16, f32)> {
+    extras.and_then(|extras| extras.tatweel_opt)
+}
+
+/// Get how the gap between the clusters of `last` and `glyph`, laid out one after another, is
+/// expanded
+fn justify_gap<'a>(
+    justify_mode: JustifyMode,
+    rtl: bool,
+    last: ExtendedGlyph<'a>,
+    glyph: ExtendedGlyph<'a>,
+) -> Option<JustifyGap<'a>> {
+    match justify_mode {
+        JustifyMode::InterWord => None,
+        JustifyMode::InterCharacter => Some(JustifyGap::Space),
+        JustifyMode::Kashida => {
+            // The letter before the gap in logical order is laid out first in RTL lines
+            let letter = if rtl { last } else { glyph };
+            tatweel(letter).map(|_| JustifyGap::Tatweel(letter))
+        }
+    }
+}
+
+/// Push `glyph`, laid out from a shaped glyph with `extras`, and the extras it keeps
+fn push_glyph(
+    glyphs: &mut Vec<LayoutGlyph>,
+    glyph_extras: &mut Vec<(usize, LayoutGlyphExtras)>,
+    glyph: LayoutGlyph,
+    extras: Option<&ShapeGlyphExtras>,
+) {
+    if let Some(extras) = extras.and_then(ShapeGlyphExtras::layout) {
+        glyph_extras.push((glyphs.len(), extras));
+    }
+    glyphs.push(glyph);
+}
+
+/// Fill `gap` = (x, y, width) with tatweels of the font of `letter`
+fn push_tatweels(
+    glyphs: &mut Vec<LayoutGlyph>,
+    glyph_extras: &mut Vec<(usize, LayoutGlyphExtras)>,
+    letter: ExtendedGlyph,
+    font_size: f32,
+    line_height_opt: Option<f32>,
+    gap: (f32, f32, f32),
+    level: unicode_bidi::Level,
+) {
+    let Some((glyph_id, advance)) = tatweel(letter) else {
+        return;
+    };
+    let (letter, extras) = letter;
+    let (x, y, w) = gap;
+    // Tatweels overlap to fill the gap exactly, allowing for rounding in whole tatweel gaps
+    let count = math::ceilf(w / (advance * font_size).max(1.0) - 0.01).max(1.0);
+    let tatweel_w = w / count;
+    for i in 0..count as usize {
+        let mut tatweel = letter.layout(
+            font_size,
+            line_height_opt,
+            x + tatweel_w * i as f32,
+            y,
+            tatweel_w,
+            level,
+        );
+        tatweel.glyph_id = glyph_id;
+        tatweel.x_offset = 0.0;
+        tatweel.y_offset = 0.0;
+        push_glyph(glyphs, glyph_extras, tatweel, extras);
+    }
+}
+
+/// Add the glyphs of `marker` after the last glyph of `layout_line`, in the direction of the
+/// line, as the [`Whitespace::LineEnding`] at `index`. They do not change the width of the line
+pub(crate) fn push_line_ending(
+    layout_line: &mut LayoutLine,
+    marker: &ShapeLine,
+    index: usize,
+    font_size: f32,
+    text_scale: f32,
+    rtl: bool,
+) {
+    let mut x = if rtl {
+        layout_line
+            .glyphs
+            .iter()
+            .map(|glyph| glyph.x)
+            .reduce(f32::min)
+    } else {
+        layout_line
+            .glyphs
+            .iter()
+            .map(|glyph| glyph.x + glyph.w)
+            .reduce(f32::max)
+    }
+    .unwrap_or(0.0);
+    let level = if rtl {
+        unicode_bidi::Level::rtl()
+    } else {
+        unicode_bidi::Level::ltr()
+    };
+    let glyphs = marker
+        .spans
+        .iter()
+        .flat_map(|span| span.words.iter())
+        .flat_map(|word| word.extended_glyphs());
+    for (glyph, extras) in glyphs {
+        let line_height_opt = glyph.line_height(font_size).map(|x| x * text_scale);
+        let glyph_font_size = glyph.font_size(font_size) * text_scale;
+        let x_advance = glyph_font_size * glyph.x_advance;
+        if rtl {
+            x -= x_advance;
+        }
+        let mut layout_glyph =
+            glyph.layout(glyph_font_size, line_height_opt, x, 0.0, x_advance, level);
+        layout_glyph.start = index as u32;
+        layout_glyph.end = index as u32;
+        layout_glyph.flags |= Whitespace::LineEnding.flag();
+        push_glyph(
+            &mut layout_line.glyphs,
+            &mut layout_line.extras,
+            layout_glyph,
+            extras,
+        );
+        if !rtl {
+            x += x_advance;
+        }
+    }
+}
+
+/// Call `f` with the glyphs of the `order` of `ranges` of a visual line and their extras, in
+/// layout order
+fn visit_glyphs<'a>(
+    spans: &'a [ShapeSpan],
+    ranges: &[VlRange],
+    order: impl Iterator<Item = Range<usize>>,
+    mut f: impl FnMut(ExtendedGlyph<'a>),
+) {
+    for range in order {
+        for &(span_index, (starting_word, starting_glyph), (ending_word, ending_glyph)) in
+            ranges[range].iter()
+        {
+            let span = &spans[span_index];
+            for i in starting_word..ending_word + usize::from(ending_glyph != 0) {
+                let word = &span.words[i];
+                let included_glyphs = match (i == starting_word, i == ending_word) {
+                    (false, false) => 0..word.glyphs.len(),
+                    (true, false) => starting_glyph..word.glyphs.len(),
+                    (false, true) => 0..ending_glyph,
+                    (true, true) => starting_glyph..ending_glyph,
+                };
+                for glyph_i in included_glyphs {
+                    f((&word.glyphs[glyph_i], word.glyph_extras(glyph_i)));
+                }
+            }
+        }
+    }
+}
+
+/// Get whether an Arabic letter joins to the letter before it only (false), or to the letters on
+/// both sides (true). None for characters that do not join
+fn arabic_joining(c: char) -> Option<bool> {
+    match c {
+        '\u{0622}'..='\u{0625}'
+        | '\u{0627}'
+        | '\u{0629}'
+        | '\u{062F}'..='\u{0632}'
+        | '\u{0648}'
+        | '\u{0671}'..='\u{0673}'
+        | '\u{0675}'..='\u{0677}'
+        | '\u{0688}'..='\u{0699}'
+        | '\u{06C0}'
+        | '\u{06C3}'..='\u{06CB}'
+        | '\u{06CD}'
+        | '\u{06CF}'
+        | '\u{06D2}'..='\u{06D3}'
+        | '\u{06D5}'
+        | '\u{06EE}'..='\u{06EF}' => Some(false),
+        '\u{0620}'
+        | '\u{0626}'
+        | '\u{0628}'
+        | '\u{062A}'..='\u{062E}'
+        | '\u{0633}'..='\u{064A}'
+        | '\u{066E}'..='\u{066F}'
+        | '\u{0678}'..='\u{0687}'
+        | '\u{069A}'..='\u{06BF}'
+        | '\u{06C1}'..='\u{06C2}'
+        | '\u{06CC}'
+        | '\u{06CE}'
+        | '\u{06D0}'..='\u{06D1}'
+        | '\u{06FA}'..='\u{06FC}'
+        | '\u{06FF}'
+        | '\u{0750}'..='\u{077F}' => Some(true),
+        _ => None,
+    }
+}
+
+/// Get the priority of lengthening the joint after the cluster at `start..end` of `line` with
+/// tatweels, lower is better. None if the cluster does not join to the next letter
+///
+/// Joints after seen and sad are lengthened first, then joints before the last letter of a word,
+/// then any other joint.
+fn kashida_priority(line: &str, start: usize, end: usize) -> Option<u8> {
+    let is_mark =
+        |c: &char| matches!(c, '\u{064B}'..='\u{065F}' | '\u{0670}' | '\u{06D6}'..='\u{06ED}');
+    let c = line.get(start..)?.chars().next()?;
+    let mut letters = line.get(end..)?.chars().filter(|c| !is_mark(c));
+    let next = letters.next()?;
+    // Tatweel itself is dual-joining, but is not lengthened further
+    if c == '\u{640}' || arabic_joining(c) != Some(true) || arabic_joining(next).is_none() {
+        return None;
+    }
+    if matches!(c, '\u{0633}'..='\u{0636}') {
+        Some(0)
+    } else if arabic_joining(next) == Some(false)
+        || letters.next().map_or(true, |c| arabic_joining(c).is_none())
+    {
+        Some(1)
+    } else {
+        Some(2)
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn shape_run(
     glyphs: &mut Vec<ShapeGlyph>,
     font_system: &mut FontSystem,
+    settings: ShapeSettings,
     line: &str,
     attrs_list: &AttrsList,
     start_run: usize,
@@ -250,106 +926,179 @@ fn shape_run(
 
     let fonts = font_system.get_font_matches(attrs);
 
-    let default_families = [&attrs.family];
-    let mut font_iter = FontFallbackIter::new(
-        font_system,
-        &fonts,
-        &default_families,
-        &scripts,
-        &line[start_run..end_run],
-    );
-
-    let font = font_iter.next().expect("no default font found");
-
-    let glyph_start = glyphs.len();
-    let mut missing = {
-        let scratch = font_iter.shape_caches();
-        shape_fallback(
-            scratch, glyphs, &font, line, attrs_list, start_run, end_run, span_rtl,
+    // Runs are split between emoji and text, so one emoji font can shape the whole run
+    let emoji_run = grapheme_indices(&line[start_run..end_run])
+        .next()
+        .map_or(false, |(_, egc)| is_emoji_presentation(egc));
+    if emoji_run
+        && shape_emoji(
+            glyphs,
+            font_system,
+            &fonts,
+            line,
+            attrs_list,
+            start_run,
+            end_run,
+            span_rtl,
+            settings.direction,
         )
-    };
-
-    //TODO: improve performance!
-    while !missing.is_empty() {
-        let font = match font_iter.next() {
-            Some(some) => some,
-            None => break,
-        };
+    {
+        font_system.shape_buffer.scripts = scripts;
+        return;
+    }
 
-        log::trace!(
-            "Evaluating fallback with font '{}'",
-            font_iter.face_name(font.id())
-        );
-        let mut fb_glyphs = Vec::new();
-        let scratch = font_iter.shape_caches();
-        let fb_missing = shape_fallback(
-            scratch,
-            &mut fb_glyphs,
+    let word = &line[start_run..end_run];
+    let glyph_start = glyphs.len();
+    let memo_font = font_system
+        .fallback_memo
+        .get(&FallbackMemoKey {
+            word,
+            scripts: &scripts,
+            attrs,
+            locale: settings.locale_opt.unwrap_or(font_system.locale()),
+        })
+        .and_then(|font_ids| Some((font_system.get_font(*font_ids.first()?)?, font_ids)));
+    let font = if let Some((font, font_ids)) = memo_font {
+        // Replay the fonts chosen for an identical run
+        let mut missing = shape_fallback(
+            &mut font_system.shape_buffer,
+            glyphs,
             &font,
             line,
             attrs_list,
             start_run,
             end_run,
             span_rtl,
+            settings.direction,
+        );
+        for &font_id in font_ids[1..].iter() {
+            if missing.is_empty() {
+                break;
+            }
+            let Some(fb_font) = font_system.get_font(font_id) else {
+                continue;
+            };
+            font_system.fallback_iterations = font_system.fallback_iterations.wrapping_add(1);
+            let mut fb_glyphs = Vec::new();
+            let fb_missing = shape_fallback(
+                &mut font_system.shape_buffer,
+                &mut fb_glyphs,
+                &fb_font,
+                line,
+                attrs_list,
+                start_run,
+                end_run,
+                span_rtl,
+                settings.direction,
+            );
+            merge_fallback(glyphs, glyph_start, &mut missing, fb_glyphs, &fb_missing);
+        }
+        font
+    } else {
+        let default_families = [&attrs.family];
+        let mut font_iter = FontFallbackIter::new(
+            font_system,
+            settings.locale_opt,
+            &fonts,
+            &default_families,
+            &scripts,
+            word,
         );
 
-        // Insert all matching glyphs
-        let mut fb_i = 0;
-        while fb_i < fb_glyphs.len() {
-            let start = fb_glyphs[fb_i].start;
-            let end = fb_glyphs[fb_i].end;
+        let font = font_iter.next().expect("no default font found");
+        let mut font_ids = Vec::from([font.id()]);
 
-            // Skip clusters that are not missing, or where the fallback font is missing
-            if !missing.contains(&start) || fb_missing.contains(&start) {
-                fb_i += 1;
-                continue;
-            }
+        let mut missing = {
+            let scratch = font_iter.shape_caches();
+            shape_fallback(
+                scratch,
+                glyphs,
+                &font,
+                line,
+                attrs_list,
+                start_run,
+                end_run,
+                span_rtl,
+                settings.direction,
+            )
+        };
 
-            let mut missing_i = 0;
-            while missing_i < missing.len() {
-                if missing[missing_i] >= start && missing[missing_i] < end {
-                    // println!("No longer missing {}", missing[missing_i]);
-                    missing.remove(missing_i);
-                } else {
-                    missing_i += 1;
-                }
-            }
+        //TODO: improve performance!
+        let mut fallback_iterations: u32 = 0;
+        while !missing.is_empty() {
+            let font = match font_iter.next() {
+                Some(some) => some,
+                None => break,
+            };
+            fallback_iterations += 1;
 
-            // Find prior glyphs
-            let mut i = glyph_start;
-            while i < glyphs.len() {
-                if glyphs[i].start >= start && glyphs[i].end <= end {
-                    break;
-                } else {
-                    i += 1;
-                }
-            }
+            log::trace!(
+                "Evaluating fallback with font '{}'",
+                font_iter.face_name(font.id())
+            );
+            let mut fb_glyphs = Vec::new();
+            let scratch = font_iter.shape_caches();
+            let fb_missing = shape_fallback(
+                scratch,
+                &mut fb_glyphs,
+                &font,
+                line,
+                attrs_list,
+                start_run,
+                end_run,
+                span_rtl,
+                settings.direction,
+            );
 
-            // Remove prior glyphs
-            while i < glyphs.len() {
-                if glyphs[i].start >= start && glyphs[i].end <= end {
-                    let _glyph = glyphs.remove(i);
-                    // log::trace!("Removed {},{} from {}", _glyph.start, _glyph.end, i);
-                } else {
-                    break;
-                }
+            if merge_fallback(glyphs, glyph_start, &mut missing, fb_glyphs, &fb_missing) {
+                font_ids.push(font.id());
             }
+        }
 
-            while fb_i < fb_glyphs.len() {
-                if fb_glyphs[fb_i].start >= start && fb_glyphs[fb_i].end <= end {
-                    let fb_glyph = fb_glyphs.remove(fb_i);
-                    // log::trace!("Insert {},{} from font {} at {}", fb_glyph.start, fb_glyph.end, font_i, i);
-                    glyphs.insert(i, fb_glyph);
-                    i += 1;
-                } else {
-                    break;
-                }
-            }
+        // Debug missing font fallbacks
+        font_iter.check_missing(word);
+
+        font_system.fallback_iterations = font_system
+            .fallback_iterations
+            .wrapping_add(fallback_iterations);
+
+        // Runs that the default font covers are quick to shape again
+        if fallback_iterations > 0 {
+            let (memo, locale) = font_system.fallback_memo_mut(settings.locale_opt);
+            let key = FallbackMemoKey {
+                word,
+                scripts: &scripts,
+                attrs,
+                locale,
+            };
+            memo.insert(&key, font_ids);
         }
+        font
+    };
+
+    if let Some(em_width) = settings.strict_monospace_em_width(attrs) {
+        substitute_monospace(
+            glyphs,
+            glyph_start,
+            font_system,
+            em_width,
+            line,
+            attrs_list,
+            start_run,
+            end_run,
+            span_rtl,
+            settings.direction,
+        );
     }
 
-    // Debug missing font fallbacks
-    font_iter.check_missing(&line[start_run..end_run]);
+    if let Some(compensation) = font_system.advance_compensation(&attrs.family, &font) {
+        for glyph in glyphs[glyph_start..]
+            .iter_mut()
+            .filter(|glyph| glyph.font_id == font.id())
+        {
+            glyph.x_advance = compensation.advance(&line[glyph.start..glyph.end], glyph.x_advance);
+        }
+    }
 
     /*
     for glyph in glyphs.iter() {
@@ -361,26 +1110,246 @@ fn shape_run(
     font_system.shape_buffer.scripts = scripts;
 }
 
+/// Replace glyphs of clusters in `missing` with the glyphs of a fallback font, returning true if
+/// any cluster was replaced
+fn merge_fallback(
+    glyphs: &mut Vec<ShapeGlyph>,
+    glyph_start: usize,
+    missing: &mut Vec<usize>,
+    mut fb_glyphs: Vec<ShapeGlyph>,
+    fb_missing: &[usize],
+) -> bool {
+    let mut merged = false;
+
+    // Insert all matching glyphs
+    let mut fb_i = 0;
+    while fb_i < fb_glyphs.len() {
+        let start = fb_glyphs[fb_i].start;
+        let end = fb_glyphs[fb_i].end;
+
+        // Skip clusters that are not missing, or where the fallback font is missing
+        if !missing.contains(&start) || fb_missing.contains(&start) {
+            fb_i += 1;
+            continue;
+        }
+        merged = true;
+
+        let mut missing_i = 0;
+        while missing_i < missing.len() {
+            if missing[missing_i] >= start && missing[missing_i] < end {
+                // println!("No longer missing {}", missing[missing_i]);
+                missing.remove(missing_i);
+            } else {
+                missing_i += 1;
+            }
+        }
+
+        // Find prior glyphs
+        let mut i = glyph_start;
+        while i < glyphs.len() {
+            if glyphs[i].start >= start && glyphs[i].end <= end {
+                break;
+            } else {
+                i += 1;
+            }
+        }
+
+        // Remove prior glyphs
+        while i < glyphs.len() {
+            if glyphs[i].start >= start && glyphs[i].end <= end {
+                let _glyph = glyphs.remove(i);
+                // log::trace!("Removed {},{} from {}", _glyph.start, _glyph.end, i);
+            } else {
+                break;
+            }
+        }
+
+        while fb_i < fb_glyphs.len() {
+            if fb_glyphs[fb_i].start >= start && fb_glyphs[fb_i].end <= end {
+                let fb_glyph = fb_glyphs.remove(fb_i);
+                // log::trace!("Insert {},{} from font {} at {}", fb_glyph.start, fb_glyph.end, font_i, i);
+                glyphs.insert(i, fb_glyph);
+                i += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    merged
+}
+
+/// Returns true if glyphs of a monospace font with `font_em_width` have to be scaled to match
+/// `match_em_width`, because it is not a multiple of it
+fn monospace_mismatch(font_em_width: f32, match_em_width: f32) -> bool {
+    let factor = font_em_width / match_em_width;
+    math::roundf(factor).max(1.0) != factor
+}
+
+/// Reshape clusters of monospace fonts that do not match `em_width` with the first monospace
+/// font matching the attributes of the run that does
+#[allow(clippy::too_many_arguments)]
+fn substitute_monospace(
+    glyphs: &mut Vec<ShapeGlyph>,
+    glyph_start: usize,
+    font_system: &mut FontSystem,
+    em_width: f32,
+    line: &str,
+    attrs_list: &AttrsList,
+    start_run: usize,
+    end_run: usize,
+    span_rtl: bool,
+    direction: Direction,
+) {
+    let mut missing = Vec::new();
+    for glyph in glyphs[glyph_start..].iter() {
+        if let Some(font_em_width) = glyph.font_monospace_em_width {
+            if monospace_mismatch(font_em_width, em_width) && !missing.contains(&glyph.start) {
+                missing.push(glyph.start);
+            }
+        }
+    }
+    if missing.is_empty() {
+        return;
+    }
+
+    let fonts = font_system.get_font_matches(attrs_list.get_span(start_run));
+    for m_key in fonts.iter() {
+        if missing.is_empty() {
+            break;
+        }
+        if !font_system.is_monospace(m_key.id) {
+            continue;
+        }
+        let Some(font) = font_system.get_font(m_key.id) else {
+            continue;
+        };
+        if font.monospace_em_width() != Some(em_width) {
+            continue;
+        }
+
+        let mut fb_glyphs = Vec::new();
+        let fb_missing = shape_fallback(
+            &mut font_system.shape_buffer,
+            &mut fb_glyphs,
+            &font,
+            line,
+            attrs_list,
+            start_run,
+            end_run,
+            span_rtl,
+            direction,
+        );
+        for fb_glyph in fb_glyphs.iter() {
+            let from = glyphs[glyph_start..]
+                .iter()
+                .find(|glyph| glyph.start == fb_glyph.start)
+                .map(|glyph| glyph.font_id);
+            if let Some(from) = from {
+                let substitutes = &mut font_system.shape_buffer.monospace_substitutes;
+                if !substitutes.contains(&(fb_glyph.start, from)) {
+                    substitutes.push((fb_glyph.start, from));
+                }
+            }
+        }
+        merge_fallback(glyphs, glyph_start, &mut missing, fb_glyphs, &fb_missing);
+    }
+
+    if !missing.is_empty() {
+        log::debug!(
+            "no monospace font with em width {} for '{}'",
+            em_width,
+            &line[start_run..end_run]
+        );
+    }
+}
+
+/// Shape an emoji run with the first emoji font that has glyphs for all of it, returning false
+/// if there is none
+#[allow(clippy::too_many_arguments)]
+fn shape_emoji(
+    glyphs: &mut Vec<ShapeGlyph>,
+    font_system: &mut FontSystem,
+    fonts: &[FontMatchKey],
+    line: &str,
+    attrs_list: &AttrsList,
+    start_run: usize,
+    end_run: usize,
+    span_rtl: bool,
+    direction: Direction,
+) -> bool {
+    //TODO: smarter way of detecting emoji fonts, see Attrs::matches
+    for m_key in fonts.iter() {
+        let is_emoji_font = font_system
+            .db()
+            .face(m_key.id)
+            .map_or(false, |face| face.post_script_name.contains("Emoji"));
+        if !is_emoji_font {
+            continue;
+        }
+        let Some(font) = font_system.get_font(m_key.id) else {
+            continue;
+        };
+
+        let glyph_start = glyphs.len();
+        let missing = shape_fallback(
+            &mut font_system.shape_buffer,
+            glyphs,
+            &font,
+            line,
+            attrs_list,
+            start_run,
+            end_run,
+            span_rtl,
+            direction,
+        );
+        if missing.is_empty() {
+            return true;
+        }
+        glyphs.truncate(glyph_start);
+    }
+    false
+}
+
 #[cfg(feature = "shape-run-cache")]
+#[allow(clippy::too_many_arguments)]
 fn shape_run_cached(
     glyphs: &mut Vec<ShapeGlyph>,
     font_system: &mut FontSystem,
+    settings: ShapeSettings,
     line: &str,
     attrs_list: &AttrsList,
     start_run: usize,
     end_run: usize,
     span_rtl: bool,
 ) {
-    use crate::{AttrsOwned, ShapeRunKey};
+    use crate::{FontMatchAttrs, ShapeRunKey};
 
+    // Only the attributes used when shaping are part of the key, so runs that differ only in
+    // color or metadata share cached glyphs. The other attributes are applied to each glyph.
     let run_range = start_run..end_run;
     let mut key = ShapeRunKey {
         text: line[run_range.clone()].to_string(),
-        default_attrs: AttrsOwned::new(attrs_list.defaults()),
+        locale: settings
+            .locale_opt
+            .unwrap_or(font_system.locale())
+            .to_string(),
+        default_attrs: FontMatchAttrs::from(attrs_list.defaults()),
         attrs_spans: Vec::new(),
+        font_features: settings
+            .direction
+            .font_features(0..run_range.len())
+            .chain(run_features(line, attrs_list, run_range.clone()))
+            .collect(),
+        variations: attrs_list.get_span(start_run).variations.to_vec(),
+        caps_opt: attrs_list.get_span(start_run).caps_opt,
+        strict_monospace_em_width: settings
+            .strict_monospace_em_width(attrs_list.get_span(start_run))
+            .map(f32::to_bits),
     };
     for (attrs_range, attrs) in attrs_list.spans.overlapping(&run_range) {
-        if attrs == &key.default_attrs {
+        let attrs = FontMatchAttrs::from(attrs.as_attrs());
+        if attrs == key.default_attrs {
             // Skip if attrs matches default attrs
             continue;
         }
@@ -391,8 +1360,11 @@ fn shape_run_cached(
             .checked_sub(start_run)
             .unwrap_or(0);
         if end > start {
-            let range = start..end;
-            key.attrs_spans.push((range, attrs.clone()));
+            match key.attrs_spans.last_mut() {
+                // Join spans that only differed in attributes not used when shaping
+                Some((range, last)) if range.end == start && *last == attrs => range.end = end,
+                _ => key.attrs_spans.push((start..end, attrs)),
+            }
         }
     }
     if let Some(cache_glyphs) = font_system.shape_run_cache.get(&key) {
@@ -400,16 +1372,19 @@ fn shape_run_cached(
             // Adjust glyph start and end to match run position
             glyph.start += start_run;
             glyph.end += start_run;
+            glyph.apply_attrs(attrs_list.get_span(glyph.start));
             glyphs.push(glyph);
         }
         return;
     }
 
     // Fill in cache if not already set
+    let substitutes = font_system.shape_buffer.monospace_substitutes.len();
     let mut cache_glyphs = Vec::new();
     shape_run(
         &mut cache_glyphs,
         font_system,
+        settings,
         line,
         attrs_list,
         start_run,
@@ -417,6 +1392,11 @@ fn shape_run_cached(
         span_rtl,
     );
     glyphs.extend_from_slice(&cache_glyphs);
+    // Fonts replaced by monospace fallbacks are only known when shaping, so those runs are not
+    // cached
+    if font_system.shape_buffer.monospace_substitutes.len() != substitutes {
+        return;
+    }
     for glyph in cache_glyphs.iter_mut() {
         // Adjust glyph start and end to remove run position
         glyph.start -= start_run;
@@ -428,6 +1408,7 @@ fn shape_run_cached(
 #[cfg(feature = "swash")]
 fn shape_skip(
     font_system: &mut FontSystem,
+    settings: ShapeSettings,
     glyphs: &mut Vec<ShapeGlyph>,
     line: &str,
     attrs_list: &AttrsList,
@@ -438,16 +1419,38 @@ fn shape_skip(
     let fonts = font_system.get_font_matches(attrs);
 
     let default_families = [&attrs.family];
-    let mut font_iter = FontFallbackIter::new(font_system, &fonts, &default_families, &[], "");
+    let mut font_iter = FontFallbackIter::new(
+        font_system,
+        settings.locale_opt,
+        &fonts,
+        &default_families,
+        &[],
+        "",
+    );
 
     let font = font_iter.next().expect("no default font found");
     let font_id = font.id();
     let font_monospace_em_width = font.monospace_em_width();
+    let font_weight = axis_weight(&font, attrs.weight);
+    let instances = &mut font_iter.shape_caches().instances;
+    let font_variations = instances.resolve(&font, &attrs);
+    let variations = instances.variations(font_variations);
     let font = font.as_swash();
 
+    // Apply the weight and variations to variable fonts
+    let coords = font
+        .variations()
+        .map(|variation| {
+            variations
+                .iter()
+                .find(|value| swash::tag_from_bytes(&value.tag) == variation.tag())
+                .map_or(0, |value| variation.normalize(value.value))
+        })
+        .collect::<Vec<_>>();
+
     let charmap = font.charmap();
-    let metrics = font.metrics(&[]);
-    let glyph_metrics = font.glyph_metrics(&[]).scale(1.0);
+    let metrics = font.metrics(&coords);
+    let glyph_metrics = font.glyph_metrics(&coords).scale(1.0);
 
     let ascent = metrics.ascent / f32::from(metrics.units_per_em);
     let descent = metrics.descent / f32::from(metrics.units_per_em);
@@ -470,16 +1473,45 @@ fn shape_skip(
                 font_monospace_em_width,
                 font_id,
                 glyph_id,
+                font_weight,
+                font_variations,
                 color_opt: attrs.color_opt,
                 metadata: attrs.metadata,
                 cache_key_flags: attrs.cache_key_flags,
                 metrics_opt: attrs.metrics_opt.map(|x| x.into()),
+                line_height_opt: attrs.line_height_opt,
+                decoration: attrs.decoration,
+                size_scale_opt: None,
+                whitespace_opt: None,
             }
         },
     ));
 }
 
+/// Iterate over the byte indices where `text` may be broken, including its end
+#[cfg(feature = "unicode-linebreak")]
+fn linebreaks(text: &str) -> impl Iterator<Item = usize> + '_ {
+    unicode_linebreak::linebreaks(text).map(|(i, _)| i)
+}
+
+/// Iterate over the byte indices where `text` may be broken, including its end. Lines are only
+/// broken after whitespace, used without the `unicode-linebreak` feature
+#[cfg(not(feature = "unicode-linebreak"))]
+fn linebreaks(text: &str) -> impl Iterator<Item = usize> + '_ {
+    let mut prev_whitespace = false;
+    text.char_indices()
+        .filter_map(move |(i, c)| {
+            let whitespace = c.is_whitespace();
+            let linebreak = prev_whitespace && !whitespace;
+            prev_whitespace = whitespace;
+            linebreak.then_some(i)
+        })
+        .chain(core::iter::once(text.len()))
+}
+
 /// A shaped glyph
+///
+/// See [`crate::MemoryUsage`] for the memory used by shaped buffers.
 #[derive(Clone, Debug)]
 pub struct ShapeGlyph {
     pub start: usize,
@@ -493,13 +1525,71 @@ pub struct ShapeGlyph {
     pub font_monospace_em_width: Option<f32>,
     pub font_id: fontdb::ID,
     pub glyph_id: u16,
+    pub font_weight: fontdb::Weight,
+    pub font_variations: FontVariationsId,
     pub color_opt: Option<Color>,
-    pub metadata: usize,
+    pub metadata: Metadata,
     pub cache_key_flags: CacheKeyFlags,
     pub metrics_opt: Option<Metrics>,
+    pub line_height_opt: Option<LineHeight>,
+    pub decoration: TextDecoration,
+    /// Scale of the font size of an uppercase letter shaped in place of a lowercase one, for
+    /// small capitals synthesized when the font lacks their features, see [`Caps`], and of
+    /// glyphs with a [`BaselineShift`]
+    pub size_scale_opt: Option<f32>,
+    /// Kind of whitespace the glyph is for, set when the line is shaped
+    pub whitespace_opt: Option<Whitespace>,
 }
 
+/// Attributes that few [`ShapeGlyph`]s have, see [`ShapeWord::extras`]
+#[derive(Clone, Debug, Default)]
+pub struct ShapeGlyphExtras {
+    /// Color override of decorations
+    pub decoration_color_opt: Option<Color>,
+    /// Background color
+    pub background_color_opt: Option<Color>,
+    /// Emphasis mark from `Attrs`
+    pub emphasis_opt: Option<EmphasisMark>,
+    /// Shift of the baseline, already applied to [`ShapeGlyph::y_offset`] and
+    /// [`ShapeGlyph::size_scale_opt`]
+    pub baseline_shift_opt: Option<BaselineShift>,
+    /// Glyph ID and advance in em of the tatweel (U+0640) of the font, if this letter is where
+    /// tatweels lengthen its word, at the joint after it in logical order, see
+    /// [`JustifyMode::Kashida`]
+    pub tatweel_opt: Option<(u16, f32)>,
+    /// Font the glyph was shaped with before being replaced by a monospace fallback font of
+    /// matching width, see [`crate::MonospaceMode::Strict`]
+    pub monospace_substituted_from: Option<fontdb::ID>,
+}
+
+impl ShapeGlyphExtras {
+    /// Get the extras that are kept by the laid out glyph, if any are set
+    fn layout(&self) -> Option<LayoutGlyphExtras> {
+        let extras = LayoutGlyphExtras {
+            decoration_color_opt: self.decoration_color_opt,
+            background_color_opt: self.background_color_opt,
+            emphasis_opt: self.emphasis_opt,
+        };
+        (extras != LayoutGlyphExtras::default()).then_some(extras)
+    }
+}
+
+// Keep the sizes documented on `MemoryUsage` up to date
+#[cfg(target_pointer_width = "64")]
+const _: () = assert!(mem::size_of::<ShapeGlyph>() == 112);
+
 impl ShapeGlyph {
+    /// Set the attributes that are not used when shaping
+    #[cfg(feature = "shape-run-cache")]
+    fn apply_attrs(&mut self, attrs: crate::Attrs) {
+        self.color_opt = attrs.color_opt;
+        self.metadata = attrs.metadata;
+        self.cache_key_flags = attrs.cache_key_flags;
+        self.metrics_opt = attrs.metrics_opt.map(|x| x.into());
+        self.line_height_opt = attrs.line_height_opt;
+        self.decoration = attrs.decoration;
+    }
+
     fn layout(
         &self,
         font_size: f32,
@@ -509,13 +1599,18 @@ impl ShapeGlyph {
         w: f32,
         level: unicode_bidi::Level,
     ) -> LayoutGlyph {
+        let flags = self
+            .whitespace_opt
+            .map_or(GlyphFlags::empty(), Whitespace::flag);
         LayoutGlyph {
-            start: self.start,
-            end: self.end,
+            start: self.start as u32,
+            end: self.end as u32,
             font_size,
             line_height_opt,
             font_id: self.font_id,
             glyph_id: self.glyph_id,
+            font_weight: self.font_weight,
+            font_variations: self.font_variations,
             x,
             y,
             w,
@@ -525,13 +1620,40 @@ impl ShapeGlyph {
             color_opt: self.color_opt,
             metadata: self.metadata,
             cache_key_flags: self.cache_key_flags,
+            decoration: self.decoration,
+            flags,
         }
     }
 
-    /// Get the width of the [`ShapeGlyph`] in pixels, either using the provided font size
-    /// or the [`ShapeGlyph::metrics_opt`] override.
+    /// Get the width of the [`ShapeGlyph`] in pixels, using [`ShapeGlyph::font_size`]
     pub fn width(&self, font_size: f32) -> f32 {
-        self.metrics_opt.map_or(font_size, |x| x.font_size) * self.x_advance
+        self.font_size(font_size) * self.x_advance
+    }
+
+    /// Get the font size of the [`ShapeGlyph`] in pixels, either the provided font size or the
+    /// [`ShapeGlyph::metrics_opt`] override, scaled by [`ShapeGlyph::size_scale_opt`]
+    pub fn font_size(&self, font_size: f32) -> f32 {
+        self.metrics_opt.map_or(font_size, |x| x.font_size) * self.size_scale_opt.unwrap_or(1.0)
+    }
+
+    /// Get the line height override of the [`ShapeGlyph`] in pixels, from
+    /// [`ShapeGlyph::line_height_opt`] or the [`ShapeGlyph::metrics_opt`] override
+    pub fn line_height(&self, font_size: f32) -> Option<f32> {
+        resolve_line_height(self.metrics_opt, self.line_height_opt, font_size)
+    }
+}
+
+/// Resolve a line height override against the font size of `metrics_opt` or `font_size`
+fn resolve_line_height(
+    metrics_opt: Option<Metrics>,
+    line_height_opt: Option<LineHeight>,
+    font_size: f32,
+) -> Option<f32> {
+    match line_height_opt {
+        Some(line_height) => {
+            Some(line_height.resolve(metrics_opt.map_or(font_size, |x| x.font_size)))
+        }
+        None => metrics_opt.map(|x| x.line_height),
     }
 }
 
@@ -540,6 +1662,9 @@ impl ShapeGlyph {
 pub struct ShapeWord {
     pub blank: bool,
     pub glyphs: Vec<ShapeGlyph>,
+    /// [`ShapeGlyphExtras`] of the glyphs that have any, by index in [`Self::glyphs`] in
+    /// increasing order, see [`Self::glyph_extras`]
+    pub extras: Vec<(usize, ShapeGlyphExtras)>,
 }
 
 impl ShapeWord {
@@ -550,6 +1675,73 @@ impl ShapeWord {
         Self {
             blank: true,
             glyphs: Vec::default(),
+            extras: Vec::new(),
+        }
+    }
+
+    /// Get the [`ShapeGlyphExtras`] of the glyph at `glyph_i` in [`Self::glyphs`], if it has any
+    pub fn glyph_extras(&self, glyph_i: usize) -> Option<&ShapeGlyphExtras> {
+        find_extras(&self.extras, glyph_i)
+    }
+
+    /// Iterate over the glyphs and their extras
+    fn extended_glyphs(&self) -> impl Iterator<Item = ExtendedGlyph<'_>> + '_ {
+        let mut extras = self.extras.iter().peekable();
+        self.glyphs.iter().enumerate().map(move |(glyph_i, glyph)| {
+            let extras = extras
+                .next_if(|(i, _)| *i == glyph_i)
+                .map(|(_, extras)| extras);
+            (glyph, extras)
+        })
+    }
+
+    /// Get the [`ShapeGlyphExtras`] of the glyph at `glyph_i`, adding them if it has none
+    fn glyph_extras_mut(&mut self, glyph_i: usize) -> &mut ShapeGlyphExtras {
+        let i = match self.extras.binary_search_by_key(&glyph_i, |&(i, _)| i) {
+            Ok(i) => i,
+            Err(i) => {
+                self.extras
+                    .insert(i, (glyph_i, ShapeGlyphExtras::default()));
+                i
+            }
+        };
+        &mut self.extras[i].1
+    }
+
+    /// Set the extras of the glyphs from their attributes and the fonts they were substituted
+    /// for, and apply baseline shifts to the glyphs
+    fn set_extras(&mut self, attrs_list: &AttrsList, substitutes: &[(usize, fontdb::ID)]) {
+        self.extras.clear();
+        for glyph_i in 0..self.glyphs.len() {
+            let glyph = &mut self.glyphs[glyph_i];
+            let attrs = attrs_list.get_span(glyph.start);
+            let monospace_substituted_from = substitutes
+                .iter()
+                .find(|(start, _)| *start == glyph.start)
+                .map(|&(_, from)| from);
+            if let Some(shift) = attrs.baseline_shift_opt {
+                // The shift is relative to the unscaled font size
+                glyph.y_offset += shift.shift() / shift.scale();
+                glyph.size_scale_opt = Some(glyph.size_scale_opt.unwrap_or(1.0) * shift.scale());
+            }
+            if attrs.decoration_color_opt.is_some()
+                || attrs.background_color_opt.is_some()
+                || attrs.emphasis_opt.is_some()
+                || attrs.baseline_shift_opt.is_some()
+                || monospace_substituted_from.is_some()
+            {
+                self.extras.push((
+                    glyph_i,
+                    ShapeGlyphExtras {
+                        decoration_color_opt: attrs.decoration_color_opt,
+                        background_color_opt: attrs.background_color_opt,
+                        emphasis_opt: attrs.emphasis_opt,
+                        baseline_shift_opt: attrs.baseline_shift_opt,
+                        tatweel_opt: None,
+                        monospace_substituted_from,
+                    },
+                ));
+            }
         }
     }
 
@@ -590,6 +1782,31 @@ impl ShapeWord {
         level: unicode_bidi::Level,
         blank: bool,
         shaping: Shaping,
+    ) {
+        self.build_with_settings(
+            font_system,
+            ShapeSettings::default(),
+            line,
+            attrs_list,
+            word_range,
+            level,
+            blank,
+            shaping,
+        );
+    }
+
+    /// See [`Self::build`], shaping with `settings`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn build_with_settings(
+        &mut self,
+        font_system: &mut FontSystem,
+        settings: ShapeSettings,
+        line: &str,
+        attrs_list: &AttrsList,
+        word_range: Range<usize>,
+        level: unicode_bidi::Level,
+        blank: bool,
+        shaping: Shaping,
     ) {
         let word = &line[word_range.clone()];
 
@@ -601,18 +1818,33 @@ impl ShapeWord {
 
         let mut glyphs = mem::take(&mut self.glyphs);
         glyphs.clear();
+        self.extras.clear();
 
         let span_rtl = level.is_rtl();
 
+        // Runs are split where attributes change, and between emoji and text so each can be
+        // shaped with a single font. Very long runs are also split into chunks, preferably after
+        // an ASCII character, so the cost of shaping and font fallback stays linear
         let mut start_run = word_range.start;
         let mut attrs = attrs_list.defaults();
-        for (egc_i, _egc) in word.grapheme_indices(true) {
+        let mut emoji = false;
+        let mut ascii = false;
+        for (egc_i, egc) in grapheme_indices(word) {
             let start_egc = word_range.start + egc_i;
             let attrs_egc = attrs_list.get_span(start_egc);
-            if !attrs.compatible(&attrs_egc) {
+            let emoji_egc = is_emoji_presentation(egc);
+            let chunk = settings.shape_chunk_len.map_or(false, |chunk_len| {
+                let run_len = start_egc - start_run;
+                (run_len >= chunk_len && ascii) || run_len >= chunk_len.saturating_mul(2)
+            });
+            if !attrs.compatible(&attrs_egc)
+                || (emoji != emoji_egc && start_egc > start_run)
+                || chunk
+            {
                 shaping.run(
                     &mut glyphs,
                     font_system,
+                    settings,
                     line,
                     attrs_list,
                     start_run,
@@ -623,11 +1855,14 @@ impl ShapeWord {
                 start_run = start_egc;
                 attrs = attrs_egc;
             }
+            emoji = emoji_egc;
+            ascii = egc.is_ascii();
         }
         if start_run < word_range.end {
             shaping.run(
                 &mut glyphs,
                 font_system,
+                settings,
                 line,
                 attrs_list,
                 start_run,
@@ -648,6 +1883,28 @@ impl ShapeWord {
         }
         width
     }
+
+    /// Get the range of text covered by the word, None if it has no glyphs, like hidden control
+    /// characters
+    pub fn range(&self) -> Option<Range<usize>> {
+        let start = self.glyphs.iter().map(|glyph| glyph.start).min()?;
+        let end = self.glyphs.iter().map(|glyph| glyph.end).max()?;
+        Some(start..end)
+    }
+
+    /// Get the text ranges of the clusters of the word, in the order of its glyphs. Glyphs that
+    /// belong to the same cluster share a range, which is returned once
+    pub fn clusters(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        let mut last = None;
+        self.glyphs.iter().filter_map(move |glyph| {
+            let range = glyph.start..glyph.end;
+            if last.as_ref() == Some(&range) {
+                return None;
+            }
+            last = Some(range.clone());
+            Some(range)
+        })
+    }
 }
 
 /// A shaped span (for bidirectional processing)
@@ -703,6 +1960,31 @@ impl ShapeSpan {
         line_rtl: bool,
         level: unicode_bidi::Level,
         shaping: Shaping,
+    ) {
+        self.build_with_settings(
+            font_system,
+            ShapeSettings::default(),
+            line,
+            attrs_list,
+            span_range,
+            line_rtl,
+            level,
+            shaping,
+        );
+    }
+
+    /// See [`Self::build`], shaping with `settings`.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn build_with_settings(
+        &mut self,
+        font_system: &mut FontSystem,
+        settings: ShapeSettings,
+        line: &str,
+        attrs_list: &AttrsList,
+        span_range: Range<usize>,
+        line_rtl: bool,
+        level: unicode_bidi::Level,
+        shaping: Shaping,
     ) {
         let span = &line[span_range.start..span_range.end];
 
@@ -725,7 +2007,7 @@ impl ShapeSpan {
         }
 
         let mut start_word = 0;
-        for (end_lb, _) in unicode_linebreak::linebreaks(span) {
+        for end_lb in linebreaks(span) {
             let mut start_lb = end_lb;
             for (i, c) in span[start_word..end_lb].char_indices().rev() {
                 // TODO: Not all whitespace characters are linebreakable, e.g. 00A0 (No-break
@@ -740,8 +2022,9 @@ impl ShapeSpan {
             }
             if start_word < start_lb {
                 let mut word = cached_words.pop().unwrap_or_else(ShapeWord::empty);
-                word.build(
+                word.build_with_settings(
                     font_system,
+                    settings,
                     line,
                     attrs_list,
                     (span_range.start + start_word)..(span_range.start + start_lb),
@@ -755,8 +2038,9 @@ impl ShapeSpan {
                 for (i, c) in span[start_lb..end_lb].char_indices() {
                     // assert!(c.is_whitespace());
                     let mut word = cached_words.pop().unwrap_or_else(ShapeWord::empty);
-                    word.build(
+                    word.build_with_settings(
                         font_system,
+                        settings,
                         line,
                         attrs_list,
                         (span_range.start + start_lb + i)
@@ -791,12 +2075,97 @@ impl ShapeSpan {
     }
 }
 
+/// A tab glyph of a [`ShapeLine`], cached so tabs can be expanded again without reshaping
+#[derive(Clone, Debug)]
+struct ShapeTab {
+    span_i: usize,
+    word_i: usize,
+    glyph_i: usize,
+    /// Index of the glyph in [`ShapeLine::visual_glyphs`]
+    visual_i: usize,
+    /// Advance of the glyphs between the previous tab, or the start edge of the line, and this
+    /// tab in visual order
+    gap: f32,
+    /// Advance of the tab as shaped, which is the advance of a space
+    space_advance: f32,
+    /// Advance of the glyphs between this tab and the first [`TabStops::decimal`] separator
+    /// before the next tab, if any
+    before_decimal: Option<f32>,
+}
+
 /// A shaped line (or paragraph)
 #[derive(Clone, Debug)]
 pub struct ShapeLine {
     pub rtl: bool,
     pub spans: Vec<ShapeSpan>,
     pub metrics_opt: Option<Metrics>,
+    pub line_height_opt: Option<LineHeight>,
+    /// Tabs in visual order from the start edge of the line
+    tabs: Vec<ShapeTab>,
+    /// Advance of the glyphs after the last tab
+    tab_trailing: f32,
+    tab_width: u16,
+    tab_stops: Option<TabStops>,
+    tab_font_size: f32,
+}
+
+/// A word of a [`ShapeLine`], see [`ShapeLine::words`]
+#[derive(Clone, Copy, Debug)]
+pub struct ShapeWordRef<'a> {
+    /// Index of the span in [`ShapeLine::spans`]
+    pub span_i: usize,
+    /// Index of the word in [`ShapeSpan::words`]
+    pub word_i: usize,
+    /// Bidi level of the span
+    pub level: unicode_bidi::Level,
+    /// The shaped word
+    pub word: &'a ShapeWord,
+}
+
+impl<'a> ShapeWordRef<'a> {
+    /// Returns true if the word is whitespace that lines can break after
+    pub fn blank(&self) -> bool {
+        self.word.blank
+    }
+
+    /// Get the width of the word in pixels, see [`ShapeWord::width`]
+    pub fn width(&self, font_size: f32) -> f32 {
+        self.word.width(font_size)
+    }
+
+    /// Get the range of text covered by the word, see [`ShapeWord::range`]
+    pub fn range(&self) -> Option<Range<usize>> {
+        self.word.range()
+    }
+}
+
+/// Line breaks computed outside of cosmic-text, for custom wrapping with
+/// [`ShapeLine::layout_with_breaks`]
+///
+/// Each break is the text index of the first word of a new visual line. Breaks that are not at
+/// the start of a word are ignored, and lines are never broken anywhere else.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BreakPlan {
+    breaks: Vec<usize>,
+}
+
+impl BreakPlan {
+    /// Create a plan breaking lines before the words starting at `breaks`
+    pub fn new(breaks: impl IntoIterator<Item = usize>) -> Self {
+        let mut breaks: Vec<usize> = breaks.into_iter().collect();
+        breaks.sort_unstable();
+        breaks.dedup();
+        Self { breaks }
+    }
+
+    /// Get the sorted text indices lines are broken at
+    pub fn breaks(&self) -> &[usize] {
+        &self.breaks
+    }
+
+    fn contains(&self, index: usize) -> bool {
+        self.breaks.binary_search(&index).is_ok()
+    }
 }
 
 // Visual Line Ranges: (span_index, (first_word_index, first_glyph_index), (last_word_index, last_glyph_index))
@@ -807,13 +2176,92 @@ struct VisualLine {
     ranges: Vec<VlRange>,
     spaces: u32,
     w: f32,
+    /// Span and word index of a blank word at the end of the line
+    trailing_blank: Option<(usize, usize)>,
+    /// Width of trailing whitespace laid out after the end of the line
+    hang: f32,
+    /// Width of trailing whitespace counted in `w`
+    trailing_w: f32,
+    /// Text index the line was cut at to make room for an ellipsis
+    ellipsis_at: Option<usize>,
+    /// Offset of the top of the line from the top of the first visual line
+    top: f32,
 }
 
 impl VisualLine {
     fn clear(&mut self) {
         self.ranges.clear();
         self.spaces = 0;
-        self.w = 0.;
+        self.w = 0.;
+        self.trailing_blank = None;
+        self.hang = 0.;
+        self.trailing_w = 0.;
+        self.ellipsis_at = None;
+        self.top = 0.;
+    }
+
+    /// Get the height of the line, the largest line height override of its glyphs or
+    /// `line_height` if none of them have one
+    fn height(
+        &self,
+        spans: &[ShapeSpan],
+        font_size: f32,
+        text_scale: f32,
+        line_height: f32,
+    ) -> f32 {
+        let mut height_opt: Option<f32> = None;
+        visit_glyphs(
+            spans,
+            &self.ranges,
+            core::iter::once(0..self.ranges.len()),
+            |(glyph, _)| {
+                if let Some(glyph_line_height) = glyph.line_height(font_size) {
+                    let glyph_line_height = glyph_line_height * text_scale;
+                    height_opt =
+                        Some(height_opt.map_or(glyph_line_height, |h| h.max(glyph_line_height)));
+                }
+            },
+        );
+        height_opt.unwrap_or(line_height)
+    }
+
+    /// Remove glyphs from the logical end of the line until it is no wider than `width`, then
+    /// remove any whitespace left at the end and mark the line as ending with an ellipsis
+    fn ellipsize(&mut self, spans: &[ShapeSpan], width: f32, font_size: f32, text_scale: f32) {
+        let mut cut = 0;
+        while let Some((span_index, start, end)) = self.ranges.last_mut() {
+            let words = &spans[*span_index].words;
+            let (word_i, glyph_end) = match *end {
+                (word_i, 0) if word_i > start.0 => (word_i - 1, words[word_i - 1].glyphs.len()),
+                (word_i, glyph_end) if word_i > start.0 || glyph_end > start.1 => {
+                    (word_i, glyph_end)
+                }
+                _ => {
+                    self.ranges.pop();
+                    continue;
+                }
+            };
+            let glyph_start = if word_i == start.0 { start.1 } else { 0 };
+            if glyph_end <= glyph_start {
+                *end = (word_i, 0);
+                continue;
+            }
+            let word = &words[word_i];
+            let glyph = &word.glyphs[glyph_end - 1];
+            if self.w <= width && !word.blank {
+                cut = glyph.end;
+                break;
+            }
+            self.w -= glyph.width(font_size) * text_scale;
+            cut = glyph.start;
+            *end = (word_i, glyph_end - 1);
+        }
+        if self.ranges.is_empty() {
+            self.w = 0.;
+        }
+        // Justification is not applied to lines ending with an ellipsis
+        self.spaces = 0;
+        self.ellipsis_at = Some(cut);
     }
 }
 
@@ -826,15 +2274,18 @@ impl ShapeLine {
             rtl: false,
             spans: Vec::default(),
             metrics_opt: None,
+            line_height_opt: None,
+            tabs: Vec::new(),
+            tab_trailing: 0.0,
+            tab_width: 0,
+            tab_stops: None,
+            tab_font_size: 0.0,
         }
     }
 
     /// Shape a line into a set of spans, using a scratch buffer. If [`unicode_bidi::BidiInfo`]
-    /// detects multiple paragraphs, they will be joined.
-    ///
-    /// # Panics
-    ///
-    /// Will panic if `line` contains multiple paragraphs that do not have matching direction
+    /// detects multiple paragraphs, they will be joined. Paragraphs with a direction different
+    /// from the first paragraph are isolated, as if surrounded by directional isolates.
     pub fn new(
         font_system: &mut FontSystem,
         line: &str,
@@ -850,10 +2301,6 @@ impl ShapeLine {
     /// See [`Self::new`].
     ///
     /// Reuses as much of the pre-existing internal allocations as possible.
-    ///
-    /// # Panics
-    ///
-    /// Will panic if `line` contains multiple paragraphs that do not have matching direction
     pub fn build(
         &mut self,
         font_system: &mut FontSystem,
@@ -861,8 +2308,29 @@ impl ShapeLine {
         attrs_list: &AttrsList,
         shaping: Shaping,
         tab_width: u16,
+    ) {
+        self.build_with_settings(
+            font_system,
+            line,
+            attrs_list,
+            shaping,
+            tab_width,
+            ShapeSettings::default(),
+        );
+    }
+
+    /// See [`Self::build`], shaping with the `settings` of a [`crate::BufferLine`].
+    pub(crate) fn build_with_settings(
+        &mut self,
+        font_system: &mut FontSystem,
+        line: &str,
+        attrs_list: &AttrsList,
+        shaping: Shaping,
+        tab_width: u16,
+        settings: ShapeSettings,
     ) {
         let mut spans = mem::take(&mut self.spans);
+        font_system.shape_buffer.monospace_substitutes.clear();
 
         // Cache the shape spans in reverse order so they can be popped for reuse in the same order.
         let mut cached_spans = mem::take(&mut font_system.shape_buffer.spans);
@@ -879,11 +2347,18 @@ impl ShapeLine {
         log::trace!("Line {}: '{}'", if rtl { "RTL" } else { "LTR" }, line);
 
         for para_info in bidi.paragraphs.iter() {
-            let line_rtl = para_info.level.is_rtl();
-            assert_eq!(line_rtl, rtl);
+            let line_rtl = rtl;
 
             let line_range = para_info.range.clone();
-            let levels = Self::adjust_levels(&unicode_bidi::Paragraph::new(&bidi, para_info));
+            let mut levels = Self::adjust_levels(&unicode_bidi::Paragraph::new(&bidi, para_info));
+            if rtl && !para_info.level.is_rtl() {
+                // Isolate an LTR paragraph by raising it above the RTL base level to the next even
+                // level. RTL paragraphs in an LTR line already have odd levels above the base level.
+                for level in levels[line_range.clone()].iter_mut() {
+                    // Levels that would exceed the maximum depth are left unchanged
+                    let _ = level.raise(2);
+                }
+            }
 
             // Find consecutive level runs. We use this to create Spans.
             // Each span is a set of characters with equal levels.
@@ -900,8 +2375,9 @@ impl ShapeLine {
                 if new_level != run_level {
                     // End of the previous run, start of a new one.
                     let mut span = cached_spans.pop().unwrap_or_else(ShapeSpan::empty);
-                    span.build(
+                    span.build_with_settings(
                         font_system,
+                        settings,
                         line,
                         attrs_list,
                         start..i,
@@ -915,8 +2391,9 @@ impl ShapeLine {
                 }
             }
             let mut span = cached_spans.pop().unwrap_or_else(ShapeSpan::empty);
-            span.build(
+            span.build_with_settings(
                 font_system,
+                settings,
                 line,
                 attrs_list,
                 start..line_range.end,
@@ -927,30 +2404,261 @@ impl ShapeLine {
             spans.push(span);
         }
 
-        // Adjust for tabs
-        let mut x = 0.0;
         for span in spans.iter_mut() {
+            let span_rtl = span.level.is_rtl();
             for word in span.words.iter_mut() {
+                settings.control_chars.replace(
+                    &mut word.glyphs,
+                    font_system,
+                    settings,
+                    line,
+                    attrs_list,
+                    shaping,
+                    span_rtl,
+                );
+                settings
+                    .direction
+                    .orient(&mut word.glyphs, font_system, line, attrs_list);
                 for glyph in word.glyphs.iter_mut() {
-                    if line.get(glyph.start..glyph.end) == Some("\t") {
-                        // Tabs are shaped as spaces, so they will always have the x_advance of a space.
-                        let tab_x_advance = (tab_width as f32) * glyph.x_advance;
-                        let tab_stop = (math::floorf(x / tab_x_advance) + 1.0) * tab_x_advance;
-                        glyph.x_advance = tab_stop - x;
-                    }
-                    x += glyph.x_advance;
+                    glyph.whitespace_opt = line
+                        .get(glyph.start..glyph.end)
+                        .and_then(Whitespace::from_cluster);
                 }
+                word.set_extras(attrs_list, &font_system.shape_buffer.monospace_substitutes);
             }
         }
 
         self.rtl = rtl;
         self.spans = spans;
+
+        // Find tabs and the advance of the text between them, in visual order as tab stops are
+        // measured from the start edge of the line
+        let mut tabs = mem::take(&mut self.tabs);
+        tabs.clear();
+        let mut gap = 0.0;
+        for (visual_i, (span_i, word_i, glyph_i)) in self.visual_glyphs().into_iter().enumerate() {
+            let glyph = &self.spans[span_i].words[word_i].glyphs[glyph_i];
+            if line.get(glyph.start..glyph.end) == Some("\t") {
+                tabs.push(ShapeTab {
+                    span_i,
+                    word_i,
+                    glyph_i,
+                    visual_i,
+                    gap,
+                    // Tabs are shaped as spaces, so they will always have the x_advance of a space.
+                    space_advance: glyph.x_advance,
+                    before_decimal: None,
+                });
+                gap = 0.0;
+            } else {
+                gap += glyph.x_advance;
+            }
+        }
+        self.tabs = tabs;
+        self.tab_trailing = gap;
+        self.tab_width = 0;
+        self.tab_stops = None;
+        self.set_tab_width(tab_width);
         self.metrics_opt = attrs_list.defaults().metrics_opt.map(|x| x.into());
+        self.line_height_opt = attrs_list.defaults().line_height_opt;
 
         // Return the buffer for later reuse.
         font_system.shape_buffer.spans = cached_spans;
     }
 
+    /// Mark the letter that tatweels lengthen the joint after in each right to left word of
+    /// `line`, for [`JustifyMode::Kashida`]
+    pub(crate) fn mark_kashidas(&mut self, font_system: &mut FontSystem, line: &str) {
+        for span in self.spans.iter_mut().filter(|span| span.level.is_rtl()) {
+            for word in span.words.iter_mut() {
+                // The last joint in logical order wins ties
+                let mut best: Option<(u8, usize)> = None;
+                for (i, glyph) in word.glyphs.iter().enumerate() {
+                    let Some(priority) = kashida_priority(line, glyph.start, glyph.end) else {
+                        continue;
+                    };
+                    let better = best.map_or(true, |(best_priority, best_i)| {
+                        priority < best_priority
+                            || (priority == best_priority
+                                && glyph.start > word.glyphs[best_i].start)
+                    });
+                    if better {
+                        best = Some((priority, i));
+                    }
+                }
+                let Some((_, i)) = best else {
+                    continue;
+                };
+                let Some(font) = font_system.get_font(word.glyphs[i].font_id) else {
+                    continue;
+                };
+                let face = font.rustybuzz();
+                if let Some(tatweel) = face.glyph_index('\u{640}') {
+                    let advance = face.glyph_hor_advance(tatweel).map_or(0.0, |advance| {
+                        f32::from(advance) / face.units_per_em() as f32
+                    });
+                    word.glyph_extras_mut(i).tatweel_opt = Some((tatweel.0, advance));
+                }
+            }
+        }
+    }
+
+    /// Get the tab width the tabs of this line are expanded to
+    pub fn tab_width(&self) -> u16 {
+        self.tab_width
+    }
+
+    /// Expand the tabs of this line to tab stops every `tab_width` spaces, without reshaping.
+    ///
+    /// Returns `true` if the advances of the line changed and it must be laid out again.
+    pub fn set_tab_width(&mut self, tab_width: u16) -> bool {
+        if tab_width == self.tab_width {
+            return false;
+        }
+        self.tab_width = tab_width;
+        self.expand_tabs();
+        !self.tabs.is_empty()
+    }
+
+    /// Get the tab stops the tabs of this line are expanded to, if they are used instead of the
+    /// tab width
+    pub fn tab_stops(&self) -> Option<&TabStops> {
+        self.tab_stops.as_ref()
+    }
+
+    /// Expand the tabs of this line to `tab_stops` at `font_size`, or to the tab width if None,
+    /// without reshaping. `line` is the text the line was shaped from, used to find decimal
+    /// separators.
+    ///
+    /// Returns `true` if the advances of the line changed and it must be laid out again.
+    pub fn set_tab_stops(
+        &mut self,
+        line: &str,
+        tab_stops: Option<&TabStops>,
+        font_size: f32,
+    ) -> bool {
+        if self.tab_stops.as_ref() == tab_stops
+            && (tab_stops.is_none() || self.tab_font_size == font_size)
+        {
+            return false;
+        }
+        self.tab_stops = tab_stops.cloned();
+        self.tab_font_size = font_size;
+        if let Some(tab_stops) = tab_stops {
+            let decimal = tab_stops.decimal();
+            let visual_glyphs = self.visual_glyphs();
+            for i in 0..self.tabs.len() {
+                self.tabs[i].before_decimal = self.before_decimal(line, &visual_glyphs, i, decimal);
+            }
+        }
+        self.expand_tabs();
+        !self.tabs.is_empty()
+    }
+
+    /// Draw whitespace glyphs as their [`Whitespace::marker`], keeping their advances. Glyphs of
+    /// fonts without the marker are left unchanged
+    pub fn show_whitespace(&mut self, font_system: &mut FontSystem) {
+        for span in self.spans.iter_mut() {
+            let rtl = span.level.is_rtl();
+            for glyph in span
+                .words
+                .iter_mut()
+                .flat_map(|word| word.glyphs.iter_mut())
+            {
+                let Some(whitespace) = glyph.whitespace_opt else {
+                    continue;
+                };
+                let Some(font) = font_system.get_font(glyph.font_id) else {
+                    continue;
+                };
+                if let Some(glyph_id) = font.rustybuzz().glyph_index(whitespace.marker(rtl)) {
+                    glyph.glyph_id = glyph_id.0;
+                }
+            }
+        }
+    }
+
+    /// Get the advance of the glyphs between tab `tab_i` and the first `decimal` before the next
+    /// tab, in the visual order of `visual_glyphs`
+    fn before_decimal(
+        &self,
+        line: &str,
+        visual_glyphs: &[(usize, usize, usize)],
+        tab_i: usize,
+        decimal: char,
+    ) -> Option<f32> {
+        let mut advance = 0.0;
+        for &(span_i, word_i, glyph_i) in &visual_glyphs[self.tabs[tab_i].visual_i + 1..] {
+            let glyph = &self.spans[span_i].words[word_i].glyphs[glyph_i];
+            let text = line.get(glyph.start..glyph.end)?;
+            if text == "\t" {
+                return None;
+            }
+            if text.contains(decimal) {
+                return Some(advance);
+            }
+            advance += glyph.x_advance;
+        }
+        None
+    }
+
+    /// Get the span, word and glyph indices of every glyph in visual order from the start edge
+    /// of the line, the right edge of RTL lines, as if the line was not wrapped
+    fn visual_glyphs(&self) -> Vec<(usize, usize, usize)> {
+        let mut visual_glyphs = Vec::new();
+        if self.spans.is_empty() {
+            return visual_glyphs;
+        }
+        let ranges: Vec<VlRange> = (0..self.spans.len())
+            .map(|span_i| (span_i, (0, 0), (self.spans[span_i].words.len(), 0)))
+            .collect();
+        let mut order = self.reorder(&ranges);
+        if self.rtl {
+            order.reverse();
+        }
+        // Words and glyphs are stored in visual order from the start edge within each span
+        for span_i in order.into_iter().flatten() {
+            for (word_i, word) in self.spans[span_i].words.iter().enumerate() {
+                for glyph_i in 0..word.glyphs.len() {
+                    visual_glyphs.push((span_i, word_i, glyph_i));
+                }
+            }
+        }
+        visual_glyphs
+    }
+
+    /// Set the advances of the tabs to reach the tab stops, or multiples of the tab width
+    fn expand_tabs(&mut self) {
+        let mut x = 0.0;
+        for (i, tab) in self.tabs.iter().enumerate() {
+            x += tab.gap;
+            let tab_x_advance = (self.tab_width as f32) * tab.space_advance;
+            let glyph = &mut self.spans[tab.span_i].words[tab.word_i].glyphs[tab.glyph_i];
+            let x_advance = match &self.tab_stops {
+                Some(tab_stops) => {
+                    // Tab stops are in pixels, advances are relative to the font size
+                    let scale = glyph.font_size(self.tab_font_size);
+                    let after = self
+                        .tabs
+                        .get(i + 1)
+                        .map_or(self.tab_trailing, |next| next.gap);
+                    tab_stops.advance(
+                        x * scale,
+                        after * scale,
+                        tab.before_decimal.map(|before| before * scale),
+                        tab_x_advance * scale,
+                    ) / scale
+                }
+                None => {
+                    let tab_stop = (math::floorf(x / tab_x_advance) + 1.0) * tab_x_advance;
+                    tab_stop - x
+                }
+            };
+            glyph.x_advance = x_advance;
+            x += x_advance;
+        }
+    }
+
     // A modified version of first part of unicode_bidi::bidi_info::visual_run
     fn adjust_levels(para: &unicode_bidi::Paragraph) -> Vec<unicode_bidi::Level> {
         use unicode_bidi::BidiClass::*;
@@ -1068,6 +2776,8 @@ impl ShapeLine {
         runs
     }
 
+    /// Lay out the line, allocating new scratch buffers and lines on every call. Use
+    /// [`Self::layout_to_buffer`] to reuse them in render loops
     pub fn layout(
         &self,
         font_size: f32,
@@ -1089,6 +2799,133 @@ impl ShapeLine {
         lines
     }
 
+    /// Get the words of the line in logical order, with the spans they belong to, for wrapping
+    /// text with custom rules and [`Self::layout_with_breaks`]
+    pub fn words(&self) -> impl Iterator<Item = ShapeWordRef<'_>> + '_ {
+        let rtl = self.rtl;
+        self.spans
+            .iter()
+            .enumerate()
+            .flat_map(move |(span_i, span)| {
+                // Words of spans with a direction opposite to the line are stored in reverse
+                let reversed = span.level.is_rtl() != rtl;
+                let len = span.words.len();
+                (0..len).map(move |i| {
+                    let word_i = if reversed { len - 1 - i } else { i };
+                    ShapeWordRef {
+                        span_i,
+                        word_i,
+                        level: span.level,
+                        word: &span.words[word_i],
+                    }
+                })
+            })
+    }
+
+    /// Lay out the line with the visual lines of `break_plan` instead of wrapping it. `width_opt`
+    /// is only used for alignment
+    pub fn layout_with_breaks(
+        &self,
+        font_size: f32,
+        width_opt: Option<f32>,
+        align: Option<Align>,
+        break_plan: &BreakPlan,
+    ) -> Vec<LayoutLine> {
+        let mut lines = Vec::with_capacity(1);
+        self.layout_to_buffer_excluding(
+            &mut ShapeBuffer::default(),
+            font_size,
+            1.0,
+            width_opt,
+            Wrap::None,
+            align,
+            &mut lines,
+            None,
+            TrailingWhitespace::default(),
+            JustifyMode::default(),
+            LineExclusions::default(),
+            None,
+            None,
+            Some(break_plan),
+        );
+        lines
+    }
+
+    /// Find the narrowest width that word wrapping at `width` can be reduced to without adding
+    /// visual lines, for [`Wrap::Balanced`]. Returns `None` if the line does not need balancing,
+    /// has too many visual lines to balance, or has words wider than `width`
+    #[allow(clippy::too_many_arguments)]
+    fn balanced_width(
+        &self,
+        scratch: &mut ShapeBuffer,
+        font_size: f32,
+        text_scale: f32,
+        width: f32,
+        match_mono_width: Option<f32>,
+        trailing_whitespace: TrailingWhitespace,
+        exclusions: LineExclusions,
+    ) -> Option<f32> {
+        // Balancing is meant for short text like headings, as each step lays out the line again
+        const MAX_LINES: usize = 6;
+        const MAX_STEPS: usize = 16;
+        // Precision of the balanced width in pixels
+        const TOLERANCE: f32 = 0.5;
+
+        let mut lines = Vec::new();
+        // Get the number of visual lines at `width`, if none of them overflow it
+        let mut count_lines = |scratch: &mut ShapeBuffer, width: f32| {
+            self.layout_to_buffer_excluding(
+                scratch,
+                font_size,
+                text_scale,
+                Some(width),
+                Wrap::Word,
+                None,
+                &mut lines,
+                match_mono_width,
+                trailing_whitespace,
+                JustifyMode::default(),
+                exclusions,
+                None,
+                None,
+                None,
+            );
+            let mut top = 0.;
+            let fits = lines.iter().enumerate().all(|(index, line)| {
+                let available = exclusions
+                    .available(index, top, Some(width), self.rtl, &mut scratch.blocked)
+                    .1;
+                top += line.height(exclusions.line_height);
+                line.w <= available + 0.001
+            });
+            fits.then_some(lines.len())
+        };
+
+        let count = count_lines(scratch, width)?;
+        if !(2..=MAX_LINES).contains(&count) {
+            return None;
+        }
+
+        // Wrapping never needs fewer lines at a narrower width, so search for the narrowest width
+        // that keeps the same number of lines
+        let mut min = 0.0;
+        let mut max = width;
+        for _ in 0..MAX_STEPS {
+            if max - min < TOLERANCE {
+                break;
+            }
+            let mid = (min + max) / 2.0;
+            if count_lines(scratch, mid) == Some(count) {
+                max = mid;
+            } else {
+                min = mid;
+            }
+        }
+        Some(max)
+    }
+
+    /// Lay out the line into `layout_lines`, reusing the glyph allocations of the lines already in
+    /// it and the buffers in `scratch`
     pub fn layout_to_buffer(
         &self,
         scratch: &mut ShapeBuffer,
@@ -1099,6 +2936,84 @@ impl ShapeLine {
         layout_lines: &mut Vec<LayoutLine>,
         match_mono_width: Option<f32>,
     ) {
+        self.layout_to_buffer_excluding(
+            scratch,
+            font_size,
+            1.0,
+            width_opt,
+            wrap,
+            align,
+            layout_lines,
+            match_mono_width,
+            TrailingWhitespace::default(),
+            JustifyMode::default(),
+            LineExclusions::default(),
+            None,
+            None,
+            None,
+        );
+    }
+
+    /// See [`Self::layout_to_buffer`].
+    ///
+    /// All font sizes, including overrides, are multiplied by `text_scale`. Whitespace at the end
+    /// of visual lines is laid out according to `trailing_whitespace`, [`Align::Justified`]
+    /// lines are expanded according to `justify_mode`, and the start and width of
+    /// every visual line are reduced to avoid the provided exclusions and indentation.
+    ///
+    /// Visual lines after `last_line` are dropped. If `ellipsis` is provided, visual lines wider
+    /// than the available width are cut short and end with its glyphs instead, and `last_line`
+    /// always ends with the ellipsis. If `break_plan` is provided, visual lines are broken where
+    /// it says instead of wrapping them.
+    pub(crate) fn layout_to_buffer_excluding(
+        &self,
+        scratch: &mut ShapeBuffer,
+        font_size: f32,
+        text_scale: f32,
+        width_opt: Option<f32>,
+        wrap: Wrap,
+        align: Option<Align>,
+        layout_lines: &mut Vec<LayoutLine>,
+        match_mono_width: Option<f32>,
+        trailing_whitespace: TrailingWhitespace,
+        justify_mode: JustifyMode,
+        exclusions: LineExclusions,
+        ellipsis: Option<&ShapeLine>,
+        last_line: Option<usize>,
+        break_plan: Option<&BreakPlan>,
+    ) {
+        // Balanced lines are wrapped at a narrower width, but aligned in the full width
+        let (wrap, wrap_width_opt) = match (wrap, width_opt) {
+            (Wrap::Balanced, Some(width)) if break_plan.is_none() => {
+                match self.balanced_width(
+                    scratch,
+                    font_size,
+                    text_scale,
+                    width,
+                    match_mono_width,
+                    trailing_whitespace,
+                    exclusions,
+                ) {
+                    Some(balanced_width) => (Wrap::Word, Some(balanced_width)),
+                    None => (Wrap::WordOrGlyph, width_opt),
+                }
+            }
+            (Wrap::Balanced, _) => (Wrap::WordOrGlyph, width_opt),
+            _ => (wrap, width_opt),
+        };
+
+        let match_mono_width = match_mono_width.map(|w| w * text_scale);
+
+        // Tab glyphs, sorted to find them while laying out glyphs
+        let mut tab_glyphs = mem::take(&mut scratch.tab_glyphs);
+        tab_glyphs.clear();
+        tab_glyphs.extend(
+            self.tabs
+                .iter()
+                .map(|tab| (tab.span_i, tab.word_i, tab.glyph_i)),
+        );
+        tab_glyphs.sort_unstable();
+
         // For each visual line a list of  (span index,  and range of words in that span)
         // Note that a BiDi visual line could have multiple spans or parts of them
         // let mut vl_range_of_spans = Vec::with_capacity(1);
@@ -1113,6 +3028,8 @@ impl ShapeLine {
         // Cache glyph sets in reverse order so they will ideally be reused in exactly the same lines.
         let mut cached_glyph_sets = mem::take(&mut scratch.glyph_sets);
         cached_glyph_sets.clear();
+
+        let mut blocked = mem::take(&mut scratch.blocked);
         cached_glyph_sets.extend(layout_lines.drain(..).rev().map(|mut v| {
             v.glyphs.clear();
             v.glyphs
@@ -1135,18 +3052,115 @@ impl ShapeLine {
             vl.spaces += number_of_blanks;
         }
 
+        // Move the finished visual line `vl` to `visual_lines` and start an empty one below it,
+        // returning the width available to the new line
+        #[allow(clippy::too_many_arguments)]
+        fn next_visual_line(
+            line: &ShapeLine,
+            exclusions: &LineExclusions,
+            font_size: f32,
+            text_scale: f32,
+            wrap_width_opt: Option<f32>,
+            visual_lines: &mut Vec<VisualLine>,
+            cached_visual_lines: &mut Vec<VisualLine>,
+            vl: &mut VisualLine,
+            blocked: &mut Vec<(f32, f32)>,
+        ) -> f32 {
+            // Positions of visual lines only matter to find the exclusions they overlap
+            let top = if exclusions.rects.is_empty() {
+                0.
+            } else {
+                vl.top + vl.height(&line.spans, font_size, text_scale, exclusions.line_height)
+            };
+            let next = cached_visual_lines.pop().unwrap_or_default();
+            visual_lines.push(mem::replace(vl, next));
+            vl.top = top;
+            exclusions
+                .available(visual_lines.len(), top, wrap_width_opt, line.rtl, blocked)
+                .1
+        }
+
         // This would keep the maximum number of spans that would fit on a visual line
         // If one span is too large, this variable will hold the range of words inside that span
         // that fits on a line.
         // let mut current_visual_line: Vec<VlRange> = Vec::with_capacity(1);
         let mut current_visual_line = cached_visual_lines.pop().unwrap_or_default();
 
-        if wrap == Wrap::None {
+        // Width available to the current visual line
+        let mut width_limit = exclusions
+            .available(0, 0., wrap_width_opt, self.rtl, &mut blocked)
+            .1;
+
+        if let Some(break_plan) = break_plan {
+            let mut first = true;
+            for (span_index, span) in self.spans.iter().enumerate() {
+                // Words of spans with a direction opposite to the line are stored in reverse
+                let congruent = self.rtl == span.level.is_rtl();
+                let len = span.words.len();
+                let mut fitting_start = if congruent { 0 } else { len };
+                let mut word_range_width = 0.;
+                let mut number_of_blanks: u32 = 0;
+                for step in 0..len {
+                    let i = if congruent { step } else { len - 1 - step };
+                    let word = &span.words[i];
+                    let breaks = word
+                        .range()
+                        .map_or(false, |range| break_plan.contains(range.start));
+                    if breaks && !first {
+                        let (start, end) = if congruent {
+                            (fitting_start, i)
+                        } else {
+                            (i + 1, fitting_start)
+                        };
+                        add_to_visual_line(
+                            &mut current_visual_line,
+                            span_index,
+                            (start, 0),
+                            (end, 0),
+                            word_range_width,
+                            number_of_blanks,
+                        );
+                        next_visual_line(
+                            self,
+                            &exclusions,
+                            font_size,
+                            text_scale,
+                            wrap_width_opt,
+                            &mut visual_lines,
+                            &mut cached_visual_lines,
+                            &mut current_visual_line,
+                            &mut blocked,
+                        );
+                        word_range_width = 0.;
+                        number_of_blanks = 0;
+                        fitting_start = if congruent { i } else { i + 1 };
+                    }
+                    first = false;
+                    word_range_width += word.width(font_size) * text_scale;
+                    if word.blank {
+                        number_of_blanks += 1;
+                    }
+                }
+                let (start, end) = if congruent {
+                    (fitting_start, len)
+                } else {
+                    (0, fitting_start)
+                };
+                add_to_visual_line(
+                    &mut current_visual_line,
+                    span_index,
+                    (start, 0),
+                    (end, 0),
+                    word_range_width,
+                    number_of_blanks,
+                );
+            }
+        } else if wrap == Wrap::None {
             for (span_index, span) in self.spans.iter().enumerate() {
                 let mut word_range_width = 0.;
                 let mut number_of_blanks: u32 = 0;
                 for word in span.words.iter() {
-                    let word_width = word.width(font_size);
+                    let word_width = word.width(font_size) * text_scale;
                     word_range_width += word_width;
                     if word.blank {
                         number_of_blanks += 1;
@@ -1172,17 +3186,17 @@ impl ShapeLine {
                     // incongruent directions
                     let mut fitting_start = (span.words.len(), 0);
                     for (i, word) in span.words.iter().enumerate().rev() {
-                        let word_width = word.width(font_size);
+                        let word_width = word.width(font_size) * text_scale;
 
                         // Addition in the same order used to compute the final width, so that
                         // relayouts with that width as the `line_width` will produce the same
                         // wrapping results.
                         if current_visual_line.w + (word_range_width + word_width)
-                            <= width_opt.unwrap_or(f32::INFINITY)
+                            <= width_limit
                             // Include one blank word over the width limit since it won't be
                             // counted in the final width
                             || (word.blank
-                                && (current_visual_line.w + word_range_width) <= width_opt.unwrap_or(f32::INFINITY))
+                                && (current_visual_line.w + word_range_width) <= width_limit)
                         {
                             // fits
                             if word.blank {
@@ -1193,12 +3207,12 @@ impl ShapeLine {
                             continue;
                         } else if wrap == Wrap::Glyph
                             // Make sure that the word is able to fit on it's own line, if not, fall back to Glyph wrapping.
-                            || (wrap == Wrap::WordOrGlyph && word_width > width_opt.unwrap_or(f32::INFINITY))
+                            || (wrap == Wrap::WordOrGlyph && word_width > width_limit)
                         {
                             // Commit the current line so that the word starts on the next line.
                             if word_range_width > 0.
                                 && wrap == Wrap::WordOrGlyph
-                                && word_width > width_opt.unwrap_or(f32::INFINITY)
+                                && word_width > width_limit
                             {
                                 add_to_visual_line(
                                     &mut current_visual_line,
@@ -1209,8 +3223,17 @@ impl ShapeLine {
                                     number_of_blanks,
                                 );
 
-                                visual_lines.push(current_visual_line);
-                                current_visual_line = cached_visual_lines.pop().unwrap_or_default();
+                                width_limit = next_visual_line(
+                                    self,
+                                    &exclusions,
+                                    font_size,
+                                    text_scale,
+                                    wrap_width_opt,
+                                    &mut visual_lines,
+                                    &mut cached_visual_lines,
+                                    &mut current_visual_line,
+                                    &mut blocked,
+                                );
 
                                 number_of_blanks = 0;
                                 word_range_width = 0.;
@@ -1219,9 +3242,9 @@ impl ShapeLine {
                             }
 
                             for (glyph_i, glyph) in word.glyphs.iter().enumerate().rev() {
-                                let glyph_width = glyph.width(font_size);
+                                let glyph_width = glyph.width(font_size) * text_scale;
                                 if current_visual_line.w + (word_range_width + glyph_width)
-                                    <= width_opt.unwrap_or(f32::INFINITY)
+                                    <= width_limit
                                 {
                                     word_range_width += glyph_width;
                                     continue;
@@ -1234,9 +3257,17 @@ impl ShapeLine {
                                         word_range_width,
                                         number_of_blanks,
                                     );
-                                    visual_lines.push(current_visual_line);
-                                    current_visual_line =
-                                        cached_visual_lines.pop().unwrap_or_default();
+                                    width_limit = next_visual_line(
+                                        self,
+                                        &exclusions,
+                                        font_size,
+                                        text_scale,
+                                        wrap_width_opt,
+                                        &mut visual_lines,
+                                        &mut cached_visual_lines,
+                                        &mut current_visual_line,
+                                        &mut blocked,
+                                    );
 
                                     number_of_blanks = 0;
                                     word_range_width = glyph_width;
@@ -1254,7 +3285,9 @@ impl ShapeLine {
                                     .words
                                     .get(i + 1)
                                     .map_or(false, |previous_word| previous_word.blank);
-                                if trailing_blank {
+                                if trailing_blank
+                                    && trailing_whitespace == TrailingWhitespace::Collapse
+                                {
                                     number_of_blanks = number_of_blanks.saturating_sub(1);
                                     add_to_visual_line(
                                         &mut current_visual_line,
@@ -1265,6 +3298,11 @@ impl ShapeLine {
                                         number_of_blanks,
                                     );
                                 } else {
+                                    // Trailing whitespace is kept at the end of the line, but not
+                                    // expanded by justification
+                                    if trailing_blank {
+                                        number_of_blanks = number_of_blanks.saturating_sub(1);
+                                    }
                                     add_to_visual_line(
                                         &mut current_visual_line,
                                         span_index,
@@ -1275,8 +3313,17 @@ impl ShapeLine {
                                     );
                                 }
 
-                                visual_lines.push(current_visual_line);
-                                current_visual_line = cached_visual_lines.pop().unwrap_or_default();
+                                width_limit = next_visual_line(
+                                    self,
+                                    &exclusions,
+                                    font_size,
+                                    text_scale,
+                                    wrap_width_opt,
+                                    &mut visual_lines,
+                                    &mut cached_visual_lines,
+                                    &mut current_visual_line,
+                                    &mut blocked,
+                                );
                                 number_of_blanks = 0;
                             }
 
@@ -1301,13 +3348,13 @@ impl ShapeLine {
                     // congruent direction
                     let mut fitting_start = (0, 0);
                     for (i, word) in span.words.iter().enumerate() {
-                        let word_width = word.width(font_size);
+                        let word_width = word.width(font_size) * text_scale;
                         if current_visual_line.w + (word_range_width + word_width)
-                            <= width_opt.unwrap_or(f32::INFINITY)
+                            <= width_limit
                             // Include one blank word over the width limit since it won't be
                             // counted in the final width.
                             || (word.blank
-                                && (current_visual_line.w + word_range_width) <= width_opt.unwrap_or(f32::INFINITY))
+                                && (current_visual_line.w + word_range_width) <= width_limit)
                         {
                             // fits
                             if word.blank {
@@ -1318,12 +3365,12 @@ impl ShapeLine {
                             continue;
                         } else if wrap == Wrap::Glyph
                             // Make sure that the word is able to fit on it's own line, if not, fall back to Glyph wrapping.
-                            || (wrap == Wrap::WordOrGlyph && word_width > width_opt.unwrap_or(f32::INFINITY))
+                            || (wrap == Wrap::WordOrGlyph && word_width > width_limit)
                         {
                             // Commit the current line so that the word starts on the next line.
                             if word_range_width > 0.
                                 && wrap == Wrap::WordOrGlyph
-                                && word_width > width_opt.unwrap_or(f32::INFINITY)
+                                && word_width > width_limit
                             {
                                 add_to_visual_line(
                                     &mut current_visual_line,
@@ -1334,8 +3381,17 @@ impl ShapeLine {
                                     number_of_blanks,
                                 );
 
-                                visual_lines.push(current_visual_line);
-                                current_visual_line = cached_visual_lines.pop().unwrap_or_default();
+                                width_limit = next_visual_line(
+                                    self,
+                                    &exclusions,
+                                    font_size,
+                                    text_scale,
+                                    wrap_width_opt,
+                                    &mut visual_lines,
+                                    &mut cached_visual_lines,
+                                    &mut current_visual_line,
+                                    &mut blocked,
+                                );
 
                                 number_of_blanks = 0;
                                 word_range_width = 0.;
@@ -1344,9 +3400,9 @@ impl ShapeLine {
                             }
 
                             for (glyph_i, glyph) in word.glyphs.iter().enumerate() {
-                                let glyph_width = glyph.width(font_size);
+                                let glyph_width = glyph.width(font_size) * text_scale;
                                 if current_visual_line.w + (word_range_width + glyph_width)
-                                    <= width_opt.unwrap_or(f32::INFINITY)
+                                    <= width_limit
                                 {
                                     word_range_width += glyph_width;
                                     continue;
@@ -1359,9 +3415,17 @@ impl ShapeLine {
                                         word_range_width,
                                         number_of_blanks,
                                     );
-                                    visual_lines.push(current_visual_line);
-                                    current_visual_line =
-                                        cached_visual_lines.pop().unwrap_or_default();
+                                    width_limit = next_visual_line(
+                                        self,
+                                        &exclusions,
+                                        font_size,
+                                        text_scale,
+                                        wrap_width_opt,
+                                        &mut visual_lines,
+                                        &mut cached_visual_lines,
+                                        &mut current_visual_line,
+                                        &mut blocked,
+                                    );
 
                                     number_of_blanks = 0;
                                     word_range_width = glyph_width;
@@ -1377,7 +3441,9 @@ impl ShapeLine {
                                 // previous word if it's a whitespace.
                                 let trailing_blank = i > 0 && span.words[i - 1].blank;
 
-                                if trailing_blank {
+                                if trailing_blank
+                                    && trailing_whitespace == TrailingWhitespace::Collapse
+                                {
                                     number_of_blanks = number_of_blanks.saturating_sub(1);
                                     add_to_visual_line(
                                         &mut current_visual_line,
@@ -1388,6 +3454,11 @@ impl ShapeLine {
                                         number_of_blanks,
                                     );
                                 } else {
+                                    // Trailing whitespace is kept at the end of the line, but not
+                                    // expanded by justification
+                                    if trailing_blank {
+                                        number_of_blanks = number_of_blanks.saturating_sub(1);
+                                    }
                                     add_to_visual_line(
                                         &mut current_visual_line,
                                         span_index,
@@ -1398,8 +3469,17 @@ impl ShapeLine {
                                     );
                                 }
 
-                                visual_lines.push(current_visual_line);
-                                current_visual_line = cached_visual_lines.pop().unwrap_or_default();
+                                width_limit = next_visual_line(
+                                    self,
+                                    &exclusions,
+                                    font_size,
+                                    text_scale,
+                                    wrap_width_opt,
+                                    &mut visual_lines,
+                                    &mut cached_visual_lines,
+                                    &mut current_visual_line,
+                                    &mut blocked,
+                                );
                                 number_of_blanks = 0;
                             }
 
@@ -1431,6 +3511,64 @@ impl ShapeLine {
             cached_visual_lines.push(current_visual_line);
         }
 
+        if let Some(last) = last_line {
+            if visual_lines.len() > last + 1 {
+                cached_visual_lines.extend(visual_lines.drain(last + 1..).map(|mut l| {
+                    l.clear();
+                    l
+                }));
+            }
+        }
+
+        if let Some(ellipsis) = ellipsis {
+            let ellipsis_w = ellipsis
+                .spans
+                .iter()
+                .flat_map(|span| span.words.iter())
+                .map(|word| word.width(font_size))
+                .sum::<f32>()
+                * text_scale;
+            for (index, visual_line) in visual_lines.iter_mut().enumerate() {
+                let width = exclusions
+                    .available(index, visual_line.top, width_opt, self.rtl, &mut blocked)
+                    .1;
+                if visual_line.w > width || last_line == Some(index) {
+                    visual_line.ellipsize(&self.spans, width - ellipsis_w, font_size, text_scale);
+                    visual_line.w += ellipsis_w;
+                }
+            }
+        }
+
+        // Collapsed whitespace is only left at the end of the paragraph, where it is laid out
+        for visual_line in visual_lines.iter_mut() {
+            let Some(&(span_index, start, end)) = visual_line.ranges.last() else {
+                continue;
+            };
+            let span = &self.spans[span_index];
+            // Words of spans with a direction opposite to the line are wrapped from the end
+            let word_i = if span.level.is_rtl() == self.rtl {
+                (end.1 == 0 && end.0 > start.0).then(|| end.0 - 1)
+            } else {
+                (start.1 == 0 && start.0 < end.0).then_some(start.0)
+            };
+            let Some(word_i) = word_i.filter(|&word_i| span.words[word_i].blank) else {
+                continue;
+            };
+            let blank_w = span.words[word_i].width(font_size) * text_scale;
+            match trailing_whitespace {
+                TrailingWhitespace::Collapse => visual_line.trailing_w = blank_w,
+                TrailingWhitespace::Hang => {
+                    visual_line.trailing_blank = Some((span_index, word_i));
+                    visual_line.hang = blank_w;
+                    visual_line.w -= visual_line.hang;
+                }
+                TrailingWhitespace::Include => {
+                    visual_line.trailing_blank = Some((span_index, word_i));
+                    visual_line.trailing_w = blank_w;
+                }
+            }
+        }
+
         // Create the LayoutLines using the ranges inside visual lines
         let align = align.unwrap_or({
             if self.rtl {
@@ -1444,43 +3582,44 @@ impl ShapeLine {
             Some(width) => width,
             None => {
                 let mut width: f32 = 0.0;
-                for visual_line in visual_lines.iter() {
-                    width = width.max(visual_line.w);
+                for (index, visual_line) in visual_lines.iter().enumerate() {
+                    width = width.max(visual_line.w + exclusions.indent.line(index));
                 }
                 width
             }
         };
 
-        let start_x = if self.rtl { line_width } else { 0.0 };
-
         let number_of_visual_lines = visual_lines.len();
         for (index, visual_line) in visual_lines.iter().enumerate() {
             if visual_line.ranges.is_empty() {
                 continue;
             }
+            // Exclusions and indentation may move the start of the line and reduce its width
+            let available =
+                exclusions.available(index, visual_line.top, width_opt, self.rtl, &mut blocked);
+            let (line_x, line_width) = match available {
+                (line_x, _) if width_opt.is_none() => {
+                    (line_x, line_width - exclusions.indent.line(index))
+                }
+                available => available,
+            };
+            let start_x = if self.rtl {
+                line_x + line_width
+            } else {
+                line_x
+            };
             let new_order = self.reorder(&visual_line.ranges);
             let mut glyphs = cached_glyph_sets
                 .pop()
                 .unwrap_or_else(|| Vec::with_capacity(1));
+            let mut glyph_extras = Vec::new();
+            let mut monospace_adjustments = Vec::new();
+            let mut tabs = Vec::new();
             let mut x = start_x;
             let mut y = 0.;
             let mut max_ascent: f32 = 0.;
             let mut max_descent: f32 = 0.;
-            let alignment_correction = match (align, self.rtl) {
-                (Align::Left, true) => line_width - visual_line.w,
-                (Align::Left, false) => 0.,
-                (Align::Right, true) => 0.,
-                (Align::Right, false) => line_width - visual_line.w,
-                (Align::Center, _) => (line_width - visual_line.w) / 2.0,
-                (Align::End, _) => line_width - visual_line.w,
-                (Align::Justified, _) => 0.,
-            };
-
-            if self.rtl {
-                x -= alignment_correction;
-            } else {
-                x += alignment_correction;
-            }
+            x += align.offset(self.rtl, line_width - visual_line.w);
 
             // TODO: Only certain `is_whitespace` chars are typically expanded but this is what is
             // currently used to compute `visual_line.spaces`.
@@ -1496,16 +3635,73 @@ impl ShapeLine {
             // (also some spaces aren't followed by potential linebreaks but they could
             //  still be expanded)
 
+            // Don't justify the last line in a paragraph.
+            let justify = matches!(align, Align::Justified) && index != number_of_visual_lines - 1;
+
+            // Widths of the gaps between clusters that are expanded, in layout order
+            let mut cluster_gaps = Vec::new();
+            let slack = line_width - visual_line.w;
+            if justify && justify_mode != JustifyMode::InterWord && slack > 0.0 {
+                // Compare each glyph to the one laid out before it
+                let mut last: Option<ExtendedGlyph> = None;
+                let mut order = new_order.iter().cloned();
+                let mut order_rev = new_order.iter().cloned().rev();
+                let order: &mut dyn Iterator<Item = _> =
+                    if self.rtl { &mut order_rev } else { &mut order };
+                visit_glyphs(&self.spans, &visual_line.ranges, order, |glyph| {
+                    let prev = last.replace(glyph);
+                    let Some(prev) = prev.filter(|prev| prev.0.start != glyph.0.start) else {
+                        return;
+                    };
+                    // Start with the width of one tatweel, zero for spaces
+                    match justify_gap(justify_mode, self.rtl, prev, glyph) {
+                        Some(JustifyGap::Space) => cluster_gaps.push(0.0),
+                        Some(JustifyGap::Tatweel(letter)) => {
+                            let advance = tatweel(letter).map_or(0.0, |(_, advance)| advance);
+                            cluster_gaps.push(advance * letter.0.font_size(font_size) * text_scale);
+                        }
+                        None => {}
+                    }
+                });
+            }
+
+            // Spaces between clusters share all of the slack evenly, while joints are lengthened
+            // by one tatweel at a time up to a limit, leaving the rest of the slack to blank
+            // spaces
+            let mut remaining = slack;
+            if cluster_gaps.contains(&0.0) {
+                let share = slack / cluster_gaps.len() as f32;
+                cluster_gaps.fill(share);
+                remaining = 0.0;
+            } else if !cluster_gaps.is_empty() {
+                let tatweels = cluster_gaps.clone();
+                cluster_gaps.fill(0.0);
+                for _ in 0..KASHIDA_MAX_TATWEELS {
+                    for (gap, tatweel) in cluster_gaps.iter_mut().zip(tatweels.iter()) {
+                        if *tatweel <= remaining {
+                            *gap += tatweel;
+                            remaining -= tatweel;
+                        }
+                    }
+                }
+                if visual_line.spaces == 0 && remaining > 0.0 {
+                    // Stretch the tatweels to fill the line
+                    let extra = remaining / cluster_gaps.len() as f32;
+                    for gap in cluster_gaps.iter_mut() {
+                        *gap += extra;
+                    }
+                    remaining = 0.0;
+                }
+            }
+
             // Amount of extra width added to each blank space within a line.
-            let justification_expansion = if matches!(align, Align::Justified)
-                && visual_line.spaces > 0
-                // Don't justify the last line in a paragraph.
-                && index != number_of_visual_lines - 1
-            {
-                (line_width - visual_line.w) / visual_line.spaces as f32
+            let justification_expansion = if justify && visual_line.spaces > 0 {
+                remaining / visual_line.spaces as f32
             } else {
                 0.
             };
+            let mut cluster_gaps = cluster_gaps.into_iter();
+            let mut last_glyph: Option<(ExtendedGlyph, f32)> = None;
 
             let mut process_range = |range: Range<usize>| {
                 for &(span_index, (starting_word, starting_glyph), (ending_word, ending_glyph)) in
@@ -1521,10 +3717,18 @@ impl ShapeLine {
                             (false, true) => &word.glyphs[..ending_glyph],
                             (true, true) => &word.glyphs[starting_glyph..ending_glyph],
                         };
+                        let first_glyph = if i == starting_word {
+                            starting_glyph
+                        } else {
+                            0
+                        };
 
-                        for glyph in included_glyphs {
+                        for (glyph_i, glyph) in included_glyphs.iter().enumerate() {
+                            let extras = word.glyph_extras(first_glyph + glyph_i);
+                            let line_height_opt =
+                                glyph.line_height(font_size).map(|x| x * text_scale);
                             // Use overridden font size
-                            let font_size = glyph.metrics_opt.map_or(font_size, |x| x.font_size);
+                            let font_size = glyph.font_size(font_size) * text_scale;
 
                             let match_mono_em_width = match_mono_width.map(|w| w / font_size);
 
@@ -1536,18 +3740,82 @@ impl ShapeLine {
                                     if glyph_em_width != match_em_width =>
                                 {
                                     let glyph_to_match_factor = glyph_em_width / match_em_width;
-                                    let glyph_font_size = math::roundf(glyph_to_match_factor)
-                                        .max(1.0)
-                                        / glyph_to_match_factor
-                                        * font_size;
+                                    let factor = math::roundf(glyph_to_match_factor).max(1.0)
+                                        / glyph_to_match_factor;
+                                    let glyph_font_size = factor * font_size;
                                     log::trace!("Adjusted glyph font size ({font_size} => {glyph_font_size})");
+                                    monospace_adjustments.push(MonospaceAdjustment::Scaled {
+                                        glyph_i: glyphs.len(),
+                                        font_em_width: glyph_em_width,
+                                        target_em_width: match_em_width,
+                                        factor,
+                                    });
                                     glyph_font_size
                                 }
                                 _ => font_size,
                             };
+                            let substituted_from =
+                                extras.and_then(|x| x.monospace_substituted_from);
+                            if let (Some(_), Some(from)) = (match_mono_em_width, substituted_from) {
+                                monospace_adjustments.push(MonospaceAdjustment::Substituted {
+                                    glyph_i: glyphs.len(),
+                                    from,
+                                });
+                            }
+
+                            let mut gap_advance = 0.0;
+                            if let Some((last, last_font_size)) = last_glyph.filter(|(last, _)| {
+                                !cluster_gaps.as_slice().is_empty() && last.0.start != glyph.start
+                            }) {
+                                let gap =
+                                    justify_gap(justify_mode, self.rtl, last, (glyph, extras));
+                                let cluster_expansion = match gap {
+                                    Some(_) => cluster_gaps.next().unwrap_or(0.0),
+                                    None => 0.0,
+                                };
+                                match gap.filter(|_| cluster_expansion > 0.0) {
+                                    Some(JustifyGap::Space) if self.rtl => {
+                                        // The glyph left of the gap covers it
+                                        gap_advance = cluster_expansion;
+                                    }
+                                    Some(JustifyGap::Space) => {
+                                        if let Some(previous) = glyphs.last_mut() {
+                                            previous.w += cluster_expansion;
+                                        }
+                                        x += cluster_expansion;
+                                    }
+                                    Some(JustifyGap::Tatweel(letter)) => {
+                                        if self.rtl {
+                                            x -= cluster_expansion;
+                                        }
+                                        let letter_font_size = if letter.0.start == last.0.start {
+                                            last_font_size
+                                        } else {
+                                            glyph_font_size
+                                        };
+                                        push_tatweels(
+                                            &mut glyphs,
+                                            &mut glyph_extras,
+                                            letter,
+                                            letter_font_size,
+                                            line_height_opt,
+                                            (x, y, cluster_expansion),
+                                            span.level,
+                                        );
+                                        if !self.rtl {
+                                            x += cluster_expansion;
+                                        }
+                                    }
+                                    None => {}
+                                }
+                            }
+                            last_glyph = Some(((glyph, extras), glyph_font_size));
 
                             let x_advance = glyph_font_size * glyph.x_advance
-                                + if word.blank {
+                                + gap_advance
+                                + if word.blank
+                                    && visual_line.trailing_blank != Some((span_index, i))
+                                {
                                     justification_expansion
                                 } else {
                                     0.0
@@ -1556,14 +3824,25 @@ impl ShapeLine {
                                 x -= x_advance;
                             }
                             let y_advance = glyph_font_size * glyph.y_advance;
-                            glyphs.push(glyph.layout(
-                                glyph_font_size,
-                                glyph.metrics_opt.map(|x| x.line_height),
-                                x,
-                                y,
-                                x_advance,
-                                span.level,
-                            ));
+                            if tab_glyphs
+                                .binary_search(&(span_index, i, first_glyph + glyph_i))
+                                .is_ok()
+                            {
+                                tabs.push(glyphs.len());
+                            }
+                            push_glyph(
+                                &mut glyphs,
+                                &mut glyph_extras,
+                                glyph.layout(
+                                    glyph_font_size,
+                                    line_height_opt,
+                                    x,
+                                    y,
+                                    x_advance,
+                                    span.level,
+                                ),
+                                extras,
+                            );
                             if !self.rtl {
                                 x += x_advance;
                             }
@@ -1586,6 +3865,39 @@ impl ShapeLine {
                 }
             }
 
+            if let (Some(cut), Some(ellipsis)) = (visual_line.ellipsis_at, ellipsis) {
+                let level = if self.rtl {
+                    unicode_bidi::Level::rtl()
+                } else {
+                    unicode_bidi::Level::ltr()
+                };
+                let ellipsis_glyphs = ellipsis
+                    .spans
+                    .iter()
+                    .flat_map(|span| span.words.iter())
+                    .flat_map(|word| word.extended_glyphs());
+                for (glyph, extras) in ellipsis_glyphs {
+                    let line_height_opt = glyph.line_height(font_size).map(|x| x * text_scale);
+                    let glyph_font_size = glyph.font_size(font_size) * text_scale;
+                    let x_advance = glyph_font_size * glyph.x_advance;
+                    if self.rtl {
+                        x -= x_advance;
+                    }
+                    // The ellipsis stands in for the text that was cut off
+                    let mut layout_glyph =
+                        glyph.layout(glyph_font_size, line_height_opt, x, y, x_advance, level);
+                    layout_glyph.start = cut as u32;
+                    layout_glyph.end = cut as u32;
+                    push_glyph(&mut glyphs, &mut glyph_extras, layout_glyph, extras);
+                    if !self.rtl {
+                        x += x_advance;
+                    }
+                    y += glyph_font_size * glyph.y_advance;
+                    max_ascent = max_ascent.max(glyph_font_size * glyph.ascent);
+                    max_descent = max_descent.max(glyph_font_size * glyph.descent);
+                }
+            }
+
             let mut line_height_opt: Option<f32> = None;
             for glyph in glyphs.iter() {
                 if let Some(glyph_line_height) = glyph.line_height_opt {
@@ -1600,14 +3912,24 @@ impl ShapeLine {
                 w: if align != Align::Justified {
                     visual_line.w
                 } else if self.rtl {
-                    start_x - x
+                    start_x - x - visual_line.hang
                 } else {
-                    x
+                    x - start_x - visual_line.hang
                 },
+                trailing_whitespace_w: visual_line.trailing_w,
                 max_ascent,
                 max_descent,
                 line_height_opt,
                 glyphs,
+                extras: glyph_extras,
+                spaces: visual_line.spaces,
+                justification_expansion,
+                monospace_adjustments,
+                tabs,
+                align,
+                slack: line_width - visual_line.w,
+                space_before: 0.0,
+                space_after: 0.0,
             });
         }
 
@@ -1615,10 +3937,25 @@ impl ShapeLine {
         if layout_lines.is_empty() {
             layout_lines.push(LayoutLine {
                 w: 0.0,
+                trailing_whitespace_w: 0.0,
                 max_ascent: 0.0,
                 max_descent: 0.0,
-                line_height_opt: self.metrics_opt.map(|x| x.line_height),
+                line_height_opt: resolve_line_height(
+                    self.metrics_opt,
+                    self.line_height_opt,
+                    font_size,
+                )
+                .map(|x| x * text_scale),
                 glyphs: Default::default(),
+                extras: Vec::new(),
+                spaces: 0,
+                justification_expansion: 0.0,
+                monospace_adjustments: Vec::new(),
+                tabs: Vec::new(),
+                align,
+                slack: 0.0,
+                space_before: 0.0,
+                space_after: 0.0,
             });
         }
 
@@ -1627,5 +3964,7 @@ impl ShapeLine {
         scratch.visual_lines.append(&mut cached_visual_lines);
         scratch.cached_visual_lines = cached_visual_lines;
         scratch.glyph_sets = cached_glyph_sets;
+        scratch.blocked = blocked;
+        scratch.tab_glyphs = tab_glyphs;
     }
 }
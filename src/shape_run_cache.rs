@@ -2,14 +2,27 @@
 use alloc::{string::String, vec::Vec};
 use core::ops::Range;
 
-use crate::{AttrsOwned, HashMap, ShapeGlyph};
+use crate::{Caps, FontFeature, FontMatchAttrs, FontVariation, HashMap, ShapeGlyph};
 
 /// Key for caching shape runs.
+///
+/// Only the attributes used when shaping are part of the key, attributes like colors are applied
+/// to the cached glyphs when they are reused.
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
 pub struct ShapeRunKey {
     pub text: String,
-    pub default_attrs: AttrsOwned,
-    pub attrs_spans: Vec<(Range<usize>, AttrsOwned)>,
+    pub locale: String,
+    pub default_attrs: FontMatchAttrs,
+    pub attrs_spans: Vec<(Range<usize>, FontMatchAttrs)>,
+    /// OpenType features and the ranges of the run they apply to
+    pub font_features: Vec<(Range<usize>, FontFeature)>,
+    /// Values of variable font axes, which are the same for the whole run
+    pub variations: Vec<FontVariation>,
+    /// Small capitals, which are the same for the whole run
+    pub caps_opt: Option<Caps>,
+    /// Bits of the em width monospace glyphs are substituted to match, see
+    /// [`crate::MonospaceMode::Strict`]
+    pub strict_monospace_em_width: Option<u32>,
 }
 
 /// A helper structure for caching shape runs.
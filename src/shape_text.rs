@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use crate::{Attrs, AttrsList, FontSystem, LayoutLine, ShapeGlyph, ShapeLine, Shaping, Wrap};
+
+/// A single line of text shaped without a [`crate::Buffer`], see [`shape_text`]
+#[derive(Clone, Debug)]
+pub struct ShapedText {
+    line: ShapeLine,
+}
+
+impl ShapedText {
+    /// Returns true if the paragraph direction of the text is RTL
+    pub fn rtl(&self) -> bool {
+        self.line.rtl
+    }
+
+    /// Iterate over the glyphs in logical order. Advances and offsets are in em, and
+    /// [`ShapeGlyph::start`] and [`ShapeGlyph::end`] are the byte range of the cluster of each
+    /// glyph in the text
+    pub fn glyphs(&self) -> impl Iterator<Item = &ShapeGlyph> + '_ {
+        self.line
+            .spans
+            .iter()
+            .flat_map(|span| span.words.iter())
+            .flat_map(|word| word.glyphs.iter())
+    }
+
+    /// Get the total advance of the text at `font_size`
+    pub fn width(&self, font_size: f32) -> f32 {
+        self.glyphs().map(|glyph| glyph.width(font_size)).sum()
+    }
+
+    /// Position the glyphs in visual order at `font_size`, without wrapping
+    pub fn layout(&self, font_system: &mut FontSystem, font_size: f32) -> LayoutLine {
+        let mut layout = Vec::with_capacity(1);
+        self.layout_to_buffer(font_system, font_size, &mut layout);
+        layout.swap_remove(0)
+    }
+
+    /// See [`Self::layout`]. Replaces the contents of `layout` with a single line, reusing its
+    /// allocations, so laying out every frame does not allocate
+    pub fn layout_to_buffer(
+        &self,
+        font_system: &mut FontSystem,
+        font_size: f32,
+        layout: &mut Vec<LayoutLine>,
+    ) {
+        self.line.layout_to_buffer(
+            &mut font_system.shape_buffer,
+            font_size,
+            None,
+            Wrap::None,
+            None,
+            layout,
+            None,
+        );
+    }
+
+    /// Get the underlying [`ShapeLine`]
+    pub fn shape_line(&self) -> &ShapeLine {
+        &self.line
+    }
+}
+
+/// Shape a single line of text with `attrs`, without creating a [`crate::Buffer`]
+///
+/// This reuses the scratch buffers and shape run cache of the [`FontSystem`]. Tabs are one
+/// space wide.
+pub fn shape_text(
+    font_system: &mut FontSystem,
+    text: &str,
+    attrs: Attrs,
+    shaping: Shaping,
+) -> ShapedText {
+    let line = ShapeLine::new(font_system, text, &AttrsList::new(attrs), shaping, 1);
+    ShapedText { line }
+}
@@ -7,7 +7,9 @@ use swash::scale::{image::Content, ScaleContext};
 use swash::scale::{Render, Source, StrikeWith};
 use swash::zeno::{Format, Vector};
 
-use crate::{CacheKey, CacheKeyFlags, Color, FontSystem, HashMap};
+use crate::{
+    Buffer, CacheKey, CacheKeyFlags, Color, Error, Font, FontSystem, FontVariation, HashMap,
+};
 
 pub use swash::scale::image::{Content as SwashContent, Image as SwashImage};
 pub use swash::zeno::{Angle, Command, Placement, Transform};
@@ -24,12 +26,38 @@ fn swash_image(
             return None;
         }
     };
+    let variations = font_system.font_variations(cache_key.font_variations);
+    swash_font_image(&font, context, cache_key, variations)
+}
 
+/// Get the settings of the variable font axes of `cache_key`, the values of `variations` override
+/// the weight
+fn swash_variations<'a>(
+    cache_key: CacheKey,
+    variations: &'a [FontVariation],
+) -> impl Iterator<Item = swash::Setting<f32>> + 'a {
+    core::iter::once(swash::Setting {
+        tag: swash::tag_from_bytes(b"wght"),
+        value: f32::from(cache_key.font_weight.0),
+    })
+    .chain(variations.iter().map(|variation| swash::Setting {
+        tag: swash::tag_from_bytes(&variation.tag),
+        value: variation.value,
+    }))
+}
+
+fn swash_font_image(
+    font: &Font,
+    context: &mut ScaleContext,
+    cache_key: CacheKey,
+    variations: &[FontVariation],
+) -> Option<SwashImage> {
     // Build the scaler
     let mut scaler = context
         .builder(font.as_swash())
         .size(f32::from_bits(cache_key.font_size_bits))
         .hint(true)
+        .variations(swash_variations(cache_key, variations))
         .build();
 
     // Compute the fractional offset-- you'll likely want to quantize this
@@ -49,18 +77,38 @@ fn swash_image(
     .format(Format::Alpha)
     // Apply the fractional offset
     .offset(offset)
-    .transform(if cache_key.flags.contains(CacheKeyFlags::FAKE_ITALIC) {
-        Some(Transform::skew(
-            Angle::from_degrees(14.0),
-            Angle::from_degrees(0.0),
-        ))
-    } else {
-        None
-    })
+    .transform(swash_transform(cache_key.flags))
+    .embolden(swash_embolden(cache_key))
     // Render the image
     .render(&mut scaler, cache_key.glyph_id)
 }
 
+/// Get the strength in pixels to embolden the outlines of `cache_key` with, for
+/// [`CacheKeyFlags::FAKE_BOLD`], widening stems by a 24th of the em like `FreeType`
+fn swash_embolden(cache_key: CacheKey) -> f32 {
+    if cache_key.flags.contains(CacheKeyFlags::FAKE_BOLD) {
+        f32::from_bits(cache_key.font_size_bits) / 24.0
+    } else {
+        0.0
+    }
+}
+
+fn swash_transform(flags: CacheKeyFlags) -> Option<Transform> {
+    let mut transform = None;
+    if flags.contains(CacheKeyFlags::FAKE_ITALIC) {
+        transform = Some(Transform::skew(
+            Angle::from_degrees(14.0),
+            Angle::from_degrees(0.0),
+        ));
+    }
+    if flags.contains(CacheKeyFlags::ROTATE_CW) {
+        // Outlines are y-up, so a clockwise turn is a negative angle
+        let rotation = Transform::rotation(Angle::from_degrees(-90.0));
+        transform = Some(transform.map_or(rotation, |transform| transform.then(&rotation)));
+    }
+    transform
+}
+
 fn swash_outline_commands(
     font_system: &mut FontSystem,
     context: &mut ScaleContext,
@@ -77,16 +125,22 @@ fn swash_outline_commands(
     };
 
     // Build the scaler
+    let variations = font_system.font_variations(cache_key.font_variations);
     let mut scaler = context
         .builder(font.as_swash())
         .size(f32::from_bits(cache_key.font_size_bits))
         .hint(true)
+        .variations(swash_variations(cache_key, variations))
         .build();
 
     // Scale the outline
-    let outline = scaler
+    let mut outline = scaler
         .scale_outline(cache_key.glyph_id)
         .or_else(|| scaler.scale_color_outline(cache_key.glyph_id))?;
+    let embolden = swash_embolden(cache_key);
+    if embolden != 0.0 {
+        outline.embolden(embolden, embolden);
+    }
 
     // Get the path information of the outline
     let path = outline.path();
@@ -95,6 +149,32 @@ fn swash_outline_commands(
     Some(path.commands().collect())
 }
 
+/// Get the cache keys of the glyphs of the visible runs of `buffer`, as drawn by
+/// [`Buffer::draw`]
+fn visible_cache_keys(buffer: &Buffer) -> impl Iterator<Item = CacheKey> + '_ {
+    buffer.layout_runs().flat_map(|run| {
+        run.glyphs
+            .iter()
+            .map(|glyph| glyph.physical((0., 0.), 1.0).cache_key)
+    })
+}
+
+/// Glyphs being rasterized on a background thread, see [`SwashCache::prewarm_in_background`]
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+#[derive(Debug)]
+pub struct PrewarmHandle {
+    thread: std::thread::JoinHandle<HashMap<CacheKey, Option<SwashImage>>>,
+}
+
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+impl PrewarmHandle {
+    /// Returns true once every glyph is rasterized, so [`SwashCache::finish_prewarm`] will not
+    /// block
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+}
+
 /// Cache for rasterizing with the swash scaler
 pub struct SwashCache {
     context: ScaleContext,
@@ -127,6 +207,27 @@ impl SwashCache {
         swash_image(font_system, &mut self.context, cache_key)
     }
 
+    /// Create a swash Image from a cache key, without caching results
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::FontParse`] if the font cannot be loaded, or [`Error::MissingGlyph`] if
+    /// the glyph cannot be rendered
+    pub fn try_get_image_uncached(
+        &mut self,
+        font_system: &mut FontSystem,
+        cache_key: CacheKey,
+    ) -> Result<SwashImage, Error> {
+        let font = font_system.try_get_font(cache_key.font_id)?;
+        let variations = font_system.font_variations(cache_key.font_variations);
+        swash_font_image(&font, &mut self.context, cache_key, variations).ok_or(
+            Error::MissingGlyph {
+                font_id: cache_key.font_id,
+                glyph_id: cache_key.glyph_id,
+            },
+        )
+    }
+
     /// Create a swash Image from a cache key, caching results
     pub fn get_image(
         &mut self,
@@ -138,6 +239,76 @@ impl SwashCache {
             .or_insert_with(|| swash_image(font_system, &mut self.context, cache_key))
     }
 
+    /// Rasterize the glyphs of the visible runs of `buffer` that are not cached yet, as drawn by
+    /// [`Buffer::draw`], so the first frame after opening a document does not rasterize them all.
+    /// Returns the number of glyphs rasterized
+    pub fn prewarm(&mut self, font_system: &mut FontSystem, buffer: &Buffer) -> usize {
+        let mut count = 0;
+        for cache_key in visible_cache_keys(buffer) {
+            if !self.image_cache.contains_key(&cache_key) {
+                self.get_image(font_system, cache_key);
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Rasterize the glyphs like [`Self::prewarm`], but on a background thread. Add them to the
+    /// cache with [`Self::finish_prewarm`] once [`PrewarmHandle::is_finished`] returns true.
+    ///
+    /// The thread only holds weak references to the fonts, so glyphs of fonts dropped from the
+    /// [`FontSystem`] in the meantime are skipped.
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    pub fn prewarm_in_background(
+        &self,
+        font_system: &mut FontSystem,
+        buffer: &Buffer,
+    ) -> PrewarmHandle {
+        let mut glyphs = Vec::new();
+        for cache_key in visible_cache_keys(buffer) {
+            if self.image_cache.contains_key(&cache_key) {
+                continue;
+            }
+            if let Some(font) = font_system.get_font(cache_key.font_id) {
+                let variations = font_system.font_variations_arc(cache_key.font_variations);
+                glyphs.push((cache_key, alloc::sync::Arc::downgrade(&font), variations));
+            }
+        }
+        let thread = std::thread::spawn(move || {
+            let mut context = ScaleContext::new();
+            let mut images = HashMap::default();
+            for (cache_key, font, variations) in glyphs {
+                if images.contains_key(&cache_key) {
+                    continue;
+                }
+                if let Some(font) = font.upgrade() {
+                    let image = swash_font_image(&font, &mut context, cache_key, &variations);
+                    images.insert(cache_key, image);
+                }
+            }
+            images
+        });
+        PrewarmHandle { thread }
+    }
+
+    /// Wait for the glyphs of `handle` to be rasterized and add them to the cache, returning the
+    /// number of glyphs added
+    #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+    pub fn finish_prewarm(&mut self, handle: PrewarmHandle) -> usize {
+        let Ok(images) = handle.thread.join() else {
+            log::warn!("glyph prewarming thread panicked");
+            return 0;
+        };
+        let mut count = 0;
+        for (cache_key, image) in images {
+            self.image_cache.entry(cache_key).or_insert_with(|| {
+                count += 1;
+                image
+            });
+        }
+        count
+    }
+
     /// Creates outline commands
     pub fn get_outline_commands(
         &mut self,
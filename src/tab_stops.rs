@@ -0,0 +1,127 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use crate::math;
+
+/// How the text after a tab is aligned to its [`TabStop`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TabAlign {
+    /// The text starts at the stop
+    #[default]
+    Left,
+    /// The text ends at the stop
+    Right,
+    /// The text is centered on the stop
+    Center,
+    /// The first [`TabStops::decimal`] separator of the text is at the stop, or the text ends at
+    /// the stop if it has none
+    Decimal,
+}
+
+/// A tab stop of [`TabStops`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TabStop {
+    /// Offset of the stop from the start of the line, in pixels
+    pub position: f32,
+    /// Alignment of the text after a tab advancing to this stop
+    pub align: TabAlign,
+}
+
+impl TabStop {
+    /// Create a new [`TabStop`]
+    pub const fn new(position: f32, align: TabAlign) -> Self {
+        Self { position, align }
+    }
+}
+
+/// Tab stops at explicit positions, used instead of the uniform tab width of a
+/// [`crate::Buffer`], see [`crate::Buffer::set_tab_stops`]
+///
+/// A tab advances to the first stop after the text before it. Tabs after the last stop advance
+/// to the next multiple of [`Self::interval`], or of the tab width if there is no interval. The
+/// text between a tab and the next tab, or the end of the line, is aligned to the stop.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TabStops {
+    stops: Vec<TabStop>,
+    interval_opt: Option<f32>,
+    decimal: char,
+}
+
+impl TabStops {
+    /// Create new [`TabStops`] from `stops` in any order, without an interval and with `.` as
+    /// the decimal separator
+    pub fn new(stops: impl IntoIterator<Item = TabStop>) -> Self {
+        let mut stops: Vec<TabStop> = stops.into_iter().collect();
+        stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+        Self {
+            stops,
+            interval_opt: None,
+            decimal: '.',
+        }
+    }
+
+    /// Set the interval in pixels between the default stops after the last stop
+    pub fn with_interval(mut self, interval: f32) -> Self {
+        self.interval_opt = Some(interval);
+        self
+    }
+
+    /// Set the decimal separator [`TabAlign::Decimal`] stops align to
+    pub fn with_decimal(mut self, decimal: char) -> Self {
+        self.decimal = decimal;
+        self
+    }
+
+    /// Get the stops, ordered by position
+    pub fn stops(&self) -> &[TabStop] {
+        &self.stops
+    }
+
+    /// Get the interval in pixels between the default stops after the last stop, None to use
+    /// the tab width
+    pub fn interval(&self) -> Option<f32> {
+        self.interval_opt
+    }
+
+    /// Get the decimal separator [`TabAlign::Decimal`] stops align to
+    pub fn decimal(&self) -> char {
+        self.decimal
+    }
+
+    /// Get the first stop after `x`, or a default [`TabAlign::Left`] stop at the next multiple of
+    /// the interval, or of `tab_interval` if there is no interval
+    pub fn next_stop(&self, x: f32, tab_interval: f32) -> TabStop {
+        if let Some(stop) = self.stops.iter().find(|stop| stop.position > x) {
+            return *stop;
+        }
+        let interval = self.interval_opt.unwrap_or(tab_interval);
+        let position = if interval > 0.0 {
+            (math::floorf(x / interval) + 1.0) * interval
+        } else {
+            x
+        };
+        TabStop::new(position, TabAlign::Left)
+    }
+
+    /// Get the advance of a tab at `x`, followed by text that is `after` wide and has its
+    /// decimal separator at `before_decimal`, if any
+    pub(crate) fn advance(
+        &self,
+        x: f32,
+        after: f32,
+        before_decimal: Option<f32>,
+        tab_interval: f32,
+    ) -> f32 {
+        let stop = self.next_stop(x, tab_interval);
+        let offset = match stop.align {
+            TabAlign::Left => 0.0,
+            TabAlign::Right => after,
+            TabAlign::Center => after / 2.0,
+            TabAlign::Decimal => before_decimal.unwrap_or(after),
+        };
+        // Text that does not fit before the stop starts right after the tab
+        (stop.position - offset - x).max(0.0)
+    }
+}
@@ -0,0 +1,174 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#[cfg(not(feature = "std"))]
+use alloc::{vec, vec::Vec};
+
+use crate::{Attrs, Buffer, FontSystem, Metrics, Shaping, Wrap};
+
+/// The content widths of a column of a [`TableMeasure`]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ColumnWidths {
+    /// Width of the widest word in the column, the narrowest the column can be without
+    /// overflowing
+    pub min: f32,
+    /// Width of the widest line in the column, the widest the column needs to be to avoid
+    /// wrapping
+    pub max: f32,
+}
+
+/// Measures and lays out the cells of a table, one [`Buffer`] per cell
+///
+/// Cells are shaped once when they are added. Measuring the columns and laying out the cells at
+/// the resolved widths reuses that shaping, so only line wrapping is done again.
+#[derive(Debug)]
+pub struct TableMeasure {
+    columns: usize,
+    cells: Vec<Buffer>,
+}
+
+impl TableMeasure {
+    /// Create a new table with `columns` cells per row
+    pub fn new(columns: usize) -> Self {
+        Self {
+            columns,
+            cells: Vec::new(),
+        }
+    }
+
+    /// Get the number of cells per row
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Get the number of rows, including a partially filled last row
+    pub fn rows(&self) -> usize {
+        match self.columns {
+            0 => 0,
+            columns => (self.cells.len() + columns - 1) / columns,
+        }
+    }
+
+    /// Add a cell after the last cell, starting a new row when the last row is full
+    pub fn push_cell(
+        &mut self,
+        font_system: &mut FontSystem,
+        metrics: Metrics,
+        text: &str,
+        attrs: Attrs,
+        shaping: Shaping,
+    ) {
+        let mut buffer = Buffer::new(font_system, metrics);
+        buffer.set_text(font_system, text, attrs, shaping);
+        self.cells.push(buffer);
+    }
+
+    /// Get the cell buffers in row-major order
+    pub fn cells(&self) -> &[Buffer] {
+        &self.cells
+    }
+
+    /// Get the cell buffers in row-major order, to change their attributes before measuring
+    pub fn cells_mut(&mut self) -> &mut [Buffer] {
+        &mut self.cells
+    }
+
+    /// Get the buffer of the cell at `row` and `column`
+    pub fn cell(&self, row: usize, column: usize) -> Option<&Buffer> {
+        if column >= self.columns {
+            return None;
+        }
+        self.cells.get(row * self.columns + column)
+    }
+
+    /// Compute the [`ColumnWidths`] of every column from the words of the cells, shaping cells
+    /// that are not shaped yet
+    pub fn measure(&mut self, font_system: &mut FontSystem) -> Vec<ColumnWidths> {
+        let mut widths = vec![ColumnWidths::default(); self.columns];
+        if self.columns == 0 {
+            return widths;
+        }
+        for (i, buffer) in self.cells.iter_mut().enumerate() {
+            let cell = measure_cell(font_system, buffer);
+            let column = &mut widths[i % self.columns];
+            column.min = column.min.max(cell.min);
+            column.max = column.max.max(cell.max);
+        }
+        widths
+    }
+
+    /// Resolve the width of every column so the table fits in `width_opt`
+    ///
+    /// Columns get their max width if it fits, or their min width if even that does not fit.
+    /// Otherwise, the space left after giving every column its min width is shared in
+    /// proportion to how much wider each column would like to be. Spacing between columns should
+    /// be subtracted from `width_opt` by the caller.
+    pub fn resolve(widths: &[ColumnWidths], width_opt: Option<f32>) -> Vec<f32> {
+        let total_min: f32 = widths.iter().map(|column| column.min).sum();
+        let total_max: f32 = widths.iter().map(|column| column.max).sum();
+        match width_opt {
+            Some(width) if width < total_max => {
+                if width <= total_min {
+                    widths.iter().map(|column| column.min).collect()
+                } else {
+                    let ratio = (width - total_min) / (total_max - total_min);
+                    widths
+                        .iter()
+                        .map(|column| column.min + (column.max - column.min) * ratio)
+                        .collect()
+                }
+            }
+            _ => widths.iter().map(|column| column.max).collect(),
+        }
+    }
+
+    /// Lay out every cell at the width of its column, returning the height of every row
+    pub fn layout(&mut self, font_system: &mut FontSystem, widths: &[f32]) -> Vec<f32> {
+        let mut heights = vec![0.0; self.rows()];
+        if self.columns == 0 {
+            return heights;
+        }
+        for (i, buffer) in self.cells.iter_mut().enumerate() {
+            let width_opt = widths.get(i % self.columns).copied();
+            buffer.set_size(font_system, width_opt, None);
+            buffer.shape_until_scroll(font_system, false);
+            let height = buffer
+                .layout_runs()
+                .map(|run| run.line_top + run.line_height)
+                .fold(0.0, f32::max);
+            let row = &mut heights[i / self.columns];
+            *row = row.max(height);
+        }
+        heights
+    }
+}
+
+/// Get the content widths of a single cell
+fn measure_cell(font_system: &mut FontSystem, buffer: &mut Buffer) -> ColumnWidths {
+    let font_size = buffer.metrics().font_size;
+    let text_scale = buffer.text_scale();
+    let mut cell = ColumnWidths::default();
+    for line_i in 0..buffer.lines.len() {
+        let Some(shape) = buffer.line_shape(font_system, line_i) else {
+            continue;
+        };
+        let mut line_width = 0.0;
+        // Trailing whitespace does not need to fit
+        let mut trailing = 0.0;
+        for word in shape.words() {
+            let width = word.width(font_size) * text_scale;
+            if word.blank() {
+                trailing += width;
+            } else {
+                line_width += trailing + width;
+                trailing = 0.0;
+                cell.min = cell.min.max(width);
+            }
+        }
+        cell.max = cell.max.max(line_width);
+    }
+    if buffer.wrap() == Wrap::None {
+        // Lines cannot wrap, so the cell is never narrower than its widest line
+        cell.min = cell.max;
+    }
+    cell
+}
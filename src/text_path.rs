@@ -0,0 +1,140 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use crate::{math, FontSystem, LayoutGlyph, ShapedText};
+
+/// A path to place text along, see [`ShapedText::layout_on_path`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TextPath<'a> {
+    /// Straight segments between points
+    Polyline(&'a [(f32, f32)]),
+    /// A cubic Bezier curve from the first point to the last, with two control points between
+    CubicBezier([(f32, f32); 4]),
+}
+
+impl TextPath<'_> {
+    /// Number of straight segments a Bezier curve is flattened into
+    const BEZIER_SEGMENTS: usize = 64;
+
+    /// Get the points of straight segments following the path
+    fn flatten(&self) -> Vec<(f32, f32)> {
+        match self {
+            Self::Polyline(points) => points.to_vec(),
+            Self::CubicBezier([p0, p1, p2, p3]) => (0..=Self::BEZIER_SEGMENTS)
+                .map(|i| {
+                    let t = i as f32 / Self::BEZIER_SEGMENTS as f32;
+                    let u = 1.0 - t;
+                    let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+                    (
+                        a * p0.0 + b * p1.0 + c * p2.0 + d * p3.0,
+                        a * p0.1 + b * p1.1 + c * p2.1 + d * p3.1,
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A glyph placed on a [`TextPath`]
+#[derive(Clone, Debug)]
+pub struct PathGlyph {
+    /// The glyph, with positions relative to the start of an unwrapped line. Draw it with its
+    /// origin at `x` and `y`, rotated by `angle` around that point
+    pub glyph: LayoutGlyph,
+    /// X position of the origin of the glyph on the path
+    pub x: f32,
+    /// Y position of the origin of the glyph on the path
+    pub y: f32,
+    /// Rotation of the glyph in radians, clockwise from the x axis in y-down coordinates
+    pub angle: f32,
+}
+
+/// Straight segments of a path, with the distance along the path at the start of each
+struct Segments {
+    points: Vec<(f32, f32)>,
+    distances: Vec<f32>,
+}
+
+impl Segments {
+    fn new(points: Vec<(f32, f32)>) -> Self {
+        let mut distances = Vec::with_capacity(points.len());
+        let mut distance = 0.0;
+        for (i, point) in points.iter().enumerate() {
+            if i > 0 {
+                let (dx, dy) = (point.0 - points[i - 1].0, point.1 - points[i - 1].1);
+                distance += math::sqrtf(dx * dx + dy * dy);
+            }
+            distances.push(distance);
+        }
+        Self { points, distances }
+    }
+
+    fn len(&self) -> f32 {
+        self.distances.last().copied().unwrap_or(0.0)
+    }
+
+    /// Get the point and the unit direction of the path at `distance` along it, or `None` if it
+    /// is off the path
+    fn at(&self, distance: f32) -> Option<((f32, f32), (f32, f32))> {
+        if distance < 0.0 || distance > self.len() {
+            return None;
+        }
+        // Skip zero length segments, which have no direction
+        let i = (1..self.points.len()).find(|&i| {
+            distance <= self.distances[i] && self.distances[i] > self.distances[i - 1]
+        })?;
+        let (start, end) = (self.points[i - 1], self.points[i]);
+        let t = (distance - self.distances[i - 1]) / (self.distances[i] - self.distances[i - 1]);
+        let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+        let len = self.distances[i] - self.distances[i - 1];
+        Some(((start.0 + dx * t, start.1 + dy * t), (dx / len, dy / len)))
+    }
+}
+
+impl ShapedText {
+    /// Place the glyphs along `path` at `font_size`, adding `letter_spacing` between glyphs
+    ///
+    /// Text starts `offset` from the start of the path, or ends `offset` from the end of the
+    /// path if the paragraph direction is RTL. Glyphs are rotated to follow the direction of the
+    /// path at their centers, and glyphs that do not fit on the path are left out.
+    pub fn layout_on_path(
+        &self,
+        font_system: &mut FontSystem,
+        font_size: f32,
+        path: TextPath,
+        offset: f32,
+        letter_spacing: f32,
+    ) -> Vec<PathGlyph> {
+        let layout = self.layout(font_system, font_size);
+        let segments = Segments::new(path.flatten());
+
+        let spacing = letter_spacing * layout.glyphs.len().saturating_sub(1) as f32;
+        let start = if self.rtl() {
+            segments.len() - offset - (layout.w + spacing)
+        } else {
+            offset
+        };
+
+        let mut glyphs = Vec::with_capacity(layout.glyphs.len());
+        for (i, glyph) in layout.glyphs.into_iter().enumerate() {
+            let half = glyph.w / 2.0;
+            let center = start + glyph.x + letter_spacing * i as f32 + half;
+            if segments.at(center - half).is_none() || segments.at(center + half).is_none() {
+                continue;
+            }
+            let Some(((x, y), (dx, dy))) = segments.at(center) else {
+                continue;
+            };
+            // Move back from the center to the origin along the direction at the center
+            glyphs.push(PathGlyph {
+                x: x - dx * half,
+                y: y - dy * half,
+                angle: math::atan2f(dy, dx),
+                glyph,
+            });
+        }
+        glyphs
+    }
+}
@@ -0,0 +1,39 @@
+use cosmic_text::{Attrs, AttrsList, BaselineShift, Buffer, FontSystem, Metrics, Shaping};
+
+#[test]
+fn baseline_shift_moves_and_scales_glyphs() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(15.0, 20.0));
+    let mut attrs_list = AttrsList::new(Attrs::new());
+    attrs_list.add_span(1..2, Attrs::new().baseline_shift(BaselineShift::Sub));
+    attrs_list.add_span(3..4, Attrs::new().baseline_shift(BaselineShift::Super));
+    attrs_list.add_span(
+        4..5,
+        Attrs::new().baseline_shift(BaselineShift::Custom {
+            shift: 0.5,
+            scale: 1.0,
+        }),
+    );
+    buffer.set_text(&mut font_system, "H2O22", Attrs::new(), Shaping::Advanced);
+    buffer.lines[0].set_attrs_list(attrs_list);
+    buffer.shape_until_scroll(&mut font_system, false);
+
+    let run = buffer.layout_runs().next().unwrap();
+    let glyphs = run.glyphs;
+    let (plain, sub, sup, custom) = (&glyphs[2], &glyphs[1], &glyphs[3], &glyphs[4]);
+    assert_eq!(plain.y_offset, 0.0);
+    assert!((sub.font_size - 10.0).abs() < 0.01);
+    assert!((sup.font_size - 10.0).abs() < 0.01);
+    assert_eq!(custom.font_size, 15.0);
+    assert!((custom.w - glyphs[1].w * 1.5).abs() < 0.01);
+
+    // Shifts are relative to the unscaled font size
+    assert!((sub.origin().1 - 3.0).abs() < 0.01, "{:?}", sub.origin());
+    assert!((sup.origin().1 + 5.0).abs() < 0.01, "{:?}", sup.origin());
+    assert!(
+        (custom.origin().1 + 7.5).abs() < 0.01,
+        "{:?}",
+        custom.origin()
+    );
+    assert!(sub.physical((0.0, 0.0), 1.0).y > plain.physical((0.0, 0.0), 1.0).y);
+}
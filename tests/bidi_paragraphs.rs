@@ -0,0 +1,78 @@
+use cosmic_text::{
+    Attrs, AttrsList, Buffer, BufferLine, FontSystem, LineEnding, Metrics, ShapeLine, Shaping, Wrap,
+};
+
+fn shape(font_system: &mut FontSystem, text: &str) -> ShapeLine {
+    let attrs_list = AttrsList::new(Attrs::new());
+    ShapeLine::new(font_system, text, &attrs_list, Shaping::Advanced, 8)
+}
+
+// Joined paragraphs with different directions used to panic when shaped as one line
+#[test]
+fn bidi_paragraphs_mixed_direction() {
+    let mut font_system = FontSystem::new();
+
+    for (text, rtl) in [
+        ("Hello world\u{2029}שלום עולם", false),
+        ("שלום עולם\u{2029}Hello world", true),
+        ("שלום\u{2029}Hello\u{2029}עולם\u{2029}world", true),
+        ("Hello\u{85}שלום עולם\u{2029}world", false),
+    ] {
+        let line = shape(&mut font_system, text);
+        assert_eq!(line.rtl, rtl, "wrong direction for {:?}", text);
+
+        for span in line.spans.iter() {
+            for word in span.words.iter() {
+                for glyph in word.glyphs.iter() {
+                    let glyph_text = &text[glyph.start..glyph.end];
+                    if glyph_text.chars().any(|c| c.is_ascii_alphabetic()) {
+                        assert!(span.level.is_ltr(), "{:?} is not LTR", glyph_text);
+                        // Embedded LTR paragraphs must stay above the RTL base level
+                        assert!(!rtl || span.level.number() >= 2);
+                    } else if glyph_text
+                        .chars()
+                        .any(|c| ('\u{5D0}'..='\u{5EA}').contains(&c))
+                    {
+                        assert!(span.level.is_rtl(), "{:?} is not RTL", glyph_text);
+                    }
+                }
+            }
+        }
+
+        let layout = line.layout(14.0, None, Wrap::Word, None, None);
+        assert_eq!(layout.len(), 1);
+    }
+}
+
+#[test]
+fn bidi_paragraphs_split_round_trip() {
+    let mut font_system = FontSystem::new();
+    let text = "Hello world\u{2029}שלום עולם\u{85}world\nend";
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_split_paragraphs(true);
+    buffer.set_text(&mut font_system, text, Attrs::new(), Shaping::Advanced);
+    assert_eq!(buffer.lines.len(), 4);
+
+    let mut saved = String::new();
+    for line in buffer.lines.iter() {
+        saved.push_str(line.text());
+        saved.push_str(line.ending().as_str());
+    }
+    assert_eq!(saved, text);
+
+    let mut line = BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(Attrs::new()),
+        Shaping::Advanced,
+    );
+    let rest = line.split_paragraphs();
+    assert_eq!(line.text(), "Hello world");
+    assert_eq!(line.ending(), LineEnding::Ps);
+    assert_eq!(rest.len(), 2);
+    assert_eq!(rest[0].text(), "שלום עולם");
+    assert_eq!(rest[0].ending(), LineEnding::Nel);
+    assert_eq!(rest[1].text(), "world\nend");
+    assert_eq!(rest[1].ending(), LineEnding::None);
+}
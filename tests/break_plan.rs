@@ -0,0 +1,45 @@
+use cosmic_text::{Attrs, AttrsList, BreakPlan, FontSystem, ShapeLine, Shaping};
+
+#[test]
+fn words_and_break_plan() {
+    let mut font_system = FontSystem::new();
+    let text = "one two three four";
+    let line = ShapeLine::new(
+        &mut font_system,
+        text,
+        &AttrsList::new(Attrs::new()),
+        Shaping::Advanced,
+        8,
+    );
+
+    let words: Vec<_> = line.words().collect();
+    let ranges: Vec<_> = words.iter().map(|word| word.range().unwrap()).collect();
+    assert_eq!(
+        ranges,
+        [0..3, 3..4, 4..7, 7..8, 8..13, 13..14, 14..18],
+        "{ranges:?}"
+    );
+    assert!(words[1].blank() && !words[0].blank());
+    assert!(words[0].width(14.0) > words[1].width(14.0));
+    assert_eq!(
+        words[0].word.clusters().collect::<Vec<_>>(),
+        [0..1, 1..2, 2..3]
+    );
+
+    // A custom breaker putting every non-blank word after the second on its own line, with a
+    // break inside a word that is ignored
+    let plan = BreakPlan::new([14, 8, 10, 8]);
+    assert_eq!(plan.breaks(), [8, 10, 14]);
+    let lines = line.layout_with_breaks(14.0, None, None, &plan);
+    let starts: Vec<_> = lines
+        .iter()
+        .map(|line| line.glyphs.iter().map(|glyph| glyph.start).min().unwrap())
+        .collect();
+    assert_eq!(starts, [0, 8, 14]);
+    let width: f32 = words[..4].iter().map(|word| word.width(14.0)).sum();
+    assert!((lines[0].w - width).abs() < 0.01, "{} {width}", lines[0].w);
+
+    // An empty plan keeps the line whole, however wide it is
+    let lines = line.layout_with_breaks(14.0, Some(10.0), None, &BreakPlan::default());
+    assert_eq!(lines.len(), 1);
+}
@@ -0,0 +1,33 @@
+use cosmic_text::{Attrs, AttrsList, Buffer, CacheKeyFlags, FontSystem, Metrics, Shaping};
+
+#[test]
+fn user_flags_reach_cache_keys() {
+    let flags = CacheKeyFlags::user(0xBEEF) | CacheKeyFlags::FAKE_ITALIC;
+    assert_eq!(flags.user_bits(), 0xBEEF);
+    assert!(flags.contains(CacheKeyFlags::FAKE_ITALIC));
+    assert_eq!(CacheKeyFlags::FAKE_ITALIC.user_bits(), 0);
+
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "page one",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+    let mut attrs_list = AttrsList::new(Attrs::new().cache_key_flags(CacheKeyFlags::user(1)));
+    attrs_list.add_span(5..8, Attrs::new().cache_key_flags(flags));
+    buffer.lines[0].set_attrs_list(attrs_list);
+    buffer.shape_until_scroll(&mut font_system, false);
+
+    let run = buffer.layout_runs().next().unwrap();
+    for glyph in run.glyphs.iter() {
+        let cache_key = glyph.physical((0.0, 0.0), 1.0).cache_key;
+        assert_eq!(cache_key.flags, glyph.cache_key_flags);
+        if glyph.start < 5 {
+            assert_eq!(cache_key.flags, CacheKeyFlags::user(1));
+        } else {
+            assert_eq!(cache_key.flags, flags);
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use common::new_wrapped_editor;
+use cosmic_text::{
+    Action, Attrs, Buffer, ChainedMotion, Cursor, Edit, FontSystem, Metrics, Motion, Shaping, Wrap,
+};
+
+mod common;
+
+#[test]
+fn chained_motion_keeps_pixel_column_across_editors() {
+    let mut font_system = FontSystem::new();
+    let mut first = new_wrapped_editor(&mut font_system, "iii\nmmm");
+    let mut second = new_wrapped_editor(&mut font_system, "iii");
+
+    first.set_cursor(Cursor::new(0, 3));
+    assert_eq!(first.chained_motion(&mut font_system, Motion::Down), None);
+    assert_eq!((first.cursor().line, first.cursor().index), (1, 1));
+
+    // The last line stays put and reports the column of the first motion
+    let cursor_x = first
+        .chained_motion(&mut font_system, Motion::Down)
+        .expect("not at boundary");
+    assert_eq!((first.cursor().line, first.cursor().index), (1, 1));
+
+    second.enter_chained(&mut font_system, Motion::Down, cursor_x);
+    assert_eq!((second.cursor().line, second.cursor().index), (0, 3));
+
+    // Going back up also keeps the column
+    let cursor_x = second
+        .chained_motion(&mut font_system, Motion::Up)
+        .expect("not at boundary");
+    first.enter_chained(&mut font_system, Motion::Up, cursor_x);
+    assert_eq!((first.cursor().line, first.cursor().index), (1, 1));
+    first.action(&mut font_system, Action::Motion(Motion::Up));
+    assert_eq!((first.cursor().line, first.cursor().index), (0, 3));
+}
+
+#[test]
+fn chained_motion_uses_visual_lines() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_wrap(Wrap::Word);
+    buffer.set_size(Some(40.0), None);
+    buffer.set_text("aaa bbb ccc", Attrs::new(), Shaping::Advanced);
+
+    // Entering from below lands on the last visual line of the wrapped line
+    let (cursor, _) = buffer.cursor_enter(Motion::Up, 0).unwrap();
+    assert_eq!((cursor.line, cursor.index), (0, 8));
+    assert_eq!(
+        buffer.cursor_motion_chained(cursor, None, Motion::Down),
+        Some(ChainedMotion::AtBoundary { cursor_x: 0 })
+    );
+    assert!(matches!(
+        buffer.cursor_motion_chained(cursor, None, Motion::Up),
+        Some(ChainedMotion::Moved(
+            Cursor {
+                line: 0,
+                index: 4,
+                ..
+            },
+            _
+        ))
+    ));
+
+    let (cursor, _) = buffer.cursor_enter(Motion::Down, 0).unwrap();
+    assert_eq!((cursor.line, cursor.index), (0, 0));
+    assert!(matches!(
+        buffer.cursor_motion_chained(cursor, None, Motion::Up),
+        Some(ChainedMotion::AtBoundary { .. })
+    ));
+    assert_eq!(buffer.cursor_enter(Motion::Left, 0), None);
+}
@@ -1,8 +1,11 @@
+// Each test uses only some of these helpers
+#![allow(dead_code)]
+
 use std::path::PathBuf;
 
 use cosmic_text::{
-    fontdb::Database, Attrs, AttrsOwned, Buffer, Color, Family, FontSystem, Metrics, Shaping,
-    SwashCache,
+    fontdb::Database, Attrs, AttrsOwned, Buffer, Color, Edit, Editor, Family, FontSystem, Metrics,
+    Shaping, SwashCache, Wrap,
 };
 use tiny_skia::{Paint, Pixmap, Rect, Transform};
 
@@ -142,3 +145,32 @@ impl DrawTestCfg {
         }
     }
 }
+
+/// Create an editor with `text` in a buffer without a size
+pub fn new_editor(font_system: &mut FontSystem, text: &str) -> Editor<'static> {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(font_system, text, Attrs::new(), Shaping::Advanced);
+    Editor::new(buffer)
+}
+
+/// Create an editor with `text` wrapped at 40 pixels, about one visual line per word
+pub fn new_wrapped_editor(font_system: &mut FontSystem, text: &str) -> Editor<'static> {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_wrap(font_system, Wrap::Word);
+    buffer.set_size(font_system, Some(40.0), None);
+    buffer.set_text(font_system, text, Attrs::new(), Shaping::Advanced);
+    buffer.shape_until_scroll(font_system, false);
+    Editor::new(buffer)
+}
+
+/// Get the text of an editor, with its lines joined by `\n`
+pub fn text(editor: &Editor) -> String {
+    editor.with_buffer(|buffer| {
+        buffer
+            .lines
+            .iter()
+            .map(|line| line.text())
+            .collect::<Vec<_>>()
+            .join("\n")
+    })
+}
@@ -0,0 +1,61 @@
+use cosmic_text::{Attrs, Buffer, Color, ControlChars, FontSystem, Metrics, Shaping};
+
+fn glyphs_for(buffer: &Buffer, start: usize) -> Vec<f32> {
+    buffer
+        .layout_runs()
+        .flat_map(|run| run.glyphs.iter())
+        .filter(|glyph| glyph.start as usize == start)
+        .map(|glyph| glyph.w)
+        .collect()
+}
+
+#[test]
+fn control_chars_policy() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_text("a\u{1b}b", Attrs::new(), Shaping::Advanced);
+
+    buffer.set_control_chars(ControlChars::Symbol);
+    let glyphs = glyphs_for(&buffer, 1);
+    assert_eq!(glyphs.len(), 1);
+    assert!(glyphs[0] > 0.0);
+
+    buffer.set_control_chars(ControlChars::Hex);
+    let glyphs = glyphs_for(&buffer, 1);
+    assert_eq!(glyphs.len(), "<1B>".len());
+    assert!(glyphs.iter().all(|&w| w > 0.0));
+
+    buffer.set_control_chars(ControlChars::Hide);
+    assert!(glyphs_for(&buffer, 1).is_empty());
+    assert_eq!(glyphs_for(&buffer, 2).len(), 1);
+}
+
+#[test]
+fn control_chars_lossy() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let replacement_attrs = Attrs::new().color(Color::rgb(0xFF, 0, 0));
+
+    buffer.set_text_lossy(
+        &mut font_system,
+        b"ok\xFFok\n\xE2\x80 \xF0",
+        Attrs::new(),
+        replacement_attrs,
+        Shaping::Advanced,
+    );
+
+    assert_eq!(buffer.lines.len(), 2);
+    assert_eq!(buffer.lines[0].text(), "ok\u{FFFD}ok");
+    assert_eq!(buffer.lines[1].text(), "\u{FFFD} \u{FFFD}");
+
+    let attrs_list = buffer.lines[0].attrs_list();
+    assert_eq!(attrs_list.get_span(0), Attrs::new());
+    assert_eq!(attrs_list.get_span(2), replacement_attrs);
+    assert_eq!(attrs_list.get_span(5), Attrs::new());
+
+    let attrs_list = buffer.lines[1].attrs_list();
+    assert_eq!(attrs_list.get_span(0), replacement_attrs);
+    assert_eq!(attrs_list.get_span(3), Attrs::new());
+    assert_eq!(attrs_list.get_span(4), replacement_attrs);
+}
@@ -0,0 +1,38 @@
+use cosmic_text::{Align, Attrs, Buffer, Cursor, Edit, Editor, FontSystem, Metrics, Shaping};
+
+#[test]
+fn cursor_area_scales_to_device_pixels() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, Some(400.0), Some(400.0));
+    buffer.set_text(
+        &mut font_system,
+        "one\ntwo",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+    buffer.lines[1].set_align(Some(Align::Center));
+    let mut editor = Editor::new(buffer);
+    editor.set_cursor(Cursor::new(1, 3));
+    editor.shape_as_needed(&mut font_system, false);
+
+    let area = editor.cursor_area(2.0).expect("cursor not visible");
+    assert_eq!(area.cursor.y, 20.0);
+    assert_eq!(area.cursor.height, 20.0);
+    assert_eq!(area.cursor.width, 0.0);
+
+    // The line rect covers the centered glyphs, and the cursor is at its end
+    assert!(area.line.x > 0.0);
+    assert!(area.line.width > 0.0);
+    assert_eq!(area.line.y, 20.0);
+    assert!((area.cursor.x - (area.line.x + area.line.width)).abs() <= 1.0);
+
+    let device = area.device_cursor();
+    assert_eq!(device.x, area.cursor.x * 2.0);
+    assert_eq!(device.y, 40.0);
+    assert_eq!(device.height, 40.0);
+    assert_eq!(area.device_line().width, area.line.width * 2.0);
+
+    let (x, y) = editor.cursor_position().expect("cursor not visible");
+    assert_eq!((x as f32, y as f32), (area.cursor.x, area.cursor.y));
+}
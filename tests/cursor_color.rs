@@ -0,0 +1,48 @@
+use cosmic_text::{
+    Attrs, Buffer, Color, Cursor, CursorColorPolicy, Edit, Editor, FontSystem, Metrics, Shaping,
+};
+
+#[test]
+fn cursor_color_policies() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let red = Color::rgb(0xFF, 0, 0);
+    buffer.set_rich_text(
+        &mut font_system,
+        [("plain ", Attrs::new()), ("red", Attrs::new().color(red))],
+        Attrs::new(),
+        Shaping::Advanced,
+        None,
+    );
+    let mut editor = Editor::new(buffer);
+    let cursor_color = Color::rgb(0, 0xFF, 0);
+    let text_color = Color::rgb(0x20, 0x20, 0x20);
+
+    editor.set_cursor(Cursor::new(0, 7));
+    assert_eq!(
+        editor.cursor_color_at(cursor_color, text_color),
+        cursor_color
+    );
+
+    editor.set_cursor_color_policy(CursorColorPolicy::FromAttrs);
+    assert_eq!(editor.cursor_color_at(cursor_color, text_color), red);
+    // At the end of the line, the text before the cursor is used
+    editor.set_cursor(Cursor::new(0, 9));
+    assert_eq!(editor.cursor_color_at(cursor_color, text_color), red);
+    editor.set_cursor(Cursor::new(0, 1));
+    assert_eq!(
+        editor.cursor_color_at(cursor_color, text_color),
+        cursor_color
+    );
+
+    editor.set_cursor_color_policy(CursorColorPolicy::Inverted);
+    assert_eq!(
+        editor.cursor_color_at(cursor_color, text_color),
+        Color::rgb(0xDF, 0xDF, 0xDF)
+    );
+    editor.set_cursor(Cursor::new(0, 7));
+    assert_eq!(
+        editor.cursor_color_at(cursor_color, text_color),
+        Color::rgb(0, 0xFF, 0xFF)
+    );
+}
@@ -0,0 +1,156 @@
+use cosmic_text::{
+    Attrs, AttrsList, Buffer, Color, Cursor, Decoration, DecorationKind, FontSystem, Metrics,
+    Shaping, SwashCache, TextDecoration, Weight,
+};
+
+#[test]
+fn decorations_merge_adjacent_glyphs() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let red = Color::rgb(0xFF, 0x00, 0x00);
+    let both = TextDecoration::UNDERLINE | TextDecoration::STRIKETHROUGH;
+    let mut attrs_list = AttrsList::new(Attrs::new());
+    attrs_list.add_span(0..9, Attrs::new().decoration(both));
+    attrs_list.add_span(
+        4..9,
+        Attrs::new()
+            .decoration(TextDecoration::UNDERLINE)
+            .decoration_color(red),
+    );
+    buffer.set_text(
+        &mut font_system,
+        "one two three",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+    buffer.lines[0].set_attrs_list(attrs_list);
+    buffer.shape_until_scroll(&mut font_system, false);
+
+    let run = buffer.layout_runs().next().unwrap();
+    let decorations = run.decorations(&mut font_system);
+    let underlines: Vec<_> = decorations
+        .iter()
+        .filter(|d| d.decoration == TextDecoration::UNDERLINE)
+        .collect();
+    let strikethroughs: Vec<_> = decorations
+        .iter()
+        .filter(|d| d.decoration == TextDecoration::STRIKETHROUGH)
+        .collect();
+
+    // The colored span splits the underline in two and replaces the strikethrough
+    assert_eq!(underlines.len(), 2, "{decorations:?}");
+    assert_eq!(underlines[0].color_opt, None);
+    assert_eq!(underlines[1].color_opt, Some(red));
+    assert_eq!(strikethroughs.len(), 1, "{decorations:?}");
+    assert!((underlines[0].x + underlines[0].w - underlines[1].x).abs() < 0.01);
+
+    for decoration in decorations.iter() {
+        assert!(decoration.w > 0.0 && decoration.h > 0.0, "{decoration:?}");
+    }
+    assert!(underlines[0].y > run.line_y);
+    assert!(strikethroughs[0].y < run.line_y);
+    assert!(strikethroughs[0].y > run.line_top);
+
+    // Undecorated text has no lines
+    let last = run.glyphs.last().unwrap();
+    assert!(decorations.iter().all(|d| d.x + d.w <= last.x));
+}
+
+#[test]
+fn backgrounds_cover_line_height() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let yellow = Color::rgb(0xFF, 0xFF, 0x00);
+    let mut attrs_list = AttrsList::new(Attrs::new());
+    attrs_list.add_span(0..3, Attrs::new().background_color(yellow));
+    attrs_list.add_span(
+        3..7,
+        Attrs::new().background_color(yellow).weight(Weight::BOLD),
+    );
+    buffer.set_text(
+        &mut font_system,
+        "one two three",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+    buffer.lines[0].set_attrs_list(attrs_list);
+    buffer.shape_until_scroll(&mut font_system, false);
+
+    let run = buffer.layout_runs().next().unwrap();
+    let backgrounds = run.backgrounds();
+    // Spans with the same background color share one rectangle
+    assert_eq!(backgrounds.len(), 1, "{backgrounds:?}");
+    let background = backgrounds[0];
+    assert_eq!(background.color, yellow);
+    assert_eq!(background.y, run.line_top);
+    assert_eq!(background.h, run.line_height);
+    let end = run.glyphs.iter().find(|glyph| glyph.start == 7).unwrap().x;
+    assert!((background.x + background.w - end).abs() < 0.01);
+}
+
+#[test]
+fn range_decorations_span_lines() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let red = Color::rgb(0xFF, 0x00, 0x00);
+    let blue = Color::rgb(0x00, 0x00, 0xFF);
+    buffer.set_text(
+        &mut font_system,
+        "one two\nthree",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+    buffer.shape_until_scroll(&mut font_system, false);
+    buffer.set_redraw(false);
+
+    buffer.set_decorations(Vec::from([
+        Decoration::new(
+            Cursor::new(0, 4)..Cursor::new(1, 3),
+            DecorationKind::Squiggle,
+            red,
+        ),
+        Decoration::new(
+            Cursor::new(1, 0)..Cursor::new(1, 5),
+            DecorationKind::Highlight,
+            blue,
+        ),
+    ]));
+    assert!(buffer.redraw());
+
+    let runs: Vec<_> = buffer.layout_runs().collect();
+    let first = runs[0].range_decorations(&mut font_system, buffer.decorations());
+    assert_eq!(first.len(), 1, "{first:?}");
+    assert_eq!(first[0].index, 0);
+    let two = runs[0]
+        .glyphs
+        .iter()
+        .find(|glyph| glyph.start == 4)
+        .unwrap();
+    assert!((first[0].x - two.x).abs() < 0.01);
+    assert!(first[0].y > runs[0].line_y);
+
+    let second = runs[1].range_decorations(&mut font_system, buffer.decorations());
+    assert_eq!(second.len(), 2, "{second:?}");
+    assert_eq!(second[1].kind, DecorationKind::Highlight);
+    assert_eq!(second[1].y, runs[1].line_top);
+    assert_eq!(second[1].h, runs[1].line_height);
+    // The squiggle ends before the highlight
+    assert!(second[0].w < second[1].w);
+
+    let mut swash_cache = SwashCache::new();
+    let mut colors = Vec::new();
+    buffer.draw(
+        &mut font_system,
+        &mut swash_cache,
+        Color::rgb(0, 0, 0),
+        |_, _, _, _, color| {
+            if colors.last() != Some(&color) {
+                colors.push(color);
+            }
+        },
+    );
+    // Highlights are drawn behind the text, squiggles over it
+    let blue_i = colors.iter().position(|&color| color == blue).unwrap();
+    let red_i = colors.iter().rposition(|&color| color == red).unwrap();
+    assert!(blue_i < red_i);
+}
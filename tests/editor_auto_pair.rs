@@ -0,0 +1,77 @@
+use common::{new_editor, text};
+use cosmic_text::{Action, Cursor, Edit, Editor, FontSystem, Selection};
+
+mod common;
+
+fn auto_pair_editor(font_system: &mut FontSystem, text: &str) -> Editor<'static> {
+    let mut editor = new_editor(font_system, text);
+    editor.set_auto_pair(true);
+    editor
+}
+
+#[test]
+fn auto_pair_insert() {
+    let mut font_system = FontSystem::new();
+    let mut editor = auto_pair_editor(&mut font_system, "");
+
+    for c in "f(\"a".chars() {
+        editor.action(&mut font_system, Action::Insert(c));
+    }
+    assert_eq!(text(&editor), "f(\"a\")");
+    assert_eq!(editor.cursor().index, 4);
+
+    // Typing closers skips over the inserted ones
+    editor.action(&mut font_system, Action::Insert('"'));
+    editor.action(&mut font_system, Action::Insert(')'));
+    assert_eq!(text(&editor), "f(\"a\")");
+    assert_eq!(editor.cursor().index, 6);
+
+    // Backspace in an empty pair deletes both characters
+    editor.action(&mut font_system, Action::Insert(' '));
+    editor.action(&mut font_system, Action::Insert('['));
+    assert_eq!(text(&editor), "f(\"a\") []");
+    editor.action(&mut font_system, Action::Backspace);
+    assert_eq!(text(&editor), "f(\"a\") ");
+
+    // Quotes directly after a word are not paired
+    let mut editor = auto_pair_editor(&mut font_system, "don");
+    editor.set_cursor(Cursor::new(0, 3));
+    editor.action(&mut font_system, Action::Insert('\''));
+    assert_eq!(text(&editor), "don'");
+
+    // Brackets are not paired before a word
+    let mut editor = auto_pair_editor(&mut font_system, "word");
+    editor.action(&mut font_system, Action::Insert('('));
+    assert_eq!(text(&editor), "(word");
+}
+
+#[test]
+fn surround_selection() {
+    let mut font_system = FontSystem::new();
+    let mut editor = auto_pair_editor(&mut font_system, "one two");
+    editor.set_selection(Selection::Normal(Cursor::new(0, 4)));
+    editor.set_cursor(Cursor::new(0, 7));
+
+    editor.start_change();
+    editor.action(&mut font_system, Action::SurroundSelection('<', '>'));
+    let change = editor.finish_change().expect("no change");
+    assert_eq!(text(&editor), "one <two>");
+    assert_eq!(editor.copy_selection().as_deref(), Some("two"));
+
+    let mut undo = change.clone();
+    undo.reverse();
+    editor.apply_change(&undo);
+    assert_eq!(text(&editor), "one two");
+
+    // Inserting an opener with auto pairing surrounds the selection
+    editor.set_selection(Selection::Normal(Cursor::new(0, 0)));
+    editor.set_cursor(Cursor::new(0, 3));
+    editor.action(&mut font_system, Action::Insert('"'));
+    assert_eq!(text(&editor), "\"one\" two");
+    assert_eq!(editor.copy_selection().as_deref(), Some("one"));
+
+    // Without a selection nothing is inserted
+    editor.set_selection(Selection::None);
+    editor.action(&mut font_system, Action::SurroundSelection('(', ')'));
+    assert_eq!(text(&editor), "\"one\" two");
+}
@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+use cosmic_text::{Attrs, Buffer, Edit, Editor, FontSystem, Metrics, Selection, Shaping};
+
+// Tests that quick clicks at the same spot are promoted to word and line selection
+#[test]
+fn click_chaining() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, Some(500.0), Some(100.0));
+    buffer.set_text(
+        &mut font_system,
+        "hello world",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+
+    let mut editor = Editor::new(buffer);
+    let mut editor = editor.borrow_with(&mut font_system);
+    editor.shape_as_needed(false);
+
+    let ms = Duration::from_millis;
+    editor.click(2, 10, ms(1000));
+    assert_eq!(editor.selection(), Selection::None);
+    editor.click(3, 10, ms(1200));
+    assert!(matches!(editor.selection(), Selection::Word(_)));
+    editor.click(3, 11, ms(1400));
+    assert!(matches!(editor.selection(), Selection::Line(_)));
+
+    // A slower click starts a new chain
+    editor.click(3, 11, ms(3000));
+    assert_eq!(editor.selection(), Selection::None);
+
+    editor.click_chain_mut().unwrap().set_interval(ms(5000));
+    editor.click(3, 11, ms(7000));
+    assert!(matches!(editor.selection(), Selection::Word(_)));
+}
@@ -0,0 +1,77 @@
+use std::sync::{Arc, Mutex};
+
+use common::{new_editor, text};
+use cosmic_text::{Action, ClipboardProvider, Cursor, Edit, FontSystem, Motion, Selection};
+
+mod common;
+
+#[derive(Debug, Default)]
+struct TestClipboard(Mutex<Option<String>>);
+
+impl ClipboardProvider for TestClipboard {
+    fn text(&self) -> Option<String> {
+        self.0.lock().unwrap().clone()
+    }
+
+    fn set_text(&self, text: String) {
+        *self.0.lock().unwrap() = Some(text);
+    }
+}
+
+#[test]
+fn cut_and_paste_through_clipboard() {
+    let mut font_system = FontSystem::new();
+    let mut editor = new_editor(&mut font_system, "one two\nthree");
+    let clipboard = Arc::new(TestClipboard::default());
+    editor.set_clipboard(Some(clipboard.clone()));
+
+    // Nothing is copied without a selection
+    editor.action(&mut font_system, Action::Copy);
+    editor.action(&mut font_system, Action::Cut);
+    assert_eq!(clipboard.text(), None);
+    assert_eq!(text(&editor), "one two\nthree");
+
+    editor.set_selection(Selection::Normal(Cursor::new(0, 4)));
+    editor.set_cursor(Cursor::new(1, 2));
+    editor.action(&mut font_system, Action::Copy);
+    assert_eq!(clipboard.text().as_deref(), Some("two\nth"));
+    assert_eq!(text(&editor), "one two\nthree");
+
+    editor.start_change();
+    editor.action(&mut font_system, Action::Cut);
+    editor.finish_change();
+    assert_eq!(text(&editor), "one ree");
+    assert_eq!(editor.cursor(), Cursor::new(0, 4));
+    assert_eq!(editor.selection(), Selection::None);
+
+    editor.action(&mut font_system, Action::Motion(Motion::End));
+    editor.start_change();
+    assert!(editor.paste(&mut font_system));
+    editor.finish_change();
+    assert_eq!(text(&editor), "one reetwo\nth");
+    assert_eq!(editor.cursor(), Cursor::new(1, 2));
+
+    // Cut and paste are undone like other changes
+    assert!(editor.undo());
+    assert_eq!(text(&editor), "one ree");
+    assert!(editor.undo());
+    assert_eq!(text(&editor), "one two\nthree");
+}
+
+#[test]
+fn paste_replaces_selection() {
+    let mut font_system = FontSystem::new();
+    let mut editor = new_editor(&mut font_system, "one two three");
+    assert!(!editor.paste(&mut font_system));
+
+    editor.set_selection(Selection::Normal(Cursor::new(0, 4)));
+    editor.set_cursor(Cursor::new(0, 7));
+    editor.action(&mut font_system, Action::Paste("2".to_string()));
+    assert_eq!(text(&editor), "one 2 three");
+    assert_eq!(editor.cursor(), Cursor::new(0, 5));
+
+    // Without a clipboard, cutting leaves the selection alone
+    editor.set_selection(Selection::Normal(Cursor::new(0, 0)));
+    editor.action(&mut font_system, Action::Cut);
+    assert_eq!(text(&editor), "one 2 three");
+}
@@ -0,0 +1,87 @@
+use cosmic_text::{
+    Attrs, Buffer, CopyOptions, Cursor, Edit, Editor, FontSystem, LineEnding, Metrics, Selection,
+    Shaping,
+};
+
+#[test]
+fn copy_selection_options() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "abcdef\nghijkl\nmn",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+    let mut editor = Editor::new(buffer);
+
+    editor.set_selection(Selection::Normal(Cursor::new(0, 4)));
+    editor.set_cursor(Cursor::new(2, 1));
+    assert_eq!(editor.copy_selection().as_deref(), Some("ef\nghijkl\nm"));
+
+    // Reuses the string, appending to it
+    let mut data = String::from(">");
+    let options = CopyOptions {
+        line_ending: LineEnding::CrLf,
+        ..CopyOptions::default()
+    };
+    assert!(editor.copy_selection_into(&mut data, options));
+    assert_eq!(data, ">ef\r\nghijkl\r\nm");
+
+    // Block selection copies the same columns of every line
+    data.clear();
+    editor.set_selection(Selection::Normal(Cursor::new(0, 3)));
+    editor.set_cursor(Cursor::new(2, 1));
+    let block = CopyOptions {
+        block: true,
+        ..CopyOptions::default()
+    };
+    assert!(editor.copy_selection_into(&mut data, block));
+    assert_eq!(data, "bc\nhi\nn");
+
+    // Line selections can end with a newline
+    data.clear();
+    editor.set_selection(Selection::Line(Cursor::new(1, 0)));
+    editor.set_cursor(Cursor::new(1, 2));
+    let line = CopyOptions {
+        line_trailing_newline: true,
+        ..CopyOptions::default()
+    };
+    assert!(editor.copy_selection_into(&mut data, line));
+    assert_eq!(data, "ghijkl\n");
+
+    editor.set_selection(Selection::None);
+    assert!(!editor.copy_selection_into(&mut data, line));
+    assert_eq!(editor.copy_selection(), None);
+}
+
+#[test]
+fn primary_selection() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "hello world",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+    let mut editor = Editor::new(buffer);
+
+    editor.set_selection(Selection::Normal(Cursor::new(0, 0)));
+    editor.set_cursor(Cursor::new(0, 5));
+    assert_eq!(editor.take_primary_selection(), None);
+
+    editor.set_primary_selection(true);
+    assert_eq!(editor.take_primary_selection().as_deref(), Some("hello"));
+    // Unchanged selections are only reported once
+    assert_eq!(editor.take_primary_selection(), None);
+
+    // Several changes between polls are coalesced
+    editor.set_cursor(Cursor::new(0, 7));
+    editor.set_cursor(Cursor::new(0, 8));
+    assert_eq!(editor.take_primary_selection().as_deref(), Some("hello wo"));
+
+    // Clearing the selection keeps the last primary selection
+    editor.set_selection(Selection::None);
+    assert_eq!(editor.take_primary_selection(), None);
+}
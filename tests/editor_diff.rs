@@ -0,0 +1,108 @@
+use common::{new_editor, text};
+use cosmic_text::{diff, Attrs, Color, Cursor, Edit, Editor, FontSystem, Selection};
+
+mod common;
+
+fn reload(editor: &mut Editor, new_text: &str) {
+    let changes = editor.with_buffer(|buffer| diff(buffer, new_text));
+    assert!(editor.apply_changes(&changes));
+}
+
+#[test]
+fn diff_applies_to_new_text() {
+    let mut font_system = FontSystem::new();
+    let cases = [
+        ("", "hello"),
+        ("hello", ""),
+        ("one\ntwo\nthree", "one\n2\nthree"),
+        ("one\ntwo\nthree", "zero\none\nthree\nfour"),
+        ("a b c\nd e f", "a x c\nd e f g\nh"),
+        ("same", "same"),
+        ("tail", "tail\n"),
+        ("line\r\nother", "line\nother\r\nnew"),
+        (
+            "fn main() {\n    foo();\n}\n",
+            "fn main() {\n    bar(1);\n    foo();\n}\n",
+        ),
+    ];
+    for (old, new) in cases {
+        let mut editor = new_editor(&mut font_system, old);
+        reload(&mut editor, new);
+        let expected = new_editor(&mut font_system, new);
+        assert_eq!(text(&editor), text(&expected), "{old:?} -> {new:?}");
+    }
+}
+
+#[test]
+fn diff_changes_only_differing_words() {
+    let mut font_system = FontSystem::new();
+    let editor = new_editor(&mut font_system, "alpha beta gamma\ndelta\nepsilon");
+    let changes = editor.with_buffer(|buffer| diff(buffer, "alpha BETA gamma\ndelta\nepsilon"));
+    assert_eq!(changes.len(), 1);
+    let items = &changes[0].items;
+    assert_eq!(items.len(), 2);
+    assert!(!items[0].insert);
+    assert_eq!(items[0].text, "beta");
+    assert_eq!(items[0].start, Cursor::new(0, 6));
+    assert!(items[1].insert);
+    assert_eq!(items[1].text, "BETA");
+
+    let unchanged = editor.with_buffer(|buffer| diff(buffer, "alpha beta gamma\ndelta\nepsilon"));
+    assert!(unchanged.is_empty());
+}
+
+#[test]
+fn apply_changes_keeps_state() {
+    let mut font_system = FontSystem::new();
+    let mut editor = new_editor(&mut font_system, "first line\nsecond line\nthird line");
+    let red = Attrs::new().color(Color::rgb(0xFF, 0, 0));
+    editor.with_buffer_mut(|buffer| {
+        let attrs_list = &mut buffer.lines[2].attrs_list().clone();
+        attrs_list.add_span(0..5, red);
+        buffer.lines[2].set_attrs_list(attrs_list.clone());
+    });
+    editor.set_cursor(Cursor::new(2, "third li".len()));
+    editor.set_selection(Selection::Normal(Cursor::new(2, "third".len())));
+
+    reload(&mut editor, "new line\nfirst line\nsecond row\nthird line");
+    assert_eq!(
+        text(&editor),
+        "new line\nfirst line\nsecond row\nthird line"
+    );
+
+    // The cursor and selection follow their text
+    assert_eq!(editor.cursor(), Cursor::new(3, "third li".len()));
+    assert_eq!(
+        editor.selection(),
+        Selection::Normal(Cursor::new(3, "third".len()))
+    );
+    // Attributes of unchanged text are kept
+    editor.with_buffer(|buffer| {
+        let attrs_list = buffer.lines[3].attrs_list();
+        assert_eq!(
+            attrs_list.get_span(2).color_opt,
+            Some(Color::rgb(0xFF, 0, 0))
+        );
+        assert_eq!(attrs_list.get_span(7).color_opt, None);
+    });
+
+    // Reloading is a single undoable change
+    assert!(editor.undo());
+    assert_eq!(text(&editor), "first line\nsecond line\nthird line");
+    assert!(editor.redo());
+    assert_eq!(
+        text(&editor),
+        "new line\nfirst line\nsecond row\nthird line"
+    );
+}
+
+#[test]
+fn apply_changes_refuses_pending_change() {
+    let mut font_system = FontSystem::new();
+    let mut editor = new_editor(&mut font_system, "text");
+    editor.start_change();
+    editor.insert_string("more ", None);
+    let changes = editor.with_buffer(|buffer| diff(buffer, "other"));
+    assert!(!editor.apply_changes(&changes));
+    assert_eq!(text(&editor), "more text");
+}
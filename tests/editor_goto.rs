@@ -0,0 +1,68 @@
+use common::new_editor;
+use cosmic_text::{Cursor, Edit, Editor, FontSystem, Scroll, ScrollPolicy, Selection};
+
+mod common;
+
+fn new_editor_100_lines(font_system: &mut FontSystem) -> Editor<'static> {
+    let text: Vec<_> = (0..100).map(|i| format!("héllo {i}")).collect();
+    let mut editor = new_editor(font_system, &text.join("\n"));
+    editor.with_buffer_mut(|buffer| buffer.set_size(font_system, Some(500.0), Some(100.0)));
+    editor.shape_as_needed(font_system, false);
+    editor
+}
+
+fn scroll(editor: &Editor) -> Scroll {
+    editor.with_buffer(|buffer| buffer.scroll())
+}
+
+#[test]
+fn goto_scrolls_with_policy() {
+    let mut font_system = FontSystem::new();
+    let mut editor = new_editor_100_lines(&mut font_system);
+    editor.set_selection(Selection::Normal(Cursor::new(0, 0)));
+
+    editor.goto(&mut font_system, 50, 3, ScrollPolicy::Minimal);
+    assert_eq!(editor.cursor(), Cursor::new(50, "hél".len()));
+    assert_eq!(editor.selection(), Selection::None);
+    let minimal = scroll(&editor);
+    assert_eq!(minimal.line as f32 * 20.0 + minimal.vertical, 46.0 * 20.0);
+
+    editor.goto(&mut font_system, 50, 3, ScrollPolicy::Top);
+    assert_eq!(scroll(&editor), Scroll::new(50, 0.0, 0.0));
+
+    editor.goto(&mut font_system, 50, 3, ScrollPolicy::Center);
+    assert_eq!(scroll(&editor), Scroll::new(48, 0.0, 0.0));
+
+    // Shaping afterwards keeps the scroll
+    editor.shape_as_needed(&mut font_system, false);
+    assert_eq!(scroll(&editor), Scroll::new(48, 0.0, 0.0));
+
+    // Moving within the view with the minimal policy does not scroll
+    editor.goto(&mut font_system, 49, 0, ScrollPolicy::Minimal);
+    assert_eq!(scroll(&editor), Scroll::new(48, 0.0, 0.0));
+
+    // Jumps from other lines are recorded
+    let lines: Vec<_> = editor
+        .jump_list()
+        .unwrap()
+        .entries()
+        .iter()
+        .map(|cursor| cursor.line)
+        .collect();
+    assert_eq!(lines, [0, 50]);
+}
+
+#[test]
+fn goto_clamps_to_buffer() {
+    let mut font_system = FontSystem::new();
+    let mut editor = new_editor_100_lines(&mut font_system);
+
+    editor.goto(&mut font_system, 1000, 1000, ScrollPolicy::Top);
+    assert_eq!(editor.cursor(), Cursor::new(99, "héllo 99".len()));
+    // Scrolling stops at the end of the buffer
+    assert_eq!(scroll(&editor).line, 95);
+
+    editor.goto(&mut font_system, 0, 0, ScrollPolicy::Center);
+    assert_eq!(editor.cursor(), Cursor::new(0, 0));
+    assert_eq!(scroll(&editor), Scroll::new(0, 0.0, 0.0));
+}
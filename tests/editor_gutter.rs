@@ -0,0 +1,70 @@
+use common::new_editor;
+use cosmic_text::{Action, Cursor, Edit, Editor, FontSystem, Gutter};
+
+mod common;
+
+fn gutter_editor(font_system: &mut FontSystem, text: &str) -> Editor<'static> {
+    let mut editor = new_editor(font_system, text);
+    editor.with_buffer_mut(|buffer| buffer.set_size(font_system, Some(60.0), None));
+    editor
+}
+
+#[test]
+fn gutter_cells_follow_visual_lines() {
+    let mut font_system = FontSystem::new();
+    let mut editor = gutter_editor(&mut font_system, "one\nwrapped two three four\nfive");
+    editor.shape_as_needed(&mut font_system, false);
+    assert!(editor.gutter_cells().is_empty());
+
+    let mut gutter = Gutter::new(40.0);
+    assert!(gutter.add_marker(1, 7));
+    assert!(gutter.add_marker(1, 3));
+    assert!(!gutter.add_marker(1, 3));
+    editor.set_gutter(Some(gutter));
+
+    let cells = editor.gutter_cells();
+    assert!(cells.len() > 3, "{cells:?}");
+    assert_eq!(cells[0].line_number, Some(1));
+    assert_eq!(cells[1].line_number, Some(2));
+    assert_eq!(cells[1].markers, [3, 7]);
+    // Wrapped visual lines have no number or markers
+    assert_eq!(cells[2].line_i, 1);
+    assert_eq!(cells[2].layout_i, 1);
+    assert_eq!(cells[2].line_number, None);
+    assert!(cells[2].markers.is_empty());
+    assert_eq!(cells[2].y, 40.0);
+    assert_eq!(cells[2].height, 20.0);
+
+    editor.action(&mut font_system, Action::GutterClick { y: 45 });
+    assert_eq!(editor.take_gutter_click(), Some(1));
+    assert_eq!(editor.take_gutter_click(), None);
+    assert_eq!(editor.cursor(), Cursor::new(0, 0));
+    editor.action(&mut font_system, Action::GutterClick { y: 1000 });
+    assert_eq!(editor.take_gutter_click(), None);
+}
+
+#[test]
+fn gutter_markers_follow_edits() {
+    let mut font_system = FontSystem::new();
+    let mut editor = gutter_editor(&mut font_system, "one\ntwo\nthree");
+    let mut gutter = Gutter::new(40.0);
+    gutter.add_marker(1, 1);
+    gutter.add_marker(2, 2);
+    editor.set_gutter(Some(gutter));
+
+    // New lines above move markers down
+    editor.set_cursor(Cursor::new(0, 3));
+    editor.action(&mut font_system, Action::Enter);
+    assert_eq!(editor.gutter().unwrap().markers(2), [1]);
+    assert_eq!(editor.gutter().unwrap().markers(3), [2]);
+
+    // Joining lines moves markers to the joined line
+    editor.set_cursor(Cursor::new(3, 0));
+    editor.action(&mut font_system, Action::Backspace);
+    assert_eq!(editor.gutter().unwrap().markers(2), [1, 2]);
+
+    let gutter = editor.gutter_mut().unwrap();
+    assert!(gutter.remove_marker(2, 1));
+    assert!(!gutter.remove_marker(2, 1));
+    assert_eq!(gutter.markers(2), [2]);
+}
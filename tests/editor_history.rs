@@ -0,0 +1,59 @@
+use common::{new_editor, text};
+use cosmic_text::{Action, Edit, Editor, FontSystem};
+
+mod common;
+
+fn type_text(editor: &mut Editor, font_system: &mut FontSystem, text: &str) {
+    for c in text.chars() {
+        editor.start_change();
+        editor.action(font_system, Action::Insert(c));
+        editor.finish_change();
+    }
+}
+
+#[test]
+fn undo_redo_typed_words() {
+    let mut font_system = FontSystem::new();
+    let mut editor = new_editor(&mut font_system, "");
+    assert!(!editor.undo());
+
+    // Typing is coalesced a word at a time
+    type_text(&mut editor, &mut font_system, "hello big world");
+    assert!(editor.history().unwrap().can_undo());
+    assert!(editor.undo());
+    assert_eq!(text(&editor), "hello big ");
+    assert!(editor.undo());
+    assert_eq!(text(&editor), "hello ");
+    assert!(editor.redo());
+    assert_eq!(text(&editor), "hello big ");
+
+    // New changes clear the changes that could be redone
+    type_text(&mut editor, &mut font_system, "red");
+    assert!(!editor.history().unwrap().can_redo());
+    assert!(!editor.redo());
+    assert_eq!(text(&editor), "hello big red");
+
+    // Deletions are separate changes
+    editor.start_change();
+    editor.action(&mut font_system, Action::Backspace);
+    editor.finish_change();
+    assert!(editor.undo());
+    assert_eq!(text(&editor), "hello big red");
+
+    // Only the most recent changes are kept
+    editor.history_mut().unwrap().set_max_depth(2);
+    assert!(editor.undo());
+    assert!(editor.undo());
+    assert!(!editor.undo());
+    assert_eq!(text(&editor), "hello ");
+}
+
+#[test]
+fn coalescing_can_be_disabled() {
+    let mut font_system = FontSystem::new();
+    let mut editor = new_editor(&mut font_system, "");
+    editor.history_mut().unwrap().set_coalesce(false);
+    type_text(&mut editor, &mut font_system, "abc");
+    assert!(editor.undo());
+    assert_eq!(text(&editor), "ab");
+}
@@ -0,0 +1,53 @@
+use cosmic_text::{Attrs, Buffer, Edit, Editor, FontSystem, Metrics, Shaping};
+
+// Tests that hovering a link span reports its range and metadata, and requests a redraw
+#[test]
+fn hover_link_span() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, Some(500.0), Some(100.0));
+    buffer.set_rich_text(
+        &mut font_system,
+        [
+            ("see ", Attrs::new()),
+            ("the docs", Attrs::new().metadata(7)),
+        ],
+        Attrs::new(),
+        Shaping::Advanced,
+        None,
+    );
+
+    let mut editor = Editor::new(buffer);
+    let mut editor = editor.borrow_with(&mut font_system);
+    editor.shape_as_needed(false);
+    let link_x = editor.with_buffer(|buffer| {
+        let run = buffer.layout_runs().next().expect("no layout run");
+        let glyph = run
+            .glyphs
+            .iter()
+            .find(|glyph| glyph.start == 5)
+            .expect("no glyph");
+        glyph.x + glyph.w / 2.0
+    });
+
+    editor.set_redraw(false);
+    editor.set_hover_position(Some((link_x as i32, 10)));
+    assert!(editor.redraw());
+    let hover = editor.hover().expect("nothing hovered").clone();
+    assert_eq!(hover.cursor.index, 5);
+    assert_eq!(hover.span, Some(4..12));
+    assert_eq!(hover.metadata, 7);
+    assert!(hover.glyph.x <= link_x && link_x < hover.glyph.x + hover.glyph.width);
+
+    // Moving within the same glyph does not request a redraw
+    editor.set_redraw(false);
+    editor.set_hover_position(Some((link_x as i32, 11)));
+    assert!(!editor.redraw());
+
+    // Plain text has no span
+    editor.set_hover_position(Some((1, 10)));
+    assert_eq!(editor.hover().and_then(|hover| hover.span.clone()), None);
+
+    editor.set_hover_position(None);
+    assert!(editor.hover().is_none());
+}
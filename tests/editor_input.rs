@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use cosmic_text::{
+    Action, Attrs, Buffer, Cursor, Edit, Editor, FontSystem, Metrics, Shaping, SmartPunctuation,
+};
+
+#[test]
+fn input_transformer() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(&mut font_system, "wait -", Attrs::new(), Shaping::Advanced);
+    let mut editor = Editor::new(buffer);
+    editor.set_input_transformer(Some(Arc::new(SmartPunctuation::default())));
+    editor.set_cursor(Cursor::new(0, 6));
+
+    // The replaced hyphen and the inserted dash are one change
+    editor.start_change();
+    editor.action(&mut font_system, Action::Insert('-'));
+    let change = editor.finish_change().expect("no change");
+    let text = |editor: &Editor| editor.with_buffer(|buffer| buffer.lines[0].text().to_string());
+    assert_eq!(text(&editor), "wait \u{2014}");
+    assert_eq!(editor.cursor().index, "wait \u{2014}".len());
+
+    let mut undo = change.clone();
+    undo.reverse();
+    editor.apply_change(&undo);
+    assert_eq!(text(&editor), "wait -");
+
+    // Pasted text is transformed as a whole
+    editor.set_cursor(Cursor::new(0, 0));
+    editor.insert_input("\"so\" ");
+    assert_eq!(text(&editor), "\u{201C}so\u{201D} wait -");
+
+    // Without a transformer, input is inserted unchanged
+    editor.set_input_transformer(None);
+    editor.action(&mut font_system, Action::Insert('\''));
+    assert_eq!(text(&editor), "\u{201C}so\u{201D} 'wait -");
+}
+
+#[test]
+fn insert_text_action() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(&mut font_system, "ab", Attrs::new(), Shaping::Advanced);
+    let mut editor = Editor::new(buffer);
+    editor.set_cursor(Cursor::new(0, 1));
+    let text = |editor: &Editor| {
+        editor.with_buffer(|buffer| {
+            buffer
+                .lines
+                .iter()
+                .map(|line| line.text().to_string())
+                .collect::<Vec<_>>()
+        })
+    };
+
+    // The whole text is one change, with control characters filtered out
+    editor.start_change();
+    editor.action(
+        &mut font_system,
+        Action::InsertText("x\u{7}y\nz\u{e9}".into()),
+    );
+    let change = editor.finish_change().expect("no change");
+    assert_eq!(text(&editor), ["axy", "z\u{e9}b"]);
+    assert_eq!(editor.cursor(), Cursor::new(1, "z\u{e9}".len()));
+
+    let mut undo = change.clone();
+    undo.reverse();
+    editor.apply_change(&undo);
+    assert_eq!(text(&editor), ["ab"]);
+
+    // Overtype replaces one grapheme cluster for every inserted one
+    editor.set_cursor(Cursor::new(0, 0));
+    editor.set_overtype(true);
+    editor.action(&mut font_system, Action::InsertText("e\u{301}c".into()));
+    assert_eq!(text(&editor), ["e\u{301}c"]);
+}
@@ -0,0 +1,104 @@
+use common::new_editor;
+use cosmic_text::{Action, Cursor, Edit, FontSystem, Motion};
+
+mod common;
+
+#[test]
+fn jump_list_navigates_back_and_forward() {
+    let mut font_system = FontSystem::new();
+    let text: Vec<_> = (0..20).map(|i| format!("line {i}")).collect();
+    let mut editor = new_editor(&mut font_system, &text.join("\n"));
+
+    editor.action(&mut font_system, Action::Motion(Motion::Right));
+    // Small motions are not recorded
+    assert!(editor.jump_list().unwrap().entries().is_empty());
+    let start = editor.cursor();
+    editor.action(&mut font_system, Action::Motion(Motion::GotoLine(10)));
+    let middle = editor.cursor();
+    assert_eq!(middle.line, 10);
+    editor.action(&mut font_system, Action::Motion(Motion::BufferEnd));
+    let end = editor.cursor();
+    assert_eq!(editor.jump_list().unwrap().entries(), &[start, middle]);
+
+    editor.action(&mut font_system, Action::JumpBack);
+    assert_eq!(editor.cursor(), middle);
+    editor.action(&mut font_system, Action::JumpBack);
+    assert_eq!(editor.cursor(), start);
+    assert!(!editor.jump_list().unwrap().can_back());
+    editor.action(&mut font_system, Action::JumpBack);
+    assert_eq!(editor.cursor(), start);
+
+    editor.action(&mut font_system, Action::JumpForward);
+    assert_eq!(editor.cursor(), middle);
+    editor.action(&mut font_system, Action::JumpForward);
+    assert_eq!(editor.cursor(), end);
+    assert!(!editor.jump_list().unwrap().can_forward());
+
+    // Jumping while navigating drops the entries ahead
+    editor.action(&mut font_system, Action::JumpBack);
+    editor.action(&mut font_system, Action::JumpBack);
+    editor.action(&mut font_system, Action::Motion(Motion::GotoLine(5)));
+    assert_eq!(editor.jump_list().unwrap().entries(), &[start]);
+    assert!(!editor.jump_list().unwrap().can_forward());
+}
+
+#[test]
+fn jump_list_follows_edits() {
+    let mut font_system = FontSystem::new();
+    let mut editor = new_editor(&mut font_system, "one\ntwo\nthree\nfour");
+    editor.set_cursor(Cursor::new(2, 2));
+    editor.action(&mut font_system, Action::Motion(Motion::BufferStart));
+
+    editor.insert_string("zero\n", None);
+    assert_eq!(editor.jump_list().unwrap().entries(), &[Cursor::new(3, 2)]);
+    editor.action(&mut font_system, Action::JumpBack);
+    assert_eq!(editor.cursor(), Cursor::new(3, 2));
+    assert_eq!(
+        editor.with_buffer(|buffer| buffer.lines[3].text().to_string()),
+        "three"
+    );
+}
+
+#[cfg(feature = "vi")]
+#[test]
+fn vi_ctrl_o_and_ctrl_i() {
+    use cosmic_text::{Attrs, Buffer, Metrics, Shaping, SyntaxEditor, SyntaxSystem, ViEditor};
+
+    let mut font_system = FontSystem::new();
+    let syntax_system = SyntaxSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "alpha\nbeta\ngamma\nbeta",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+    let editor = SyntaxEditor::new(buffer, &syntax_system, "base16-eighties.dark").unwrap();
+    let mut editor = ViEditor::new(editor);
+    editor.shape_as_needed(&mut font_system, false);
+
+    // Searching records the position before the match
+    for c in "/gamma\n".chars() {
+        let action = if c == '\n' {
+            Action::Enter
+        } else {
+            Action::Insert(c)
+        };
+        editor.action(&mut font_system, action);
+    }
+    assert_eq!(editor.cursor(), Cursor::new(2, 0));
+    editor.action(&mut font_system, Action::Insert('G'));
+    assert_eq!(editor.cursor().line, 3);
+
+    editor.action(&mut font_system, Action::Insert('\u{f}'));
+    assert_eq!(editor.cursor(), Cursor::new(2, 0));
+    editor.action(&mut font_system, Action::Insert('\u{f}'));
+    assert_eq!(editor.cursor(), Cursor::new(0, 0));
+    editor.action(&mut font_system, Action::Insert('\t'));
+    assert_eq!(editor.cursor(), Cursor::new(2, 0));
+    // No text was inserted
+    assert_eq!(
+        editor.with_buffer(|buffer| buffer.lines[0].text().to_string()),
+        "alpha"
+    );
+}
@@ -0,0 +1,64 @@
+use common::{new_editor, text};
+use cosmic_text::{Action, Cursor, Edit, Editor, FontSystem, Motion, Selection};
+
+mod common;
+
+fn positions(editor: &Editor) -> Vec<(usize, usize)> {
+    editor
+        .cursors()
+        .iter()
+        .map(|(cursor, _)| (cursor.line, cursor.index))
+        .collect()
+}
+
+#[test]
+fn actions_apply_at_every_cursor() {
+    let mut font_system = FontSystem::new();
+    let mut editor = new_editor(&mut font_system, "let a = 1;\nlet b = 2;");
+    editor.set_cursor(Cursor::new(0, 4));
+    editor.add_cursor(Cursor::new(0, 8), Selection::None);
+    editor.add_cursor(Cursor::new(1, 4), Selection::None);
+    // Cursors at an existing position are ignored
+    editor.add_cursor(Cursor::new(0, 4), Selection::None);
+    assert_eq!(positions(&editor), [(0, 4), (0, 8), (1, 4)]);
+
+    // Later cursors on the same line move with the inserted text
+    editor.start_change();
+    editor.action(&mut font_system, Action::Insert('x'));
+    editor.action(&mut font_system, Action::Insert('y'));
+    let change = editor.finish_change().unwrap();
+    assert_eq!(change.items.len(), 6);
+    assert_eq!(text(&editor), "let xya = xy1;\nlet xyb = 2;");
+    assert_eq!(positions(&editor), [(0, 6), (0, 12), (1, 6)]);
+
+    editor.action(&mut font_system, Action::Backspace);
+    assert_eq!(text(&editor), "let xa = x1;\nlet xb = 2;");
+    assert_eq!(positions(&editor), [(0, 5), (0, 10), (1, 5)]);
+
+    // Joining lines moves the cursors on the following line
+    editor.action(&mut font_system, Action::Motion(Motion::Home));
+    assert_eq!(positions(&editor), [(0, 0), (1, 0)]);
+    editor.action(&mut font_system, Action::Backspace);
+    assert_eq!(text(&editor), "let xa = x1;let xb = 2;");
+    assert_eq!(positions(&editor), [(0, 0), (0, 12)]);
+
+    // Selections are replaced at every cursor
+    editor.set_selection(Selection::Normal(Cursor::new(0, 3)));
+    editor.clear_cursors();
+    editor.add_cursor(Cursor::new(0, 15), Selection::Normal(Cursor::new(0, 12)));
+    editor.action(&mut font_system, Action::Insert('/'));
+    assert_eq!(text(&editor), "/ xa = x1;/ xb = 2;");
+
+    // Edits at all cursors are undone together
+    editor.start_change();
+    editor.action(&mut font_system, Action::Insert('!'));
+    editor.finish_change();
+    assert_eq!(text(&editor), "/! xa = x1;/! xb = 2;");
+    assert!(editor.undo());
+    assert_eq!(text(&editor), "/ xa = x1;/ xb = 2;");
+    assert_eq!(editor.cursors().len(), 1);
+
+    editor.add_cursor(Cursor::new(0, 0), Selection::None);
+    editor.action(&mut font_system, Action::Escape);
+    assert_eq!(editor.cursors().len(), 1);
+}
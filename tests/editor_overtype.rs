@@ -0,0 +1,41 @@
+use common::{new_editor, text};
+use cosmic_text::{Action, Cursor, Edit, FontSystem};
+
+mod common;
+
+#[test]
+fn overtype_replaces_graphemes() {
+    let mut font_system = FontSystem::new();
+    let mut editor = new_editor(&mut font_system, "ae\u{301}c\nd");
+    editor.set_overtype(true);
+    editor.set_cursor(Cursor::new(0, 1));
+
+    // Replaces the whole cluster under the cursor
+    editor.action(&mut font_system, Action::Insert('x'));
+    assert_eq!(text(&editor), "axc\nd");
+    assert_eq!(editor.cursor().index, 2);
+
+    editor.action(&mut font_system, Action::Insert('y'));
+    // Appends at the end of a line instead of joining the next line
+    editor.action(&mut font_system, Action::Insert('z'));
+    assert_eq!(text(&editor), "axyz\nd");
+    assert_eq!(editor.cursor().index, 4);
+}
+
+#[test]
+fn overtype_toggle() {
+    let mut font_system = FontSystem::new();
+    let mut editor = new_editor(&mut font_system, "abc");
+    assert!(!editor.overtype());
+
+    editor.action(&mut font_system, Action::ToggleOvertype);
+    assert!(editor.overtype());
+    editor.set_cursor(Cursor::new(0, 0));
+    editor.action(&mut font_system, Action::Insert('x'));
+    assert_eq!(text(&editor), "xbc");
+
+    editor.action(&mut font_system, Action::ToggleOvertype);
+    assert!(!editor.overtype());
+    editor.action(&mut font_system, Action::Insert('y'));
+    assert_eq!(text(&editor), "xybc");
+}
@@ -0,0 +1,105 @@
+use common::{new_editor, text};
+use cosmic_text::{Cursor, Edit, FontSystem, SearchOptions, Selection};
+
+mod common;
+
+#[test]
+fn find_matches_across_lines() {
+    let mut font_system = FontSystem::new();
+    let editor = new_editor(&mut font_system, "one two\ntwo three\nfour");
+    let options = SearchOptions::default();
+
+    assert_eq!(
+        editor.find("two", options),
+        [
+            (Cursor::new(0, 4), Cursor::new(0, 7)),
+            (Cursor::new(1, 0), Cursor::new(1, 3)),
+        ]
+    );
+    assert_eq!(
+        editor.find("two\ntwo", options),
+        [(Cursor::new(0, 4), Cursor::new(1, 3))]
+    );
+    assert_eq!(
+        editor.find("three\nfour", options),
+        [(Cursor::new(1, 4), Cursor::new(2, 4))]
+    );
+    assert!(editor.find("four\n", options).is_empty());
+    assert!(editor.find("", options).is_empty());
+
+    // Matches do not overlap
+    let editor = new_editor(&mut font_system, "aaaa");
+    assert_eq!(editor.find("aa", options).len(), 2);
+}
+
+#[test]
+fn find_case_insensitive_and_whole_word() {
+    let mut font_system = FontSystem::new();
+    let editor = new_editor(&mut font_system, "Cat concat cat_food ÉCOLE école");
+
+    assert_eq!(editor.find("cat", SearchOptions::default()).len(), 2);
+    let case_insensitive = SearchOptions {
+        case_insensitive: true,
+        ..Default::default()
+    };
+    assert_eq!(editor.find("cat", case_insensitive).len(), 3);
+    assert_eq!(editor.find("école", case_insensitive).len(), 2);
+
+    let whole_word = SearchOptions {
+        case_insensitive: true,
+        whole_word: true,
+    };
+    assert_eq!(
+        editor.find("cat", whole_word),
+        [(Cursor::new(0, 0), Cursor::new(0, 3))]
+    );
+}
+
+#[test]
+fn replace_all_keeps_cursor_on_text() {
+    let mut font_system = FontSystem::new();
+    let mut editor = new_editor(&mut font_system, "a-b\nb-a-b");
+    // Cursor after the last "a", selecting back to the first "b" of the second line
+    editor.set_cursor(Cursor::new(1, 3));
+    editor.set_selection(Selection::Normal(Cursor::new(1, 0)));
+
+    let replaced = editor.replace_all("-", " + ", SearchOptions::default());
+    assert_eq!(replaced, 3);
+    assert_eq!(text(&editor), "a + b\nb + a + b");
+    assert_eq!(editor.cursor(), Cursor::new(1, 5));
+    assert_eq!(editor.selection(), Selection::Normal(Cursor::new(1, 0)));
+
+    // Replacing a multi-line match joins the lines
+    let replaced = editor.replace_all("b\nb", "B", SearchOptions::default());
+    assert_eq!(replaced, 1);
+    assert_eq!(text(&editor), "a + B + a + b");
+    assert_eq!(editor.cursor(), Cursor::new(0, 9));
+}
+
+#[test]
+fn replace_next_wraps_around() {
+    let mut font_system = FontSystem::new();
+    let mut editor = new_editor(&mut font_system, "foo bar foo\nfoo");
+    editor.set_cursor(Cursor::new(0, 5));
+    let options = SearchOptions::default();
+
+    assert!(editor.replace_next("foo", "baz", options));
+    assert_eq!(text(&editor), "foo bar baz\nfoo");
+    assert_eq!(editor.cursor(), Cursor::new(0, 11));
+
+    assert!(editor.replace_next("foo", "baz", options));
+    assert_eq!(text(&editor), "foo bar baz\nbaz");
+
+    assert!(editor.replace_next("foo", "baz", options));
+    assert_eq!(text(&editor), "baz bar baz\nbaz");
+    assert_eq!(editor.cursor(), Cursor::new(0, 3));
+
+    assert!(!editor.replace_next("foo", "baz", options));
+
+    // A selected match is replaced
+    editor.set_cursor(Cursor::new(0, 11));
+    editor.set_selection(Selection::Normal(Cursor::new(0, 8)));
+    assert!(editor.replace_next("baz", "qux", options));
+    assert_eq!(text(&editor), "baz bar qux\nbaz");
+    assert_eq!(editor.selection(), Selection::None);
+}
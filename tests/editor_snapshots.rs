@@ -0,0 +1,281 @@
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use cosmic_text::{
+    Action, Attrs, Buffer, Color, Cursor, Edit, Editor, FontSystem, Metrics, Motion, Selection,
+    Shaping, SwashCache,
+};
+
+const TEXT_COLOR: Color = Color::rgb(0x00, 0x00, 0x00);
+const CURSOR_COLOR: Color = Color::rgb(0xFF, 0x00, 0x00);
+const SELECTION_COLOR: Color = Color::rgb(0x00, 0x00, 0xFF);
+const SELECTED_TEXT_COLOR: Color = Color::rgb(0xFF, 0xFF, 0xFF);
+
+/// An editor drawing snapshot test case.
+/// The editor is drawn with [`FontSystem::new_mock`], where every glyph is a box, so the snapshots
+/// are the same on every platform and do not need the fonts stored with Git LFS. Every pixel drawn
+/// with at least half coverage is written as a character for its color: `#` for text, `c` for the
+/// cursor, `s` for the selection and `w` for selected text. The result is compared to the snapshot
+/// with the name `name` under the `tests/snapshots` directory in this repository.
+/// NOTE: if an environment variable `GENERATE_SNAPSHOTS` is set, the test will create and save
+/// the snapshots instead.
+#[derive(Debug)]
+struct EditorSnapshotCfg {
+    name: &'static str,
+    text: &'static str,
+    width: usize,
+    height: usize,
+    cursor: Cursor,
+    selection: Selection,
+    actions: Vec<Action>,
+}
+
+impl EditorSnapshotCfg {
+    fn new(name: &'static str, text: &'static str) -> Self {
+        Self {
+            name,
+            text,
+            width: 60,
+            height: 12,
+            cursor: Cursor::default(),
+            selection: Selection::None,
+            actions: Vec::new(),
+        }
+    }
+
+    fn size(mut self, width: usize, height: usize) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    fn cursor(mut self, cursor: Cursor) -> Self {
+        self.cursor = cursor;
+        self
+    }
+
+    fn selection(mut self, selection: Selection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    fn actions(mut self, actions: impl IntoIterator<Item = Action>) -> Self {
+        self.actions.extend(actions);
+        self
+    }
+
+    fn buffer(&self, font_system: &mut FontSystem) -> Buffer {
+        let mut buffer = Buffer::new(font_system, Metrics::new(10.0, 12.0));
+        buffer.set_size(
+            font_system,
+            Some(self.width as f32),
+            Some(self.height as f32),
+        );
+        buffer.set_text(font_system, self.text, Attrs::new(), Shaping::Advanced);
+        buffer
+    }
+
+    fn apply_actions<'buffer, E: Edit<'buffer>>(
+        &self,
+        editor: &mut E,
+        font_system: &mut FontSystem,
+    ) {
+        editor.shape_as_needed(font_system, true);
+        editor.set_cursor(self.cursor);
+        editor.set_selection(self.selection);
+        for action in self.actions.iter() {
+            editor.action(font_system, action.clone());
+        }
+        editor.shape_as_needed(font_system, true);
+    }
+
+    /// Write the pixels drawn by `draw` with one of `colors` as the character of that color
+    fn grid(
+        &self,
+        colors: &[(Color, char)],
+        draw: impl FnOnce(&mut dyn FnMut(i32, i32, u32, u32, Color)),
+    ) -> String {
+        let mut grid = vec![vec!['.'; self.width]; self.height];
+        draw(&mut |x, y, w, h, color| {
+            if color.a() < 0x80 {
+                return;
+            }
+            let rgb = |color: Color| (color.r(), color.g(), color.b());
+            let c = colors
+                .iter()
+                .find(|&&(known, _)| rgb(known) == rgb(color))
+                .map_or('?', |&(_, c)| c);
+            if c == '.' {
+                return;
+            }
+            for row in y.max(0)..(y + h as i32).min(self.height as i32) {
+                for col in x.max(0)..(x + w as i32).min(self.width as i32) {
+                    grid[row as usize][col as usize] = c;
+                }
+            }
+        });
+
+        let mut snapshot = String::new();
+        writeln!(snapshot, "text: {:?}", self.text).unwrap();
+        for row in grid {
+            writeln!(snapshot, "{}", row.into_iter().collect::<String>()).unwrap();
+        }
+        snapshot
+    }
+
+    fn snapshot(&self) -> String {
+        let mut font_system = FontSystem::new_mock();
+        let mut swash_cache = SwashCache::new();
+        let mut editor = Editor::new(self.buffer(&mut font_system));
+        self.apply_actions(&mut editor, &mut font_system);
+
+        let colors = [
+            (TEXT_COLOR, '#'),
+            (CURSOR_COLOR, 'c'),
+            (SELECTION_COLOR, 's'),
+            (SELECTED_TEXT_COLOR, 'w'),
+        ];
+        self.grid(&colors, |f| {
+            editor.draw(
+                &mut font_system,
+                &mut swash_cache,
+                TEXT_COLOR,
+                CURSOR_COLOR,
+                SELECTION_COLOR,
+                SELECTED_TEXT_COLOR,
+                f,
+            )
+        })
+    }
+
+    /// Like [`Self::snapshot`], with a [`cosmic_text::ViEditor`] drawn with the colors of the
+    /// `base16-eighties.dark` theme. Its background is left out
+    #[cfg(feature = "vi")]
+    fn vi_snapshot(&self) -> String {
+        use cosmic_text::{SyntaxEditor, SyntaxSystem, ViEditor};
+
+        let mut font_system = FontSystem::new_mock();
+        let mut swash_cache = SwashCache::new();
+        let syntax_system = SyntaxSystem::new();
+        let editor = SyntaxEditor::new(
+            self.buffer(&mut font_system),
+            &syntax_system,
+            "base16-eighties.dark",
+        )
+        .expect("theme `base16-eighties.dark` should be found");
+        let mut editor = ViEditor::new(editor);
+        self.apply_actions(&mut editor, &mut font_system);
+
+        let colors = [
+            (editor.background_color(), '.'),
+            (editor.foreground_color(), '#'),
+            (editor.cursor_color(), 'c'),
+            (editor.selection_color(), 's'),
+        ];
+        self.grid(&colors, |f| {
+            editor.draw(&mut font_system, &mut swash_cache, f)
+        })
+    }
+
+    fn validate_snapshot(&self) {
+        self.validate(self.snapshot());
+    }
+
+    #[cfg(feature = "vi")]
+    fn validate_vi_snapshot(&self) {
+        self.validate(self.vi_snapshot());
+    }
+
+    fn validate(&self, snapshot: String) {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("snapshots")
+            .join(format!("{}.txt", self.name));
+
+        let generate_snapshots = std::env::var("GENERATE_SNAPSHOTS")
+            .map(|v| {
+                let val = v.trim().to_ascii_lowercase();
+                ["t", "true", "1"].iter().any(|&v| v == val)
+            })
+            .unwrap_or_default();
+
+        if generate_snapshots {
+            std::fs::write(path, snapshot).unwrap();
+        } else {
+            let reference = std::fs::read_to_string(&path).unwrap();
+            assert_eq!(
+                snapshot,
+                reference,
+                "drawing of {} does not match {}",
+                self.name,
+                path.display()
+            );
+        }
+    }
+}
+
+#[test]
+fn snapshot_editor_selection() {
+    EditorSnapshotCfg::new("editor_selection", "Hello world\nsecond line")
+        .size(60, 24)
+        .selection(Selection::Normal(Cursor::new(0, 6)))
+        .cursor(Cursor::new(1, 6))
+        .validate_snapshot();
+}
+
+#[test]
+fn snapshot_editor_bidi_selection() {
+    // Selecting across the direction change highlights two separate ranges
+    EditorSnapshotCfg::new("editor_bidi_selection", "ab אבג cd")
+        .selection(Selection::Normal(Cursor::new(0, 1)))
+        .cursor(Cursor::new(0, 7))
+        .validate_snapshot();
+}
+
+#[test]
+fn snapshot_editor_bidi_caret() {
+    // Moving left from the end of the line enters the right to left text from its end
+    EditorSnapshotCfg::new("editor_bidi_caret", "abc אבג")
+        .actions([
+            Action::Motion(Motion::End),
+            Action::Motion(Motion::Left),
+            Action::Motion(Motion::Left),
+        ])
+        .validate_snapshot();
+}
+
+#[test]
+fn snapshot_editor_overtype_cursor() {
+    EditorSnapshotCfg::new("editor_overtype_cursor", "overtype")
+        .cursor(Cursor::new(0, 4))
+        .actions([Action::ToggleOvertype])
+        .validate_snapshot();
+}
+
+#[cfg(feature = "vi")]
+#[test]
+fn snapshot_vi_editor_normal_cursor() {
+    // `w` moves the block cursor to the start of the next word
+    EditorSnapshotCfg::new("vi_editor_normal_cursor", "hello world")
+        .actions([Action::Insert('w')])
+        .validate_vi_snapshot();
+}
+
+#[cfg(feature = "vi")]
+#[test]
+fn snapshot_vi_editor_visual_selection() {
+    // Visual mode selection includes the character under the cursor
+    EditorSnapshotCfg::new("vi_editor_visual_selection", "hello world\nsecond line")
+        .size(60, 24)
+        .actions([Action::Insert('v'), Action::Insert('j')])
+        .validate_vi_snapshot();
+}
+
+#[cfg(feature = "vi")]
+#[test]
+fn snapshot_vi_editor_insert_cursor() {
+    // Insert mode draws a line cursor instead of a block
+    EditorSnapshotCfg::new("vi_editor_insert_cursor", "hello world")
+        .actions([Action::Insert('e'), Action::Insert('a')])
+        .validate_vi_snapshot();
+}
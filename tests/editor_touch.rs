@@ -0,0 +1,89 @@
+use cosmic_text::{
+    Action, Attrs, Buffer, Cursor, Edit, Editor, FontSystem, Metrics, Selection, SelectionHandle,
+    Shaping,
+};
+
+fn selected_range(editor: &Editor) -> Option<std::ops::Range<usize>> {
+    editor
+        .selection_bounds()
+        .map(|(start, end)| start.index..end.index)
+}
+
+// Tests that dragging a selection handle moves only that edge of the selection
+#[test]
+fn drag_selection_handle() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, Some(500.0), Some(100.0));
+    buffer.set_text(
+        &mut font_system,
+        "hello world",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+
+    let mut editor = Editor::new(buffer);
+    let mut editor = editor.borrow_with(&mut font_system);
+    editor.shape_as_needed(false);
+    assert_eq!(editor.selection_handles(), None);
+
+    editor.set_cursor(Cursor::new(0, 6));
+    editor.set_selection(Selection::Normal(Cursor::new(0, 8)));
+    let handles = editor.selection_handles().expect("no selection handles");
+    let (start_x, start_y) = handles.start.expect("start handle not visible");
+    let (end_x, end_y) = handles.end.expect("end handle not visible");
+    assert!(start_x < end_x);
+    assert_eq!(start_y, 20);
+    assert_eq!(end_y, 20);
+
+    editor.action(Action::DragHandle {
+        which: SelectionHandle::End,
+        x: 499,
+        y: 10,
+    });
+    assert_eq!(
+        editor.selection_bounds(),
+        Some((Cursor::new(0, 6), Cursor::new(0, 11)))
+    );
+
+    editor.action(Action::DragHandle {
+        which: SelectionHandle::Start,
+        x: 0,
+        y: 10,
+    });
+    let (start, end) = editor.selection_bounds().expect("no selection");
+    assert_eq!(start.index, 0);
+    assert_eq!(end.index, 11);
+}
+
+// Tests that a long press selects a word and dragging afterwards extends by words
+#[test]
+fn long_press_selects_words() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, Some(500.0), Some(100.0));
+    buffer.set_text(
+        &mut font_system,
+        "hello world again",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+
+    let mut editor = Editor::new(buffer);
+    let mut editor = editor.borrow_with(&mut font_system);
+    editor.shape_as_needed(false);
+
+    editor.action(Action::LongPress { x: 1, y: 10 });
+    assert_eq!(selected_range(&editor), Some(0..5));
+
+    // Drag into the middle of "world"
+    let (end_x, _) = editor
+        .selection_handles()
+        .and_then(|handles| handles.end)
+        .expect("end handle not visible");
+    editor.action(Action::Drag {
+        x: end_x + 20,
+        y: 10,
+    });
+    assert_eq!(selected_range(&editor), Some(0..11));
+}
@@ -0,0 +1,66 @@
+use common::{new_editor, text};
+use cosmic_text::{Action, Attrs, AttrsList, Color, Cursor, Edit, FontSystem};
+
+mod common;
+
+#[test]
+fn transpose_chars() {
+    let mut font_system = FontSystem::new();
+
+    // Swaps whole grapheme clusters around the cursor
+    let mut editor = new_editor(&mut font_system, "ae\u{301}b");
+    editor.set_cursor(Cursor::new(0, 1));
+    editor.action(&mut font_system, Action::TransposeChars);
+    assert_eq!(text(&editor), "e\u{301}ab");
+    assert_eq!(editor.cursor().index, 4);
+
+    // At the end of a line, swaps the two characters before the cursor
+    let mut editor = new_editor(&mut font_system, "abc");
+    editor.set_cursor(Cursor::new(0, 3));
+    editor.action(&mut font_system, Action::TransposeChars);
+    assert_eq!(text(&editor), "acb");
+    assert_eq!(editor.cursor().index, 3);
+
+    // Nothing to swap at the start of a line
+    editor.set_cursor(Cursor::new(0, 0));
+    editor.action(&mut font_system, Action::TransposeChars);
+    assert_eq!(text(&editor), "acb");
+}
+
+#[test]
+fn transpose_words() {
+    let mut font_system = FontSystem::new();
+
+    let mut editor = new_editor(&mut font_system, "one, two three");
+    editor.set_cursor(Cursor::new(0, 1));
+    editor.action(&mut font_system, Action::TransposeWords);
+    assert_eq!(text(&editor), "two, one three");
+    assert_eq!(editor.cursor().index, 8);
+
+    // With no following word, swaps the last two words
+    editor.set_cursor(Cursor::new(0, 14));
+    editor.action(&mut font_system, Action::TransposeWords);
+    assert_eq!(text(&editor), "two, three one");
+
+    // Attributes move with the text, and the change is undone as one
+    let red = Attrs::new().color(Color::rgb(0xFF, 0, 0));
+    let mut editor = new_editor(&mut font_system, "");
+    let mut attrs_list = AttrsList::new(Attrs::new());
+    attrs_list.add_span(0..3, red);
+    editor.insert_at(Cursor::new(0, 0), "red blue", Some(attrs_list));
+    editor.set_cursor(Cursor::new(0, 4));
+    editor.start_change();
+    editor.action(&mut font_system, Action::TransposeWords);
+    let change = editor.finish_change().expect("no change");
+    assert_eq!(text(&editor), "blue red");
+    editor.with_buffer(|buffer| {
+        let attrs_list = buffer.lines[0].attrs_list();
+        assert_eq!(attrs_list.get_span(0), Attrs::new());
+        assert_eq!(attrs_list.get_span(5), red);
+    });
+
+    let mut undo = change.clone();
+    undo.reverse();
+    editor.apply_change(&undo);
+    assert_eq!(text(&editor), "red blue");
+}
@@ -0,0 +1,52 @@
+use common::{new_editor, text};
+use cosmic_text::{Action, Cursor, Edit, FontSystem, Motion, Selection};
+
+mod common;
+
+#[test]
+fn word_end_stays_on_line() {
+    let mut font_system = FontSystem::new();
+    let mut editor = new_editor(&mut font_system, "one two\nthree");
+
+    editor.set_cursor(Cursor::new(0, 1));
+    editor.action(&mut font_system, Action::Motion(Motion::WordEnd));
+    assert_eq!(editor.cursor(), Cursor::new(0, 3));
+    editor.action(&mut font_system, Action::Motion(Motion::WordEnd));
+    assert_eq!(editor.cursor(), Cursor::new(0, 7));
+    editor.action(&mut font_system, Action::Motion(Motion::WordEnd));
+    assert_eq!(editor.cursor(), Cursor::new(0, 7));
+
+    editor.action(&mut font_system, Action::Motion(Motion::RightWord));
+    assert_eq!(editor.cursor(), Cursor::new(1, 0));
+    editor.action(&mut font_system, Action::Motion(Motion::LeftWord));
+    assert_eq!(editor.cursor(), Cursor::new(0, 7));
+}
+
+#[test]
+fn delete_word_backward_and_forward() {
+    let mut font_system = FontSystem::new();
+    let mut editor = new_editor(&mut font_system, "one two, three\nfour");
+
+    editor.set_cursor(Cursor::new(0, 7));
+    editor.action(&mut font_system, Action::DeleteWordBackward);
+    assert_eq!(text(&editor), "one , three\nfour");
+    assert_eq!(editor.cursor(), Cursor::new(0, 4));
+
+    editor.action(&mut font_system, Action::DeleteWordForward);
+    assert_eq!(text(&editor), "one \nfour");
+    assert_eq!(editor.cursor(), Cursor::new(0, 4));
+
+    // Lines are joined at the end and start of a line
+    editor.action(&mut font_system, Action::DeleteWordForward);
+    assert_eq!(text(&editor), "one four");
+    editor.set_cursor(Cursor::new(0, 0));
+    editor.action(&mut font_system, Action::DeleteWordBackward);
+    assert_eq!(text(&editor), "one four");
+
+    // The selection is deleted instead, if there is one
+    editor.set_selection(Selection::Normal(Cursor::new(0, 1)));
+    editor.set_cursor(Cursor::new(0, 2));
+    editor.action(&mut font_system, Action::DeleteWordBackward);
+    assert_eq!(text(&editor), "oe four");
+    assert_eq!(editor.cursor(), Cursor::new(0, 1));
+}
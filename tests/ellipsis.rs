@@ -0,0 +1,108 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Overflow, Shaping, Wrap};
+
+fn visible_text(buffer: &Buffer) -> Vec<String> {
+    buffer
+        .layout_runs()
+        .map(|run| {
+            let mut text = String::new();
+            for glyph in run.glyphs.iter() {
+                if glyph.start == glyph.end {
+                    text.push_str(buffer.ellipsis());
+                } else {
+                    text.push_str(&run.text[glyph.range()]);
+                }
+            }
+            text
+        })
+        .collect()
+}
+
+#[test]
+fn ellipsis_cuts_lines_wider_than_buffer() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_wrap(Wrap::None);
+    buffer.set_size(Some(80.0), None);
+    buffer.set_text(
+        "The quick brown fox jumps over the lazy dog\nShort",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+    assert_eq!(buffer.overflow(), Overflow::Clip);
+    assert!(buffer.layout_runs().next().unwrap().line_w > 80.0);
+
+    buffer.set_overflow(Overflow::Ellipsis);
+    let runs: Vec<_> = buffer.layout_runs().map(|run| run.line_w).collect();
+    assert!(runs[0] <= 80.0, "{runs:?}");
+    let text = visible_text(&buffer);
+    assert!(text[0].starts_with("The"), "{text:?}");
+    assert!(text[0].ends_with('…'), "{text:?}");
+    assert_eq!(text[1], "Short");
+
+    buffer.set_ellipsis("...");
+    let text = visible_text(&buffer);
+    assert!(text[0].ends_with("..."), "{text:?}");
+    assert!(buffer.layout_runs().next().unwrap().line_w <= 80.0);
+}
+
+#[test]
+fn ellipsis_cuts_last_line_that_fits_height() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_overflow(Overflow::Ellipsis);
+    buffer.set_size(Some(1000.0), Some(50.0));
+    buffer.set_text("one\ntwo\nthree\nfour", Attrs::new(), Shaping::Advanced);
+    assert_eq!(visible_text(&buffer), ["one", "two…"]);
+
+    // The hidden text is shown again when there is room for it
+    buffer.set_size(Some(1000.0), Some(80.0));
+    assert_eq!(visible_text(&buffer), ["one", "two", "three", "four"]);
+
+    buffer.set_size(Some(1000.0), Some(60.0));
+    assert_eq!(visible_text(&buffer), ["one", "two", "three…"]);
+
+    buffer.set_overflow(Overflow::Clip);
+    assert_eq!(visible_text(&buffer), ["one", "two", "three"]);
+
+    // Wrapped lines of a single paragraph are cut short too
+    buffer.set_overflow(Overflow::Ellipsis);
+    buffer.set_size(Some(100.0), Some(50.0));
+    buffer.set_text(
+        "The quick brown fox jumps over the lazy dog",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+    let text = visible_text(&buffer);
+    assert_eq!(text.len(), 2, "{text:?}");
+    assert!(text[1].ends_with('…'), "{text:?}");
+    assert!(buffer.layout_runs().all(|run| run.line_w <= 100.0));
+}
+
+#[test]
+fn max_lines_limits_visual_lines() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_text("one\ntwo\nthree\nfour", Attrs::new(), Shaping::Advanced);
+    buffer.set_max_lines(Some(2));
+    assert_eq!(buffer.max_lines(), Some(2));
+    assert_eq!(visible_text(&buffer), ["one", "two"]);
+
+    buffer.set_overflow(Overflow::Ellipsis);
+    assert_eq!(visible_text(&buffer), ["one", "two…"]);
+
+    // Lines with larger metrics still count as a single line
+    let mut attrs_list = buffer.lines[0].attrs_list().clone();
+    attrs_list.add_span(0..3, Attrs::new().metrics(Metrics::new(40.0, 60.0)));
+    buffer.lines[0].set_attrs_list(attrs_list);
+    buffer.set_max_lines(Some(3));
+    assert_eq!(visible_text(&buffer), ["one", "two", "three…"]);
+
+    buffer.set_max_lines(Some(4));
+    assert_eq!(visible_text(&buffer), ["one", "two", "three", "four"]);
+
+    buffer.set_max_lines(None);
+    assert_eq!(buffer.layout_runs().count(), 4);
+}
@@ -0,0 +1,53 @@
+use cosmic_text::{Attrs, Buffer, Family, FontSystem, Metrics, Shaping};
+
+fn shape(font_system: &mut FontSystem, text: &str) -> Vec<(usize, usize, cosmic_text::fontdb::ID)> {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(font_system);
+    buffer.set_text(
+        text,
+        Attrs::new().family(Family::SansSerif),
+        Shaping::Advanced,
+    );
+    buffer.shape_until_scroll(false);
+    buffer
+        .layout_runs()
+        .flat_map(|run| run.glyphs.iter())
+        .map(|glyph| (glyph.start as usize, glyph.end as usize, glyph.font_id))
+        .collect()
+}
+
+#[test]
+fn emoji_between_text() {
+    let mut font_system = FontSystem::new();
+
+    // Text on both sides of the emoji is shaped with the same font
+    for text in [
+        "ab\u{1F600}cd",
+        "ab\u{1F44D}\u{1F3FD}cd",
+        "ab\u{2764}\u{FE0F}cd",
+    ] {
+        let glyphs = shape(&mut font_system, text);
+        let emoji_end = text.len() - 2;
+        let text_fonts = glyphs
+            .iter()
+            .filter(|(start, _, _)| *start < 2 || *start >= emoji_end)
+            .map(|(_, _, font_id)| *font_id)
+            .collect::<Vec<_>>();
+        assert_eq!(text_fonts.len(), 4, "{text:?}");
+        assert!(text_fonts.iter().all(|font_id| *font_id == text_fonts[0]));
+
+        // The emoji cluster is covered by its own glyphs
+        assert!(glyphs
+            .iter()
+            .any(|(start, end, _)| *start == 2 && *end == emoji_end));
+    }
+}
+
+#[test]
+fn text_presentation_stays_in_text_run() {
+    let mut font_system = FontSystem::new();
+    let text = "a\u{2764}\u{FE0E}b";
+    let glyphs = shape(&mut font_system, text);
+    assert_eq!(glyphs.first().map(|glyph| glyph.0), Some(0));
+    assert_eq!(glyphs.last().map(|glyph| glyph.1), Some(text.len()));
+}
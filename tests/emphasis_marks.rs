@@ -0,0 +1,69 @@
+use cosmic_text::{
+    Attrs, AttrsList, Buffer, Color, EmphasisMark, EmphasisPosition, EmphasisShape, FontSystem,
+    Metrics, Shaping, SwashCache,
+};
+
+#[test]
+fn emphasis_marks_skip_spaces_and_punctuation() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(20.0, 40.0));
+    let red = Color::rgb(0xFF, 0x00, 0x00);
+    let over = EmphasisMark::new(EmphasisShape::Dot, EmphasisPosition::Over);
+    let under = EmphasisMark::new(EmphasisShape::Circle, EmphasisPosition::Under).filled(false);
+    let mut attrs_list = AttrsList::new(Attrs::new());
+    attrs_list.add_span(0..5, Attrs::new().emphasis(over));
+    attrs_list.add_span(5..7, Attrs::new().emphasis(under).color(red));
+    buffer.set_text(&mut font_system, "ab, cde", Attrs::new(), Shaping::Advanced);
+    buffer.lines[0].set_attrs_list(attrs_list);
+    buffer.shape_until_scroll(&mut font_system, false);
+
+    let run = buffer.layout_runs().next().unwrap();
+    let marks = run.emphasis_marks(&mut font_system);
+    let clusters: Vec<_> = marks.iter().map(|mark| mark.start..mark.end).collect();
+    assert_eq!(clusters, [0..1, 1..2, 4..5, 5..6, 6..7]);
+
+    for (mark, glyph) in marks.iter().zip(
+        run.glyphs
+            .iter()
+            .filter(|glyph| ![2, 3].contains(&glyph.start)),
+    ) {
+        assert!(
+            (mark.x - (glyph.x + glyph.w / 2.0)).abs() < 0.01,
+            "{mark:?}"
+        );
+    }
+    // Marks on the same side line up
+    assert_eq!(marks[0].y, marks[2].y);
+    assert!(marks[0].y < run.line_y - 10.0, "{marks:?}");
+    assert!(marks[3].y > run.line_y, "{marks:?}");
+    assert_eq!(marks[0].color_opt, None);
+    assert_eq!(marks[3].color_opt, Some(red));
+
+    // Open marks are drawn in the color of the text, with an empty center
+    let mut pixels = Vec::new();
+    marks[3].draw(Color::rgb(0, 0, 0), |x, y, w, h, color| {
+        assert_eq!(color, red);
+        for x in x..x + w as i32 {
+            for y in y..y + h as i32 {
+                pixels.push((x, y));
+            }
+        }
+    });
+    assert!(!pixels.is_empty());
+    let center = (marks[3].x as i32, marks[3].y as i32);
+    assert!(!pixels.contains(&center), "{pixels:?}");
+
+    let mut over_text = false;
+    let mut swash_cache = SwashCache::new();
+    buffer.draw(
+        &mut font_system,
+        &mut swash_cache,
+        Color::rgb(0, 0, 0),
+        |_, y, _, _, _| {
+            if (y as f32) < marks[0].y + marks[0].size / 2.0 {
+                over_text = true;
+            }
+        },
+    );
+    assert!(over_text);
+}
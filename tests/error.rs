@@ -0,0 +1,51 @@
+use cosmic_text::{Attrs, Buffer, Cursor, Error, FontSystem, Metrics, Shaping};
+
+#[test]
+fn error_load_font() {
+    let mut font_system = FontSystem::new_with_locale_and_db("en-US".into(), Default::default());
+
+    assert!(matches!(
+        font_system.load_font_data(b"not a font".to_vec()),
+        Err(Error::FontParse(None))
+    ));
+    assert!(matches!(
+        font_system.load_font_file("fonts/missing.ttf"),
+        Err(Error::IoError(_))
+    ));
+    assert_eq!(font_system.db().len(), 0);
+
+    // Copy the data of a system font
+    let mut system_fonts = FontSystem::new();
+    let id = system_fonts.db().faces().next().unwrap().id;
+    let data = system_fonts.get_font(id).unwrap().data().to_vec();
+
+    let ids = font_system.load_font_data(data).unwrap();
+    assert!(!ids.is_empty());
+    assert!(font_system.try_get_font(ids[0]).is_ok());
+}
+
+#[test]
+fn error_invalid_cursor() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(Some(100.0), Some(40.0));
+    buffer.set_text("héllo\nworld", Attrs::new(), Shaping::Advanced);
+
+    assert!(buffer.try_layout_cursor(Cursor::new(1, 5)).is_ok());
+    for cursor in [Cursor::new(2, 0), Cursor::new(1, 6), Cursor::new(0, 2)] {
+        assert!(matches!(
+            buffer.try_layout_cursor(cursor),
+            Err(Error::InvalidCursor(invalid)) if invalid == cursor
+        ));
+        assert!(matches!(
+            buffer.try_shape_until_cursor(cursor, false),
+            Err(Error::InvalidCursor(_))
+        ));
+        // Does not panic
+        buffer.shape_until_cursor(cursor, false);
+    }
+    assert!(buffer
+        .try_shape_until_cursor(Cursor::new(1, 5), false)
+        .is_ok());
+}
@@ -0,0 +1,74 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, LineHeight, Metrics, Rect, Shaping, Wrap};
+
+// Tests that glyphs never overlap an exclusion zone, and that lines beside it are shortened.
+#[test]
+fn exclusions_avoid_rect() {
+    let mut font_system = FontSystem::new();
+    let metrics = Metrics::new(14.0, 20.0);
+    let exclusion = Rect::new(0.0, 0.0, 100.0, 60.0);
+
+    let mut buffer = Buffer::new(&mut font_system, metrics);
+    let mut buffer = buffer.borrow_with(&mut font_system);
+
+    buffer.set_wrap(Wrap::Word);
+    buffer.set_size(Some(300.0), Some(1000.0));
+    buffer.set_text(
+        "Lorem ipsum dolor sit amet, qui minim labore adipisicing minim sint cillum sint consectetur cupidatat. Lorem ipsum dolor sit amet, qui minim labore adipisicing minim sint cillum sint consectetur cupidatat.",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+    buffer.set_exclusions(vec![exclusion]);
+    buffer.shape_until_scroll(false);
+
+    let mut runs = 0;
+    for run in buffer.layout_runs() {
+        runs += 1;
+        let overlaps_y = run.line_top < exclusion.y + exclusion.height
+            && run.line_top + run.line_height > exclusion.y;
+        for glyph in run.glyphs.iter() {
+            assert!(glyph.x + glyph.w <= 300.0 + 0.01);
+            if overlaps_y {
+                assert!(
+                    glyph.x >= exclusion.x + exclusion.width,
+                    "glyph at {} overlaps exclusion on line at {}",
+                    glyph.x,
+                    run.line_top
+                );
+            }
+        }
+    }
+    assert!(runs > 3);
+
+    // Removing exclusions restores the original layout
+    buffer.set_exclusions(Vec::new());
+    let first_x = buffer
+        .layout_runs()
+        .next()
+        .and_then(|run| run.glyphs.first().map(|glyph| glyph.x));
+    assert_eq!(first_x, Some(0.0));
+}
+
+// Tests that visual lines taller than the buffer line height are placed at their real position
+#[test]
+fn exclusions_follow_line_height() {
+    let mut font_system = FontSystem::new_mock();
+    let exclusion = Rect::new(0.0, 40.0, 100.0, 20.0);
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(10.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+
+    buffer.set_wrap(Wrap::Word);
+    buffer.set_size(Some(300.0), None);
+    buffer.set_text(
+        &"word ".repeat(60),
+        Attrs::new().line_height(LineHeight::Absolute(40.0)),
+        Shaping::Advanced,
+    );
+    buffer.set_exclusions(vec![exclusion]);
+
+    let starts: Vec<(f32, f32)> = buffer
+        .layout_runs()
+        .map(|run| (run.line_top, run.glyphs[0].x))
+        .collect();
+    assert_eq!(starts[..3], [(0.0, 0.0), (40.0, 100.0), (80.0, 0.0)]);
+}
@@ -0,0 +1,36 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping};
+
+fn glyphs(font_system: &mut FontSystem, text: &str) -> Vec<(usize, cosmic_text::fontdb::ID, u16)> {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(font_system);
+    buffer.set_text(text, Attrs::new(), Shaping::Advanced);
+    buffer.shape_until_scroll(false);
+    buffer
+        .layout_runs()
+        .flat_map(|run| run.glyphs.iter())
+        .map(|glyph| (glyph.start as usize, glyph.font_id, glyph.glyph_id))
+        .collect()
+}
+
+#[test]
+fn repeated_words_use_same_fallback() {
+    let mut font_system = FontSystem::new();
+
+    // Mixed coverage and tofu words shape the same when repeated
+    let word = "a\u{3042}\u{10FFFD}\u{0627}";
+    let text = format!("{word} {word}");
+    let glyphs = glyphs(&mut font_system, &text);
+    let second = word.len() + 1;
+    let (first_glyphs, second_glyphs): (Vec<&(usize, _, u16)>, Vec<_>) = glyphs
+        .iter()
+        .filter(|glyph| glyph.0 != word.len())
+        .partition(|glyph| glyph.0 < second);
+    assert_eq!(first_glyphs.len(), second_glyphs.len());
+    for (a, b) in first_glyphs.iter().zip(second_glyphs.iter()) {
+        assert_eq!(a.0 + second, b.0);
+        assert_eq!((a.1, a.2), (b.1, b.2));
+    }
+
+    // Shaping again in a new buffer gives the same result
+    assert_eq!(glyphs, self::glyphs(&mut font_system, &text));
+}
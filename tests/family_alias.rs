@@ -0,0 +1,90 @@
+use cosmic_text::{Attrs, Buffer, Family, FontSystem, Metrics, Shaping};
+
+fn family_of_text(font_system: &mut FontSystem, family: &str) -> String {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(font_system);
+    buffer.set_text(
+        "Hello",
+        Attrs::new().family(Family::Name(family)),
+        Shaping::Advanced,
+    );
+    buffer.shape_until_scroll(false);
+    let font_id = buffer.layout_runs().next().unwrap().glyphs[0].font_id;
+    font_system.db().face(font_id).unwrap().families[0]
+        .0
+        .clone()
+}
+
+#[test]
+fn family_alias_substitutes_missing_family() {
+    let mut font_system = FontSystem::new();
+    let installed = |font_system: &FontSystem, family: &str| {
+        font_system
+            .db()
+            .faces()
+            .any(|face| face.families.iter().any(|(name, _)| name == family))
+    };
+    if !installed(&font_system, "DejaVu Sans") || !installed(&font_system, "DejaVu Serif") {
+        return;
+    }
+
+    font_system.add_family_alias("Helvetica", "Missing Family");
+    font_system.add_family_alias("Helvetica", "DejaVu Serif");
+    assert_eq!(
+        family_of_text(&mut font_system, "helvetica"),
+        "DejaVu Serif"
+    );
+
+    font_system.remove_family_aliases("Helvetica");
+    font_system.add_family_alias("Helvetica", "DejaVu Sans");
+    assert_eq!(family_of_text(&mut font_system, "Helvetica"), "DejaVu Sans");
+
+    // Installed families are not substituted
+    font_system.add_family_alias("DejaVu Serif", "DejaVu Sans");
+    assert_eq!(
+        family_of_text(&mut font_system, "DejaVu Serif"),
+        "DejaVu Serif"
+    );
+
+    assert_eq!(
+        font_system.family_aliases().collect::<Vec<_>>(),
+        [
+            ("dejavu serif", "DejaVu Sans"),
+            ("helvetica", "DejaVu Sans")
+        ]
+    );
+}
+
+#[test]
+fn family_alias_metric_compensation() {
+    let mut font_system = FontSystem::new();
+    if !font_system
+        .db()
+        .faces()
+        .any(|face| face.families.iter().any(|(name, _)| name == "DejaVu Sans"))
+    {
+        return;
+    }
+    font_system.add_family_alias("Arial", "DejaVu Sans");
+
+    let line_w = |font_system: &mut FontSystem| {
+        let mut buffer = Buffer::new(font_system, Metrics::new(10.0, 20.0));
+        let mut buffer = buffer.borrow_with(font_system);
+        buffer.set_text(
+            "Hello",
+            Attrs::new().family(Family::Name("Arial")),
+            Shaping::Advanced,
+        );
+        buffer.shape_until_scroll(false);
+        buffer.layout_runs().next().unwrap().line_w
+    };
+
+    let uncompensated = line_w(&mut font_system);
+    font_system.set_metric_compensation(true);
+    let compensated = line_w(&mut font_system);
+
+    // Published Helvetica widths of H, e, l, l, and o
+    let published = (722.0 + 556.0 + 222.0 + 222.0 + 556.0) / 1000.0 * 10.0;
+    assert!((compensated - published).abs() < 0.01, "{compensated}");
+    assert!((uncompensated - published).abs() > 0.1);
+}
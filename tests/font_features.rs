@@ -0,0 +1,49 @@
+use cosmic_text::{Attrs, AttrsList, Buffer, FontFeature, FontSystem, Metrics, Shaping};
+
+const NO_LIGATURES: &[FontFeature] = &[FontFeature::disable(b"liga")];
+
+/// Get the number of glyphs of each half of the first line
+fn half_glyphs(buffer: &Buffer) -> [usize; 2] {
+    let run = buffer.layout_runs().next().unwrap();
+    let half = run.text.len() / 2;
+    let first = run
+        .glyphs
+        .iter()
+        .filter(|glyph| (glyph.start as usize) < half)
+        .count();
+    [first, run.glyphs.len() - first]
+}
+
+#[test]
+fn font_features_apply_to_their_span() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    // A single word, so both halves are shaped together
+    let text = "officeoffice";
+
+    buffer.set_text(text, Attrs::new(), Shaping::Advanced);
+    let ligated = half_glyphs(&buffer);
+    assert!(ligated[0] < 6, "the font has no ligatures: {ligated:?}");
+
+    // Ligatures are only turned off in the first half
+    let mut attrs_list = AttrsList::new(Attrs::new());
+    attrs_list.add_span(0..6, Attrs::new().font_features(NO_LIGATURES));
+    buffer.lines[0].set_attrs_list(attrs_list);
+    buffer.shape_until_scroll(false);
+    assert_eq!(half_glyphs(&buffer), [6, ligated[1]]);
+
+    // And only in the second half
+    let mut attrs_list = AttrsList::new(Attrs::new().font_features(NO_LIGATURES));
+    attrs_list.add_span(6..12, Attrs::new());
+    buffer.lines[0].set_attrs_list(attrs_list);
+    buffer.shape_until_scroll(false);
+    assert_eq!(half_glyphs(&buffer), [6, ligated[1]]);
+
+    buffer.set_text(
+        text,
+        Attrs::new().font_features(NO_LIGATURES),
+        Shaping::Advanced,
+    );
+    assert_eq!(half_glyphs(&buffer), [6, 6]);
+}
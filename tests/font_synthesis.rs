@@ -0,0 +1,76 @@
+use cosmic_text::{
+    fontdb, Attrs, Buffer, CacheKeyFlags, FontSystem, Metrics, Shaping, SwashCache, Weight,
+};
+
+/// Create a font system with only a regular face of a system font
+fn regular_font_system() -> FontSystem {
+    let mut system_fonts = FontSystem::new();
+    let id = system_fonts
+        .db()
+        .faces()
+        .find(|face| face.weight == Weight::NORMAL && face.style == fontdb::Style::Normal)
+        .unwrap()
+        .id;
+    let data = system_fonts.get_font(id).unwrap().data().to_vec();
+
+    let mut font_system = FontSystem::new_with_locale_and_db("en-US".into(), Default::default());
+    font_system.load_font_data(data).unwrap();
+    font_system
+}
+
+fn shape_flags(font_system: &mut FontSystem, attrs: Attrs) -> Vec<CacheKeyFlags> {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(font_system, "bold", attrs, Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+    run.glyphs
+        .iter()
+        .map(|glyph| glyph.cache_key_flags)
+        .collect()
+}
+
+#[test]
+fn font_synthesis_fake_bold() {
+    let mut font_system = regular_font_system();
+    let family = font_system.db().faces().next().unwrap().families[0]
+        .0
+        .clone();
+    let bold = Attrs::new()
+        .family(fontdb::Family::Name(&family))
+        .weight(Weight::BOLD);
+
+    // Disabled by default
+    assert!(!font_system.font_synthesis());
+    assert!(shape_flags(&mut font_system, bold)
+        .iter()
+        .all(|flags| flags.is_empty()));
+    assert!(font_system
+        .get_font_matches(bold)
+        .iter()
+        .all(|key| key.synthesis().is_empty()));
+
+    font_system.set_font_synthesis(true);
+    assert!(font_system
+        .get_font_matches(bold)
+        .iter()
+        .all(|key| key.synthesis() == CacheKeyFlags::FAKE_BOLD));
+    let flags = shape_flags(&mut font_system, bold);
+    assert!(flags.iter().all(|flags| *flags == CacheKeyFlags::FAKE_BOLD));
+    assert!(shape_flags(&mut font_system, bold.weight(Weight::NORMAL))
+        .iter()
+        .all(|flags| flags.is_empty()));
+
+    // Emboldened glyphs are wider
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(32.0, 40.0));
+    buffer.set_text(&mut font_system, "l", bold, Shaping::Advanced);
+    let glyph = buffer.layout_runs().next().unwrap().glyphs[0].clone();
+    let mut cache_key = glyph.physical((0.0, 0.0), 1.0).cache_key;
+    let mut swash_cache = SwashCache::new();
+    let fake_bold = swash_cache
+        .get_image_uncached(&mut font_system, cache_key)
+        .unwrap();
+    cache_key.flags = CacheKeyFlags::empty();
+    let regular = swash_cache
+        .get_image_uncached(&mut font_system, cache_key)
+        .unwrap();
+    assert!(fake_bold.placement.width > regular.placement.width);
+}
@@ -0,0 +1,74 @@
+use cosmic_text::{
+    Attrs, AttrsList, Buffer, FontSystem, FontVariation, FontVariationsId, Metrics, Shaping,
+};
+
+const CONDENSED: &[FontVariation] = &[FontVariation::new(b"wdth", 75.0)];
+
+#[test]
+fn font_variations_split_shaping() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    let text = "office";
+
+    buffer.set_text(text, Attrs::new(), Shaping::Advanced);
+    let ligated = buffer.layout_runs().next().unwrap().glyphs.len();
+    assert!(ligated < text.len(), "the font has no ligatures");
+
+    // The variations of "of" make it a different instance of the font, so the "ffi" ligature is
+    // broken up
+    let mut attrs_list = AttrsList::new(Attrs::new());
+    attrs_list.add_span(0..2, Attrs::new().variations(CONDENSED));
+    buffer.lines[0].set_attrs_list(attrs_list);
+    buffer.shape_until_scroll(false);
+    let run = buffer.layout_runs().next().unwrap();
+    assert!(run.glyphs.len() > ligated);
+}
+
+#[test]
+fn font_variations_of_static_fonts_are_empty() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "wide",
+        Attrs::new().variations(CONDENSED),
+        Shaping::Advanced,
+    );
+
+    // The default font has no `wdth` axis, so the glyphs use its default instance
+    let run = buffer.layout_runs().next().unwrap();
+    for glyph in run.glyphs.iter() {
+        assert_eq!(glyph.font_variations, FontVariationsId::default());
+        let physical = glyph.physical((0.0, 0.0), 1.0);
+        assert_eq!(physical.cache_key.font_variations, glyph.font_variations);
+    }
+    assert!(font_system
+        .font_variations(FontVariationsId::default())
+        .is_empty());
+}
+
+#[test]
+fn named_instances_of_static_fonts_are_empty() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    let text = "office";
+
+    buffer.set_text(text, Attrs::new(), Shaping::Advanced);
+    let ligated = buffer.layout_runs().next().unwrap().glyphs.len();
+
+    // The instance of "of" is not found, but it is still shaped separately
+    let mut attrs_list = AttrsList::new(Attrs::new());
+    attrs_list.add_span(0..2, Attrs::new().named_instance("Condensed SemiBold"));
+    buffer.lines[0].set_attrs_list(attrs_list);
+    buffer.shape_until_scroll(false);
+    let run = buffer.layout_runs().next().unwrap();
+    assert!(run.glyphs.len() > ligated);
+    let font_id = run.glyphs[0].font_id;
+    for glyph in run.glyphs.iter() {
+        assert_eq!(glyph.font_variations, FontVariationsId::default());
+    }
+
+    assert!(font_system.named_instances(font_id).is_empty());
+}
@@ -0,0 +1,44 @@
+use cosmic_text::{Attrs, Buffer, Family, FontSystem, Metrics, Shaping, Weight};
+
+#[test]
+fn glyphs_carry_font_weight() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "Semi",
+        Attrs::new().family(Family::SansSerif).weight(Weight(450)),
+        Shaping::Advanced,
+    );
+    buffer.shape_until_scroll(&mut font_system, false);
+
+    // Only fonts with a `wght` axis apply the weight, others have one cache key for all weights
+    let run = buffer.layout_runs().next().unwrap();
+    for glyph in run.glyphs.iter() {
+        let font = font_system.get_font(glyph.font_id).unwrap();
+        let weight = if font.weight_axis().is_some() {
+            Weight(450)
+        } else {
+            Weight::NORMAL
+        };
+        assert_eq!(glyph.font_weight, weight);
+        assert_eq!(
+            glyph.physical((0.0, 0.0), 1.0).cache_key.font_weight,
+            weight
+        );
+    }
+}
+
+#[test]
+fn font_matches_by_weight() {
+    let mut font_system = FontSystem::new();
+    let attrs = Attrs::new().family(Family::SansSerif);
+
+    let normal = font_system.get_font_matches(attrs);
+    let bold = font_system.get_font_matches(attrs.weight(Weight::BOLD));
+    assert_eq!(normal.len(), bold.len());
+
+    // Cached results are reused
+    let normal_again = font_system.get_font_matches(attrs.color(cosmic_text::Color::rgb(1, 2, 3)));
+    assert!(std::sync::Arc::ptr_eq(&normal, &normal_again));
+}
@@ -0,0 +1,51 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping};
+
+#[test]
+fn glyph_metrics_cover_outlines() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(20.0, 30.0));
+    buffer.set_text(&mut font_system, "Ag x", Attrs::new(), Shaping::Advanced);
+    buffer.shape_until_scroll(&mut font_system, false);
+
+    let run = buffer.layout_runs().next().unwrap();
+    let metrics = run.glyph_metrics(&mut font_system);
+    assert_eq!(metrics.len(), run.glyphs.len());
+
+    for (glyph, metrics) in run.glyphs.iter().zip(metrics.iter()) {
+        assert_eq!(
+            (metrics.start, metrics.end),
+            (glyph.start as usize, glyph.end as usize)
+        );
+        assert_eq!(metrics.origin.1, run.line_y);
+        assert_eq!(metrics.advance.y, run.line_top);
+        assert_eq!(metrics.advance.height, run.line_height);
+        assert!((metrics.advance.width - glyph.w).abs() < 0.01);
+
+        // The bounds of the glyph are the same, relative to the baseline
+        let ink_opt = glyph.ink_rect(&mut font_system);
+        assert_eq!(ink_opt.is_some(), metrics.ink_opt.is_some());
+        if let (Some(ink), Some(run_ink)) = (ink_opt, metrics.ink_opt) {
+            assert!((ink.y + run.line_y - run_ink.y).abs() < 0.01);
+        }
+    }
+
+    // Spaces have no outline
+    assert_eq!(metrics[2].ink_opt, None);
+
+    // Capitals rise above the baseline, descenders fall below it
+    let a = metrics[0].ink_opt.unwrap();
+    assert!(
+        a.y < run.line_y && a.y + a.height <= run.line_y + 0.5,
+        "{a:?}"
+    );
+    assert!(a.y >= run.line_top, "{a:?}");
+    let g = metrics[1].ink_opt.unwrap();
+    assert!(g.y + g.height > run.line_y, "{g:?}");
+    assert!(g.x >= metrics[1].advance.x - 1.0);
+    assert!(g.x + g.width <= metrics[1].advance.x + metrics[1].advance.width + 1.0);
+
+    // The advance box is one em above the baseline of the glyph
+    let advance = run.glyphs[0].advance_rect();
+    assert_eq!(advance.y, -20.0);
+    assert_eq!(advance.height, 20.0);
+}
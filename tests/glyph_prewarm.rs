@@ -0,0 +1,57 @@
+#![cfg(feature = "swash")]
+
+use cosmic_text::{Attrs, Buffer, Color, FontSystem, Metrics, Shaping, SwashCache};
+
+fn buffer(font_system: &mut FontSystem) -> Buffer {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, Some(200.0), Some(40.0));
+    buffer.set_text(
+        font_system,
+        "abc abc\ndef\nghi not visible",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+    buffer.shape_until_scroll(font_system, false);
+    buffer
+}
+
+fn drawn_glyphs(font_system: &mut FontSystem, buffer: &Buffer, cache: &mut SwashCache) -> usize {
+    buffer.draw(font_system, cache, Color::rgb(0, 0, 0), |_, _, _, _, _| {});
+    cache.image_cache.len()
+}
+
+#[test]
+fn prewarm_rasterizes_visible_glyphs() {
+    let mut font_system = FontSystem::new();
+    let buffer = buffer(&mut font_system);
+    let drawn = drawn_glyphs(&mut font_system, &buffer, &mut SwashCache::new());
+    // Only the first two lines are visible
+    assert!(drawn < "abcabcdefghinotvisible".len());
+
+    let mut cache = SwashCache::new();
+    assert_eq!(cache.prewarm(&mut font_system, &buffer), drawn);
+    assert_eq!(cache.prewarm(&mut font_system, &buffer), 0);
+
+    // Drawing needs no more glyphs
+    assert_eq!(drawn_glyphs(&mut font_system, &buffer, &mut cache), drawn);
+}
+
+#[test]
+fn prewarm_in_background() {
+    let mut font_system = FontSystem::new();
+    let buffer = buffer(&mut font_system);
+    let mut cache = SwashCache::new();
+    cache.prewarm(&mut font_system, &buffer);
+    let warm = cache.image_cache.clone();
+
+    let mut cache = SwashCache::new();
+    let handle = cache.prewarm_in_background(&mut font_system, &buffer);
+    assert_eq!(cache.finish_prewarm(handle), warm.len());
+    for (cache_key, image) in warm.iter() {
+        let background = &cache.image_cache[cache_key];
+        assert_eq!(
+            image.as_ref().map(|image| &image.data),
+            background.as_ref().map(|image| &image.data)
+        );
+    }
+}
@@ -0,0 +1,38 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Scroll, Shaping, Wrap};
+
+#[test]
+fn hit_view_accounts_for_scroll() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_wrap(Wrap::Word);
+    buffer.set_size(Some(60.0), Some(100.0));
+    buffer.set_text("one two three four\nfive", Attrs::new(), Shaping::Advanced);
+    buffer.shape_until_scroll(false);
+
+    // The second visual line of the first text line
+    let (cursor, layout_i) = buffer.hit_view(1.0, 25.0).expect("no hit");
+    assert_eq!(cursor.line, 0);
+    assert_eq!(layout_i, 1);
+    assert!(cursor.index > 0);
+
+    // With horizontal scroll, view positions are offset from layout positions
+    let glyph_x = buffer
+        .layout_runs()
+        .next()
+        .and_then(|run| run.glyphs.get(1).map(|glyph| glyph.x + 1.0))
+        .expect("no glyph");
+    let mut scroll = buffer.scroll();
+    scroll.horizontal = glyph_x;
+    buffer.set_scroll(scroll);
+    let (cursor, layout_i) = buffer.hit_view(0.0, 5.0).expect("no hit");
+    assert_eq!((cursor.line, cursor.index, layout_i), (0, 1, 0));
+    assert_eq!(buffer.hit(glyph_x, 5.0), Some(cursor));
+
+    // Vertical scroll moves the first visible line to the top of the view
+    buffer.set_size(Some(60.0), Some(20.0));
+    buffer.set_scroll(Scroll::new(1, 0.0, 0.0));
+    buffer.shape_until_scroll(false);
+    let (cursor, layout_i) = buffer.hit_view(0.0, 5.0).expect("no hit");
+    assert_eq!((cursor.line, layout_i), (1, 0));
+}
@@ -0,0 +1,60 @@
+use cosmic_text::{Attrs, Buffer, Cursor, FontSystem, Metrics, Scroll, Shaping, Wrap};
+
+fn single_line(font_system: &mut FontSystem, text: &str) -> Buffer {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_wrap(font_system, Wrap::None);
+    buffer.set_size(font_system, Some(100.0), Some(20.0));
+    buffer.set_text(font_system, text, Attrs::new(), Shaping::Advanced);
+    buffer
+}
+
+#[test]
+fn rtl_lines_scroll_from_the_right() {
+    let mut font_system = FontSystem::new();
+    let text = "שלום עולם ".repeat(5);
+    let mut buffer = single_line(&mut font_system, &text);
+
+    // The start of the line is shown at the right edge without scrolling
+    let run = buffer.layout_runs().next().unwrap();
+    assert!(run.rtl);
+    let first = run.glyphs.iter().find(|glyph| glyph.start == 0).unwrap();
+    assert!((first.x + first.w - 100.0).abs() < 0.01);
+    let range = buffer.horizontal_scroll_range();
+    assert!(range.start < -100.0, "{range:?}");
+    assert_eq!(range.end, 0.0);
+
+    // Scrolling to the end of the line scrolls left
+    let end = Cursor::new(0, text.len());
+    buffer.shape_until_cursor(&mut font_system, end, false);
+    let left = buffer.scroll().horizontal;
+    assert!(left < 0.0 && left >= range.start, "{left}");
+
+    // And back to the start
+    buffer.shape_until_cursor(&mut font_system, Cursor::new(0, 0), false);
+    assert!(buffer.scroll().horizontal > left);
+
+    // Scroll past the content is clamped when the cursor moves
+    buffer.set_scroll(buffer.scroll().horizontal_start());
+    buffer.set_text(&mut font_system, "שלום", Attrs::new(), Shaping::Advanced);
+    buffer.set_scroll(Scroll::new(0, 0.0, -500.0));
+    buffer.shape_until_cursor(&mut font_system, Cursor::new(0, 0), false);
+    assert_eq!(buffer.scroll().horizontal, 0.0);
+}
+
+#[test]
+fn ltr_lines_scroll_from_the_left() {
+    let mut font_system = FontSystem::new();
+    let text = "hello world ".repeat(5);
+    let mut buffer = single_line(&mut font_system, &text);
+    let range = buffer.horizontal_scroll_range();
+    assert_eq!(range.start, 0.0);
+    assert!(range.end > 100.0, "{range:?}");
+
+    buffer.shape_until_cursor(&mut font_system, Cursor::new(0, text.len()), false);
+    let right = buffer.scroll().horizontal;
+    assert!(right > 0.0 && right <= range.end, "{right}");
+
+    buffer.set_scroll(Scroll::new(0, 0.0, 500.0));
+    buffer.shape_until_cursor(&mut font_system, Cursor::new(0, text.len()), false);
+    assert_eq!(buffer.scroll().horizontal, range.end);
+}
@@ -0,0 +1,54 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Indent, LayoutLine, Metrics, Shaping, Wrap};
+
+fn indented_layout(text: &str, width_opt: Option<f32>, indent: Indent) -> Vec<LayoutLine> {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_wrap(Wrap::Word);
+    buffer.set_size(width_opt, None);
+    buffer.set_text(text, Attrs::new(), Shaping::Advanced);
+    assert!(buffer.lines[0].set_indent(indent));
+    assert!(!buffer.lines[0].set_indent(indent));
+    buffer.line_layout(0).expect("no layout").to_vec()
+}
+
+fn extents(line: &LayoutLine) -> (f32, f32) {
+    let left = line.glyphs.iter().map(|g| g.x).fold(f32::MAX, f32::min);
+    let right = line.glyphs.iter().map(|g| g.x + g.w).fold(0.0, f32::max);
+    (left, right)
+}
+
+#[test]
+fn indent_moves_first_and_wrapped_lines() {
+    let text = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor";
+    let indent = Indent::new(30.0, 10.0);
+    let layout = indented_layout(text, Some(200.0), indent);
+    assert!(layout.len() > 2);
+    for (i, line) in layout.iter().enumerate() {
+        let (left, right) = extents(line);
+        assert!((left - indent.line(i)).abs() < 0.01, "{i}: {left}");
+        assert!(right <= 200.0 + 0.01, "{i}: {right}");
+        assert!(line.w <= 200.0 - indent.line(i) + 0.01);
+    }
+
+    // Without a width, lines are only moved
+    let layout = indented_layout(text, None, indent);
+    assert_eq!(layout.len(), 1);
+    assert!((extents(&layout[0]).0 - 30.0).abs() < 0.01);
+}
+
+#[test]
+fn indent_starts_at_right_edge_of_rtl_lines() {
+    let text = "بسم الله الرحمن الرحيم بسم الله الرحمن الرحيم بسم الله الرحمن الرحيم";
+    let indent = Indent::new(30.0, 10.0);
+    let layout = indented_layout(text, Some(200.0), indent);
+    assert!(layout.len() > 2);
+    for (i, line) in layout.iter().enumerate() {
+        let (left, right) = extents(line);
+        assert!(
+            (right - (200.0 - indent.line(i))).abs() < 0.01,
+            "{i}: {right}"
+        );
+        assert!(left >= -0.01, "{i}: {left}");
+    }
+}
@@ -0,0 +1,185 @@
+use cosmic_text::{
+    Align, Attrs, Buffer, FontSystem, JustifyMode, LayoutLine, Metrics, Shaping, Wrap,
+};
+
+#[test]
+fn justified_lines_expose_expansion() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_wrap(Wrap::Word);
+    buffer.set_size(Some(200.0), None);
+    buffer.set_text(
+        "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+    buffer.lines[0].set_align(Some(Align::Justified));
+    let layout = buffer.line_layout(0).expect("no layout").to_vec();
+    assert!(layout.len() > 1);
+
+    let first = &layout[0];
+    assert!(first.spaces > 0);
+    assert!(first.justification_expansion > 0.0);
+    assert!((first.w - 200.0).abs() < 0.5);
+
+    // The last line of a paragraph is not justified
+    let last = layout.last().unwrap();
+    assert_eq!(last.justification_expansion, 0.0);
+
+    // Removing the expansion from the blanks gives the natural width
+    buffer.lines[0].set_align(None);
+    let natural = buffer.line_layout(0).expect("no layout")[0].w;
+    assert_eq!(
+        buffer.lines[0].layout_opt().unwrap()[0].justification_expansion,
+        0.0
+    );
+    let blank_glyphs = first
+        .glyphs
+        .iter()
+        .filter(|glyph| {
+            buffer.lines[0].text()[glyph.range()]
+                .chars()
+                .all(char::is_whitespace)
+        })
+        .count();
+    let unexpanded = first.w - first.justification_expansion * blank_glyphs as f32;
+    assert!((unexpanded - natural).abs() < 0.5);
+}
+
+fn justified_layout(text: &str, justify_mode: JustifyMode) -> Vec<LayoutLine> {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_wrap(Wrap::Word);
+    buffer.set_size(Some(200.0), None);
+    buffer.set_justify_mode(justify_mode);
+    buffer.set_text(text, Attrs::new(), Shaping::Advanced);
+    buffer.lines[0].set_align(Some(Align::Justified));
+    buffer.line_layout(0).expect("no layout").to_vec()
+}
+
+#[test]
+fn inter_character_justification_expands_clusters() {
+    let text = "Lorem ipsum dolor sit amet, consectetur adipiscing elit";
+    let natural = justified_layout(text, JustifyMode::InterWord);
+    let layout = justified_layout(text, JustifyMode::InterCharacter);
+    assert!(layout.len() > 1);
+    let first = &layout[0];
+    assert_eq!(first.justification_expansion, 0.0);
+    assert!((first.w - 200.0).abs() < 0.5, "{}", first.w);
+
+    // Glyphs stay adjacent, each widened by the same amount except the last
+    let expansion = first.glyphs[0].w - natural[0].glyphs[0].w;
+    assert!(expansion > 0.0);
+    for pair in first.glyphs.windows(2) {
+        assert!((pair[0].x + pair[0].w - pair[1].x).abs() < 0.01);
+    }
+    for (glyph, natural_glyph) in first
+        .glyphs
+        .iter()
+        .zip(natural[0].glyphs.iter())
+        .rev()
+        .skip(1)
+    {
+        let natural_w = natural_glyph.w
+            - if glyph.start as usize + 1 == glyph.end as usize
+                && text.as_bytes()[glyph.start as usize] == b' '
+            {
+                natural[0].justification_expansion
+            } else {
+                0.0
+            };
+        assert!((glyph.w - natural_w - expansion).abs() < 0.01);
+    }
+}
+
+#[test]
+fn kashida_justification_inserts_tatweels() {
+    let mut font_system = FontSystem::new();
+    let tatweel = {
+        let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+        buffer.set_text(&mut font_system, "\u{640}", Attrs::new(), Shaping::Advanced);
+        buffer.layout_runs().next().unwrap().glyphs[0].glyph_id
+    };
+
+    let text = "بسم الله الرحمن الرحيم بسم الله الرحمن الرحيم";
+    let layout = justified_layout(text, JustifyMode::Kashida);
+    assert!(layout.len() > 1);
+    let first = &layout[0];
+    let left = first
+        .glyphs
+        .iter()
+        .map(|glyph| glyph.x)
+        .fold(f32::MAX, f32::min);
+    assert!(left.abs() < 0.5, "{left}");
+
+    // Each word is lengthened at one joint by whole tatweels, after seen in the first word
+    let tatweels: Vec<_> = first
+        .glyphs
+        .iter()
+        .filter(|glyph| glyph.glyph_id == tatweel)
+        .collect();
+    assert!(!tatweels.is_empty());
+    assert_eq!(tatweels[0].start as usize, "ب".len());
+    let mut joints: Vec<_> = tatweels.iter().map(|glyph| glyph.start as usize).collect();
+    joints.dedup();
+    for joint in joints.iter() {
+        let count = tatweels
+            .iter()
+            .filter(|glyph| glyph.start as usize == *joint)
+            .count();
+        assert!(count <= 3, "{count}");
+    }
+    assert!(tatweels
+        .iter()
+        .all(|glyph| (glyph.w - tatweels[0].w).abs() < 0.01));
+    assert!(joints
+        .windows(2)
+        .all(|pair| text[pair[0]..pair[1]].contains(' ')));
+
+    // The rest of the width expands the spaces
+    assert!(first.justification_expansion > 0.0);
+
+    // Lines without spaces stretch the tatweels to fill the line
+    let word = "بسمالله".repeat(3);
+    let layout = justified_layout(&[word.as_str(); 3].join(" "), JustifyMode::Kashida);
+    assert!(layout.len() > 1);
+    assert_eq!(layout[0].justification_expansion, 0.0);
+    let right = layout[0]
+        .glyphs
+        .iter()
+        .map(|glyph| glyph.x + glyph.w)
+        .fold(0.0, f32::max);
+    let left = layout[0]
+        .glyphs
+        .iter()
+        .map(|glyph| glyph.x)
+        .fold(f32::MAX, f32::min);
+    assert!((right - left - 200.0).abs() < 0.5, "{left} {right}");
+
+    // Spaces are expanded by default, and selecting Kashida later finds the joints
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    assert_eq!(buffer.justify_mode(), JustifyMode::InterWord);
+    buffer.set_wrap(Wrap::Word);
+    buffer.set_size(Some(200.0), None);
+    buffer.set_text(text, Attrs::new(), Shaping::Advanced);
+    buffer.lines[0].set_align(Some(Align::Justified));
+    let has_tatweels = |layout: &[LayoutLine]| {
+        layout[0]
+            .glyphs
+            .iter()
+            .any(|glyph| glyph.glyph_id == tatweel)
+    };
+    assert!(!has_tatweels(buffer.line_layout(0).unwrap()));
+    buffer.set_justify_mode(JustifyMode::Kashida);
+    assert!(has_tatweels(buffer.line_layout(0).unwrap()));
+
+    // Text without Arabic joints expands spaces instead
+    let layout = justified_layout(
+        "Lorem ipsum dolor sit amet, consectetur adipiscing elit",
+        JustifyMode::Kashida,
+    );
+    assert!(layout[0].justification_expansion > 0.0);
+}
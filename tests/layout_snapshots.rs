@@ -0,0 +1,167 @@
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping, TrailingWhitespace, Wrap};
+
+/// A layout snapshot test case.
+/// The text is laid out with [`FontSystem::new_mock`], where every glyph is 5px wide at the 10px
+/// font size used, so the snapshots are the same on every platform. The visual lines, with the byte
+/// range of the text they show, their widths, and the start of each glyph in visual order, are
+/// compared to the snapshot with the name `name` under the `tests/snapshots` directory in this
+/// repository.
+/// NOTE: if an environment variable `GENERATE_SNAPSHOTS` is set, the test will create and save
+/// the snapshots instead.
+#[derive(Debug)]
+struct SnapshotCfg {
+    name: &'static str,
+    text: &'static str,
+    wrap: Wrap,
+    width: f32,
+    trailing_whitespace: TrailingWhitespace,
+}
+
+impl SnapshotCfg {
+    fn new(name: &'static str, text: &'static str) -> Self {
+        Self {
+            name,
+            text,
+            wrap: Wrap::Word,
+            width: 38.0,
+            trailing_whitespace: TrailingWhitespace::Collapse,
+        }
+    }
+
+    fn wrap(mut self, wrap: Wrap) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    fn width(mut self, width: f32) -> Self {
+        self.width = width;
+        self
+    }
+
+    fn trailing_whitespace(mut self, trailing_whitespace: TrailingWhitespace) -> Self {
+        self.trailing_whitespace = trailing_whitespace;
+        self
+    }
+
+    fn snapshot(&self) -> String {
+        let mut font_system = FontSystem::new_mock();
+        let mut buffer = Buffer::new(&mut font_system, Metrics::new(10.0, 20.0));
+        let mut buffer = buffer.borrow_with(&mut font_system);
+        buffer.set_wrap(self.wrap);
+        buffer.set_size(Some(self.width), None);
+        buffer.set_trailing_whitespace(self.trailing_whitespace);
+        buffer.set_text(self.text, Attrs::new(), Shaping::Advanced);
+
+        let mut snapshot = String::new();
+        writeln!(snapshot, "text: {:?}", self.text).unwrap();
+        writeln!(
+            snapshot,
+            "wrap: {:?}, width: {}, trailing whitespace: {:?}",
+            self.wrap, self.width, self.trailing_whitespace
+        )
+        .unwrap();
+        for run in buffer.layout_runs() {
+            let start = run.glyphs.iter().map(|glyph| glyph.start).min();
+            let end = run.glyphs.iter().map(|glyph| glyph.end).max();
+            let starts: Vec<_> = run
+                .glyphs
+                .iter()
+                .map(|glyph| glyph.start.to_string())
+                .collect();
+            writeln!(
+                snapshot,
+                "line {}.{}: {}..{} w={} {} [{}]",
+                run.line_i,
+                run.layout_i,
+                start.unwrap_or_default(),
+                end.unwrap_or_default(),
+                run.line_w,
+                if run.rtl { "rtl" } else { "ltr" },
+                starts.join(" ")
+            )
+            .unwrap();
+        }
+        snapshot
+    }
+
+    fn validate_snapshot(&self) {
+        let snapshot = self.snapshot();
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("snapshots")
+            .join(format!("{}.txt", self.name));
+
+        let generate_snapshots = std::env::var("GENERATE_SNAPSHOTS")
+            .map(|v| {
+                let val = v.trim().to_ascii_lowercase();
+                ["t", "true", "1"].iter().any(|&v| v == val)
+            })
+            .unwrap_or_default();
+
+        if generate_snapshots {
+            std::fs::write(path, snapshot).unwrap();
+        } else {
+            let reference = std::fs::read_to_string(&path).unwrap();
+            assert_eq!(
+                snapshot,
+                reference,
+                "layout of {} does not match {}",
+                self.name,
+                path.display()
+            );
+        }
+    }
+}
+
+#[test]
+fn snapshot_wrap_words() {
+    SnapshotCfg::new("wrap_words", "aaa bbb ccc ddd").validate_snapshot();
+}
+
+#[test]
+fn snapshot_wrap_trailing_blanks() {
+    // Runs of blanks at a wrap and at the end of the paragraph
+    SnapshotCfg::new("wrap_trailing_blanks_collapse", "aaa    bbb   ").validate_snapshot();
+    SnapshotCfg::new("wrap_trailing_blanks_hang", "aaa    bbb   ")
+        .trailing_whitespace(TrailingWhitespace::Hang)
+        .validate_snapshot();
+    SnapshotCfg::new("wrap_trailing_blanks_include", "aaa    bbb   ")
+        .trailing_whitespace(TrailingWhitespace::Include)
+        .validate_snapshot();
+}
+
+#[test]
+fn snapshot_wrap_word_or_glyph() {
+    // Words longer than the line fall back to wrapping between glyphs, others wrap as words
+    SnapshotCfg::new("wrap_word_or_glyph", "aaaaaaaaaaaa bb ccccccccc")
+        .wrap(Wrap::WordOrGlyph)
+        .validate_snapshot();
+    SnapshotCfg::new("wrap_word_overflow", "aaaaaaaaaaaa bb ccccccccc").validate_snapshot();
+    SnapshotCfg::new("wrap_glyph", "aaa bbb ccc")
+        .wrap(Wrap::Glyph)
+        .validate_snapshot();
+    // Narrower than a single glyph
+    SnapshotCfg::new("wrap_word_or_glyph_narrow", "abc")
+        .wrap(Wrap::WordOrGlyph)
+        .width(3.0)
+        .validate_snapshot();
+}
+
+#[test]
+fn snapshot_wrap_bidi() {
+    SnapshotCfg::new("wrap_bidi_ltr", "abc אבג דהו def").validate_snapshot();
+    SnapshotCfg::new("wrap_bidi_rtl", "אבג abc def דהו").validate_snapshot();
+}
+
+#[test]
+fn snapshot_wrap_paragraphs() {
+    SnapshotCfg::new("wrap_paragraphs", "aaa bbb\n\n中文中文中文")
+        .wrap(Wrap::WordOrGlyph)
+        .validate_snapshot();
+    SnapshotCfg::new("wrap_none", "aaa bbb ccc\nddd")
+        .wrap(Wrap::None)
+        .validate_snapshot();
+}
@@ -0,0 +1,31 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, LineEnding, Metrics, Shaping};
+
+#[test]
+fn buffer_line_endings() {
+    let mut font_system = FontSystem::new_mock();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(10.0, 20.0));
+    buffer.set_split_paragraphs(true);
+    buffer.set_text(
+        &mut font_system,
+        "a\r\nb\nc\r\nd\u{85}e",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+
+    let endings: Vec<_> = buffer.line_endings().collect();
+    assert_eq!(
+        endings,
+        [
+            (0, LineEnding::CrLf),
+            (1, LineEnding::Lf),
+            (2, LineEnding::CrLf),
+            (3, LineEnding::Nel),
+            (4, LineEnding::None),
+        ]
+    );
+
+    let stats = buffer.line_ending_stats();
+    assert_eq!((stats.crlf, stats.lf, stats.nel, stats.none), (2, 1, 1, 1));
+    assert_eq!(stats.dominant(), Some(LineEnding::CrLf));
+    assert!(stats.is_mixed());
+}
@@ -0,0 +1,46 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, LineHeight, Metrics, Shaping};
+
+fn line_heights(buffer: &Buffer) -> Vec<f32> {
+    buffer.layout_runs().map(|run| run.line_height).collect()
+}
+
+#[test]
+fn metrics_with_line_height() {
+    let metrics = Metrics::with_line_height(20.0, LineHeight::Proportional(1.5));
+    assert_eq!(metrics, Metrics::new(20.0, 30.0));
+    let metrics = Metrics::with_line_height(20.0, LineHeight::Absolute(24.0));
+    assert_eq!(metrics, Metrics::new(20.0, 24.0));
+    assert_eq!(LineHeight::from(24.0), LineHeight::Absolute(24.0));
+    assert_ne!(LineHeight::Absolute(2.0), LineHeight::Proportional(2.0));
+}
+
+#[test]
+fn attrs_line_height_resolved_at_layout() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+
+    // Proportional line heights follow the font size of the buffer
+    let attrs = Attrs::new().line_height(LineHeight::Proportional(2.0));
+    buffer.set_text("a\n\nb", attrs, Shaping::Advanced);
+    buffer.shape_until_scroll(false);
+    assert_eq!(line_heights(&buffer), [28.0, 28.0, 28.0]);
+    buffer.set_metrics(Metrics::new(20.0, 20.0));
+    buffer.shape_until_scroll(false);
+    assert_eq!(line_heights(&buffer), [40.0, 40.0, 40.0]);
+
+    // And the font size of spans with metrics
+    let attrs = Attrs::new()
+        .metrics(Metrics::new(10.0, 12.0))
+        .line_height(LineHeight::Proportional(1.5));
+    buffer.set_text("a", attrs, Shaping::Advanced);
+    buffer.shape_until_scroll(false);
+    assert_eq!(line_heights(&buffer), [15.0]);
+
+    let attrs = Attrs::new()
+        .metrics(Metrics::new(10.0, 12.0))
+        .line_height(LineHeight::Absolute(30.0));
+    buffer.set_text("a", attrs, Shaping::Advanced);
+    buffer.shape_until_scroll(false);
+    assert_eq!(line_heights(&buffer), [30.0]);
+}
@@ -0,0 +1,36 @@
+use cosmic_text::{fontdb, Attrs, Buffer, FontSystem, Metrics, Shaping};
+
+#[test]
+fn font_system_locales() {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    assert_eq!(font_system.locale(), "en-US");
+    assert_eq!(font_system.locales(), ["en-US"]);
+
+    font_system.set_locales(vec!["ja-JP".into(), "en-US".into()]);
+    assert_eq!(font_system.locale(), "ja-JP");
+
+    // An empty list is ignored
+    font_system.set_locales(Vec::new());
+    assert_eq!(font_system.locales(), ["ja-JP", "en-US"]);
+}
+
+#[test]
+fn buffer_locale() {
+    let mut font_system = FontSystem::new();
+    let locale = font_system.locale().to_string();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_text("漢字\n中文", Attrs::new(), Shaping::Advanced);
+    buffer.shape_until_scroll(false);
+    assert_eq!(buffer.locale(), None);
+
+    // Lines are reshaped with the new locale
+    buffer.set_locale(Some("zh-TW"));
+    assert_eq!(buffer.locale(), Some("zh-TW"));
+    for line in buffer.lines.iter() {
+        assert_eq!(line.locale(), Some("zh-TW"));
+        assert!(line.shape_opt().is_some());
+    }
+    assert_eq!(font_system.locale(), locale);
+}
@@ -0,0 +1,48 @@
+use cosmic_text::{Attrs, AttrsList, Buffer, Color, FontSystem, Metrics, Shaping};
+
+#[test]
+fn minimap_runs_cover_every_line() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    let red = Color::rgb(0xFF, 0x00, 0x00);
+    buffer.set_size(Some(500.0), Some(100.0));
+    let text: Vec<_> = (0..100).map(|i| format!("let x{i} = {i};")).collect();
+    buffer.set_text(&text.join("\n"), Attrs::new(), Shaping::Advanced);
+    let mut attrs_list = AttrsList::new(Attrs::new());
+    attrs_list.add_span(0..3, Attrs::new().color(red).metadata(1));
+    buffer.lines[0].set_attrs_list(attrs_list.clone());
+    buffer.lines[99].set_attrs_list(attrs_list);
+    buffer.shape_until_scroll(false);
+
+    let runs = buffer.minimap_runs(0.1);
+    assert_eq!(runs.len(), 100);
+    assert!(!runs[0].estimated);
+    assert!(runs[99].estimated);
+    for (i, run) in runs.iter().enumerate() {
+        assert_eq!(run.line_i, i);
+        assert!((run.y - i as f32 * 2.0).abs() < 0.01, "{run:?}");
+        assert!((run.h - 2.0).abs() < 0.01, "{run:?}");
+        // "let", "x0", "=", "0;"
+        assert_eq!(run.segments.len(), 4, "{run:?}");
+        assert!(run.segments.windows(2).all(|s| s[0].x + s[0].w < s[1].x));
+    }
+    for run in [&runs[0], &runs[99]] {
+        assert_eq!(run.segments[0].color_opt, Some(red));
+        assert_eq!(run.segments[0].metadata, 1);
+        assert_eq!(run.segments[1].color_opt, None);
+    }
+
+    // Laid out lines use the widths of their glyphs
+    let glyphs = &buffer.layout_runs().next().unwrap().glyphs;
+    let end = glyphs[2].x + glyphs[2].w;
+    assert!((runs[0].segments[0].w - end * 0.1).abs() < 0.01);
+
+    // Only the reshaped line changes
+    buffer.lines[1].set_text("renamed", Default::default(), AttrsList::new(Attrs::new()));
+    buffer.shape_until_scroll(false);
+    let new_runs = buffer.minimap_runs(0.1);
+    assert_eq!(new_runs[1].segments.len(), 1);
+    assert_eq!(new_runs[0], runs[0]);
+    assert_eq!(new_runs[2..], runs[2..]);
+}
@@ -0,0 +1,68 @@
+use cosmic_text::{
+    Action, Attrs, Buffer, Cursor, Edit, Editor, Family, FontSystem, Metrics, MockFont, Motion,
+    Shaping, Wrap,
+};
+
+#[test]
+fn mock_fonts_layout() {
+    let mut font_system = FontSystem::new_mock();
+    assert_eq!(font_system.db().len(), 1);
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(10.0, 20.0));
+    buffer.set_wrap(&mut font_system, Wrap::Word);
+    buffer.set_size(&mut font_system, Some(38.0), None);
+    buffer.set_text(
+        &mut font_system,
+        "abc def 中文",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+
+    // Every glyph has the same advance, so "abc def" fits on the first line of 38px
+    let runs: Vec<_> = buffer.layout_runs().collect();
+    assert_eq!(runs.len(), 2);
+    for glyph in runs.iter().flat_map(|run| run.glyphs.iter()) {
+        assert_eq!(glyph.w, 10.0 * MockFont::ADVANCE);
+    }
+    assert_eq!(runs[0].glyphs.len(), 7);
+    assert_eq!(runs[0].line_w, 35.0);
+    assert_eq!(runs[1].glyphs.len(), 2);
+    assert_eq!(runs[1].line_top, 20.0);
+}
+
+#[test]
+fn mock_fonts_editing() {
+    let mut font_system = FontSystem::new_mock();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(10.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "hello\nworld",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+    let mut editor = Editor::new(buffer);
+
+    editor.set_cursor(Cursor::new(0, 3));
+    editor.action(&mut font_system, Action::Motion(Motion::Down));
+    assert_eq!((editor.cursor().line, editor.cursor().index), (1, 3));
+    editor.action(&mut font_system, Action::Click { x: 26, y: 5 });
+    assert_eq!((editor.cursor().line, editor.cursor().index), (0, 5));
+}
+
+#[test]
+fn mock_fonts_fallback() {
+    let mut font_system = FontSystem::new_mock();
+    let attrs = Attrs::new().family(Family::Monospace);
+    assert_eq!(font_system.get_font_matches(attrs).len(), 1);
+    assert_eq!(font_system.locale(), "en-US");
+
+    // Every script falls back to the synthetic font
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(10.0, 20.0));
+    buffer.set_text(&mut font_system, "héllo 😀 中文", attrs, Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+    for glyph in run.glyphs.iter() {
+        let face = font_system.db().face(glyph.font_id).unwrap();
+        assert_eq!(face.families[0].0, MockFont::FAMILY);
+        assert!(face.monospaced);
+    }
+}
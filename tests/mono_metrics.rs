@@ -0,0 +1,51 @@
+use cosmic_text::{fontdb::Family, Attrs, Buffer, FontSystem, Metrics, MockFont, Shaping};
+
+#[test]
+fn snap_mono_metrics_integer_cells() {
+    let mut font_system = FontSystem::new_mock();
+    let family = Family::Name(MockFont::FAMILY);
+
+    // The advance of 7.5px is rounded to 8px
+    let mono = font_system.snap_mono_metrics(family, 15.0, 1.0).unwrap();
+    assert_eq!(mono.font_size, 16.0);
+    assert_eq!(mono.cell_width, 8.0);
+    assert_eq!(mono.cell_height, 16.0);
+
+    for &(font_size, scale_factor) in &[(13.0, 1.5), (12.0, 1.25), (9.0, 2.0), (0.1, 1.0)] {
+        let mono = font_system
+            .snap_mono_metrics(family, font_size, scale_factor)
+            .unwrap();
+        let device_width = mono.cell_width * scale_factor;
+        let device_height = mono.cell_height * scale_factor;
+        assert!((device_width - device_width.round()).abs() < 1e-3);
+        assert!((device_height - device_height.round()).abs() < 1e-3);
+        assert!(device_width >= 1.0);
+        assert!((mono.font_size * MockFont::ADVANCE - mono.cell_width).abs() < 1e-3);
+    }
+
+    assert_eq!(
+        font_system.snap_mono_metrics(Family::Name("Missing"), 15.0, 1.0),
+        None
+    );
+}
+
+#[test]
+fn set_mono_metrics() {
+    let mut font_system = FontSystem::new_mock();
+    let mono = font_system
+        .snap_mono_metrics(Family::Name(MockFont::FAMILY), 13.0, 1.5)
+        .unwrap();
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(10.0, 20.0));
+    buffer.set_mono_metrics(&mut font_system, mono);
+    buffer.set_text(&mut font_system, "abc", Attrs::new(), Shaping::Advanced);
+    assert_eq!(buffer.metrics(), mono.metrics());
+    assert_eq!(buffer.monospace_width(), Some(mono.cell_width));
+
+    let run = buffer.layout_runs().next().unwrap();
+    for (i, glyph) in run.glyphs.iter().enumerate() {
+        assert!((glyph.x - i as f32 * mono.cell_width).abs() < 1e-3);
+        assert!((glyph.w - mono.cell_width).abs() < 1e-3);
+    }
+    assert_eq!(run.line_height, mono.cell_height);
+}
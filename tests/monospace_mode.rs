@@ -0,0 +1,92 @@
+#![cfg(feature = "monospace_fallback")]
+
+use cosmic_text::{
+    Attrs, Buffer, Family, FontSystem, Metrics, MockFont, MonospaceAdjustment, MonospaceMode,
+    Shaping,
+};
+
+fn is_scaled(adjustment: MonospaceAdjustment) -> bool {
+    matches!(adjustment, MonospaceAdjustment::Scaled { .. })
+}
+
+#[test]
+fn monospace_diagnostics_report_scaling() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(10.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_text(
+        "abc",
+        Attrs::new().family(Family::Monospace),
+        Shaping::Advanced,
+    );
+    buffer.shape_until_scroll(false);
+    assert_eq!(buffer.monospace_diagnostics().count(), 0);
+
+    buffer.set_monospace_width(Some(5.0));
+    let diagnostics = buffer.monospace_diagnostics().collect::<Vec<_>>();
+    assert_eq!(diagnostics.len(), 3);
+    for (i, diagnostic) in diagnostics.iter().enumerate() {
+        assert_eq!((diagnostic.line_i, diagnostic.layout_i), (0, 0));
+        let MonospaceAdjustment::Scaled {
+            glyph_i,
+            target_em_width,
+            factor,
+            ..
+        } = diagnostic.adjustment
+        else {
+            panic!("unexpected {:?}", diagnostic.adjustment);
+        };
+        assert_eq!(glyph_i, i);
+        assert_eq!(target_em_width, 0.5);
+        assert!(factor < 1.0);
+        assert_eq!(diagnostic.glyph.font_size, 10.0 * factor);
+    }
+
+    // Without a monospace font of matching width, strict mode still scales
+    buffer.set_monospace_mode(MonospaceMode::Strict);
+    assert_eq!(buffer.monospace_diagnostics().count(), 3);
+    assert!(buffer
+        .monospace_diagnostics()
+        .all(|diagnostic| is_scaled(diagnostic.adjustment)));
+}
+
+#[test]
+fn strict_monospace_substitutes_font() {
+    // The mock font is 0.5 em wide, Fira Mono 0.6 em. Monospace fonts are found when the font
+    // system is created, so Fira Mono is added to the database of the mock font system first
+    let (locale, mut db) = FontSystem::new_mock().into_locale_and_db();
+    let faces = db.len();
+    db.load_font_file("fonts/FiraMono-Medium.ttf").unwrap();
+    // The fonts are stored with Git LFS, run `git lfs pull` to fetch them
+    let fira_id = db
+        .faces()
+        .nth(faces)
+        .map(|face| face.id)
+        .expect("fonts/FiraMono-Medium.ttf is not a font");
+    let mut font_system = FontSystem::new_with_locale_and_db(locale, db);
+    let em_width = font_system
+        .get_font(fira_id)
+        .and_then(|font| font.monospace_em_width())
+        .unwrap();
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(10.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    let attrs = Attrs::new().family(Family::Name(MockFont::FAMILY));
+    buffer.set_text("abc", attrs, Shaping::Advanced);
+    buffer.set_monospace_width(Some(10.0 * em_width));
+    assert!(buffer
+        .monospace_diagnostics()
+        .all(|diagnostic| is_scaled(diagnostic.adjustment) && diagnostic.glyph.font_id != fira_id));
+
+    buffer.set_monospace_mode(MonospaceMode::Strict);
+    let diagnostics = buffer.monospace_diagnostics().collect::<Vec<_>>();
+    assert_eq!(diagnostics.len(), 3);
+    for diagnostic in diagnostics.iter() {
+        assert_eq!(diagnostic.glyph.font_id, fira_id);
+        assert_eq!(diagnostic.glyph.font_size, 10.0);
+        assert!(matches!(
+            diagnostic.adjustment,
+            MonospaceAdjustment::Substituted { from, .. } if from != fira_id
+        ));
+    }
+}
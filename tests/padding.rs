@@ -0,0 +1,73 @@
+use cosmic_text::{
+    Attrs, Buffer, Cursor, Edit, Editor, FontSystem, Insets, Metrics, Shaping, Wrap,
+};
+
+#[test]
+fn padding_layout() {
+    let mut font_system = FontSystem::new_mock();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(10.0, 20.0));
+    buffer.set_wrap(&mut font_system, Wrap::Word);
+    buffer.set_size(&mut font_system, Some(48.0), None);
+    buffer.set_padding(&mut font_system, Insets::new(4.0, 5.0, 6.0, 5.0));
+    buffer.set_text(
+        &mut font_system,
+        "abc def 中文",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+
+    // Lines wrap at the 38px inside the padding
+    let runs: Vec<_> = buffer.layout_runs().collect();
+    assert_eq!(runs.len(), 2);
+    assert_eq!(runs[0].glyphs.len(), 7);
+    assert_eq!(runs[0].glyphs[0].x, 5.0);
+    assert_eq!(runs[0].line_top, 4.0);
+    assert_eq!(runs[1].glyphs[0].x, 5.0);
+    assert_eq!(runs[1].line_top, 24.0);
+
+    // Hit testing uses the same coordinates as the glyphs
+    let hit = |x, y| {
+        let cursor = buffer.hit(x, y).unwrap();
+        (cursor.line, cursor.index)
+    };
+    assert_eq!(hit(6.0, 10.0), (0, 0));
+    assert_eq!(hit(0.0, 10.0), (0, 0));
+    assert_eq!(hit(14.0, 10.0), (0, 2));
+    assert_eq!(hit(11.0, 30.0), (0, 11));
+
+    // Removing the padding lays the text out at the edges again
+    buffer.set_padding(&mut font_system, Insets::default());
+    let runs: Vec<_> = buffer.layout_runs().collect();
+    assert_eq!(runs[0].glyphs[0].x, 0.0);
+    assert_eq!(runs[0].line_top, 0.0);
+}
+
+#[test]
+fn padding_height() {
+    let mut font_system = FontSystem::new_mock();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(10.0, 20.0));
+    buffer.set_size(&mut font_system, Some(100.0), Some(30.0));
+    buffer.set_padding(&mut font_system, Insets::new(4.0, 0.0, 6.0, 0.0));
+    buffer.set_text(&mut font_system, "a\nb\nc", Attrs::new(), Shaping::Advanced);
+
+    // Only one line fits in the 20px between the top and bottom padding
+    let runs: Vec<_> = buffer.layout_runs().collect();
+    assert_eq!(runs.len(), 1);
+    assert_eq!(runs[0].line_top, 4.0);
+    assert_eq!(buffer.scroll_limits(), (0.0, 40.0));
+}
+
+#[test]
+fn padding_editor_cursor() {
+    let mut font_system = FontSystem::new_mock();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(10.0, 20.0));
+    buffer.set_size(&mut font_system, Some(100.0), Some(100.0));
+    buffer.set_padding(&mut font_system, Insets::uniform(8.0));
+    buffer.set_text(&mut font_system, "\nab", Attrs::new(), Shaping::Advanced);
+    let mut editor = Editor::new(buffer);
+
+    // The cursor of an empty line is at the start of the text, inside the padding
+    assert_eq!(editor.cursor_position(), Some((8, 8)));
+    editor.set_cursor(Cursor::new(1, 1));
+    assert_eq!(editor.cursor_position(), Some((13, 28)));
+}
@@ -0,0 +1,70 @@
+use cosmic_text::{
+    Attrs, Buffer, FontSystem, LineEnding, Metrics, ParagraphSpacing, Shaping, Wrap,
+};
+
+fn spaced_buffer(font_system: &mut FontSystem) -> Buffer {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_wrap(font_system, Wrap::Word);
+    buffer.set_size(font_system, Some(150.0), None);
+    buffer.set_text(
+        font_system,
+        "Title\nLorem ipsum dolor sit amet, consectetur adipiscing elit\nEnd",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+    let spacing = ParagraphSpacing::new(10.0, 6.0);
+    assert!(buffer.lines[1].set_spacing(spacing));
+    assert!(!buffer.lines[1].set_spacing(spacing));
+    buffer.shape_until_scroll(font_system, false);
+    buffer
+}
+
+#[test]
+fn spacing_separates_paragraphs() {
+    let mut font_system = FontSystem::new();
+    let buffer = spaced_buffer(&mut font_system);
+
+    let tops: Vec<(usize, f32, f32)> = buffer
+        .layout_runs()
+        .map(|run| (run.line_i, run.line_top, run.line_height))
+        .collect();
+    let wrapped = tops.iter().filter(|(line_i, _, _)| *line_i == 1).count();
+    assert!(wrapped > 1);
+
+    // Space before the first visual line of the paragraph only
+    assert_eq!(tops[0], (0, 0.0, 20.0));
+    assert_eq!(tops[1], (1, 30.0, 20.0));
+    assert_eq!(tops[2], (1, 50.0, 20.0));
+
+    // Space after the last visual line of the paragraph only
+    let last = tops.last().expect("no runs");
+    assert_eq!(last.0, 2);
+    assert_eq!(last.1, 30.0 + wrapped as f32 * 20.0 + 6.0);
+
+    let layout = buffer.lines[1].layout_opt().expect("no layout");
+    assert_eq!(layout[0].height(20.0), 30.0);
+    assert_eq!(layout[1].height(20.0), 20.0);
+    assert_eq!(layout[wrapped - 1].height(20.0), 26.0);
+}
+
+#[test]
+fn spacing_hits_line_above() {
+    let mut font_system = FontSystem::new();
+    let buffer = spaced_buffer(&mut font_system);
+
+    assert_eq!(buffer.hit(0.0, 25.0).map(|cursor| cursor.line), Some(0));
+    assert_eq!(buffer.hit(0.0, 35.0).map(|cursor| cursor.line), Some(1));
+}
+
+#[test]
+fn spacing_is_kept_when_splitting() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = spaced_buffer(&mut font_system);
+    let spacing = buffer.lines[1].spacing();
+    let new = buffer.lines[1].split_off(5);
+    assert_eq!(new.spacing(), spacing);
+
+    let attrs_list = buffer.lines[1].attrs_list().clone();
+    buffer.lines[1].reset_new("text", LineEnding::default(), attrs_list, Shaping::Advanced);
+    assert_eq!(buffer.lines[1].spacing(), ParagraphSpacing::default());
+}
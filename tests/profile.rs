@@ -0,0 +1,59 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, LayoutGlyph, Metrics, Scroll, Shaping};
+
+#[test]
+fn profile_report_records_shaped_lines() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    assert!(!buffer.profiling());
+
+    buffer.set_text("one\ntwo", Attrs::new(), Shaping::Advanced);
+    assert!(buffer.profile_report().lines.is_empty());
+
+    buffer.set_profiling(true);
+    buffer.set_text("short\nmuch longer line", Attrs::new(), Shaping::Advanced);
+    let report = buffer.profile_report();
+    assert_eq!(report.lines.len(), 2);
+    assert_eq!(report.lines[1].line_i, 1);
+    assert_eq!(report.lines[1].len, 16);
+    for entry in report.lines.iter() {
+        assert_eq!(entry.profile.shape_count, 1);
+        assert_eq!(entry.profile.layout_count, 1);
+    }
+    assert_eq!(report.total().shape_count, 2);
+    assert_eq!(report.slowest(1).len(), 1);
+    assert!(report.to_string().starts_with("2 lines: shaped 2 times"));
+
+    // Layout changes are recorded without shaping again
+    buffer.set_size(Some(50.0), None);
+    let report = buffer.profile_report();
+    assert_eq!(report.total().shape_count, 2);
+    assert_eq!(report.total().layout_count, 4);
+
+    buffer.set_profiling(false);
+    assert!(buffer.profile_report().lines.is_empty());
+}
+
+#[test]
+fn memory_usage_drops_when_pruning() {
+    // Guard against glyphs growing, as every character of a shaped buffer has one
+    assert!(core::mem::size_of::<LayoutGlyph>() <= 88);
+
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(None, Some(100.0));
+    let text = "a line of text\n".repeat(20);
+    buffer.set_text(&text, Attrs::new(), Shaping::Advanced);
+    buffer.set_scroll(Scroll::new(10, 0.0, 0.0));
+    buffer.shape_until_scroll(false);
+    let usage = buffer.memory_usage();
+    assert_eq!(usage.shape_glyphs, usage.layout_glyphs);
+    assert_eq!(usage.shape_glyphs, 12 * 14);
+
+    // Lines scrolled out of view free their glyphs
+    buffer.shape_until_scroll(true);
+    let pruned = buffer.memory_usage();
+    assert_eq!(pruned.shape_glyphs, 6 * 14);
+    assert!(pruned.glyph_bytes < usage.glyph_bytes);
+}
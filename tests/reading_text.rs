@@ -0,0 +1,40 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping};
+
+#[test]
+fn reading_text_words_and_rects() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(Some(500.0), Some(500.0));
+    buffer.set_text(
+        "Hello, world\n\u{5e9}\u{5dc}\u{5d5}\u{5dd} \u{5e2}\u{5d5}\u{5dc}\u{5dd}",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+    buffer.shape_until_scroll(false);
+
+    let paragraphs = buffer.reading_text();
+    assert_eq!(paragraphs.len(), 2);
+
+    let ltr = &paragraphs[0];
+    assert!(!ltr.rtl);
+    let words: Vec<&str> = ltr
+        .words
+        .iter()
+        .map(|word| &ltr.text[word.range.clone()])
+        .collect();
+    assert_eq!(words, ["Hello", "world"]);
+    let first = ltr.words[0].rects[0];
+    let second = ltr.words[1].rects[0];
+    assert!(first.x + first.width <= second.x);
+    assert_eq!(first.y, 0.0);
+
+    // Words stay in logical order while their rects follow the visual order
+    let rtl = &paragraphs[1];
+    assert!(rtl.rtl);
+    assert_eq!(rtl.words.len(), 2);
+    let first = rtl.words[0].rects[0];
+    let second = rtl.words[1].rects[0];
+    assert!(second.x + second.width <= first.x);
+    assert_eq!(first.y, 20.0);
+}
@@ -0,0 +1,68 @@
+use cosmic_text::{Align, Attrs, Buffer, FontSystem, Metrics, Shaping, Wrap};
+
+const TEXT: &str = "one two three four five six\nאחת שתיים שלוש ארבע חמש";
+
+fn glyph_xs(buffer: &Buffer) -> Vec<f32> {
+    buffer
+        .layout_runs()
+        .flat_map(|run| run.glyphs.iter().map(|glyph| glyph.x))
+        .collect()
+}
+
+#[test]
+fn realign_matches_layout() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_wrap(Wrap::Word);
+    buffer.set_size(Some(120.0), None);
+    buffer.set_profiling(true);
+    buffer.set_text(TEXT, Attrs::new(), Shaping::Advanced);
+    let layouts = buffer.profile_report().total().layout_count;
+
+    for align in [
+        Some(Align::Right),
+        Some(Align::Center),
+        Some(Align::End),
+        Some(Align::Left),
+        None,
+    ] {
+        buffer.set_align(align);
+        let realigned = glyph_xs(&buffer);
+
+        for line in buffer.lines.iter_mut() {
+            line.reset_layout();
+        }
+        buffer.shape_until_scroll(false);
+        let laid_out = glyph_xs(&buffer);
+        assert_eq!(realigned.len(), laid_out.len());
+        for (a, b) in realigned.iter().zip(laid_out.iter()) {
+            assert!((a - b).abs() < 0.01, "{align:?}: {a} != {b}");
+        }
+    }
+
+    // Only the layouts forced by the test were done
+    let report = buffer.profile_report();
+    assert_eq!(report.total().layout_count, layouts * 6);
+}
+
+#[test]
+fn justified_lines_are_laid_out_again() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_wrap(Wrap::Word);
+    buffer.set_size(Some(120.0), None);
+    buffer.set_text(TEXT, Attrs::new(), Shaping::Advanced);
+
+    buffer.set_align(Some(Align::Justified));
+    let justified = buffer.layout_runs().next().unwrap().line_w;
+    assert!((justified - 120.0).abs() < 0.01, "{justified}");
+
+    buffer.set_align(Some(Align::Center));
+    let run = buffer.layout_runs().next().unwrap();
+    assert!(run.line_w < 120.0);
+    let left = run.glyphs[0].x;
+    let right = 120.0 - run.glyphs.last().map_or(0.0, |glyph| glyph.x + glyph.w);
+    assert!((left - right).abs() < 0.01, "{left} != {right}");
+}
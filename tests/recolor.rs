@@ -0,0 +1,47 @@
+use cosmic_text::{Attrs, AttrsList, Buffer, Color, FontSystem, Metrics, Shaping, Weight};
+
+#[test]
+fn recolor_span_keeps_layout() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(Some(500.0), Some(500.0));
+    buffer.set_text("fade in", Attrs::new(), Shaping::Advanced);
+    buffer.shape_until_scroll(false);
+    buffer.set_redraw(false);
+
+    let red = Color::rgba(0xFF, 0, 0, 0x80);
+    buffer.recolor_span(0, 5..7, red);
+    assert!(buffer.redraw());
+
+    // Shaping and layout were not reset
+    assert!(buffer.lines[0].shape_opt().is_some());
+    assert!(buffer.lines[0].layout_opt().is_some());
+
+    for run in buffer.layout_runs() {
+        for glyph in run.glyphs.iter() {
+            let expected = if glyph.start >= 5 { Some(red) } else { None };
+            assert_eq!(glyph.color_opt, expected);
+        }
+    }
+    assert_eq!(
+        buffer.lines[0].attrs_list().get_span(5).color_opt,
+        Some(red)
+    );
+}
+
+#[test]
+fn attrs_list_set_color() {
+    let mut attrs_list = AttrsList::new(Attrs::new());
+    attrs_list.add_span(2..6, Attrs::new().weight(Weight::BOLD));
+
+    let blue = Color::rgb(0, 0, 0xFF);
+    attrs_list.set_color(0..4, blue);
+    assert_eq!(attrs_list.get_span(0), Attrs::new().color(blue));
+    assert_eq!(
+        attrs_list.get_span(3),
+        Attrs::new().weight(Weight::BOLD).color(blue)
+    );
+    assert_eq!(attrs_list.get_span(4), Attrs::new().weight(Weight::BOLD));
+    assert_eq!(attrs_list.get_span(6), Attrs::new());
+}
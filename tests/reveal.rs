@@ -0,0 +1,61 @@
+use std::time::Duration;
+
+use cosmic_text::{Attrs, Buffer, Cursor, FontSystem, Metrics, Shaping, TextReveal};
+
+#[test]
+fn visible_prefix_clamps_to_graphemes() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(Some(500.0), Some(500.0));
+    buffer.set_text("ae\u{301}\nbc", Attrs::new(), Shaping::Advanced);
+    buffer.shape_until_scroll(false);
+
+    assert_eq!(buffer.visible_prefix(0), Cursor::new(0, 0));
+    assert_eq!(buffer.visible_prefix(2), Cursor::new(1, 0));
+    assert_eq!(buffer.visible_prefix(3), Cursor::new(1, 1));
+    assert_eq!(buffer.visible_prefix(100), Cursor::new(1, 2));
+
+    let count_glyphs = |end: Cursor| -> usize {
+        buffer
+            .layout_runs()
+            .map(|run| run.glyphs_before(end).count())
+            .sum()
+    };
+    assert_eq!(count_glyphs(buffer.visible_prefix(0)), 0);
+    assert_eq!(
+        count_glyphs(buffer.visible_prefix(3)),
+        count_glyphs(Cursor::new(1, 1))
+    );
+    assert!(count_glyphs(buffer.visible_prefix(3)) < count_glyphs(buffer.visible_prefix(4)));
+}
+
+#[test]
+fn text_reveal() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(&mut font_system, "abcd", Attrs::new(), Shaping::Advanced);
+
+    let mut reveal = TextReveal::new(10.0);
+    assert_eq!(reveal.end(&buffer), Cursor::new(0, 0));
+    assert!(!reveal.tick(Duration::from_millis(50)));
+    assert!(reveal.tick(Duration::from_millis(200)));
+    assert_eq!(reveal.count(), 2);
+    assert_eq!(reveal.end(&buffer), Cursor::new(0, 2));
+    assert!(!reveal.is_finished(&buffer));
+
+    reveal.set_rate(20.0);
+    assert_eq!(reveal.count(), 2);
+    reveal.tick(Duration::from_millis(100));
+    assert_eq!(reveal.count(), 4);
+    assert!(reveal.is_finished(&buffer));
+
+    reveal.reset();
+    assert_eq!(reveal.count(), 0);
+
+    // Negative rates are clamped like in set_rate
+    let mut reveal = TextReveal::new(-10.0);
+    assert_eq!(reveal.rate(), 0.0);
+    reveal.tick(Duration::from_secs(1));
+    assert_eq!(reveal.count(), 0);
+}
@@ -0,0 +1,49 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Scroll, Shaping};
+
+#[test]
+fn visible_lines_and_scroll_limits() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    let text: Vec<String> = (0..10).map(|i| format!("line {i}")).collect();
+    buffer.set_text(&text.join("\n"), Attrs::new(), Shaping::Advanced);
+    buffer.set_size(Some(200.0), Some(50.0));
+    buffer.shape_until_scroll(false);
+
+    // The third line is cut off above its baseline, so it is not drawn
+    assert_eq!(buffer.visible_lines(), 0..2);
+    assert_eq!(buffer.scroll_offset(), 0.0);
+    assert_eq!(buffer.scroll_limits(), (0.0, 150.0));
+
+    buffer.set_scroll(Scroll::new(4, 10.0, 0.0));
+    buffer.shape_until_scroll(false);
+    assert_eq!(buffer.visible_lines(), 4..7);
+    assert_eq!(buffer.scroll_offset(), 90.0);
+
+    // Scrolling past the end is limited to the maximum
+    buffer.set_scroll(Scroll::new(9, 0.0, 0.0));
+    buffer.shape_until_scroll(false);
+    assert_eq!(buffer.scroll_offset(), 150.0);
+    assert_eq!(buffer.visible_lines(), 7..10);
+
+    // Buffers taller than the text do not scroll
+    buffer.set_size(Some(200.0), Some(500.0));
+    buffer.shape_until_scroll(false);
+    assert_eq!(buffer.scroll_limits(), (0.0, 0.0));
+    assert_eq!(buffer.visible_lines(), 0..10);
+}
+
+#[test]
+fn scroll_limits_include_wrapped_lines() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(Some(60.0), Some(20.0));
+    buffer.set_text("one two three four\nfive", Attrs::new(), Shaping::Advanced);
+    buffer.shape_until_scroll(false);
+
+    let wrapped = buffer.lines[0].layout_opt().unwrap().len();
+    assert!(wrapped > 1);
+    let (_, max) = buffer.scroll_limits();
+    assert_eq!(max, (wrapped as f32 + 1.0) * 20.0 - 20.0);
+}
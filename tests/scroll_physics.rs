@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, ScrollPhysics, Shaping};
+
+const FRAME: Duration = Duration::from_millis(16);
+
+// Tests that a fling decelerates to a stop and overshoot springs back to the buffer extents
+#[test]
+fn scroll_physics_fling_and_overshoot() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, Some(200.0), Some(100.0));
+    let text = (0..50).map(|i| format!("line {}\n", i)).collect::<String>();
+    buffer.set_text(&mut font_system, &text, Attrs::new(), Shaping::Advanced);
+
+    let mut physics = ScrollPhysics::new();
+    physics.set_overshoot_limit(Some(50.0));
+
+    // Drag down quickly and release
+    for _ in 0..5 {
+        let delta = physics.drag(20.0, FRAME);
+        physics.apply(&mut font_system, &mut buffer, delta);
+    }
+    physics.release();
+    assert!(physics.velocity() > 0.0);
+    assert_eq!(physics.overshoot(), 0.0);
+
+    let mut frames = 0;
+    let mut last_velocity = physics.velocity();
+    while physics.is_animating() {
+        let delta = physics.tick(FRAME);
+        physics.apply(&mut font_system, &mut buffer, delta);
+        assert!(physics.velocity() <= last_velocity);
+        last_velocity = physics.velocity();
+        frames += 1;
+        assert!(frames < 1000, "fling did not stop");
+    }
+    assert!(buffer.scroll().line > 5);
+
+    // Drag beyond the start of the buffer
+    let delta = physics.drag(-10000.0, FRAME);
+    physics.apply(&mut font_system, &mut buffer, delta);
+    assert_eq!(buffer.scroll().line, 0);
+    assert!(physics.overshoot() < 0.0);
+    assert!(physics.overshoot() >= -50.0);
+    physics.release();
+
+    let mut frames = 0;
+    while physics.is_animating() {
+        let delta = physics.tick(FRAME);
+        physics.apply(&mut font_system, &mut buffer, delta);
+        frames += 1;
+        assert!(frames < 1000, "overshoot did not spring back");
+    }
+    assert_eq!(physics.overshoot(), 0.0);
+    assert_eq!(buffer.scroll().line, 0);
+    assert_eq!(buffer.scroll().vertical, 0.0);
+}
@@ -0,0 +1,67 @@
+use cosmic_text::{Attrs, Buffer, Color, Cursor, FontSystem, Metrics, Shaping};
+
+fn lines(buffer: &Buffer) -> Vec<&str> {
+    buffer.lines.iter().map(|line| line.text()).collect()
+}
+
+#[test]
+fn set_text_reuses_lines() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+
+    let red = Attrs::new().color(Color::rgb(0xFF, 0, 0));
+    buffer.set_text("one\ntwo\nthree", red, Shaping::Advanced);
+    assert_eq!(lines(&buffer), ["one", "two", "three"]);
+
+    buffer.set_text("four", Attrs::new(), Shaping::Advanced);
+    assert_eq!(lines(&buffer), ["four"]);
+    assert_eq!(buffer.lines[0].attrs_list().defaults(), Attrs::new());
+    assert_eq!(buffer.layout_runs().count(), 1);
+
+    buffer.set_text("", Attrs::new(), Shaping::Advanced);
+    assert_eq!(lines(&buffer), [""]);
+
+    buffer.set_text("five\nsix", Attrs::new(), Shaping::Advanced);
+    assert_eq!(lines(&buffer), ["five", "six"]);
+    assert_eq!(buffer.layout_runs().count(), 2);
+}
+
+#[test]
+fn text_between_lines() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "one\ntwo\nthree",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+    let text = |start, end| buffer.text_between(start, end).collect::<Vec<_>>();
+    assert_eq!(text(Cursor::new(0, 1), Cursor::new(0, 2)), ["n"]);
+    assert_eq!(
+        text(Cursor::new(0, 1), Cursor::new(2, 2)),
+        ["ne", "two", "th"]
+    );
+    assert!(text(Cursor::new(5, 0), Cursor::new(6, 0)).is_empty());
+}
+
+#[test]
+fn text_between_reversed_and_stale() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "one\ntwé",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+    let text = |start, end| buffer.text_between(start, end).collect::<Vec<_>>();
+    // A selection made from right to left
+    assert_eq!(text(Cursor::new(0, 2), Cursor::new(0, 1)), ["n"]);
+    assert_eq!(text(Cursor::new(1, 1), Cursor::new(0, 2)), ["e", "t"]);
+    // Cursors left past the end of a line or inside a character by an edit
+    assert_eq!(text(Cursor::new(0, 1), Cursor::new(0, 10)), ["ne"]);
+    assert_eq!(text(Cursor::new(1, 1), Cursor::new(1, 3)), ["w"]);
+    assert_eq!(text(Cursor::new(1, 10), Cursor::new(1, 20)), [""]);
+}
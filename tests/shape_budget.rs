@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Scroll, ShapeBudget, Shaping};
+
+fn unshaped(buffer: &Buffer) -> usize {
+    buffer
+        .lines
+        .iter()
+        .filter(|line| line.layout_opt().is_none())
+        .count()
+}
+
+#[test]
+fn shape_with_budget_spreads_shaping() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(Some(200.0), Some(100.0));
+    let text = (0..100)
+        .map(|i| format!("line {i}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    buffer.set_text(&text, Attrs::new(), Shaping::Advanced);
+    buffer.set_scroll(Scroll::new(50, 0.0, 0.0));
+    let before = unshaped(&buffer);
+    assert!(before > 0);
+
+    let progress = buffer.shape_with_budget(ShapeBudget::Lines(10));
+    assert_eq!(progress.shaped, 10);
+    assert_eq!(progress.remaining, before - 10);
+    assert!(!progress.is_complete());
+    // Lines from the scroll position are shaped first
+    assert!((50..60).all(|line_i| buffer.lines[line_i].layout_opt().is_some()));
+
+    // A budget of zero lines still makes progress
+    let progress = buffer.shape_with_budget(ShapeBudget::Lines(0));
+    assert_eq!(progress.shaped, 1);
+
+    let mut calls = 0;
+    loop {
+        calls += 1;
+        let progress = buffer.shape_with_budget(ShapeBudget::Duration(Duration::from_secs(10)));
+        if progress.is_complete() {
+            break;
+        }
+        assert!(calls < 100);
+    }
+    assert_eq!(unshaped(&buffer), 0);
+    assert_eq!(buffer.shape_with_budget(ShapeBudget::Lines(10)).shaped, 0);
+}
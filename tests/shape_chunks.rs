@@ -0,0 +1,38 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping};
+
+fn glyph_ranges(buffer: &mut Buffer, font_system: &mut FontSystem) -> Vec<(usize, usize)> {
+    let shape = buffer.line_shape(font_system, 0).unwrap();
+    assert_eq!(shape.spans.len(), 1);
+    // The run is still a single word, so wrapping is unaffected
+    assert_eq!(shape.spans[0].words.len(), 1);
+    shape.spans[0].words[0]
+        .glyphs
+        .iter()
+        .map(|glyph| (glyph.start, glyph.end))
+        .collect()
+}
+
+#[test]
+fn long_runs_are_shaped_in_chunks() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    assert_eq!(
+        buffer.shape_chunk_len(),
+        Some(Buffer::DEFAULT_SHAPE_CHUNK_LEN)
+    );
+    let text = "aGVsbG8gd29ybGQ=ü".repeat(1000);
+    buffer.set_text(&mut font_system, &text, Attrs::new(), Shaping::Advanced);
+
+    buffer.set_shape_chunk_len(&mut font_system, None);
+    let whole = glyph_ranges(&mut buffer, &mut font_system);
+
+    buffer.set_shape_chunk_len(&mut font_system, Some(100));
+    assert_eq!(buffer.shape_chunk_len(), Some(100));
+    let chunked = glyph_ranges(&mut buffer, &mut font_system);
+    assert_eq!(chunked, whole);
+    assert_eq!(chunked.last().unwrap().1, text.len());
+
+    // A length of zero disables chunking
+    buffer.set_shape_chunk_len(&mut font_system, Some(0));
+    assert_eq!(buffer.shape_chunk_len(), None);
+}
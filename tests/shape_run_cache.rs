@@ -0,0 +1,44 @@
+use cosmic_text::{Attrs, AttrsList, Buffer, Color, FontSystem, Metrics, Shaping, TextDecoration};
+
+#[test]
+fn cached_runs_get_render_attrs() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_text("let x = 1;", Attrs::new(), Shaping::Advanced);
+
+    for (color, metadata) in [
+        (Color::rgb(0xFF, 0x00, 0x00), 1),
+        (Color::rgb(0x00, 0xFF, 0x00), 2),
+    ] {
+        // Only attributes that do not affect shaping change, so the cached runs are reused
+        let mut attrs_list = AttrsList::new(Attrs::new().color(color));
+        attrs_list.add_span(
+            0..3,
+            Attrs::new()
+                .metadata(metadata)
+                .decoration(TextDecoration::UNDERLINE)
+                .background_color(color),
+        );
+        buffer.lines[0].set_attrs_list(attrs_list);
+        buffer.shape_until_scroll(false);
+
+        let run = buffer.layout_runs().next().unwrap();
+        for (glyph_i, glyph) in run.glyphs.iter().enumerate() {
+            if glyph.start < 3 {
+                assert_eq!(glyph.metadata, metadata);
+                assert_eq!(glyph.decoration, TextDecoration::UNDERLINE);
+                assert_eq!(
+                    run.glyph_extras(glyph_i)
+                        .and_then(|extras| extras.background_color_opt),
+                    Some(color)
+                );
+            } else {
+                assert_eq!(glyph.color_opt, Some(color));
+                assert_eq!(glyph.metadata, 0);
+                assert_eq!(glyph.decoration, TextDecoration::empty());
+                assert_eq!(run.glyph_extras(glyph_i), None);
+            }
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use cosmic_text::{shape_text, Attrs, Buffer, FontSystem, Metrics, Shaping};
+
+#[test]
+fn shape_text_matches_buffer() {
+    let mut font_system = FontSystem::new();
+    let text = "Hello \u{5E9}\u{5DC}\u{5D5}\u{5DD} world";
+
+    let shaped = shape_text(&mut font_system, text, Attrs::new(), Shaping::Advanced);
+    assert!(!shaped.rtl());
+
+    // Clusters cover the text in logical order
+    let mut end = 0;
+    for glyph in shaped.glyphs() {
+        assert!(glyph.start <= glyph.end);
+        end = end.max(glyph.end);
+    }
+    assert_eq!(end, text.len());
+
+    let layout = shaped.layout(&mut font_system, 14.0);
+    assert!((layout.w - shaped.width(14.0)).abs() < 0.01);
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_text(text, Attrs::new(), Shaping::Advanced);
+    buffer.shape_until_scroll(false);
+    let run = buffer.layout_runs().next().unwrap();
+    let positions = |glyphs: &[cosmic_text::LayoutGlyph]| {
+        glyphs
+            .iter()
+            .map(|glyph| (glyph.start, glyph.glyph_id, glyph.x))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(positions(&layout.glyphs), positions(run.glyphs));
+}
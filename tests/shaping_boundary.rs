@@ -0,0 +1,47 @@
+use cosmic_text::{Attrs, AttrsList, Buffer, Color, FontSystem, Metrics, Shaping};
+
+fn glyph_colors(
+    font_system: &mut FontSystem,
+    text: &str,
+    attrs_list: AttrsList,
+) -> Vec<Option<Color>> {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(font_system, text, Attrs::new(), Shaping::Advanced);
+    buffer.lines[0].set_attrs_list(attrs_list);
+    buffer.shape_until_scroll(font_system, false);
+    let run = buffer.layout_runs().next().unwrap();
+    run.glyphs.iter().map(|glyph| glyph.color_opt).collect()
+}
+
+#[test]
+fn shaping_boundary_splits_ligatures() {
+    let mut font_system = FontSystem::new();
+    let red = Color::rgb(0xFF, 0x00, 0x00);
+    let blue = Color::rgb(0x00, 0x00, 0xFF);
+
+    // Color does not split shaping, the ligature takes the color of its first character
+    let mut attrs_list = AttrsList::new(Attrs::new());
+    attrs_list.add_span(0..1, Attrs::new().color(red));
+    attrs_list.add_span(1..2, Attrs::new().color(blue));
+    assert_eq!(
+        glyph_colors(&mut font_system, "fi", attrs_list),
+        [Some(red)]
+    );
+
+    // The same text in other colors gets its own colors
+    let mut attrs_list = AttrsList::new(Attrs::new());
+    attrs_list.add_span(0..1, Attrs::new().color(blue));
+    attrs_list.add_span(1..2, Attrs::new().color(red));
+    assert_eq!(
+        glyph_colors(&mut font_system, "fi", attrs_list),
+        [Some(blue)]
+    );
+
+    let mut attrs_list = AttrsList::new(Attrs::new());
+    attrs_list.add_span(0..1, Attrs::new().color(red).shaping_boundary(true));
+    attrs_list.add_span(1..2, Attrs::new().color(blue));
+    assert_eq!(
+        glyph_colors(&mut font_system, "fi", attrs_list),
+        [Some(red), Some(blue)]
+    );
+}
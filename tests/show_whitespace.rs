@@ -0,0 +1,70 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping, Whitespace};
+
+#[test]
+fn whitespace_glyphs_are_marked() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_text("a b\tc\u{A0}d\ne", Attrs::new(), Shaping::Advanced);
+
+    let run = buffer.layout_runs().next().unwrap();
+    let kinds: Vec<_> = run
+        .glyphs
+        .iter()
+        .map(|glyph| glyph.whitespace_opt())
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            None,
+            Some(Whitespace::Space),
+            None,
+            Some(Whitespace::Tab),
+            None,
+            Some(Whitespace::NoBreakSpace),
+            None,
+        ]
+    );
+}
+
+#[test]
+fn show_whitespace_substitutes_markers() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_text("a b\tc\ne", Attrs::new(), Shaping::Advanced);
+    let hidden: Vec<_> = buffer
+        .layout_runs()
+        .map(|run| run.glyphs.to_vec())
+        .collect();
+    let width = buffer.layout_runs().next().unwrap().line_w;
+
+    buffer.set_show_whitespace(true);
+    let runs: Vec<_> = buffer.layout_runs().collect();
+
+    // Spaces and tabs are drawn differently but keep their advances
+    for (glyph, shown) in hidden[0].iter().zip(runs[0].glyphs.iter()) {
+        assert_eq!(glyph.x, shown.x);
+        assert_eq!(glyph.w, shown.w);
+        if glyph.whitespace_opt().is_some() {
+            assert_ne!(glyph.glyph_id, shown.glyph_id);
+        } else {
+            assert_eq!(glyph.glyph_id, shown.glyph_id);
+        }
+    }
+    assert_eq!(runs[0].line_w, width);
+
+    // Only the line with a line ending gets a marker after its last glyph
+    assert_eq!(runs[0].glyphs.len(), hidden[0].len() + 1);
+    let marker = runs[0].glyphs.last().unwrap();
+    assert_eq!(marker.whitespace_opt(), Some(Whitespace::LineEnding));
+    assert_eq!((marker.start, marker.end), (5, 5));
+    let last = &hidden[0][hidden[0].len() - 1];
+    assert_eq!(marker.x, last.x + last.w);
+    assert_eq!(runs[1].glyphs.len(), hidden[1].len());
+
+    buffer.set_show_whitespace(false);
+    let run = buffer.layout_runs().next().unwrap();
+    assert_eq!(run.glyphs.len(), hidden[0].len());
+    assert_eq!(run.glyphs[1].glyph_id, hidden[0][1].glyph_id);
+}
@@ -0,0 +1,60 @@
+use cosmic_text::{Attrs, AttrsList, Buffer, Caps, FontSystem, Metrics, Shaping};
+
+/// Lay out `text` with `caps`, returning the start, end, and font size of each glyph
+fn layout(text: &str, caps: Option<Caps>) -> Vec<(usize, usize, f32)> {
+    let mut font_system = FontSystem::new_mock();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(10.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    let attrs = caps.map_or(Attrs::new(), |caps| Attrs::new().font_caps(caps));
+    buffer.set_text(text, attrs, Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+    run.glyphs
+        .iter()
+        .map(|glyph| (glyph.start as usize, glyph.end as usize, glyph.font_size))
+        .collect()
+}
+
+#[test]
+fn small_caps_synthesized() {
+    // The mock font has no `smcp` feature, so lowercase letters are shaped as reduced capitals
+    let small = 10.0 * Caps::SYNTHESIZED_SCALE;
+    assert_eq!(
+        layout("aB1", Some(Caps::SmallCaps)),
+        [(0, 1, small), (1, 2, 10.0), (2, 3, 10.0)]
+    );
+    assert_eq!(
+        layout("aB1", Some(Caps::Petite)),
+        [(0, 1, small), (1, 2, 10.0), (2, 3, 10.0)]
+    );
+    assert_eq!(
+        layout("aB1", Some(Caps::AllSmallCaps)),
+        [(0, 1, small), (1, 2, small), (2, 3, 10.0)]
+    );
+    assert_eq!(
+        layout("aB1", None),
+        [(0, 1, 10.0), (1, 2, 10.0), (2, 3, 10.0)]
+    );
+
+    // Both capitals of "ß" map back to the letter they replace
+    assert_eq!(
+        layout("ßa", Some(Caps::SmallCaps)),
+        [(0, 2, small), (0, 2, small), (2, 3, small)]
+    );
+}
+
+#[test]
+fn small_caps_span() {
+    let mut font_system = FontSystem::new_mock();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(10.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_text("abcd", Attrs::new(), Shaping::Advanced);
+    let mut attrs_list = AttrsList::new(Attrs::new());
+    attrs_list.add_span(1..3, Attrs::new().font_caps(Caps::SmallCaps));
+    buffer.lines[0].set_attrs_list(attrs_list);
+    buffer.shape_until_scroll(false);
+
+    let run = buffer.layout_runs().next().unwrap();
+    let widths: Vec<_> = run.glyphs.iter().map(|glyph| glyph.w).collect();
+    assert_eq!(widths, [5.0, 3.5, 3.5, 5.0]);
+    assert_eq!(run.line_w, 17.0);
+}
@@ -0,0 +1,137 @@
+#![cfg(feature = "syntect")]
+
+use std::{fs, path::PathBuf};
+
+use cosmic_text::{
+    Attrs, Buffer, Cursor, Edit, FontSystem, Metrics, Reload, SyntaxEditor, SyntaxSystem,
+};
+
+const TEXT: &str = "fn main() {\n    let a = 1;\n    let b = 2;\n}\n";
+
+fn temp_file(name: &str, text: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("cosmic-text-{}-{name}.rs", std::process::id()));
+    fs::write(&path, text).expect("failed to write file");
+    path
+}
+
+fn editor<'a>(
+    font_system: &mut FontSystem,
+    syntax_system: &'a SyntaxSystem,
+    path: &PathBuf,
+) -> SyntaxEditor<'a, 'static> {
+    let buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    let mut editor = SyntaxEditor::new(buffer, syntax_system, "base16-eighties.dark")
+        .expect("Default theme `base16-eighties.dark` should be found");
+    editor
+        .load_text(font_system, path, Attrs::new())
+        .expect("failed to load file");
+    editor.shape_as_needed(font_system, false);
+    editor
+}
+
+fn text(editor: &SyntaxEditor) -> String {
+    editor.with_buffer(|buffer| {
+        buffer
+            .lines
+            .iter()
+            .map(|line| line.text())
+            .collect::<Vec<_>>()
+            .join("\n")
+    })
+}
+
+#[test]
+fn reload_applies_changes_and_keeps_history() {
+    let mut font_system = FontSystem::new();
+    let path = temp_file("reload", TEXT);
+    let syntax_system = SyntaxSystem::new();
+    let mut editor = editor(&mut font_system, &syntax_system, &path);
+    let keyword_color =
+        editor.with_buffer(|buffer| buffer.lines[0].attrs_list().get_span(0).color_opt);
+    editor.set_cursor(Cursor::new(2, 8));
+
+    fs::write(
+        &path,
+        "fn main() {\n    let z = 0;\n    let a = 1;\n    let b = 2;\n}\n",
+    )
+    .expect("failed to write file");
+    let reload = editor.reload_from(&path).expect("failed to reload");
+    fs::remove_file(&path).ok();
+    assert_eq!(
+        reload,
+        Reload {
+            changes: 1,
+            conflicts: Vec::new(),
+        }
+    );
+    assert_eq!(
+        text(&editor),
+        "fn main() {\n    let z = 0;\n    let a = 1;\n    let b = 2;\n}"
+    );
+
+    // The cursor follows its text, unchanged lines keep their highlighting
+    assert_eq!(editor.cursor(), Cursor::new(3, 8));
+    assert!(editor.history().unwrap().is_saved());
+    editor.with_buffer(|buffer| {
+        assert_eq!(
+            buffer.lines[0].attrs_list().get_span(0).color_opt,
+            keyword_color
+        );
+        assert!(buffer.lines[0].metadata().is_some());
+        assert!(buffer.lines[1].metadata().is_none());
+    });
+    editor.shape_as_needed(&mut font_system, false);
+    editor.with_buffer(|buffer| {
+        assert_eq!(
+            buffer.lines[1].attrs_list().get_span(4).color_opt,
+            keyword_color
+        );
+    });
+
+    // Reloading can be undone
+    assert!(editor.undo());
+    assert_eq!(text(&editor), TEXT.trim_end());
+}
+
+#[test]
+fn reload_keeps_unsaved_edits_and_reports_conflicts() {
+    let mut font_system = FontSystem::new();
+    let path = temp_file("conflict", TEXT);
+    let syntax_system = SyntaxSystem::new();
+    let mut editor = editor(&mut font_system, &syntax_system, &path);
+
+    editor.start_change();
+    editor.insert_at(Cursor::new(1, 13), "0", None);
+    editor.finish_change();
+    assert!(!editor.history().unwrap().is_saved());
+
+    // Changes elsewhere keep the edit
+    fs::write(&path, "fn main() {\n    let a = 1;\n    let b = 3;\n}\n")
+        .expect("failed to write file");
+    let reload = editor.reload_from(&path).expect("failed to reload");
+    assert!(reload.conflicts.is_empty());
+    assert_eq!(
+        text(&editor),
+        "fn main() {\n    let a = 10;\n    let b = 3;\n}"
+    );
+    assert!(!editor.history().unwrap().is_saved());
+
+    // Changes on the edited line conflict and take the file
+    fs::write(&path, "fn main() {\n    let a = 2;\n    let b = 3;\n}\n")
+        .expect("failed to write file");
+    let reload = editor.reload_from(&path).expect("failed to reload");
+    fs::remove_file(&path).ok();
+    assert_eq!(reload.changes, 1);
+    assert_eq!(reload.conflicts, vec![1..2]);
+    assert_eq!(
+        text(&editor),
+        "fn main() {\n    let a = 2;\n    let b = 3;\n}"
+    );
+
+    // Undoing the reload restores the edit
+    assert!(editor.undo());
+    assert_eq!(
+        text(&editor),
+        "fn main() {\n    let a = 10;\n    let b = 3;\n}"
+    );
+}
@@ -0,0 +1,183 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping, TabAlign, TabStop, TabStops};
+
+fn tab_ends(buffer: &Buffer) -> Vec<f32> {
+    let run = buffer.layout_runs().next().unwrap();
+    run.glyphs
+        .iter()
+        .filter(|glyph| &run.text[glyph.range()] == "\t")
+        .map(|glyph| glyph.x + glyph.w)
+        .collect()
+}
+
+#[test]
+fn tab_width_change_does_not_reshape() {
+    let mut font_system = FontSystem::new();
+    let mut space_buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    space_buffer.set_text(&mut font_system, " ", Attrs::new(), Shaping::Advanced);
+    let space = space_buffer.layout_runs().next().unwrap().glyphs[0].w;
+
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_profiling(true);
+    buffer.set_text("a\tb\tc\td", Attrs::new(), Shaping::Advanced);
+    buffer.set_tab_width(4);
+    assert_eq!(buffer.lines[0].shape_opt().unwrap().tab_width(), 4);
+
+    let ends = tab_ends(&buffer);
+    assert_eq!(ends.len(), 3);
+    for (i, end) in ends.iter().enumerate() {
+        assert!(
+            (end - space * 4.0 * (i + 1) as f32).abs() < 0.01,
+            "{ends:?}"
+        );
+    }
+
+    buffer.set_tab_width(8);
+    let ends = tab_ends(&buffer);
+    for (i, end) in ends.iter().enumerate() {
+        assert!(
+            (end - space * 8.0 * (i + 1) as f32).abs() < 0.01,
+            "{ends:?}"
+        );
+    }
+
+    let profile = buffer.profile_report().total();
+    assert_eq!(profile.shape_count, 1);
+    assert_eq!(profile.layout_count, 3);
+}
+
+#[test]
+fn tab_stops_align_text() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_profiling(true);
+    buffer.set_text("a\tleft\tright\t3.25\tz", Attrs::new(), Shaping::Advanced);
+    let tab_stops = TabStops::new([
+        TabStop::new(400.0, TabAlign::Decimal),
+        TabStop::new(100.0, TabAlign::Left),
+        TabStop::new(250.0, TabAlign::Right),
+    ])
+    .with_interval(50.0);
+    assert_eq!(tab_stops.stops()[0].position, 100.0);
+    buffer.set_tab_stops(Some(tab_stops));
+
+    let run = buffer.layout_runs().next().unwrap();
+    let glyph_at = |text: &str| {
+        let index = run.text.find(text).unwrap();
+        run.glyphs
+            .iter()
+            .find(|glyph| glyph.start as usize == index)
+            .unwrap()
+    };
+    assert!((glyph_at("left").x - 100.0).abs() < 0.01);
+    let right_end = run
+        .glyphs
+        .iter()
+        .filter(|glyph| (glyph.start as usize) < run.text.find("\t3").unwrap())
+        .map(|glyph| glyph.x + glyph.w)
+        .fold(0.0, f32::max);
+    assert!((right_end - 250.0).abs() < 0.01, "{right_end}");
+    assert!((glyph_at(".").x - 400.0).abs() < 0.01);
+    // After the last stop, tabs advance to multiples of the interval
+    let z = glyph_at("z").x;
+    assert!(
+        z > 400.0 && (z / 50.0 - (z / 50.0).round()).abs() < 0.01,
+        "{z}"
+    );
+
+    // Going back to the tab width does not reshape
+    buffer.set_tab_stops(None);
+    assert_eq!(tab_ends(&buffer).len(), 4);
+    let run = buffer.layout_runs().next().unwrap();
+    let left = run.glyphs.iter().find(|glyph| glyph.start == 2).unwrap();
+    assert!(left.x < 100.0);
+    assert_eq!(buffer.profile_report().total().shape_count, 1);
+}
+
+#[test]
+fn tab_stops_fall_back_to_interval() {
+    let stops = TabStops::new([TabStop::new(50.0, TabAlign::Right)]);
+    assert_eq!(
+        stops.next_stop(10.0, 30.0),
+        TabStop::new(50.0, TabAlign::Right)
+    );
+    assert_eq!(
+        stops.next_stop(50.0, 30.0),
+        TabStop::new(60.0, TabAlign::Left)
+    );
+    let stops = stops.with_interval(40.0).with_decimal(',');
+    assert_eq!(
+        stops.next_stop(50.0, 30.0),
+        TabStop::new(80.0, TabAlign::Left)
+    );
+    assert_eq!(stops.decimal(), ',');
+}
+
+#[test]
+fn rtl_tabs_are_measured_from_the_right() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_text("א ב", Attrs::new(), Shaping::Advanced);
+    let space = buffer.layout_runs().next().unwrap().glyphs[1].w;
+
+    buffer.set_text("אב\tג\tד", Attrs::new(), Shaping::Advanced);
+    buffer.set_tab_width(4);
+    let layout = buffer.line_layout(0).unwrap();
+    let right = layout[0]
+        .glyphs
+        .iter()
+        .map(|glyph| glyph.x + glyph.w)
+        .fold(0.0, f32::max);
+    let tabs: Vec<_> = layout[0].tab_positions().collect();
+    assert_eq!(tabs.len(), 2);
+    for (i, tab) in tabs.iter().enumerate() {
+        assert!(tab.level.is_rtl());
+        let end = right - tab.x;
+        assert!(
+            (end - space * 4.0 * (i + 1) as f32).abs() < 0.01,
+            "tab {i} ends at {end}"
+        );
+    }
+}
+
+#[test]
+fn tab_stops_align_decimals_in_visual_order() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_tab_stops(Some(TabStops::new([TabStop::new(
+        200.0,
+        TabAlign::Decimal,
+    )])));
+
+    // The RTL run is reversed, so the long word is between the tab and the number
+    buffer.set_text("a\tאב 1.5 אבגדהו", Attrs::new(), Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+    let decimal = run.text.find('.').unwrap();
+    let glyph = run
+        .glyphs
+        .iter()
+        .find(|glyph| glyph.start as usize == decimal)
+        .unwrap();
+    assert!((glyph.x - 200.0).abs() < 0.01, "{}", glyph.x);
+
+    // Tab stops of RTL lines are measured from the right edge
+    buffer.set_text("\tאב 1.5 אבגדהו", Attrs::new(), Shaping::Advanced);
+    let run = buffer.layout_runs().next().unwrap();
+    assert!(run.rtl);
+    let decimal = run.text.find('.').unwrap();
+    let right = run
+        .glyphs
+        .iter()
+        .map(|glyph| glyph.x + glyph.w)
+        .fold(0.0, f32::max);
+    let glyph = run
+        .glyphs
+        .iter()
+        .find(|glyph| glyph.start as usize == decimal)
+        .unwrap();
+    let end = right - glyph.x - glyph.w;
+    assert!((end - 200.0).abs() < 0.01, "{end}");
+}
@@ -0,0 +1,83 @@
+use cosmic_text::{Attrs, ColumnWidths, FontSystem, Metrics, Shaping, TableMeasure};
+
+fn table(font_system: &mut FontSystem) -> TableMeasure {
+    let metrics = Metrics::new(14.0, 20.0);
+    let mut table = TableMeasure::new(2);
+    for text in [
+        "Name",
+        "Description",
+        "cosmic_text",
+        "Pure Rust multi-line text handling",
+        "swash",
+        "Font introspection, complex text shaping and glyph rendering",
+    ] {
+        table.push_cell(font_system, metrics, text, Attrs::new(), Shaping::Advanced);
+    }
+    table
+}
+
+#[test]
+fn table_measure_columns() {
+    let mut font_system = FontSystem::new();
+    let mut table = table(&mut font_system);
+    assert_eq!(table.rows(), 3);
+    assert_eq!(table.cell(1, 0).unwrap().lines[0].text(), "cosmic_text");
+    assert!(table.cell(0, 2).is_none());
+
+    let widths = table.measure(&mut font_system);
+    assert_eq!(widths.len(), 2);
+    for column in widths.iter() {
+        assert!(column.min > 0.0 && column.min <= column.max, "{column:?}");
+    }
+    // The first column is one word per cell
+    assert_eq!(widths[0].min, widths[0].max);
+    assert!(widths[1].min < widths[1].max);
+
+    // Unconstrained columns do not wrap
+    let resolved = TableMeasure::resolve(&widths, None);
+    assert_eq!(resolved, vec![widths[0].max, widths[1].max]);
+    let heights = table.layout(&mut font_system, &resolved);
+    assert_eq!(heights, vec![20.0; 3]);
+
+    // Too narrow tables fall back to the min widths
+    let resolved = TableMeasure::resolve(&widths, Some(10.0));
+    assert_eq!(resolved, vec![widths[0].min, widths[1].min]);
+
+    // Space between min and max goes to the column that wants it
+    let total_min = widths[0].min + widths[1].min;
+    let resolved = TableMeasure::resolve(&widths, Some(total_min + 40.0));
+    assert_eq!(resolved[0], widths[0].min);
+    assert!((resolved[1] - widths[1].min - 40.0).abs() < 0.01);
+    let heights = table.layout(&mut font_system, &resolved);
+    assert_eq!(heights[0], 20.0);
+    assert!(heights[2] > 20.0, "{heights:?}");
+    for run in table.cell(2, 1).unwrap().layout_runs() {
+        assert!(run.line_w <= resolved[1] + 0.01);
+    }
+}
+
+#[test]
+fn table_resolve_widths() {
+    let widths = [
+        ColumnWidths {
+            min: 10.0,
+            max: 30.0,
+        },
+        ColumnWidths {
+            min: 20.0,
+            max: 20.0,
+        },
+        ColumnWidths {
+            min: 0.0,
+            max: 60.0,
+        },
+    ];
+    assert_eq!(
+        TableMeasure::resolve(&widths, Some(200.0)),
+        vec![30.0, 20.0, 60.0]
+    );
+    assert_eq!(
+        TableMeasure::resolve(&widths, Some(70.0)),
+        vec![20.0, 20.0, 30.0]
+    );
+}
@@ -0,0 +1,85 @@
+use cosmic_text::{Attrs, Buffer, Cursor, FontSystem, Metrics, Motion, Shaping};
+
+fn motions(
+    buffer: &mut Buffer,
+    font_system: &mut FontSystem,
+    mut cursor: Cursor,
+    motion: Motion,
+    count: usize,
+) -> Vec<(usize, usize)> {
+    let mut cursors = Vec::new();
+    for _ in 0..count {
+        cursor = buffer
+            .cursor_motion(font_system, cursor, None, motion)
+            .unwrap()
+            .0;
+        cursors.push((cursor.line, cursor.index));
+    }
+    cursors
+}
+
+#[test]
+fn paragraph_motions_stop_at_blank_lines() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "one\ntwo\n\n  \nthree\n\nfour",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+
+    assert_eq!(
+        motions(
+            &mut buffer,
+            &mut font_system,
+            Cursor::new(0, 1),
+            Motion::ParagraphDown,
+            4
+        ),
+        [(2, 0), (5, 0), (6, 4), (6, 4)]
+    );
+    assert_eq!(
+        motions(
+            &mut buffer,
+            &mut font_system,
+            Cursor::new(6, 2),
+            Motion::ParagraphUp,
+            3
+        ),
+        [(5, 0), (3, 0), (0, 0)]
+    );
+}
+
+#[test]
+fn sentence_motions_cross_lines() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "One. Two? Three!\nFour.",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+
+    assert_eq!(
+        motions(
+            &mut buffer,
+            &mut font_system,
+            Cursor::new(0, 1),
+            Motion::SentenceNext,
+            5
+        ),
+        [(0, 5), (0, 10), (1, 0), (1, 5), (1, 5)]
+    );
+    assert_eq!(
+        motions(
+            &mut buffer,
+            &mut font_system,
+            Cursor::new(1, 2),
+            Motion::SentencePrevious,
+            5
+        ),
+        [(1, 0), (0, 10), (0, 5), (0, 0), (0, 0)]
+    );
+}
@@ -0,0 +1,82 @@
+use cosmic_text::{shape_text, Attrs, FontSystem, Shaping, TextPath};
+
+#[test]
+fn text_on_straight_paths() {
+    let mut font_system = FontSystem::new();
+    let shaped = shape_text(&mut font_system, "Hello", Attrs::new(), Shaping::Advanced);
+
+    let horizontal = [(0.0, 50.0), (500.0, 50.0)];
+    let glyphs = shaped.layout_on_path(
+        &mut font_system,
+        14.0,
+        TextPath::Polyline(&horizontal),
+        10.0,
+        0.0,
+    );
+    assert_eq!(glyphs.len(), 5);
+    for glyph in glyphs.iter() {
+        assert!((glyph.x - (glyph.glyph.x + 10.0)).abs() < 0.01);
+        assert!((glyph.y - 50.0).abs() < 0.01);
+        assert_eq!(glyph.angle, 0.0);
+    }
+
+    // Letter spacing is added between glyphs
+    let spaced = shaped.layout_on_path(
+        &mut font_system,
+        14.0,
+        TextPath::Polyline(&horizontal),
+        10.0,
+        2.0,
+    );
+    for (i, (glyph, spaced)) in glyphs.iter().zip(spaced.iter()).enumerate() {
+        assert!((spaced.x - glyph.x - 2.0 * i as f32).abs() < 0.01);
+    }
+
+    // A straight Bezier curve places glyphs like a line
+    let bezier = shaped.layout_on_path(
+        &mut font_system,
+        14.0,
+        TextPath::CubicBezier([(0.0, 50.0), (100.0, 50.0), (400.0, 50.0), (500.0, 50.0)]),
+        10.0,
+        0.0,
+    );
+    for (glyph, bezier) in glyphs.iter().zip(bezier.iter()) {
+        assert!((glyph.x - bezier.x).abs() < 0.1);
+        assert!((glyph.y - bezier.y).abs() < 0.1);
+    }
+
+    // Glyphs follow the direction of the path, and glyphs off the path are left out
+    let vertical = [(20.0, 0.0), (20.0, 20.0)];
+    let glyphs = shaped.layout_on_path(
+        &mut font_system,
+        14.0,
+        TextPath::Polyline(&vertical),
+        0.0,
+        0.0,
+    );
+    assert!(!glyphs.is_empty() && glyphs.len() < 5);
+    for glyph in glyphs.iter() {
+        assert!((glyph.x - 20.0).abs() < 0.01);
+        assert!((glyph.angle - core::f32::consts::FRAC_PI_2).abs() < 0.01);
+    }
+}
+
+#[test]
+fn rtl_text_ends_at_path_end() {
+    let mut font_system = FontSystem::new();
+    let shaped = shape_text(
+        &mut font_system,
+        "\u{5E9}\u{5DC}\u{5D5}\u{5DD}",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+    assert!(shaped.rtl());
+
+    let path = [(0.0, 0.0), (200.0, 0.0)];
+    let glyphs = shaped.layout_on_path(&mut font_system, 14.0, TextPath::Polyline(&path), 5.0, 0.0);
+    let end = glyphs
+        .iter()
+        .map(|glyph| glyph.x + glyph.glyph.w)
+        .fold(0.0, f32::max);
+    assert!((end - 195.0).abs() < 0.01);
+}
@@ -0,0 +1,38 @@
+use cosmic_text::{Attrs, AttrsList, Buffer, FontSystem, Metrics, Shaping};
+
+fn glyph_sizes(buffer: &Buffer) -> Vec<(f32, f32)> {
+    buffer
+        .layout_runs()
+        .flat_map(|run| {
+            run.glyphs
+                .iter()
+                .map(move |g| (g.font_size, run.line_height))
+        })
+        .collect()
+}
+
+#[test]
+fn text_scale_applies_to_all_font_sizes() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    assert_eq!(buffer.text_scale(), 1.0);
+
+    buffer.set_text("a\nb", Attrs::new(), Shaping::Advanced);
+    let mut attrs_list = AttrsList::new(Attrs::new());
+    attrs_list.add_span(0..1, Attrs::new().metrics(Metrics::new(10.0, 12.0)));
+    buffer.lines[1].set_attrs_list(attrs_list);
+    buffer.shape_until_scroll(false);
+    assert_eq!(glyph_sizes(&buffer), [(14.0, 20.0), (10.0, 12.0)]);
+
+    buffer.set_text_scale(2.0);
+    assert_eq!(buffer.text_scale(), 2.0);
+    assert_eq!(buffer.scaled_metrics(), Metrics::new(28.0, 40.0));
+    assert_eq!(glyph_sizes(&buffer), [(28.0, 40.0), (20.0, 24.0)]);
+
+    // Invalid scales are ignored
+    buffer.set_text_scale(0.0);
+    buffer.set_text_scale(f32::NAN);
+    assert_eq!(buffer.text_scale(), 2.0);
+    assert_eq!(glyph_sizes(&buffer), [(28.0, 40.0), (20.0, 24.0)]);
+}
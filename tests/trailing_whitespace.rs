@@ -0,0 +1,108 @@
+use cosmic_text::{Align, Attrs, Buffer, FontSystem, Metrics, Shaping, TrailingWhitespace, Wrap};
+
+/// Lay out `text` wrapped after the first word, returning the width and glyph ranges of each
+/// layout run
+fn layout(
+    font_system: &mut FontSystem,
+    text: &str,
+    align: Option<Align>,
+    trailing_whitespace: TrailingWhitespace,
+) -> Vec<(f32, Vec<usize>)> {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(font_system);
+    buffer.set_wrap(Wrap::Word);
+    buffer.set_size(Some(40.0), None);
+    buffer.set_trailing_whitespace(trailing_whitespace);
+    buffer.set_text(text, Attrs::new(), Shaping::Advanced);
+    for line in buffer.lines.iter_mut() {
+        line.set_align(align);
+    }
+    buffer.shape_until_scroll(false);
+    buffer
+        .layout_runs()
+        .map(|run| {
+            (
+                run.line_w,
+                run.glyphs
+                    .iter()
+                    .map(|glyph| glyph.start as usize)
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn trailing_whitespace_at_wrap() {
+    let mut font_system = FontSystem::new();
+    let text = "aaa bbb";
+
+    let collapse = layout(&mut font_system, text, None, TrailingWhitespace::Collapse);
+    assert_eq!(collapse.len(), 2);
+    assert_eq!(collapse[0].1, [0, 1, 2]);
+
+    // Hanging whitespace is laid out but not counted in the width
+    let hang = layout(&mut font_system, text, None, TrailingWhitespace::Hang);
+    assert_eq!(hang.len(), 2);
+    assert_eq!(hang[0].1, [0, 1, 2, 3]);
+    assert_eq!(hang[0].0, collapse[0].0);
+
+    let include = layout(&mut font_system, text, None, TrailingWhitespace::Include);
+    assert_eq!(include.len(), 2);
+    assert_eq!(include[0].1, [0, 1, 2, 3]);
+    assert!(include[0].0 > collapse[0].0);
+
+    // The last line is not affected by wrapping
+    assert_eq!(collapse[1], hang[1]);
+    assert_eq!(collapse[1], include[1]);
+}
+
+#[test]
+fn trailing_whitespace_justified() {
+    let mut font_system = FontSystem::new();
+    let text = "aa b ccc";
+
+    // Hanging whitespace is not expanded by justification and not counted in the width
+    let hang = layout(
+        &mut font_system,
+        text,
+        Some(Align::Justified),
+        TrailingWhitespace::Hang,
+    );
+    assert!(hang[0].1.contains(&4));
+    assert!(hang[0].0 <= 40.0);
+}
+
+#[test]
+fn trailing_whitespace_width() {
+    let mut font_system = FontSystem::new_mock();
+    let mut widths = |trailing_whitespace| {
+        let mut buffer = Buffer::new(&mut font_system, Metrics::new(10.0, 20.0));
+        buffer.set_wrap(&mut font_system, Wrap::Word);
+        buffer.set_size(&mut font_system, Some(30.0), None);
+        buffer.set_trailing_whitespace(&mut font_system, trailing_whitespace);
+        buffer.set_text(
+            &mut font_system,
+            "aaa bbb ",
+            Attrs::new(),
+            Shaping::Advanced,
+        );
+        buffer
+            .line_layout(&mut font_system, 0)
+            .unwrap()
+            .iter()
+            .map(|line| (line.w, line.trailing_whitespace_w))
+            .collect::<Vec<_>>()
+    };
+
+    // Every glyph of the mock font is 5px wide at 10px
+    assert_eq!(
+        widths(TrailingWhitespace::Collapse),
+        [(15.0, 0.0), (20.0, 5.0)]
+    );
+    assert_eq!(widths(TrailingWhitespace::Hang), [(15.0, 0.0), (15.0, 0.0)]);
+    assert_eq!(
+        widths(TrailingWhitespace::Include),
+        [(20.0, 5.0), (20.0, 5.0)]
+    );
+}
@@ -0,0 +1,147 @@
+use cosmic_text::{
+    Attrs, Buffer, CacheKeyFlags, Color, Direction, FontSystem, Metrics, Shaping, SwashCache,
+};
+
+#[test]
+fn vertical_columns_wrap_at_height() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(Some(200.0), Some(60.0));
+    buffer.set_text("漢字かなabc漢字かな", Attrs::new(), Shaping::Advanced);
+    let horizontal_runs = buffer.layout_runs().count();
+
+    buffer.set_direction(Direction::Vertical);
+    assert_eq!(buffer.direction(), Direction::Vertical);
+    // The physical size is kept, lines now wrap at the height
+    assert_eq!(buffer.size(), (Some(200.0), Some(60.0)));
+    let runs: Vec<_> = buffer.layout_runs().collect();
+    assert!(runs.len() > horizontal_runs, "{}", runs.len());
+    for run in runs.iter() {
+        assert!(run.line_w <= 60.0, "{}", run.line_w);
+        for glyph in run.glyphs.iter() {
+            let upright = !run.text[glyph.range()].is_ascii();
+            assert_eq!(
+                glyph.cache_key_flags.contains(CacheKeyFlags::ROTATE_CW),
+                !upright
+            );
+        }
+    }
+
+    buffer.set_direction(Direction::Horizontal);
+    assert_eq!(buffer.size(), (Some(200.0), Some(60.0)));
+    assert_eq!(buffer.layout_runs().count(), horizontal_runs);
+}
+
+#[test]
+fn vertical_draw_fills_columns_from_the_right() {
+    let mut font_system = FontSystem::new();
+    let mut swash_cache = SwashCache::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(Some(200.0), Some(200.0));
+    buffer.set_direction(Direction::Vertical);
+    buffer.set_text("llllllll", Attrs::new(), Shaping::Advanced);
+
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (i32::MAX, i32::MAX, i32::MIN, i32::MIN);
+    buffer.draw(&mut swash_cache, Color::rgb(0, 0, 0), |x, y, w, h, _| {
+        min_x = min_x.min(x);
+        min_y = min_y.min(y);
+        max_x = max_x.max(x + w as i32);
+        max_y = max_y.max(y + h as i32);
+    });
+    // A single column of rotated glyphs at the right edge, running down
+    assert!(min_x >= 180 && max_x <= 200, "{min_x}..{max_x}");
+    assert!(
+        min_y >= 0 && max_y - min_y > 2 * (max_x - min_x),
+        "{min_y}..{max_y}"
+    );
+}
+
+#[test]
+fn vertical_alternates_stay_upright() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(Some(200.0), Some(200.0));
+    let text = "「漢字」、かなー。";
+    buffer.set_text(text, Attrs::new(), Shaping::Advanced);
+    let horizontal: Vec<_> = buffer
+        .layout_runs()
+        .flat_map(|run| run.glyphs.iter().map(|glyph| (glyph.start, glyph.glyph_id)))
+        .collect();
+
+    // Brackets and the prolonged sound mark are set upright if `vert` replaced their glyphs
+    buffer.set_direction(Direction::Vertical);
+    for run in buffer.layout_runs() {
+        for glyph in run.glyphs.iter() {
+            let replaced = horizontal
+                .iter()
+                .any(|&(start, glyph_id)| start == glyph.start && glyph_id != glyph.glyph_id);
+            let rotated = glyph.cache_key_flags.contains(CacheKeyFlags::ROTATE_CW);
+            match &text[glyph.range()] {
+                "「" | "」" | "ー" => assert_eq!(rotated, !replaced),
+                _ => assert!(!rotated),
+            }
+        }
+    }
+}
+
+#[test]
+fn vertical_combine_upright() {
+    let mut font_system = FontSystem::new_mock();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(10.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(Some(200.0), Some(200.0));
+    buffer.set_direction(Direction::Vertical);
+    let attrs = Attrs::new();
+    buffer.set_rich_text(
+        [
+            ("第", attrs),
+            ("12", attrs.combine_upright(true)),
+            ("回", attrs),
+            ("100", attrs.combine_upright(true)),
+        ],
+        attrs,
+        Shaping::Advanced,
+        None,
+    );
+
+    let run = buffer.layout_runs().next().unwrap();
+    let glyphs: Vec<_> = run
+        .glyphs
+        .iter()
+        .map(|glyph| (glyph.start, glyph.x, glyph.w))
+        .collect();
+    // Each combined run takes one em down the column, its glyphs are not rotated
+    assert_eq!(
+        glyphs,
+        [
+            (0, 0.0, 10.0),
+            (3, 10.0, 0.0),
+            (4, 10.0, 10.0),
+            (5, 20.0, 10.0),
+            (8, 30.0, 0.0),
+            (9, 30.0, 0.0),
+            (10, 30.0, 10.0),
+        ]
+    );
+    for glyph in run.glyphs[1..3].iter().chain(run.glyphs[4..].iter()) {
+        assert!(!glyph.cache_key_flags.contains(CacheKeyFlags::ROTATE_CW));
+    }
+
+    // Two glyphs of half an em fill the em across the column, three are scaled down to fit
+    let offsets: Vec<_> = run.glyphs[1..3]
+        .iter()
+        .map(|glyph| (glyph.font_size, glyph.font_size * glyph.y_offset))
+        .collect();
+    assert_eq!(offsets, [(10.0, -5.0), (10.0, 0.0)]);
+    for (glyph, y) in run.glyphs[4..].iter().zip([-5.0, -5.0 / 3.0, 5.0 / 3.0]) {
+        assert!(
+            (glyph.font_size - 20.0 / 3.0).abs() < 1e-4,
+            "{}",
+            glyph.font_size
+        );
+        assert!((glyph.font_size * glyph.y_offset - y).abs() < 1e-4);
+    }
+}
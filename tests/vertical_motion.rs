@@ -0,0 +1,38 @@
+use common::new_wrapped_editor;
+use cosmic_text::{Action, Cursor, Edit, FontSystem, Motion, VerticalMotion};
+
+mod common;
+
+#[test]
+fn vertical_motion_visual_and_logical() {
+    let mut font_system = FontSystem::new();
+    let mut editor = new_wrapped_editor(&mut font_system, "aaa bbb ccc\nddd");
+    assert_eq!(editor.vertical_motion(), VerticalMotion::Visual);
+
+    editor.set_cursor(Cursor::new(0, 1));
+    editor.action(&mut font_system, Action::Motion(Motion::Down));
+    assert_eq!((editor.cursor().line, editor.cursor().index), (0, 5));
+    editor.action(&mut font_system, Action::Motion(Motion::Up));
+    assert_eq!((editor.cursor().line, editor.cursor().index), (0, 1));
+
+    editor.set_vertical_motion(VerticalMotion::Logical);
+    editor.action(&mut font_system, Action::Motion(Motion::Down));
+    assert_eq!((editor.cursor().line, editor.cursor().index), (1, 1));
+    editor.action(&mut font_system, Action::Motion(Motion::Up));
+    assert_eq!((editor.cursor().line, editor.cursor().index), (0, 1));
+}
+
+#[test]
+fn vertical_motion_keeps_pixel_column() {
+    let mut font_system = FontSystem::new();
+    let mut editor = new_wrapped_editor(&mut font_system, "iii\nmmm\niii");
+
+    // The cursor moves to the closest position, not the same glyph index
+    editor.set_cursor(Cursor::new(0, 3));
+    editor.action(&mut font_system, Action::Motion(Motion::Down));
+    assert_eq!((editor.cursor().line, editor.cursor().index), (1, 1));
+
+    // The column of the first motion is kept for following motions
+    editor.action(&mut font_system, Action::Motion(Motion::Down));
+    assert_eq!((editor.cursor().line, editor.cursor().index), (2, 3));
+}
@@ -0,0 +1,46 @@
+#![cfg(feature = "vi")]
+
+use cosmic_text::{
+    Action, Attrs, Buffer, Cursor, Edit, FontSystem, Metrics, Shaping, SyntaxEditor, SyntaxSystem,
+    ViEditor,
+};
+
+fn editor<'a>(
+    font_system: &mut FontSystem,
+    syntax_system: &'a SyntaxSystem,
+    text: &str,
+) -> ViEditor<'a, 'static> {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(font_system, text, Attrs::new(), Shaping::Advanced);
+    let editor = SyntaxEditor::new(buffer, syntax_system, "base16-eighties.dark").unwrap();
+    let mut editor = ViEditor::new(editor);
+    editor.shape_as_needed(font_system, false);
+    editor
+}
+
+fn keys(editor: &mut ViEditor, font_system: &mut FontSystem, keys: &str) {
+    for c in keys.chars() {
+        let action = if c == '\n' {
+            Action::Enter
+        } else {
+            Action::Insert(c)
+        };
+        editor.action(font_system, action);
+    }
+}
+
+#[test]
+fn braces_and_parentheses_move_by_blocks() {
+    let mut font_system = FontSystem::new();
+    let syntax_system = SyntaxSystem::new();
+    let mut editor = editor(&mut font_system, &syntax_system, "One. Two.\nthree\n\nfour");
+
+    keys(&mut editor, &mut font_system, "}");
+    assert_eq!(editor.cursor(), Cursor::new(2, 0));
+    keys(&mut editor, &mut font_system, "{");
+    assert_eq!(editor.cursor(), Cursor::new(0, 0));
+    keys(&mut editor, &mut font_system, ")");
+    assert_eq!(editor.cursor(), Cursor::new(0, 5));
+    keys(&mut editor, &mut font_system, "(");
+    assert_eq!(editor.cursor(), Cursor::new(0, 0));
+}
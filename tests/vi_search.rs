@@ -0,0 +1,97 @@
+#![cfg(feature = "vi")]
+
+use cosmic_text::{
+    Action, Attrs, Buffer, Cursor, Edit, FontSystem, Metrics, Shaping, SyntaxEditor, SyntaxSystem,
+    ViEditor,
+};
+
+fn editor<'a>(
+    font_system: &mut FontSystem,
+    syntax_system: &'a SyntaxSystem,
+    text: &str,
+) -> ViEditor<'a, 'static> {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(font_system, text, Attrs::new(), Shaping::Advanced);
+    let editor = SyntaxEditor::new(buffer, syntax_system, "base16-eighties.dark").unwrap();
+    let mut editor = ViEditor::new(editor);
+    editor.shape_as_needed(font_system, false);
+    editor
+}
+
+fn keys(editor: &mut ViEditor, font_system: &mut FontSystem, keys: &str) {
+    for c in keys.chars() {
+        let action = if c == '\n' {
+            Action::Enter
+        } else {
+            Action::Insert(c)
+        };
+        editor.action(font_system, action);
+    }
+}
+
+#[test]
+fn search_highlights_while_typing_and_moves() {
+    let mut font_system = FontSystem::new();
+    let syntax_system = SyntaxSystem::new();
+    let mut editor = editor(
+        &mut font_system,
+        &syntax_system,
+        "one two\ntwo three\ntwofold",
+    );
+
+    keys(&mut editor, &mut font_system, "/tw");
+    assert_eq!(editor.search_pattern(), Some("tw"));
+    assert_eq!(editor.search_matches().len(), 3);
+    assert_eq!(editor.cursor(), Cursor::new(0, 0));
+
+    keys(&mut editor, &mut font_system, "o\n");
+    assert_eq!(editor.search_pattern(), Some("two"));
+    assert_eq!(editor.cursor(), Cursor::new(0, 4));
+
+    keys(&mut editor, &mut font_system, "n");
+    assert_eq!(editor.cursor(), Cursor::new(1, 0));
+    keys(&mut editor, &mut font_system, "n");
+    assert_eq!(editor.cursor(), Cursor::new(2, 0));
+    keys(&mut editor, &mut font_system, "N");
+    assert_eq!(editor.cursor(), Cursor::new(1, 0));
+
+    // Searching backwards reverses n and N
+    keys(&mut editor, &mut font_system, "?one\n");
+    assert_eq!(editor.cursor(), Cursor::new(0, 0));
+    assert_eq!(
+        editor.search_matches(),
+        [(Cursor::new(0, 0), Cursor::new(0, 3))]
+    );
+
+    editor.clear_search();
+    assert_eq!(editor.search_pattern(), None);
+    assert!(editor.search_matches().is_empty());
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn search_with_regex_matcher() {
+    use cosmic_text::RegexMatcher;
+    use std::sync::Arc;
+
+    let mut font_system = FontSystem::new();
+    let syntax_system = SyntaxSystem::new();
+    let mut editor = editor(&mut font_system, &syntax_system, "cat cot\ncut");
+    editor.set_search_matcher(Arc::new(RegexMatcher::default()));
+
+    // Incomplete patterns match nothing
+    keys(&mut editor, &mut font_system, "/c[");
+    assert!(editor.search_matches().is_empty());
+
+    keys(&mut editor, &mut font_system, "ou]t\n");
+    assert_eq!(editor.cursor(), Cursor::new(0, 4));
+    assert_eq!(
+        editor.search_matches(),
+        [
+            (Cursor::new(0, 4), Cursor::new(0, 7)),
+            (Cursor::new(1, 0), Cursor::new(1, 3)),
+        ]
+    );
+    keys(&mut editor, &mut font_system, "n");
+    assert_eq!(editor.cursor(), Cursor::new(1, 0));
+}
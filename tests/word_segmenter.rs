@@ -0,0 +1,79 @@
+use std::{ops::Range, sync::Arc};
+
+use cosmic_text::{
+    Attrs, Buffer, Cursor, FontSystem, Metrics, Motion, Shaping, UnicodeWordSegmenter,
+    WordSegmenter,
+};
+
+/// Splits Japanese text at a fixed list of words, standing in for a dictionary
+#[derive(Debug)]
+struct DictionarySegmenter(&'static [&'static str]);
+
+impl WordSegmenter for DictionarySegmenter {
+    fn segment(&self, text: &str, locale: Option<&str>, words: &mut Vec<Range<usize>>) {
+        if locale != Some("ja") {
+            UnicodeWordSegmenter.segment(text, locale, words);
+            return;
+        }
+        let mut start = 0;
+        while start < text.len() {
+            let len = self
+                .0
+                .iter()
+                .find(|word| text[start..].starts_with(*word))
+                .map_or_else(
+                    || text[start..].chars().next().unwrap().len_utf8(),
+                    |w| w.len(),
+                );
+            words.push(start..start + len);
+            start += len;
+        }
+    }
+}
+
+fn next_words(buffer: &mut Buffer, font_system: &mut FontSystem) -> Vec<usize> {
+    let mut cursor = Cursor::new(0, 0);
+    let mut indices = Vec::new();
+    while cursor.index < buffer.lines[0].text().len() {
+        (cursor, _) = buffer
+            .cursor_motion(font_system, cursor, None, Motion::NextWord)
+            .unwrap();
+        indices.push(cursor.index);
+    }
+    indices
+}
+
+#[test]
+fn word_segmenter_overrides_word_motions() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "東京都に",
+        Attrs::new(),
+        Shaping::Advanced,
+    );
+
+    // Every ideograph is a word by default
+    assert_eq!(next_words(&mut buffer, &mut font_system), [3, 6, 9, 12]);
+
+    buffer.set_word_segmenter(Some(Arc::new(DictionarySegmenter(&["東京都"]))));
+    assert!(buffer.word_segmenter().is_some());
+    // The segmenter only applies its dictionary to Japanese lines
+    assert_eq!(next_words(&mut buffer, &mut font_system), [3, 6, 9, 12]);
+
+    buffer.set_locale(&mut font_system, Some("ja"));
+    assert_eq!(next_words(&mut buffer, &mut font_system), [9, 12]);
+    let (cursor, _) = buffer
+        .cursor_motion(
+            &mut font_system,
+            Cursor::new(0, 12),
+            None,
+            Motion::PreviousWord,
+        )
+        .unwrap();
+    assert_eq!(cursor.index, 9);
+
+    buffer.set_word_segmenter(None);
+    assert_eq!(next_words(&mut buffer, &mut font_system), [3, 6, 9, 12]);
+}
@@ -0,0 +1,78 @@
+use common::new_wrapped_editor;
+use cosmic_text::{
+    Action, Affinity, Cursor, Edit, Editor, FontSystem, Motion, TrailingWhitespace, WrapAffinity,
+};
+
+mod common;
+
+/// Create an editor with one visual line per word, keeping the spaces at the end of each line so
+/// the visual lines share their boundaries
+fn new_editor(font_system: &mut FontSystem, text: &str) -> Editor<'static> {
+    let mut editor = new_wrapped_editor(font_system, text);
+    editor.with_buffer_mut(|buffer| {
+        buffer.set_trailing_whitespace(font_system, TrailingWhitespace::Include);
+        buffer.shape_until_scroll(font_system, false);
+    });
+    editor
+}
+
+#[test]
+fn cursor_layout_line_at_wrap() {
+    let mut font_system = FontSystem::new();
+    let mut editor = new_editor(&mut font_system, "aaa bbb ccc");
+    editor.with_buffer_mut(|buffer| {
+        assert_eq!(buffer.lines[0].layout_opt().map(Vec::len), Some(3));
+
+        let before = Cursor::new_with_affinity(0, 4, Affinity::Before);
+        let after = Cursor::new_with_affinity(0, 4, Affinity::After);
+        assert_eq!(buffer.cursor_layout_line(Cursor::new(0, 2)), Some(0));
+        assert_eq!(buffer.cursor_layout_line(Cursor::new(0, 6)), Some(1));
+        assert_eq!(buffer.cursor_layout_line(before), Some(0));
+        assert_eq!(buffer.cursor_layout_line(after), Some(1));
+
+        // The policy overrides the affinity of the cursor
+        buffer.set_wrap_affinity(WrapAffinity::After);
+        assert_eq!(buffer.cursor_layout_line(before), Some(1));
+        buffer.set_wrap_affinity(WrapAffinity::Before);
+        assert_eq!(buffer.cursor_layout_line(after), Some(0));
+    });
+}
+
+#[test]
+fn home_end_at_wrap() {
+    let mut font_system = FontSystem::new();
+    let mut editor = new_editor(&mut font_system, "aaa bbb ccc");
+
+    editor.set_cursor(Cursor::new(0, 5));
+    editor.action(&mut font_system, Action::Motion(Motion::SoftHome));
+    assert_eq!(
+        editor.cursor(),
+        Cursor::new_with_affinity(0, 4, Affinity::After)
+    );
+    editor.action(&mut font_system, Action::Motion(Motion::SoftEnd));
+    assert_eq!(
+        editor.cursor(),
+        Cursor::new_with_affinity(0, 7, Affinity::Before)
+    );
+    editor.action(&mut font_system, Action::Motion(Motion::End));
+    assert_eq!(editor.cursor().index, 8);
+
+    // End of the first visual line stays on that line, and start of the second on that line
+    editor.set_cursor(Cursor::new(0, 1));
+    editor.action(&mut font_system, Action::Motion(Motion::End));
+    let end = editor.cursor();
+    assert_eq!(end, Cursor::new_with_affinity(0, 4, Affinity::Before));
+    editor.action(&mut font_system, Action::Motion(Motion::Home));
+    assert_eq!(editor.cursor().index, 0);
+    editor.set_cursor(Cursor::new_with_affinity(0, 4, Affinity::After));
+    editor.action(&mut font_system, Action::Motion(Motion::End));
+    assert_eq!(editor.cursor().index, 8);
+
+    // With the policy, the same cursor is placed on the next visual line
+    editor.with_buffer_mut(|buffer| buffer.set_wrap_affinity(WrapAffinity::After));
+    editor.set_cursor(end);
+    let (_, y_after) = editor.cursor_position().unwrap();
+    editor.action(&mut font_system, Action::Motion(Motion::End));
+    assert_eq!(editor.cursor().index, 8);
+    assert_eq!(y_after, 20);
+}
@@ -0,0 +1,47 @@
+use cosmic_text::{Align, Attrs, Buffer, FontSystem, Metrics, Shaping, Wrap};
+
+/// Lay out `text` in a buffer 60px wide, returning the start X and width of each layout run.
+/// Every glyph of the mock font is 5px wide at 10px
+fn layout(text: &str, wrap: Wrap, align: Option<Align>) -> Vec<(f32, f32)> {
+    let mut font_system = FontSystem::new_mock();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(10.0, 20.0));
+    buffer.set_wrap(&mut font_system, wrap);
+    buffer.set_size(&mut font_system, Some(60.0), None);
+    buffer.set_text(&mut font_system, text, Attrs::new(), Shaping::Advanced);
+    buffer.set_align(&mut font_system, align);
+    buffer
+        .layout_runs()
+        .map(|run| (run.glyphs[0].x, run.line_w))
+        .collect()
+}
+
+#[test]
+fn wrap_balanced_lines() {
+    let text = "aa bb cc dd ee";
+
+    // Greedy wrapping leaves a single word on the last line
+    assert_eq!(layout(text, Wrap::Word, None), [(0.0, 55.0), (0.0, 10.0)]);
+    assert_eq!(
+        layout(text, Wrap::Balanced, None),
+        [(0.0, 40.0), (0.0, 25.0)]
+    );
+
+    // Balanced lines are still aligned in the full width
+    assert_eq!(
+        layout(text, Wrap::Balanced, Some(Align::Center)),
+        [(10.0, 40.0), (17.5, 25.0)]
+    );
+}
+
+#[test]
+fn wrap_balanced_fallback() {
+    // Text that fits is not wrapped
+    assert_eq!(layout("aa bb", Wrap::Balanced, None), [(0.0, 25.0)]);
+
+    // Words wider than the buffer are wrapped at glyphs
+    assert_eq!(
+        layout("aaaaaaaaaaaaaaa", Wrap::Balanced, None),
+        layout("aaaaaaaaaaaaaaa", Wrap::WordOrGlyph, None)
+    );
+    assert_eq!(layout("aaaaaaaaaaaaaaa", Wrap::Balanced, None).len(), 2);
+}
@@ -125,7 +125,7 @@ fn dbg_layout_lines(text: &str, lines: &[LayoutLine]) {
     for line in lines {
         let mut s = String::new();
         for glyph in line.glyphs.iter() {
-            s.push_str(&text[glyph.start..glyph.end]);
+            s.push_str(&text[glyph.range()]);
         }
         println!("\"{s}\"");
     }